@@ -0,0 +1,285 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    event::{EventListener, EventPropagation},
+    reactive::{create_memo, create_rw_signal, use_context, ReadSignal, RwSignal},
+    style::CursorStyle,
+    views::{dyn_stack, label, stack, Decorators},
+    View,
+};
+
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    db::LapceDb,
+    panel::kind::PanelKind,
+    window_tab::{DragContent, WindowTabData},
+};
+
+/// The panels the activity bar has icons for, in the order they're in until the
+/// user reorders them
+const DEFAULT_ORDER: [PanelKind; 5] = [
+    PanelKind::FileExplorer,
+    PanelKind::Search,
+    PanelKind::SourceControl,
+    PanelKind::Debug,
+    PanelKind::Plugin,
+];
+
+/// The slim bar at the left of the window with an icon for each of the main
+/// panels, which toggles it, and a badge with how many things need looking at
+/// in it. Its icons can be dragged to reorder them.
+pub fn activity_bar(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let db: Arc<LapceDb> = use_context().unwrap();
+    let order =
+        create_rw_signal(activity_bar_order(db.get_activity_bar_order().ok()));
+
+    dyn_stack(
+        move || order.get(),
+        |kind| *kind,
+        move |kind| activity_item(window_tab_data.clone(), kind, order, config),
+    )
+    .style(move |s| {
+        let config = config.get();
+        s.flex_col()
+            .height_pct(100.0)
+            .padding_vert(4.0)
+            .border_right(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .background(config.color(LapceColor::PANEL_BACKGROUND))
+            .apply_if(!config.ui.activity_bar, |s| s.hide())
+    })
+    .debug_name("Activity Bar")
+}
+
+fn activity_item(
+    window_tab_data: Rc<WindowTabData>,
+    kind: PanelKind,
+    order: RwSignal<Vec<PanelKind>>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let dragging = window_tab_data.common.dragging;
+    let (icon, tooltip) = match kind {
+        PanelKind::FileExplorer => (LapceIcons::FILE_EXPLORER, "Explorer"),
+        PanelKind::Search => (LapceIcons::SEARCH, "Search"),
+        PanelKind::SourceControl => (LapceIcons::SCM, "Source Control"),
+        PanelKind::Debug => (LapceIcons::DEBUG_ALT, "Run and Debug"),
+        PanelKind::Plugin => (LapceIcons::EXTENSIONS, "Plugins"),
+        _ => (kind.svg_name(), ""),
+    };
+    let is_active = {
+        let panel = window_tab_data.panel.clone();
+        move || {
+            panel.panels.track();
+            panel.styles.track();
+            panel.is_panel_visible(&kind)
+        }
+    };
+    let badge = {
+        let window_tab_data = window_tab_data.clone();
+        create_memo(move |_| badge_count(&window_tab_data, kind))
+    };
+    let dragging_over = create_rw_signal(false);
+
+    stack((
+        clickable_icon(
+            move || icon,
+            move || window_tab_data.toggle_panel_visual(kind),
+            || false,
+            || false,
+            move || tooltip,
+            config,
+        )
+        .draggable()
+        .on_event_stop(EventListener::DragStart, move |_| {
+            dragging.set(Some(DragContent::ActivityBarItem(kind)));
+        })
+        .on_event_stop(EventListener::DragEnd, move |_| {
+            dragging.set(None);
+        })
+        .style(|s| s.padding(4.0)),
+        label(move || {
+            let count = badge.get();
+            if count > 99 {
+                "99+".to_string()
+            } else {
+                count.to_string()
+            }
+        })
+        .style(move |s| {
+            let config = config.get();
+            s.absolute()
+                .inset_right(2.0)
+                .inset_bottom(2.0)
+                .min_width(14.0)
+                .padding_horiz(3.0)
+                .justify_center()
+                .font_size(9.0)
+                .border_radius(7.0)
+                .color(config.color(LapceColor::LAPCE_BUTTON_PRIMARY_FOREGROUND))
+                .background(
+                    config.color(LapceColor::LAPCE_BUTTON_PRIMARY_BACKGROUND),
+                )
+                .selectable(false)
+                .apply_if(badge.get() == 0, |s| s.hide())
+        }),
+        label(|| "".to_string()).style(move |s| {
+            s.selectable(false)
+                .absolute()
+                .size_pct(100.0, 100.0)
+                .apply_if(is_active(), |s| s.border_left(2.0))
+                .apply_if(dragging_over.get(), |s| s.border_top(2.0))
+                .border_color(
+                    config.get().color(LapceColor::LAPCE_TAB_ACTIVE_UNDERLINE),
+                )
+        }),
+    ))
+    .on_event(EventListener::DragEnter, move |_| {
+        if is_dragging_item(dragging) {
+            dragging_over.set(true);
+            EventPropagation::Stop
+        } else {
+            EventPropagation::Continue
+        }
+    })
+    .on_event(EventListener::DragLeave, move |_| {
+        if is_dragging_item(dragging) {
+            dragging_over.set(false);
+            EventPropagation::Stop
+        } else {
+            EventPropagation::Continue
+        }
+    })
+    .on_event(EventListener::Drop, move |_| {
+        if let Some(DragContent::ActivityBarItem(dragged)) = dragging.get_untracked()
+        {
+            dragging_over.set(false);
+            move_before(order, dragged, kind);
+            EventPropagation::Stop
+        } else {
+            EventPropagation::Continue
+        }
+    })
+    .style(move |s| {
+        s.padding(6.0).hover(|s| {
+            s.cursor(CursorStyle::Pointer)
+                .background(config.get().color(LapceColor::PANEL_HOVERED_BACKGROUND))
+        })
+    })
+}
+
+fn is_dragging_item(dragging: RwSignal<Option<DragContent>>) -> bool {
+    dragging.with_untracked(|d| matches!(d, Some(DragContent::ActivityBarItem(_))))
+}
+
+/// How many things need looking at in the panel: the changed files, the files
+/// with search results, the debug sessions or the plugins with updates
+fn badge_count(window_tab_data: &WindowTabData, kind: PanelKind) -> usize {
+    match kind {
+        PanelKind::SourceControl => window_tab_data
+            .source_control
+            .file_diffs
+            .with(|diffs| diffs.len()),
+        PanelKind::Search => window_tab_data
+            .global_search
+            .search_result
+            .with(|result| result.len()),
+        PanelKind::Debug => {
+            window_tab_data.terminal.debug.daps.with(|daps| daps.len())
+        }
+        PanelKind::Plugin => window_tab_data.plugin.installed.with(|installed| {
+            installed
+                .values()
+                .filter(|volt| {
+                    volt.meta
+                        .with(|m| volt.latest.with(|i| i.version != m.version))
+                })
+                .count()
+        }),
+        _ => 0,
+    }
+}
+
+/// Move `dragged` to just before `target`, and remember the order
+fn move_before(
+    order: RwSignal<Vec<PanelKind>>,
+    dragged: PanelKind,
+    target: PanelKind,
+) {
+    if dragged == target {
+        return;
+    }
+    order.update(|order| reorder(order, dragged, target));
+    let db: Arc<LapceDb> = use_context().unwrap();
+    db.save_activity_bar_order(order.get_untracked());
+}
+
+fn reorder(order: &mut Vec<PanelKind>, dragged: PanelKind, target: PanelKind) {
+    order.retain(|kind| *kind != dragged);
+    let index = order
+        .iter()
+        .position(|kind| *kind == target)
+        .unwrap_or(order.len());
+    order.insert(index, dragged);
+}
+
+/// The saved order of the activity bar, keeping to the panels it has icons for,
+/// and with any of them it's missing at the end
+fn activity_bar_order(saved: Option<Vec<PanelKind>>) -> Vec<PanelKind> {
+    let mut order = Vec::new();
+    for kind in saved.unwrap_or_default() {
+        if DEFAULT_ORDER.contains(&kind) && !order.contains(&kind) {
+            order.push(kind);
+        }
+    }
+    for kind in DEFAULT_ORDER {
+        if !order.contains(&kind) {
+            order.push(kind);
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{activity_bar_order, reorder, DEFAULT_ORDER};
+    use crate::panel::kind::PanelKind;
+
+    #[test]
+    fn test_activity_bar_order() {
+        assert_eq!(activity_bar_order(None), DEFAULT_ORDER.to_vec());
+        assert_eq!(
+            activity_bar_order(Some(vec![
+                PanelKind::Plugin,
+                PanelKind::Terminal,
+                PanelKind::Search,
+            ])),
+            vec![
+                PanelKind::Plugin,
+                PanelKind::Search,
+                PanelKind::FileExplorer,
+                PanelKind::SourceControl,
+                PanelKind::Debug,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reorder() {
+        let mut order = DEFAULT_ORDER.to_vec();
+        reorder(&mut order, PanelKind::Plugin, PanelKind::Search);
+        assert_eq!(
+            order,
+            vec![
+                PanelKind::FileExplorer,
+                PanelKind::Plugin,
+                PanelKind::Search,
+                PanelKind::SourceControl,
+                PanelKind::Debug,
+            ]
+        );
+        reorder(&mut order, PanelKind::FileExplorer, PanelKind::Debug);
+        assert_eq!(order[3], PanelKind::FileExplorer);
+    }
+}
@@ -37,6 +37,8 @@ pub struct AlertBoxData {
     pub title: RwSignal<String>,
     pub msg: RwSignal<String>,
     pub buttons: RwSignal<Vec<AlertButton>>,
+    /// Run when the alert is cancelled instead of answered with a button
+    pub on_cancel: RwSignal<Option<Rc<dyn Fn()>>>,
     pub config: ReadSignal<Arc<LapceConfig>>,
 }
 
@@ -47,6 +49,7 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             title: cx.create_rw_signal("".to_string()),
             msg: cx.create_rw_signal("".to_string()),
             buttons: cx.create_rw_signal(Vec::new()),
+            on_cancel: cx.create_rw_signal(None),
             config: common.config,
         }
     }
@@ -58,6 +61,7 @@ pub fn alert_box(alert_data: AlertBoxData) -> impl View {
     let title = alert_data.title;
     let msg = alert_data.msg;
     let buttons = alert_data.buttons;
+    let on_cancel = alert_data.on_cancel;
     let button_id = AtomicU64::new(0);
 
     container({
@@ -85,6 +89,7 @@ pub fn alert_box(alert_data: AlertBoxData) -> impl View {
                     move |button| {
                         label(move || button.text.clone())
                             .on_click_stop(move |_| {
+                                on_cancel.set(None);
                                 (button.action)();
                             })
                             .style(move |s| {
@@ -117,6 +122,11 @@ pub fn alert_box(alert_data: AlertBoxData) -> impl View {
                 .style(|s| s.flex_col().width_pct(100.0).margin_top(10.0)),
                 label(|| "Cancel".to_string())
                     .on_click_stop(move |_| {
+                        if let Some(on_cancel) =
+                            on_cancel.try_update(Option::take).flatten()
+                        {
+                            on_cancel();
+                        }
                         active.set(false);
                     })
                     .style(move |s| {
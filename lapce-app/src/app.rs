@@ -1,18 +1,21 @@
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use std::{
+    borrow::Cow,
     io::{BufReader, IsTerminal, Read, Write},
     ops::Range,
     path::PathBuf,
     process::Stdio,
     rc::Rc,
     sync::{atomic::AtomicU64, Arc},
+    time::Duration,
 };
 
 use anyhow::{anyhow, Result};
 use clap::Parser;
 use crossbeam_channel::Sender;
 use floem::{
+    action::exec_after,
     cosmic_text::{Style as FontStyle, Weight},
     event::{Event, EventListener, EventPropagation},
     ext_event::{create_ext_action, create_signal_from_channel},
@@ -33,37 +36,44 @@
         style_helpers::{self, auto, fr},
         Line,
     },
-    unit::PxPctAuto,
+    unit::{Px, PxPctAuto},
     views::{
         clip, container, drag_resize_window_area, drag_window_area, dyn_stack,
         empty, label, rich_text,
         scroll::{
-            scroll, HideBar, PropagatePointerWheel, VerticalScrollAsHorizontal,
+            scroll, HideBar, PropagatePointerWheel, Thickness,
+            VerticalScrollAsHorizontal,
         },
         stack, svg, tab, text, tooltip, virtual_stack, Decorators, VirtualDirection,
         VirtualItemSize, VirtualVector,
     },
-    window::{ResizeDirection, WindowConfig, WindowId},
+    window::{ResizeDirection, Theme, WindowConfig, WindowId},
     IntoView, View,
 };
 use lapce_core::{
     command::{EditCommand, FocusCommand},
     directory::Directory,
+    language::LapceLanguage,
     meta,
     syntax::highlight::reset_highlight_configs,
 };
+use lapce_proxy::plugin::wasi::find_all_volts;
 use lapce_rpc::{
+    buffer::BufferId,
     core::{CoreMessage, CoreNotification},
     file::PathObject,
+    proxy::ProxyStatus,
     RpcMessage,
 };
-use lsp_types::{CompletionItemKind, MessageType, ShowMessageParams};
+use lsp_types::CompletionItemKind;
 use notify::Watcher;
 use serde::{Deserialize, Serialize};
 use tracing_subscriber::{filter::Targets, reload::Handle};
 
 use crate::{
-    about, alert,
+    about,
+    activity_bar::activity_bar,
+    alert,
     code_action::CodeActionStatus,
     command::{
         CommandKind, InternalCommand, LapceCommand, LapceWorkbenchCommand,
@@ -83,23 +93,27 @@
     editor_tab::{EditorTabChild, EditorTabData},
     focus_text::focus_text,
     id::{EditorTabId, SplitId},
+    image_view::image_view,
     keymap::keymap_view,
     keypress::keymap::KeyMap,
     listener::Listener,
     main_split::{SplitContent, SplitData, SplitDirection, SplitMoveDirection},
     markdown::MarkdownContent,
+    notification::notification_toasts,
     palette::{
         item::{PaletteItem, PaletteItemContent},
-        PaletteStatus,
+        PaletteData, PaletteStatus,
     },
     panel::{position::PanelContainerPosition, view::panel_container_view},
     plugin::{plugin_info_view, PluginData},
     settings::{settings_view, theme_color_settings_view},
+    ssh_prompt,
     status::status,
     text_input::TextInputBuilder,
     title::{title, window_controls_view},
     tracing::*,
     update::ReleaseInfo,
+    welcome::welcome_view,
     window::{TabsInfo, WindowData, WindowInfo},
     window_tab::{Focus, WindowTabData},
     workspace::{LapceWorkspace, LapceWorkspaceType},
@@ -115,9 +129,19 @@ struct Cli {
     /// Launch new window even if Lapce is already running
     #[clap(short, long, action)]
     new: bool,
-    /// Don't return instantly when opened in a terminal
+    /// Open the paths in a new window of the running Lapce, rather than in its
+    /// active one
+    #[clap(long, action)]
+    new_window: bool,
+    /// Return once the files are closed, to use Lapce as the editor of git and
+    /// other programs, with `EDITOR="lapce --wait"`, or once the new Lapce is
+    /// quit with --new. It used to do what --foreground does.
     #[clap(short, long, action)]
     wait: bool,
+    /// Don't return instantly when opened in a terminal. This was -w/--wait
+    /// before it waited for files to be closed.
+    #[clap(long, action)]
+    foreground: bool,
 
     /// Path(s) to plugins to load.  
     /// This is primarily used for plugin development to make it easier to test changes to the
@@ -126,6 +150,10 @@ struct Cli {
     #[clap(long, action)]
     plugin_path: Vec<PathBuf>,
 
+    /// Print how long each phase of starting up took, once the windows are shown
+    #[clap(long, action)]
+    profile_startup: bool,
+
     /// Paths to file(s) and/or folder(s) to open.
     /// When path is a file (that exists or not),
     /// it accepts `path:line:column` syntax
@@ -147,6 +175,8 @@ pub enum AppCommand {
     CloseWindow(WindowId),
     WindowGotFocus(WindowId),
     WindowClosed(WindowId),
+    /// The appearance of the OS changed between light and dark
+    ThemeChanged(Theme),
 }
 
 #[derive(Clone)]
@@ -165,6 +195,13 @@ pub struct AppData {
     pub plugin_paths: Arc<Vec<PathBuf>>,
 }
 
+/// How often the unsaved changes are stored, to be restored after a crash
+const HOT_EXIT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the workspaces' open files, cursors and layouts are stored, so that
+/// they're restored even when Lapce didn't get to store them on quitting
+const SESSION_INTERVAL: Duration = Duration::from_secs(30);
+
 impl AppData {
     pub fn reload_config(&self) {
         let config =
@@ -199,6 +236,16 @@ fn default_window_config(&self) -> WindowConfig {
     }
 
     pub fn new_window(&self) {
+        self.new_window_with_paths(Vec::new(), None);
+    }
+
+    /// Open a new window with the folders as its workspaces and the files in
+    /// the first one, telling `closed` once the files are closed if it's given
+    pub fn new_window_with_paths(
+        &self,
+        paths: Vec<PathObject>,
+        closed: Option<Sender<()>>,
+    ) {
         let config = self
             .active_window()
             .map(|window| {
@@ -224,10 +271,23 @@ pub fn new_window(&self) {
         } else {
             config
         };
+        let (dirs, files): (Vec<PathObject>, Vec<PathObject>) =
+            paths.into_iter().partition(|p| p.is_dir);
+        let workspaces = if dirs.is_empty() {
+            vec![LapceWorkspace::default()]
+        } else {
+            dirs.into_iter()
+                .map(|dir| LapceWorkspace {
+                    kind: cli_workspace_type(),
+                    path: Some(dir.path),
+                    last_open: 0,
+                })
+                .collect()
+        };
         let app_data = self.clone();
         floem::new_window(
             move |window_id| {
-                app_data.app_view(
+                let view = app_data.app_view(
                     window_id,
                     WindowInfo {
                         size: Size::ZERO,
@@ -235,10 +295,21 @@ pub fn new_window(&self) {
                         maximised: false,
                         tabs: TabsInfo {
                             active_tab: 0,
-                            workspaces: vec![LapceWorkspace::default()],
+                            workspaces,
                         },
                     },
-                )
+                );
+                let window_tab = app_data
+                    .windows
+                    .with_untracked(|windows| windows.get(&window_id).cloned())
+                    .and_then(|window| window.active_window_tab());
+                if let Some(window_tab) = window_tab {
+                    window_tab.open_paths(&files);
+                    if let Some(closed) = closed {
+                        window_tab.notify_files_closed(&files, closed);
+                    }
+                }
+                view
             },
             Some(config),
         );
@@ -275,10 +346,72 @@ pub fn run_app_command(&self, cmd: AppCommand) {
             }
             AppCommand::WindowGotFocus(window_id) => {
                 self.active_window.set(window_id);
+                // The file watcher can miss changes, such as those made while the
+                // system was asleep
+                if let Some(window) = self
+                    .windows
+                    .with_untracked(|windows| windows.get(&window_id).cloned())
+                {
+                    for (_, window_tab) in window.window_tabs.get_untracked() {
+                        window_tab.common.proxy.check_open_files();
+                    }
+                }
+            }
+            AppCommand::ThemeChanged(theme) => {
+                let changed = LapceConfig::set_os_dark_mode(theme == Theme::Dark);
+                if changed
+                    && self
+                        .config
+                        .with_untracked(|config| config.core.follow_os_theme)
+                {
+                    self.reload_config();
+                }
             }
         }
     }
 
+    /// Store the workspaces every so often while their unsaved changes change, so
+    /// that the changes are restored after a crash as well as after quitting
+    fn journal_unsaved_docs(
+        &self,
+        db: Arc<LapceDb>,
+        last_revs: Vec<(BufferId, u64)>,
+    ) {
+        let app = self.clone();
+        exec_after(HOT_EXIT_INTERVAL, move |_| {
+            if app.app_terminated.get_untracked() {
+                return;
+            }
+            let mut revs: Vec<(BufferId, u64)> = app
+                .windows
+                .get_untracked()
+                .values()
+                .flat_map(|window| window.window_tabs.get_untracked())
+                .flat_map(|(_, window_tab)| window_tab.main_split.unsaved_revs())
+                .collect();
+            revs.sort_by_key(|(id, _)| id.0);
+            if revs != last_revs
+                && app.config.with_untracked(|config| config.editor.hot_exit)
+            {
+                let _ = db.save_app(&app);
+            }
+            app.journal_unsaved_docs(db, revs);
+        });
+    }
+
+    /// Store the workspaces every so often, for the times Lapce is killed or
+    /// crashes instead of quitting
+    fn save_sessions(&self, db: Arc<LapceDb>) {
+        let app = self.clone();
+        exec_after(SESSION_INTERVAL, move |_| {
+            if app.app_terminated.get_untracked() {
+                return;
+            }
+            let _ = db.save_app(&app);
+            app.save_sessions(db);
+        });
+    }
+
     fn create_windows(
         &self,
         db: Arc<LapceDb>,
@@ -301,20 +434,7 @@ fn create_windows(
                 .unwrap_or_else(|_| (Size::new(800.0, 600.0), Point::new(0.0, 0.0)));
 
             for dir in dirs {
-                #[cfg(windows)]
-                let workspace_type = if !std::env::var("WSL_DISTRO_NAME")
-                    .unwrap_or_default()
-                    .is_empty()
-                    || !std::env::var("WSL_INTEROP").unwrap_or_default().is_empty()
-                {
-                    LapceWorkspaceType::RemoteWSL(crate::workspace::WslHost {
-                        host: String::new(),
-                    })
-                } else {
-                    LapceWorkspaceType::Local
-                };
-                #[cfg(not(windows))]
-                let workspace_type = LapceWorkspaceType::Local;
+                let workspace_type = cli_workspace_type();
 
                 let info = WindowInfo {
                     size,
@@ -586,6 +706,11 @@ fn app_view(&self, window_id: WindowId, info: WindowInfo) -> impl View {
             .on_event_stop(EventListener::WindowClosed, move |_| {
                 app_command.send(AppCommand::WindowClosed(window_id));
             })
+            .on_event_stop(EventListener::ThemeChanged, move |event| {
+                if let Event::ThemeChanged(theme) = event {
+                    app_command.send(AppCommand::ThemeChanged(*theme));
+                }
+            })
             .debug_name("App View")
     }
 }
@@ -610,6 +735,26 @@ fn editor_tab_header(
 
     let editor_tab_active =
         create_memo(move |_| editor_tab.with(|editor_tab| editor_tab.active));
+    // The file of the active editor, when its language has a run template
+    let run_path = create_memo(move |_| {
+        let child = editor_tab.with(|editor_tab| {
+            editor_tab
+                .children
+                .get(editor_tab.active)
+                .map(|(_, _, child)| child.clone())
+        });
+        let Some(EditorTabChild::Editor(editor_id)) = child else {
+            return None;
+        };
+        let path = editors
+            .editor(editor_id)?
+            .doc_signal()
+            .get()
+            .content
+            .with(|content| content.path().cloned())?;
+        config.get().terminal.get_run_template(&path)?;
+        Some(path)
+    });
     let items = move || {
         let editor_tab = editor_tab.get();
         for (i, (index, _, _)) in editor_tab.children.iter().enumerate() {
@@ -656,8 +801,8 @@ fn editor_tab_header(
             })
             .style(|s| s.padding(4.));
 
-            let tab_content = label(move || info.with(|info| info.path.clone()))
-                .style(move |s| {
+            let tab_content = stack((
+                label(move || info.with(|info| info.path.clone())).style(move |s| {
                     s.apply_if(
                         !info
                             .with(|info| info.confirmed)
@@ -666,7 +811,17 @@ fn editor_tab_header(
                         |s| s.font_style(FontStyle::Italic),
                     )
                     .selectable(false)
-                });
+                }),
+                svg(move || config.get().ui_svg(LapceIcons::LOCK)).style(move |s| {
+                    let config = config.get();
+                    let size = config.ui.icon_size() as f32;
+                    s.size(size, size)
+                        .margin_left(4.0)
+                        .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+                        .apply_if(!info.with(|info| info.read_only), |s| s.hide())
+                }),
+            ))
+            .style(|s| s.items_center());
 
             let tab_close_button = clickable_icon(
                 move || {
@@ -1022,6 +1177,32 @@ fn editor_tab_header(
                         })
                 }),
                 stack((
+                    clickable_icon(
+                        || LapceIcons::START,
+                        move || {
+                            let config = config.get_untracked();
+                            let run_config =
+                                run_path.get_untracked().and_then(|path| {
+                                    config.terminal.get_run_config(&path)
+                                });
+                            if let Some(config) = run_config {
+                                internal_command.send(
+                                    InternalCommand::RunAndDebug {
+                                        mode: RunDebugMode::Run,
+                                        config,
+                                    },
+                                );
+                            }
+                        },
+                        || false,
+                        || false,
+                        || "Run File",
+                        config,
+                    )
+                    .style(move |s| {
+                        s.margin_left(6.0)
+                            .apply_if(run_path.get().is_none(), |s| s.hide())
+                    }),
                     clickable_icon(
                         || LapceIcons::SPLIT_HORIZONTAL,
                         move || {
@@ -1094,6 +1275,7 @@ fn editor_tab_content(
     let workspace = common.workspace.clone();
     let editors = main_split.editors;
     let diff_editors = main_split.diff_editors;
+    let images = main_split.images;
     let config = common.config;
     let focus = common.focus;
     let items = move || {
@@ -1152,6 +1334,7 @@ fn editor_tab_content(
                 });
                 if let Some(diff_editor_data) = diff_editor_data {
                     let focus_right = diff_editor_data.focus_right;
+                    let inline = diff_editor_data.inline;
                     let diff_editor_tab_id = diff_editor_data.editor_tab_id;
                     let diff_editor_scope = diff_editor_data.scope;
                     let is_active = move |tracked: bool| {
@@ -1226,6 +1409,7 @@ fn editor_tab_content(
                                 .border_color(
                                     config.get().color(LapceColor::LAPCE_BORDER),
                                 )
+                                .apply_if(inline.get(), |s| s.hide())
                         }),
                         container(
                             editor_container_view(
@@ -1271,6 +1455,15 @@ fn editor_tab_content(
             EditorTabChild::Volt(_, id) => {
                 plugin_info_view(plugin.clone(), id).into_any()
             }
+            EditorTabChild::Image(id, _) => {
+                if let Some(image) =
+                    images.with_untracked(|images| images.get(&id).cloned())
+                {
+                    image_view(image, config).into_any()
+                } else {
+                    text("empty image view").into_any()
+                }
+            }
         };
         child.style(|s| s.size_full())
     };
@@ -1884,12 +2077,16 @@ fn main_split(window_tab_data: Rc<WindowTabData>) -> impl View {
     let plugin = window_tab_data.plugin.clone();
     let dragging: RwSignal<Option<(RwSignal<usize>, EditorTabId)>> =
         create_rw_signal(None);
-    split_list(
-        root_split,
-        window_tab_data.clone(),
-        plugin.clone(),
-        dragging,
-    )
+    stack((
+        split_list(
+            root_split,
+            window_tab_data.clone(),
+            plugin.clone(),
+            dragging,
+        )
+        .style(|s| s.size_full()),
+        welcome_view(window_tab_data.clone()),
+    ))
     .style(move |s| {
         let config = config.get();
         let is_hidden = panel.panel_bottom_maximized(true)
@@ -2016,6 +2213,7 @@ fn tooltip_tip<V: View + 'static>(
         s.padding_horiz(10.0)
             .padding_vert(5.0)
             .font_size(config.ui.font_size() as f32)
+            .font_weight(config.ui.font_weight())
             .font_family(config.ui.font_family.clone())
             .color(config.color(LapceColor::TOOLTIP_FOREGROUND))
             .background(config.color(LapceColor::TOOLTIP_BACKGROUND))
@@ -2033,6 +2231,7 @@ fn workbench(window_tab_data: Rc<WindowTabData>) -> impl View {
     let workbench_size = window_tab_data.common.workbench_size;
     let main_split_width = window_tab_data.main_split.width;
     stack((
+        activity_bar(window_tab_data.clone()),
         panel_container_view(window_tab_data.clone(), PanelContainerPosition::Left),
         {
             let window_tab_data = window_tab_data.clone();
@@ -2052,7 +2251,10 @@ fn workbench(window_tab_data: Rc<WindowTabData>) -> impl View {
             .style(|s| s.flex_col().flex_grow(1.0))
         },
         panel_container_view(window_tab_data.clone(), PanelContainerPosition::Right),
-        window_message_view(window_tab_data.messages, window_tab_data.common.config),
+        notification_toasts(
+            window_tab_data.notifications,
+            window_tab_data.common.config,
+        ),
     ))
     .on_resize(move |rect| {
         let size = rect.size();
@@ -2064,8 +2266,10 @@ fn workbench(window_tab_data: Rc<WindowTabData>) -> impl View {
     .debug_name("Workbench")
 }
 
+#[allow(clippy::too_many_arguments)]
 fn palette_item(
     workspace: Arc<LapceWorkspace>,
+    palette: PaletteData,
     i: usize,
     item: PaletteItem,
     index: ReadSignal<usize>,
@@ -2296,6 +2500,69 @@ fn palette_item(
                 .style(|s| s.align_items(Some(AlignItems::Center)).max_width_full()),
             )
         }
+        PaletteItemContent::LanguageServer { detail, .. } => {
+            // The filter text is the server's name and languages then the detail
+            let text_len = item.filter_text.len() - detail.len();
+            let text = item.filter_text[..text_len].to_string();
+            let hint = detail.clone();
+            let text_indices: Vec<usize> = item
+                .indices
+                .iter()
+                .filter(|i| **i < text_len)
+                .copied()
+                .collect();
+            let hint_indices: Vec<usize> = item
+                .indices
+                .iter()
+                .filter(|i| **i >= text_len)
+                .map(|i| i - text_len)
+                .collect();
+            container(
+                stack((
+                    focus_text(
+                        move || text.clone(),
+                        move || text_indices.clone(),
+                        move || config.get().color(LapceColor::EDITOR_FOCUS),
+                    )
+                    .style(|s| s.margin_right(6.0).max_width_full()),
+                    focus_text(
+                        move || hint.clone(),
+                        move || hint_indices.clone(),
+                        move || config.get().color(LapceColor::EDITOR_FOCUS),
+                    )
+                    .style(move |s| {
+                        s.color(config.get().color(LapceColor::EDITOR_DIM))
+                            .min_width(0.0)
+                            .flex_grow(1.0)
+                            .flex_basis(0.0)
+                    }),
+                ))
+                .style(|s| s.align_items(Some(AlignItems::Center)).max_width_full()),
+            )
+        }
+        PaletteItemContent::ColorTheme { source, .. } => {
+            let text = item.filter_text;
+            let indices = item.indices;
+            let source = source.clone();
+            container(
+                stack((
+                    focus_text(
+                        move || text.clone(),
+                        move || indices.clone(),
+                        move || config.get().color(LapceColor::EDITOR_FOCUS),
+                    )
+                    .style(|s| s.margin_right(6.0).max_width_full()),
+                    label(move || source.clone()).style(move |s| {
+                        s.color(config.get().color(LapceColor::EDITOR_DIM))
+                            .min_width(0.0)
+                            .flex_grow(1.0)
+                            .flex_basis(0.0)
+                            .text_ellipsis()
+                    }),
+                ))
+                .style(|s| s.align_items(Some(AlignItems::Center)).max_width_full()),
+            )
+        }
         PaletteItemContent::RunAndDebug {
             mode,
             config: run_config,
@@ -2416,15 +2683,66 @@ fn palette_item(
                 .style(|s| s.width_full().items_center()),
             )
         }
+        PaletteItemContent::Workspace {
+            workspace: recent,
+            pinned,
+        } => {
+            let text = item.filter_text;
+            let indices = item.indices;
+            let pinned = *pinned;
+            let pin_palette = palette.clone();
+            let pin_workspace = recent.clone();
+            let remove_workspace = recent.clone();
+            container(
+                stack((
+                    focus_text(
+                        move || text.clone(),
+                        move || indices.clone(),
+                        move || config.get().color(LapceColor::EDITOR_FOCUS),
+                    )
+                    .style(|s| {
+                        s.align_items(Some(AlignItems::Center))
+                            .min_width(0.0)
+                            .flex_grow(1.0)
+                    }),
+                    clickable_icon(
+                        move || {
+                            if pinned {
+                                LapceIcons::PINNED
+                            } else {
+                                LapceIcons::PIN
+                            }
+                        },
+                        move || pin_palette.pin_workspace(&pin_workspace, !pinned),
+                        || false,
+                        || false,
+                        move || if pinned { "Unpin" } else { "Pin" },
+                        config,
+                    ),
+                    clickable_icon(
+                        || LapceIcons::CLOSE,
+                        move || palette.remove_workspace(&remove_workspace),
+                        || false,
+                        || false,
+                        || "Remove from Recent",
+                        config,
+                    ),
+                ))
+                .style(|s| s.width_full().items_center()),
+            )
+        }
         PaletteItemContent::Line { .. }
-        | PaletteItemContent::Workspace { .. }
         | PaletteItemContent::SshHost { .. }
+        | PaletteItemContent::ContainerHost { .. }
         | PaletteItemContent::Language { .. }
         | PaletteItemContent::LineEnding { .. }
-        | PaletteItemContent::ColorTheme { .. }
+        | PaletteItemContent::Encoding { .. }
         | PaletteItemContent::SCMReference { .. }
+        | PaletteItemContent::SCMCreateBranch { .. }
         | PaletteItemContent::TerminalProfile { .. }
-        | PaletteItemContent::IconTheme { .. } => {
+        | PaletteItemContent::IconTheme { .. }
+        | PaletteItemContent::PaletteMode { .. }
+        | PaletteItemContent::Custom { .. } => {
             let text = item.filter_text;
             let indices = item.indices;
             container(
@@ -2525,6 +2843,7 @@ fn palette_content(
     let input = window_tab_data.palette.input.read_only();
     let palette_item_height = 25.0;
     let workspace = window_tab_data.workspace.clone();
+    let palette = window_tab_data.palette.clone();
     stack((
         scroll({
             let workspace = workspace.clone();
@@ -2537,23 +2856,25 @@ fn palette_content(
                 },
                 move |(i, item)| {
                     let workspace = workspace.clone();
+                    let palette = palette.clone();
                     let keymap = {
-                        let cmd_kind = match &item.content {
+                        let cmd_name = match &item.content {
                             PaletteItemContent::PaletteHelp { cmd } => {
-                                Some(CommandKind::Workbench(cmd.clone()))
+                                Some(Cow::Borrowed(
+                                    CommandKind::Workbench(cmd.clone()).str(),
+                                ))
                             }
-                            PaletteItemContent::Command {
-                                cmd: LapceCommand { kind, .. },
-                            } => Some(kind.clone()),
+                            PaletteItemContent::Command { cmd } => Some(cmd.name()),
                             _ => None,
                         };
 
-                        cmd_kind
-                            .and_then(|kind| keymaps.get(kind.str()))
+                        cmd_name
+                            .and_then(|name| keymaps.get(&*name))
                             .and_then(|maps| maps.first())
                     };
                     container(palette_item(
                         workspace,
+                        palette,
                         i,
                         item,
                         index,
@@ -2693,103 +3014,6 @@ fn palette(window_tab_data: Rc<WindowTabData>) -> impl View {
     .debug_name("Pallete Layer")
 }
 
-fn window_message_view(
-    messages: RwSignal<Vec<(String, ShowMessageParams)>>,
-    config: ReadSignal<Arc<LapceConfig>>,
-) -> impl View {
-    let view_fn =
-        move |(i, (title, message)): (usize, (String, ShowMessageParams))| {
-            stack((
-                svg(move || {
-                    if let MessageType::ERROR = message.typ {
-                        config.get().ui_svg(LapceIcons::ERROR)
-                    } else {
-                        config.get().ui_svg(LapceIcons::WARNING)
-                    }
-                })
-                .style(move |s| {
-                    let config = config.get();
-                    let size = config.ui.icon_size() as f32;
-                    let color = if let MessageType::ERROR = message.typ {
-                        config.color(LapceColor::LAPCE_ERROR)
-                    } else {
-                        config.color(LapceColor::LAPCE_WARN)
-                    };
-                    s.min_width(size)
-                        .size(size, size)
-                        .margin_right(10.0)
-                        .margin_top(4.0)
-                        .color(color)
-                }),
-                stack((
-                    text(title.clone()).style(|s| {
-                        s.min_width(0.0).line_height(1.6).font_weight(Weight::BOLD)
-                    }),
-                    text(message.message.clone()).style(|s| {
-                        s.min_width(0.0).line_height(1.6).margin_top(5.0)
-                    }),
-                ))
-                .style(move |s| {
-                    s.flex_col().min_width(0.0).flex_basis(0.0).flex_grow(1.0)
-                }),
-                clickable_icon(
-                    || LapceIcons::CLOSE,
-                    move || {
-                        messages.update(|messages| {
-                            messages.remove(i);
-                        });
-                    },
-                    || false,
-                    || false,
-                    || "Close",
-                    config,
-                )
-                .style(|s| s.margin_left(6.0)),
-            ))
-            .style(move |s| {
-                let config = config.get();
-                s.width_full()
-                    .items_start()
-                    .padding(10.0)
-                    .border(1.0)
-                    .border_radius(6.0)
-                    .border_color(config.color(LapceColor::LAPCE_BORDER))
-                    .background(config.color(LapceColor::PANEL_BACKGROUND))
-                    .apply_if(i > 0, |s| s.margin_top(10.0))
-            })
-        };
-
-    let id = AtomicU64::new(0);
-    container(
-        container(
-            container(
-                scroll(
-                    dyn_stack(
-                        move || messages.get().into_iter().enumerate(),
-                        move |_| {
-                            id.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-                        },
-                        view_fn,
-                    )
-                    .style(|s| s.flex_col().width_full()),
-                )
-                .style(|s| {
-                    s.absolute().width_full().min_height(0.0).max_height_full()
-                }),
-            )
-            .style(|s| s.size_full()),
-        )
-        .style(|s| {
-            s.width(360.0)
-                .max_width_pct(80.0)
-                .padding(10.0)
-                .height_full()
-        }),
-    )
-    .style(|s| s.absolute().size_full().justify_end())
-    .debug_name("Window Message View")
-}
-
 struct VectorItems<V>(im::Vector<V>);
 
 impl<V: Clone + 'static> VirtualVector<(usize, V)> for VectorItems<V> {
@@ -3128,6 +3352,27 @@ fn rename(window_tab_data: Rc<WindowTabData>) -> impl View {
     .debug_name("Rename Layer")
 }
 
+/// A strip shown under the title bar while the remote proxy is reconnecting,
+/// which doesn't get in the way of editing
+fn reconnecting_banner(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let proxy_status = window_tab_data.common.proxy_status;
+    label(|| "Connection to the remote lost, reconnecting…".to_string()).style(
+        move |s| {
+            let config = config.get();
+            s.width_pct(100.0)
+                .padding_horiz(10.0)
+                .padding_vert(4.0)
+                .color(Color::WHITE)
+                .background(config.color(LapceColor::LAPCE_REMOTE_CONNECTING))
+                .apply_if(
+                    !matches!(proxy_status.get(), Some(ProxyStatus::Reconnecting)),
+                    |s| s.hide(),
+                )
+        },
+    )
+}
+
 fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
     let source_control = window_tab_data.source_control.clone();
     let window_origin = window_tab_data.common.window_origin;
@@ -3141,6 +3386,7 @@ fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
     let view = stack((
         stack((
             title(window_tab_data.clone()),
+            reconnecting_banner(window_tab_data.clone()),
             workbench(window_tab_data.clone()),
             status(
                 window_tab_data.clone(),
@@ -3164,6 +3410,7 @@ fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
         rename(window_tab_data.clone()),
         palette(window_tab_data.clone()),
         about::about_popup(window_tab_data.clone()),
+        ssh_prompt::ssh_prompt(window_tab_data.clone()),
         alert::alert_box(window_tab_data.alert_data.clone()),
     ))
     .on_cleanup(move || {
@@ -3180,11 +3427,16 @@ fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
             .color(config.color(LapceColor::EDITOR_FOREGROUND))
             .background(config.color(LapceColor::EDITOR_BACKGROUND))
             .font_size(config.ui.font_size() as f32)
+            .font_weight(config.ui.font_weight())
             .apply_if(!config.ui.font_family.is_empty(), |s| {
                 s.font_family(config.ui.font_family.clone())
             })
             .class(floem::views::scroll::Handle, |s| {
                 s.background(config.color(LapceColor::LAPCE_SCROLL_BAR))
+                    .set(Thickness, Px(config.ui.scroll_width() as f64))
+            })
+            .class(floem::views::scroll::Track, |s| {
+                s.set(Thickness, Px(config.ui.scroll_width() as f64))
             })
     })
     .debug_name("Window Tab");
@@ -3202,6 +3454,7 @@ fn workspace_title(workspace: &LapceWorkspace) -> Option<String> {
         LapceWorkspaceType::RemoteSSH(remote) => format!("{dir} [{remote}]"),
         #[cfg(windows)]
         LapceWorkspaceType::RemoteWSL(remote) => format!("{dir} [{remote}]"),
+        LapceWorkspaceType::RemoteContainer(remote) => format!("{dir} [{remote}]"),
     })
 }
 
@@ -3473,6 +3726,7 @@ fn workspace_tab_header(window_data: WindowData) -> impl View {
             .width_full()
             .height(37.0)
             .font_size(config.ui.font_size() as f32)
+            .font_weight(config.ui.font_weight())
             .apply_if(!config.ui.font_family.is_empty(), |s| {
                 s.font_family(config.ui.font_family.clone())
             })
@@ -3545,6 +3799,14 @@ fn window(window_data: WindowData) -> impl View {
 }
 
 pub fn launch() {
+    let launched = std::time::Instant::now();
+
+    // Run by ssh to ask for a password, which is answered by the Lapce window
+    // that's connecting
+    if let Some(code) = crate::proxy::run_askpass() {
+        std::process::exit(code);
+    }
+
     logging::panic_hook();
 
     let (reload_handle, _guard) = logging::logging();
@@ -3578,52 +3840,48 @@ pub fn launch() {
     let stdin = std::io::stdin();
     if !stdin.is_terminal() {
         trace!(TraceLevel::INFO, "Loading custom environment from shell");
-        load_shell_env();
+        lapce_proxy::shell_env::load_shell_env();
     }
 
     let cli = Cli::parse();
+    if cli.profile_startup {
+        crate::startup::start(launched);
+        crate::startup::phase("set up logging and environment");
+    }
+
+    // Open the files in the running Lapce process, or in one launched for
+    // them, and block until they're closed. A new Lapce is run in the
+    // foreground instead, until it's quit.
+    if cli.wait && !cli.new {
+        let socket = get_socket().or_else(|_| {
+            let mut args = vec![
+                std::env::args().next().unwrap_or_default(),
+                "--foreground".to_string(),
+            ];
+            for path in &cli.plugin_path {
+                args.push("--plugin-path".to_string());
+                args.push(path.to_string_lossy().to_string());
+            }
+            launch_detached(&args);
+            wait_for_socket()
+        });
+        let result = socket.and_then(|socket| {
+            try_open_in_existing_process(socket, &cli.paths, cli.new_window, true)
+        });
+        if let Err(e) = result {
+            eprintln!("Failed to open path(s) in lapce: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
 
     // small hack to unblock terminal if launched from it
-    // launch it as a separate process that waits
-    if !cli.wait {
+    // launch it as a separate process that waits, unless the startup profile
+    // is to be printed to it or it's waited for
+    if !cli.foreground && !cli.wait && !cli.profile_startup {
         let mut args = std::env::args().collect::<Vec<_>>();
-        args.push("--wait".to_string());
-        let mut cmd = std::process::Command::new(&args[0]);
-        #[cfg(target_os = "windows")]
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-
-        let stderr_file_path =
-            Directory::logs_directory().unwrap().join("stderr.log");
-        let stderr_file = std::fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .read(true)
-            .open(stderr_file_path)
-            .unwrap();
-        let stderr = Stdio::from(stderr_file);
-
-        let stdout_file_path =
-            Directory::logs_directory().unwrap().join("stdout.log");
-        let stdout_file = std::fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .read(true)
-            .open(stdout_file_path)
-            .unwrap();
-        let stdout = Stdio::from(stdout_file);
-
-        if let Err(why) = cmd
-            .args(&args[1..])
-            .stderr(stderr)
-            .stdout(stdout)
-            .env("LAPCE_LOG", "lapce_app::app=error,off")
-            .spawn()
-        {
-            eprintln!("Failed to launch lapce: {why}");
-            std::process::exit(1);
-        };
+        args.push("--foreground".to_string());
+        launch_detached(&args);
         return;
     }
 
@@ -3631,7 +3889,12 @@ pub fn launch() {
     // in the existing Lapce process
     if !cli.new {
         if let Ok(socket) = get_socket() {
-            if let Err(e) = try_open_in_existing_process(socket, &cli.paths) {
+            if let Err(e) = try_open_in_existing_process(
+                socket,
+                &cli.paths,
+                cli.new_window,
+                false,
+            ) {
                 trace!(TraceLevel::ERROR, "failed to open path(s): {e}");
             };
             return;
@@ -3678,6 +3941,7 @@ pub fn launch() {
             std::process::exit(1);
         }
     };
+    crate::startup::phase("open database");
     let scope = Scope::new();
     provide_context(db.clone());
 
@@ -3687,6 +3951,15 @@ pub fn launch() {
 
     let plugin_paths = Arc::new(cli.plugin_path);
 
+    // The languages volts add have to be known before any file is opened
+    let disabled_volts = db.get_disabled_volts().unwrap_or_default();
+    let volts: Vec<_> = find_all_volts(&plugin_paths)
+        .into_iter()
+        .filter(|meta| !disabled_volts.contains(&meta.id()))
+        .collect();
+    LapceLanguage::load_runtime_languages(&volts);
+    crate::startup::phase("load plugin languages");
+
     let (tx, rx) = crossbeam_channel::bounded(1);
     let mut watcher = notify::recommended_watcher(ConfigWatcher::new(tx)).unwrap();
     if let Some(path) = LapceConfig::settings_file() {
@@ -3704,6 +3977,7 @@ pub fn launch() {
 
     let windows = scope.create_rw_signal(im::HashMap::new());
     let config = LapceConfig::load(&LapceWorkspace::default(), &[], &plugin_paths);
+    crate::startup::phase("load config");
 
     // Restore scale from config
     window_scale.set(config.ui.scale());
@@ -3723,6 +3997,14 @@ pub fn launch() {
     };
 
     let app = app_data.create_windows(db.clone(), cli.paths);
+    crate::startup::phase("create windows");
+    app_data.journal_unsaved_docs(db.clone(), Vec::new());
+    app_data.save_sessions(db.clone());
+    // Timers only run once the event loop has shown the windows
+    exec_after(Duration::ZERO, |_| {
+        crate::startup::phase("show windows");
+        crate::startup::report();
+    });
 
     {
         let app_data = app_data.clone();
@@ -3757,9 +4039,21 @@ pub fn launch() {
         let notification = create_signal_from_channel(rx);
         let app_data = app_data.clone();
         create_effect(move |_| {
-            if let Some(CoreNotification::OpenPaths { paths }) = notification.get() {
-                if let Some(window_tab) = app_data.active_window_tab() {
-                    window_tab.open_paths(&paths);
+            if let Some((
+                CoreNotification::OpenPaths {
+                    paths, new_window, ..
+                },
+                closed,
+            )) = notification.get()
+            {
+                match app_data.active_window_tab().filter(|_| !new_window) {
+                    Some(window_tab) => {
+                        window_tab.open_paths(&paths);
+                        if let Some(closed) = closed {
+                            window_tab.notify_files_closed(&paths, closed);
+                        }
+                    }
+                    None => app_data.new_window_with_paths(paths, closed),
                 }
             }
         });
@@ -3791,60 +4085,6 @@ pub fn launch() {
     .run();
 }
 
-/// Uses a login shell to load the correct shell environment for the current user.
-fn load_shell_env() {
-    use std::process::Command;
-
-    use tracing::warn;
-
-    #[cfg(not(windows))]
-    let shell = match std::env::var("SHELL") {
-        Ok(s) => s,
-        Err(error) => {
-            // Shell variable is not set, so we can't determine the correct shell executable.
-            trace!(
-                TraceLevel::ERROR,
-                "Failed to obtain shell environment: {error}"
-            );
-            return;
-        }
-    };
-
-    #[cfg(windows)]
-    let shell = "powershell";
-
-    let mut command = Command::new(shell);
-
-    #[cfg(not(windows))]
-    command.args(["--login", "-c", "printenv"]);
-
-    #[cfg(windows)]
-    command.args(["{ ls env: | foreach { '{0}={1}' -f $_.Name, $_.Value } }"]);
-
-    let env = match command.output() {
-        Ok(output) => String::from_utf8(output.stdout).unwrap_or_default(),
-
-        Err(error) => {
-            trace!(
-                TraceLevel::ERROR,
-                "Failed to obtain shell environment: {error}"
-            );
-            return;
-        }
-    };
-
-    env.split('\n')
-        .filter_map(|line| line.split_once('='))
-        .for_each(|(key, value)| {
-            if let Ok(v) = std::env::var(key) {
-                if v != value {
-                    warn!("Overwriting '{key}', previous value: '{v}', new value '{value}'");
-                }
-            };
-            std::env::set_var(key, value);
-        })
-}
-
 pub fn get_socket() -> Result<interprocess::local_socket::LocalSocketStream> {
     let local_socket = Directory::local_socket()
         .ok_or_else(|| anyhow!("can't get local socket folder"))?;
@@ -3853,35 +4093,128 @@ pub fn get_socket() -> Result<interprocess::local_socket::LocalSocketStream> {
     Ok(socket)
 }
 
+/// The type of the workspaces of the folders opened from the command line
+fn cli_workspace_type() -> LapceWorkspaceType {
+    #[cfg(windows)]
+    if !std::env::var("WSL_DISTRO_NAME")
+        .unwrap_or_default()
+        .is_empty()
+        || !std::env::var("WSL_INTEROP").unwrap_or_default().is_empty()
+    {
+        // Without the name of the distribution, the default one is used
+        return LapceWorkspaceType::RemoteWSL(crate::workspace::WslHost {
+            host: std::env::var("WSL_DISTRO_NAME").unwrap_or_default(),
+        });
+    }
+    LapceWorkspaceType::Local
+}
+
+/// Wait for a Lapce process that was just launched to listen on the socket
+fn wait_for_socket() -> Result<interprocess::local_socket::LocalSocketStream> {
+    let mut tries = 0;
+    loop {
+        match get_socket() {
+            Ok(socket) => return Ok(socket),
+            Err(e) if tries >= 100 => return Err(e),
+            Err(_) => {
+                tries += 1;
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+        }
+    }
+}
+
+/// Launch Lapce as a separate process with the arguments, its output going to
+/// the log files, so that the terminal it's launched from isn't blocked
+fn launch_detached(args: &[String]) {
+    let mut cmd = std::process::Command::new(&args[0]);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let stderr_file_path = Directory::logs_directory().unwrap().join("stderr.log");
+    let stderr_file = std::fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .read(true)
+        .open(stderr_file_path)
+        .unwrap();
+    let stderr = Stdio::from(stderr_file);
+
+    let stdout_file_path = Directory::logs_directory().unwrap().join("stdout.log");
+    let stdout_file = std::fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .read(true)
+        .open(stdout_file_path)
+        .unwrap();
+    let stdout = Stdio::from(stdout_file);
+
+    if let Err(why) = cmd
+        .args(&args[1..])
+        .stderr(stderr)
+        .stdout(stdout)
+        .env("LAPCE_LOG", "lapce_app::app=error,off")
+        .spawn()
+    {
+        eprintln!("Failed to launch lapce: {why}");
+        std::process::exit(1);
+    };
+}
+
+/// Open the paths in the Lapce process listening on the socket, and with
+/// `wait` return once the files are closed
 pub fn try_open_in_existing_process(
     mut socket: interprocess::local_socket::LocalSocketStream,
     paths: &[PathObject],
+    new_window: bool,
+    wait: bool,
 ) -> Result<()> {
     let msg: CoreMessage = RpcMessage::Notification(CoreNotification::OpenPaths {
         paths: paths.to_vec(),
+        new_window,
+        wait,
     });
     lapce_rpc::stdio::write_msg(&mut socket, msg)?;
 
     let (tx, rx) = crossbeam_channel::bounded(1);
+    let (closed_tx, closed_rx) = crossbeam_channel::bounded(1);
     std::thread::spawn(move || {
         let mut buf = [0; 100];
-        let received = if let Ok(n) = socket.read(&mut buf) {
-            &buf[..n] == b"received"
+        let (received, closed) = if let Ok(n) = socket.read(&mut buf) {
+            (
+                buf[..n].starts_with(b"received"),
+                buf[..n].ends_with(b"closed"),
+            )
         } else {
-            false
+            (false, false)
         };
-        tx.send(received)
+        let _ = tx.send(received);
+        if received && wait {
+            if !closed {
+                let _ = socket.read(&mut buf);
+            }
+            let _ = closed_tx.send(());
+        }
     });
 
     let received = rx.recv_timeout(std::time::Duration::from_millis(500))?;
     if !received {
         return Err(anyhow!("didn't receive response"));
     }
+    if wait {
+        let _ = closed_rx.recv();
+    }
 
     Ok(())
 }
 
-fn listen_local_socket(tx: Sender<CoreNotification>) -> Result<()> {
+/// Listen for the paths other Lapce processes are asked to open, which come with
+/// where to tell when the files are closed if they wait for it
+fn listen_local_socket(
+    tx: Sender<(CoreNotification, Option<Sender<()>>)>,
+) -> Result<()> {
     let local_socket = Directory::local_socket()
         .ok_or_else(|| anyhow!("can't get local socket folder"))?;
     let _ = std::fs::remove_file(&local_socket);
@@ -3896,15 +4229,29 @@ fn listen_local_socket(tx: Sender<CoreNotification>) -> Result<()> {
                 let msg: Option<CoreMessage> =
                     lapce_rpc::stdio::read_msg(&mut reader)?;
 
-                if let Some(RpcMessage::Notification(msg)) = msg {
-                    tx.send(msg)?;
+                let closed = if let Some(RpcMessage::Notification(msg)) = msg {
+                    let wait = matches!(
+                        msg,
+                        CoreNotification::OpenPaths { wait: true, .. }
+                    );
+                    let (closed_tx, closed_rx) = crossbeam_channel::bounded(1);
+                    tx.send((msg, wait.then_some(closed_tx)))?;
+                    wait.then_some(closed_rx)
                 } else {
                     trace!(TraceLevel::ERROR, "Unhandled message: {msg:?}");
-                }
+                    None
+                };
 
                 let stream_ref = reader.get_mut();
                 let _ = stream_ref.write_all(b"received");
                 let _ = stream_ref.flush();
+
+                if let Some(closed) = closed {
+                    // The sender is dropped if the window is closed first
+                    let _ = closed.recv();
+                    let _ = stream_ref.write_all(b"closed");
+                    let _ = stream_ref.flush();
+                }
             }
         });
     }
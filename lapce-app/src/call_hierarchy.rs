@@ -0,0 +1,290 @@
+use std::{path::PathBuf, rc::Rc};
+
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{RwSignal, Scope},
+};
+use lapce_rpc::{
+    plugin::PluginId,
+    proxy::{CallHierarchyDirection, ProxyResponse, TypeHierarchyDirection},
+    RpcError,
+};
+use lsp_types::{CallHierarchyItem, Position};
+
+use crate::{
+    editor::location::{EditorLocation, EditorPosition},
+    id::CallHierarchyNodeId,
+    lsp::path_from_url,
+    window_tab::CommonData,
+};
+
+/// What the hierarchy panel lists under each item
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HierarchyDirection {
+    Callers,
+    Callees,
+    Supertypes,
+    Subtypes,
+}
+
+impl HierarchyDirection {
+    /// Whether the hierarchy is of types rather than calls, whose items are
+    /// prepared with a different request
+    pub fn is_type(&self) -> bool {
+        matches!(
+            self,
+            HierarchyDirection::Supertypes | HierarchyDirection::Subtypes
+        )
+    }
+}
+
+/// An item of the call or type hierarchy, whose calls or related types are
+/// requested the first time it's opened
+#[derive(Clone)]
+pub struct CallHierarchyNode {
+    pub id: CallHierarchyNodeId,
+    pub item: Rc<CallHierarchyItem>,
+    /// The depth in the tree, the item the hierarchy was shown for is 0
+    pub level: usize,
+    /// Where clicking the node jumps to, the call for the callers and the
+    /// definition otherwise
+    pub location: EditorLocation,
+    /// The number of times the item calls or is called by its parent
+    pub calls: usize,
+    pub open: RwSignal<bool>,
+    pub children: RwSignal<Option<im::Vector<CallHierarchyNode>>>,
+}
+
+impl CallHierarchyNode {
+    fn new(
+        cx: Scope,
+        item: CallHierarchyItem,
+        level: usize,
+        call: Option<(PathBuf, Position)>,
+        calls: usize,
+    ) -> Self {
+        let (path, position) = call.unwrap_or_else(|| {
+            (path_from_url(&item.uri), item.selection_range.start)
+        });
+        Self {
+            id: CallHierarchyNodeId::next(),
+            item: Rc::new(item),
+            level,
+            location: EditorLocation {
+                path,
+                position: Some(EditorPosition::Position(position)),
+                scroll_offset: None,
+                ignore_unconfirmed: false,
+                same_editor_tab: false,
+            },
+            calls,
+            open: cx.create_rw_signal(false),
+            children: cx.create_rw_signal(None),
+        }
+    }
+
+    /// Add the node and the nodes under it that are open to `nodes`
+    fn push_visible(&self, nodes: &mut Vec<CallHierarchyNode>) {
+        nodes.push(self.clone());
+        if self.open.get() {
+            if let Some(children) = self.children.get() {
+                for child in children.iter() {
+                    child.push_visible(nodes);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CallHierarchyData {
+    pub root: RwSignal<Option<CallHierarchyNode>>,
+    pub direction: RwSignal<HierarchyDirection>,
+    /// Why there's no hierarchy to show, shown in its place
+    pub error: RwSignal<Option<String>>,
+    /// The server that prepared the hierarchy, which is asked for the calls
+    plugin_id: RwSignal<Option<PluginId>>,
+    scope: Scope,
+    pub common: Rc<CommonData>,
+}
+
+impl CallHierarchyData {
+    pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
+        Self {
+            root: cx.create_rw_signal(None),
+            direction: cx.create_rw_signal(HierarchyDirection::Callers),
+            error: cx.create_rw_signal(None),
+            plugin_id: cx.create_rw_signal(None),
+            scope: cx,
+            common,
+        }
+    }
+
+    /// Show the hierarchy of the symbol at the position, the calls or the
+    /// types depending on the direction
+    pub fn show(
+        &self,
+        path: PathBuf,
+        position: Position,
+        direction: HierarchyDirection,
+    ) {
+        let data = self.clone();
+        let send = create_ext_action(
+            self.scope,
+            move |response: Result<ProxyResponse, RpcError>| {
+                let (plugin_id, items) = match response {
+                    Ok(ProxyResponse::PrepareCallHierarchyResponse {
+                        plugin_id,
+                        items,
+                    })
+                    | Ok(ProxyResponse::PrepareTypeHierarchyResponse {
+                        plugin_id,
+                        items,
+                    }) => (plugin_id, items),
+                    Ok(_) => return,
+                    Err(err) => {
+                        data.show_error(&err.message);
+                        return;
+                    }
+                };
+                if let Some(item) = items.into_iter().next() {
+                    data.plugin_id.set(Some(plugin_id));
+                    data.direction.set(direction);
+                    data.set_root(item);
+                } else if direction.is_type() {
+                    data.show_error("No type hierarchy at the cursor");
+                } else {
+                    data.show_error("No call hierarchy at the cursor");
+                }
+            },
+        );
+        let proxy = &self.common.proxy;
+        if direction.is_type() {
+            proxy.prepare_type_hierarchy(path, position, move |result| {
+                send(result);
+            });
+        } else {
+            proxy.prepare_call_hierarchy(path, position, move |result| {
+                send(result);
+            });
+        }
+    }
+
+    /// Switch between the callers and the callees of the item, or its
+    /// supertypes and subtypes
+    pub fn set_direction(&self, direction: HierarchyDirection) {
+        if self.direction.get_untracked() == direction {
+            return;
+        }
+        self.direction.set(direction);
+        if let Some(root) = self.root.get_untracked() {
+            self.set_root((*root.item).clone());
+        }
+    }
+
+    fn set_root(&self, item: CallHierarchyItem) {
+        self.error.set(None);
+        let root = CallHierarchyNode::new(self.scope, item, 0, None, 0);
+        self.root.set(Some(root.clone()));
+        self.toggle(&root);
+    }
+
+    /// Open or close the node, requesting its calls the first time
+    pub fn toggle(&self, node: &CallHierarchyNode) {
+        let open = !node.open.get_untracked();
+        node.open.set(open);
+        if open && node.children.with_untracked(|children| children.is_none()) {
+            self.load_calls(node);
+        }
+    }
+
+    /// The nodes shown in the panel, each followed by the children of the
+    /// open ones
+    pub fn visible_nodes(&self) -> Vec<CallHierarchyNode> {
+        let mut nodes = Vec::new();
+        if let Some(root) = self.root.get() {
+            root.push_visible(&mut nodes);
+        }
+        nodes
+    }
+
+    fn load_calls(&self, node: &CallHierarchyNode) {
+        let Some(plugin_id) = self.plugin_id.get_untracked() else {
+            return;
+        };
+        let direction = self.direction.get_untracked();
+        let scope = self.scope;
+        let children = node.children;
+        let level = node.level + 1;
+        let send = create_ext_action(
+            self.scope,
+            move |response: Result<ProxyResponse, RpcError>| {
+                let calls = match response {
+                    Ok(ProxyResponse::CallHierarchyCallsResponse { calls }) => calls,
+                    Ok(ProxyResponse::TypeHierarchyRelatedResponse { items }) => {
+                        items.into_iter().map(|item| (item, Vec::new())).collect()
+                    }
+                    _ => Vec::new(),
+                };
+                let nodes = calls
+                    .into_iter()
+                    .map(|(item, ranges)| {
+                        // The ranges of the calls are in the callers
+                        let call = match direction {
+                            HierarchyDirection::Callers => {
+                                ranges.first().map(|range| {
+                                    (path_from_url(&item.uri), range.start)
+                                })
+                            }
+                            _ => None,
+                        };
+                        CallHierarchyNode::new(
+                            scope,
+                            item,
+                            level,
+                            call,
+                            ranges.len(),
+                        )
+                    })
+                    .collect();
+                children.set(Some(nodes));
+            },
+        );
+        let item = (*node.item).clone();
+        let proxy = &self.common.proxy;
+        let send = move |result| {
+            send(result);
+        };
+        match direction {
+            HierarchyDirection::Callers => proxy.call_hierarchy_calls(
+                plugin_id,
+                item,
+                CallHierarchyDirection::Incoming,
+                send,
+            ),
+            HierarchyDirection::Callees => proxy.call_hierarchy_calls(
+                plugin_id,
+                item,
+                CallHierarchyDirection::Outgoing,
+                send,
+            ),
+            HierarchyDirection::Supertypes => proxy.type_hierarchy_related(
+                plugin_id,
+                item,
+                TypeHierarchyDirection::Supertypes,
+                send,
+            ),
+            HierarchyDirection::Subtypes => proxy.type_hierarchy_related(
+                plugin_id,
+                item,
+                TypeHierarchyDirection::Subtypes,
+                send,
+            ),
+        }
+    }
+
+    fn show_error(&self, message: &str) {
+        self.root.set(None);
+        self.error.set(Some(message.to_string()));
+    }
+}
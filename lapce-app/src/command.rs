@@ -1,4 +1,4 @@
-use std::{path::PathBuf, rc::Rc, sync::Arc};
+use std::{borrow::Cow, path::PathBuf, rc::Rc, sync::Arc};
 
 use floem::{
     keyboard::Modifiers, peniko::kurbo::Vec2, views::editor::command::Command,
@@ -10,8 +10,9 @@
 };
 use lapce_rpc::{
     dap_types::{DapId, RunDebugConfig},
-    plugin::{PluginId, VoltID},
+    plugin::{PluginCommand, PluginId, VoltID},
     proxy::ProxyStatus,
+    source_control::GitSyncKind,
     terminal::{TermId, TerminalProfile},
 };
 use lsp_types::{CodeActionOrCommand, Position, WorkspaceEdit};
@@ -21,7 +22,7 @@
 
 use crate::{
     alert::AlertButton,
-    debug::RunDebugMode,
+    debug::{BreakpointTarget, RunDebugMode},
     doc::Doc,
     editor::location::EditorLocation,
     editor_tab::EditorTabChild,
@@ -38,6 +39,46 @@ pub struct LapceCommand {
     pub data: Option<Value>,
 }
 
+impl LapceCommand {
+    pub fn plugin(command: PluginCommand) -> Self {
+        Self {
+            kind: CommandKind::Workbench(LapceWorkbenchCommand::RunPluginCommand),
+            data: serde_json::to_value(command).ok(),
+        }
+    }
+
+    /// The volt's command this runs, if it is one
+    pub fn plugin_command(&self) -> Option<PluginCommand> {
+        if self.kind
+            != CommandKind::Workbench(LapceWorkbenchCommand::RunPluginCommand)
+        {
+            return None;
+        }
+        serde_json::from_value(self.data.clone()?).ok()
+    }
+
+    /// The name the command is bound by in the keymaps
+    pub fn name(&self) -> Cow<'static, str> {
+        match self.plugin_command() {
+            Some(command) => {
+                Cow::Owned(format!("plugin.{}.{}", command.volt_id, command.command))
+            }
+            None => Cow::Borrowed(self.kind.str()),
+        }
+    }
+
+    /// What the palette shows for the command, none for those it doesn't list
+    pub fn desc(&self) -> Option<Cow<'static, str>> {
+        match self.plugin_command() {
+            Some(command) => Some(Cow::Owned(format!(
+                "{}: {}",
+                command.volt_display_name, command.title
+            ))),
+            None => self.kind.desc().map(Cow::Borrowed),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CommandKind {
     Workbench(LapceWorkbenchCommand),
@@ -183,10 +224,66 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Open File")]
     OpenFile,
 
+    #[strum(serialize = "file_explorer_toggle_hidden_files")]
+    #[strum(message = "File Explorer: Toggle Showing Excluded and Ignored Files")]
+    FileExplorerToggleHiddenFiles,
+
+    #[strum(serialize = "reveal_in_explorer")]
+    #[strum(message = "File Explorer: Reveal Active File")]
+    RevealInExplorer,
+
     #[strum(serialize = "reveal_active_file_in_file_explorer")]
-    #[strum(message = "Reveal Active File in File Explorer")]
+    #[strum(message = "Reveal Active File in System File Manager")]
     RevealActiveFileInFileExplorer,
 
+    #[strum(serialize = "file_explorer_new_file")]
+    #[strum(message = "File Explorer: New File")]
+    FileExplorerNewFile,
+
+    #[strum(serialize = "file_explorer_new_directory")]
+    #[strum(message = "File Explorer: New Directory")]
+    FileExplorerNewDirectory,
+
+    #[strum(serialize = "file_explorer_rename")]
+    #[strum(message = "File Explorer: Rename")]
+    FileExplorerRename,
+
+    #[strum(serialize = "file_explorer_duplicate")]
+    #[strum(message = "File Explorer: Duplicate")]
+    FileExplorerDuplicate,
+
+    #[strum(serialize = "file_explorer_delete")]
+    #[strum(message = "File Explorer: Move to Trash")]
+    FileExplorerDelete,
+
+    #[strum(serialize = "file_explorer_cut")]
+    #[strum(message = "File Explorer: Cut")]
+    FileExplorerCut,
+
+    #[strum(serialize = "file_explorer_copy")]
+    #[strum(message = "File Explorer: Copy")]
+    FileExplorerCopy,
+
+    #[strum(serialize = "file_explorer_paste")]
+    #[strum(message = "File Explorer: Paste")]
+    FileExplorerPaste,
+
+    #[strum(serialize = "file_explorer_copy_path")]
+    #[strum(message = "File Explorer: Copy Path")]
+    FileExplorerCopyPath,
+
+    #[strum(serialize = "file_explorer_copy_relative_path")]
+    #[strum(message = "File Explorer: Copy Relative Path")]
+    FileExplorerCopyRelativePath,
+
+    #[strum(serialize = "file_explorer_open_in_terminal")]
+    #[strum(message = "File Explorer: Open in Integrated Terminal")]
+    FileExplorerOpenInTerminal,
+
+    #[strum(serialize = "file_explorer_reveal_in_file_manager")]
+    #[strum(message = "File Explorer: Reveal in System File Manager")]
+    FileExplorerRevealInFileManager,
+
     #[strum(serialize = "open_ui_inspector")]
     #[strum(message = "Open Internal UI Inspector")]
     OpenUIInspector,
@@ -227,6 +324,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Open Keyboard Shortcuts File")]
     OpenKeyboardShortcutsFile,
 
+    #[strum(serialize = "import_vscode_settings")]
+    #[strum(message = "Import Settings and Keybindings from VS Code")]
+    ImportVsCodeSettings,
+
     #[strum(serialize = "open_log_file")]
     #[strum(message = "Open Log File")]
     OpenLogFile,
@@ -271,6 +372,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Create New Terminal Tab")]
     NewTerminalTab,
 
+    #[strum(serialize = "new_terminal_tab_with_profile")]
+    #[strum(message = "Create New Terminal Tab with Profile")]
+    NewTerminalTabWithProfile,
+
     #[strum(serialize = "close_terminal_tab")]
     #[strum(message = "Close Terminal Tab")]
     CloseTerminalTab,
@@ -283,6 +388,30 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Previous Terminal Tab")]
     PreviousTerminalTab,
 
+    #[strum(serialize = "run_selection_in_terminal")]
+    #[strum(message = "Run Selection in Terminal")]
+    RunSelectionInTerminal,
+
+    #[strum(serialize = "run_file")]
+    #[strum(message = "Run Current File")]
+    RunFile,
+
+    #[strum(serialize = "split_terminal")]
+    #[strum(message = "Split Terminal")]
+    SplitTerminal,
+
+    #[strum(serialize = "rename_terminal")]
+    #[strum(message = "Rename Terminal")]
+    RenameTerminal,
+
+    #[strum(serialize = "restart_terminal")]
+    #[strum(message = "Restart Terminal")]
+    RestartTerminal,
+
+    #[strum(serialize = "kill_terminal")]
+    #[strum(message = "Kill Terminal")]
+    KillTerminal,
+
     #[strum(serialize = "next_window_tab")]
     #[strum(message = "Go To Next Window Tab")]
     NextWindowTab,
@@ -307,19 +436,35 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "new_file")]
     NewFile,
 
+    #[strum(message = "New Untitled File")]
+    #[strum(serialize = "new_untitled_file")]
+    NewUntitledFile,
+
     #[strum(serialize = "connect_ssh_host")]
     #[strum(message = "Connect to SSH Host")]
     ConnectSshHost,
 
+    #[strum(serialize = "manage_workspace_trust")]
+    #[strum(message = "Manage Workspace Trust")]
+    ManageWorkspaceTrust,
+
     #[cfg(windows)]
     #[strum(serialize = "connect_wsl_host")]
     #[strum(message = "Connect to WSL Host")]
     ConnectWslHost,
 
+    #[strum(serialize = "connect_container")]
+    #[strum(message = "Connect to Container")]
+    ConnectContainer,
+
     #[strum(serialize = "disconnect_remote")]
     #[strum(message = "Disconnect From Remote")]
     DisconnectRemote,
 
+    #[strum(serialize = "forward_port")]
+    #[strum(message = "Forward a Port")]
+    ForwardPort,
+
     #[strum(message = "Go To Line")]
     #[strum(serialize = "palette.line")]
     PaletteLine,
@@ -341,8 +486,8 @@ pub enum LapceWorkbenchCommand {
     PaletteCommand,
 
     #[strum(message = "Open Recent Workspace")]
-    #[strum(serialize = "palette.workspace")]
-    PaletteWorkspace,
+    #[strum(serialize = "open_recent_workspace")]
+    OpenRecentWorkspace,
 
     #[strum(message = "Run and Debug")]
     #[strum(serialize = "palette.run_and_debug")]
@@ -364,9 +509,72 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.run_and_debug_stop")]
     RunAndDebugStop,
 
+    #[strum(message = "Toggle Breakpoint")]
+    #[strum(serialize = "toggle_breakpoint")]
+    ToggleBreakpoint,
+
+    #[strum(message = "Debug: Continue")]
+    #[strum(serialize = "debug_continue")]
+    DebugContinue,
+
+    #[strum(message = "Debug: Pause")]
+    #[strum(serialize = "debug_pause")]
+    DebugPause,
+
+    #[strum(message = "Debug: Step Over")]
+    #[strum(serialize = "debug_step_over")]
+    DebugStepOver,
+
+    #[strum(message = "Debug: Step Into")]
+    #[strum(serialize = "debug_step_into")]
+    DebugStepInto,
+
+    #[strum(message = "Debug: Step Out")]
+    #[strum(serialize = "debug_step_out")]
+    DebugStepOut,
+
+    #[strum(message = "Debug: Add Watch Expression")]
+    #[strum(serialize = "debug_add_watch_expression")]
+    DebugAddWatchExpression,
+
     #[strum(serialize = "source_control.checkout_reference")]
     CheckoutReference,
 
+    #[strum(serialize = "source_control.create_branch")]
+    CreateBranch,
+
+    #[strum(message = "Source Control: Stash Changes")]
+    #[strum(serialize = "source_control.stash")]
+    SourceControlStash,
+
+    #[strum(message = "Source Control: Pop Latest Stash")]
+    #[strum(serialize = "source_control.stash_pop")]
+    SourceControlStashPop,
+
+    #[strum(message = "Source Control: Apply Latest Stash")]
+    #[strum(serialize = "source_control.stash_apply")]
+    SourceControlStashApply,
+
+    #[strum(message = "Source Control: Fetch")]
+    #[strum(serialize = "source_control.fetch")]
+    SourceControlFetch,
+
+    #[strum(message = "Source Control: Pull")]
+    #[strum(serialize = "source_control.pull")]
+    SourceControlPull,
+
+    #[strum(message = "Source Control: Push")]
+    #[strum(serialize = "source_control.push")]
+    SourceControlPush,
+
+    #[strum(message = "Source Control: Show Git Log")]
+    #[strum(serialize = "source_control.show_log")]
+    SourceControlShowLog,
+
+    #[strum(message = "Source Control: Show File History")]
+    #[strum(serialize = "source_control.show_file_history")]
+    SourceControlShowFileHistory,
+
     #[strum(serialize = "toggle_maximized_panel")]
     ToggleMaximizedPanel,
 
@@ -396,6 +604,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_panel_bottom_visual")]
     TogglePanelBottomVisual,
 
+    #[strum(message = "Collapse or Expand Left Panel")]
+    #[strum(serialize = "toggle_panel_left_collapsed")]
+    TogglePanelLeftCollapsed,
+
+    #[strum(message = "Collapse or Expand Right Panel")]
+    #[strum(serialize = "toggle_panel_right_collapsed")]
+    TogglePanelRightCollapsed,
+
+    #[strum(message = "Collapse or Expand Bottom Panel")]
+    #[strum(serialize = "toggle_panel_bottom_collapsed")]
+    TogglePanelBottomCollapsed,
+
     // Focus toggle commands
     #[strum(message = "Toggle Terminal Focus")]
     #[strum(serialize = "toggle_terminal_focus")]
@@ -420,6 +640,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_focus")]
     ToggleSearchFocus,
 
+    #[strum(message = "Toggle Debug Console Focus")]
+    #[strum(serialize = "toggle_debug_console_focus")]
+    ToggleDebugConsoleFocus,
+
     // Visual toggle commands
     #[strum(serialize = "toggle_terminal_visual")]
     ToggleTerminalVisual,
@@ -442,6 +666,24 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_visual")]
     ToggleSearchVisual,
 
+    #[strum(serialize = "toggle_call_hierarchy_visual")]
+    ToggleCallHierarchyVisual,
+
+    #[strum(serialize = "toggle_ports_visual")]
+    TogglePortsVisual,
+
+    #[strum(serialize = "toggle_plugin_views_visual")]
+    TogglePluginViewsVisual,
+
+    #[strum(serialize = "toggle_undo_history_visual")]
+    ToggleUndoHistoryVisual,
+
+    #[strum(serialize = "toggle_notification_visual")]
+    ToggleNotificationVisual,
+
+    #[strum(serialize = "toggle_debug_console_visual")]
+    ToggleDebugConsoleVisual,
+
     #[strum(serialize = "focus_editor")]
     FocusEditor,
 
@@ -455,6 +697,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "source_control_commit")]
     SourceControlCommit,
 
+    #[strum(message = "Source Control: Commit (Amend)")]
+    #[strum(serialize = "source_control_commit_amend")]
+    SourceControlCommitAmend,
+
+    #[strum(message = "Source Control: Stage All Changes")]
+    #[strum(serialize = "source_control_stage_all")]
+    SourceControlStageAll,
+
+    #[strum(message = "Source Control: Unstage All Changes")]
+    #[strum(serialize = "source_control_unstage_all")]
+    SourceControlUnstageAll,
+
     #[strum(message = "Source Control: Copy Remote File Url")]
     #[strum(serialize = "source_control_copy_active_file_remote_url")]
     SourceControlCopyActiveFileRemoteUrl,
@@ -486,6 +740,22 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Change current file line ending")]
     ChangeFileLineEnding,
 
+    #[strum(serialize = "reopen_with_encoding")]
+    #[strum(message = "Reopen with Encoding")]
+    ReopenWithEncoding,
+
+    #[strum(serialize = "save_with_encoding")]
+    #[strum(message = "Save with Encoding")]
+    SaveWithEncoding,
+
+    #[strum(serialize = "toggle_read_only")]
+    #[strum(message = "Toggle Read-Only for Current File")]
+    ToggleReadOnly,
+
+    #[strum(serialize = "keep_tab_open")]
+    #[strum(message = "Keep Tab Open")]
+    KeepTabOpen,
+
     #[strum(serialize = "next_editor_tab")]
     #[strum(message = "Next Editor Tab")]
     NextEditorTab,
@@ -498,6 +768,43 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Toggle Inlay Hints")]
     ToggleInlayHints,
 
+    #[strum(serialize = "restart_language_server")]
+    #[strum(message = "Restart Language Server")]
+    RestartLanguageServer,
+
+    #[strum(serialize = "stop_language_server")]
+    #[strum(message = "Stop Language Server")]
+    StopLanguageServer,
+
+    #[strum(serialize = "show_language_servers")]
+    #[strum(message = "Show Running Language Servers")]
+    ShowLanguageServers,
+
+    #[strum(serialize = "open_language_server_log")]
+    #[strum(message = "Open LSP Log")]
+    OpenLanguageServerLog,
+
+    /// Runs the [`PluginCommand`] in its data, which is how a volt's
+    /// command is found in the keymaps and the palette
+    #[strum(serialize = "run_plugin_command")]
+    RunPluginCommand,
+
+    #[strum(serialize = "undo_workspace_edit")]
+    #[strum(message = "Undo Last Refactoring")]
+    UndoWorkspaceEdit,
+
+    #[strum(serialize = "format_selection")]
+    #[strum(message = "Format Selection")]
+    FormatSelection,
+
+    #[strum(serialize = "show_call_hierarchy")]
+    #[strum(message = "Show Call Hierarchy")]
+    ShowCallHierarchy,
+
+    #[strum(serialize = "show_type_hierarchy")]
+    #[strum(message = "Show Type Hierarchy")]
+    ShowTypeHierarchy,
+
     #[strum(serialize = "restart_to_update")]
     RestartToUpdate,
 
@@ -539,10 +846,78 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "previous_error")]
     PreviousError,
 
+    #[strum(message = "Go to Next Change")]
+    #[strum(serialize = "next_hunk")]
+    NextHunk,
+
+    #[strum(message = "Go to Previous Change")]
+    #[strum(serialize = "previous_hunk")]
+    PreviousHunk,
+
+    #[strum(message = "Go to Next Symbol Occurrence")]
+    #[strum(serialize = "next_symbol_occurrence")]
+    NextSymbolOccurrence,
+
+    #[strum(message = "Go to Previous Symbol Occurrence")]
+    #[strum(serialize = "previous_symbol_occurrence")]
+    PreviousSymbolOccurrence,
+
+    #[strum(message = "Toggle Git Blame")]
+    #[strum(serialize = "toggle_blame")]
+    ToggleBlame,
+
+    #[strum(message = "Toggle Inline Diff")]
+    #[strum(serialize = "toggle_inline_diff")]
+    ToggleInlineDiff,
+
+    #[strum(message = "Stage Change at Cursor")]
+    #[strum(serialize = "stage_hunk")]
+    StageHunk,
+
+    #[strum(message = "Revert Change at Cursor")]
+    #[strum(serialize = "revert_hunk")]
+    RevertHunk,
+
+    #[strum(message = "Merge Conflict: Accept Current Change")]
+    #[strum(serialize = "accept_current_change")]
+    AcceptCurrentChange,
+
+    #[strum(message = "Merge Conflict: Accept Incoming Change")]
+    #[strum(serialize = "accept_incoming_change")]
+    AcceptIncomingChange,
+
+    #[strum(message = "Merge Conflict: Accept Both Changes")]
+    #[strum(serialize = "accept_both_changes")]
+    AcceptBothChanges,
+
+    #[strum(message = "Merge Conflict: Compare Changes")]
+    #[strum(serialize = "compare_conflict")]
+    CompareConflict,
+
+    #[strum(message = "Merge Conflict: Go to Next Conflict")]
+    #[strum(serialize = "next_conflict")]
+    NextConflict,
+
+    #[strum(message = "Merge Conflict: Go to Previous Conflict")]
+    #[strum(serialize = "previous_conflict")]
+    PreviousConflict,
+
     #[strum(message = "Diff Files")]
     #[strum(serialize = "diff_files")]
     DiffFiles,
 
+    #[strum(message = "Compare Active File with Saved")]
+    #[strum(serialize = "compare_with_saved")]
+    CompareWithSaved,
+
+    #[strum(message = "Compare Active File with HEAD")]
+    #[strum(serialize = "compare_with_head")]
+    CompareWithHead,
+
+    #[strum(message = "Compare Active File with Clipboard")]
+    #[strum(serialize = "compare_with_clipboard")]
+    CompareWithClipboard,
+
     #[strum(serialize = "quit")]
     #[strum(message = "Quit Editor")]
     Quit,
@@ -561,6 +936,18 @@ pub enum InternalCommand {
     OpenFileChanges {
         path: PathBuf,
     },
+    /// Open the unsaved changes of `path` against its content on disk
+    CompareWithSaved {
+        path: PathBuf,
+    },
+    OpenFileAtRevision {
+        path: PathBuf,
+        revision: String,
+    },
+    OpenCommitFileChanges {
+        path: PathBuf,
+        id: String,
+    },
     ReloadFileExplorer,
     /// Test whether a file/directory can be created at that path
     TestPathCreation {
@@ -578,6 +965,13 @@ pub enum InternalCommand {
         source: PathBuf,
         path: PathBuf,
     },
+    /// Move or copy a file/directory to `new_path`, e.g. when it is dropped onto a directory
+    /// in the file explorer
+    MovePath {
+        current_path: PathBuf,
+        new_path: PathBuf,
+        copy: bool,
+    },
     GoToLocation {
         location: EditorLocation,
     },
@@ -587,6 +981,9 @@ pub enum InternalCommand {
     PaletteReferences {
         references: Vec<EditorLocation>,
     },
+    PaletteGitCredentials {
+        kind: GitSyncKind,
+    },
     SaveJumpLocation {
         path: PathBuf,
         offset: usize,
@@ -606,9 +1003,25 @@ pub enum InternalCommand {
     NewTerminal {
         profile: Option<TerminalProfile>,
     },
+    /// Open a new terminal tab in the given directory and focus it
+    NewTerminalInDirectory {
+        path: PathBuf,
+    },
     SplitTerminal {
         term_id: TermId,
     },
+    RenameTerminal {
+        term_id: TermId,
+        name: String,
+    },
+    SetWatchExpression {
+        index: Option<usize>,
+        expression: String,
+    },
+    SetBreakpointCondition {
+        target: BreakpointTarget,
+        condition: String,
+    },
     SplitTerminalPrevious {
         term_id: TermId,
     },
@@ -618,6 +1031,20 @@ pub enum InternalCommand {
     SplitTerminalExchange {
         term_id: TermId,
     },
+    /// Restart the plugins that run these language servers
+    RestartLanguageServers {
+        volt_ids: Vec<VoltID>,
+    },
+    /// Stop the plugins that run these language servers for this session
+    StopLanguageServers {
+        volt_ids: Vec<VoltID>,
+    },
+    /// Show the find bar of the terminal panel
+    OpenTerminalFind,
+    /// Select the next match of the terminal find bar's pattern
+    TerminalFindNext {
+        forward: bool,
+    },
     EditorTabClose {
         editor_tab_id: EditorTabId,
     },
@@ -628,7 +1055,7 @@ pub enum InternalCommand {
     ShowCodeActions {
         offset: usize,
         mouse_click: bool,
-        code_actions: Arc<(PluginId, Vec<CodeActionOrCommand>)>,
+        code_actions: Arc<Vec<(PluginId, CodeActionOrCommand)>>,
     },
     RunCodeAction {
         plugin_id: PluginId,
@@ -695,6 +1122,9 @@ pub enum InternalCommand {
         buttons: Vec<AlertButton>,
     },
     HideAlert,
+    SetWorkspaceTrust {
+        trusted: bool,
+    },
     SaveScratchDoc {
         doc: Rc<Doc>,
     },
@@ -116,7 +116,12 @@ pub fn receive(
                 indices: Vec::new(),
             })
             .collect();
-        self.input_items.insert(input.to_string(), items);
+        // Each server responds on its own, their items are merged
+        if let Some(input_items) = self.input_items.get_mut(input) {
+            input_items.append(items);
+        } else {
+            self.input_items.insert(input.to_string(), items);
+        }
         self.filter_items();
     }
 
@@ -7,9 +7,9 @@
 
 use floem::peniko::Color;
 use itertools::Itertools;
-use lapce_core::directory::Directory;
+use lapce_core::{directory::Directory, language::LapceLanguage};
 use lapce_proxy::plugin::wasi::find_all_volts;
-use lapce_rpc::plugin::VoltID;
+use lapce_rpc::plugin::{LanguageServerConfig, VoltID};
 use lsp_types::{CompletionItemKind, SymbolKind};
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
@@ -19,11 +19,16 @@
 
 use self::{
     color::LapceColor,
-    color_theme::{ColorThemeConfig, ThemeColor, ThemeColorPreference},
+    color_theme::{
+        ColorThemeConfig, ThemeColor, ThemeColorPreference, THEME_RECURSION_LIMIT,
+    },
     core::CoreConfig,
-    editor::{EditorConfig, WrapStyle, SCALE_OR_SIZE_LIMIT},
+    editor::{
+        CaretStyle, DefaultLineEnding, EditorConfig, WrapStyle, SCALE_OR_SIZE_LIMIT,
+    },
     icon::LapceIcons,
     icon_theme::IconThemeConfig,
+    language::{language_config_id, LanguageConfig},
     svg::SvgStore,
     terminal::TerminalConfig,
     ui::UIConfig,
@@ -36,9 +41,11 @@
 pub mod editor;
 pub mod icon;
 pub mod icon_theme;
+pub mod language;
 pub mod svg;
 pub mod terminal;
 pub mod ui;
+pub mod vscode;
 pub mod watcher;
 
 pub const LOGO: &str = include_str!("../../extra/images/logo.svg");
@@ -47,6 +54,9 @@
 const DEFAULT_DARK_THEME: &str = include_str!("../../defaults/dark-theme.toml");
 const DEFAULT_ICON_THEME: &str = include_str!("../../defaults/icon-theme.toml");
 
+/// Whether the appearance of the OS is dark, once the window told
+static OS_DARK_MODE: RwLock<Option<bool>> = parking_lot::const_rwlock(None);
+
 static DEFAULT_CONFIG: Lazy<config::Config> = Lazy::new(LapceConfig::default_config);
 static DEFAULT_LAPCE_CONFIG: Lazy<LapceConfig> =
     Lazy::new(LapceConfig::default_lapce_config);
@@ -104,12 +114,31 @@ pub struct LapceConfig {
     pub color_theme: ColorThemeConfig,
     #[serde(default)]
     pub icon_theme: IconThemeConfig,
+    /// The language server configs by language id, see [`Self::load_raw_tables`]
+    #[serde(default)]
+    pub lsp: HashMap<String, LanguageServerConfig>,
+    /// The environment variables set for language servers and terminals, on
+    /// top of the login shell's, see [`Self::load_raw_tables`]
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// The editor settings overridden for the documents of a language, by
+    /// the language's id, see [`Self::language_config`]
+    #[serde(default)]
+    pub language: HashMap<String, LanguageConfig>,
     #[serde(flatten)]
     pub plugins: HashMap<String, HashMap<String, serde_json::Value>>,
+    /// Why the settings, keymaps or theme files couldn't be read, when they
+    /// couldn't, see [`Self::file_errors`]
+    #[serde(skip)]
+    pub errors: Vec<String>,
     #[serde(skip)]
     pub color: ThemeColor,
     #[serde(skip)]
     pub available_color_themes: HashMap<String, (String, config::Config)>,
+    /// Where each of the available color themes comes from, by their lowercase
+    /// names, see [`Self::color_theme_source`]
+    #[serde(skip)]
+    color_theme_sources: HashMap<String, String>,
     #[serde(skip)]
     pub available_icon_themes:
         HashMap<String, (String, config::Config, Option<PathBuf>)>,
@@ -134,17 +163,26 @@ pub fn load(
         disabled_volts: &[VoltID],
         extra_plugin_paths: &[PathBuf],
     ) -> Self {
+        let mut errors = Self::file_errors(workspace);
         let config = Self::merge_config(workspace, None, None);
         let mut lapce_config: LapceConfig = match config.try_deserialize() {
             Ok(config) => config,
             Err(error) => {
                 error!("Failed to deserialize configuration file: {error}");
+                errors.push(format!("Failed to read the settings: {error}"));
                 DEFAULT_LAPCE_CONFIG.clone()
             }
         };
+        lapce_config.errors = errors;
+        if let Some(theme) = lapce_config.os_color_theme() {
+            lapce_config.core.color_theme = theme.to_string();
+        }
 
-        lapce_config.available_color_themes =
-            Self::load_color_themes(disabled_volts, extra_plugin_paths);
+        lapce_config.available_color_themes = Self::load_color_themes(
+            disabled_volts,
+            extra_plugin_paths,
+            &mut lapce_config.color_theme_sources,
+        );
         lapce_config.available_icon_themes =
             Self::load_icon_themes(disabled_volts, extra_plugin_paths);
         lapce_config.resolve_theme(workspace);
@@ -173,10 +211,88 @@ pub fn load(
         ];
 
         lapce_config.terminal.get_indexed_colors();
+        (lapce_config.lsp, lapce_config.env) = Self::load_raw_tables(workspace);
 
         lapce_config
     }
 
+    /// Read the `[lsp]` and `[env]` tables of the settings files on their own,
+    /// since the merged config lowercases the keys of the options passed to the
+    /// servers and the names of the environment variables
+    fn load_raw_tables(
+        workspace: &LapceWorkspace,
+    ) -> (
+        HashMap<String, LanguageServerConfig>,
+        HashMap<String, String>,
+    ) {
+        #[derive(Deserialize)]
+        struct Settings {
+            #[serde(default)]
+            lsp: HashMap<String, LanguageServerConfig>,
+            #[serde(default)]
+            env: HashMap<String, String>,
+        }
+
+        let mut paths = vec![Self::settings_file()];
+        if let (LapceWorkspaceType::Local, Some(path)) =
+            (&workspace.kind, workspace.path.as_ref())
+        {
+            paths.push(Some(path.join("./.lapce/settings.toml")));
+        }
+
+        let mut configs = HashMap::new();
+        let mut env = HashMap::new();
+        for path in paths.into_iter().flatten() {
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            match toml::from_str::<Settings>(&content) {
+                Ok(settings) => {
+                    configs.extend(settings.lsp);
+                    env.extend(settings.env);
+                }
+                Err(error) => {
+                    error!("Failed to read the settings of {path:?}: {error}");
+                }
+            }
+        }
+        (configs, env)
+    }
+
+    /// The errors of the settings, keymaps and theme files that fail to parse,
+    /// which loading the config leaves out rather than failing on
+    fn file_errors(workspace: &LapceWorkspace) -> Vec<String> {
+        let mut paths = vec![Self::settings_file(), Self::keymaps_file()];
+        if let (LapceWorkspaceType::Local, Some(path)) =
+            (&workspace.kind, workspace.path.as_ref())
+        {
+            paths.push(Some(path.join("./.lapce/settings.toml")));
+        }
+        if let Some(entries) = Directory::themes_directory()
+            .and_then(|folder| std::fs::read_dir(folder).ok())
+        {
+            paths.extend(
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| {
+                        path.extension().and_then(|ext| ext.to_str()) == Some("toml")
+                    })
+                    .map(Some),
+            );
+        }
+
+        paths
+            .into_iter()
+            .flatten()
+            .filter_map(|path| {
+                let content = std::fs::read_to_string(&path).ok()?;
+                let error = content.parse::<toml_edit::Document>().err()?;
+                Some(format!("Failed to parse {}: {error}", path.display()))
+            })
+            .collect()
+    }
+
     fn merge_config(
         workspace: &LapceWorkspace,
         color_theme_config: Option<config::Config>,
@@ -227,6 +343,7 @@ fn merge_config(
             LapceWorkspaceType::RemoteSSH(_) => {}
             #[cfg(windows)]
             LapceWorkspaceType::RemoteWSL(_) => {}
+            LapceWorkspaceType::RemoteContainer(_) => {}
         }
 
         config
@@ -262,10 +379,8 @@ fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
         let default_lapce_config = DEFAULT_LAPCE_CONFIG.clone();
 
         let color_theme_config = self
-            .available_color_themes
-            .get(&self.core.color_theme.to_lowercase())
-            .map(|(_, config)| config)
-            .unwrap_or(&DEFAULT_DARK_THEME_CONFIG);
+            .layered_color_theme(&self.core.color_theme, 0)
+            .unwrap_or_else(|| DEFAULT_DARK_THEME_CONFIG.clone());
 
         let icon_theme_config = self
             .available_icon_themes
@@ -280,7 +395,7 @@ fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
 
         if let Ok(new) = Self::merge_config(
             workspace,
-            Some(color_theme_config.clone()),
+            Some(color_theme_config),
             Some(icon_theme_config.clone()),
         )
         .try_deserialize::<LapceConfig>()
@@ -296,6 +411,7 @@ fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
             if let Some(icon_theme_path) = icon_theme_path {
                 self.icon_theme.path = icon_theme_path.clone().unwrap_or_default();
             }
+            self.language = new.language;
             self.plugins = new.plugins;
         }
         self.resolve_colors(Some(&default_lapce_config));
@@ -305,29 +421,97 @@ fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
     fn load_color_themes(
         disabled_volts: &[VoltID],
         extra_plugin_paths: &[PathBuf],
+        sources: &mut HashMap<String, String>,
     ) -> HashMap<String, (String, config::Config)> {
         let mut themes = Self::load_local_themes().unwrap_or_default();
+        for key in themes.keys() {
+            sources.insert(key.clone(), "Themes Folder".to_string());
+        }
 
-        for (key, theme) in
+        for (key, (theme, volt)) in
             Self::load_plugin_color_themes(disabled_volts, extra_plugin_paths)
         {
+            sources.insert(key.clone(), volt);
             themes.insert(key, theme);
         }
 
-        let (name, theme) =
-            Self::load_color_theme_from_str(DEFAULT_LIGHT_THEME).unwrap();
-        themes.insert(name.to_lowercase(), (name, theme));
-        let (name, theme) =
-            Self::load_color_theme_from_str(DEFAULT_DARK_THEME).unwrap();
-        themes.insert(name.to_lowercase(), (name, theme));
+        for default_theme in [DEFAULT_LIGHT_THEME, DEFAULT_DARK_THEME] {
+            let (name, theme) =
+                Self::load_color_theme_from_str(default_theme).unwrap();
+            sources.insert(name.to_lowercase(), "Built-in".to_string());
+            themes.insert(name.to_lowercase(), (name, theme));
+        }
 
         themes
     }
 
+    /// The editor settings the config overrides for the documents of the
+    /// language
+    pub fn language_config(
+        &self,
+        language: LapceLanguage,
+    ) -> Option<&LanguageConfig> {
+        self.language.get(&language_config_id(language))
+    }
+
+    pub fn tab_width(&self, language: LapceLanguage) -> usize {
+        self.language_config(language)
+            .and_then(|config| config.tab_width)
+            .unwrap_or(self.editor.tab_width)
+    }
+
+    pub fn format_on_save(&self, language: LapceLanguage) -> bool {
+        self.language_config(language)
+            .and_then(|config| config.format_on_save)
+            .unwrap_or(self.editor.format_on_save)
+    }
+
+    pub fn wrap_style(&self, language: LapceLanguage) -> WrapStyle {
+        self.language_config(language)
+            .and_then(|config| config.wrap_style)
+            .unwrap_or(self.editor.wrap_style)
+    }
+
+    pub fn rulers(&self, language: LapceLanguage) -> &[usize] {
+        self.language_config(language)
+            .and_then(|config| config.rulers.as_deref())
+            .unwrap_or_default()
+    }
+
     pub fn default_color_theme(&self) -> &ColorThemeConfig {
         &DEFAULT_DARK_THEME_COLOR_CONFIG
     }
 
+    /// Record whether the OS appearance is dark, returns whether that changed
+    pub fn set_os_dark_mode(dark: bool) -> bool {
+        let mut os_dark_mode = OS_DARK_MODE.write();
+        let changed = *os_dark_mode != Some(dark);
+        *os_dark_mode = Some(dark);
+        changed
+    }
+
+    /// The color theme for the appearance of the OS, when following it
+    fn os_color_theme(&self) -> Option<&str> {
+        if !self.core.follow_os_theme {
+            return None;
+        }
+        let dark = (*OS_DARK_MODE.read())?;
+        Some(if dark {
+            &self.core.dark_color_theme
+        } else {
+            &self.core.light_color_theme
+        })
+    }
+
+    /// The `core` setting that picks the color theme in use
+    pub fn color_theme_setting(&self) -> &'static str {
+        match *OS_DARK_MODE.read() {
+            Some(true) if self.core.follow_os_theme => "dark-color-theme",
+            Some(false) if self.core.follow_os_theme => "light-color-theme",
+            _ => "color-theme",
+        }
+    }
+
     /// Set the active color theme.
     /// Note that this does not save the config.
     pub fn set_color_theme(&mut self, workspace: &LapceWorkspace, theme: &str) {
@@ -392,6 +576,40 @@ pub fn completion_color(
         self.style_color(theme_str)
     }
 
+    /// The color theme layered over the theme it extends, so that the keys it
+    /// leaves out come from there rather than from the default dark theme.  
+    /// A theme names the theme it extends with `extends`, and a theme with a
+    /// `color-preference` of `light` extends the default light theme.
+    fn layered_color_theme(
+        &self,
+        name: &str,
+        depth: usize,
+    ) -> Option<config::Config> {
+        let (_, config) = self.available_color_themes.get(&name.to_lowercase())?;
+
+        let base = config.get_string("color-theme.extends").ok().or_else(|| {
+            let preference =
+                config.get_string("color-theme.color-preference").ok()?;
+            preference
+                .eq_ignore_ascii_case("light")
+                .then(|| "Lapce Light".to_string())
+        });
+        let base = base
+            .filter(|base| {
+                !base.eq_ignore_ascii_case(name) && depth < THEME_RECURSION_LIMIT
+            })
+            .and_then(|base| self.layered_color_theme(&base, depth + 1));
+
+        match base {
+            Some(base) => config::Config::builder()
+                .add_source(base)
+                .add_source(config.clone())
+                .build()
+                .ok(),
+            None => Some(config.clone()),
+        }
+    }
+
     fn resolve_colors(&mut self, default_config: Option<&LapceConfig>) {
         self.color.base = self
             .color_theme
@@ -486,11 +704,13 @@ fn load_icon_theme_from_str(s: &str) -> Option<(String, config::Config)> {
         Some((name, config))
     }
 
+    /// The color themes of the enabled volts, with the display name of the
+    /// volt each comes from
     fn load_plugin_color_themes(
         disabled_volts: &[VoltID],
         extra_plugin_paths: &[PathBuf],
-    ) -> HashMap<String, (String, config::Config)> {
-        let mut themes: HashMap<String, (String, config::Config)> = HashMap::new();
+    ) -> HashMap<String, ((String, config::Config), String)> {
+        let mut themes = HashMap::new();
         for meta in find_all_volts(extra_plugin_paths) {
             if disabled_volts.contains(&meta.id()) {
                 continue;
@@ -500,7 +720,7 @@ fn load_plugin_color_themes(
                     if let Some((key, theme)) =
                         Self::load_color_theme(&PathBuf::from(theme_path))
                     {
-                        themes.insert(key, theme);
+                        themes.insert(key, (theme, meta.display_name.clone()));
                     }
                 }
             }
@@ -625,6 +845,33 @@ pub fn file_svg(&self, path: &Path) -> (String, Option<Color>) {
         self.files_svg(slice::from_ref(&path))
     }
 
+    /// The icon of the folder at `path`, falling back to the generic opened/closed directory
+    /// icon if the icon theme has none for its name.
+    pub fn folder_svg(&self, path: &Path, open: bool) -> (String, Option<Color>) {
+        let svg = self
+            .icon_theme
+            .resolve_folder_to_icon(path)
+            .and_then(|p| self.svg_store.write().get_svg_on_disk(&p));
+
+        if let Some(svg) = svg {
+            let color = if self.icon_theme.use_editor_color.unwrap_or(false) {
+                Some(self.color(LapceColor::LAPCE_ICON_ACTIVE))
+            } else {
+                None
+            };
+            (svg, color)
+        } else {
+            let svg_str = match open {
+                true => LapceIcons::DIRECTORY_OPENED,
+                false => LapceIcons::DIRECTORY_CLOSED,
+            };
+            (
+                self.ui_svg(svg_str),
+                Some(self.color(LapceColor::LAPCE_ICON_ACTIVE)),
+            )
+        }
+    }
+
     pub fn symbol_svg(&self, kind: &SymbolKind) -> Option<String> {
         let kind_str = match *kind {
             SymbolKind::ARRAY => LapceIcons::SYMBOL_KIND_ARRAY,
@@ -664,6 +911,14 @@ pub fn color_theme_list(&self) -> im::Vector<String> {
         self.color_theme_list.clone()
     }
 
+    /// Where the color theme comes from: the display name of the volt which
+    /// ships it, the themes folder, or built-in
+    pub fn color_theme_source(&self, name: &str) -> Option<&str> {
+        self.color_theme_sources
+            .get(&name.to_lowercase())
+            .map(String::as_str)
+    }
+
     /// List of the icon themes that are available by their display names.
     pub fn icon_theme_list(&self) -> im::Vector<String> {
         self.icon_theme_list.clone()
@@ -854,6 +1109,22 @@ pub fn get_dropdown_info(&self, kind: &str, key: &str) -> Option<DropdownInfo> {
                     .unwrap_or(0),
                 items: self.color_theme_list.clone(),
             }),
+            ("core", "light-color-theme") => Some(DropdownInfo {
+                active_index: self
+                    .color_theme_list
+                    .iter()
+                    .position(|s| s == &self.core.light_color_theme)
+                    .unwrap_or(0),
+                items: self.color_theme_list.clone(),
+            }),
+            ("core", "dark-color-theme") => Some(DropdownInfo {
+                active_index: self
+                    .color_theme_list
+                    .iter()
+                    .position(|s| s == &self.core.dark_color_theme)
+                    .unwrap_or(0),
+                items: self.color_theme_list.clone(),
+            }),
             ("core", "icon-theme") => Some(DropdownInfo {
                 active_index: self
                     .icon_theme_list
@@ -874,6 +1145,21 @@ pub fn get_dropdown_info(&self, kind: &str, key: &str) -> Option<DropdownInfo> {
                     .unwrap_or(0),
                 items: self.wrap_style_list.clone(),
             }),
+            ("editor", "caret-style-normal") => Some(DropdownInfo {
+                active_index: self.editor.caret_style_normal as usize,
+                items: CaretStyle::VARIANTS.iter().map(|s| s.to_string()).collect(),
+            }),
+            ("editor", "caret-style-insert") => Some(DropdownInfo {
+                active_index: self.editor.caret_style_insert as usize,
+                items: CaretStyle::VARIANTS.iter().map(|s| s.to_string()).collect(),
+            }),
+            ("editor", "default-line-ending") => Some(DropdownInfo {
+                active_index: self.editor.default_line_ending as usize,
+                items: DefaultLineEnding::VARIANTS
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            }),
             ("ui", "tab-close-button") => Some(DropdownInfo {
                 active_index: self.ui.tab_close_button as usize,
                 items: ui::TabCloseButton::VARIANTS
@@ -910,14 +1196,52 @@ pub fn get_dropdown_info(&self, kind: &str, key: &str) -> Option<DropdownInfo> {
         }
     }
 
-    fn get_file_table() -> Option<toml_edit::Document> {
+    pub fn get_file_table() -> Option<toml_edit::Document> {
         let path = Self::settings_file()?;
         let content = std::fs::read_to_string(path).ok()?;
         let document: toml_edit::Document = content.parse().ok()?;
         Some(document)
     }
 
+    /// The values of the config with nothing but the defaults applied
+    pub fn default_values() -> &'static LapceConfig {
+        &DEFAULT_LAPCE_CONFIG
+    }
+
+    /// Where the setting lives in the config file, which is not always where
+    /// it lives in the config
+    fn file_key<'a>(parent: &'a str, key: &'a str) -> (&'a str, &'a str) {
+        // TODO: This is a hack to fix the fact that terminal default profile is saved in a
+        // different manner than other fields. As it is per-operating-system.
+        // Thus we have to instead set the terminal.default-profile.{OS}
+        // It would be better to not need a special hack.
+        if parent == "terminal" && key == "default-profile" {
+            ("terminal.default-profile", std::env::consts::OS)
+        } else {
+            (parent, key)
+        }
+    }
+
+    /// Whether the config file sets the setting, rather than leaving it to the
+    /// default
+    pub fn file_sets(
+        main_table: &toml_edit::Document,
+        parent: &str,
+        key: &str,
+    ) -> bool {
+        let (parent, key) = Self::file_key(parent, key);
+        let mut item = main_table.as_item();
+        for key in parent.split('.') {
+            match item.get(key) {
+                Some(table) => item = table,
+                None => return false,
+            }
+        }
+        item.get(key).is_some()
+    }
+
     pub fn reset_setting(parent: &str, key: &str) -> Option<()> {
+        let (parent, key) = Self::file_key(parent, key);
         let mut main_table = Self::get_file_table().unwrap_or_default();
 
         // Find the container table
@@ -948,15 +1272,7 @@ pub fn update_file(
         key: &str,
         value: toml_edit::Value,
     ) -> Option<()> {
-        // TODO: This is a hack to fix the fact that terminal default profile is saved in a
-        // different manner than other fields. As it is per-operating-system.
-        // Thus we have to instead set the terminal.default-profile.{OS}
-        // It would be better to not need a special hack.
-        let (parent, key) = if parent == "terminal" && key == "default-profile" {
-            ("terminal.default-profile", std::env::consts::OS)
-        } else {
-            (parent, key)
-        };
+        let (parent, key) = Self::file_key(parent, key);
 
         let mut main_table = Self::get_file_table().unwrap_or_default();
 
@@ -130,6 +130,9 @@ pub struct ColorThemeConfig {
     #[serde(skip)]
     pub path: PathBuf,
     pub name: String,
+    /// The name of the theme this one builds on, taking the colors it
+    /// doesn't set from there
+    pub extends: Option<String>,
     pub high_contrast: Option<bool>,
     pub base: ThemeBaseConfig,
     pub syntax: BTreeMap<String, String>,
@@ -259,4 +262,50 @@ fn test_resolve() {
         // don't bother with wrap style list
         // don't bother with terminal colors
     }
+
+    #[test]
+    fn test_extends() {
+        let workspace = LapceWorkspace::default();
+
+        let config = LapceConfig::merge_config(&workspace, None, None);
+        let mut lapce_config: LapceConfig = config.try_deserialize().unwrap();
+
+        let test_theme_str = r##"
+[color-theme]
+name = "test"
+extends = "Lapce Light"
+
+[color-theme.ui]
+"lapce.error" = "#ffffff"
+"##;
+        let test_theme_cfg = Config::builder()
+            .add_source(config::File::from_str(
+                test_theme_str,
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .unwrap();
+        let (light_name, light_cfg) = LapceConfig::load_color_theme_from_str(
+            crate::config::DEFAULT_LIGHT_THEME,
+        )
+        .unwrap();
+
+        lapce_config.available_color_themes = [
+            ("test".to_string(), ("test".to_string(), test_theme_cfg)),
+            (light_name.to_lowercase(), (light_name, light_cfg)),
+        ]
+        .into_iter()
+        .collect();
+        lapce_config.core.color_theme = "test".to_string();
+
+        lapce_config.resolve_theme(&workspace);
+
+        assert_eq!(lapce_config.color("lapce.error"), Color::WHITE);
+        // test that it falls through to the light theme it extends
+        assert_eq!(
+            lapce_config.color("lapce.warn"),
+            Color::rgb8(0xE9, 0xA7, 0x00),
+            "Failed to get from the extended light theme"
+        );
+    }
 }
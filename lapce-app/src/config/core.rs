@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -8,10 +10,65 @@ pub struct CoreConfig {
     pub modal: bool,
     #[field_names(desc = "Set the color theme of Lapce")]
     pub color_theme: String,
+    #[field_names(
+        desc = "Switch between the light and the dark color theme with the appearance of the OS, rather than using the color theme"
+    )]
+    pub follow_os_theme: bool,
+    #[field_names(
+        desc = "Set the color theme used when the OS appearance is light, if following it"
+    )]
+    pub light_color_theme: String,
+    #[field_names(
+        desc = "Set the color theme used when the OS appearance is dark, if following it"
+    )]
+    pub dark_color_theme: String,
     #[field_names(desc = "Set the icon theme of Lapce")]
     pub icon_theme: String,
     #[field_names(
         desc = "Enable customised titlebar and disable OS native one (Linux, BSD, Windows)"
     )]
     pub custom_titlebar: bool,
+
+    #[serde(default)]
+    #[field_names(skip)]
+    pub palette_modes: HashMap<String, PaletteModeConfig>,
+}
+
+/// A user defined palette mode, entered by typing its prefix into the palette.
+#[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct PaletteModeConfig {
+    #[field_names(desc = "Prefix typed into the palette to enter this mode")]
+    pub prefix: String,
+    #[field_names(desc = "Description shown in the palette help")]
+    pub description: Option<String>,
+    #[field_names(desc = "Static list of items to show")]
+    pub items: Option<Vec<String>>,
+    #[field_names(
+        desc = "Command to execute, each line of its output is shown as an item"
+    )]
+    pub command: Option<String>,
+    #[field_names(desc = "Arguments passed to command")]
+    pub arguments: Option<Vec<String>>,
+    #[serde(default)]
+    #[field_names(desc = "What to do with the selected item")]
+    pub on_select: PaletteModeAction,
+    #[field_names(
+        desc = "Lapce command to run with the selected item as its argument, when on-select is \"command\""
+    )]
+    pub select_command: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PaletteModeAction {
+    /// Insert the item into the active editor
+    #[default]
+    Insert,
+    /// Open the item as a file path, relative to the workspace
+    Open,
+    /// Copy the item to the clipboard
+    Copy,
+    /// Run `select-command` with the item as its argument
+    Command,
 }
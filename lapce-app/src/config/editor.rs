@@ -1,4 +1,8 @@
-use floem::views::editor::text::RenderWhitespace;
+use std::path::Path;
+
+use floem::{cosmic_text::Weight, views::editor::text::RenderWhitespace};
+use globset::Glob;
+use lapce_core::line_ending::LineEnding;
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -49,6 +53,61 @@ pub fn try_from_str(s: &str) -> Option<Self> {
     }
 }
 
+/// The shape the caret is drawn with
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    Default,
+    PartialEq,
+    strum_macros::VariantNames,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum CaretStyle {
+    /// A box covering the character at the cursor
+    #[default]
+    Block,
+    /// A thin line before the character at the cursor
+    Bar,
+    /// A line under the character at the cursor
+    Underline,
+}
+
+/// The line ending of new files, and of files without a line break to detect it from
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    Default,
+    PartialEq,
+    strum_macros::VariantNames,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum DefaultLineEnding {
+    /// The line ending of the platform
+    #[default]
+    Auto,
+    Lf,
+    Crlf,
+}
+
+impl DefaultLineEnding {
+    pub fn line_ending(self) -> LineEnding {
+        match self {
+            DefaultLineEnding::Auto if cfg!(windows) => LineEnding::CrLf,
+            DefaultLineEnding::Auto => LineEnding::Lf,
+            DefaultLineEnding::Lf => LineEnding::Lf,
+            DefaultLineEnding::Crlf => LineEnding::CrLf,
+        }
+    }
+}
+
 impl std::fmt::Display for WrapStyle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_str())?;
@@ -64,6 +123,10 @@ pub struct EditorConfig {
     pub font_family: String,
     #[field_names(desc = "Set the editor font size")]
     font_size: usize,
+    #[field_names(
+        desc = "Set the editor font weight, from 100 (thin) to 900 (black), 400 being normal"
+    )]
+    font_weight: u16,
     #[field_names(desc = "Set the font size in the code lens")]
     pub code_lens_font_size: usize,
     #[field_names(
@@ -129,10 +192,19 @@ pub struct EditorConfig {
     )]
     pub format_on_save: bool,
 
+    #[field_names(
+        desc = "If the language servers format the code after the characters they trigger on are typed, such as reindenting a line on `}`"
+    )]
+    pub format_on_type: bool,
+
     #[field_names(
         desc = "Whether newlines should be automatically converted to the current line ending"
     )]
     pub normalize_line_endings: bool,
+    #[field_names(
+        desc = "The line ending of new files, and of files without a line break to detect it from.\nOptions: auto, lf, crlf."
+    )]
+    pub default_line_ending: DefaultLineEnding,
 
     #[field_names(desc = "If matching brackets are highlighted")]
     pub highlight_matching_brackets: bool,
@@ -140,6 +212,11 @@ pub struct EditorConfig {
     #[field_names(desc = "If scope lines are highlighted")]
     pub highlight_scope_lines: bool,
 
+    #[field_names(
+        desc = "If the occurrences of the symbol at the cursor are highlighted"
+    )]
+    pub highlight_symbol_occurrences: bool,
+
     #[field_names(desc = "If inlay hints should be displayed")]
     pub enable_inlay_hints: bool,
 
@@ -171,6 +248,14 @@ pub struct EditorConfig {
         desc = "Set the error lens font size. If 0 it uses the inlay hint font size."
     )]
     pub error_lens_font_size: usize,
+    #[field_names(
+        desc = "If the author and date of the commit that last changed the cursor's line should be displayed at its end"
+    )]
+    pub inline_blame: bool,
+    #[field_names(
+        desc = "If the values of the variables a line uses should be displayed at its end while debugging is paused"
+    )]
+    pub inline_debug_values: bool,
     #[field_names(
         desc = "If the editor should display the completion item as phantom text"
     )]
@@ -189,6 +274,22 @@ pub struct EditorConfig {
         desc = "Set the cursor blink interval (in milliseconds). Set to 0 to completely disable."
     )]
     blink_interval: u64,
+    #[field_names(
+        desc = "Set the shape of the caret in normal and visual mode.\nOptions: block, bar, underline."
+    )]
+    pub caret_style_normal: CaretStyle,
+    #[field_names(
+        desc = "Set the shape of the caret in insert mode, which is the only mode when modal editing is off.\nOptions: block, bar, underline."
+    )]
+    pub caret_style_insert: CaretStyle,
+    #[field_names(desc = "If the line the cursor is on is highlighted")]
+    pub highlight_current_line: bool,
+    #[field_names(desc = "If the editor shows its scroll bar")]
+    pub show_scroll_bar: bool,
+    #[field_names(
+        desc = "If the changes to the file are marked next to the scroll bar"
+    )]
+    pub scroll_bar_markers: bool,
     #[field_names(
         desc = "Whether the multiple cursor selection is case sensitive."
     )]
@@ -211,6 +312,14 @@ pub struct EditorConfig {
         desc = "Whether the document should be formatted when an autosave is triggered (required Format on Save)"
     )]
     pub format_on_autosave: bool,
+    #[field_names(
+        desc = "Whether a document without unsaved changes is reloaded when its file changes on disk, rather than asking first"
+    )]
+    pub auto_reload_clean_files: bool,
+    #[field_names(
+        desc = "Whether unsaved changes are kept when Lapce is closed or crashes, and restored when the workspace is opened again"
+    )]
+    pub hot_exit: bool,
     #[field_names(
         desc = "If enabled the cursor treats leading soft tabs as if they are hard tabs."
     )]
@@ -233,6 +342,10 @@ pub struct EditorConfig {
         desc = "Glob patterns for excluding files and folders (in file explorer)"
     )]
     pub files_exclude: String,
+    #[field_names(
+        desc = "Glob patterns for files that are opened read-only, like generated or vendored files"
+    )]
+    pub read_only_files: String,
 }
 
 impl EditorConfig {
@@ -240,6 +353,10 @@ pub fn font_size(&self) -> usize {
         self.font_size.clamp(6, 32)
     }
 
+    pub fn font_weight(&self) -> Weight {
+        Weight(self.font_weight.clamp(100, 900))
+    }
+
     pub fn line_height(&self) -> usize {
         let line_height = if self.line_height < SCALE_OR_SIZE_LIMIT {
             self.line_height * self.font_size as f64
@@ -292,4 +409,22 @@ pub fn blink_interval(&self) -> u64 {
         }
         self.blink_interval.max(200)
     }
+
+    /// Whether `path` is one of the `read-only-files`
+    pub fn is_read_only_file(&self, path: &Path) -> bool {
+        if self.read_only_files.is_empty() {
+            return false;
+        }
+        match Glob::new(&self.read_only_files) {
+            Ok(glob) => glob.compile_matcher().is_match(path),
+            Err(e) => {
+                tracing::error!(
+                    target:"read_only_files",
+                    "Failed to compile glob: {}",
+                    e
+                );
+                false
+            }
+        }
+    }
 }
@@ -31,11 +31,16 @@ impl LapceIcons {
     pub const RUN_ERRORS: &'static str = "run_errors";
     pub const UNSAVED: &'static str = "unsaved";
     pub const WARNING: &'static str = "warning";
+    pub const NOTIFICATION: &'static str = "notification";
     pub const TERMINAL: &'static str = "terminal";
     pub const SETTINGS: &'static str = "settings";
     pub const LIGHTBULB: &'static str = "lightbulb";
     pub const EXTENSIONS: &'static str = "extensions";
     pub const KEYBOARD: &'static str = "keyboard";
+    pub const LOCK: &'static str = "lock";
+    pub const PIN: &'static str = "pin";
+    pub const PINNED: &'static str = "pinned";
+    pub const EDIT: &'static str = "edit";
     pub const BREADCRUMB_SEPARATOR: &'static str = "breadcrumb_separator";
     pub const SYMBOL_COLOR: &'static str = "symbol_color";
 
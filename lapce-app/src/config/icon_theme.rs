@@ -54,6 +54,15 @@ pub fn resolve_path_to_icon(&self, paths: &[&Path]) -> Option<PathBuf> {
             })
             .map(|icon| self.path.join(icon))
     }
+
+    /// Returns the path of the icon associated with the name of the folder at `path`, if there
+    /// is one.
+    pub fn resolve_folder_to_icon(&self, path: &Path) -> Option<PathBuf> {
+        path.file_name()
+            .and_then(OsStr::to_str)
+            .and_then(|folder_name| self.foldername.get(folder_name))
+            .map(|icon| self.path.join(icon))
+    }
 }
 
 #[cfg(test)]
@@ -119,6 +128,9 @@ fn get_icon_theme_config() -> IconThemeConfig {
             extension: [("rs", "rust.svg"), ("c", "c.svg"), ("py", "python.svg")]
                 .map(|(k, v)| (k.to_owned(), v.to_owned()))
                 .into(),
+            foldername: [("src", "folder-src.svg"), (".git", "folder-git.svg")]
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .into(),
             ..Default::default()
         }
     }
@@ -241,4 +253,37 @@ fn resolve_path_to_icon_extension_match_some() {
             )
         );
     }
+
+    #[test]
+    fn resolve_folder_to_icon_no_match_none() {
+        let icon_theme_config = get_icon_theme_config();
+
+        assert_eq!(
+            None,
+            icon_theme_config.resolve_folder_to_icon("lib".as_ref())
+        );
+        assert_eq!(
+            None,
+            icon_theme_config.resolve_folder_to_icon("/src/dir".as_ref())
+        );
+        assert_eq!(
+            None,
+            icon_theme_config.resolve_folder_to_icon("..".as_ref())
+        );
+    }
+
+    #[test]
+    fn resolve_folder_to_icon_match_some() {
+        let icon_theme_config = get_icon_theme_config();
+
+        assert_eq!(
+            Some("icons/folder-src.svg".to_owned().into()),
+            icon_theme_config.resolve_folder_to_icon("src".as_ref())
+        );
+        assert_eq!(
+            Some("icons/folder-git.svg".to_owned().into()),
+            icon_theme_config
+                .resolve_folder_to_icon("/home/user/project/.git".as_ref())
+        );
+    }
 }
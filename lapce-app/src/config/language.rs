@@ -0,0 +1,57 @@
+use lapce_core::language::LapceLanguage;
+use serde::{Deserialize, Serialize};
+
+use super::editor::WrapStyle;
+
+/// The editor settings a `[language.<id>]` table overrides for the documents
+/// of that language, where the id is the lowercase name of the language,
+/// `[language.rust]` or `[language.python]`
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct LanguageConfig {
+    pub tab_width: Option<usize>,
+    /// Whether to indent with spaces rather than tabs when the document has
+    /// no indentation of its own to follow
+    pub insert_spaces: Option<bool>,
+    /// The columns to draw vertical rulers at
+    pub rulers: Option<Vec<usize>>,
+    pub format_on_save: Option<bool>,
+    pub wrap_style: Option<WrapStyle>,
+}
+
+/// The id the `[language]` tables use for the language
+pub fn language_config_id(language: LapceLanguage) -> String {
+    match language.runtime_id() {
+        Some(id) => id.to_lowercase(),
+        None => language.as_ref().to_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_core::language::LapceLanguage;
+
+    use super::{language_config_id, LanguageConfig};
+    use crate::config::editor::WrapStyle;
+
+    #[test]
+    fn test_language_config() {
+        let config: LanguageConfig = toml::from_str(
+            r#"
+            tab-width = 2
+            insert-spaces = false
+            rulers = [80, 100]
+            wrap-style = "none"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.tab_width, Some(2));
+        assert_eq!(config.insert_spaces, Some(false));
+        assert_eq!(config.rulers, Some(vec![80, 100]));
+        assert_eq!(config.format_on_save, None);
+        assert_eq!(config.wrap_style, Some(WrapStyle::None));
+
+        assert_eq!(language_config_id(LapceLanguage::Rust), "rust");
+        assert_eq!(language_config_id(LapceLanguage::Python), "python");
+    }
+}
@@ -1,6 +1,8 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, path::Path, sync::Arc};
 
 use floem::peniko::Color;
+use lapce_core::language::LapceLanguage;
+use lapce_rpc::dap_types::RunDebugConfig;
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -19,11 +21,19 @@ pub struct TerminalConfig {
         desc = "Set the terminal line height, If 0, it uses editor line height"
     )]
     pub line_height: f64,
+    #[field_names(
+        desc = "Set the number of lines kept in the terminal scrollback history"
+    )]
+    pub scrollback: usize,
 
     #[field_names(skip)]
     pub profiles: HashMap<String, TerminalProfile>,
     #[field_names(skip)]
     pub default_profile: HashMap<String, String>,
+    /// The command running a file of a language, where `{file}` is replaced with
+    /// the path of the file
+    #[field_names(skip)]
+    pub run_templates: HashMap<String, String>,
 
     #[serde(skip)]
     #[field_names(skip)]
@@ -37,9 +47,9 @@ pub struct TerminalProfile {
     pub command: Option<String>,
     #[field_names(desc = "Arguments passed to command")]
     pub arguments: Option<Vec<String>>,
-    #[field_names(desc = "Command to execute when launching terminal")]
+    #[field_names(desc = "Directory the terminal starts in")]
     pub workdir: Option<std::path::PathBuf>,
-    #[field_names(desc = "Arguments passed to command")]
+    #[field_names(desc = "Environment variables set for the command")]
     pub environment: Option<HashMap<String, String>>,
 }
 
@@ -74,28 +84,67 @@ pub fn get_indexed_colors(&mut self) {
         self.indexed_colors = Arc::new(indexed_colors);
     }
 
+    /// The default profile of the current platform
     pub fn get_default_profile(
         &self,
     ) -> Option<lapce_rpc::terminal::TerminalProfile> {
-        let profile = self.profiles.get(
+        self.get_profile(
             self.default_profile
                 .get(std::env::consts::OS)
-                .unwrap_or(&String::from("default")),
-        )?;
-        let workdir = if let Some(workdir) = &profile.workdir {
-            url::Url::parse(&workdir.display().to_string()).ok()
-        } else {
-            None
-        };
+                .map(String::as_str)
+                .unwrap_or("default"),
+        )
+    }
 
-        let profile = profile.clone();
+    /// The profile called `name`. A `workdir` that isn't absolute is ignored,
+    /// so that the terminal starts in the workspace.
+    pub fn get_profile(
+        &self,
+        name: &str,
+    ) -> Option<lapce_rpc::terminal::TerminalProfile> {
+        let profile = self.profiles.get(name)?.clone();
+        let workdir = profile
+            .workdir
+            .and_then(|workdir| url::Url::from_file_path(workdir).ok());
 
         Some(lapce_rpc::terminal::TerminalProfile {
-            name: std::env::consts::OS.to_string(),
+            name: name.to_string(),
             command: profile.command,
             arguments: profile.arguments,
             workdir,
             environment: profile.environment,
         })
     }
+
+    /// The run template of the language of `path`
+    pub fn get_run_template(&self, path: &Path) -> Option<&str> {
+        let language = LapceLanguage::from_path(path);
+        self.run_templates
+            .get(&language.as_ref().to_lowercase())
+            .map(String::as_str)
+    }
+
+    /// The configuration running `path` with the run template of its language
+    pub fn get_run_config(&self, path: &Path) -> Option<RunDebugConfig> {
+        let template = self.get_run_template(path)?;
+        let file = path.to_string_lossy();
+        let mut args = template
+            .split_whitespace()
+            .map(|arg| arg.replace("{file}", &file));
+        let program = args.next()?;
+        let args: Vec<String> = args.collect();
+
+        let file_name = path.file_name()?.to_string_lossy();
+        Some(RunDebugConfig {
+            ty: None,
+            name: format!("Run {file_name}"),
+            program,
+            args: (!args.is_empty()).then_some(args),
+            cwd: None,
+            env: None,
+            prelaunch: None,
+            debug_command: None,
+            dap_id: Default::default(),
+        })
+    }
 }
@@ -1,4 +1,4 @@
-use floem::cosmic_text::FamilyOwned;
+use floem::cosmic_text::{FamilyOwned, Weight};
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -16,6 +16,11 @@ pub struct UIConfig {
     #[field_names(desc = "Set the UI base font size")]
     font_size: usize,
 
+    #[field_names(
+        desc = "Set the UI font weight, from 100 (thin) to 900 (black), 400 being normal"
+    )]
+    font_weight: u16,
+
     #[field_names(desc = "Set the icon size in the UI")]
     icon_size: usize,
 
@@ -62,6 +67,58 @@ pub struct UIConfig {
 
     #[field_names(desc = "Display the Open Editors section in the explorer")]
     pub open_editors_visible: bool,
+
+    #[field_names(desc = "Decorate files in the explorer with their git status")]
+    pub file_explorer_git_status: bool,
+
+    #[field_names(
+        desc = "Reveal the active file in the explorer whenever it changes"
+    )]
+    pub file_explorer_follow_active_file: bool,
+
+    #[field_names(desc = "Hide files ignored by git in the explorer")]
+    pub file_explorer_hide_gitignored: bool,
+
+    #[field_names(
+        desc = "Show the activity bar, with the icons of the main panels, at the left of the window"
+    )]
+    pub activity_bar: bool,
+
+    #[field_names(desc = "Show the mode of modal editing in the status bar")]
+    pub status_bar_mode: bool,
+
+    #[field_names(desc = "Show the git branch in the status bar")]
+    pub status_bar_branch: bool,
+
+    #[field_names(
+        desc = "Show the counts of errors and warnings in the status bar"
+    )]
+    pub status_bar_diagnostics: bool,
+
+    #[field_names(desc = "Show the progress of language servers in the status bar")]
+    pub status_bar_progress: bool,
+
+    #[field_names(
+        desc = "Show the cursor position and selection in the status bar"
+    )]
+    pub status_bar_cursor: bool,
+
+    #[field_names(desc = "Show the indentation of the file in the status bar")]
+    pub status_bar_indentation: bool,
+
+    #[field_names(desc = "Show the line ending of the file in the status bar")]
+    pub status_bar_line_ending: bool,
+
+    #[field_names(desc = "Show the encoding of the file in the status bar")]
+    pub status_bar_encoding: bool,
+
+    #[field_names(desc = "Show the language of the file in the status bar")]
+    pub status_bar_language: bool,
+
+    #[field_names(
+        desc = "Show the bell with the count of unread notifications in the status bar"
+    )]
+    pub status_bar_notifications: bool,
 }
 
 #[derive(
@@ -112,6 +169,10 @@ pub fn font_size(&self) -> usize {
         self.font_size.clamp(6, 32)
     }
 
+    pub fn font_weight(&self) -> Weight {
+        Weight(self.font_weight.clamp(100, 900))
+    }
+
     pub fn font_family(&self) -> Vec<FamilyOwned> {
         FamilyOwned::parse_list(&self.font_family).collect()
     }
@@ -134,6 +195,10 @@ pub fn status_height(&self) -> usize {
         self.status_height.max(font_size)
     }
 
+    pub fn scroll_width(&self) -> usize {
+        self.scroll_width.clamp(2, 32)
+    }
+
     pub fn palette_width(&self) -> usize {
         if self.palette_width == 0 {
             500
@@ -0,0 +1,815 @@
+use std::{path::Path, str::FromStr};
+
+use floem::keyboard::{Key, Modifiers};
+use itertools::Itertools;
+use lapce_core::mode::Modes;
+use serde_json::Value;
+
+use super::LapceConfig;
+use crate::keypress::{
+    keymap::{KeyMap, KeyMapKey, KeyMapPress},
+    KeyPressData,
+};
+
+type Translate = fn(&Value) -> Option<toml_edit::Value>;
+
+/// The VS Code settings Lapce has an equivalent of, with the table and the
+/// key they go to
+const SETTINGS: &[(&str, &str, &str, Translate)] = &[
+    ("editor.fontFamily", "editor", "font-family", string),
+    ("editor.fontSize", "editor", "font-size", integer),
+    ("editor.fontWeight", "editor", "font-weight", font_weight),
+    ("editor.lineHeight", "editor", "line-height", line_height),
+    ("editor.tabSize", "editor", "tab-width", integer),
+    ("editor.wordWrap", "editor", "wrap-style", wrap_style),
+    ("editor.formatOnSave", "editor", "format-on-save", boolean),
+    ("editor.formatOnType", "editor", "format-on-type", boolean),
+    (
+        "editor.cursorStyle",
+        "editor",
+        "caret-style-insert",
+        caret_style,
+    ),
+    ("editor.cursorBlinking", "editor", "blink-interval", |v| {
+        let interval: i64 = if v.as_str()? == "solid" { 0 } else { 500 };
+        Some(interval.into())
+    }),
+    (
+        "editor.cursorSurroundingLines",
+        "editor",
+        "cursor-surrounding-lines",
+        integer,
+    ),
+    (
+        "editor.renderWhitespace",
+        "editor",
+        "render-whitespace",
+        |v| {
+            let value = v.as_str()?;
+            ["none", "all", "boundary", "trailing"]
+                .contains(&value)
+                .then(|| value.into())
+        },
+    ),
+    (
+        "editor.renderLineHighlight",
+        "editor",
+        "highlight-current-line",
+        |v| Some((v.as_str()? != "none").into()),
+    ),
+    (
+        "editor.scrollbar.vertical",
+        "editor",
+        "show-scroll-bar",
+        |v| Some((v.as_str()? != "hidden").into()),
+    ),
+    (
+        "editor.scrollBeyondLastLine",
+        "editor",
+        "scroll-beyond-last-line",
+        boolean,
+    ),
+    (
+        "editor.stickyScroll.enabled",
+        "editor",
+        "sticky-header",
+        boolean,
+    ),
+    (
+        "editor.guides.indentation",
+        "editor",
+        "show-indent-guide",
+        boolean,
+    ),
+    (
+        "editor.bracketPairColorization.enabled",
+        "editor",
+        "bracket-pair-colorization",
+        boolean,
+    ),
+    (
+        "editor.inlayHints.enabled",
+        "editor",
+        "enable-inlay-hints",
+        |v| Some(v.as_str()?.starts_with("on").into()),
+    ),
+    (
+        "editor.inlineSuggest.enabled",
+        "editor",
+        "enable-inline-completion",
+        boolean,
+    ),
+    (
+        "editor.autoClosingBrackets",
+        "editor",
+        "auto-closing-matching-pairs",
+        |v| Some((v.as_str()? != "never").into()),
+    ),
+    ("editor.autoSurround", "editor", "auto-surround", |v| {
+        Some((v.as_str()? != "never").into())
+    }),
+    ("editor.hover.delay", "editor", "hover-delay", integer),
+    (
+        "editor.parameterHints.enabled",
+        "editor",
+        "show-signature",
+        boolean,
+    ),
+    (
+        "git.blame.editorDecoration.enabled",
+        "editor",
+        "inline-blame",
+        boolean,
+    ),
+    (
+        "explorer.excludeGitIgnore",
+        "ui",
+        "file-explorer-hide-gitignored",
+        boolean,
+    ),
+    (
+        "terminal.integrated.fontFamily",
+        "terminal",
+        "font-family",
+        string,
+    ),
+    (
+        "terminal.integrated.fontSize",
+        "terminal",
+        "font-size",
+        integer,
+    ),
+];
+
+/// The VS Code settings a `[<language>]` object can hold that Lapce has a
+/// `[language.<id>]` equivalent of
+const LANGUAGE_SETTINGS: &[(&str, &str, Translate)] = &[
+    ("editor.tabSize", "tab-width", integer),
+    ("editor.insertSpaces", "insert-spaces", boolean),
+    ("editor.rulers", "rulers", rulers),
+    ("editor.formatOnSave", "format-on-save", boolean),
+    ("editor.wordWrap", "wrap-style", wrap_style),
+];
+
+/// The VS Code commands Lapce has an equivalent of
+const COMMANDS: &[(&str, &str)] = &[
+    ("workbench.action.showCommands", "palette.command"),
+    ("workbench.action.quickOpen", "palette"),
+    ("workbench.action.gotoLine", "palette.line"),
+    ("workbench.action.gotoSymbol", "palette.symbol"),
+    (
+        "workbench.action.showAllSymbols",
+        "palette.workspace_symbol",
+    ),
+    ("workbench.action.files.save", "save"),
+    ("workbench.action.files.saveAll", "save_all"),
+    ("workbench.action.files.newUntitledFile", "new_file"),
+    ("workbench.action.files.openFile", "open_file"),
+    ("workbench.action.files.openFolder", "open_folder"),
+    ("workbench.action.closeActiveEditor", "split_close"),
+    ("workbench.action.closeFolder", "close_folder"),
+    ("workbench.action.newWindow", "new_window"),
+    ("workbench.action.closeWindow", "close_window"),
+    ("workbench.action.reloadWindow", "reload_window"),
+    ("workbench.action.quit", "quit"),
+    ("workbench.action.openSettings", "open_settings"),
+    ("workbench.action.openSettingsJson", "open_settings_file"),
+    (
+        "workbench.action.openGlobalKeybindings",
+        "open_keyboard_shortcuts",
+    ),
+    (
+        "workbench.action.openGlobalKeybindingsFile",
+        "open_keyboard_shortcuts_file",
+    ),
+    ("workbench.action.selectTheme", "change_color_theme"),
+    ("workbench.action.selectIconTheme", "change_icon_theme"),
+    ("workbench.action.zoomIn", "zoom_in"),
+    ("workbench.action.zoomOut", "zoom_out"),
+    ("workbench.action.zoomReset", "zoom_reset"),
+    ("workbench.action.nextEditor", "next_editor_tab"),
+    ("workbench.action.previousEditor", "previous_editor_tab"),
+    ("workbench.action.splitEditor", "split_vertical"),
+    ("workbench.action.splitEditorDown", "split_horizontal"),
+    ("workbench.action.focusLeftGroup", "split_left"),
+    ("workbench.action.focusRightGroup", "split_right"),
+    ("workbench.action.focusAboveGroup", "split_up"),
+    ("workbench.action.focusBelowGroup", "split_down"),
+    ("workbench.action.togglePanel", "toggle_panel_bottom_visual"),
+    (
+        "workbench.action.toggleSidebarVisibility",
+        "toggle_panel_left_visual",
+    ),
+    (
+        "workbench.action.toggleMaximizedPanel",
+        "toggle_maximized_panel",
+    ),
+    (
+        "workbench.action.terminal.toggleTerminal",
+        "toggle_terminal_focus",
+    ),
+    ("workbench.action.terminal.new", "new_terminal_tab"),
+    ("workbench.action.terminal.kill", "kill_terminal"),
+    ("workbench.action.terminal.split", "split_terminal"),
+    (
+        "workbench.action.terminal.runSelectedText",
+        "run_selection_in_terminal",
+    ),
+    ("workbench.view.explorer", "toggle_file_explorer_focus"),
+    ("workbench.view.search", "toggle_search_focus"),
+    ("workbench.action.findInFiles", "toggle_search_focus"),
+    ("workbench.view.scm", "toggle_source_control_focus"),
+    ("workbench.view.extensions", "toggle_plugin_focus"),
+    ("workbench.actions.view.problems", "toggle_problem_focus"),
+    ("workbench.action.navigateBack", "jump_location_backward"),
+    ("workbench.action.navigateForward", "jump_location_forward"),
+    ("workbench.action.debug.run", "run_file"),
+    ("revealFileInOS", "file_explorer_reveal_in_file_manager"),
+    (
+        "workbench.files.action.showActiveFileInExplorer",
+        "reveal_active_file_in_file_explorer",
+    ),
+    ("git.commit", "source_control_commit"),
+    ("editor.action.commentLine", "toggle_line_comment"),
+    (
+        "editor.action.addSelectionToNextFindMatch",
+        "select_next_current",
+    ),
+    (
+        "editor.action.moveSelectionToNextFindMatch",
+        "select_skip_current",
+    ),
+    ("editor.action.selectHighlights", "select_all_current"),
+    ("editor.action.insertCursorAbove", "insert_cursor_above"),
+    ("editor.action.insertCursorBelow", "insert_cursor_below"),
+    (
+        "editor.action.insertCursorAtEndOfEachLineSelected",
+        "insert_cursor_end_of_line",
+    ),
+    ("editor.action.moveLinesUpAction", "move_line_up"),
+    ("editor.action.moveLinesDownAction", "move_line_down"),
+    ("editor.action.copyLinesUpAction", "duplicate_line_up"),
+    ("editor.action.copyLinesDownAction", "duplicate_line_down"),
+    ("editor.action.deleteLines", "delete_line"),
+    ("editor.action.joinLines", "join_lines"),
+    ("editor.action.indentLines", "indent_line"),
+    ("editor.action.outdentLines", "outdent_line"),
+    ("editor.action.insertLineAfter", "new_line_below"),
+    ("editor.action.insertLineBefore", "new_line_above"),
+    ("editor.action.jumpToBracket", "match_pairs"),
+    ("editor.action.revealDefinition", "goto_definition"),
+    ("editor.action.goToReferences", "get_references"),
+    ("editor.action.referenceSearch.trigger", "get_references"),
+    ("editor.action.rename", "rename_symbol"),
+    ("editor.action.quickFix", "show_code_actions"),
+    ("editor.action.showHover", "show_hover"),
+    ("editor.action.triggerSuggest", "get_completion"),
+    ("editor.action.triggerParameterHints", "get_signature"),
+    ("editor.action.formatDocument", "format_document"),
+    ("editor.action.formatSelection", "format_selection"),
+    ("editor.action.marker.next", "next_error"),
+    ("editor.action.marker.nextInFiles", "next_error"),
+    ("editor.action.marker.prev", "previous_error"),
+    ("editor.action.marker.prevInFiles", "previous_error"),
+    ("editor.action.dirtydiff.next", "next_hunk"),
+    ("editor.action.dirtydiff.previous", "previous_hunk"),
+    (
+        "editor.action.inlineSuggest.trigger",
+        "inline_completion.invoke",
+    ),
+    (
+        "editor.action.inlineSuggest.showNext",
+        "inline_completion.next",
+    ),
+    (
+        "editor.action.inlineSuggest.showPrevious",
+        "inline_completion.previous",
+    ),
+    (
+        "editor.action.inlineSuggest.commit",
+        "inline_completion.select",
+    ),
+    (
+        "editor.action.inlineSuggest.hide",
+        "inline_completion.cancel",
+    ),
+    ("actions.find", "search"),
+    ("editor.action.nextMatchFindAction", "search_forward"),
+    ("editor.action.previousMatchFindAction", "search_backward"),
+    ("editor.action.clipboardCopyAction", "clipboard_copy"),
+    ("editor.action.clipboardCutAction", "clipboard_cut"),
+    ("editor.action.clipboardPasteAction", "clipboard_paste"),
+    ("editor.action.selectAll", "select_all"),
+    ("undo", "undo"),
+    ("redo", "redo"),
+    ("cursorUndo", "select_undo"),
+    ("expandLineSelection", "select_current_line"),
+    ("cursorUp", "up"),
+    ("cursorDown", "down"),
+    ("cursorLeft", "left"),
+    ("cursorRight", "right"),
+    ("cursorWordLeft", "word_backward"),
+    ("cursorWordRight", "word_forward"),
+    ("cursorHome", "line_start_non_blank"),
+    ("cursorEnd", "line_end"),
+    ("cursorTop", "document_start"),
+    ("cursorBottom", "document_end"),
+    ("cursorPageUp", "page_up"),
+    ("cursorPageDown", "page_down"),
+    ("scrollLineUp", "scroll_up"),
+    ("scrollLineDown", "scroll_down"),
+    ("deleteLeft", "delete_backward"),
+    ("deleteRight", "delete_forward"),
+    ("deleteWordLeft", "delete_word_backward"),
+    ("deleteWordRight", "delete_word_forward"),
+];
+
+/// The VS Code `when` clause contexts Lapce has a condition for
+const CONDITIONS: &[(&str, &str)] = &[
+    ("editorFocus", "editor_focus"),
+    ("editorTextFocus", "editor_focus"),
+    ("textInputFocus", "editor_focus"),
+    ("terminalFocus", "terminal_focus"),
+    ("listFocus", "list_focus"),
+    ("inQuickOpen", "palette_focus"),
+    ("suggestWidgetVisible", "completion_focus"),
+    ("inlineSuggestionVisible", "inline_completion_visible"),
+    ("inSnippetMode", "in_snippet"),
+    ("filesExplorerFocus", "file_explorer_focus"),
+    ("explorerViewletFocus", "file_explorer_focus"),
+    ("panelFocus", "panel_focus"),
+    ("renameInputVisible", "rename_focus"),
+    ("findWidgetVisible", "search_active"),
+    ("findInputFocussed", "search_focus"),
+    ("replaceInputFocussed", "replace_focus"),
+];
+
+/// The `when` clause contexts that can be left out, as they hold wherever
+/// Lapce runs the command
+const IGNORED_CONDITIONS: &[&str] = &["!editorReadonly", "!isInDiffEditor"];
+
+/// The settings and keybindings of VS Code, translated to Lapce
+#[derive(Debug, Default)]
+pub struct VsCodeImport {
+    /// The settings with the table they go in, which is `language.<id>` for
+    /// the language specific ones
+    pub settings: Vec<(String, &'static str, toml_edit::Value)>,
+    pub keymaps: Vec<KeyMap>,
+    /// The settings and keybindings that have no Lapce equivalent, and the
+    /// files that failed to parse
+    pub untranslated: Vec<String>,
+}
+
+impl VsCodeImport {
+    /// Read the `settings.json` and `keybindings.json` in the VS Code user
+    /// directory `dir`
+    pub fn read(dir: &Path, config: &LapceConfig) -> Self {
+        let mut import = Self::default();
+        for file in ["settings.json", "keybindings.json"] {
+            let Ok(content) = std::fs::read_to_string(dir.join(file)) else {
+                continue;
+            };
+            let value: Value = match serde_json::from_str(&strip_jsonc(&content)) {
+                Ok(value) => value,
+                Err(err) => {
+                    import.untranslated.push(format!("{file}: {err}"));
+                    continue;
+                }
+            };
+            if file == "settings.json" {
+                import.translate_settings(&value, config);
+            } else {
+                import.translate_keybindings(&value);
+            }
+        }
+        import
+    }
+
+    fn translate_settings(&mut self, settings: &Value, config: &LapceConfig) {
+        let Some(settings) = settings.as_object() else {
+            return;
+        };
+
+        for (name, value) in settings {
+            if let Some(languages) =
+                name.strip_prefix('[').and_then(|n| n.strip_suffix(']'))
+            {
+                self.translate_language_settings(languages, value);
+                continue;
+            }
+
+            let translated = match name.as_str() {
+                // Only read along with `files.autoSave`
+                "files.autoSaveDelay" => continue,
+                "files.autoSave" => {
+                    let interval = match value.as_str() {
+                        Some("off") => Some(0),
+                        Some("afterDelay") => Some(
+                            settings
+                                .get("files.autoSaveDelay")
+                                .and_then(Value::as_u64)
+                                .unwrap_or(1000),
+                        ),
+                        _ => None,
+                    };
+                    interval.map(|interval| {
+                        ("editor", "autosave-interval", (interval as i64).into())
+                    })
+                }
+                "workbench.colorTheme" => value
+                    .as_str()
+                    .and_then(|theme| {
+                        config.available_color_themes.get(&theme.to_lowercase())
+                    })
+                    .map(|(theme, _)| {
+                        ("core", "color-theme", theme.as_str().into())
+                    }),
+                "workbench.iconTheme" => value
+                    .as_str()
+                    .and_then(|theme| {
+                        config.available_icon_themes.get(&theme.to_lowercase())
+                    })
+                    .map(|(theme, _, _)| {
+                        ("core", "icon-theme", theme.as_str().into())
+                    }),
+                _ => SETTINGS
+                    .iter()
+                    .find(|(setting, ..)| setting == name)
+                    .and_then(|(_, parent, key, translate)| {
+                        Some((*parent, *key, translate(value)?))
+                    }),
+            };
+
+            match translated {
+                Some((parent, key, value)) => {
+                    self.settings.push((parent.to_string(), key, value))
+                }
+                None => self.untranslated.push(format!("{name}: {value}")),
+            }
+        }
+    }
+
+    /// Translate the settings of a `[<language>]` object, whose name can
+    /// list several languages, as in `[javascript][typescript]`
+    fn translate_language_settings(&mut self, languages: &str, settings: &Value) {
+        let Some(settings) = settings.as_object() else {
+            return;
+        };
+
+        for (name, value) in settings {
+            let translated = LANGUAGE_SETTINGS
+                .iter()
+                .find(|(setting, ..)| setting == name)
+                .and_then(|(_, key, translate)| Some((*key, translate(value)?)));
+            let Some((key, value)) = translated else {
+                self.untranslated
+                    .push(format!("[{languages}] {name}: {value}"));
+                continue;
+            };
+            for language in languages.split("][") {
+                self.settings.push((
+                    format!("language.{}", language.to_lowercase()),
+                    key,
+                    value.clone(),
+                ));
+            }
+        }
+    }
+
+    fn translate_keybindings(&mut self, keybindings: &Value) {
+        let Some(keybindings) = keybindings.as_array() else {
+            return;
+        };
+
+        for binding in keybindings {
+            let key = binding.get("key").and_then(Value::as_str).unwrap_or("");
+            let command =
+                binding.get("command").and_then(Value::as_str).unwrap_or("");
+            match translate_keybinding(binding, key, command) {
+                Some(keymap) => self.keymaps.push(keymap),
+                None => self.untranslated.push(format!("{key}: {command}")),
+            }
+        }
+    }
+
+    /// Add the translated settings and keybindings to the Lapce settings and
+    /// keymaps files
+    pub fn write(&self) {
+        for (parent, key, value) in &self.settings {
+            LapceConfig::update_file(parent, key, value.clone());
+        }
+        if !self.keymaps.is_empty() {
+            KeyPressData::append_to_file(&self.keymaps);
+        }
+    }
+
+    /// A description of what was imported and what was not
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Imported {} settings and {} keybindings from VS Code.",
+            self.settings.len(),
+            self.keymaps.len()
+        );
+        if !self.untranslated.is_empty() {
+            summary.push_str(" These could not be translated:\n");
+            summary.push_str(&self.untranslated.join("\n"));
+        }
+        summary
+    }
+}
+
+fn translate_keybinding(
+    binding: &Value,
+    key: &str,
+    command: &str,
+) -> Option<KeyMap> {
+    // Commands that take arguments do something different for each of them
+    if binding.get("args").is_some() {
+        return None;
+    }
+
+    // A command starting with `-` removes the binding rather than adding it
+    let (removal, command) = match command.strip_prefix('-') {
+        Some(command) => ("-", command),
+        None => ("", command),
+    };
+    let (_, command) = COMMANDS.iter().find(|(vscode, _)| *vscode == command)?;
+
+    let when = match binding.get("when").and_then(Value::as_str) {
+        Some(when) => translate_when(when)?,
+        None => None,
+    };
+
+    Some(KeyMap {
+        key: translate_key(key)?,
+        modes: Modes::empty(),
+        when,
+        command: format!("{removal}{command}"),
+    })
+}
+
+/// Translate a key chord like `ctrl+k ctrl+s`
+fn translate_key(key: &str) -> Option<Vec<KeyMapPress>> {
+    let presses: Vec<KeyMapPress> = key
+        .split_whitespace()
+        .map(|press| {
+            let (modifiers, key) = match press.strip_suffix("++") {
+                Some(modifiers) => (modifiers, "+"),
+                None => press.rsplit_once('+').unwrap_or(("", press)),
+            };
+
+            let mut mods = Modifiers::empty();
+            for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+                match modifier.to_lowercase().as_str() {
+                    "ctrl" => mods.set(Modifiers::CONTROL, true),
+                    "shift" => mods.set(Modifiers::SHIFT, true),
+                    "alt" => mods.set(Modifiers::ALT, true),
+                    "cmd" | "meta" | "win" => mods.set(Modifiers::META, true),
+                    _ => return None,
+                }
+            }
+
+            // Anything that isn't a single character or a key Lapce knows by
+            // name, such as `numpad_add`, would be taken as a character
+            let key = KeyMapKey::from_str(key).ok()?;
+            match &key {
+                KeyMapKey::Logical(Key::Named(_)) => {}
+                KeyMapKey::Logical(Key::Character(c)) if c.chars().count() == 1 => {}
+                _ => return None,
+            }
+
+            Some(KeyMapPress { key, mods })
+        })
+        .collect::<Option<_>>()?;
+    (!presses.is_empty()).then_some(presses)
+}
+
+/// Translate a `when` clause, which can only be a chain of `&&` over the
+/// contexts Lapce has a condition for
+fn translate_when(when: &str) -> Option<Option<String>> {
+    if ["||", "(", "==", "!=", "=~", " in "]
+        .iter()
+        .any(|op| when.contains(op))
+    {
+        return None;
+    }
+
+    let mut conditions = Vec::new();
+    for context in when.split("&&").map(str::trim) {
+        if IGNORED_CONDITIONS.contains(&context) {
+            continue;
+        }
+        let (negation, name) = match context.strip_prefix('!') {
+            Some(name) => ("!", name.trim()),
+            None => ("", context),
+        };
+        let (_, condition) =
+            CONDITIONS.iter().find(|(vscode, _)| *vscode == name)?;
+        conditions.push(format!("{negation}{condition}"));
+    }
+
+    Some((!conditions.is_empty()).then(|| conditions.iter().unique().join(" && ")))
+}
+
+/// Strip the comments and trailing commas VS Code allows in its JSON files
+fn strip_jsonc(content: &str) -> String {
+    let mut stripped = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            stripped.push(c);
+            match c {
+                '\\' => stripped.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                stripped.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '}' | ']' => {
+                let end = stripped.trim_end().len();
+                if stripped[..end].ends_with(',') {
+                    stripped.truncate(end - 1);
+                }
+                stripped.push(c);
+            }
+            _ => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+fn string(value: &Value) -> Option<toml_edit::Value> {
+    Some(value.as_str()?.into())
+}
+
+fn integer(value: &Value) -> Option<toml_edit::Value> {
+    Some(value.as_i64()?.into())
+}
+
+fn boolean(value: &Value) -> Option<toml_edit::Value> {
+    Some(value.as_bool()?.into())
+}
+
+fn font_weight(value: &Value) -> Option<toml_edit::Value> {
+    let weight: i64 = match value.as_str()? {
+        "normal" => 400,
+        "bold" => 700,
+        weight => weight.parse().ok()?,
+    };
+    Some(weight.into())
+}
+
+fn line_height(value: &Value) -> Option<toml_edit::Value> {
+    // 0 has VS Code work the line height out from the font size
+    let line_height = value.as_f64().filter(|h| *h > 0.0)?;
+    Some(line_height.into())
+}
+
+fn wrap_style(value: &Value) -> Option<toml_edit::Value> {
+    match value.as_str()? {
+        "off" => Some("none".into()),
+        "on" => Some("editor-width".into()),
+        _ => None,
+    }
+}
+
+fn caret_style(value: &Value) -> Option<toml_edit::Value> {
+    match value.as_str()? {
+        "line" | "line-thin" => Some("bar".into()),
+        "block" | "block-outline" => Some("block".into()),
+        "underline" | "underline-thin" => Some("underline".into()),
+        _ => None,
+    }
+}
+
+fn rulers(value: &Value) -> Option<toml_edit::Value> {
+    // A ruler is either its column or an object with a column and a color
+    let columns = value
+        .as_array()?
+        .iter()
+        .map(|ruler| {
+            ruler
+                .as_i64()
+                .or_else(|| ruler.get("column").and_then(Value::as_i64))
+        })
+        .collect::<Option<toml_edit::Array>>()?;
+    Some(columns.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{strip_jsonc, translate_key, translate_when, VsCodeImport};
+    use crate::config::LapceConfig;
+
+    #[test]
+    fn test_strip_jsonc() {
+        let content = r#"{
+            // The font
+            "editor.fontFamily": "Fira // Code", /* inline */
+            "editor.rulers": [80, 100,],
+        }"#;
+        let value: serde_json::Value =
+            serde_json::from_str(&strip_jsonc(content)).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "editor.fontFamily": "Fira // Code",
+                "editor.rulers": [80, 100],
+            })
+        );
+    }
+
+    #[test]
+    fn test_translate_settings() {
+        let mut import = VsCodeImport::default();
+        import.translate_settings(
+            &json!({
+                "editor.fontSize": 15,
+                "editor.wordWrap": "off",
+                "editor.minimap.enabled": false,
+                "files.autoSave": "afterDelay",
+                "files.autoSaveDelay": 2000,
+                "[rust][toml]": { "editor.tabSize": 2 },
+            }),
+            LapceConfig::default_values(),
+        );
+
+        let setting = |parent: &str, key: &str| {
+            import
+                .settings
+                .iter()
+                .find(|(p, k, _)| p == parent && *k == key)
+                .map(|(_, _, value)| value.clone())
+        };
+        assert_eq!(
+            setting("editor", "font-size").and_then(|v| v.as_integer()),
+            Some(15)
+        );
+        assert_eq!(
+            setting("editor", "wrap-style")
+                .and_then(|v| v.as_str().map(str::to_string)),
+            Some("none".to_string())
+        );
+        assert_eq!(
+            setting("editor", "autosave-interval").and_then(|v| v.as_integer()),
+            Some(2000)
+        );
+        assert_eq!(
+            setting("language.rust", "tab-width").and_then(|v| v.as_integer()),
+            Some(2)
+        );
+        assert_eq!(
+            setting("language.toml", "tab-width").and_then(|v| v.as_integer()),
+            Some(2)
+        );
+        assert_eq!(import.untranslated, vec!["editor.minimap.enabled: false"]);
+    }
+
+    #[test]
+    fn test_translate_keybindings() {
+        let mut import = VsCodeImport::default();
+        import.translate_keybindings(&json!([
+            {
+                "key": "ctrl+k ctrl+c",
+                "command": "editor.action.commentLine",
+                "when": "editorTextFocus && !editorReadonly",
+            },
+            { "key": "ctrl+shift+p", "command": "-workbench.action.showCommands" },
+            { "key": "ctrl+k z", "command": "workbench.action.toggleZenMode" },
+            { "key": "numpad_add", "command": "editor.action.rename" },
+        ]));
+
+        assert_eq!(import.keymaps.len(), 2);
+        assert_eq!(import.keymaps[0].command, "toggle_line_comment");
+        assert_eq!(import.keymaps[0].when.as_deref(), Some("editor_focus"));
+        assert_eq!(import.keymaps[0].key.len(), 2);
+        assert_eq!(import.keymaps[1].command, "-palette.command");
+        assert_eq!(import.untranslated.len(), 2);
+
+        assert!(translate_key("cmd+shift+[").is_some());
+        assert!(translate_key("hyper+a").is_none());
+        assert_eq!(translate_when("terminalFocus || editorFocus"), None);
+    }
+}
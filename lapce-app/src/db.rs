@@ -2,6 +2,7 @@
     path::{Path, PathBuf},
     rc::Rc,
     sync::Arc,
+    time::{Duration, SystemTime},
 };
 
 use anyhow::{anyhow, Result};
@@ -9,12 +10,16 @@
 use floem::peniko::kurbo::Vec2;
 use lapce_core::directory::Directory;
 use lapce_rpc::plugin::VoltID;
+use lapce_xi_rope::{Rope, RopeDelta};
 use sha2::{Digest, Sha256};
 
 use crate::{
     app::{AppData, AppInfo},
     doc::DocInfo,
-    panel::{data::PanelOrder, kind::PanelKind, position::PanelPosition},
+    panel::{
+        data::{add_missing_panels, PanelOrder},
+        kind::PanelKind,
+    },
     window::{WindowData, WindowInfo},
     window_tab::WindowTabData,
     workspace::{LapceWorkspace, WorkspaceInfo},
@@ -24,9 +29,18 @@
 const WINDOW: &str = "window";
 const WORKSPACE_INFO: &str = "workspace_info";
 const WORKSPACE_FILES: &str = "workspace_files";
+const WORKSPACE_TRUST: &str = "workspace_trust";
 const PANEL_ORDERS: &str = "panel_orders";
+const ACTIVITY_BAR_ORDER: &str = "activity_bar_order";
 const DISABLED_VOLTS: &str = "disabled_volts";
 const RECENT_WORKSPACES: &str = "recent_workspaces";
+const PINNED_WORKSPACES: &str = "pinned_workspaces";
+const UNDO_HISTORY: &str = "undo_history";
+
+/// Undo histories not saved again for this long are removed
+const UNDO_HISTORY_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// The most space the undo histories take, past which the oldest are removed
+const UNDO_HISTORY_MAX_SIZE: u64 = 64 * 1024 * 1024;
 
 pub enum SaveEvent {
     App(AppInfo),
@@ -35,7 +49,10 @@ pub enum SaveEvent {
     Doc(DocInfo),
     DisabledVolts(Vec<VoltID>),
     WorkspaceDisabledVolts(Arc<LapceWorkspace>, Vec<VoltID>),
+    WorkspaceTrust(Arc<LapceWorkspace>, bool),
     PanelOrder(PanelOrder),
+    ActivityBarOrder(Vec<PanelKind>),
+    UndoHistory(PathBuf, Rope, Vec<RopeDelta>),
 }
 
 #[derive(Clone)]
@@ -84,9 +101,19 @@ pub fn new() -> Result<Self> {
                         let _ = local_db
                             .insert_workspace_disabled_volts(workspace, volts);
                     }
+                    SaveEvent::WorkspaceTrust(workspace, trusted) => {
+                        let _ = local_db.insert_workspace_trust(&workspace, trusted);
+                    }
                     SaveEvent::PanelOrder(order) => {
                         let _ = local_db.insert_panel_orders(&order);
                     }
+                    SaveEvent::ActivityBarOrder(order) => {
+                        let _ = local_db.insert_activity_bar_order(&order);
+                    }
+                    SaveEvent::UndoHistory(path, content, undos) => {
+                        let _ =
+                            local_db.insert_undo_history(&path, &content, &undos);
+                    }
                 }
             }
         });
@@ -144,6 +171,38 @@ pub fn get_workspace_disabled_volts(
         Ok(volts)
     }
 
+    /// Whether the settings and tasks in the workspace were trusted to be used,
+    /// if it was ever asked
+    pub fn get_workspace_trust(&self, workspace: &LapceWorkspace) -> Option<bool> {
+        let folder = self.workspace_folder.join(workspace_folder_name(workspace));
+        let trusted = std::fs::read_to_string(folder.join(WORKSPACE_TRUST)).ok()?;
+        serde_json::from_str(&trusted).ok()
+    }
+
+    pub fn save_workspace_trust(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        trusted: bool,
+    ) {
+        let _ = self
+            .save_tx
+            .send(SaveEvent::WorkspaceTrust(workspace, trusted));
+    }
+
+    fn insert_workspace_trust(
+        &self,
+        workspace: &LapceWorkspace,
+        trusted: bool,
+    ) -> Result<()> {
+        let folder = self.workspace_folder.join(workspace_folder_name(workspace));
+        let _ = std::fs::create_dir_all(&folder);
+        std::fs::write(
+            folder.join(WORKSPACE_TRUST),
+            serde_json::to_string(&trusted)?,
+        )?;
+        Ok(())
+    }
+
     pub fn recent_workspaces(&self) -> Result<Vec<LapceWorkspace>> {
         let workspaces =
             std::fs::read_to_string(self.folder.join(RECENT_WORKSPACES))?;
@@ -160,6 +219,54 @@ pub fn update_recent_workspace(&self, workspace: &LapceWorkspace) -> Result<()>
         Ok(())
     }
 
+    /// The workspaces pinned to the top of the recent workspaces
+    pub fn pinned_workspaces(&self) -> Result<Vec<LapceWorkspace>> {
+        let workspaces =
+            std::fs::read_to_string(self.folder.join(PINNED_WORKSPACES))?;
+        let workspaces: Vec<LapceWorkspace> = serde_json::from_str(&workspaces)?;
+        Ok(workspaces)
+    }
+
+    /// The recent workspaces with the pinned ones first, and whether each of them
+    /// is pinned
+    pub fn recent_workspaces_with_pins(&self) -> Vec<(LapceWorkspace, bool)> {
+        let pinned = self.pinned_workspaces().unwrap_or_default();
+        let recent = self.recent_workspaces().unwrap_or_default();
+        let mut workspaces: Vec<(LapceWorkspace, bool)> =
+            pinned.into_iter().map(|w| (w, true)).collect();
+        for w in recent {
+            if !workspaces.iter().any(|(p, _)| p.is_same(&w)) {
+                workspaces.push((w, false));
+            }
+        }
+        workspaces
+    }
+
+    pub fn set_workspace_pinned(
+        &self,
+        workspace: &LapceWorkspace,
+        pinned: bool,
+    ) -> Result<()> {
+        let mut workspaces = self.pinned_workspaces().unwrap_or_default();
+        workspaces.retain(|w| !w.is_same(workspace));
+        if pinned {
+            workspaces.push(workspace.clone());
+        }
+        let workspaces = serde_json::to_string_pretty(&workspaces)?;
+        std::fs::write(self.folder.join(PINNED_WORKSPACES), workspaces)?;
+        Ok(())
+    }
+
+    /// Remove the workspace from the recent workspaces, unpinning it too
+    pub fn remove_recent_workspace(&self, workspace: &LapceWorkspace) -> Result<()> {
+        self.set_workspace_pinned(workspace, false)?;
+        let mut workspaces = self.recent_workspaces().unwrap_or_default();
+        workspaces.retain(|w| !w.is_same(workspace));
+        let workspaces = serde_json::to_string_pretty(&workspaces)?;
+        std::fs::write(self.folder.join(RECENT_WORKSPACES), workspaces)?;
+        Ok(())
+    }
+
     fn insert_recent_workspace(&self, workspace: LapceWorkspace) -> Result<()> {
         let mut workspaces = self.recent_workspaces().unwrap_or_default();
 
@@ -327,15 +434,7 @@ pub fn insert_window_tab(&self, data: Rc<WindowTabData>) -> Result<()> {
     pub fn get_panel_orders(&self) -> Result<PanelOrder> {
         let panel_orders = std::fs::read_to_string(self.folder.join(PANEL_ORDERS))?;
         let mut panel_orders: PanelOrder = serde_json::from_str(&panel_orders)?;
-
-        use strum::IntoEnumIterator;
-        for kind in PanelKind::iter() {
-            if kind.position(&panel_orders).is_none() {
-                let panels = panel_orders.entry(PanelPosition::LeftTop).or_default();
-                panels.push_back(kind);
-            }
-        }
-
+        add_missing_panels(&mut panel_orders);
         Ok(panel_orders)
     }
 
@@ -349,6 +448,23 @@ fn insert_panel_orders(&self, order: &PanelOrder) -> Result<()> {
         Ok(())
     }
 
+    /// The panels of the activity bar, in the order the user put them in
+    pub fn get_activity_bar_order(&self) -> Result<Vec<PanelKind>> {
+        let order = std::fs::read_to_string(self.folder.join(ACTIVITY_BAR_ORDER))?;
+        let order: Vec<PanelKind> = serde_json::from_str(&order)?;
+        Ok(order)
+    }
+
+    pub fn save_activity_bar_order(&self, order: Vec<PanelKind>) {
+        let _ = self.save_tx.send(SaveEvent::ActivityBarOrder(order));
+    }
+
+    fn insert_activity_bar_order(&self, order: &[PanelKind]) -> Result<()> {
+        let info = serde_json::to_string_pretty(order)?;
+        std::fs::write(self.folder.join(ACTIVITY_BAR_ORDER), info)?;
+        Ok(())
+    }
+
     pub fn save_doc_position(
         &self,
         workspace: &LapceWorkspace,
@@ -389,6 +505,88 @@ pub fn get_doc_info(
         let info: DocInfo = serde_json::from_str(&info)?;
         Ok(info)
     }
+
+    /// Store the undo history of `path` saved with `content`, as the deltas that undo
+    /// its edits one after another
+    pub fn save_undo_history(
+        &self,
+        path: PathBuf,
+        content: Rope,
+        undos: Vec<RopeDelta>,
+    ) {
+        let _ = self
+            .save_tx
+            .send(SaveEvent::UndoHistory(path, content, undos));
+    }
+
+    fn insert_undo_history(
+        &self,
+        path: &Path,
+        content: &Rope,
+        undos: &[RopeDelta],
+    ) -> Result<()> {
+        let folder = self.folder.join(UNDO_HISTORY);
+        let _ = std::fs::create_dir_all(&folder);
+
+        // Only the history of the last saved content of a file is kept
+        let path_name = doc_path_name(path);
+        for entry in std::fs::read_dir(&folder)?.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&path_name) {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+
+        if !undos.is_empty() {
+            let history = serde_json::to_string(undos)?;
+            std::fs::write(folder.join(undo_history_name(path, content)), history)?;
+        }
+
+        prune_undo_history(&folder)
+    }
+
+    /// The undo history of `path`, if it was last saved with `content`
+    pub fn get_undo_history(
+        &self,
+        path: &Path,
+        content: &Rope,
+    ) -> Result<Vec<RopeDelta>> {
+        let history = std::fs::read_to_string(
+            self.folder
+                .join(UNDO_HISTORY)
+                .join(undo_history_name(path, content)),
+        )?;
+        let undos: Vec<RopeDelta> = serde_json::from_str(&history)?;
+        Ok(undos)
+    }
+}
+
+/// Remove the undo histories that are too old, and then the oldest ones until the
+/// rest fit in the size limit
+fn prune_undo_history(folder: &Path) -> Result<()> {
+    let now = SystemTime::now();
+    let mut histories = Vec::new();
+    for entry in std::fs::read_dir(folder)?.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let modified = metadata.modified().unwrap_or(now);
+        let age = now.duration_since(modified).unwrap_or_default();
+        if age > UNDO_HISTORY_MAX_AGE {
+            let _ = std::fs::remove_file(entry.path());
+        } else {
+            histories.push((modified, metadata.len(), entry.path()));
+        }
+    }
+
+    histories.sort_by_key(|(modified, _, _)| std::cmp::Reverse(*modified));
+    let mut size = 0;
+    for (_, len, path) in histories {
+        size += len;
+        if size > UNDO_HISTORY_MAX_SIZE {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+    Ok(())
 }
 
 fn workspace_folder_name(workspace: &LapceWorkspace) -> String {
@@ -402,3 +600,13 @@ fn doc_path_name(path: &Path) -> String {
     hasher.update(path.to_string_lossy().as_bytes());
     format!("{:x}", hasher.finalize())
 }
+
+/// The name of the undo history of `path` with `content`, starting with the name of
+/// `path` so that the histories of its older content can be found
+fn undo_history_name(path: &Path, content: &Rope) -> String {
+    let mut hasher = Sha256::new();
+    for chunk in content.iter_chunks(0..content.len()) {
+        hasher.update(chunk.as_bytes());
+    }
+    format!("{}-{:x}", doc_path_name(path), hasher.finalize())
+}
@@ -1,7 +1,8 @@
 use std::{
+    cell::Cell,
     collections::{BTreeMap, HashMap},
     fmt::Display,
-    path::PathBuf,
+    path::{Path, PathBuf},
     rc::Rc,
     time::Instant,
 };
@@ -16,7 +17,7 @@
         self, DapId, RunDebugConfig, SourceBreakpoint, StackFrame, Stopped,
         ThreadId, Variable,
     },
-    proxy::ProxyResponse,
+    proxy::{ProxyResponse, ProxyRpcHandler},
     terminal::TermId,
 };
 use serde::{Deserialize, Serialize};
@@ -62,6 +63,8 @@ pub struct RunDebugData {
     pub active_term: RwSignal<Option<TermId>>,
     pub daps: RwSignal<im::HashMap<DapId, DapData>>,
     pub breakpoints: RwSignal<BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>>,
+    /// The expressions the debug panel shows the values of when stopped
+    pub watch_expressions: RwSignal<Vec<String>>,
 }
 
 impl RunDebugData {
@@ -77,9 +80,106 @@ pub fn new(
             active_term,
             daps,
             breakpoints,
+            watch_expressions: cx.create_rw_signal(Vec::new()),
         }
     }
 
+    /// Replace the watch expression at `index`, or add one without it. An
+    /// empty expression removes it instead.
+    pub fn set_watch_expression(&self, index: Option<usize>, expression: String) {
+        let expression = expression.trim().to_string();
+        self.watch_expressions.update(|expressions| match index {
+            Some(index) if index < expressions.len() => {
+                if expression.is_empty() {
+                    expressions.remove(index);
+                } else {
+                    expressions[index] = expression;
+                }
+            }
+            _ => {
+                if !expression.is_empty() {
+                    expressions.push(expression);
+                }
+            }
+        });
+        self.evaluate_watches();
+    }
+
+    /// Evaluate the watch expressions again in the debug sessions that are
+    /// stopped
+    pub fn evaluate_watches(&self) {
+        let expressions = self.watch_expressions.get_untracked();
+        for dap in self.daps.get_untracked().values() {
+            if dap.stopped.get_untracked() {
+                dap.evaluate_watches(&expressions);
+            }
+        }
+    }
+
+    /// Set the condition of the breakpoint, nothing to remove it. A breakpoint
+    /// is added at the line if there isn't one to set it on.
+    pub fn set_breakpoint_condition(
+        &self,
+        proxy: &ProxyRpcHandler,
+        target: BreakpointTarget,
+        condition: String,
+    ) {
+        let condition = condition.trim();
+        let condition = (!condition.is_empty()).then(|| condition.to_string());
+        self.breakpoints.update(|breakpoints| {
+            let breakpoints = breakpoints.entry(target.path.clone()).or_default();
+            if condition.is_none() && !breakpoints.contains_key(&target.line) {
+                return;
+            }
+            let breakpoint = breakpoints
+                .entry(target.line)
+                .or_insert_with(|| LapceBreakpoint::new(target.line, target.offset));
+            match target.kind {
+                BreakpointConditionKind::Expression => {
+                    breakpoint.condition = condition
+                }
+                BreakpointConditionKind::HitCount => {
+                    breakpoint.hit_condition = condition
+                }
+                BreakpointConditionKind::LogMessage => {
+                    breakpoint.log_message = condition
+                }
+            }
+        });
+        self.sync_breakpoints(proxy, &target.path);
+    }
+
+    /// Enable or disable the breakpoint
+    pub fn toggle_breakpoint_active(
+        &self,
+        proxy: &ProxyRpcHandler,
+        path: &Path,
+        line: usize,
+    ) {
+        self.breakpoints.update(|breakpoints| {
+            if let Some(breakpoint) =
+                breakpoints.get_mut(path).and_then(|b| b.get_mut(&line))
+            {
+                breakpoint.active = !breakpoint.active;
+            }
+        });
+        self.sync_breakpoints(proxy, path);
+    }
+
+    pub fn remove_breakpoint(
+        &self,
+        proxy: &ProxyRpcHandler,
+        path: &Path,
+        line: usize,
+    ) {
+        self.breakpoints.update(|breakpoints| {
+            if let Some(breakpoints) = breakpoints.get_mut(path) {
+                breakpoints.remove(&line);
+            }
+        });
+        self.sync_breakpoints(proxy, path);
+    }
+
     pub fn source_breakpoints(&self) -> HashMap<PathBuf, Vec<SourceBreakpoint>> {
         self.breakpoints
             .get_untracked()
@@ -87,26 +187,72 @@ pub fn source_breakpoints(&self) -> HashMap<PathBuf, Vec<SourceBreakpoint>> {
             .map(|(path, breakpoints)| {
                 (
                     path.to_path_buf(),
-                    breakpoints
-                        .iter()
-                        .filter_map(|(_, b)| {
-                            if b.active {
-                                Some(SourceBreakpoint {
-                                    line: b.line + 1,
-                                    column: None,
-                                    condition: None,
-                                    hit_condition: None,
-                                    log_message: None,
-                                })
-                            } else {
-                                None
-                            }
-                        })
-                        .collect(),
+                    breakpoints.values().filter_map(source_breakpoint).collect(),
                 )
             })
             .collect()
     }
+
+    /// Add a breakpoint at the line of the file, or enable the disabled one
+    /// that's there, or remove the enabled one, and tell the debug sessions
+    pub fn toggle_breakpoint(
+        &self,
+        proxy: &ProxyRpcHandler,
+        path: &Path,
+        line: usize,
+        offset: usize,
+    ) {
+        self.breakpoints.update(|breakpoints| {
+            let breakpoints = breakpoints.entry(path.to_path_buf()).or_default();
+            match breakpoints.get_mut(&line) {
+                Some(breakpoint) if !breakpoint.active => {
+                    breakpoint.active = true;
+                }
+                Some(_) => {
+                    breakpoints.remove(&line);
+                }
+                None => {
+                    breakpoints.insert(line, LapceBreakpoint::new(line, offset));
+                }
+            }
+        });
+        self.sync_breakpoints(proxy, path);
+    }
+
+    /// Send the enabled breakpoints of the file to all the debug sessions
+    pub fn sync_breakpoints(&self, proxy: &ProxyRpcHandler, path: &Path) {
+        let source_breakpoints: Vec<SourceBreakpoint> =
+            self.breakpoints.with_untracked(|breakpoints| {
+                breakpoints
+                    .get(path)
+                    .map(|b| b.values().filter_map(source_breakpoint).collect())
+                    .unwrap_or_default()
+            });
+        let daps: Vec<DapId> = self
+            .daps
+            .with_untracked(|daps| daps.keys().cloned().collect());
+        for dap_id in daps {
+            proxy.dap_set_breakpoints(
+                dap_id,
+                path.to_path_buf(),
+                source_breakpoints.clone(),
+            );
+        }
+    }
+}
+
+/// The breakpoint to send to the debug adapter, if it's enabled
+fn source_breakpoint(breakpoint: &LapceBreakpoint) -> Option<SourceBreakpoint> {
+    if !breakpoint.active {
+        return None;
+    }
+    Some(SourceBreakpoint {
+        line: breakpoint.line + 1,
+        column: None,
+        condition: breakpoint.condition.clone(),
+        hit_condition: breakpoint.hit_condition.clone(),
+        log_message: breakpoint.log_message.clone(),
+    })
 }
 
 #[derive(Clone, PartialEq)]
@@ -125,6 +271,63 @@ pub struct LapceBreakpoint {
     pub offset: usize,
     pub dap_line: Option<usize>,
     pub active: bool,
+    /// The expression that has to be true for the breakpoint to stop
+    #[serde(default)]
+    pub condition: Option<String>,
+    /// The expression of how many hits the breakpoint stops after, in the
+    /// syntax of the debug adapter
+    #[serde(default)]
+    pub hit_condition: Option<String>,
+    /// The message logged instead of stopping, with the expressions in `{}`
+    /// interpolated
+    #[serde(default)]
+    pub log_message: Option<String>,
+}
+
+impl LapceBreakpoint {
+    pub fn new(line: usize, offset: usize) -> Self {
+        Self {
+            id: None,
+            verified: false,
+            message: None,
+            line,
+            offset,
+            dap_line: None,
+            active: true,
+            condition: None,
+            hit_condition: None,
+            log_message: None,
+        }
+    }
+
+    /// The current value of the condition
+    pub fn condition(&self, kind: BreakpointConditionKind) -> Option<&str> {
+        match kind {
+            BreakpointConditionKind::Expression => self.condition.as_deref(),
+            BreakpointConditionKind::HitCount => self.hit_condition.as_deref(),
+            BreakpointConditionKind::LogMessage => self.log_message.as_deref(),
+        }
+    }
+}
+
+/// What of a breakpoint decides what it does when it's hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointConditionKind {
+    /// The expression that has to be true to stop
+    Expression,
+    /// How many hits to stop after
+    HitCount,
+    /// The message to log instead of stopping
+    LogMessage,
+}
+
+/// The breakpoint line which condition is edited
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakpointTarget {
+    pub path: PathBuf,
+    pub line: usize,
+    pub offset: usize,
+    pub kind: BreakpointConditionKind,
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -180,6 +383,121 @@ pub struct DapVariable {
     pub children_expanded_count: usize,
 }
 
+/// How many lines the debug console of a session keeps
+const CONSOLE_LEN: usize = 5000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsoleLineKind {
+    /// Output of the adapter or the program it runs
+    Output,
+    /// Output to stderr, or why an expression couldn't be evaluated
+    Error,
+    /// An expression typed in the debug console
+    Input,
+    /// The value an expression evaluated to
+    Result,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsoleLine {
+    pub id: usize,
+    pub kind: ConsoleLineKind,
+    pub text: String,
+}
+
+/// How many characters of a value are shown inline before it's cut
+const INLINE_VALUE_LEN: usize = 50;
+
+/// The values of the variables of the stack frame a debug session is stopped
+/// at, shown at the end of the lines of its file that use them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlineDebugValues {
+    pub dap_id: DapId,
+    pub path: PathBuf,
+    /// The line the stack frame is at, the values aren't shown after it
+    pub line: usize,
+    /// The first and last lines of the innermost scope around the frame's
+    /// line, when the debug adapter reports where its scopes are
+    pub scope_lines: Option<(usize, usize)>,
+    /// The variables of the frame's scopes by name, of the innermost scope
+    /// when several have the same name
+    pub variables: im::HashMap<String, Variable>,
+}
+
+impl InlineDebugValues {
+    /// The values shown at the end of `line`, whose text is `text`. They're
+    /// only shown on the lines of the frame's scope up to the frame's line.
+    /// `function` is the first and last lines of the function around the
+    /// frame's line, for when the debug adapter doesn't say where the scope
+    /// is. Without either, the values are only shown on the frame's line.
+    pub fn line_values(
+        &self,
+        line: usize,
+        text: &str,
+        function: Option<(usize, usize)>,
+    ) -> Option<String> {
+        let first_line = self
+            .scope_lines
+            .or(function)
+            .filter(|(start, end)| (*start..=*end).contains(&self.line))
+            .map_or(self.line, |(start, _)| start);
+        if line < first_line || line > self.line {
+            return None;
+        }
+        self.line_text(text)
+    }
+
+    /// The values of the variables the text of a line uses, in the order it
+    /// first uses them
+    pub fn line_text(&self, text: &str) -> Option<String> {
+        let mut names: Vec<&str> = Vec::new();
+        let mut word_start = None;
+        for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+            let is_word = c.is_alphanumeric() || c == '_';
+            match (word_start, is_word) {
+                (None, true) => word_start = Some(i),
+                (Some(start), false) => {
+                    word_start = None;
+                    let name = &text[start..i];
+                    // Fields and paths aren't the variables of the same name
+                    let is_member = text[..start].ends_with(['.', ':']);
+                    if !is_member
+                        && self.variables.contains_key(name)
+                        && !names.contains(&name)
+                    {
+                        names.push(name);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if names.is_empty() {
+            return None;
+        }
+        let values: Vec<String> = names
+            .into_iter()
+            .map(|name| format!("{name} = {}", inline_value(&self.variables[name])))
+            .collect();
+        Some(values.join(", "))
+    }
+}
+
+/// The value of the variable on one line, cut when it's long
+fn inline_value(variable: &Variable) -> String {
+    let value = variable
+        .value
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" ");
+    if value.chars().count() > INLINE_VALUE_LEN {
+        let value: String = value.chars().take(INLINE_VALUE_LEN).collect();
+        format!("{value}…")
+    } else {
+        value
+    }
+}
+
 #[derive(Clone)]
 pub struct DapData {
     pub term_id: TermId,
@@ -190,6 +508,15 @@ pub struct DapData {
     pub variables_id: RwSignal<usize>,
     pub variables: RwSignal<DapVariable>,
     pub breakline: Memo<Option<(usize, PathBuf)>>,
+    /// The stack frame the variables and watch expressions are of
+    pub frame_id: RwSignal<Option<usize>>,
+    /// The values of the variables of the stack frame, while it's stopped
+    pub inline_values: Memo<Option<InlineDebugValues>>,
+    /// The values of the watch expressions, or why they couldn't be evaluated
+    pub watches: RwSignal<im::HashMap<String, Result<String, String>>>,
+    /// The lines of the debug console
+    pub console: RwSignal<im::Vector<ConsoleLine>>,
+    next_console_id: Rc<Cell<usize>>,
     pub common: Rc<CommonData>,
 }
 
@@ -227,6 +554,62 @@ pub fn new(
                 None
             }
         });
+        let variables = cx.create_rw_signal(DapVariable {
+            item: ScopeOrVar::Scope(dap_types::Scope::default()),
+            parent: Vec::new(),
+            expanded: true,
+            read: true,
+            children: Vec::new(),
+            children_expanded_count: 0,
+        });
+        let frame_id = cx.create_rw_signal(None);
+        let inline_values = cx.create_memo(move |_| {
+            if !stopped.get() {
+                return None;
+            }
+            let frame_id = frame_id.get()?;
+            let frame = stack_traces.with(|stack_traces| {
+                stack_traces.values().find_map(|trace| {
+                    trace.frames.with(|frames| {
+                        frames.iter().find(|frame| frame.id == frame_id).cloned()
+                    })
+                })
+            })?;
+            let path = frame.source?.path?;
+            let line = frame.line.saturating_sub(1);
+            let (scope_lines, variables) = variables.with(|root| {
+                let scope_lines = root
+                    .children
+                    .iter()
+                    .filter_map(|scope| match &scope.item {
+                        ScopeOrVar::Scope(scope) => Some((
+                            scope.line?.saturating_sub(1),
+                            scope.end_line?.saturating_sub(1),
+                        )),
+                        ScopeOrVar::Var(_) => None,
+                    })
+                    .filter(|(start, end)| *start <= line && line <= *end)
+                    .min_by_key(|(start, end)| end - start);
+                let mut variables = im::HashMap::new();
+                for scope in &root.children {
+                    for var in &scope.children {
+                        if let ScopeOrVar::Var(var) = &var.item {
+                            variables
+                                .entry(var.name.clone())
+                                .or_insert_with(|| var.clone());
+                        }
+                    }
+                }
+                (scope_lines, variables)
+            });
+            Some(InlineDebugValues {
+                dap_id,
+                path,
+                line,
+                scope_lines,
+                variables,
+            })
+        });
         Self {
             term_id,
             dap_id,
@@ -234,15 +617,13 @@ pub fn new(
             thread_id,
             stack_traces,
             variables_id: cx.create_rw_signal(0),
-            variables: cx.create_rw_signal(DapVariable {
-                item: ScopeOrVar::Scope(dap_types::Scope::default()),
-                parent: Vec::new(),
-                expanded: true,
-                read: true,
-                children: Vec::new(),
-                children_expanded_count: 0,
-            }),
+            variables,
             breakline,
+            frame_id,
+            inline_values,
+            watches: cx.create_rw_signal(im::HashMap::new()),
+            console: cx.create_rw_signal(im::Vector::new()),
+            next_console_id: Rc::new(Cell::new(0)),
             common,
         }
     }
@@ -265,6 +646,7 @@ pub fn stopped(
         for (thread_id, frames) in stack_traces {
             let is_main_thread = main_thread_id.as_ref() == Some(thread_id);
             if is_main_thread {
+                self.frame_id.set(frames.first().map(|frame| frame.id));
                 if let Some(frame) = frames.first() {
                     if let Some(path) =
                         frame.source.as_ref().and_then(|source| source.path.clone())
@@ -355,6 +737,54 @@ pub fn toggle_expand(&self, parent: Vec<usize>, reference: usize) {
         });
     }
 
+    /// Add a line to the debug console for each line of the text
+    pub fn console_push(&self, kind: ConsoleLineKind, text: &str) {
+        self.console.update(|console| {
+            for line in text.lines() {
+                let id = self.next_console_id.get();
+                self.next_console_id.set(id + 1);
+                console.push_back(ConsoleLine {
+                    id,
+                    kind,
+                    text: line.to_string(),
+                });
+            }
+            while console.len() > CONSOLE_LEN {
+                console.pop_front();
+            }
+        });
+    }
+
+    /// Evaluate the watch expressions in the selected stack frame
+    pub fn evaluate_watches(&self, expressions: &[String]) {
+        let frame_id = self.frame_id.get_untracked();
+        for expression in expressions {
+            let watches = self.watches;
+            let key = expression.clone();
+            let send = create_ext_action(self.common.scope, move |result| {
+                let value = match result {
+                    Ok(ProxyResponse::DapEvaluateResponse { result }) => {
+                        Ok(result.result)
+                    }
+                    Ok(_) => return,
+                    Err(err) => Err(err.message),
+                };
+                watches.update(|watches| {
+                    watches.insert(key, value);
+                });
+            });
+            self.common.proxy.dap_evaluate(
+                self.dap_id,
+                expression.clone(),
+                frame_id,
+                "watch",
+                move |result| {
+                    send(result);
+                },
+            );
+        }
+    }
+
     fn read_var_children(&self, parent: &[usize], reference: usize) {
         let root = self.variables;
         let parent = parent.to_vec();
@@ -515,9 +945,121 @@ pub fn update_count(
 
 #[cfg(test)]
 mod tests {
-    use lapce_rpc::dap_types::{Scope, Variable};
+    use lapce_rpc::dap_types::{DapId, Scope, Variable};
+
+    use super::{
+        source_breakpoint, DapVariable, InlineDebugValues, LapceBreakpoint,
+        ScopeOrVar,
+    };
+
+    #[test]
+    fn test_inline_values_line_text() {
+        let variable = |name: &str, value: &str| {
+            (
+                name.to_string(),
+                Variable {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                    ..Default::default()
+                },
+            )
+        };
+        let values = InlineDebugValues {
+            dap_id: DapId(0),
+            path: "main.rs".into(),
+            line: 10,
+            scope_lines: None,
+            variables: [
+                variable("x", "5"),
+                variable("count", "1"),
+                variable("name", "\"a\nb\""),
+                variable("long", &"a".repeat(60)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        assert_eq!(
+            values.line_text("let y = x + count * x;").as_deref(),
+            Some("x = 5, count = 1")
+        );
+        assert_eq!(
+            values.line_text("self.x = name;").as_deref(),
+            Some("name = \"a b\"")
+        );
+        assert_eq!(values.line_text("Foo::count(x1);"), None);
+        assert_eq!(
+            values.line_text("long").as_deref(),
+            Some(format!("long = {}…", "a".repeat(50)).as_str())
+        );
+    }
+
+    #[test]
+    fn test_inline_values_scope() {
+        let text = [
+            "fn first() {",
+            "    let x = 1;",
+            "    println!(\"{x}\");",
+            "}",
+            "",
+            "fn second() {",
+            "    let x = 2;",
+            "    let y = x + 1;",
+            "    println!(\"{y}\");",
+            "}",
+        ]
+        .join("\n");
+        let mut values = InlineDebugValues {
+            dap_id: DapId(0),
+            path: "main.rs".into(),
+            // Stopped at `let y = x + 1;` in the second function
+            line: 7,
+            scope_lines: Some((5, 9)),
+            variables: ["x", "y"]
+                .into_iter()
+                .map(|name| {
+                    let variable = Variable {
+                        name: name.to_string(),
+                        value: "2".to_string(),
+                        ..Default::default()
+                    };
+                    (name.to_string(), variable)
+                })
+                .collect(),
+        };
+        let shown = |values: &InlineDebugValues, function| {
+            text.lines()
+                .enumerate()
+                .filter_map(|(line, text)| {
+                    values.line_values(line, text, function).map(|_| line)
+                })
+                .collect::<Vec<_>>()
+        };
 
-    use super::{DapVariable, ScopeOrVar};
+        // The first function's `x` is another variable
+        assert_eq!(shown(&values, None), vec![6, 7]);
+
+        // The function is used when the adapter doesn't say where the scope is
+        values.scope_lines = None;
+        assert_eq!(shown(&values, Some((5, 9))), vec![6, 7]);
+        // A function that isn't around the frame's line is ignored
+        assert_eq!(shown(&values, Some((0, 3))), vec![7]);
+        assert_eq!(shown(&values, None), vec![7]);
+    }
+
+    #[test]
+    fn test_source_breakpoint() {
+        let mut breakpoint = LapceBreakpoint::new(9, 120);
+        breakpoint.hit_condition = Some(">= 3".to_string());
+        breakpoint.log_message = Some("x is {x}".to_string());
+        let source = source_breakpoint(&breakpoint).unwrap();
+        assert_eq!(source.line, 10);
+        assert_eq!(source.condition, None);
+        assert_eq!(source.hit_condition.as_deref(), Some(">= 3"));
+        assert_eq!(source.log_message.as_deref(), Some("x is {x}"));
+
+        breakpoint.active = false;
+        assert!(source_breakpoint(&breakpoint).is_none());
+    }
 
     #[test]
     fn test_update_count() {
@@ -0,0 +1,350 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use floem::{ext_event::create_ext_action, keyboard::Modifiers, reactive::Scope};
+use lapce_core::{
+    command::{EditCommand, MoveCommand},
+    mode::Mode,
+    selection::Selection,
+};
+use lapce_rpc::{dap_types::CompletionItem, proxy::ProxyResponse};
+use lapce_xi_rope::Rope;
+
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    debug::{ConsoleLineKind, DapData},
+    editor::EditorData,
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::Editors,
+    terminal::panel::TerminalPanelData,
+    window_tab::CommonData,
+};
+
+/// How many of the expressions evaluated in the debug console are kept to go
+/// back to
+const HISTORY_LEN: usize = 100;
+
+/// The input of the debug console, which evaluates expressions in the selected
+/// stack frame of the active debug session
+#[derive(Clone)]
+pub struct DebugConsoleData {
+    pub input_editor: EditorData,
+    /// The expressions evaluated, oldest first
+    history: Rc<RefCell<Vec<String>>>,
+    /// Where in the history the input is, while going through it
+    history_index: Rc<Cell<Option<usize>>>,
+    terminal: TerminalPanelData,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for DebugConsoleData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PanelFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Edit(EditCommand::InsertNewLine) => {
+                self.evaluate_input();
+                CommandExecuted::Yes
+            }
+            CommandKind::Edit(EditCommand::InsertTab) => {
+                self.complete();
+                CommandExecuted::Yes
+            }
+            CommandKind::Move(MoveCommand::Up) => {
+                self.history_move(true);
+                CommandExecuted::Yes
+            }
+            CommandKind::Move(MoveCommand::Down) => {
+                self.history_move(false);
+                CommandExecuted::Yes
+            }
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.input_editor.run_command(command, count, mods)
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.input_editor.receive_char(c);
+    }
+}
+
+impl DebugConsoleData {
+    pub fn new(
+        cx: Scope,
+        editors: Editors,
+        terminal: TerminalPanelData,
+        common: Rc<CommonData>,
+    ) -> Self {
+        Self {
+            input_editor: editors.make_local(cx, common.clone()),
+            history: Rc::new(RefCell::new(Vec::new())),
+            history_index: Rc::new(Cell::new(None)),
+            terminal,
+            common,
+        }
+    }
+
+    fn input(&self) -> String {
+        self.input_editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string())
+    }
+
+    fn set_input(&self, text: &str) {
+        self.input_editor.doc().reload(Rope::from(text), true);
+        self.input_editor
+            .cursor()
+            .update(|cursor| cursor.set_insert(Selection::caret(text.len())));
+    }
+
+    /// Evaluate the expression typed in the input, and show it and its value
+    /// in the console of the active debug session
+    pub fn evaluate_input(&self) {
+        let expression = self.input().trim().to_string();
+        if expression.is_empty() {
+            return;
+        }
+        let Some(dap) = self.terminal.get_active_dap(false) else {
+            return;
+        };
+        self.set_input("");
+        self.push_history(expression.clone());
+        dap.console_push(ConsoleLineKind::Input, &expression);
+
+        let dap_id = dap.dap_id;
+        let frame_id = dap.frame_id.get_untracked();
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::DapEvaluateResponse { result }) => {
+                    dap.console_push(ConsoleLineKind::Result, &result.result);
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    dap.console_push(ConsoleLineKind::Error, &err.message);
+                }
+            });
+        self.common.proxy.dap_evaluate(
+            dap_id,
+            expression,
+            frame_id,
+            "repl",
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    fn push_history(&self, expression: String) {
+        let mut history = self.history.borrow_mut();
+        history.retain(|e| *e != expression);
+        history.push(expression);
+        if history.len() > HISTORY_LEN {
+            history.remove(0);
+        }
+        self.history_index.set(None);
+    }
+
+    /// Put the expression before (`back`) or after the one in the input from
+    /// the history in the input, or clear it after the last one
+    fn history_move(&self, back: bool) {
+        let history = self.history.borrow();
+        if history.is_empty() {
+            return;
+        }
+        let index = match (self.history_index.get(), back) {
+            (None, true) => Some(history.len() - 1),
+            (None, false) => return,
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i + 1 < history.len() => Some(i + 1),
+            (Some(_), false) => None,
+        };
+        self.history_index.set(index);
+        let text = index.map(|i| history[i].clone()).unwrap_or_default();
+        self.set_input(&text);
+    }
+
+    /// Complete the input with what the adapter suggests, if it can. With
+    /// several suggestions the input gets what they all start with, and they're
+    /// listed in the console.
+    fn complete(&self) {
+        let Some(dap) = self.terminal.get_active_dap(false) else {
+            return;
+        };
+        let input = self.input();
+        let column = input.encode_utf16().count() + 1;
+        let dap_id = dap.dap_id;
+        let frame_id = dap.frame_id.get_untracked();
+
+        let console = self.clone();
+        let text = input.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            if let Ok(ProxyResponse::DapCompletionsResponse { items }) = result {
+                console.apply_completions(&dap, &input, &items);
+            }
+        });
+        self.common.proxy.dap_completions(
+            dap_id,
+            text,
+            column,
+            frame_id,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    fn apply_completions(
+        &self,
+        dap: &DapData,
+        input: &str,
+        items: &[CompletionItem],
+    ) {
+        // The input was changed while the adapter was asked
+        if self.input() != input {
+            return;
+        }
+        let completed: Vec<String> = items
+            .iter()
+            .map(|item| apply_completion(input, item))
+            .collect();
+        match completed.as_slice() {
+            [] => {}
+            [completed] => self.set_input(completed),
+            _ => {
+                let prefix = common_prefix(&completed);
+                if prefix.len() > input.len() {
+                    self.set_input(prefix);
+                }
+                let labels: Vec<&str> =
+                    items.iter().map(|item| item.label.as_str()).collect();
+                dap.console_push(ConsoleLineKind::Output, &labels.join("  "));
+            }
+        }
+    }
+}
+
+/// The input with the completion in place of what it replaces: the text from
+/// its start, or the word at the end of the input without one
+fn apply_completion(input: &str, item: &CompletionItem) -> String {
+    let text = item.text.as_deref().unwrap_or(&item.label);
+    let (start, end) = match (item.start, item.length) {
+        (Some(start), length) => {
+            let start = start.saturating_sub(1);
+            (
+                utf16_to_byte(input, start),
+                utf16_to_byte(input, start + length.unwrap_or(0)),
+            )
+        }
+        (None, Some(length)) => {
+            let len = input.encode_utf16().count();
+            (
+                utf16_to_byte(input, len.saturating_sub(length)),
+                input.len(),
+            )
+        }
+        (None, None) => (word_start(input), input.len()),
+    };
+    format!("{}{text}{}", &input[..start], &input[end..])
+}
+
+/// The byte offset of the UTF-16 offset in the text
+fn utf16_to_byte(text: &str, offset: usize) -> usize {
+    let mut utf16 = 0;
+    for (i, c) in text.char_indices() {
+        if utf16 >= offset {
+            return i;
+        }
+        utf16 += c.len_utf16();
+    }
+    text.len()
+}
+
+/// Where the identifier the text ends with starts
+fn word_start(text: &str) -> usize {
+    text.char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(text.len())
+}
+
+/// What all the strings start with
+fn common_prefix(strings: &[String]) -> &str {
+    let Some(first) = strings.first() else {
+        return "";
+    };
+    let len = strings.iter().skip(1).fold(first.len(), |len, s| {
+        first[..len]
+            .char_indices()
+            .zip(s.chars())
+            .find(|((_, a), b)| a != b)
+            .map(|((i, _), _)| i)
+            .unwrap_or_else(|| len.min(s.len()))
+    });
+    &first[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_rpc::dap_types::CompletionItem;
+
+    use super::{apply_completion, common_prefix};
+
+    #[test]
+    fn test_apply_completion() {
+        let item = |label: &str, start, length| CompletionItem {
+            label: label.to_string(),
+            start,
+            length,
+            ..Default::default()
+        };
+        assert_eq!(
+            apply_completion("self.na", &item("name", None, None)),
+            "self.name"
+        );
+        assert_eq!(
+            apply_completion("self.na", &item("name", Some(6), Some(2))),
+            "self.name"
+        );
+        assert_eq!(
+            apply_completion("x + é", &item("éa", None, Some(1))),
+            "x + éa"
+        );
+        assert_eq!(
+            apply_completion("foo.", &item("bar", None, None)),
+            "foo.bar"
+        );
+    }
+
+    #[test]
+    fn test_common_prefix() {
+        let strings =
+            |s: &[&str]| s.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            common_prefix(&strings(&["self.name", "self.names"])),
+            "self.name"
+        );
+        assert_eq!(common_prefix(&strings(&["abc", "abd", "ab"])), "ab");
+        assert_eq!(common_prefix(&strings(&["é1", "é2"])), "é");
+        assert_eq!(common_prefix(&[]), "");
+    }
+}
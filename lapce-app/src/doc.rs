@@ -18,7 +18,7 @@
     ext_event::create_ext_action,
     keyboard::Modifiers,
     peniko::Color,
-    reactive::{batch, ReadSignal, RwSignal, Scope},
+    reactive::{batch, use_context, ReadSignal, RwSignal, Scope},
     views::editor::{
         actions::CommonAction,
         command::{Command, CommandExecuted},
@@ -40,7 +40,7 @@
     },
     char_buffer::CharBuffer,
     command::EditCommand,
-    cursor::{Cursor, CursorAffinity},
+    cursor::{Cursor, CursorAffinity, CursorMode},
     editor::{Action, EditConf, EditType},
     indent::IndentStyle,
     language::LapceLanguage,
@@ -57,6 +57,7 @@
     buffer::BufferId,
     plugin::PluginId,
     proxy::ProxyResponse,
+    source_control::BlameHunk,
     style::{LineStyle, LineStyles, Style},
 };
 use lapce_xi_rope::{
@@ -64,7 +65,8 @@
     Interval, Rope, RopeDelta, Transformer,
 };
 use lsp_types::{
-    CodeActionResponse, Diagnostic, DiagnosticSeverity, InlayHint, InlayHintLabel,
+    CodeActionOrCommand, Diagnostic, DiagnosticSeverity, InlayHint, InlayHintLabel,
+    Url,
 };
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
@@ -72,16 +74,27 @@
 use crate::{
     command::{CommandKind, LapceCommand},
     config::{color::LapceColor, LapceConfig},
-    editor::{compute_screen_lines, EditorData},
+    db::LapceDb,
+    debug::InlineDebugValues,
+    editor::{
+        blame::{blame_hunk_at_line, inline_blame_text},
+        compute_screen_lines,
+        conflict::{find_conflicts, MergeConflict},
+        EditorData,
+    },
     find::{Find, FindProgress, FindResult},
     history::DocumentHistory,
     keypress::KeyPressFocus,
     main_split::Editors,
     panel::kind::PanelKind,
+    undo_tree::{changed_range, UndoTree},
     window_tab::{CommonData, Focus},
     workspace::LapceWorkspace,
 };
 
+/// The most edits before a save that are kept in the undo history stored with it
+const UNDO_HISTORY_LEN: usize = 200;
+
 #[derive(Clone, Debug)]
 pub struct DiagnosticData {
     pub expanded: RwSignal<bool>,
@@ -122,6 +135,10 @@ pub fn is_file(&self) -> bool {
         matches!(self, DocContent::File { .. })
     }
 
+    pub fn is_scratch(&self) -> bool {
+        matches!(self, DocContent::Scratch { .. })
+    }
+
     pub fn read_only(&self) -> bool {
         match self {
             DocContent::File { read_only, .. } => *read_only,
@@ -150,7 +167,72 @@ pub struct DocInfo {
 }
 
 /// (Offset -> (Plugin the code actions are from, Code Actions))
-pub type CodeActions = im::HashMap<usize, Arc<(PluginId, CodeActionResponse)>>;
+/// The actions at an offset, with the plugin of the server that resolves each
+pub type CodeActions = im::HashMap<usize, Arc<Vec<(PluginId, CodeActionOrCommand)>>>;
+
+/// An occurrence of the symbol at the cursor, as reported by the language server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SymbolOccurrence {
+    pub start: usize,
+    pub end: usize,
+    /// Whether the symbol is written to, rather than read
+    pub write: bool,
+}
+
+/// A reference to a file or a web page in the document, as reported by the
+/// language server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocLink {
+    pub start: usize,
+    pub end: usize,
+    pub target: Url,
+}
+
+/// The content of a file along with the undo history it was saved with, which is made
+/// off the UI thread since restoring the history goes through the text for each edit
+pub struct RestoredContent {
+    /// The content before the oldest edit of the history
+    oldest: Rope,
+    /// The content after each edit of the history, the file's content last
+    states: Vec<Rope>,
+    /// A buffer with the edits between the states undoable, if there are any
+    buffer: Option<Buffer>,
+}
+
+impl RestoredContent {
+    /// The content of a file with the history of the edits that `undos` undo one
+    /// after another
+    pub fn new(content: Rope, undos: Vec<RopeDelta>) -> Self {
+        let mut states = undo_states(content, undos);
+        let oldest = states.remove(0);
+        let buffer = (!states.is_empty()).then(|| {
+            let mut buffer = Buffer::new("");
+            buffer.init_content(oldest.clone());
+            // Going through each state after the oldest makes the edits between
+            // them undoable
+            for state in &states {
+                buffer.reload(state.clone(), false);
+            }
+            buffer.set_pristine();
+            buffer
+        });
+        Self {
+            oldest,
+            states,
+            buffer,
+        }
+    }
+}
+
+/// The state of a document's file on disk, compared to the content it was loaded from or last
+/// saved with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiskState {
+    Unchanged,
+    /// Changed on disk while the document had unsaved changes, so it wasn't reloaded
+    Changed,
+    Deleted,
+}
 
 #[derive(Clone)]
 pub struct Doc {
@@ -165,6 +247,8 @@ pub struct Doc {
     semantic_styles: RwSignal<Option<Spans<Style>>>,
     /// Inlay hints for the document
     pub inlay_hints: RwSignal<Option<Spans<InlayHint>>>,
+    /// The links in the document, opened with a Ctrl+click
+    pub links: RwSignal<im::Vector<DocLink>>,
     /// Current completion lens text, if any.
     /// This will be displayed even on views that are not focused.
     pub completion_lens: RwSignal<Option<String>>,
@@ -179,10 +263,21 @@ pub struct Doc {
 
     /// (Offset -> (Plugin the code actions are from, Code Actions))
     pub code_actions: RwSignal<CodeActions>,
+    /// The occurrences of the symbol the cursor last rested on
+    pub symbol_occurrences: RwSignal<im::Vector<SymbolOccurrence>>,
 
     /// Stores information about different versions of the document from source control.
     histories: RwSignal<im::HashMap<String, DocumentHistory>>,
     pub head_changes: RwSignal<im::Vector<DiffLines>>,
+    /// The commit that last changed each range of lines, empty until it's retrieved
+    pub blame: RwSignal<im::Vector<BlameHunk>>,
+    /// The line whose blame is shown at its end, if inline blame is enabled
+    pub inline_blame_line: RwSignal<Option<usize>>,
+    /// The values of the variables of the stack frame a debug session is
+    /// stopped at in the document, shown at the end of the lines using them
+    pub inline_debug_values: RwSignal<Option<InlineDebugValues>>,
+    /// The merge conflicts left in the document, marked by conflict markers
+    pub conflicts: RwSignal<im::Vector<MergeConflict>>,
 
     line_styles: Rc<RefCell<LineStyles>>,
     pub parser: Rc<RefCell<BracketParser>>,
@@ -197,6 +292,15 @@ pub struct Doc {
     /// The diagnostics for the document
     pub diagnostics: DiagnosticData,
 
+    /// Whether the file was changed or deleted on disk since it was loaded or saved
+    pub disk_state: RwSignal<DiskState>,
+
+    /// The name of the encoding the file is read and written with
+    pub encoding: RwSignal<String>,
+
+    /// The states the content has been in, which undo and redo go between
+    pub undo_tree: RwSignal<UndoTree>,
+
     editors: Editors,
     pub common: Rc<CommonData>,
 }
@@ -223,6 +327,7 @@ pub fn new(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            links: cx.create_rw_signal(im::Vector::new()),
             diagnostics,
             completion_lens: cx.create_rw_signal(None),
             completion_pos: cx.create_rw_signal((0, 0)),
@@ -236,10 +341,18 @@ pub fn new(
             loaded: cx.create_rw_signal(false),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(im::Vector::new()),
+            inline_blame_line: cx.create_rw_signal(None),
+            inline_debug_values: cx.create_rw_signal(None),
+            conflicts: cx.create_rw_signal(im::Vector::new()),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
+            symbol_occurrences: cx.create_rw_signal(im::Vector::new()),
             find_result: FindResult::new(cx),
             preedit: PreeditData::new(cx),
+            disk_state: cx.create_rw_signal(DiskState::Unchanged),
+            encoding: cx.create_rw_signal("UTF-8".to_string()),
+            undo_tree: cx.create_rw_signal(UndoTree::new(Rope::from(""))),
             editors,
             common,
         }
@@ -270,6 +383,7 @@ pub fn new_content(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            links: cx.create_rw_signal(im::Vector::new()),
             diagnostics: DiagnosticData {
                 expanded: cx.create_rw_signal(true),
                 diagnostics: cx.create_rw_signal(im::Vector::new()),
@@ -283,11 +397,19 @@ pub fn new_content(
             content: cx.create_rw_signal(content),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(im::Vector::new()),
+            inline_blame_line: cx.create_rw_signal(None),
+            inline_debug_values: cx.create_rw_signal(None),
+            conflicts: cx.create_rw_signal(im::Vector::new()),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             loaded: cx.create_rw_signal(true),
             find_result: FindResult::new(cx),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
+            symbol_occurrences: cx.create_rw_signal(im::Vector::new()),
             preedit: PreeditData::new(cx),
+            disk_state: cx.create_rw_signal(DiskState::Unchanged),
+            encoding: cx.create_rw_signal("UTF-8".to_string()),
+            undo_tree: cx.create_rw_signal(UndoTree::new(Rope::from(""))),
             editors,
             common,
         }
@@ -318,6 +440,7 @@ pub fn new_history(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            links: cx.create_rw_signal(im::Vector::new()),
             diagnostics: DiagnosticData {
                 expanded: cx.create_rw_signal(true),
                 diagnostics: cx.create_rw_signal(im::Vector::new()),
@@ -333,9 +456,17 @@ pub fn new_history(
             loaded: cx.create_rw_signal(true),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(im::Vector::new()),
+            inline_blame_line: cx.create_rw_signal(None),
+            inline_debug_values: cx.create_rw_signal(None),
+            conflicts: cx.create_rw_signal(im::Vector::new()),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
+            symbol_occurrences: cx.create_rw_signal(im::Vector::new()),
             find_result: FindResult::new(cx),
             preedit: PreeditData::new(cx),
+            disk_state: cx.create_rw_signal(DiskState::Unchanged),
+            encoding: cx.create_rw_signal("UTF-8".to_string()),
+            undo_tree: cx.create_rw_signal(UndoTree::new(Rope::from(""))),
             editors,
             common,
         }
@@ -364,14 +495,20 @@ pub fn create_editor(
         let register = common.register;
         // TODO: we could have these Rcs created once and stored somewhere, maybe on
         // common, to avoid recreating them everytime.
+        let mut editor =
+            Editor::new_direct(cx, id, self.clone(), self.styling(), modal);
         let cursor_info = CursorInfo {
             blink_interval: Rc::new(move || config.editor.blink_interval()),
             blink_timer: common.window_common.cursor_blink_timer,
             hidden: common.window_common.hide_cursor,
-            should_blink: Rc::new(should_blink(common.focus, common.keyboard_focus)),
+            should_blink: Rc::new(should_blink(
+                common.focus,
+                common.keyboard_focus,
+                editor.doc_signal(),
+                editor.cursor,
+                editor.screen_lines,
+            )),
         };
-        let mut editor =
-            Editor::new_direct(cx, id, self.clone(), self.styling(), modal);
 
         editor.register = register;
         editor.cursor_info = cursor_info;
@@ -400,6 +537,27 @@ pub fn set_syntax(&self, syntax: Syntax) {
         });
     }
 
+    /// The language the syntax highlighting of the document is for
+    pub fn language(&self) -> LapceLanguage {
+        self.syntax.with_untracked(|syntax| syntax.language)
+    }
+
+    /// The indentation for documents of the language that have none of their
+    /// own to follow
+    fn default_indent_style(&self, language: LapceLanguage) -> IndentStyle {
+        let config = self.common.config.get_untracked();
+        match config
+            .language_config(language)
+            .and_then(|config| config.insert_spaces)
+        {
+            Some(false) => IndentStyle::Tabs,
+            Some(true) => {
+                IndentStyle::Spaces(config.tab_width(language).min(8) as u8)
+            }
+            None => IndentStyle::from_str(language.indent_unit()),
+        }
+    }
+
     /// Set the syntax highlighting this document should use.
     pub fn set_language(&self, language: LapceLanguage) {
         self.syntax.set(Syntax::from_language(language));
@@ -416,39 +574,93 @@ pub fn loaded(&self) -> bool {
 
     //// Initialize the content with some text, this marks the document as loaded.
     pub fn init_content(&self, content: Rope) {
+        self.init_restored_content(RestoredContent::new(content, Vec::new()));
+    }
+
+    /// Fill the document with the content of a file, along with the history of
+    /// the edits stored when the file was last saved
+    pub fn init_restored_content(&self, content: RestoredContent) {
+        let RestoredContent {
+            oldest,
+            states,
+            buffer: restored_buffer,
+        } = content;
+        let restored = restored_buffer.is_some();
+        let mut undo_tree = UndoTree::new(oldest.clone());
+        for state in states {
+            undo_tree.record(state);
+        }
+        undo_tree.set_saved();
         batch(|| {
             self.syntax.with_untracked(|syntax| {
                 self.buffer.update(|buffer| {
-                    buffer.init_content(content);
+                    match restored_buffer {
+                        Some(restored_buffer) => *buffer = restored_buffer,
+                        None => buffer.init_content(oldest),
+                    }
                     buffer.detect_indent(|| {
-                        IndentStyle::from_str(syntax.language.indent_unit())
+                        self.default_indent_style(syntax.language)
                     });
                 });
             });
+            self.undo_tree.set(undo_tree);
+            // The proxy's buffer was made with the current content alone
+            if let (true, DocContent::File { path, .. }) =
+                (restored, self.content.get_untracked())
+            {
+                self.common.proxy.set_buffer_rev(path, self.rev());
+            }
+            self.use_default_line_ending();
             self.loaded.set(true);
             self.on_update(None);
             self.init_parser();
             self.init_diagnostics();
             self.retrieve_head();
+            if self.common.config.get_untracked().editor.inline_blame {
+                self.retrieve_blame();
+            }
         });
     }
 
+    /// Use the configured default line ending if the content has no line break to
+    /// detect it from
+    pub fn use_default_line_ending(&self) {
+        if self.buffer.with_untracked(|buffer| buffer.last_line() > 0) {
+            return;
+        }
+        let line_ending = self
+            .common
+            .config
+            .get_untracked()
+            .editor
+            .default_line_ending
+            .line_ending();
+        self.buffer
+            .update(|buffer| buffer.set_line_ending(line_ending));
+    }
+
+    /// Show the first lines of the file while the rest of it is read. The document
+    /// stays unloaded, so it can't be edited, until [`Doc::init_content`].
+    pub fn show_preview(&self, content: Rope) {
+        if self.loaded() {
+            return;
+        }
+        self.buffer.set(Buffer::new(content));
+        self.clear_text_cache();
+    }
+
+    /// Whether edits to the buffer are refused, as the document is read only or only
+    /// its preview is shown
+    fn edits_blocked(&self) -> bool {
+        !self.loaded() || self.content.with_untracked(|c| c.read_only())
+    }
+
     fn init_parser(&self) {
-        let code = self.buffer.get_untracked().to_string();
         self.syntax.with_untracked(|syntax| {
-            if syntax.styles.is_some() {
-                self.parser.borrow_mut().update_code(
-                    code,
-                    &self.buffer.get_untracked(),
-                    Some(syntax),
-                );
-            } else {
-                self.parser.borrow_mut().update_code(
-                    code,
-                    &self.buffer.get_untracked(),
-                    None,
-                );
-            }
+            let syntax = syntax.styles.is_some().then_some(syntax);
+            self.buffer.with_untracked(|buffer| {
+                self.parser.borrow_mut().update_code(buffer, syntax);
+            });
         });
     }
 
@@ -462,25 +674,59 @@ pub fn reload(&self, content: Rope, set_pristine: bool) {
             .try_update(|buffer| buffer.reload(content, set_pristine))
             .unwrap();
         self.apply_deltas(&[delta]);
+        if set_pristine {
+            self.undo_tree.update(|tree| tree.set_saved());
+        }
     }
 
     pub fn handle_file_changed(&self, content: Rope) {
-        if self.is_pristine() {
+        let auto_reload = self
+            .common
+            .config
+            .with_untracked(|config| config.editor.auto_reload_clean_files);
+        if auto_reload && self.is_pristine() {
             self.reload(content, true);
+            self.disk_state.set(DiskState::Unchanged);
+        } else {
+            self.disk_state.set(DiskState::Changed);
         }
     }
 
+    /// Replace the content with the file's content on disk, dropping the unsaved
+    /// changes, which can still be undone back to
+    pub fn reload_from_disk(&self) {
+        let Some(path) = self.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::BufferHeadResponse { content, .. }) = result {
+                doc.reload(Rope::from(content), true);
+                doc.disk_state.set(DiskState::Unchanged);
+            }
+        });
+        self.common.proxy.get_buffer_saved(path, move |result| {
+            send(result);
+        });
+    }
+
+    pub fn handle_file_deleted(&self) {
+        self.disk_state.set(DiskState::Deleted);
+    }
+
     pub fn do_insert(
         &self,
         cursor: &mut Cursor,
         s: &str,
         config: &LapceConfig,
     ) -> Vec<(Rope, RopeDelta, InvalLines)> {
-        if self.content.with_untracked(|c| c.read_only()) {
+        if self.edits_blocked() {
             return Vec::new();
         }
 
         let old_cursor = cursor.mode.clone();
+        let start = cursor.offset();
         let deltas = self.syntax.with_untracked(|syntax| {
             self.buffer
                 .try_update(|buffer| {
@@ -503,6 +749,11 @@ pub fn do_insert(
             buffer.set_cursor_after(cursor.mode.clone());
         });
         self.apply_deltas(&deltas);
+        if !deltas.is_empty() {
+            // Typing goes in one undo state until a new line or a move elsewhere
+            let end = (!s.contains('\n')).then(|| cursor.offset());
+            self.undo_tree.update(|tree| tree.merge_insert(start, end));
+        }
         deltas
     }
 
@@ -511,7 +762,7 @@ pub fn do_raw_edit(
         edits: &[(impl AsRef<Selection>, &str)],
         edit_type: EditType,
     ) -> Option<(Rope, RopeDelta, InvalLines)> {
-        if self.content.with_untracked(|c| c.read_only()) {
+        if self.edits_blocked() {
             return None;
         }
 
@@ -531,12 +782,20 @@ pub fn do_edit(
         register: &mut Register,
         smart_tab: bool,
     ) -> Vec<(Rope, RopeDelta, InvalLines)> {
-        if self.content.with_untracked(|c| c.read_only())
-            && !cmd.not_changing_buffer()
-        {
+        if self.edits_blocked() && !cmd.not_changing_buffer() {
             return Vec::new();
         }
 
+        match cmd {
+            EditCommand::Undo => {
+                return self.go_to_undo_state(Some(cursor), UndoTree::undo);
+            }
+            EditCommand::Redo => {
+                return self.go_to_undo_state(Some(cursor), UndoTree::redo);
+            }
+            _ => {}
+        }
+
         let mut clipboard = SystemClipboard::new();
         let old_cursor = cursor.mode.clone();
         let deltas = self.syntax.with_untracked(|syntax| {
@@ -572,6 +831,64 @@ pub fn do_edit(
     }
 
     pub fn apply_deltas(&self, deltas: &[(Rope, RopeDelta, InvalLines)]) {
+        if !deltas.is_empty() {
+            let text = self.buffer.with_untracked(|buffer| buffer.text().clone());
+            self.undo_tree.update(|tree| tree.record(text));
+        }
+        self.update_for_deltas(deltas);
+    }
+
+    /// Change the content to the state of the undo tree that `go_to` goes to, putting
+    /// the cursor at the change
+    pub fn go_to_undo_state(
+        &self,
+        cursor: Option<&mut Cursor>,
+        go_to: impl FnOnce(&mut UndoTree) -> Option<Rope>,
+    ) -> Vec<(Rope, RopeDelta, InvalLines)> {
+        if self.edits_blocked() {
+            return Vec::new();
+        }
+        let Some(text) = self.undo_tree.try_update(go_to).flatten() else {
+            return Vec::new();
+        };
+        let saved = self
+            .undo_tree
+            .with_untracked(|tree| tree.is_saved(tree.current()));
+
+        let old_text = self.buffer.with_untracked(|buffer| buffer.text().clone());
+        let (old_range, new_range) = changed_range(&old_text, &text);
+        let new_content = text.slice_to_cow(new_range.clone());
+        let delta = self
+            .buffer
+            .try_update(|buffer| {
+                let delta = buffer.edit(
+                    &[(
+                        Selection::region(old_range.start, old_range.end),
+                        new_content.as_ref(),
+                    )],
+                    EditType::Other,
+                );
+                if saved {
+                    buffer.set_pristine();
+                }
+                delta
+            })
+            .unwrap();
+        let deltas = vec![delta];
+        self.update_for_deltas(&deltas);
+
+        if let Some(cursor) = cursor {
+            cursor.mode = match cursor.mode {
+                CursorMode::Insert(_) => {
+                    CursorMode::Insert(Selection::caret(new_range.end))
+                }
+                _ => CursorMode::Normal(new_range.start),
+            };
+        }
+        deltas
+    }
+
+    fn update_for_deltas(&self, deltas: &[(Rope, RopeDelta, InvalLines)]) {
         let rev = self.rev() - deltas.len() as u64;
         batch(|| {
             for (i, (_, delta, inval)) in deltas.iter().enumerate() {
@@ -580,6 +897,7 @@ pub fn apply_deltas(&self, deltas: &[(Rope, RopeDelta, InvalLines)]) {
                 self.update_diagnostics(delta);
                 self.update_completion_lens(delta);
                 self.update_find_result(delta);
+                self.update_links(delta);
                 if let DocContent::File { path, .. } = self.content.get_untracked() {
                     self.update_breakpoints(delta, &path, &inval.old_text);
                     self.common.proxy.update(
@@ -623,32 +941,51 @@ pub fn line_ending(&self) -> LineEnding {
         batch(|| {
             self.trigger_syntax_change(edits);
             self.trigger_head_change();
+            self.update_conflicts();
             self.check_auto_save();
             self.get_inlay_hints();
+            self.get_document_links();
             self.find_result.reset();
             self.get_semantic_styles();
             self.do_bracket_colorization();
             self.clear_code_actions();
+            self.symbol_occurrences.set(im::Vector::new());
             self.clear_style_cache();
         });
     }
 
+    /// Look for the conflict markers in the text off the UI thread, since it
+    /// goes through the whole document
+    fn update_conflicts(&self) {
+        if !self.content.with_untracked(|c| c.is_file()) {
+            return;
+        }
+        let (rev, text) = self
+            .buffer
+            .with_untracked(|buffer| (buffer.rev(), buffer.text().clone()));
+
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |conflicts| {
+            if doc.buffer.with_untracked(|b| b.rev()) == rev
+                && doc.conflicts.with_untracked(|c| c != &conflicts)
+            {
+                doc.conflicts.set(conflicts);
+            }
+        });
+        rayon::spawn(move || {
+            let conflicts: im::Vector<MergeConflict> = find_conflicts(&text).into();
+            send(conflicts);
+        });
+    }
+
     fn do_bracket_colorization(&self) {
         if self.parser.borrow().active {
             self.syntax.with_untracked(|syntax| {
-                if syntax.rev == self.rev() && syntax.styles.is_some() {
-                    self.parser.borrow_mut().update_code(
-                        self.buffer.get_untracked().to_string(),
-                        &self.buffer.get_untracked(),
-                        Some(syntax),
-                    );
-                } else {
-                    self.parser.borrow_mut().update_code(
-                        self.buffer.get_untracked().to_string(),
-                        &self.buffer.get_untracked(),
-                        None,
-                    );
-                }
+                let syntax = (syntax.rev == self.rev() && syntax.styles.is_some())
+                    .then_some(syntax);
+                self.buffer.with_untracked(|buffer| {
+                    self.parser.borrow_mut().update_code(buffer, syntax);
+                });
             })
         }
     }
@@ -893,6 +1230,56 @@ fn get_inlay_hints(&self) {
         });
     }
 
+    fn get_document_links(&self) {
+        if !self.loaded() {
+            return;
+        }
+
+        let path =
+            if let DocContent::File { path, .. } = self.content.get_untracked() {
+                path
+            } else {
+                return;
+            };
+
+        let (buffer, rev) = self.buffer.with_untracked(|b| (b.clone(), b.rev()));
+
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |links| {
+            if doc.buffer.with_untracked(|b| b.rev()) == rev {
+                doc.links.set(links);
+            }
+        });
+
+        self.common.proxy.get_document_links(path, move |result| {
+            if let Ok(ProxyResponse::GetDocumentLinksResponse { links }) = result {
+                // Links without a target would have to be resolved first, which
+                // isn't supported
+                let links = links
+                    .into_iter()
+                    .filter_map(|link| {
+                        Some(DocLink {
+                            start: buffer.offset_of_position(&link.range.start),
+                            end: buffer.offset_of_position(&link.range.end),
+                            target: link.target?,
+                        })
+                    })
+                    .collect();
+                send(links);
+            }
+        });
+    }
+
+    /// The link the offset is in
+    pub fn link_at(&self, offset: usize) -> Option<DocLink> {
+        self.links.with_untracked(|links| {
+            links
+                .iter()
+                .find(|link| link.start <= offset && offset < link.end)
+                .cloned()
+        })
+    }
+
     pub fn diagnostics(&self) -> &DiagnosticData {
         &self.diagnostics
     }
@@ -1028,6 +1415,31 @@ pub fn update_completion_lens(&self, delta: &RopeDelta) {
         self.completion_pos.set(new_pos);
     }
 
+    /// Update the links' positions after an edit, so they stay on their text
+    /// until the language server reports them again
+    fn update_links(&self, delta: &RopeDelta) {
+        if self.links.with_untracked(|links| links.is_empty()) {
+            return;
+        }
+
+        let mut transformer = Transformer::new(delta);
+        self.links.update(|links| {
+            *links = links
+                .iter()
+                .filter_map(|link| {
+                    let start = transformer.transform(link.start, true);
+                    let end = transformer.transform(link.end, false);
+                    // Drop the links whose text was deleted
+                    (start < end).then(|| DocLink {
+                        start,
+                        end,
+                        target: link.target.clone(),
+                    })
+                })
+                .collect();
+        });
+    }
+
     fn update_find_result(&self, delta: &RopeDelta) {
         self.find_result.occurrences.update(|s| {
             *s = s.apply_delta(delta, true, InsertDrift::Default);
@@ -1166,13 +1578,17 @@ pub fn retrieve_head(&self) {
         }
     }
 
+    /// The text of the `head` version of the buffer, if it has been retrieved
+    pub fn head_text(&self) -> Option<Rope> {
+        self.histories.with_untracked(|histories| {
+            histories
+                .get("head")
+                .map(|history| history.buffer.text().clone())
+        })
+    }
+
     pub fn trigger_head_change(&self) {
-        let history = if let Some(text) =
-            self.histories.with_untracked(|histories| {
-                histories
-                    .get("head")
-                    .map(|history| history.buffer.text().clone())
-            }) {
+        let history = if let Some(text) = self.head_text() {
             text
         } else {
             return;
@@ -1210,25 +1626,170 @@ pub fn trigger_head_change(&self) {
     }
 
     pub fn save(&self, after_action: impl FnOnce() + 'static) {
+        self.save_in(None, after_action);
+    }
+
+    /// Save the file in `encoding`, which it's read and written with from then on
+    pub fn save_with_encoding(&self, encoding: String) {
+        self.save_in(Some(encoding), || {});
+    }
+
+    fn save_in(
+        &self,
+        encoding: Option<String>,
+        after_action: impl FnOnce() + 'static,
+    ) {
         let content = self.content.get_untracked();
         if let DocContent::File { path, .. } = content {
             let rev = self.rev();
             let buffer = self.buffer;
+            let disk_state = self.disk_state;
+            let doc = self.clone();
+            let new_encoding = encoding.clone();
             let send = create_ext_action(self.scope, move |result| {
                 if let Ok(ProxyResponse::SaveResponse {}) = result {
+                    disk_state.set(DiskState::Unchanged);
+                    if let Some(encoding) = new_encoding {
+                        doc.encoding.set(encoding);
+                    }
+                    // Keep the blame in use up to date with the saved lines
+                    if !doc.blame.with_untracked(im::Vector::is_empty) {
+                        doc.retrieve_blame();
+                    }
                     let current_rev = buffer.with_untracked(|buffer| buffer.rev());
                     if current_rev == rev {
                         buffer.update(|buffer| {
                             buffer.set_pristine();
                         });
+                        doc.undo_tree.update(|tree| tree.set_saved());
+                        doc.save_undo_history();
                         after_action();
                     }
                 }
             });
 
-            self.common.proxy.save(rev, path, true, move |result| {
+            match encoding {
+                Some(encoding) => self.common.proxy.save_with_encoding(
+                    rev,
+                    path,
+                    encoding,
+                    move |result| {
+                        send(result);
+                    },
+                ),
+                None => self.common.proxy.save(rev, path, true, move |result| {
+                    send(result);
+                }),
+            }
+        }
+    }
+
+    /// Store the history of the edits up to the content just saved, to be restored
+    /// when the file is opened again with that content. Undoing the edits goes
+    /// through the text for each of them, so it's done off the UI thread.
+    fn save_undo_history(&self) {
+        let Some(path) = self.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let mut buffer = self.buffer.get_untracked();
+        let db: Arc<LapceDb> = use_context().unwrap();
+        rayon::spawn(move || {
+            let content = buffer.text().clone();
+            let mut cursor = Cursor::origin(false);
+            let mut clipboard = SystemClipboard::new();
+            let mut register = Register::default();
+            let mut undos = Vec::new();
+            while undos.len() < UNDO_HISTORY_LEN {
+                let deltas = Action::do_edit(
+                    &mut cursor,
+                    &mut buffer,
+                    &EditCommand::Undo,
+                    &mut clipboard,
+                    &mut register,
+                    EditConf {
+                        comment_token: "",
+                        modal: false,
+                        smart_tab: false,
+                        keep_indent: true,
+                        auto_indent: true,
+                    },
+                );
+                if deltas.is_empty() {
+                    break;
+                }
+                undos.extend(deltas.into_iter().map(|(_, delta, _)| delta));
+            }
+            db.save_undo_history(path, content, undos);
+        });
+    }
+
+    /// Read the file again in `encoding`, which it's read and written with from then
+    /// on. Unsaved edits are replaced, but can be undone back to.
+    pub fn reload_with_encoding(&self, encoding: String) {
+        let Some(path) = self.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        if !self.loaded() {
+            return;
+        }
+
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::NewBufferResponse {
+                content, encoding, ..
+            }) = result
+            {
+                doc.reload(Rope::from(content), true);
+                doc.disk_state.set(DiskState::Unchanged);
+                doc.encoding.set(encoding);
+            }
+        });
+        self.common
+            .proxy
+            .reload_with_encoding(path, encoding, move |result| {
                 send(result);
-            })
+            });
+    }
+
+    /// Retrieve the commit that last changed each line of the file
+    pub fn retrieve_blame(&self) {
+        let Some(path) = self.content.with_untracked(|content| match content {
+            DocContent::File { path, .. } => Some(path.clone()),
+            _ => None,
+        }) else {
+            return;
+        };
+
+        let blame = self.blame;
+        let cache_rev = self.cache_rev;
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::GitBlameResponse { hunks }) = result {
+                blame.set(hunks.into());
+                // The inline blame is phantom text
+                cache_rev.try_update(|cache_rev| *cache_rev += 1);
+            }
+        });
+        self.common.proxy.git_blame(path, move |result| {
+            send(result);
+        });
+    }
+
+    /// Show the blame of `line` at its end, if inline blame is enabled
+    pub fn set_inline_blame_line(&self, line: Option<usize>) {
+        if self.inline_blame_line.get_untracked() != line {
+            self.inline_blame_line.set(line);
+            // TODO: more granular invalidation
+            self.clear_text_cache();
+        }
+    }
+
+    /// Show the values of the variables of the stack frame a debug session is
+    /// stopped at in the document, or none to stop showing them
+    pub fn set_inline_debug_values(&self, values: Option<InlineDebugValues>) {
+        if self.inline_debug_values.with_untracked(|v| v != &values) {
+            self.inline_debug_values.set(values);
+            // TODO: more granular invalidation
+            self.clear_text_cache();
         }
     }
 
@@ -1643,6 +2204,66 @@ fn phantom_text(
             text.push(inline_completion_text);
         }
 
+        let inline_blame = config
+            .editor
+            .inline_blame
+            .then_some(())
+            .filter(|_| self.inline_blame_line.get_untracked() == Some(line))
+            .and_then(|_| {
+                self.blame
+                    .with_untracked(|blame| blame_hunk_at_line(blame, line).cloned())
+            })
+            .map(|hunk| PhantomText {
+                kind: PhantomTextKind::Diagnostic,
+                col: end_offset - start_offset,
+                affinity: Some(CursorAffinity::Backward),
+                text: format!("    {}", inline_blame_text(hunk.commit.as_ref())),
+                fg: Some(config.color(LapceColor::EDITOR_DIM)),
+                font_size: Some(config.editor.error_lens_font_size()),
+                bg: None,
+                under_line: None,
+            });
+        if let Some(inline_blame) = inline_blame {
+            text.push(inline_blame);
+        }
+
+        let inline_debug_values = config
+            .editor
+            .inline_debug_values
+            .then_some(())
+            .and_then(|_| {
+                self.inline_debug_values.with_untracked(|values| {
+                    let values = values.as_ref()?;
+                    let (line_content, function) = self.buffer.with_untracked(|b| {
+                        let offset =
+                            b.first_non_blank_character_on_line(values.line);
+                        let function = self.syntax.with_untracked(|syntax| {
+                            syntax.enclosing_function(offset)
+                        });
+                        (
+                            b.line_content(line).to_string(),
+                            function.map(|(start, end)| {
+                                (b.line_of_offset(start), b.line_of_offset(end))
+                            }),
+                        )
+                    });
+                    values.line_values(line, &line_content, function)
+                })
+            })
+            .map(|values| PhantomText {
+                kind: PhantomTextKind::Diagnostic,
+                col: end_offset - start_offset,
+                affinity: Some(CursorAffinity::Backward),
+                text: format!("    {values}"),
+                fg: Some(config.color(LapceColor::EDITOR_DIM)),
+                font_size: Some(config.editor.error_lens_font_size()),
+                bg: None,
+                under_line: None,
+            });
+        if let Some(inline_debug_values) = inline_debug_values {
+            text.push(inline_debug_values);
+        }
+
         if let Some(preedit) = self
             .preedit_phantom(Some(config.color(LapceColor::EDITOR_FOREGROUND)), line)
         {
@@ -1762,7 +2383,8 @@ fn font_family(
     }
 
     fn weight(&self, _: EditorId, _line: usize) -> floem::cosmic_text::Weight {
-        floem::cosmic_text::Weight::NORMAL
+        self.config
+            .with_untracked(|config| config.editor.font_weight())
     }
 
     fn italic_style(&self, _: EditorId, _line: usize) -> floem::cosmic_text::Style {
@@ -1788,7 +2410,9 @@ fn indent_line(&self, _: EditorId, line: usize, line_content: &str) -> usize {
     }
 
     fn tab_width(&self, _: EditorId, _line: usize) -> usize {
-        self.config.with_untracked(|config| config.editor.tab_width)
+        let language = self.doc.language();
+        self.config
+            .with_untracked(|config| config.tab_width(language))
     }
 
     fn atomic_soft_tabs(&self, _: EditorId, _line: usize) -> bool {
@@ -1976,11 +2600,26 @@ fn syntax_prev_unmatched(
 fn should_blink(
     focus: RwSignal<Focus>,
     keyboard_focus: RwSignal<Option<ViewId>>,
+    doc: RwSignal<Rc<dyn Document>>,
+    cursor: RwSignal<Cursor>,
+    screen_lines: RwSignal<ScreenLines>,
 ) -> impl Fn() -> bool {
     move || {
         let Some(focus) = focus.try_get_untracked() else {
             return false;
         };
+        // The editor may have been given another doc since it was created
+        let Some(Ok(doc)) =
+            doc.try_with_untracked(|doc| doc.clone().downcast_rc::<Doc>())
+        else {
+            return false;
+        };
+        let buffer = doc.buffer;
+        // Each blink repaints the window, which is wasted on a caret that can't be
+        // seen. The editor view restarts the blinking when it is scrolled back to.
+        if !caret_on_screen(buffer, cursor, screen_lines) {
+            return false;
+        }
         if matches!(
             focus,
             Focus::Workbench
@@ -1999,6 +2638,41 @@ fn should_blink(
     }
 }
 
+/// Whether a caret of `cursor` is on one of the lines on the screen
+pub fn caret_on_screen(
+    buffer: RwSignal<Buffer>,
+    cursor: RwSignal<Cursor>,
+    screen_lines: RwSignal<ScreenLines>,
+) -> bool {
+    let Some(lines) = screen_lines.try_with_untracked(|screen_lines| {
+        let first = screen_lines.lines.first()?;
+        let last = screen_lines.lines.last()?;
+        Some(first.line..=last.line)
+    }) else {
+        return false;
+    };
+    // Nothing has been laid out yet
+    let Some(lines) = lines else {
+        return true;
+    };
+    let Some(offsets) = cursor.try_with_untracked(|cursor| match &cursor.mode {
+        CursorMode::Normal(offset) => vec![*offset],
+        CursorMode::Visual { end, .. } => vec![*end],
+        CursorMode::Insert(selection) => {
+            selection.regions().iter().map(|r| r.end).collect()
+        }
+    }) else {
+        return false;
+    };
+    buffer
+        .try_with_untracked(|buffer| {
+            offsets
+                .iter()
+                .any(|offset| lines.contains(&buffer.line_of_offset(*offset)))
+        })
+        .unwrap_or(false)
+}
+
 fn extra_styles_for_range(
     text_layout: &TextLayout,
     start: usize,
@@ -2048,3 +2722,19 @@ fn extra_styles_for_range(
             })
         })
 }
+
+/// The content before each edit that `undos` undo one after another, from the oldest
+/// to `content`, stopping at an undo that doesn't fit the content it's applied to
+fn undo_states(content: Rope, undos: Vec<RopeDelta>) -> Vec<Rope> {
+    let mut states = vec![content];
+    for delta in undos {
+        let text = &states[states.len() - 1];
+        if delta.base_len != text.len() {
+            break;
+        }
+        let older = delta.apply(text);
+        states.push(older);
+    }
+    states.reverse();
+    states
+}
@@ -1,5 +1,6 @@
 use std::{
     collections::{HashMap, HashSet},
+    path::PathBuf,
     rc::Rc,
     str::FromStr,
     sync::Arc,
@@ -38,15 +39,17 @@
     },
     cursor::{Cursor, CursorMode},
     editor::EditType,
-    mode::{Mode, MotionMode},
+    language::LapceLanguage,
+    mode::{Mode, MotionMode, VisualMode},
     rope_text_pos::RopeTextPosition,
     selection::{InsertDrift, SelRegion, Selection},
 };
-use lapce_rpc::{buffer::BufferId, plugin::PluginId, proxy::ProxyResponse};
+use lapce_rpc::{buffer::BufferId, dap_types::Variable, proxy::ProxyResponse};
 use lapce_xi_rope::{Rope, RopeDelta, Transformer};
 use lsp_types::{
-    CompletionItem, CompletionTextEdit, GotoDefinitionResponse, HoverContents,
-    InlineCompletionTriggerKind, Location, MarkedString, MarkupKind, TextEdit,
+    CompletionItem, CompletionTextEdit, DocumentHighlight, DocumentHighlightKind,
+    GotoDefinitionResponse, HoverContents, InlineCompletionTriggerKind, Location,
+    MarkedString, MarkupKind, TextEdit,
 };
 use serde::{Deserialize, Serialize};
 
@@ -55,7 +58,7 @@
     completion::CompletionStatus,
     config::LapceConfig,
     db::LapceDb,
-    doc::{Doc, DocContent},
+    doc::{Doc, DocContent, DocLink, SymbolOccurrence},
     editor_tab::EditorTabChild,
     id::{DiffEditorId, EditorTabId},
     inline_completion::{InlineCompletionItem, InlineCompletionStatus},
@@ -71,12 +74,17 @@
 };
 
 use self::{
+    conflict::{resolved_text, ConflictResolution, MergeConflict},
     diff::DiffInfo,
+    hunk::{diff_hunks, DiffHunk},
     location::{EditorLocation, EditorPosition},
 };
 
+pub mod blame;
+pub mod conflict;
 pub mod diff;
 pub mod gutter;
+pub mod hunk;
 pub mod location;
 pub mod view;
 
@@ -92,6 +100,10 @@ pub struct EditorInfo {
     pub unsaved: Option<String>,
     pub offset: usize,
     pub scroll_offset: (f64, f64),
+    /// The language picked for an untitled file, which has no path to tell it
+    /// from
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 impl EditorInfo {
@@ -154,6 +166,14 @@ pub fn to_data(
                         if let Some(unsaved) = &self.unsaved {
                             doc.reload(Rope::from(unsaved), false);
                         }
+                        if let Some(language) = self
+                            .language
+                            .as_deref()
+                            .and_then(LapceLanguage::from_name)
+                        {
+                            doc.set_language(language);
+                            doc.trigger_syntax_change(None);
+                        }
                         scratch_docs.insert(name.to_string(), doc.clone());
                         doc
                     })
@@ -200,6 +220,14 @@ pub struct EditorData {
     pub editor: Rc<Editor>,
     pub kind: RwSignal<EditorViewKind>,
     pub sticky_header_height: RwSignal<f64>,
+    /// The change against the `head` version whose original lines are being shown
+    pub hunk_peek: RwSignal<Option<DiffHunk>>,
+    /// Whether the commit that last changed each line is shown next to the gutter
+    pub show_blame: RwSignal<bool>,
+    /// The merge conflict whose sides are being compared
+    pub conflict_peek: RwSignal<Option<MergeConflict>>,
+    /// The link under the mouse while the modifier that opens it is held
+    pub hovered_link: RwSignal<Option<DocLink>>,
     pub common: Rc<CommonData>,
 }
 
@@ -233,6 +261,10 @@ fn new(
             editor: Rc::new(editor),
             kind: cx.create_rw_signal(EditorViewKind::Normal),
             sticky_header_height: cx.create_rw_signal(0.0),
+            hunk_peek: cx.create_rw_signal(None),
+            show_blame: cx.create_rw_signal(false),
+            conflict_peek: cx.create_rw_signal(None),
+            hovered_link: cx.create_rw_signal(None),
             common,
         }
     }
@@ -324,16 +356,23 @@ pub fn editor_info(&self, _data: &WindowTabData) -> EditorInfo {
         let scroll_offset = self.viewport().get_untracked().origin();
         let doc = self.doc();
         let is_pristine = doc.is_pristine();
-        let unsaved = if is_pristine {
+        let hot_exit = self.common.config.with_untracked(|c| c.editor.hot_exit);
+        let unsaved = if is_pristine || !hot_exit {
             None
         } else {
             Some(doc.buffer.with_untracked(|b| b.to_string()))
         };
+        let content = doc.content.get_untracked();
+        let language = matches!(content, DocContent::Scratch { .. })
+            .then(|| doc.syntax().with_untracked(|s| s.language))
+            .filter(|language| *language != LapceLanguage::Plaintext)
+            .map(|language| language.key().to_string());
         EditorInfo {
-            content: self.doc().content.get_untracked(),
+            content,
             unsaved,
             offset,
             scroll_offset: (scroll_offset.x, scroll_offset.y),
+            language,
         }
     }
 
@@ -390,7 +429,7 @@ pub fn rope_text(&self) -> RopeTextVal {
         self.editor.rope_text()
     }
 
-    fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
+    pub fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
         let doc = self.doc();
         let text = self.editor.rope_text();
         let is_local = doc.content.with_untracked(|content| content.is_local());
@@ -745,6 +784,8 @@ pub fn run_focus_command(
         match cmd {
             FocusCommand::ModalClose => {
                 self.cancel_completion();
+                self.hunk_peek.set(None);
+                self.conflict_peek.set(None);
             }
             FocusCommand::SplitVertical => {
                 if let Some(editor_tab_id) = self.editor_tab_id.get_untracked() {
@@ -1938,6 +1979,256 @@ pub fn go_to_position(
         }
     }
 
+    /// The changes between the `head` version of the file and the buffer
+    pub fn head_hunks(&self) -> Vec<DiffHunk> {
+        self.doc()
+            .head_changes()
+            .with_untracked(|changes| diff_hunks(changes))
+    }
+
+    /// The first line of each change in the buffer: against the other side in a diff
+    /// editor, and against the `head` version otherwise
+    fn hunk_lines(&self) -> Vec<usize> {
+        let diff = self.kind.with_untracked(|kind| match kind {
+            EditorViewKind::Diff(diff) => {
+                Some((diff.is_right, diff_hunks(&diff.changes)))
+            }
+            EditorViewKind::Normal => None,
+        });
+        match diff {
+            Some((true, hunks)) => hunks.iter().map(|h| h.right.start).collect(),
+            Some((false, hunks)) => hunks.iter().map(|h| h.left.start).collect(),
+            None => self.head_hunks().iter().map(|h| h.right.start).collect(),
+        }
+    }
+
+    /// Move the cursor to the start of the next change, wrapping around to the first
+    /// change.
+    pub fn next_hunk(&self) {
+        let lines = self.hunk_lines();
+        let line = self.cursor_line();
+        let next = lines
+            .iter()
+            .find(|start| **start > line)
+            .or_else(|| lines.first());
+        if let Some(next) = next {
+            self.go_to_hunk(*next);
+        }
+    }
+
+    /// Move the cursor to the start of the previous change, wrapping around to the last
+    /// change.
+    pub fn previous_hunk(&self) {
+        let lines = self.hunk_lines();
+        let line = self.cursor_line();
+        let previous = lines
+            .iter()
+            .rev()
+            .find(|start| **start < line)
+            .or_else(|| lines.last());
+        if let Some(previous) = previous {
+            self.go_to_hunk(*previous);
+        }
+    }
+
+    fn cursor_line(&self) -> usize {
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        self.doc()
+            .buffer
+            .with_untracked(|buffer| buffer.line_of_offset(offset))
+    }
+
+    /// Move the cursor to the change starting at `line`, moving the open change popup
+    /// along with it.
+    fn go_to_hunk(&self, line: usize) {
+        self.go_to_position(
+            EditorPosition::Line(line.min(self.editor.last_line())),
+            None,
+            None,
+        );
+        if self.hunk_peek.with_untracked(Option::is_some) {
+            let hunk = self
+                .head_hunks()
+                .into_iter()
+                .find(|hunk| hunk.right.start == line);
+            self.hunk_peek.set(hunk);
+        }
+    }
+
+    /// Show or hide the popup with the original lines of the change whose gutter marker is
+    /// on `line`.
+    pub fn toggle_hunk_peek_at_line(&self, line: usize) {
+        let Some(hunk) = self
+            .head_hunks()
+            .into_iter()
+            .find(|hunk| hunk.is_on_line(line))
+        else {
+            return;
+        };
+
+        if self
+            .hunk_peek
+            .with_untracked(|peek| peek.as_ref() == Some(&hunk))
+        {
+            self.hunk_peek.set(None);
+        } else {
+            self.hunk_peek.set(Some(hunk));
+        }
+    }
+
+    /// Replace the lines of `hunk` in the buffer with their `head` version.
+    pub fn revert_hunk(&self, hunk: &DiffHunk) {
+        let doc = self.doc();
+        let Some(head) = doc.head_text() else {
+            return;
+        };
+
+        let head = RopeTextVal::new(head);
+        let original = head
+            .slice_to_cow(
+                head.offset_of_line(hunk.left.start)
+                    ..head.offset_of_line(hunk.left.end),
+            )
+            .to_string();
+        let (selection, region) = doc.buffer.with_untracked(|buffer| {
+            let selection = self.cursor().get_untracked().edit_selection(buffer);
+            let region = Selection::region(
+                buffer.offset_of_line(hunk.right.start),
+                buffer.offset_of_line(hunk.right.end),
+            );
+            (selection, region)
+        });
+
+        self.hunk_peek.set(None);
+        self.do_edit(&selection, &[(region, original.as_str())]);
+    }
+
+    pub fn toggle_blame(&self) {
+        let show_blame = !self.show_blame.get_untracked();
+        if show_blame {
+            self.doc().retrieve_blame();
+        }
+        self.show_blame.set(show_blame);
+    }
+
+    /// Stage the lines of `hunk` in the buffer, in place of its lines in the `head`
+    /// version.
+    pub fn stage_hunk(&self, hunk: &DiffHunk) {
+        let doc = self.doc();
+        let Some(path) = doc.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let content = doc.buffer.with_untracked(|buffer| {
+            buffer
+                .slice_to_cow(
+                    buffer.offset_of_line(hunk.right.start)
+                        ..buffer.offset_of_line(hunk.right.end),
+                )
+                .to_string()
+        });
+
+        self.hunk_peek.set(None);
+        self.common
+            .proxy
+            .git_stage_hunk(path, hunk.left.clone(), content);
+    }
+
+    /// The change against the `head` version whose gutter marker is on the cursor's line
+    pub fn hunk_at_cursor(&self) -> Option<DiffHunk> {
+        let line = self.cursor_line();
+        self.head_hunks()
+            .into_iter()
+            .find(|hunk| hunk.is_on_line(line))
+    }
+
+    /// The merge conflict containing the cursor's line
+    pub fn conflict_at_cursor(&self) -> Option<MergeConflict> {
+        let line = self.cursor_line();
+        self.doc().conflicts.with_untracked(|conflicts| {
+            conflicts
+                .iter()
+                .find(|conflict| conflict.lines().contains(&line))
+                .cloned()
+        })
+    }
+
+    /// Replace the whole of `conflict`, markers included, with the side(s) picked by
+    /// `resolution`.
+    pub fn resolve_conflict(
+        &self,
+        conflict: &MergeConflict,
+        resolution: ConflictResolution,
+    ) {
+        let doc = self.doc();
+        // The conflicts are looked for again after edits, which may not be done yet
+        if !doc
+            .buffer
+            .with_untracked(|buffer| conflict.is_in(buffer.text()))
+        {
+            return;
+        }
+        let (selection, region, text) = doc.buffer.with_untracked(|buffer| {
+            let selection = self.cursor().get_untracked().edit_selection(buffer);
+            let lines = conflict.lines();
+            let region = Selection::region(
+                buffer.offset_of_line(lines.start),
+                buffer.offset_of_line(lines.end),
+            );
+            (
+                selection,
+                region,
+                resolved_text(buffer.text(), conflict, resolution),
+            )
+        });
+
+        self.conflict_peek.set(None);
+        self.do_edit(&selection, &[(region, text.as_str())]);
+    }
+
+    /// Show or hide the current, incoming and common ancestor versions of `conflict`
+    /// side by side.
+    pub fn toggle_conflict_peek(&self, conflict: MergeConflict) {
+        if self
+            .conflict_peek
+            .with_untracked(|peek| peek.as_ref() == Some(&conflict))
+        {
+            self.conflict_peek.set(None);
+        } else {
+            self.conflict_peek.set(Some(conflict));
+        }
+    }
+
+    /// Move the cursor to the next merge conflict, wrapping around to the first one
+    pub fn next_conflict(&self) {
+        let line = self.cursor_line();
+        let next = self.doc().conflicts.with_untracked(|conflicts| {
+            conflicts
+                .iter()
+                .find(|conflict| conflict.start > line)
+                .or_else(|| conflicts.front())
+                .map(|conflict| conflict.start)
+        });
+        if let Some(next) = next {
+            self.go_to_position(EditorPosition::Line(next), None, None);
+        }
+    }
+
+    /// Move the cursor to the previous merge conflict, wrapping around to the last one
+    pub fn previous_conflict(&self) {
+        let line = self.cursor_line();
+        let previous = self.doc().conflicts.with_untracked(|conflicts| {
+            conflicts
+                .iter()
+                .rev()
+                .find(|conflict| conflict.start < line)
+                .or_else(|| conflicts.back())
+                .map(|conflict| conflict.start)
+        });
+        if let Some(previous) = previous {
+            self.go_to_position(EditorPosition::Line(previous), None, None);
+        }
+    }
+
     pub fn get_code_actions(&self) {
         let doc = self.doc();
         let path = match if doc.loaded() {
@@ -1960,7 +2251,7 @@ pub fn get_code_actions(&self) {
 
         // insert some empty data, so that we won't make the request again
         doc.code_actions().update(|c| {
-            c.insert(offset, Arc::new((PluginId(0), Vec::new())));
+            c.insert(offset, Arc::new(Vec::new()));
         });
 
         let (position, rev, diagnostics) = doc.buffer.with_untracked(|buffer| {
@@ -1995,17 +2286,117 @@ pub fn get_code_actions(&self) {
             position,
             diagnostics,
             move |result| {
-                if let Ok(ProxyResponse::GetCodeActionsResponse {
-                    plugin_id,
-                    resp,
-                }) = result
+                if let Ok(ProxyResponse::GetCodeActionsResponse { actions }) = result
                 {
-                    send((plugin_id, resp))
+                    send(actions)
                 }
             },
         );
     }
 
+    /// Request the occurrences of the symbol at the cursor, unless the cursor is
+    /// still on one of the occurrences already shown
+    pub fn get_symbol_occurrences(&self) {
+        let doc = self.doc();
+        if !self
+            .common
+            .config
+            .get_untracked()
+            .editor
+            .highlight_symbol_occurrences
+        {
+            return;
+        }
+        let path = match if doc.loaded() {
+            doc.content.with_untracked(|c| c.path().cloned())
+        } else {
+            None
+        } {
+            Some(path) => path,
+            None => return,
+        };
+
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let on_occurrence = doc.symbol_occurrences.with_untracked(|occurrences| {
+            occurrences
+                .iter()
+                .any(|o| o.start <= offset && offset <= o.end)
+        });
+        if on_occurrence {
+            return;
+        }
+
+        let position = doc
+            .buffer
+            .with_untracked(|buffer| buffer.offset_to_position(offset));
+        let rev = doc.rev();
+        let send = create_ext_action(
+            self.scope,
+            move |highlights: Vec<DocumentHighlight>| {
+                if doc.rev() != rev {
+                    return;
+                }
+                let mut occurrences: im::Vector<SymbolOccurrence> =
+                    doc.buffer.with_untracked(|buffer| {
+                        highlights
+                            .iter()
+                            .map(|h| SymbolOccurrence {
+                                start: buffer.offset_of_position(&h.range.start),
+                                end: buffer.offset_of_position(&h.range.end),
+                                write: h.kind == Some(DocumentHighlightKind::WRITE),
+                            })
+                            .collect()
+                    });
+                occurrences.sort_by_key(|o| o.start);
+                doc.symbol_occurrences.set(occurrences);
+            },
+        );
+        self.common
+            .proxy
+            .get_document_highlight(path, position, move |result| {
+                let highlights = match result {
+                    Ok(ProxyResponse::GetDocumentHighlightResponse {
+                        highlights,
+                    }) => highlights,
+                    _ => Vec::new(),
+                };
+                send(highlights);
+            });
+    }
+
+    /// Move the cursor to the next occurrence of the symbol at the cursor, wrapping
+    /// around to the first one.
+    pub fn next_symbol_occurrence(&self) {
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let next = self.doc().symbol_occurrences.with_untracked(|occurrences| {
+            occurrences
+                .iter()
+                .find(|o| o.start > offset)
+                .or_else(|| occurrences.front())
+                .map(|o| o.start)
+        });
+        if let Some(next) = next {
+            self.go_to_position(EditorPosition::Offset(next), None, None);
+        }
+    }
+
+    /// Move the cursor to the previous occurrence of the symbol at the cursor,
+    /// wrapping around to the last one.
+    pub fn previous_symbol_occurrence(&self) {
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let previous = self.doc().symbol_occurrences.with_untracked(|occurrences| {
+            occurrences
+                .iter()
+                .rev()
+                .find(|o| o.end < offset)
+                .or_else(|| occurrences.back())
+                .map(|o| o.start)
+        });
+        if let Some(previous) = previous {
+            self.go_to_position(EditorPosition::Offset(previous), None, None);
+        }
+    }
+
     pub fn show_code_actions(&self, mouse_click: bool) {
         let offset = self.cursor().with_untracked(|c| c.offset());
         let doc = self.doc();
@@ -2013,7 +2404,7 @@ pub fn show_code_actions(&self, mouse_click: bool) {
             .code_actions()
             .with_untracked(|c| c.get(&offset).cloned());
         if let Some(code_actions) = code_actions {
-            if !code_actions.1.is_empty() {
+            if !code_actions.is_empty() {
                 self.common.internal_command.send(
                     InternalCommand::ShowCodeActions {
                         offset,
@@ -2062,7 +2453,8 @@ pub fn save(
         }
 
         let rev = doc.rev();
-        let format_on_save = allow_formatting && config.editor.format_on_save;
+        let format_on_save =
+            allow_formatting && config.format_on_save(doc.language());
         if format_on_save {
             let editor = self.clone();
             let send = create_ext_action(self.scope, move |result| {
@@ -2074,23 +2466,50 @@ pub fn save(
                         editor.do_text_edit(&edits);
                     }
                 }
-                editor.do_save(after_action);
+                editor.will_save(path, after_action);
             });
 
             let (tx, rx) = crossbeam_channel::bounded(1);
             let proxy = self.common.proxy.clone();
+            let format_path = path.clone();
             std::thread::spawn(move || {
-                proxy.get_document_formatting(path, move |result| {
+                proxy.get_document_formatting(format_path, move |result| {
                     let _ = tx.send(result);
                 });
                 let result = rx.recv_timeout(std::time::Duration::from_secs(1));
                 send(result);
             });
         } else {
-            self.do_save(after_action);
+            self.will_save(path, after_action);
         }
     }
 
+    /// Tell the language servers the document is about to be saved and make
+    /// the edits they ask for, like organizing imports, before saving
+    fn will_save(&self, path: PathBuf, after_action: impl FnOnce() + 'static) {
+        let rev = self.doc().rev();
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(Ok(ProxyResponse::WillSaveDocumentResponse { edits })) = result
+            {
+                if !edits.is_empty() && editor.doc().rev() == rev {
+                    editor.do_text_edit(&edits);
+                }
+            }
+            editor.do_save(after_action);
+        });
+
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        let proxy = self.common.proxy.clone();
+        std::thread::spawn(move || {
+            proxy.will_save_document(path, move |result| {
+                let _ = tx.send(result);
+            });
+            let result = rx.recv_timeout(std::time::Duration::from_secs(1));
+            send(result);
+        });
+    }
+
     pub fn format(&self) {
         let doc = self.doc();
         let rev = doc.rev();
@@ -2121,6 +2540,83 @@ pub fn format(&self) {
         }
     }
 
+    /// Format the selected lines, or the line of the cursor when nothing is
+    /// selected
+    pub fn format_selection(&self) {
+        let doc = self.doc();
+        let rev = doc.rev();
+        let DocContent::File { path, .. } = doc.content.get_untracked() else {
+            return;
+        };
+
+        let range = doc.buffer.with_untracked(|buffer| {
+            let selection = self.cursor().get_untracked().edit_selection(buffer);
+            let (start, end) = match selection.regions().first() {
+                Some(region) if !region.is_caret() => (region.min(), region.max()),
+                _ => {
+                    let offset = self.cursor().with_untracked(|c| c.offset());
+                    let line = buffer.line_of_offset(offset);
+                    (
+                        buffer.offset_of_line(line),
+                        buffer.line_end_offset(line, true),
+                    )
+                }
+            };
+            lsp_types::Range {
+                start: buffer.offset_to_position(start),
+                end: buffer.offset_to_position(end),
+            }
+        });
+
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::GetDocumentFormatting { edits }) = result {
+                if editor.doc().rev() == rev {
+                    editor.do_text_edit(&edits);
+                }
+            }
+        });
+        self.common
+            .proxy
+            .get_range_formatting(path, range, move |result| {
+                send(result);
+            });
+    }
+
+    /// Let the language servers format the code after `c` was typed, if they
+    /// trigger on it
+    fn format_on_type(&self, c: &str) {
+        if !self.common.config.get_untracked().editor.format_on_type {
+            return;
+        }
+        let doc = self.doc();
+        let rev = doc.rev();
+        let DocContent::File { path, .. } = doc.content.get_untracked() else {
+            return;
+        };
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let position = doc
+            .buffer
+            .with_untracked(|buffer| buffer.offset_to_position(offset));
+
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::GetDocumentFormatting { edits }) = result {
+                if editor.doc().rev() == rev {
+                    editor.do_text_edit(&edits);
+                }
+            }
+        });
+        self.common.proxy.get_on_type_formatting(
+            path,
+            position,
+            c.to_string(),
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
     fn search_whole_word_forward(&self, mods: Modifiers) {
         let offset = self.cursor().with_untracked(|c| c.offset());
         let (word, buffer) = self.doc().buffer.with_untracked(|buffer| {
@@ -2309,6 +2805,42 @@ fn rename(&self) {
             });
     }
 
+    /// The selected text, or the line of the cursor if nothing is selected
+    pub fn selection_or_line(&self) -> String {
+        let doc = self.doc();
+        doc.buffer.with_untracked(|buffer| {
+            let (start, end) = self.cursor().with_untracked(|c| match &c.mode {
+                CursorMode::Normal(offset) => (*offset, *offset),
+                CursorMode::Visual { start, end, mode } => {
+                    let (start, end) = (*start.min(end), *start.max(end));
+                    if *mode == VisualMode::Linewise {
+                        (
+                            buffer.offset_of_line(buffer.line_of_offset(start)),
+                            buffer
+                                .line_end_offset(buffer.line_of_offset(end), false),
+                        )
+                    } else {
+                        (start, buffer.next_grapheme_offset(end, 1, buffer.len()))
+                    }
+                }
+                CursorMode::Insert(selection) => selection
+                    .last_inserted()
+                    .map(|region| (region.min(), region.max()))
+                    .unwrap_or((0, 0)),
+            });
+
+            if start == end {
+                let line = buffer.line_of_offset(start);
+                buffer
+                    .line_content(line)
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string()
+            } else {
+                buffer.slice_to_cow(start..end).to_string()
+            }
+        })
+    }
+
     #[instrument]
     pub fn word_at_cursor(&self) -> String {
         let doc = self.doc();
@@ -2390,20 +2922,15 @@ pub fn pointer_down(&self, pointer_event: &PointerInputEvent) {
                 self.active().set(true);
                 self.left_click(pointer_event);
 
-                if cfg!(target_os = "macos") && pointer_event.modifiers.meta() {
-                    self.common.lapce_command.send(LapceCommand {
-                        kind: CommandKind::Focus(FocusCommand::GotoDefinition),
-                        data: None,
-                    })
-                }
-
-                if cfg!(not(target_os = "macos"))
-                    && pointer_event.modifiers.control()
-                {
-                    self.common.lapce_command.send(LapceCommand {
-                        kind: CommandKind::Focus(FocusCommand::GotoDefinition),
-                        data: None,
-                    })
+                if link_modifier(&pointer_event.modifiers) {
+                    if let Some(link) = self.link_at_point(pointer_event.pos) {
+                        self.open_link(&link);
+                    } else {
+                        self.common.lapce_command.send(LapceCommand {
+                            kind: CommandKind::Focus(FocusCommand::GotoDefinition),
+                            data: None,
+                        })
+                    }
                 }
             }
             PointerButton::Secondary => {
@@ -2471,6 +2998,14 @@ pub fn pointer_move(&self, pointer_event: &PointerMoveEvent) {
                 }
             }
         }
+        let link = if link_modifier(&pointer_event.modifiers) && is_inside {
+            self.doc().link_at(offset)
+        } else {
+            None
+        };
+        if self.hovered_link.with_untracked(|hovered| hovered != &link) {
+            self.hovered_link.set(link);
+        }
         let hover_delay = self.common.config.get_untracked().editor.hover_delay;
         if hover_delay > 0 {
             if is_inside {
@@ -2504,6 +3039,49 @@ pub fn pointer_up(&self, pointer_event: &PointerInputEvent) {
     #[instrument]
     pub fn pointer_leave(&self) {
         self.common.mouse_hover_timer.set(TimerToken::INVALID);
+        self.hovered_link.set(None);
+    }
+
+    fn link_at_point(&self, point: Point) -> Option<DocLink> {
+        let mode = self.cursor().with_untracked(|c| c.get_mode());
+        let (offset, is_inside) = self.editor.offset_of_point(mode, point);
+        if !is_inside {
+            return None;
+        }
+        self.doc().link_at(offset)
+    }
+
+    /// Open a link to a file in an editor, at the line of a `#L<line>` fragment,
+    /// and any other link in the browser
+    fn open_link(&self, link: &DocLink) {
+        if link.target.scheme() != "file" {
+            self.common
+                .internal_command
+                .send(InternalCommand::OpenWebUri {
+                    uri: link.target.to_string(),
+                });
+            return;
+        }
+        let Ok(path) = link.target.to_file_path() else {
+            return;
+        };
+        let line = link
+            .target
+            .fragment()
+            .and_then(|fragment| fragment.strip_prefix('L'))
+            .and_then(|line| line.split(',').next()?.parse::<usize>().ok());
+        self.common
+            .internal_command
+            .send(InternalCommand::JumpToLocation {
+                location: EditorLocation {
+                    path,
+                    position: line
+                        .map(|line| EditorPosition::Line(line.saturating_sub(1))),
+                    scroll_offset: None,
+                    ignore_unconfirmed: false,
+                    same_editor_tab: false,
+                },
+            });
     }
 
     #[instrument]
@@ -2526,6 +3104,12 @@ fn right_click(&self, pointer_event: &PointerInputEvent) {
             vec![
                 Some(CommandKind::Focus(FocusCommand::GotoDefinition)),
                 Some(CommandKind::Focus(FocusCommand::GotoTypeDefinition)),
+                Some(CommandKind::Workbench(
+                    LapceWorkbenchCommand::ShowCallHierarchy,
+                )),
+                Some(CommandKind::Workbench(
+                    LapceWorkbenchCommand::ShowTypeHierarchy,
+                )),
                 None,
                 Some(CommandKind::Focus(FocusCommand::Rename)),
                 None,
@@ -2570,6 +3154,9 @@ fn right_click(&self, pointer_event: &PointerInputEvent) {
 
     #[instrument]
     fn update_hover(&self, offset: usize) {
+        if self.update_debug_hover(offset) {
+            return;
+        }
         let doc = self.doc();
         let path = doc
             .content
@@ -2598,6 +3185,59 @@ fn update_hover(&self, offset: usize) {
         });
     }
 
+    /// Show the value of the variable at the offset and the values of its
+    /// fields, if a debug session is stopped in the document and it's one of
+    /// the variables of the stack frame
+    fn update_debug_hover(&self, offset: usize) -> bool {
+        let doc = self.doc();
+        let Some(values) = doc.inline_debug_values.get_untracked() else {
+            return false;
+        };
+        let (name, is_member) = doc.buffer.with_untracked(|buffer| {
+            let end = buffer.next_code_boundary(offset);
+            let name = buffer.slice_to_cow(offset..end).to_string();
+            let prev =
+                lapce_xi_rope::Cursor::new(buffer.text(), offset).prev_codepoint();
+            (name, matches!(prev, Some('.' | ':')))
+        });
+        // Fields and paths aren't the variables of the same name
+        let Some(variable) =
+            values.variables.get(&name).filter(|_| !is_member).cloned()
+        else {
+            return false;
+        };
+
+        let config = self.common.config;
+        let hover_data = self.common.hover.clone();
+        let editor_id = self.id();
+        let reference = variable.variables_reference;
+        let show = move |children: &[Variable]| {
+            let content =
+                debug_hover_content(&variable, children, &config.get_untracked());
+            hover_data.content.set(content);
+            hover_data.offset.set(offset);
+            hover_data.editor_id.set(editor_id);
+            hover_data.active.set(true);
+        };
+        if reference == 0 {
+            show(&[]);
+            return true;
+        }
+        let send = create_ext_action(self.scope, move |resp| {
+            let children = match resp {
+                Ok(ProxyResponse::DapVariableResponse { varialbes }) => varialbes,
+                _ => Vec::new(),
+            };
+            show(&children);
+        });
+        self.common
+            .proxy
+            .dap_variable(values.dap_id, reference, move |resp| {
+                send(resp);
+            });
+        true
+    }
+
     // reset the doc inside and move cursor back
     pub fn reset(&self) {
         let doc = self.doc();
@@ -2617,7 +3257,9 @@ pub fn visual_line(&self, line: usize) -> usize {
                 while let Some(change) = changes.next() {
                     match (is_right, change) {
                         (true, DiffLines::Left(range)) => {
-                            if let Some(DiffLines::Right(_)) = changes.peek() {
+                            if let (None, Some(DiffLines::Right(_))) =
+                                (&diff.inline, changes.peek())
+                            {
                             } else {
                                 visual_line += range.len();
                             }
@@ -2638,7 +3280,7 @@ pub fn visual_line(&self, line: usize) -> usize {
                                 return visual_line + line - range.start;
                             }
                             visual_line += range.len();
-                            if is_right {
+                            if is_right && diff.inline.is_none() {
                                 if let Some(DiffLines::Left(r)) = last_change {
                                     let len = r.len() - r.len().min(range.len());
                                     if len > 0 {
@@ -2702,7 +3344,9 @@ pub fn actual_line(&self, visual_line: usize, bottom_affinity: bool) -> usize {
                 while let Some(change) = changes.next() {
                     match (is_right, change) {
                         (true, DiffLines::Left(range)) => {
-                            if let Some(DiffLines::Right(_)) = changes.peek() {
+                            if let (None, Some(DiffLines::Right(_))) =
+                                (&diff.inline, changes.peek())
+                            {
                             } else {
                                 current_visual_line += range.len();
                                 if current_visual_line >= visual_line {
@@ -2736,7 +3380,7 @@ pub fn actual_line(&self, visual_line: usize, bottom_affinity: bool) -> usize {
                             }
                             current_visual_line += len;
                             actual_line += len;
-                            if is_right {
+                            if is_right && diff.inline.is_none() {
                                 if let Some(DiffLines::Left(r)) = last_change {
                                     let len = r.len() - r.len().min(range.len());
                                     if len > 0 {
@@ -2969,6 +3613,7 @@ fn receive_char(&self, c: &str) {
                 );
 
                 self.apply_deltas(&deltas);
+                self.format_on_type(c);
             } else if let Some(direction) = self.inline_find.get_untracked() {
                 self.inline_find(direction.clone(), c);
                 self.last_inline_find.set(Some((direction, c.to_string())));
@@ -3021,6 +3666,16 @@ pub fn track(&self) {
 
 /// Checks if completion should be triggered if the received command
 /// is one that inserts whitespace or deletes whitespace
+/// Whether the modifier that turns a click into a jump is held, Cmd on macOS and
+/// Ctrl elsewhere
+fn link_modifier(modifiers: &Modifiers) -> bool {
+    if cfg!(target_os = "macos") {
+        modifiers.meta()
+    } else {
+        modifiers.control()
+    }
+}
+
 fn show_completion(
     cmd: &EditCommand,
     doc: &Rope,
@@ -3174,7 +3829,16 @@ pub(crate) fn compute_screen_lines(
             while let Some(change) = changes.next() {
                 match (is_right, change) {
                     (true, DiffLines::Left(range)) => {
-                        if let Some(DiffLines::Right(_)) = changes.peek() {
+                        if diff_info.inline.is_some() {
+                            // The removed lines are painted over this section
+                            let len = range.len();
+                            diff_sections.push(DiffSection {
+                                y_idx,
+                                height: len,
+                                kind: DiffSectionKind::Removed,
+                            });
+                            y_idx += len;
+                        } else if let Some(DiffLines::Right(_)) = changes.peek() {
                         } else {
                             let len = range.len();
                             diff_sections.push(DiffSection {
@@ -3220,7 +3884,7 @@ pub(crate) fn compute_screen_lines(
                         y_idx += height;
 
                         if y_idx < min_vline.get() {
-                            if is_right {
+                            if is_right && diff_info.inline.is_none() {
                                 if let Some(DiffLines::Left(r)) = last_change {
                                     // TODO: count vline count in the other editor since this is skipping an amount dependent on those vlines
                                     let len = r.len() - r.len().min(range.len());
@@ -3277,7 +3941,7 @@ pub(crate) fn compute_screen_lines(
                             }
                         }
 
-                        if is_right {
+                        if is_right && diff_info.inline.is_none() {
                             if let Some(DiffLines::Left(r)) = last_change {
                                 // TODO: count vline count in the other editor since this is skipping an amount dependent on those vlines
                                 let len = r.len() - r.len().min(range.len());
@@ -3371,6 +4035,25 @@ pub(crate) fn compute_screen_lines(
     }
 }
 
+/// The value of a variable of a stopped debug session shown on hover, followed
+/// by the values of its fields
+fn debug_hover_content(
+    variable: &Variable,
+    children: &[Variable],
+    config: &LapceConfig,
+) -> Vec<MarkdownContent> {
+    let variable_text = |variable: &Variable| match &variable.ty {
+        Some(ty) => format!("{}: {ty} = {}", variable.name, variable.value),
+        None => format!("{} = {}", variable.name, variable.value),
+    };
+    let mut text = variable_text(variable);
+    for child in children {
+        text.push_str("\n    ");
+        text.push_str(&variable_text(child));
+    }
+    parse_markdown(&format!("```\n{text}\n```"), 1.5, config)
+}
+
 fn parse_hover_resp(
     hover: lsp_types::Hover,
     config: &LapceConfig,
@@ -0,0 +1,215 @@
+use floem::{
+    reactive::RwSignal,
+    views::{clip, dyn_stack, label, Decorators},
+    View,
+};
+use lapce_rpc::source_control::{BlameHunk, CommitInfo};
+
+use super::EditorData;
+use crate::{app::tooltip_label, config::color::LapceColor};
+
+/// The range of lines of `blame` that contains `line`
+pub fn blame_hunk_at_line(
+    blame: &im::Vector<BlameHunk>,
+    line: usize,
+) -> Option<&BlameHunk> {
+    let index = blame
+        .binary_search_by(|hunk| {
+            if hunk.start_line + hunk.lines <= line {
+                std::cmp::Ordering::Less
+            } else if hunk.start_line > line {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .ok()?;
+    blame.get(index)
+}
+
+/// The short annotation shown in the blame gutter
+pub fn blame_text(commit: Option<&CommitInfo>) -> String {
+    match commit {
+        Some(commit) => {
+            format!(
+                "{} {} {}",
+                commit.short_id(),
+                date(commit.time),
+                commit.author
+            )
+        }
+        None => "Uncommitted changes".to_string(),
+    }
+}
+
+/// The annotation shown at the end of the cursor's line
+pub fn inline_blame_text(commit: Option<&CommitInfo>) -> String {
+    match commit {
+        Some(commit) => format!(
+            "{}, {} • {}",
+            commit.author,
+            relative_time(commit.time, chrono::Utc::now().timestamp()),
+            commit.summary()
+        ),
+        None => "Uncommitted changes".to_string(),
+    }
+}
+
+/// The full details of the commit, shown when hovering its annotation
+pub fn commit_details(commit: Option<&CommitInfo>) -> String {
+    match commit {
+        Some(commit) => format!(
+            "{}\n{}, {}\n\n{}",
+            commit.id,
+            commit.author,
+            date(commit.time),
+            commit.message
+        ),
+        None => "Uncommitted changes".to_string(),
+    }
+}
+
+fn date(time: i64) -> String {
+    chrono::DateTime::from_timestamp(time, 0)
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// How long before `now` the `time` was, both in seconds since the Unix epoch
+pub fn relative_time(time: i64, now: i64) -> String {
+    let seconds = (now - time).max(0);
+    let (count, unit) = match seconds {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86399 => (seconds / 3600, "hour"),
+        86400..=2591999 => (seconds / 86400, "day"),
+        2592000..=31535999 => (seconds / 2592000, "month"),
+        _ => (seconds / 31536000, "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{count} {unit}{plural} ago")
+}
+
+/// The column next to the gutter showing the commit that last changed each range of lines,
+/// on the first visible line of the range
+pub fn editor_blame_view(e_data: RwSignal<EditorData>) -> impl View {
+    let (doc, show_blame, screen_lines, viewport, config) =
+        e_data.with_untracked(|e| {
+            (
+                e.doc_signal(),
+                e.show_blame,
+                e.screen_lines(),
+                e.viewport(),
+                e.common.config,
+            )
+        });
+
+    let each_fn = move || {
+        if !show_blame.get() {
+            return Vec::new();
+        }
+        let blame = doc.get().blame.get();
+        let screen_lines = screen_lines.get();
+
+        let mut rows = Vec::new();
+        let mut last_start = None;
+        for line_info in screen_lines.iter_line_info() {
+            let line = line_info.vline_info.rvline.line;
+            let Some(hunk) = blame_hunk_at_line(&blame, line) else {
+                continue;
+            };
+            if last_start == Some(hunk.start_line) {
+                continue;
+            }
+            last_start = Some(hunk.start_line);
+            rows.push((line, hunk.clone()));
+        }
+        rows
+    };
+
+    let key_fn = |(line, hunk): &(usize, BlameHunk)| {
+        (
+            *line,
+            hunk.start_line,
+            hunk.commit.as_ref().map(|commit| commit.id.clone()),
+        )
+    };
+
+    let view_fn = move |(line, hunk): (usize, BlameHunk)| {
+        let line_y = screen_lines
+            .with_untracked(|s| s.info_for_line(line))
+            .map(|l| l.y)
+            .unwrap_or_default();
+        let text = blame_text(hunk.commit.as_ref());
+        let tooltip = commit_details(hunk.commit.as_ref());
+
+        tooltip_label(
+            config,
+            label(move || text.clone()).style(move |s| {
+                s.text_ellipsis()
+                    .min_width(0.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+                    .selectable(false)
+            }),
+            move || tooltip.clone(),
+        )
+        .style(move |s| {
+            let config = config.get();
+            s.absolute()
+                .width_pct(100.0)
+                .height(config.editor.line_height() as f32)
+                .padding_horiz(10.0)
+                .items_center()
+                .margin_top(line_y as f32 - viewport.get().y0 as f32)
+        })
+    };
+
+    clip(
+        dyn_stack(each_fn, key_fn, view_fn)
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+    )
+    .style(move |s| {
+        let config = config.get();
+        s.width(250.0)
+            .height_pct(100.0)
+            .border_right(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .background(config.color(LapceColor::EDITOR_BACKGROUND))
+            .apply_if(!show_blame.get(), |s| s.hide())
+    })
+    .debug_name("Editor Blame")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_time() {
+        assert_eq!(relative_time(1000, 1030), "just now");
+        assert_eq!(relative_time(0, 60), "1 minute ago");
+        assert_eq!(relative_time(0, 7200), "2 hours ago");
+        assert_eq!(relative_time(0, 86400 * 3), "3 days ago");
+        assert_eq!(relative_time(0, 86400 * 400), "1 year ago");
+    }
+
+    #[test]
+    fn test_blame_hunk_at_line() {
+        let blame: im::Vector<BlameHunk> = [(0, 2), (2, 1), (3, 4)]
+            .into_iter()
+            .map(|(start_line, lines)| BlameHunk {
+                start_line,
+                lines,
+                commit: None,
+            })
+            .collect();
+        assert_eq!(blame_hunk_at_line(&blame, 1).map(|h| h.start_line), Some(0));
+        assert_eq!(blame_hunk_at_line(&blame, 2).map(|h| h.start_line), Some(2));
+        assert_eq!(blame_hunk_at_line(&blame, 6).map(|h| h.start_line), Some(3));
+        assert_eq!(blame_hunk_at_line(&blame, 7), None);
+    }
+}
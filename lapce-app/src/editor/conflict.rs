@@ -0,0 +1,534 @@
+use std::{ops::Range, sync::Arc};
+
+use floem::{
+    event::EventListener,
+    reactive::{create_memo, Memo, ReadSignal, RwSignal},
+    style::CursorStyle,
+    views::{clip, container, dyn_stack, label, scroll, stack, Decorators},
+    View,
+};
+use lapce_xi_rope::Rope;
+
+use super::EditorData;
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+};
+
+const CURRENT_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const INCOMING_MARKER: &str = ">>>>>>>";
+
+/// A region of a buffer with conflicting changes left by a merge, delimited by conflict
+/// markers.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MergeConflict {
+    /// The `<<<<<<<` line, followed by the current changes
+    pub start: usize,
+    /// The `|||||||` line of a diff3 style conflict, followed by the common ancestor
+    pub base: Option<usize>,
+    /// The `=======` line, followed by the incoming changes
+    pub separator: usize,
+    /// The `>>>>>>>` line
+    pub end: usize,
+}
+
+impl MergeConflict {
+    /// All the lines of the conflict, including the markers
+    pub fn lines(&self) -> Range<usize> {
+        self.start..self.end + 1
+    }
+
+    pub fn current(&self) -> Range<usize> {
+        self.start + 1..self.base.unwrap_or(self.separator)
+    }
+
+    pub fn base_lines(&self) -> Option<Range<usize>> {
+        self.base.map(|base| base + 1..self.separator)
+    }
+
+    pub fn incoming(&self) -> Range<usize> {
+        self.separator + 1..self.end
+    }
+
+    /// Whether the markers of the conflict are still on its lines in `text`, which
+    /// they may not be after edits the conflicts weren't looked for again since
+    pub fn is_in(&self, text: &Rope) -> bool {
+        let line_is = |line: usize, marker: &str| {
+            line <= text.line_of_offset(text.len())
+                && text
+                    .lines_raw(text.offset_of_line(line)..text.len())
+                    .next()
+                    .is_some_and(|content| is_marker(&content, marker))
+        };
+        line_is(self.start, CURRENT_MARKER)
+            && self.base.iter().all(|base| line_is(*base, BASE_MARKER))
+            && line_is(self.separator, SEPARATOR_MARKER)
+            && line_is(self.end, INCOMING_MARKER)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Current,
+    Incoming,
+    Both,
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// Find the conflicts in `text`, in order. Unterminated conflicts are ignored.
+pub fn find_conflicts(text: &Rope) -> Vec<MergeConflict> {
+    let mut conflicts = Vec::new();
+    let mut pending: Option<MergeConflict> = None;
+
+    for (line, content) in text.lines_raw(0..text.len()).enumerate() {
+        if is_marker(&content, CURRENT_MARKER) {
+            pending = Some(MergeConflict {
+                start: line,
+                base: None,
+                separator: line,
+                end: line,
+            });
+            continue;
+        }
+        let Some(conflict) = pending.as_mut() else {
+            continue;
+        };
+        let has_separator = conflict.separator != conflict.start;
+        if is_marker(&content, BASE_MARKER) && !has_separator {
+            conflict.base = Some(line);
+        } else if is_marker(&content, SEPARATOR_MARKER) && !has_separator {
+            conflict.separator = line;
+        } else if is_marker(&content, INCOMING_MARKER) && has_separator {
+            conflict.end = line;
+            conflicts.extend(pending.take());
+        }
+    }
+
+    conflicts
+}
+
+/// The text after the marker on `line`, such as the branch name
+pub fn marker_label(text: &Rope, line: usize) -> String {
+    let content = text.lines_raw(text.offset_of_line(line)..text.len()).next();
+    content
+        .map(|content| content.get(CURRENT_MARKER.len()..).unwrap_or("").trim())
+        .unwrap_or("")
+        .to_string()
+}
+
+fn lines_text(text: &Rope, lines: Range<usize>) -> String {
+    text.slice_to_cow(
+        text.offset_of_line(lines.start)..text.offset_of_line(lines.end),
+    )
+    .to_string()
+}
+
+/// The text of the current changes of `conflict`
+pub fn current_text(text: &Rope, conflict: &MergeConflict) -> String {
+    lines_text(text, conflict.current())
+}
+
+/// The text of the common ancestor of `conflict`, if it has one
+pub fn base_text(text: &Rope, conflict: &MergeConflict) -> Option<String> {
+    conflict.base_lines().map(|lines| lines_text(text, lines))
+}
+
+/// The text of the incoming changes of `conflict`
+pub fn incoming_text(text: &Rope, conflict: &MergeConflict) -> String {
+    lines_text(text, conflict.incoming())
+}
+
+/// The text that replaces the whole of `conflict` once it's resolved with `resolution`
+pub fn resolved_text(
+    text: &Rope,
+    conflict: &MergeConflict,
+    resolution: ConflictResolution,
+) -> String {
+    match resolution {
+        ConflictResolution::Current => current_text(text, conflict),
+        ConflictResolution::Incoming => incoming_text(text, conflict),
+        ConflictResolution::Both => {
+            current_text(text, conflict) + &incoming_text(text, conflict)
+        }
+    }
+}
+
+fn conflict_action(
+    text: &'static str,
+    on_click: impl Fn() + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(move || text.to_string())
+        .on_click_stop(move |_| on_click())
+        .style(move |s| {
+            let config = config.get();
+            let hover_color = config.color(LapceColor::EDITOR_LINK);
+            s.padding_horiz(6.0)
+                .color(config.color(LapceColor::EDITOR_DIM))
+                .selectable(false)
+                .hover(|s| s.cursor(CursorStyle::Pointer).color(hover_color))
+        })
+}
+
+/// The actions to resolve each merge conflict, shown on the line of its `<<<<<<<` marker
+pub fn editor_conflicts_view(e_data: RwSignal<EditorData>) -> impl View {
+    let (doc, screen_lines, viewport, config) = e_data.with_untracked(|e| {
+        (
+            e.doc_signal(),
+            e.screen_lines(),
+            e.viewport(),
+            e.common.config,
+        )
+    });
+
+    let each_fn = move || {
+        let screen_lines = screen_lines.get();
+        doc.get().conflicts.with(|conflicts| {
+            conflicts
+                .iter()
+                .filter(|conflict| {
+                    screen_lines.info_for_line(conflict.start).is_some()
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let view_fn = move |conflict: MergeConflict| {
+        let line_y = screen_lines
+            .with_untracked(|s| s.info_for_line(conflict.start))
+            .map(|l| l.y)
+            .unwrap_or_default();
+        let compare = {
+            let conflict = conflict.clone();
+            move || {
+                e_data
+                    .get_untracked()
+                    .toggle_conflict_peek(conflict.clone())
+            }
+        };
+        let resolve = move |resolution| {
+            let conflict = conflict.clone();
+            move || {
+                e_data
+                    .get_untracked()
+                    .resolve_conflict(&conflict, resolution)
+            }
+        };
+
+        stack((
+            conflict_action(
+                "Accept Current Change",
+                resolve(ConflictResolution::Current),
+                config,
+            ),
+            conflict_action(
+                "Accept Incoming Change",
+                resolve(ConflictResolution::Incoming),
+                config,
+            ),
+            conflict_action(
+                "Accept Both Changes",
+                resolve(ConflictResolution::Both),
+                config,
+            ),
+            conflict_action("Compare Changes", compare, config),
+        ))
+        .style(move |s| {
+            let config = config.get();
+            s.absolute()
+                .height(config.editor.line_height() as f32)
+                .padding_horiz(6.0)
+                .items_center()
+                .inset_right(20.0)
+                .margin_top(line_y as f32 - viewport.get().y0 as f32)
+                .background(config.color(LapceColor::EDITOR_BACKGROUND))
+                .font_size((config.ui.font_size() as f32 - 1.0).max(1.0))
+        })
+    };
+
+    clip(
+        dyn_stack(each_fn, |conflict| conflict.clone(), view_fn)
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+    )
+    .style(|s| s.absolute().size_pct(100.0, 100.0))
+    .debug_name("Editor Conflicts")
+}
+
+/// One side of the merge conflict being compared, with a button to resolve the conflict
+/// with it
+fn conflict_side(
+    editor: RwSignal<EditorData>,
+    peek: Memo<Option<MergeConflict>>,
+    title: fn(&Rope, &MergeConflict) -> String,
+    text: fn(&Rope, &MergeConflict) -> Option<String>,
+    resolution: Option<ConflictResolution>,
+    color: &'static str,
+) -> impl View {
+    let (doc, config) =
+        editor.with_untracked(|editor| (editor.doc_signal(), editor.common.config));
+    let content = move || {
+        let conflict = peek.get()?;
+        doc.get()
+            .buffer
+            .with_untracked(|buffer| text(buffer.text(), &conflict))
+    };
+    let title = move || {
+        let conflict = peek.get()?;
+        doc.get()
+            .buffer
+            .with_untracked(|buffer| Some(title(buffer.text(), &conflict)))
+    };
+    let is_shown = move || content().is_some();
+
+    stack((
+        stack((
+            label(move || title().unwrap_or_default())
+                .style(|s| s.flex_grow(1.0).min_width(0.0).text_ellipsis()),
+            conflict_action(
+                "Accept",
+                move || {
+                    if let (Some(conflict), Some(resolution)) =
+                        (peek.get_untracked(), resolution)
+                    {
+                        editor
+                            .get_untracked()
+                            .resolve_conflict(&conflict, resolution);
+                    }
+                },
+                config,
+            )
+            .style(move |s| s.apply_if(resolution.is_none(), |s| s.hide())),
+        ))
+        .style(|s| s.width_full().items_center().padding_horiz(10.0)),
+        scroll(
+            label(move || {
+                content()
+                    .unwrap_or_default()
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string()
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.padding_horiz(10.0)
+                    .font_family(config.editor.font_family.clone())
+                    .font_size(config.editor.font_size() as f32)
+                    .color(config.color(color))
+            }),
+        )
+        .style(move |s| {
+            let config = config.get();
+            s.width_full()
+                .margin_top(4.0)
+                .max_height(config.editor.line_height() as f32 * 10.0)
+        }),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.flex_col()
+            .flex_basis(0.0)
+            .flex_grow(1.0)
+            .min_width(0.0)
+            .border_left(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .apply_if(!is_shown(), |s| s.hide())
+    })
+}
+
+/// The current, common ancestor and incoming versions of a merge conflict side by side,
+/// shown below the conflict
+pub fn conflict_peek_view(editor: RwSignal<EditorData>) -> impl View {
+    let (ed, doc, conflict_peek, config) = editor.with_untracked(|editor| {
+        (
+            editor.editor.clone(),
+            editor.doc_signal(),
+            editor.conflict_peek,
+            editor.common.config,
+        )
+    });
+    let viewport = ed.viewport;
+
+    // Hidden once an edit changes the conflict
+    let peek = create_memo(move |_| {
+        let conflict = conflict_peek.get()?;
+        doc.get()
+            .conflicts
+            .with(|conflicts| conflicts.contains(&conflict))
+            .then_some(conflict)
+    });
+
+    container(
+        stack((
+            stack((
+                label(|| "Merge Conflict".to_string())
+                    .style(|s| s.flex_grow(1.0).padding_horiz(10.0)),
+                conflict_action(
+                    "Accept Both",
+                    move || {
+                        if let Some(conflict) = peek.get_untracked() {
+                            editor.get_untracked().resolve_conflict(
+                                &conflict,
+                                ConflictResolution::Both,
+                            );
+                        }
+                    },
+                    config,
+                ),
+                clickable_icon(
+                    || LapceIcons::CLOSE,
+                    move || {
+                        conflict_peek.set(None);
+                    },
+                    || false,
+                    || false,
+                    || "Close",
+                    config,
+                )
+                .style(|s| s.padding_horiz(6.0)),
+            ))
+            .style(|s| s.width_full().items_center()),
+            stack((
+                conflict_side(
+                    editor,
+                    peek,
+                    |text, conflict| {
+                        format!("Current: {}", marker_label(text, conflict.start))
+                    },
+                    |text, conflict| Some(current_text(text, conflict)),
+                    Some(ConflictResolution::Current),
+                    LapceColor::SOURCE_CONTROL_ADDED,
+                ),
+                conflict_side(
+                    editor,
+                    peek,
+                    |text, conflict| {
+                        let base = conflict.base.unwrap_or(conflict.start);
+                        format!("Common Ancestor: {}", marker_label(text, base))
+                    },
+                    base_text,
+                    None,
+                    LapceColor::EDITOR_DIM,
+                ),
+                conflict_side(
+                    editor,
+                    peek,
+                    |text, conflict| {
+                        format!("Incoming: {}", marker_label(text, conflict.end))
+                    },
+                    |text, conflict| Some(incoming_text(text, conflict)),
+                    Some(ConflictResolution::Incoming),
+                    LapceColor::SOURCE_CONTROL_MODIFIED,
+                ),
+            ))
+            .style(|s| s.width_full().margin_top(4.0)),
+        ))
+        .style(move |s| {
+            let config = config.get();
+            s.width_full()
+                .flex_col()
+                .padding_vert(4.0)
+                .background(config.color(LapceColor::PANEL_BACKGROUND))
+                .border_top(1.0)
+                .border_bottom(1.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .cursor(CursorStyle::Default)
+        })
+        // Keep clicks from going through to the editor below
+        .on_event_stop(EventListener::PointerDown, |_| {}),
+    )
+    .style(move |s| {
+        let Some(conflict) = peek.get() else {
+            return s.hide();
+        };
+
+        let line_height = config.get().editor.line_height() as f64;
+        let line = conflict.end.min(ed.last_line());
+        let y = (ed.vline_of_line(line).get() + 1) as f64 * line_height;
+
+        s.absolute()
+            .width_pct(100.0)
+            .margin_top((y - viewport.get().y0) as f32)
+    })
+    .debug_name("Conflict Peek")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "a
+<<<<<<< HEAD
+b
+=======
+c
+d
+>>>>>>> feature
+e
+<<<<<<< HEAD
+f
+||||||| base
+g
+=======
+h
+>>>>>>> feature
+<<<<<<< unterminated
+";
+
+    #[test]
+    fn test_find_conflicts() {
+        let conflicts = find_conflicts(&Rope::from(TEXT));
+        assert_eq!(
+            conflicts,
+            vec![
+                MergeConflict {
+                    start: 1,
+                    base: None,
+                    separator: 3,
+                    end: 6
+                },
+                MergeConflict {
+                    start: 8,
+                    base: Some(10),
+                    separator: 12,
+                    end: 14
+                },
+            ]
+        );
+        assert_eq!(conflicts[0].current(), 2..3);
+        assert_eq!(conflicts[0].incoming(), 4..6);
+        assert_eq!(conflicts[1].current(), 9..10);
+        assert_eq!(conflicts[1].base_lines(), Some(11..12));
+    }
+
+    #[test]
+    fn test_resolved_text() {
+        let text = Rope::from(TEXT);
+        let conflicts = find_conflicts(&text);
+        let resolve = |index: usize, resolution| {
+            resolved_text(&text, &conflicts[index], resolution)
+        };
+        assert_eq!(resolve(0, ConflictResolution::Current), "b\n");
+        assert_eq!(resolve(0, ConflictResolution::Incoming), "c\nd\n");
+        assert_eq!(resolve(0, ConflictResolution::Both), "b\nc\nd\n");
+        assert_eq!(resolve(1, ConflictResolution::Current), "f\n");
+        assert_eq!(base_text(&text, &conflicts[1]).as_deref(), Some("g\n"));
+        assert_eq!(marker_label(&text, 6), "feature");
+    }
+
+    #[test]
+    fn test_conflict_is_in() {
+        let text = Rope::from(TEXT);
+        let conflicts = find_conflicts(&text);
+        assert!(conflicts.iter().all(|conflict| conflict.is_in(&text)));
+
+        let edited = Rope::from(format!("x\n{TEXT}"));
+        assert!(!conflicts[0].is_in(&edited));
+        assert!(!conflicts[1].is_in(&Rope::from("a\n")));
+    }
+}
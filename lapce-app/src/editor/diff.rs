@@ -1,4 +1,4 @@
-use std::{rc::Rc, sync::atomic};
+use std::{collections::HashMap, ops::Range, rc::Rc, sync::atomic};
 
 use floem::{
     event::EventListener,
@@ -12,7 +12,7 @@
 };
 use lapce_core::buffer::{
     diff::{expand_diff_lines, rope_diff, DiffExpand, DiffLines},
-    rope_text::RopeText,
+    rope_text::{RopeText, RopeTextRef},
 };
 use lapce_rpc::{buffer::BufferId, proxy::ProxyResponse};
 use lapce_xi_rope::Rope;
@@ -29,10 +29,25 @@
 
 use super::{EditorData, EditorViewKind};
 
+/// Modified lines with more words than this aren't diffed word by word
+const MAX_WORD_DIFF_WORDS: usize = 500;
+/// Only this many lines of a modification are diffed word by word
+const MAX_WORD_DIFF_LINES: usize = 500;
+
+/// The byte ranges within each modified line that differ from the other side, by line
+pub type WordChanges = HashMap<usize, Vec<Range<usize>>>;
+
+/// The line changes between the two sides, and the word changes of each side
+type DiffResult = (Vec<DiffLines>, (WordChanges, WordChanges));
+
 #[derive(Clone)]
 pub struct DiffInfo {
     pub is_right: bool,
     pub changes: Vec<DiffLines>,
+    pub word_changes: Rc<WordChanges>,
+    /// The text of the left side, when its removed lines are shown inline in the right
+    /// editor rather than side by side
+    pub inline: Option<Rope>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -82,12 +97,31 @@ pub fn to_data(
                                 doc.init_content(Rope::from(content));
                             }
                         });
-                        common.proxy.get_buffer_head(
-                            history.path.clone(),
-                            move |result| {
-                                send(result);
-                            },
-                        );
+                        if history.version == "head" {
+                            common.proxy.get_buffer_head(
+                                history.path.clone(),
+                                move |result| {
+                                    send(result);
+                                },
+                            );
+                        } else if history.version == "saved" {
+                            common.proxy.get_buffer_saved(
+                                history.path.clone(),
+                                move |result| {
+                                    send(result);
+                                },
+                            );
+                        } else if history.version == "clipboard" {
+                            // The clipboard content of the comparison isn't kept
+                        } else {
+                            common.proxy.git_file_at_revision(
+                                history.path.clone(),
+                                history.version.clone(),
+                                move |result| {
+                                    send(result);
+                                },
+                            );
+                        }
                     }
 
                     doc
@@ -142,6 +176,8 @@ pub struct DiffEditorData {
     pub right: EditorData,
     pub confirmed: RwSignal<bool>,
     pub focus_right: RwSignal<bool>,
+    /// Whether the changes are shown in a single editor rather than side by side
+    pub inline: RwSignal<bool>,
 }
 
 impl DiffEditorData {
@@ -177,6 +213,7 @@ pub fn new(
             right,
             confirmed,
             focus_right: cx.create_rw_signal(true),
+            inline: cx.create_rw_signal(false),
         };
 
         data.listen_diff_changes();
@@ -218,6 +255,7 @@ pub fn copy(
             id: diff_editor_id,
             editor_tab_id: cx.create_rw_signal(editor_tab_id),
             focus_right: cx.create_rw_signal(true),
+            inline: cx.create_rw_signal(self.inline.get_untracked()),
             left,
             right,
             confirmed,
@@ -227,8 +265,15 @@ pub fn copy(
         diff_editor
     }
 
+    pub fn toggle_inline(&self) {
+        self.inline.update(|inline| *inline = !*inline);
+        // The right editor is the only one shown inline
+        self.focus_right.set(true);
+    }
+
     fn listen_diff_changes(&self) {
         let cx = self.scope;
+        let inline = self.inline;
 
         let left = self.left.clone();
         let left_doc_rev = {
@@ -263,12 +308,12 @@ fn listen_diff_changes(&self) {
                     (buffer.atomic_rev(), buffer.text().clone())
                 });
 
+            let inline = inline.get().then(|| left_rope.clone());
+
             let send = {
                 let right_atomic_rev = right_atomic_rev.clone();
-                create_ext_action(cx, move |changes: Option<Vec<DiffLines>>| {
-                    let changes = if let Some(changes) = changes {
-                        changes
-                    } else {
+                create_ext_action(cx, move |diff: Option<DiffResult>| {
+                    let Some((changes, (left_words, right_words))) = diff else {
                         return;
                     };
 
@@ -284,28 +329,168 @@ fn listen_diff_changes(&self) {
                     left_editor_view.set(EditorViewKind::Diff(DiffInfo {
                         is_right: false,
                         changes: changes.clone(),
+                        word_changes: Rc::new(left_words),
+                        inline: None,
                     }));
                     right_editor_view.set(EditorViewKind::Diff(DiffInfo {
                         is_right: true,
                         changes,
+                        word_changes: Rc::new(right_words),
+                        inline,
                     }));
                 })
             };
 
             rayon::spawn(move || {
-                let changes = rope_diff(
-                    left_rope,
-                    right_rope,
+                let diff = rope_diff(
+                    left_rope.clone(),
+                    right_rope.clone(),
                     right_rev,
                     right_atomic_rev.clone(),
                     Some(3),
-                );
-                send(changes);
+                )
+                .map(|changes| {
+                    let words = word_changes(&left_rope, &right_rope, &changes);
+                    (changes, words)
+                });
+                send(diff);
             });
         });
     }
 }
 
+/// The changed words of the lines that were modified between `left` and `right`, for
+/// each side
+fn word_changes(
+    left: &Rope,
+    right: &Rope,
+    changes: &[DiffLines],
+) -> (WordChanges, WordChanges) {
+    let left = RopeTextRef::new(left);
+    let right = RopeTextRef::new(right);
+    let mut left_words = WordChanges::new();
+    let mut right_words = WordChanges::new();
+
+    for (change, next) in changes.iter().zip(changes.iter().skip(1)) {
+        // Removed lines directly followed by added lines are modified lines
+        let (DiffLines::Left(left_range), DiffLines::Right(right_range)) =
+            (change, next)
+        else {
+            continue;
+        };
+
+        let lines = left_range
+            .clone()
+            .zip(right_range.clone())
+            .take(MAX_WORD_DIFF_LINES);
+        for (left_line, right_line) in lines {
+            let left_content = left.line_content(left_line);
+            let right_content = right.line_content(right_line);
+            if let Some((left_ranges, right_ranges)) = word_diff(
+                left_content.trim_end_matches(['\n', '\r']),
+                right_content.trim_end_matches(['\n', '\r']),
+            ) {
+                left_words.insert(left_line, left_ranges);
+                right_words.insert(right_line, right_ranges);
+            }
+        }
+    }
+
+    (left_words, right_words)
+}
+
+/// Split a line into words, runs of whitespace and single other characters
+fn word_ranges(line: &str) -> Vec<Range<usize>> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut last_class = None;
+    for (i, c) in line.char_indices() {
+        let current = class(c);
+        match ranges.last_mut() {
+            Some(range) if last_class == Some(current) && current != 2 => {
+                range.end = i + c.len_utf8();
+            }
+            _ => ranges.push(i..i + c.len_utf8()),
+        }
+        last_class = Some(current);
+    }
+    ranges
+}
+
+/// The byte ranges of the words that differ between the `left` and `right` versions of a
+/// line, or `None` if the lines have nothing in common
+pub fn word_diff(
+    left: &str,
+    right: &str,
+) -> Option<(Vec<Range<usize>>, Vec<Range<usize>>)> {
+    let left_words = word_ranges(left);
+    let right_words = word_ranges(right);
+    let (n, m) = (left_words.len(), right_words.len());
+    if n > MAX_WORD_DIFF_WORDS || m > MAX_WORD_DIFF_WORDS {
+        return None;
+    }
+
+    let equal = |i: usize, j: usize| {
+        left[left_words[i].clone()] == right[right_words[j].clone()]
+    };
+
+    // The length of the longest common subsequence of the words from `i` and `j` on
+    let mut lcs = vec![0usize; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[at(i, j)] = if equal(i, j) {
+                lcs[at(i + 1, j + 1)] + 1
+            } else {
+                lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+            };
+        }
+    }
+    if lcs[at(0, 0)] == 0 {
+        return None;
+    }
+
+    fn push(ranges: &mut Vec<Range<usize>>, range: &Range<usize>) {
+        match ranges.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => ranges.push(range.clone()),
+        }
+    }
+
+    let mut left_changes = Vec::new();
+    let mut right_changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if equal(i, j) {
+            i += 1;
+            j += 1;
+        } else if lcs[at(i + 1, j)] >= lcs[at(i, j + 1)] {
+            push(&mut left_changes, &left_words[i]);
+            i += 1;
+        } else {
+            push(&mut right_changes, &right_words[j]);
+            j += 1;
+        }
+    }
+    for range in &left_words[i..] {
+        push(&mut left_changes, range);
+    }
+    for range in &right_words[j..] {
+        push(&mut right_changes, range);
+    }
+
+    Some((left_changes, right_changes))
+}
+
 struct DiffShowMoreSection {
     left_actual_line: usize,
     right_actual_line: usize,
@@ -339,7 +524,9 @@ pub fn diff_show_more_section_view(
             while let Some(change) = changes.next() {
                 match change {
                     DiffLines::Left(range) => {
-                        if let Some(DiffLines::Right(_)) = changes.peek() {
+                        if let (None, Some(DiffLines::Right(_))) =
+                            (&diff_info.inline, changes.peek())
+                        {
                         } else {
                             let len = range.len();
                             visual_line += len;
@@ -349,7 +536,9 @@ pub fn diff_show_more_section_view(
                         let len = range.len();
                         visual_line += len;
 
-                        if let Some(DiffLines::Left(r)) = last_change {
+                        if let (None, Some(DiffLines::Left(r))) =
+                            (&diff_info.inline, last_change)
+                        {
                             let len = r.len() - r.len().min(range.len());
                             if len > 0 {
                                 visual_line += len;
@@ -546,3 +735,29 @@ pub fn diff_show_more_section_view(
     .style(|s| s.absolute().flex_col().size_pct(100.0, 100.0))
     .debug_name("Diff Show More Section")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_diff() {
+        assert_eq!(
+            word_diff("let foo = bar(1);", "let foo = baz(1, 2);"),
+            Some((vec![10..13], vec![10..13, 15..18]))
+        );
+        assert_eq!(
+            word_diff("fn main() {}", "fn main() {}"),
+            Some((vec![], vec![]))
+        );
+        assert_eq!(word_diff("abc", "xyz"), None);
+    }
+
+    #[test]
+    fn test_word_diff_unicode() {
+        assert_eq!(
+            word_diff("let ü = 1;", "let ö = 1;"),
+            Some((vec![4..6], vec![4..6]))
+        );
+    }
+}
@@ -6,7 +6,10 @@
 };
 use lapce_core::{buffer::rope_text::RopeText, mode::Mode};
 
-use crate::config::{color::LapceColor, LapceConfig};
+use crate::{
+    config::{color::LapceColor, LapceConfig},
+    layout_cache::TextLayoutCache,
+};
 
 use super::{view::changes_colors_screen, EditorData};
 
@@ -14,6 +17,8 @@ pub struct EditorGutterView {
     id: ViewId,
     editor: EditorData,
     width: f64,
+    /// The line numbers, and whether they're the current line's
+    text_layouts: TextLayoutCache<(String, bool)>,
 }
 
 pub fn editor_gutter_view(editor: EditorData) -> EditorGutterView {
@@ -23,6 +28,7 @@ pub fn editor_gutter_view(editor: EditorData) -> EditorGutterView {
         id,
         editor,
         width: 0.0,
+        text_layouts: TextLayoutCache::default(),
     }
 }
 
@@ -165,12 +171,21 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
                 }
                 .to_string();
 
-                let mut text_layout = TextLayout::new();
-                if line == current_line {
-                    text_layout.set_text(&text, current_line_attrs_list.clone());
-                } else {
-                    text_layout.set_text(&text, attrs_list.clone());
-                }
+                let is_current = line == current_line;
+                let text_layout = self.text_layouts.get_or_build(
+                    config.id,
+                    (text.clone(), is_current),
+                    || {
+                        let mut text_layout = TextLayout::new();
+                        if is_current {
+                            text_layout
+                                .set_text(&text, current_line_attrs_list.clone());
+                        } else {
+                            text_layout.set_text(&text, attrs_list.clone());
+                        }
+                        text_layout
+                    },
+                );
                 let size = text_layout.size();
                 let height = size.height;
 
@@ -0,0 +1,188 @@
+use std::ops::Range;
+
+use lapce_core::buffer::diff::DiffLines;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffHunkKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// A contiguous change between the `head` version of a file and the buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffHunk {
+    /// The lines in the `head` version that were removed or modified
+    pub left: Range<usize>,
+    /// The lines in the buffer that were added or modified.
+    /// Empty for a deletion, in which case `start` is the line the removed lines were
+    /// before.
+    pub right: Range<usize>,
+}
+
+impl DiffHunk {
+    pub fn kind(&self) -> DiffHunkKind {
+        if self.left.is_empty() {
+            DiffHunkKind::Added
+        } else if self.right.is_empty() {
+            DiffHunkKind::Deleted
+        } else {
+            DiffHunkKind::Modified
+        }
+    }
+
+    /// Whether the hunk's marker in the gutter is on the buffer line `line`
+    pub fn is_on_line(&self, line: usize) -> bool {
+        if self.right.is_empty() {
+            // The marker for a deletion sits between the two lines around it
+            line == self.right.start || line + 1 == self.right.start
+        } else {
+            self.right.contains(&line)
+        }
+    }
+}
+
+/// Group the line changes between the `head` version and the buffer into hunks, in order
+pub fn diff_hunks<'a>(
+    changes: impl IntoIterator<Item = &'a DiffLines>,
+) -> Vec<DiffHunk> {
+    let mut hunks = Vec::new();
+    let mut pending: Option<DiffHunk> = None;
+    let mut left_line = 0;
+    let mut right_line = 0;
+
+    for change in changes {
+        match change {
+            DiffLines::Both(info) => {
+                hunks.extend(pending.take());
+                left_line = info.left.end;
+                right_line = info.right.end;
+            }
+            DiffLines::Left(range) => {
+                hunks.extend(pending.take());
+                pending = Some(DiffHunk {
+                    left: range.clone(),
+                    right: right_line..right_line,
+                });
+                left_line = range.end;
+            }
+            DiffLines::Right(range) => {
+                match pending.as_mut() {
+                    // Removed lines directly followed by added lines are a modification
+                    Some(hunk)
+                        if hunk.right.is_empty()
+                            && hunk.right.start == range.start =>
+                    {
+                        hunk.right = range.clone();
+                    }
+                    _ => {
+                        hunks.extend(pending.take());
+                        pending = Some(DiffHunk {
+                            left: left_line..left_line,
+                            right: range.clone(),
+                        });
+                    }
+                }
+                right_line = range.end;
+            }
+        }
+    }
+    hunks.extend(pending);
+
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_core::buffer::diff::DiffBothInfo;
+
+    use super::*;
+
+    fn both(left: Range<usize>, right: Range<usize>) -> DiffLines {
+        DiffLines::Both(DiffBothInfo {
+            left,
+            right,
+            skip: None,
+        })
+    }
+
+    #[test]
+    fn test_diff_hunks() {
+        let changes = [
+            both(0..2, 0..2),
+            DiffLines::Left(2..4),
+            DiffLines::Right(2..3),
+            both(4..6, 3..5),
+            DiffLines::Right(5..7),
+            both(6..8, 7..9),
+            DiffLines::Left(8..9),
+            both(9..10, 9..10),
+        ];
+        let hunks = diff_hunks(&changes);
+        assert_eq!(
+            hunks,
+            vec![
+                DiffHunk {
+                    left: 2..4,
+                    right: 2..3
+                },
+                DiffHunk {
+                    left: 6..6,
+                    right: 5..7
+                },
+                DiffHunk {
+                    left: 8..9,
+                    right: 9..9
+                },
+            ]
+        );
+        assert_eq!(
+            hunks.iter().map(DiffHunk::kind).collect::<Vec<_>>(),
+            vec![
+                DiffHunkKind::Modified,
+                DiffHunkKind::Added,
+                DiffHunkKind::Deleted
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_hunks_at_edges() {
+        let changes = [
+            DiffLines::Right(0..1),
+            both(0..3, 1..4),
+            DiffLines::Left(3..5),
+        ];
+        assert_eq!(
+            diff_hunks(&changes),
+            vec![
+                DiffHunk {
+                    left: 0..0,
+                    right: 0..1
+                },
+                DiffHunk {
+                    left: 3..5,
+                    right: 4..4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hunk_is_on_line() {
+        let deleted = DiffHunk {
+            left: 3..5,
+            right: 4..4,
+        };
+        assert!(deleted.is_on_line(3));
+        assert!(deleted.is_on_line(4));
+        assert!(!deleted.is_on_line(5));
+
+        let added = DiffHunk {
+            left: 0..0,
+            right: 0..2,
+        };
+        assert!(added.is_on_line(1));
+        assert!(!added.is_on_line(2));
+    }
+}
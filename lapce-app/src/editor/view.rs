@@ -1,8 +1,12 @@
-use std::{cmp, path::PathBuf, rc::Rc, sync::Arc};
+use std::{cmp, path::PathBuf, rc::Rc, sync::Arc, time::Duration};
 
 use floem::{
-    action::{set_ime_allowed, set_ime_cursor_area},
+    action::{
+        exec_after, set_ime_allowed, set_ime_cursor_area, show_context_menu,
+        TimerToken,
+    },
     context::{PaintCx, StyleCx},
+    cosmic_text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     event::{Event, EventListener, EventPropagation},
     keyboard::Modifiers,
     peniko::{
@@ -37,19 +41,35 @@
 };
 use itertools::Itertools;
 use lapce_core::{
-    buffer::{diff::DiffLines, rope_text::RopeText, Buffer},
+    buffer::{
+        diff::DiffLines,
+        rope_text::{RopeText, RopeTextRef, RopeTextVal},
+        Buffer,
+    },
     cursor::{CursorAffinity, CursorMode},
+    language::LapceLanguage,
 };
-use lapce_rpc::dap_types::{DapId, SourceBreakpoint};
 use lapce_xi_rope::find::CaseMatching;
 
-use super::{gutter::editor_gutter_view, DocSignal, EditorData};
+use super::{
+    blame::editor_blame_view,
+    conflict::{conflict_peek_view, editor_conflicts_view},
+    gutter::editor_gutter_view,
+    hunk::{diff_hunks, DiffHunkKind},
+    DocSignal, EditorData, EditorViewKind,
+};
 use crate::{
     app::clickable_icon,
     command::InternalCommand,
-    config::{color::LapceColor, editor::WrapStyle, icon::LapceIcons, LapceConfig},
-    debug::LapceBreakpoint,
-    doc::DocContent,
+    config::{
+        color::LapceColor,
+        editor::{CaretStyle, WrapStyle},
+        icon::LapceIcons,
+        LapceConfig,
+    },
+    doc::{caret_on_screen, DiskState, DocContent},
+    layout_cache::TextLayoutCache,
+    panel::debug_view::breakpoint_menu,
     text_input::TextInputBuilder,
     window_tab::{Focus, WindowTabData},
     workspace::LapceWorkspace,
@@ -61,11 +81,11 @@ struct StickyHeaderInfo {
     y_diff: f64,
 }
 
-fn editor_wrap(config: &LapceConfig) -> WrapMethod {
+fn editor_wrap(config: &LapceConfig, language: LapceLanguage) -> WrapMethod {
     /// Minimum width that we'll allow the view to be wrapped at.
     const MIN_WRAPPED_WIDTH: f32 = 100.0;
 
-    match config.editor.wrap_style {
+    match config.wrap_style(language) {
         WrapStyle::None => WrapMethod::None,
         WrapStyle::EditorWidth => WrapMethod::EditorWidth,
         WrapStyle::WrapWidth => WrapMethod::WrapWidth {
@@ -81,12 +101,14 @@ pub fn editor_style(
 ) -> Style {
     let config = config.get();
     let doc = doc.get();
+    let language = doc.syntax.with(|syntax| syntax.language);
 
     s.set(
         IndentStyleProp,
         doc.buffer.with_untracked(Buffer::indent_style),
     )
-    .set(CursorColor, config.color(LapceColor::EDITOR_CARET))
+    // The caret is painted by `paint_cursor`, in the shape the settings ask for
+    .set(CursorColor, Color::TRANSPARENT)
     .set(SelectionColor, config.color(LapceColor::EDITOR_SELECTION))
     .set(
         CurrentLineColor,
@@ -116,7 +138,7 @@ pub fn editor_style(
         config.editor.modal_mode_relative_line_numbers,
     )
     .set(SmartTab, config.editor.smart_tab)
-    .set(WrapProp, editor_wrap(&config))
+    .set(WrapProp, editor_wrap(&config, language))
     .set(
         CursorSurroundingLines,
         config.editor.cursor_surrounding_lines,
@@ -132,6 +154,16 @@ pub struct EditorView {
     viewport: RwSignal<Rect>,
     debug_breakline: Memo<Option<(usize, PathBuf)>>,
     sticky_header_info: StickyHeaderInfo,
+    text_layouts: TextLayoutCache<TextLayoutKey>,
+}
+
+/// The text the editor view lays out itself, rather than as a line of the document
+#[derive(PartialEq, Eq, Hash)]
+enum TextLayoutKey {
+    /// The width of a character, which the rulers are placed by
+    CharWidth,
+    /// A line removed from the left side of a diff shown inline
+    RemovedLine(String),
 }
 
 pub fn editor_view(
@@ -154,13 +186,48 @@ pub fn editor_view(
     });
 
     let hide_cursor = e_data.common.window_common.hide_cursor;
+    let hovered_link = e_data.hovered_link;
     create_effect(move |_| {
         hide_cursor.track();
         let occurrences = doc.with(|doc| doc.find_result.occurrences);
         occurrences.track();
+        doc.with(|doc| doc.symbol_occurrences).track();
+        hovered_link.track();
         id.request_paint();
     });
 
+    // The caret stops blinking while it's scrolled out of view, so start it again
+    // when it's scrolled back to
+    let cursor = e_data.cursor();
+    let editor = e_data.clone();
+    create_effect(move |was_on_screen| {
+        screen_lines.track();
+        let buffer = doc.with_untracked(|doc| doc.buffer);
+        let on_screen = caret_on_screen(buffer, cursor, screen_lines);
+        if on_screen && was_on_screen == Some(false) && is_active.get_untracked() {
+            editor.editor.cursor_info.reset();
+        }
+        on_screen
+    });
+
+    // Ask for the occurrences of the symbol at the cursor once it rests on it
+    let symbol_occurrences_timer = create_rw_signal(TimerToken::INVALID);
+    let editor = e_data.clone();
+    create_effect(move |_| {
+        cursor.track();
+        if !is_active.get() {
+            symbol_occurrences_timer.set(TimerToken::INVALID);
+            return;
+        }
+        let editor = editor.clone();
+        let token = exec_after(Duration::from_millis(250), move |token| {
+            if symbol_occurrences_timer.try_get_untracked() == Some(token) {
+                editor.get_symbol_occurrences();
+            }
+        });
+        symbol_occurrences_timer.set(token);
+    });
+
     create_effect(move |last_rev| {
         let buffer = doc.with(|doc| doc.buffer);
         let rev = buffer.with(|buffer| buffer.rev());
@@ -242,6 +309,19 @@ pub fn editor_view(
         }
     });
 
+    let editor_doc = e_data.doc_signal();
+    create_effect(move |_| {
+        let inline_blame = config.with(|config| config.editor.inline_blame);
+        let line = (inline_blame && is_active.get()).then(|| {
+            let offset = cursor.with(|c| c.offset());
+            editor_doc
+                .with(|doc| doc.buffer.with(|buffer| buffer.line_of_offset(offset)))
+        });
+        if line.is_some() || is_active.get_untracked() {
+            editor_doc.get_untracked().set_inline_blame_line(line);
+        }
+    });
+
     let doc = e_data.doc_signal();
     EditorView {
         id,
@@ -255,6 +335,7 @@ pub fn editor_view(
             last_sticky_should_scroll: false,
             y_diff: 0.0,
         },
+        text_layouts: TextLayoutCache::default(),
     }
     .on_event(EventListener::ImePreedit, move |event| {
         if !is_active.get_untracked() {
@@ -346,6 +427,183 @@ fn paint_diff_sections(
         }
     }
 
+    /// Highlight the sides of each merge conflict, with their markers stronger
+    fn paint_conflicts(
+        &self,
+        cx: &mut PaintCx,
+        viewport: Rect,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        let conflicts = self.editor.doc().conflicts.get_untracked();
+        if conflicts.is_empty() {
+            return;
+        }
+
+        let line_height = config.editor.line_height() as f64;
+        let current = config.color(LapceColor::SOURCE_CONTROL_ADDED);
+        let incoming = config.color(LapceColor::SOURCE_CONTROL_MODIFIED);
+        let base = config.color(LapceColor::EDITOR_DIM);
+
+        for line_info in screen_lines.iter_line_info() {
+            let line = line_info.vline_info.rvline.line;
+            let Some(conflict) =
+                conflicts.iter().find(|c| c.lines().contains(&line))
+            else {
+                continue;
+            };
+
+            let color = if line == conflict.start {
+                current.with_alpha_factor(0.4)
+            } else if conflict.current().contains(&line) {
+                current.with_alpha_factor(0.15)
+            } else if conflict.base == Some(line) {
+                base.with_alpha_factor(0.4)
+            } else if conflict.base_lines().is_some_and(|l| l.contains(&line)) {
+                base.with_alpha_factor(0.15)
+            } else if conflict.incoming().contains(&line) {
+                incoming.with_alpha_factor(0.15)
+            } else if line == conflict.end {
+                incoming.with_alpha_factor(0.4)
+            } else {
+                // The `=======` separator
+                continue;
+            };
+
+            cx.fill(
+                &Size::new(viewport.width(), line_height)
+                    .to_rect()
+                    .with_origin(Point::new(viewport.x0, line_info.vline_y)),
+                color,
+                0.0,
+            );
+        }
+    }
+
+    /// Highlight the words that changed within the modified lines of a diff editor
+    fn paint_diff_word_changes(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        let (is_right, word_changes) = match self.editor.kind.get_untracked() {
+            EditorViewKind::Diff(diff) => (diff.is_right, diff.word_changes),
+            EditorViewKind::Normal => return,
+        };
+        if word_changes.is_empty() {
+            return;
+        }
+
+        let ed = &self.editor.editor;
+        let line_height = config.editor.line_height() as f64;
+        let color = if is_right {
+            config.color(LapceColor::SOURCE_CONTROL_ADDED)
+        } else {
+            config.color(LapceColor::SOURCE_CONTROL_REMOVED)
+        }
+        .with_alpha_factor(0.35);
+
+        for line_info in screen_lines.iter_line_info() {
+            let rvline = line_info.vline_info.rvline;
+            // Lines in the diff editor aren't wrapped
+            if rvline.line_index != 0 {
+                continue;
+            }
+            let Some(ranges) = word_changes.get(&rvline.line) else {
+                continue;
+            };
+
+            for range in ranges {
+                let x0 = ed
+                    .line_point_of_line_col(
+                        rvline.line,
+                        range.start,
+                        CursorAffinity::Forward,
+                        true,
+                    )
+                    .x;
+                let x1 = ed
+                    .line_point_of_line_col(
+                        rvline.line,
+                        range.end,
+                        CursorAffinity::Backward,
+                        true,
+                    )
+                    .x;
+                cx.fill(
+                    &Size::new(x1 - x0, line_height)
+                        .to_rect()
+                        .with_origin(Point::new(x0, line_info.vline_y)),
+                    color,
+                    0.0,
+                );
+            }
+        }
+    }
+
+    /// Paint the lines removed from the left side over their sections in the right editor,
+    /// when the diff is shown inline
+    fn paint_diff_inline_removed(
+        &mut self,
+        cx: &mut PaintCx,
+        viewport: Rect,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        let EditorViewKind::Diff(diff) = self.editor.kind.get_untracked() else {
+            return;
+        };
+        let (Some(left), Some(diff_sections)) =
+            (diff.inline.as_ref(), screen_lines.diff_sections.as_ref())
+        else {
+            return;
+        };
+        let left = RopeTextRef::new(left);
+
+        let line_height = config.editor.line_height();
+        let family: Vec<FamilyOwned> =
+            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let attrs = Attrs::new()
+            .family(&family)
+            .font_size(config.editor.font_size() as f32)
+            .color(config.color(LapceColor::EDITOR_DIM));
+
+        // Every removed section of the right editor shows a removed range of lines, in order
+        let removed = diff.changes.iter().filter_map(|change| match change {
+            DiffLines::Left(range) => Some(range),
+            _ => None,
+        });
+        let sections = diff_sections
+            .iter()
+            .filter(|section| matches!(section.kind, DiffSectionKind::Removed));
+        for (section, range) in sections.zip(removed) {
+            for (i, line) in range.clone().enumerate() {
+                let y = ((section.y_idx + i) * line_height) as f64;
+                if y + (line_height as f64) < viewport.y0 || y > viewport.y1 {
+                    continue;
+                }
+
+                let content = left.line_content(line);
+                let content = content.trim_end_matches(['\n', '\r']);
+                let text_layout = self.text_layouts.get_or_build(
+                    config.id,
+                    TextLayoutKey::RemovedLine(content.to_string()),
+                    || {
+                        let mut text_layout = TextLayout::new();
+                        text_layout.set_text(content, AttrsList::new(attrs));
+                        text_layout
+                    },
+                );
+                let height = text_layout.size().height;
+                cx.draw_text(
+                    &text_layout,
+                    Point::new(0.0, y + (line_height as f64 - height) / 2.0),
+                );
+            }
+        }
+    }
+
     fn paint_diff_no_code(
         &self,
         cx: &mut PaintCx,
@@ -444,7 +702,9 @@ fn paint_current_line(
 
         cursor.with_untracked(|cursor| {
             let highlight_current_line = match cursor.mode {
-                CursorMode::Normal(_) | CursorMode::Insert(_) => true,
+                CursorMode::Normal(_) | CursorMode::Insert(_) => {
+                    config.editor.highlight_current_line
+                }
                 CursorMode::Visual { .. } => false,
             };
 
@@ -472,6 +732,244 @@ fn paint_current_line(
         });
     }
 
+    /// Paint the carets, in the style configured for the cursor's mode
+    fn paint_cursor(
+        &self,
+        cx: &mut PaintCx,
+        is_active: bool,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        let ed = &self.editor.editor;
+        if !is_active || ed.cursor_info.hidden.get_untracked() {
+            return;
+        }
+
+        let line_height = config.editor.line_height() as f64;
+        let color = config.color(LapceColor::EDITOR_CARET);
+        let cursor = self.editor.cursor();
+        cursor.with_untracked(|cursor| {
+            let (offsets, style) = match &cursor.mode {
+                CursorMode::Normal(offset) => {
+                    (vec![*offset], config.editor.caret_style_normal)
+                }
+                CursorMode::Visual { end, .. } => {
+                    (vec![*end], config.editor.caret_style_normal)
+                }
+                CursorMode::Insert(selection) => (
+                    selection.regions().iter().map(|r| r.end).collect(),
+                    config.editor.caret_style_insert,
+                ),
+            };
+
+            for offset in offsets {
+                let LineRegion { x, width, rvline } =
+                    cursor_caret(ed, offset, true, cursor.affinity);
+                let Some(info) = screen_lines.info(rvline) else {
+                    continue;
+                };
+
+                let rect = match style {
+                    CaretStyle::Block => Rect::from_origin_size(
+                        (x, info.vline_y),
+                        (width, line_height),
+                    ),
+                    CaretStyle::Bar => Rect::from_origin_size(
+                        (x - 1.0, info.vline_y),
+                        (2.0, line_height),
+                    ),
+                    CaretStyle::Underline => Rect::from_origin_size(
+                        (x, info.vline_y + line_height - 2.0),
+                        (width, 2.0),
+                    ),
+                };
+                cx.fill(&rect, color, 0.0);
+            }
+        });
+    }
+
+    /// Add the rects covering `start..end` on the screen lines to `rects`
+    fn range_rects(
+        &self,
+        start: usize,
+        end: usize,
+        screen_lines: &ScreenLines,
+        line_height: f64,
+        rects: &mut Vec<Rect>,
+    ) {
+        // TODO: The selection rect creation logic is quite similar to the version
+        // within insert cursor. It would be good to deduplicate it.
+        let ed = &self.editor.editor;
+        // TODO(minor): the proper affinity here should probably be tracked by selregion
+        let (start_rvline, start_col) =
+            ed.rvline_col_of_offset(start, CursorAffinity::Forward);
+        let (end_rvline, end_col) =
+            ed.rvline_col_of_offset(end, CursorAffinity::Backward);
+
+        for line_info in screen_lines.iter_line_info() {
+            let rvline_info = line_info.vline_info;
+            let rvline = rvline_info.rvline;
+            let line = rvline.line;
+
+            if rvline < start_rvline {
+                continue;
+            }
+
+            if rvline > end_rvline {
+                break;
+            }
+
+            let left_col = if rvline == start_rvline { start_col } else { 0 };
+            let (right_col, _vline_end) = if rvline == end_rvline {
+                let max_col = ed.last_col(rvline_info, true);
+                (end_col.min(max_col), false)
+            } else {
+                (ed.last_col(rvline_info, true), true)
+            };
+
+            // TODO(minor): sel region should have the affinity of the start/end
+            let x0 = ed
+                .line_point_of_line_col(
+                    line,
+                    left_col,
+                    CursorAffinity::Forward,
+                    true,
+                )
+                .x;
+            let x1 = ed
+                .line_point_of_line_col(
+                    line,
+                    right_col,
+                    CursorAffinity::Backward,
+                    true,
+                )
+                .x;
+
+            if !rvline_info.is_empty() && start != end && left_col != right_col {
+                rects.push(
+                    Size::new(x1 - x0, line_height)
+                        .to_rect()
+                        .with_origin(Point::new(x0, line_info.vline_y)),
+                );
+            }
+        }
+    }
+
+    /// Draw the rulers the config sets for the document's language
+    fn paint_rulers(
+        &mut self,
+        cx: &mut PaintCx,
+        viewport: Rect,
+        config: &LapceConfig,
+    ) {
+        let language = self.editor.doc().language();
+        let rulers = config.rulers(language);
+        if rulers.is_empty() {
+            return;
+        }
+
+        let char_width = self
+            .text_layouts
+            .get_or_build(config.id, TextLayoutKey::CharWidth, || {
+                let family: Vec<FamilyOwned> =
+                    FamilyOwned::parse_list(&config.editor.font_family).collect();
+                let attrs = Attrs::new()
+                    .family(&family)
+                    .font_size(config.editor.font_size() as f32);
+                let mut text_layout = TextLayout::new();
+                text_layout.set_text("W", AttrsList::new(attrs));
+                text_layout
+            })
+            .size()
+            .width;
+
+        let color = config.color(LapceColor::EDITOR_INDENT_GUIDE);
+        for column in rulers {
+            let x = *column as f64 * char_width;
+            if x < viewport.x0 || x > viewport.x1 {
+                continue;
+            }
+            let line =
+                Line::new(Point::new(x, viewport.y0), Point::new(x, viewport.y1));
+            cx.stroke(&line, color, 1.0);
+        }
+    }
+
+    /// Softly highlight the occurrences of the symbol at the cursor, outlining the
+    /// ones that write to it
+    fn paint_symbol_occurrences(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        let occurrences = self.editor.doc().symbol_occurrences.get_untracked();
+        if occurrences.is_empty() || screen_lines.lines.is_empty() {
+            return;
+        }
+
+        let ed = &self.editor.editor;
+        let min_vline = *screen_lines.lines.first().unwrap();
+        let max_vline = *screen_lines.lines.last().unwrap();
+        let min_line = screen_lines.info(min_vline).unwrap().vline_info.rvline.line;
+        let max_line = screen_lines.info(max_vline).unwrap().vline_info.rvline.line;
+        let start = ed.offset_of_line(min_line);
+        let end = ed.offset_of_line(max_line + 1);
+
+        let line_height = config.editor.line_height() as f64;
+        let color = config.color(LapceColor::EDITOR_SELECTION);
+        for occurrence in occurrences
+            .iter()
+            .filter(|o| o.end >= start && o.start <= end)
+        {
+            let mut rects = Vec::new();
+            self.range_rects(
+                occurrence.start,
+                occurrence.end,
+                screen_lines,
+                line_height,
+                &mut rects,
+            );
+            for rect in rects {
+                cx.fill(&rect, color.with_alpha_factor(0.5), 0.0);
+                if occurrence.write {
+                    cx.stroke(&rect, color, 1.0);
+                }
+            }
+        }
+    }
+
+    /// Underline the link under the mouse while the modifier opening it is held
+    fn paint_hovered_link(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        let Some(link) = self.editor.hovered_link.get_untracked() else {
+            return;
+        };
+
+        let line_height = config.editor.line_height() as f64;
+        let mut rects = Vec::new();
+        self.range_rects(
+            link.start,
+            link.end,
+            screen_lines,
+            line_height,
+            &mut rects,
+        );
+        let color = config.color(LapceColor::EDITOR_LINK);
+        for rect in rects {
+            let y = rect.y1 - 2.0;
+            cx.stroke(
+                &Line::new(Point::new(rect.x0, y), Point::new(rect.x1, y)),
+                color,
+                1.0,
+            );
+        }
+    }
+
     fn paint_find(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
         let visual = self.editor.common.find.visual;
         if !visual.get_untracked() {
@@ -500,8 +998,6 @@ fn paint_find(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
         let start = ed.offset_of_line(min_line);
         let end = ed.offset_of_line(max_line + 1);
 
-        // TODO: The selection rect creation logic for find is quite similar to the version
-        // within insert cursor. It would be good to deduplicate it.
         let mut rects = Vec::new();
         for region in occurrences.with_untracked(|selection| {
             selection.regions_in_range(start, end).to_vec()
@@ -509,59 +1005,7 @@ fn paint_find(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
             let start = region.min();
             let end = region.max();
 
-            // TODO(minor): the proper affinity here should probably be tracked by selregion
-            let (start_rvline, start_col) =
-                ed.rvline_col_of_offset(start, CursorAffinity::Forward);
-            let (end_rvline, end_col) =
-                ed.rvline_col_of_offset(end, CursorAffinity::Backward);
-
-            for line_info in screen_lines.iter_line_info() {
-                let rvline_info = line_info.vline_info;
-                let rvline = rvline_info.rvline;
-                let line = rvline.line;
-
-                if rvline < start_rvline {
-                    continue;
-                }
-
-                if rvline > end_rvline {
-                    break;
-                }
-
-                let left_col = if rvline == start_rvline { start_col } else { 0 };
-                let (right_col, _vline_end) = if rvline == end_rvline {
-                    let max_col = ed.last_col(rvline_info, true);
-                    (end_col.min(max_col), false)
-                } else {
-                    (ed.last_col(rvline_info, true), true)
-                };
-
-                // TODO(minor): sel region should have the affinity of the start/end
-                let x0 = ed
-                    .line_point_of_line_col(
-                        line,
-                        left_col,
-                        CursorAffinity::Forward,
-                        true,
-                    )
-                    .x;
-                let x1 = ed
-                    .line_point_of_line_col(
-                        line,
-                        right_col,
-                        CursorAffinity::Backward,
-                        true,
-                    )
-                    .x;
-
-                if !rvline_info.is_empty() && start != end && left_col != right_col {
-                    rects.push(
-                        Size::new(x1 - x0, line_height)
-                            .to_rect()
-                            .with_origin(Point::new(x0, line_info.vline_y)),
-                    );
-                }
-            }
+            self.range_rects(start, end, screen_lines, line_height, &mut rects);
         }
 
         let color = config.color(LapceColor::EDITOR_FOREGROUND);
@@ -689,25 +1133,28 @@ fn paint_scroll_bar(
         is_local: bool,
         config: Arc<LapceConfig>,
     ) {
-        const BAR_WIDTH: f64 = 10.0;
-
         if is_local {
             return;
         }
 
-        cx.fill(
-            &Rect::ZERO
-                .with_size(Size::new(1.0, viewport.height()))
-                .with_origin(Point::new(
-                    viewport.x0 + viewport.width() - BAR_WIDTH,
-                    viewport.y0,
-                ))
-                .inflate(0.0, 10.0),
-            config.color(LapceColor::LAPCE_SCROLL_BAR),
-            0.0,
-        );
+        let bar_width = config.ui.scroll_width() as f64;
+        if config.editor.show_scroll_bar {
+            cx.fill(
+                &Rect::ZERO
+                    .with_size(Size::new(1.0, viewport.height()))
+                    .with_origin(Point::new(
+                        viewport.x0 + viewport.width() - bar_width,
+                        viewport.y0,
+                    ))
+                    .inflate(0.0, 10.0),
+                config.color(LapceColor::LAPCE_SCROLL_BAR),
+                0.0,
+            );
+        }
 
-        if !self.editor.kind.get_untracked().is_normal() {
+        if !config.editor.scroll_bar_markers
+            || !self.editor.kind.get_untracked().is_normal()
+        {
             return;
         }
 
@@ -731,7 +1178,7 @@ fn paint_scroll_bar(
                 .max(3.0);
             let rect = Rect::ZERO.with_size(Size::new(3.0, height)).with_origin(
                 Point::new(
-                    viewport.x0 + total_width - BAR_WIDTH + 1.0,
+                    viewport.x0 + total_width - bar_width + 1.0,
                     y + viewport.y0,
                 ),
             );
@@ -1104,15 +1551,22 @@ fn paint(&mut self, cx: &mut PaintCx) {
         // within the active screen lines without issue.
         let screen_lines = ed.screen_lines.get_untracked();
         self.paint_current_line(cx, is_local, &screen_lines);
+        self.paint_rulers(cx, viewport, &config);
         FloemEditorView::paint_selection(cx, ed, &screen_lines);
         let screen_lines = ed.screen_lines.get_untracked();
         self.paint_diff_sections(cx, viewport, &screen_lines, &config);
+        self.paint_diff_word_changes(cx, &screen_lines, &config);
+        self.paint_diff_inline_removed(cx, viewport, &screen_lines, &config);
+        self.paint_conflicts(cx, viewport, &screen_lines, &config);
         let screen_lines = ed.screen_lines.get_untracked();
+        self.paint_symbol_occurrences(cx, &screen_lines, &config);
         self.paint_find(cx, &screen_lines);
         let screen_lines = ed.screen_lines.get_untracked();
         self.paint_bracket_highlights_scope_lines(cx, viewport, &screen_lines);
         let screen_lines = ed.screen_lines.get_untracked();
+        self.paint_cursor(cx, is_active, &screen_lines, &config);
         FloemEditorView::paint_text(cx, ed, viewport, is_active, &screen_lines);
+        self.paint_hovered_link(cx, &screen_lines, &config);
         let screen_lines = ed.screen_lines.get_untracked();
         self.paint_sticky_headers(cx, viewport, &screen_lines);
         self.paint_scroll_bar(cx, viewport, is_local, config);
@@ -1258,11 +1712,25 @@ pub fn editor_container_view(
     let replace_focus = main_split.common.find.replace_focus;
     let debug_breakline = window_tab_data.terminal.breakline;
 
+    // Show the values of the paused debug session in the file it's paused in
+    let inline_debug_values = window_tab_data.terminal.inline_debug_values;
+    create_effect(move |_| {
+        let values = inline_debug_values.get();
+        let doc = editor.with(|editor| editor.doc_signal()).get();
+        let values = values.filter(|values| {
+            doc.content
+                .with(|content| content.path() == Some(&values.path))
+        });
+        doc.set_inline_debug_values(values);
+    });
+
     stack((
         editor_breadcrumbs(workspace, editor.get_untracked(), config),
         stack((
+            editor_blame_view(editor),
             editor_gutter(window_tab_data.clone(), editor, is_active),
             editor_content(editor, debug_breakline, is_active),
+            editor_conflicts_view(editor),
             empty().style(move |s| {
                 let config = config.get();
                 s.absolute()
@@ -1289,6 +1757,10 @@ pub fn editor_container_view(
                 replace_focus,
                 is_active,
             ),
+            hunk_peek_view(editor),
+            conflict_peek_view(editor),
+            editor_loading_view(editor),
+            disk_changed_view(editor),
         ))
         .style(|s| s.width_full().flex_basis(0).flex_grow(1.0)),
     ))
@@ -1326,8 +1798,8 @@ fn editor_gutter(
     e_data: RwSignal<EditorData>,
     is_active: impl Fn(bool) -> bool + 'static + Copy,
 ) -> impl View {
-    let breakpoints = window_tab_data.terminal.debug.breakpoints;
-    let daps = window_tab_data.terminal.debug.daps;
+    let debug = window_tab_data.terminal.debug.clone();
+    let breakpoints = debug.breakpoints;
 
     let padding_left = 25.0;
     let padding_right = 30.0;
@@ -1354,7 +1826,7 @@ fn editor_gutter(
                 cursor.with(|cursor| (cursor.offset(), cursor.affinity));
             let has_code_actions = doc
                 .code_actions()
-                .with(|c| c.get(&offset).map(|c| !c.1.is_empty()).unwrap_or(false));
+                .with(|c| c.get(&offset).map(|c| !c.is_empty()).unwrap_or(false));
             if has_code_actions {
                 let vline = ed.vline_of_offset(offset, affinity);
                 Some(vline)
@@ -1371,6 +1843,8 @@ fn editor_gutter(
 
     let breakpoints_view = move |i: usize| {
         let hovered = create_rw_signal(false);
+        let debug = debug.clone();
+        let window_tab_data = window_tab_data.clone();
         container(
             svg(move || config.get().ui_svg(LapceIcons::DEBUG_BREAKPOINT)).style(
                 move |s| {
@@ -1393,62 +1867,24 @@ fn editor_gutter(
             let doc = e_data.doc();
             let offset = doc.buffer.with_untracked(|b| b.offset_of_line(line));
             if let Some(path) = doc.content.get_untracked().path() {
-                let path_breakpoints = breakpoints
-                    .try_update(|breakpoints| {
-                        let breakpoints =
-                            breakpoints.entry(path.clone()).or_default();
-                        if let std::collections::btree_map::Entry::Vacant(e) =
-                            breakpoints.entry(line)
-                        {
-                            e.insert(LapceBreakpoint {
-                                id: None,
-                                verified: false,
-                                message: None,
-                                line,
-                                offset,
-                                dap_line: None,
-                                active: true,
-                            });
-                        } else {
-                            let mut toggle_active = false;
-                            if let Some(breakpint) = breakpoints.get_mut(&line) {
-                                if !breakpint.active {
-                                    breakpint.active = true;
-                                    toggle_active = true;
-                                }
-                            }
-                            if !toggle_active {
-                                breakpoints.remove(&line);
-                            }
-                        }
-                        breakpoints.clone()
-                    })
-                    .unwrap();
-                let source_breakpoints: Vec<SourceBreakpoint> = path_breakpoints
-                    .iter()
-                    .filter_map(|(_, b)| {
-                        if b.active {
-                            Some(SourceBreakpoint {
-                                line: b.line + 1,
-                                column: None,
-                                condition: None,
-                                hit_condition: None,
-                                log_message: None,
-                            })
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                let daps: Vec<DapId> =
-                    daps.with_untracked(|daps| daps.keys().cloned().collect());
-                for dap_id in daps {
-                    e_data.common.proxy.dap_set_breakpoints(
-                        dap_id,
+                debug.toggle_breakpoint(&e_data.common.proxy, path, line, offset);
+            }
+        })
+        .on_secondary_click_stop(move |_| {
+            let screen_lines = screen_lines.get_untracked();
+            let line = screen_lines.lines.get(i).map(|r| r.line).unwrap_or(0);
+            let doc = e_data.get_untracked().doc();
+            let offset = doc.buffer.with_untracked(|b| b.offset_of_line(line));
+            if let Some(path) = doc.content.get_untracked().path() {
+                show_context_menu(
+                    breakpoint_menu(
+                        &window_tab_data,
                         path.to_path_buf(),
-                        source_breakpoints.clone(),
-                    );
-                }
+                        line,
+                        offset,
+                    ),
+                    None,
+                );
             }
         })
         .on_event_stop(EventListener::PointerEnter, move |_| {
@@ -1616,6 +2052,29 @@ fn editor_gutter(
             ))
             .style(|s| s.size_pct(100.0, 100.0)),
         )
+        .on_event_cont(EventListener::PointerDown, move |event| {
+            let Event::PointerDown(pointer_event) = event else {
+                return;
+            };
+
+            // Clicking on the marker of a change shows its original lines
+            let marker_x = padding_left + gutter_width.get_untracked();
+            if pointer_event.pos.x < marker_x
+                || pointer_event.pos.x > marker_x + 15.0
+            {
+                return;
+            }
+            let e_data = e_data.get_untracked();
+            let mode = cursor.with_untracked(|c| c.get_mode());
+            let point =
+                Point::new(0.0, pointer_event.pos.y + viewport.get_untracked().y0);
+            let (offset, _) = e_data.editor.offset_of_point(mode, point);
+            let line = e_data
+                .doc()
+                .buffer
+                .with_untracked(|buffer| buffer.line_of_offset(offset));
+            e_data.toggle_hunk_peek_at_line(line);
+        })
         .style(move |s| {
             s.absolute()
                 .size_pct(100.0, 100.0)
@@ -1756,6 +2215,7 @@ fn editor_content(
         viewport,
         sticky_header_height,
         config,
+        hovered_link,
         editor,
     ) = e_data.with_untracked(|editor| {
         (
@@ -1766,6 +2226,7 @@ fn editor_content(
             editor.viewport(),
             editor.sticky_header_height,
             editor.common.config,
+            editor.hovered_link,
             editor.editor.clone(),
         )
     });
@@ -1783,10 +2244,13 @@ fn editor_content(
         let editor_content_view =
             editor_view(e_data.get_untracked(), debug_breakline, is_active).style(
                 move |s| {
-                    s.absolute()
-                        .margin_left(1.0)
-                        .min_size_full()
-                        .cursor(CursorStyle::Text)
+                    s.absolute().margin_left(1.0).min_size_full().cursor(
+                        if hovered_link.with(Option::is_some) {
+                            CursorStyle::Pointer
+                        } else {
+                            CursorStyle::Text
+                        },
+                    )
                 },
             );
 
@@ -1883,7 +2347,11 @@ fn editor_content(
             rect
         }
     })
-    .style(|s| s.size_full().set(PropagatePointerWheel, false))
+    .style(move |s| {
+        s.size_full()
+            .set(PropagatePointerWheel, false)
+            .set(HideBar, !config.get().editor.show_scroll_bar)
+    })
     .debug_name("Editor Content")
 }
 
@@ -2206,6 +2674,280 @@ fn find_view(
     })
 }
 
+/// A note shown while the document's file is still being read, when only its start
+/// may be shown
+fn editor_loading_view(editor: RwSignal<EditorData>) -> impl View {
+    let (doc, config) =
+        editor.with_untracked(|editor| (editor.doc_signal(), editor.common.config));
+    let loading = create_memo(move |_| {
+        let doc = doc.get();
+        !doc.loaded.get() && doc.content.with(|content| content.is_file())
+    });
+
+    label(|| "Loading...".to_string()).style(move |s| {
+        let config = config.get();
+        s.absolute()
+            .inset_right(20.0)
+            .inset_bottom(10.0)
+            .padding_horiz(10.0)
+            .padding_vert(4.0)
+            .background(config.color(LapceColor::PANEL_BACKGROUND))
+            .border_radius(6.0)
+            .border(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .color(config.color(LapceColor::EDITOR_DIM))
+            .apply_if(!loading.get(), |s| s.hide())
+    })
+}
+
+/// A banner shown when the document's file changed on disk while it had unsaved
+/// changes, asking whether to reload it or keep the changes
+fn disk_changed_view(editor: RwSignal<EditorData>) -> impl View {
+    let (doc, internal_command, config) = editor.with_untracked(|editor| {
+        (
+            editor.doc_signal(),
+            editor.common.internal_command,
+            editor.common.config,
+        )
+    });
+    let changed =
+        create_memo(move |_| doc.get().disk_state.get() == DiskState::Changed);
+
+    let action = move |text: &'static str, on_click: Box<dyn Fn()>| {
+        label(move || text.to_string())
+            .on_click_stop(move |_| on_click())
+            .style(move |s| {
+                let config = config.get();
+                let hover_color = config.color(LapceColor::EDITOR_LINK);
+                s.padding_horiz(6.0)
+                    .color(config.color(LapceColor::EDITOR_DIM))
+                    .selectable(false)
+                    .hover(|s| s.cursor(CursorStyle::Pointer).color(hover_color))
+            })
+    };
+
+    stack((
+        label(|| "The file has changed on disk.".to_string())
+            .style(|s| s.flex_grow(1.0).padding_horiz(10.0)),
+        action(
+            "Reload",
+            Box::new(move || doc.get_untracked().reload_from_disk()),
+        ),
+        action(
+            "Keep My Version",
+            Box::new(move || {
+                doc.get_untracked().disk_state.set(DiskState::Unchanged)
+            }),
+        ),
+        action(
+            "Compare",
+            Box::new(move || {
+                let path = doc
+                    .get_untracked()
+                    .content
+                    .with_untracked(|c| c.path().cloned());
+                if let Some(path) = path {
+                    internal_command
+                        .send(InternalCommand::CompareWithSaved { path });
+                }
+            }),
+        ),
+    ))
+    // Keep clicks from going through to the editor below
+    .on_event_stop(EventListener::PointerDown, |_| {})
+    .style(move |s| {
+        let config = config.get();
+        s.absolute()
+            .width_full()
+            .items_center()
+            .padding_vert(4.0)
+            .background(config.color(LapceColor::PANEL_BACKGROUND))
+            .border_bottom(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .cursor(CursorStyle::Default)
+            .apply_if(!changed.get(), |s| s.hide())
+    })
+    .debug_name("Disk Changed Banner")
+}
+
+/// A popup below a change against the `head` version, showing the original lines
+fn hunk_peek_view(editor: RwSignal<EditorData>) -> impl View {
+    let (ed, doc, hunk_peek, config) = editor.with_untracked(|editor| {
+        (
+            editor.editor.clone(),
+            editor.doc_signal(),
+            editor.hunk_peek,
+            editor.common.config,
+        )
+    });
+    let viewport = ed.viewport;
+
+    // The shown change and its position among all the changes, hidden once an edit
+    // changes it
+    let peek = create_memo(move |_| {
+        let hunk = hunk_peek.get()?;
+        let hunks = doc.get().head_changes().with(|changes| diff_hunks(changes));
+        let index = hunks.iter().position(|h| h == &hunk)?;
+        Some((hunk, index, hunks.len()))
+    });
+
+    let original = move || {
+        let (hunk, _, _) = peek.get()?;
+        let head = doc.get_untracked().head_text()?;
+        let head = RopeTextVal::new(head);
+        let text = head
+            .slice_to_cow(
+                head.offset_of_line(hunk.left.start)
+                    ..head.offset_of_line(hunk.left.end),
+            )
+            .trim_end_matches(['\n', '\r'])
+            .to_string();
+        Some(text)
+    };
+
+    container(
+        stack((
+            stack((
+                label(move || {
+                    let Some((hunk, index, count)) = peek.get() else {
+                        return String::new();
+                    };
+                    let kind = match hunk.kind() {
+                        DiffHunkKind::Added => "Added",
+                        DiffHunkKind::Modified => "Modified",
+                        DiffHunkKind::Deleted => "Deleted",
+                    };
+                    format!("{kind} ({} of {count})", index + 1)
+                })
+                .style(|s| s.flex_grow(1.0).padding_horiz(10.0)),
+                clickable_icon(
+                    || LapceIcons::SCM_CHANGE_ADD,
+                    move || {
+                        if let Some((hunk, _, _)) = peek.get_untracked() {
+                            editor.get_untracked().stage_hunk(&hunk);
+                        }
+                    },
+                    || false,
+                    || false,
+                    || "Stage Change",
+                    config,
+                )
+                .style(|s| s.padding_left(6.0)),
+                clickable_icon(
+                    || LapceIcons::DEBUG_RESTART,
+                    move || {
+                        if let Some((hunk, _, _)) = peek.get_untracked() {
+                            editor.get_untracked().revert_hunk(&hunk);
+                        }
+                    },
+                    || false,
+                    || false,
+                    || "Revert Change",
+                    config,
+                )
+                .style(|s| s.padding_left(6.0)),
+                clickable_icon(
+                    || LapceIcons::SEARCH_BACKWARD,
+                    move || {
+                        editor.get_untracked().previous_hunk();
+                    },
+                    || false,
+                    || false,
+                    || "Previous Change",
+                    config,
+                )
+                .style(|s| s.padding_left(6.0)),
+                clickable_icon(
+                    || LapceIcons::SEARCH_FORWARD,
+                    move || {
+                        editor.get_untracked().next_hunk();
+                    },
+                    || false,
+                    || false,
+                    || "Next Change",
+                    config,
+                )
+                .style(|s| s.padding_left(6.0)),
+                clickable_icon(
+                    || LapceIcons::CLOSE,
+                    move || {
+                        hunk_peek.set(None);
+                    },
+                    || false,
+                    || false,
+                    || "Close",
+                    config,
+                )
+                .style(|s| s.padding_horiz(6.0)),
+            ))
+            .style(|s| s.width_full().items_center()),
+            scroll(
+                label(move || {
+                    original().unwrap_or_else(|| {
+                        "There are no original lines for added lines".to_string()
+                    })
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    let color = if peek.with(|peek| {
+                        peek.as_ref().is_some_and(|(h, _, _)| !h.left.is_empty())
+                    }) {
+                        LapceColor::SOURCE_CONTROL_REMOVED
+                    } else {
+                        LapceColor::EDITOR_DIM
+                    };
+                    s.padding_horiz(10.0)
+                        .font_family(config.editor.font_family.clone())
+                        .font_size(config.editor.font_size() as f32)
+                        .color(config.color(color))
+                }),
+            )
+            .style(move |s| {
+                let config = config.get();
+                s.width_full()
+                    .margin_top(4.0)
+                    .max_height(config.editor.line_height() as f32 * 10.0)
+            }),
+        ))
+        .style(move |s| {
+            let config = config.get();
+            s.width_full()
+                .flex_col()
+                .padding_vert(4.0)
+                .background(config.color(LapceColor::PANEL_BACKGROUND))
+                .border_top(1.0)
+                .border_bottom(1.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .cursor(CursorStyle::Default)
+        })
+        // Keep clicks from going through to the editor below
+        .on_event_stop(EventListener::PointerDown, |_| {}),
+    )
+    .style(move |s| {
+        let Some((hunk, _, _)) = peek.get() else {
+            return s.hide();
+        };
+
+        let line_height = config.get().editor.line_height() as f64;
+        let last_line = ed.last_line();
+        // Show the popup below the changed lines, or where the lines were deleted
+        let (line, below) = if hunk.right.is_empty() {
+            (hunk.right.start, hunk.right.start > last_line)
+        } else {
+            (hunk.right.end - 1, true)
+        };
+        let mut y = ed.vline_of_line(line.min(last_line)).get() as f64 * line_height;
+        if below {
+            y += line_height;
+        }
+
+        s.absolute()
+            .width_pct(100.0)
+            .margin_top((y - viewport.get().y0) as f32)
+    })
+    .debug_name("Hunk Peek")
+}
+
 /// Iterator over (len, color, modified) for each change in the diff
 fn changes_color_iter<'a>(
     changes: &'a im::Vector<DiffLines>,
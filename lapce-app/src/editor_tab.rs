@@ -17,14 +17,14 @@
 
 use crate::{
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
-    doc::{Doc, DocContent},
+    doc::{DiskState, Doc, DocContent},
     editor::{
         diff::{DiffEditorData, DiffEditorInfo},
         location::EditorLocation,
         EditorData, EditorInfo,
     },
     id::{
-        DiffEditorId, EditorTabId, KeymapId, SettingsId, SplitId,
+        DiffEditorId, EditorTabId, ImageViewId, KeymapId, SettingsId, SplitId,
         ThemeColorSettingsId, VoltViewId,
     },
     main_split::{Editors, MainSplitData},
@@ -40,6 +40,7 @@ pub enum EditorTabChildInfo {
     ThemeColorSettings,
     Keymap,
     Volt(VoltID),
+    Image(PathBuf),
 }
 
 impl EditorTabChildInfo {
@@ -67,6 +68,10 @@ pub fn to_data(
             EditorTabChildInfo::Volt(id) => {
                 EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
             }
+            EditorTabChildInfo::Image(path) => EditorTabChild::Image(
+                data.new_image_view(path.to_owned()),
+                path.to_owned(),
+            ),
         }
     }
 }
@@ -128,6 +133,7 @@ pub enum EditorTabChildSource {
     ThemeColorSettings,
     Keymap,
     Volt(VoltID),
+    Image(PathBuf),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -138,6 +144,7 @@ pub enum EditorTabChild {
     ThemeColorSettings(ThemeColorSettingsId),
     Keymap(KeymapId),
     Volt(VoltViewId, VoltID),
+    Image(ImageViewId, PathBuf),
 }
 
 #[derive(PartialEq)]
@@ -147,6 +154,8 @@ pub struct EditorTabChildViewInfo {
     pub path: String,
     pub confirmed: Option<RwSignal<bool>>,
     pub is_pristine: bool,
+    /// Whether the file is opened read-only, which the tab shows a lock for
+    pub read_only: bool,
 }
 
 impl EditorTabChild {
@@ -158,6 +167,7 @@ pub fn id(&self) -> u64 {
             EditorTabChild::ThemeColorSettings(id) => id.to_raw(),
             EditorTabChild::Keymap(id) => id.to_raw(),
             EditorTabChild::Volt(id, _) => id.to_raw(),
+            EditorTabChild::Image(id, _) => id.to_raw(),
         }
     }
 
@@ -191,6 +201,9 @@ pub fn child_info(&self, data: &WindowTabData) -> EditorTabChildInfo {
             }
             EditorTabChild::Keymap(_) => EditorTabChildInfo::Keymap,
             EditorTabChild::Volt(_, id) => EditorTabChildInfo::Volt(id.to_owned()),
+            EditorTabChild::Image(_, path) => {
+                EditorTabChildInfo::Image(path.to_owned())
+            }
         }
     }
 
@@ -205,39 +218,60 @@ pub fn view_info(
             EditorTabChild::Editor(editor_id) => create_memo(move |_| {
                 let config = config.get();
                 let editor_data = editors.editor(editor_id);
+                let mut read_only = false;
                 let path = if let Some(editor_data) = editor_data {
                     let doc = editor_data.doc_signal().get();
-                    let (content, is_pristine, confirmed) = (
+                    let (content, is_pristine, confirmed, disk_state) = (
                         doc.content.get(),
                         doc.buffer.with(|b| b.is_pristine()),
                         editor_data.confirmed,
+                        doc.disk_state.get(),
+                    );
+                    read_only = matches!(
+                        content,
+                        DocContent::File {
+                            read_only: true,
+                            ..
+                        }
                     );
                     match content {
                         DocContent::File { path, .. } => {
-                            Some((path, confirmed, is_pristine))
+                            Some((path, confirmed, is_pristine, disk_state))
                         }
                         DocContent::Local => None,
-                        DocContent::History(_) => None,
-                        DocContent::Scratch { name, .. } => {
-                            Some((PathBuf::from(name), confirmed, is_pristine))
-                        }
+                        DocContent::History(history) => Some((
+                            history.path,
+                            confirmed,
+                            is_pristine,
+                            DiskState::Unchanged,
+                        )),
+                        DocContent::Scratch { name, .. } => Some((
+                            PathBuf::from(name),
+                            confirmed,
+                            is_pristine,
+                            DiskState::Unchanged,
+                        )),
                     }
                 } else {
                     None
                 };
                 let (icon, color, path, confirmed, is_pristine) = match path {
-                    Some((path, confirmed, is_pritine)) => {
+                    Some((path, confirmed, is_pritine, disk_state)) => {
                         let (svg, color) = config.file_svg(&path);
-                        (
-                            svg,
-                            color,
-                            path.file_name()
-                                .unwrap_or_default()
-                                .to_string_lossy()
-                                .into_owned(),
-                            confirmed,
-                            is_pritine,
-                        )
+                        let file_name = path.file_name().unwrap_or_default();
+                        let file_name = match disk_state {
+                            DiskState::Unchanged => {
+                                file_name.to_string_lossy().into_owned()
+                            }
+                            DiskState::Changed => format!(
+                                "{} (Changed on Disk)",
+                                file_name.to_string_lossy()
+                            ),
+                            DiskState::Deleted => {
+                                format!("{} (Deleted)", file_name.to_string_lossy())
+                            }
+                        };
+                        (svg, color, file_name, confirmed, is_pritine)
                     }
                     None => (
                         config.ui_svg(LapceIcons::FILE),
@@ -253,6 +287,7 @@ pub fn view_info(
                     path,
                     confirmed: Some(confirmed),
                     is_pristine,
+                    read_only,
                 }
             }),
             EditorTabChild::DiffEditor(diff_editor_id) => create_memo(move |_| {
@@ -331,6 +366,7 @@ pub fn view_info(
                     path,
                     confirmed,
                     is_pristine,
+                    read_only: false,
                 }
             }),
             EditorTabChild::Settings(_) => create_memo(move |_| {
@@ -341,6 +377,7 @@ pub fn view_info(
                     path: "Settings".to_string(),
                     confirmed: None,
                     is_pristine: true,
+                    read_only: false,
                 }
             }),
             EditorTabChild::ThemeColorSettings(_) => create_memo(move |_| {
@@ -351,6 +388,7 @@ pub fn view_info(
                     path: "Theme Colors".to_string(),
                     confirmed: None,
                     is_pristine: true,
+                    read_only: false,
                 }
             }),
             EditorTabChild::Keymap(_) => create_memo(move |_| {
@@ -361,6 +399,7 @@ pub fn view_info(
                     path: "Keyboard Shortcuts".to_string(),
                     confirmed: None,
                     is_pristine: true,
+                    read_only: false,
                 }
             }),
             EditorTabChild::Volt(_, id) => create_memo(move |_| {
@@ -384,6 +423,23 @@ pub fn view_info(
                     path: display_name,
                     confirmed: None,
                     is_pristine: true,
+                    read_only: false,
+                }
+            }),
+            EditorTabChild::Image(_, path) => create_memo(move |_| {
+                let config = config.get();
+                let (icon, color) = config.file_svg(&path);
+                EditorTabChildViewInfo {
+                    icon,
+                    color,
+                    path: path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                    confirmed: None,
+                    is_pristine: true,
+                    read_only: false,
                 }
             }),
         }
@@ -12,7 +12,7 @@
     ext_event::create_ext_action,
     keyboard::Modifiers,
     menu::{Menu, MenuItem},
-    reactive::{RwSignal, Scope},
+    reactive::{Memo, ReadSignal, RwSignal, Scope},
     views::editor::text::SystemClipboard,
 };
 use globset::Glob;
@@ -20,13 +20,17 @@
     command::{EditCommand, FocusCommand},
     mode::Mode,
     register::Clipboard,
+    selection::Selection,
 };
 use lapce_rpc::{
     file::{Duplicating, FileNodeItem, Naming, NamingState, NewNode, Renaming},
     proxy::ProxyResponse,
+    RpcError,
 };
+use lapce_xi_rope::Rope;
 
 use crate::{
+    alert::AlertButton,
     command::{CommandExecuted, CommandKind, InternalCommand, LapceCommand},
     editor::EditorData,
     keypress::{condition::Condition, KeyPressFocus},
@@ -34,6 +38,13 @@
     window_tab::CommonData,
 };
 
+#[cfg(target_os = "macos")]
+const REVEAL_IN_FILE_MANAGER: &str = "Reveal in Finder";
+#[cfg(target_os = "windows")]
+const REVEAL_IN_FILE_MANAGER: &str = "Reveal in File Explorer";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const REVEAL_IN_FILE_MANAGER: &str = "Reveal in File Manager";
+
 enum RenamedPath {
     NotRenaming,
     NameUnchanged,
@@ -43,11 +54,37 @@ enum RenamedPath {
     },
 }
 
+/// A file or directory that was cut or copied in the file explorer, waiting to be pasted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileClipboard {
+    pub path: PathBuf,
+    /// Whether pasting moves the node rather than copying it
+    pub cut: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct FileExplorerData {
     pub root: RwSignal<FileNodeItem>,
     pub naming: RwSignal<Naming>,
     pub naming_editor_data: EditorData,
+    /// The input used to filter the tree
+    pub filter_editor_data: EditorData,
+    /// The current text of the filter input
+    pub filter: Memo<String>,
+    /// Whether key presses go to the filter input rather than the tree
+    pub filter_focused: RwSignal<bool>,
+    /// The file or directory that keyboard actions apply to
+    pub select: RwSignal<Option<PathBuf>>,
+    /// The directory that a dragged file or directory would be dropped into
+    pub drag_over: RwSignal<Option<PathBuf>>,
+    /// The row that the file explorer should scroll into view
+    pub scroll_to_line: RwSignal<Option<usize>>,
+    /// Whether files excluded by `files-exclude` or ignored by git are temporarily shown
+    pub show_hidden: RwSignal<bool>,
+    /// The file or directory that was last cut or copied
+    pub clipboard: RwSignal<Option<FileClipboard>>,
+    /// Files and directories ignored by git
+    ignored: ReadSignal<Vec<PathBuf>>,
     pub common: Rc<CommonData>,
     left_diff_path: RwSignal<Option<PathBuf>>,
 }
@@ -58,8 +95,18 @@ fn get_mode(&self) -> Mode {
     }
 
     fn check_condition(&self, condition: Condition) -> bool {
-        self.naming.with_untracked(Naming::is_accepting_input)
-            && condition == Condition::ModalFocus
+        let is_naming = self.naming.with_untracked(Naming::is_accepting_input);
+        let is_filtering = self.filter_focused.get_untracked();
+        match condition {
+            Condition::PanelFocus => true,
+            Condition::ModalFocus => {
+                is_naming
+                    || is_filtering
+                    || !self.filter.with_untracked(String::is_empty)
+            }
+            Condition::FileExplorerFocus => !is_naming && !is_filtering,
+            _ => false,
+        }
     }
 
     fn run_command(
@@ -92,6 +139,24 @@ fn run_command(
                 }
                 _ => self.naming_editor_data.run_command(command, count, mods),
             }
+        } else if let CommandKind::Focus(FocusCommand::ModalClose) = command.kind {
+            if self.filter_focused.get_untracked()
+                || !self.filter.with_untracked(String::is_empty)
+            {
+                self.clear_filter();
+                CommandExecuted::Yes
+            } else {
+                CommandExecuted::No
+            }
+        } else if self.filter_focused.get_untracked() {
+            match command.kind {
+                CommandKind::Edit(_)
+                | CommandKind::Move(_)
+                | CommandKind::MultiSelection(_) => {
+                    self.filter_editor_data.run_command(command, count, mods)
+                }
+                _ => CommandExecuted::No,
+            }
         } else {
             CommandExecuted::No
         }
@@ -106,12 +171,21 @@ fn receive_char(&self, c: &str) {
                     .internal_command
                     .send(InternalCommand::TestPathCreation { new_path });
             }
+        } else {
+            // Typing in the tree starts filtering it
+            self.filter_focused.set(true);
+            self.filter_editor_data.receive_char(c);
         }
     }
 }
 
 impl FileExplorerData {
-    pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
+    pub fn new(
+        cx: Scope,
+        editors: Editors,
+        ignored: ReadSignal<Vec<PathBuf>>,
+        common: Rc<CommonData>,
+    ) -> Self {
         let path = common.workspace.path.clone().unwrap_or_default();
         let root = cx.create_rw_signal(FileNodeItem {
             path: path.clone(),
@@ -123,16 +197,46 @@ pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
         });
         let naming = cx.create_rw_signal(Naming::None);
         let naming_editor_data = editors.make_local(cx, common.clone());
+        let filter_editor_data = editors.make_local(cx, common.clone());
+        let filter = {
+            let buffer = filter_editor_data.doc().buffer;
+            cx.create_memo(move |_| buffer.with(|buffer| buffer.to_string()))
+        };
         let data = Self {
             root,
             naming,
             naming_editor_data,
+            filter_editor_data,
+            filter,
+            filter_focused: cx.create_rw_signal(false),
+            select: cx.create_rw_signal(None),
+            drag_over: cx.create_rw_signal(None),
+            scroll_to_line: cx.create_rw_signal(None),
+            show_hidden: cx.create_rw_signal(false),
+            clipboard: cx.create_rw_signal(None),
+            ignored,
             common,
             left_diff_path: cx.create_rw_signal(None),
         };
         if data.common.workspace.path.is_some() {
             // only fill in the child files if there is open folder
             data.toggle_expand(&path);
+
+            // Refilter the tree when the ignored files change
+            let config = data.common.config;
+            let hide_gitignored = cx.create_memo(move |_| {
+                config.with(|config| config.ui.file_explorer_hide_gitignored)
+            });
+            let data = data.clone();
+            cx.create_effect(move |prev: Option<bool>| {
+                ignored.track();
+                let hide_gitignored = hide_gitignored.get();
+                // Reload when hiding ignored files is, or just was, enabled
+                if prev.is_some_and(|prev| prev || hide_gitignored) {
+                    data.reload();
+                }
+                hide_gitignored
+            });
         }
         data
     }
@@ -140,6 +244,16 @@ pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
     /// Reload the file explorer data via reading the root directory.  
     /// Note that this will not update immediately.
     pub fn reload(&self) {
+        // Cached listings are checked against the directories' modification times
+        self.common.fs_cache.invalidate_all();
+        let path = self.root.with_untracked(|root| root.path.clone());
+        self.read_dir(&path);
+    }
+
+    /// Reload the directories affected by `paths` being created, removed or renamed,
+    /// reusing the cached listings of the others.
+    pub fn files_changed(&self, paths: &[PathBuf]) {
+        self.common.fs_cache.invalidate(paths);
         let path = self.root.with_untracked(|root| root.path.clone());
         self.read_dir(&path);
     }
@@ -176,6 +290,73 @@ pub fn toggle_expand(&self, path: &Path) {
         }
     }
 
+    /// Expand the tree down to `path`, then select it and scroll it into view.  
+    /// Directories that haven't been read yet are read first, so this may not update
+    /// immediately.
+    pub fn reveal_path(&self, path: &Path) {
+        let root_path = self.root.with_untracked(|root| root.path.clone());
+        let dirs: Vec<PathBuf> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|p| p.starts_with(&root_path))
+            .map(PathBuf::from)
+            .collect();
+        if dirs.is_empty() {
+            return;
+        }
+
+        for dir in dirs.iter().rev() {
+            let read = self
+                .root
+                .try_update(|root| {
+                    let node = root.get_file_node_mut(dir)?;
+                    let read = node.read;
+                    if !node.open {
+                        node.open = true;
+                        root.update_node_count_recursive(dir);
+                    }
+                    Some(read)
+                })
+                .flatten();
+            match read {
+                Some(true) => {}
+                Some(false) => {
+                    // Continue revealing once the directory's children are known
+                    let data = self.clone();
+                    let path = path.to_path_buf();
+                    self.read_dir_cb(dir, move |was_read| {
+                        if was_read {
+                            data.reveal_path(&path);
+                        }
+                    });
+                    return;
+                }
+                None => return,
+            }
+        }
+
+        self.select.set(Some(path.to_path_buf()));
+        if let Some(line) = self.root.with_untracked(|root| root.view_index(path)) {
+            self.scroll_to_line.set(Some(line));
+        }
+    }
+
+    /// Clear the filter input, showing the whole tree again.
+    pub fn clear_filter(&self) {
+        self.filter_focused.set(false);
+        self.filter_editor_data.doc().reload(Rope::from(""), true);
+        self.filter_editor_data
+            .cursor()
+            .update(|cursor| cursor.set_insert(Selection::caret(0)));
+    }
+
+    /// Toggle temporarily showing the files that are normally hidden from the tree.
+    pub fn toggle_show_hidden(&self) {
+        self.show_hidden
+            .update(|show_hidden| *show_hidden = !*show_hidden);
+        self.reload();
+    }
+
     pub fn read_dir(&self, path: &Path) {
         self.read_dir_cb(path, |_| {});
     }
@@ -187,30 +368,62 @@ pub fn read_dir_cb(&self, path: &Path, done: impl FnOnce(bool) + 'static) {
         let root = self.root;
         let data = self.clone();
         let config = self.common.config;
+        let show_hidden = self.show_hidden;
+        let ignored = self.ignored;
+        let fs_cache = self.common.fs_cache.clone();
         let send = {
             let path = path.to_path_buf();
             create_ext_action(self.common.scope, move |result| {
-                let Ok(ProxyResponse::ReadDirResponse { mut items }) = result else {
-                    done(false);
-                    return;
+                let mut items = match result {
+                    Ok(ProxyResponse::ReadDirResponse { items, mtime }) => {
+                        fs_cache.set_dir(path.clone(), items.clone(), mtime);
+                        items
+                    }
+                    Ok(ProxyResponse::ReadDirUnchangedResponse {}) => {
+                        match fs_cache.revalidate_dir(&path) {
+                            Some(items) => items,
+                            None => {
+                                done(false);
+                                return;
+                            }
+                        }
+                    }
+                    _ => {
+                        done(false);
+                        return;
+                    }
                 };
 
                 root.update(|root| {
                     // Get the node for this path, which should already exist if we're calling
                     // read_dir on it.
                     if let Some(node) = root.get_file_node_mut(&path) {
-                        // TODO: do not recreate glob every time we read a directory
-                        // Retain only items that are not excluded from view by the configuration
-                        match Glob::new(&config.get().editor.files_exclude) {
-                            Ok(glob) => {
-                                let matcher = glob.compile_matcher();
-                                items.retain(|i| !matcher.is_match(&i.path));
+                        if !show_hidden.get_untracked() {
+                            let config = config.get_untracked();
+                            // TODO: do not recreate glob every time we read a directory
+                            // Retain only items that are not excluded from view by the
+                            // configuration
+                            match Glob::new(&config.editor.files_exclude) {
+                                Ok(glob) => {
+                                    let matcher = glob.compile_matcher();
+                                    items.retain(|i| !matcher.is_match(&i.path));
+                                }
+                                Err(e) => tracing::error!(
+                                    target:"files_exclude",
+                                    "Failed to compile glob: {}",
+                                    e
+                                ),
+                            }
+
+                            if config.ui.file_explorer_hide_gitignored {
+                                ignored.with_untracked(|ignored| {
+                                    items.retain(|i| {
+                                        !ignored
+                                            .iter()
+                                            .any(|p| i.path.starts_with(p))
+                                    });
+                                });
                             }
-                            Err(e) => tracing::error!(
-                                target:"files_exclude",
-                                "Failed to compile glob: {}",
-                                e
-                            ),
                         }
 
                         node.read = true;
@@ -244,8 +457,14 @@ pub fn read_dir_cb(&self, path: &Path, done: impl FnOnce(bool) + 'static) {
             })
         };
 
+        let mtime = self.common.fs_cache.dir_mtime(path);
+        if let Some(items) = self.common.fs_cache.dir(path) {
+            send(Ok(ProxyResponse::ReadDirResponse { items, mtime }));
+            return;
+        }
+
         // Ask the proxy for the directory information
-        self.common.proxy.read_dir(path.to_path_buf(), send);
+        self.common.proxy.read_dir(path.to_path_buf(), mtime, send);
     }
 
     /// Returns `true` if `path` exists in the file explorer tree and is a directory, `false`
@@ -387,7 +606,376 @@ pub fn cancel_naming(&self) {
         self.naming.set(Naming::None);
     }
 
+    /// The directory that new nodes are created in when triggered from the keyboard: the
+    /// selected directory, the parent of the selected file, or the workspace root.
+    fn selected_base_path(&self) -> Option<PathBuf> {
+        let workspace_path = self.common.workspace.path.as_ref()?;
+        let base_path = self.select.get_untracked().and_then(|path| {
+            if self.is_dir(&path) {
+                Some(path)
+            } else {
+                path.parent().map(ToOwned::to_owned)
+            }
+        });
+
+        Some(base_path.unwrap_or_else(|| workspace_path.clone()))
+    }
+
+    /// The selected path, if it is something that can be renamed, duplicated or deleted.
+    fn selected_child_path(&self) -> Option<PathBuf> {
+        let path = self.select.get_untracked()?;
+        if self.common.workspace.path.as_ref() == Some(&path) {
+            return None;
+        }
+
+        Some(path)
+    }
+
+    /// Start naming a new file or directory inside of `base_path`.
+    pub fn new_node(&self, base_path: &Path, is_dir: bool) {
+        let naming = self.naming;
+        let base_path_b = base_path.to_path_buf();
+        self.read_dir_cb(base_path, move |was_read| {
+            if !was_read {
+                tracing::warn!(
+                    "Failed to read directory, avoiding creating node in: {:?}",
+                    base_path_b
+                );
+                return;
+            }
+
+            naming.set(Naming::NewNode(NewNode {
+                state: NamingState::Naming,
+                base_path: base_path_b.clone(),
+                is_dir,
+                editor_needs_reset: true,
+            }));
+        });
+    }
+
+    /// Start renaming the node at `path` inline.
+    pub fn rename(&self, path: &Path) {
+        self.naming.set(Naming::Renaming(Renaming {
+            state: NamingState::Naming,
+            path: path.to_path_buf(),
+            editor_needs_reset: true,
+        }));
+    }
+
+    /// Start naming the duplicate of the node at `path`.
+    pub fn duplicate(&self, path: &Path) {
+        self.naming.set(Naming::Duplicating(Duplicating {
+            state: NamingState::Naming,
+            path: path.to_path_buf(),
+            editor_needs_reset: true,
+        }));
+    }
+
+    /// Ask for confirmation, then move the node at `path` to the trash.
+    pub fn trash(&self, path: &Path) {
+        let is_dir = self.is_dir(path);
+        let file_name = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .to_string();
+
+        let internal_command = self.common.internal_command;
+        let proxy = self.common.proxy.clone();
+        let select = self.select;
+        let path = path.to_path_buf();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("Are you sure you want to delete '{file_name}'?"),
+            msg: if is_dir {
+                "The directory and all of its contents will be moved to the trash."
+            } else {
+                "The file will be moved to the trash."
+            }
+            .to_string(),
+            buttons: vec![AlertButton {
+                text: "Move to Trash".to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    if select.get_untracked().as_ref() == Some(&path) {
+                        select.set(None);
+                    }
+                    proxy.trash_path(path.clone(), |res| {
+                        if let Err(err) = res {
+                            tracing::warn!("Failed to trash path: {:?}", err);
+                        }
+                    });
+                }),
+            }],
+        });
+    }
+
+    pub fn new_node_at_selection(&self, is_dir: bool) {
+        if let Some(base_path) = self.selected_base_path() {
+            self.new_node(&base_path, is_dir);
+        }
+    }
+
+    pub fn rename_selection(&self) {
+        if let Some(path) = self.selected_child_path() {
+            self.rename(&path);
+        }
+    }
+
+    pub fn duplicate_selection(&self) {
+        if let Some(path) = self.selected_child_path() {
+            self.duplicate(&path);
+        }
+    }
+
+    pub fn trash_selection(&self) {
+        if let Some(path) = self.selected_child_path() {
+            self.trash(&path);
+        }
+    }
+
+    pub fn cut_selection(&self) {
+        if let Some(path) = self.selected_child_path() {
+            self.cut(&path);
+        }
+    }
+
+    pub fn copy_selection(&self) {
+        if let Some(path) = self.selected_child_path() {
+            self.copy(&path);
+        }
+    }
+
+    pub fn paste_selection(&self) {
+        if let Some(base_path) = self.selected_base_path() {
+            self.paste(&base_path);
+        }
+    }
+
+    pub fn copy_path_selection(&self) {
+        if let Some(path) = self.select.get_untracked() {
+            self.copy_path(&path);
+        }
+    }
+
+    pub fn copy_relative_path_selection(&self) {
+        if let Some(path) = self.select.get_untracked() {
+            self.copy_relative_path(&path);
+        }
+    }
+
+    pub fn open_in_terminal_selection(&self) {
+        if let Some(base_path) = self.selected_base_path() {
+            self.open_in_terminal(&base_path);
+        }
+    }
+
+    pub fn reveal_in_file_manager_selection(&self) {
+        if self.common.workspace.kind.is_remote() {
+            return;
+        }
+
+        if let Some(path) = self.select.get_untracked() {
+            reveal_in_file_manager(&path);
+        }
+    }
+
+    /// Open a new terminal tab in the directory of the node at `path`.
+    pub fn open_in_terminal(&self, path: &Path) {
+        if let Some(path) = self.drop_target(path) {
+            self.common
+                .internal_command
+                .send(InternalCommand::NewTerminalInDirectory { path });
+        }
+    }
+
+    /// Mark the node at `path` to be moved by the next paste.
+    pub fn cut(&self, path: &Path) {
+        self.clipboard.set(Some(FileClipboard {
+            path: path.to_path_buf(),
+            cut: true,
+        }));
+    }
+
+    /// Mark the node at `path` to be copied by the next paste.
+    pub fn copy(&self, path: &Path) {
+        self.clipboard.set(Some(FileClipboard {
+            path: path.to_path_buf(),
+            cut: false,
+        }));
+    }
+
+    /// Move or copy the cut or copied node into the directory of the node at `path`.  
+    /// Copying a node into its own directory creates a copy next to it with a new name.
+    pub fn paste(&self, path: &Path) {
+        let Some(clipboard) = self.clipboard.get_untracked() else {
+            return;
+        };
+        let Some(target_dir) = self.drop_target(path) else {
+            return;
+        };
+
+        if clipboard.cut {
+            // The cut node has been moved, so it can't be pasted again
+            self.clipboard.set(None);
+        }
+
+        self.drop_path(&clipboard.path, &target_dir, !clipboard.cut);
+    }
+
+    /// A path next to `path` that nothing exists at yet, used for copying a node into its
+    /// own directory. For `name.ext` it is `name copy.ext`, `name copy 2.ext`, ...
+    fn copy_name(&self, path: &Path) -> PathBuf {
+        let stem = path
+            .file_stem()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .to_string();
+        let extension = if self.is_dir(path) {
+            None
+        } else {
+            path.extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+        };
+
+        let mut n = 1;
+        loop {
+            let mut file_name = if n == 1 {
+                format!("{stem} copy")
+            } else {
+                format!("{stem} copy {n}")
+            };
+            if let Some(extension) = &extension {
+                file_name.push('.');
+                file_name.push_str(extension);
+            }
+
+            let new_path = path.with_file_name(file_name);
+            let exists = self
+                .root
+                .with_untracked(|root| root.get_file_node(&new_path).is_some());
+            if !exists {
+                return new_path;
+            }
+            n += 1;
+        }
+    }
+
+    /// Put the absolute path of the node at `path` on the system clipboard.
+    pub fn copy_path(&self, path: &Path) {
+        let mut clipboard = SystemClipboard::new();
+        clipboard.put_string(path.to_string_lossy());
+    }
+
+    /// Put the path of the node at `path` relative to the workspace on the system clipboard.
+    pub fn copy_relative_path(&self, path: &Path) {
+        let relative_path = if let Some(workspace_path) = &self.common.workspace.path
+        {
+            path.strip_prefix(workspace_path).unwrap_or(path)
+        } else {
+            path
+        };
+
+        let mut clipboard = SystemClipboard::new();
+        clipboard.put_string(relative_path.to_string_lossy());
+    }
+
+    /// The directory that something dropped onto the node at `path` ends up in.
+    pub fn drop_target(&self, path: &Path) -> Option<PathBuf> {
+        if self.is_dir(path) {
+            Some(path.to_path_buf())
+        } else {
+            path.parent().map(ToOwned::to_owned)
+        }
+    }
+
+    /// Move the node at `current_path` into the directory `target_dir`, or copy it there if
+    /// `copy` is true.  
+    /// Copying a node into its own directory creates a copy next to it with a new name. If
+    /// something else already exists at the destination the user is asked whether to replace it.
+    pub fn drop_path(&self, current_path: &Path, target_dir: &Path, copy: bool) {
+        let Some(file_name) = current_path.file_name() else {
+            return;
+        };
+
+        // Moving a node to where it already is does nothing, and a directory can't be moved
+        // inside of itself.
+        if (!copy && current_path.parent() == Some(target_dir))
+            || target_dir.starts_with(current_path)
+        {
+            return;
+        }
+
+        let internal_command = self.common.internal_command;
+        if current_path.parent() == Some(target_dir) {
+            internal_command.send(InternalCommand::MovePath {
+                current_path: current_path.to_path_buf(),
+                new_path: self.copy_name(current_path),
+                copy: true,
+            });
+            return;
+        }
+
+        let new_path = target_dir.join(file_name);
+        // Replacing the node with itself would trash it before it's copied
+        if new_path == current_path {
+            return;
+        }
+        let exists = self
+            .root
+            .with_untracked(|root| root.get_file_node(&new_path).is_some());
+        if !exists {
+            internal_command.send(InternalCommand::MovePath {
+                current_path: current_path.to_path_buf(),
+                new_path,
+                copy,
+            });
+            return;
+        }
+
+        let scope = self.common.scope;
+        let proxy = self.common.proxy.clone();
+        let current_path = current_path.to_path_buf();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!(
+                "'{}' already exists in '{}'. Do you want to replace it?",
+                file_name.to_string_lossy(),
+                target_dir
+                    .file_name()
+                    .unwrap_or(target_dir.as_os_str())
+                    .to_string_lossy()
+            ),
+            msg: "The existing file or directory will be moved to the trash."
+                .to_string(),
+            buttons: vec![AlertButton {
+                text: "Replace".to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+
+                    let current_path = current_path.clone();
+                    let new_path = new_path.clone();
+                    let send = create_ext_action(scope, {
+                        let new_path = new_path.clone();
+                        move |result: Result<ProxyResponse, RpcError>| {
+                            if let Err(err) = result {
+                                tracing::warn!("Failed to trash path: {:?}", err);
+                                return;
+                            }
+
+                            internal_command.send(InternalCommand::MovePath {
+                                current_path,
+                                new_path,
+                                copy,
+                            });
+                        }
+                    });
+                    proxy.trash_path(new_path, send);
+                }),
+            }],
+        });
+    }
+
     pub fn click(&self, path: &Path) {
+        self.select.set(Some(path.to_path_buf()));
         if self.is_dir(path) {
             self.toggle_expand(path);
         } else {
@@ -423,6 +1011,7 @@ pub fn secondary_click(&self, path: &Path) {
         };
 
         let is_workspace = path == workspace_path;
+        self.select.set(Some(path_a.clone()));
 
         let base_path_a = if is_dir {
             Some(path_a.clone())
@@ -435,50 +1024,14 @@ pub fn secondary_click(&self, path: &Path) {
 
         let base_path = base_path_a.clone();
         let data = self.clone();
-        let naming = self.naming;
         menu = menu.entry(MenuItem::new("New File").action(move || {
-            let base_path_b = &base_path;
-            let base_path = base_path.clone();
-            data.read_dir_cb(base_path_b, move |was_read| {
-                if !was_read {
-                    tracing::warn!(
-                        "Failed to read directory, avoiding creating node in: {:?}",
-                        base_path
-                    );
-                    return;
-                }
-
-                naming.set(Naming::NewNode(NewNode {
-                    state: NamingState::Naming,
-                    base_path: base_path.clone(),
-                    is_dir: false,
-                    editor_needs_reset: true,
-                }));
-            });
+            data.new_node(&base_path, false);
         }));
 
         let base_path = base_path_a.clone();
         let data = self.clone();
-        let naming = self.naming;
         menu = menu.entry(MenuItem::new("New Directory").action(move || {
-            let base_path_b = &base_path;
-            let base_path = base_path.clone();
-            data.read_dir_cb(base_path_b, move |was_read| {
-                if !was_read {
-                    tracing::warn!(
-                        "Failed to read directory, avoiding creating node in: {:?}",
-                        base_path
-                    );
-                    return;
-                }
-
-                naming.set(Naming::NewNode(NewNode {
-                    state: NamingState::Naming,
-                    base_path: base_path.clone(),
-                    is_dir: true,
-                    editor_needs_reset: true,
-                }));
-            })
+            data.new_node(&base_path, true);
         }));
 
         menu = menu.separator();
@@ -486,79 +1039,88 @@ pub fn secondary_click(&self, path: &Path) {
         // TODO: there are situations where we can open the file explorer to remote files
         if !common.workspace.kind.is_remote() {
             let path = path_a.clone();
-            menu = menu.entry(MenuItem::new("Reveal in file explorer").action(
+            menu = menu.entry(MenuItem::new(REVEAL_IN_FILE_MANAGER).action(
                 move || {
-                    let path = path.parent().unwrap_or(&path);
-                    if !path.exists() {
-                        return;
-                    }
-
-                    if let Err(err) = open::that(path) {
-                        tracing::error!(
-                            "Failed to reveal file in system file explorer: {}",
-                            err
-                        );
-                    }
+                    reveal_in_file_manager(&path);
                 },
             ));
         }
 
+        let path = path_a.clone();
+        let data = self.clone();
+        menu = menu.entry(MenuItem::new("Open in Integrated Terminal").action(
+            move || {
+                data.open_in_terminal(&path);
+            },
+        ));
+
+        menu = menu.separator();
+
         if !is_workspace {
             let path = path_a.clone();
+            let data = self.clone();
             menu = menu.entry(MenuItem::new("Rename").action(move || {
-                naming.set(Naming::Renaming(Renaming {
-                    state: NamingState::Naming,
-                    path: path.clone(),
-                    editor_needs_reset: true,
-                }));
+                data.rename(&path);
             }));
 
             let path = path_a.clone();
+            let data = self.clone();
             menu = menu.entry(MenuItem::new("Duplicate").action(move || {
-                naming.set(Naming::Duplicating(Duplicating {
-                    state: NamingState::Naming,
-                    path: path.clone(),
-                    editor_needs_reset: true,
-                }));
+                data.duplicate(&path);
             }));
 
             // TODO: it is common for shift+right click to make 'Move file to trash' an actual
             // Delete, which can be useful for large files.
             let path = path_a.clone();
-            let proxy = common.proxy.clone();
+            let data = self.clone();
             let trash_text = if is_dir {
                 "Move Directory to Trash"
             } else {
                 "Move File to Trash"
             };
             menu = menu.entry(MenuItem::new(trash_text).action(move || {
-                proxy.trash_path(path.clone(), |res| {
-                    if let Err(err) = res {
-                        tracing::warn!("Failed to trash path: {:?}", err);
-                    }
-                })
+                data.trash(&path);
+            }));
+        }
+
+        menu = menu.separator();
+
+        if !is_workspace {
+            let path = path_a.clone();
+            let data = self.clone();
+            menu = menu.entry(MenuItem::new("Cut").action(move || {
+                data.cut(&path);
+            }));
+
+            let path = path_a.clone();
+            let data = self.clone();
+            menu = menu.entry(MenuItem::new("Copy").action(move || {
+                data.copy(&path);
             }));
         }
 
+        let path = path_a.clone();
+        let data = self.clone();
+        menu = menu.entry(
+            MenuItem::new("Paste")
+                .enabled(self.clipboard.with_untracked(Option::is_some))
+                .action(move || {
+                    data.paste(&path);
+                }),
+        );
+
         menu = menu.separator();
 
         let path = path_a.clone();
+        let data = self.clone();
         menu = menu.entry(MenuItem::new("Copy Path").action(move || {
-            let mut clipboard = SystemClipboard::new();
-            clipboard.put_string(path.to_string_lossy());
+            data.copy_path(&path);
         }));
 
         let path = path_a.clone();
-        let workspace = common.workspace.clone();
+        let data = self.clone();
         menu = menu.entry(MenuItem::new("Copy Relative Path").action(move || {
-            let relative_path = if let Some(workspace_path) = &workspace.path {
-                path.strip_prefix(workspace_path).unwrap_or(&path)
-            } else {
-                path.as_ref()
-            };
-
-            let mut clipboard = SystemClipboard::new();
-            clipboard.put_string(relative_path.to_string_lossy());
+            data.copy_relative_path(&path);
         }));
 
         menu = menu.separator();
@@ -608,3 +1170,72 @@ pub fn middle_click(&self, path: &Path) -> EventPropagation {
         }
     }
 }
+
+/// Show the local `path` selected in the system file manager.  
+/// Falls back to opening the directory containing it if the file manager can't select it.
+pub fn reveal_in_file_manager(path: &Path) {
+    let path = path.to_path_buf();
+    std::thread::spawn(move || {
+        if !path.exists() {
+            return;
+        }
+
+        if !select_in_file_manager(&path) {
+            let dir = if path.is_dir() {
+                &path
+            } else {
+                path.parent().unwrap_or(&path)
+            };
+            if let Err(err) = open::that(dir) {
+                tracing::error!(
+                    "Failed to reveal file in system file explorer: {}",
+                    err
+                );
+            }
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+fn select_in_file_manager(path: &Path) -> bool {
+    std::process::Command::new("open")
+        .arg("-R")
+        .arg(path)
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(target_os = "windows")]
+fn select_in_file_manager(path: &Path) -> bool {
+    use std::os::windows::process::CommandExt;
+    // Explorer exits with an error code even when it succeeds, so only check that it started
+    std::process::Command::new("explorer")
+        .raw_arg(format!("/select,\"{}\"", path.display()))
+        .spawn()
+        .is_ok()
+}
+
+/// Ask the file manager to select the path through the freedesktop `FileManager1` D-Bus
+/// interface, which Nautilus, Dolphin, Nemo and others implement.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn select_in_file_manager(path: &Path) -> bool {
+    let Ok(uri) = url::Url::from_file_path(path) else {
+        return false;
+    };
+
+    std::process::Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+            &format!("array:string:{uri}"),
+            "string:",
+        ])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
@@ -1,29 +1,97 @@
 use floem::views::VirtualVector;
-use lapce_rpc::file::{FileNodeItem, FileNodeViewData, Naming};
+use lapce_rpc::file::{FileNodeItem, FileNodeViewData, FileNodeViewKind, Naming};
+use nucleo::Utf32Str;
 
 pub struct FileNodeVirtualList {
     file_node_item: FileNodeItem,
     naming: Naming,
+    /// The entries matching the explorer's filter, if there is one
+    filtered: Option<Vec<FileNodeViewData>>,
 }
 
 impl FileNodeVirtualList {
-    pub fn new(file_node_item: FileNodeItem, naming: Naming) -> Self {
+    pub fn new(file_node_item: FileNodeItem, naming: Naming, filter: &str) -> Self {
+        let filtered = (!filter.is_empty()).then(|| {
+            let pattern = nucleo::pattern::Pattern::parse(
+                filter,
+                nucleo::pattern::CaseMatching::Ignore,
+                nucleo::pattern::Normalization::Smart,
+            );
+            let mut matcher = nucleo::Matcher::new(nucleo::Config::DEFAULT);
+            let mut items = Vec::new();
+            append_filtered(&file_node_item, &pattern, &mut matcher, 1, &mut items);
+            items
+        });
+
         Self {
             file_node_item,
             naming,
+            filtered,
+        }
+    }
+}
+
+/// Append the read descendants of `node` whose file name fuzzily matches `pattern`, along with
+/// the directories leading to them, which are shown expanded.
+/// Returns whether anything was appended.
+fn append_filtered(
+    node: &FileNodeItem,
+    pattern: &nucleo::pattern::Pattern,
+    matcher: &mut nucleo::Matcher,
+    level: usize,
+    items: &mut Vec<FileNodeViewData>,
+) -> bool {
+    let mut appended = false;
+    let mut buf = Vec::new();
+    for child in node.sorted_children() {
+        let file_name = child
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let is_match = pattern
+            .score(Utf32Str::new(&file_name, &mut buf), matcher)
+            .is_some();
+
+        let index = items.len();
+        items.push(FileNodeViewData {
+            kind: FileNodeViewKind::Path(child.path.clone()),
+            is_dir: child.is_dir,
+            open: false,
+            level,
+        });
+        let has_matching_children =
+            append_filtered(child, pattern, matcher, level + 1, items);
+
+        if has_matching_children {
+            items[index].open = true;
+        } else if !is_match {
+            items.truncate(index);
+            continue;
         }
+        appended = true;
     }
+    appended
 }
 
 impl VirtualVector<FileNodeViewData> for FileNodeVirtualList {
     fn total_len(&self) -> usize {
-        self.file_node_item.children_open_count
+        match &self.filtered {
+            Some(filtered) => filtered.len(),
+            None => self.file_node_item.children_open_count,
+        }
     }
 
     fn slice(
         &mut self,
         range: std::ops::Range<usize>,
     ) -> impl Iterator<Item = FileNodeViewData> {
+        if let Some(filtered) = &self.filtered {
+            let start = range.start.min(filtered.len());
+            let end = range.end.min(filtered.len());
+            return filtered[start..end].to_vec().into_iter();
+        }
+
         let naming = &self.naming;
         let root = &self.file_node_item;
 
@@ -2,8 +2,12 @@
 
 use floem::{
     cosmic_text::Style as FontStyle,
-    event::{Event, EventListener},
-    peniko::Color,
+    event::{Event, EventListener, EventPropagation},
+    keyboard::Modifiers,
+    peniko::{
+        kurbo::{Point, Size},
+        Color,
+    },
     reactive::{create_rw_signal, ReadSignal, RwSignal},
     style::{AlignItems, CursorStyle, Position, Style},
     views::{
@@ -15,7 +19,7 @@
 use lapce_core::selection::Selection;
 use lapce_rpc::{
     file::{FileNodeViewData, FileNodeViewKind, Naming},
-    source_control::FileDiffKind,
+    source_control::{FileDiff, FileDiffKind},
 };
 use lapce_xi_rope::Rope;
 
@@ -32,7 +36,7 @@
     plugin::PluginData,
     source_control::SourceControlData,
     text_input::TextInputBuilder,
-    window_tab::{Focus, WindowTabData},
+    window_tab::{DragContent, Focus, WindowTabData},
 };
 
 /// Blends `foreground` with `background`.
@@ -86,10 +90,14 @@ pub fn file_explorer_panel(
         )
         .add(
             "File Explorer",
-            container(
-                new_file_node_view(data, source_control).style(|s| s.absolute()),
-            )
-            .style(|s| s.size_full().line_height(1.6)),
+            stack((
+                file_explorer_filter_view(data.clone()),
+                container(
+                    new_file_node_view(data, source_control).style(|s| s.absolute()),
+                )
+                .style(|s| s.width_full().flex_grow(1.0).min_height(0.0)),
+            ))
+            .style(|s| s.size_full().flex_col().line_height(1.6)),
             window_tab_data
                 .panel
                 .section_open(PanelSection::FileExplorer),
@@ -133,34 +141,97 @@ fn initialize_naming_editor(
         .update(|naming| naming.set_editor_needs_reset(false));
 }
 
-fn file_node_text_color(
+/// The git status of a node in the file explorer.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FileNodeGitStatus {
+    Modified,
+    Added,
+    Untracked,
+    Deleted,
+    Renamed,
+    Ignored,
+}
+
+impl FileNodeGitStatus {
+    fn color(self) -> &'static str {
+        match self {
+            FileNodeGitStatus::Modified | FileNodeGitStatus::Renamed => {
+                LapceColor::SOURCE_CONTROL_MODIFIED
+            }
+            FileNodeGitStatus::Added | FileNodeGitStatus::Untracked => {
+                LapceColor::SOURCE_CONTROL_ADDED
+            }
+            FileNodeGitStatus::Deleted => LapceColor::SOURCE_CONTROL_REMOVED,
+            FileNodeGitStatus::Ignored => LapceColor::PANEL_FOREGROUND_DIM,
+        }
+    }
+
+    /// The short badge shown next to the file name
+    fn badge(self) -> &'static str {
+        match self {
+            FileNodeGitStatus::Modified => "M",
+            FileNodeGitStatus::Added => "A",
+            FileNodeGitStatus::Untracked => "U",
+            FileNodeGitStatus::Deleted => "D",
+            FileNodeGitStatus::Renamed => "R",
+            FileNodeGitStatus::Ignored => "",
+        }
+    }
+}
+
+fn file_node_git_status(
     config: ReadSignal<Arc<LapceConfig>>,
-    node: FileNodeViewData,
-    source_control: SourceControlData,
-) -> Color {
-    let diff = source_control.file_diffs.with(|file_diffs| {
-        let FileNodeViewKind::Path(path) = &node.kind else {
-            return None;
-        };
+    node: &FileNodeViewData,
+    source_control: &SourceControlData,
+) -> Option<FileNodeGitStatus> {
+    if !config.with(|config| config.ui.file_explorer_git_status) {
+        return None;
+    }
 
+    let FileNodeViewKind::Path(path) = &node.kind else {
+        return None;
+    };
+
+    let diff = source_control.file_diffs.with(|file_diffs| {
         if node.is_dir {
             file_diffs
                 .keys()
                 .find(|p| p.as_path().starts_with(path))
                 .map(|_| FileDiffKind::Modified)
         } else {
-            file_diffs.get(path).map(|(diff, _)| diff.kind())
+            file_diffs.get(path).map(FileDiff::kind)
         }
     });
 
-    let color = match diff {
-        Some(FileDiffKind::Modified | FileDiffKind::Renamed) => {
-            LapceColor::SOURCE_CONTROL_MODIFIED
+    match diff {
+        Some(FileDiffKind::Modified) => Some(FileNodeGitStatus::Modified),
+        Some(FileDiffKind::Added) => {
+            if source_control
+                .untracked
+                .with(|untracked| untracked.contains(path))
+            {
+                Some(FileNodeGitStatus::Untracked)
+            } else {
+                Some(FileNodeGitStatus::Added)
+            }
         }
-        Some(FileDiffKind::Added) => LapceColor::SOURCE_CONTROL_ADDED,
-        Some(FileDiffKind::Deleted) => LapceColor::SOURCE_CONTROL_REMOVED,
-        None => LapceColor::PANEL_FOREGROUND,
-    };
+        Some(FileDiffKind::Deleted) => Some(FileNodeGitStatus::Deleted),
+        Some(FileDiffKind::Renamed) => Some(FileNodeGitStatus::Renamed),
+        None => source_control
+            .ignored
+            .with(|ignored| ignored.iter().any(|p| path.starts_with(p)))
+            .then_some(FileNodeGitStatus::Ignored),
+    }
+}
+
+fn file_node_text_color(
+    config: ReadSignal<Arc<LapceConfig>>,
+    node: FileNodeViewData,
+    source_control: SourceControlData,
+) -> Color {
+    let color = file_node_git_status(config, &node, &source_control)
+        .map(FileNodeGitStatus::color)
+        .unwrap_or(LapceColor::PANEL_FOREGROUND);
 
     config.get().color(color)
 }
@@ -174,23 +245,67 @@ fn file_node_text_view(
     let ui_line_height = data.common.ui_line_height;
 
     let view = match node.kind.clone() {
-        FileNodeViewKind::Path(path) => container(
-            label(move || {
-                path.file_name()
-                    .map(|f| f.to_string_lossy().to_string())
-                    .unwrap_or_default()
-            })
-            .style(move |s| {
-                s.flex_grow(1.0)
-                    .height(ui_line_height.get())
-                    .color(file_node_text_color(
-                        config,
-                        node.clone(),
-                        source_control.clone(),
-                    ))
-                    .selectable(false)
-            }),
-        ),
+        FileNodeViewKind::Path(path) => {
+            let git_status = {
+                let node = node.clone();
+                let source_control = source_control.clone();
+                move || file_node_git_status(config, &node, &source_control)
+            };
+            let clipboard = data.clipboard;
+            let is_cut = {
+                let path = path.clone();
+                move || {
+                    clipboard.with(|clipboard| {
+                        clipboard.as_ref().is_some_and(|clipboard| {
+                            clipboard.cut && clipboard.path == path
+                        })
+                    })
+                }
+            };
+            container(stack((
+                label(move || {
+                    path.file_name()
+                        .map(|f| f.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                })
+                .style(move |s| {
+                    // A cut node is dimmed until it is pasted
+                    let color = if is_cut() {
+                        config.get().color(LapceColor::PANEL_FOREGROUND_DIM)
+                    } else {
+                        file_node_text_color(
+                            config,
+                            node.clone(),
+                            source_control.clone(),
+                        )
+                    };
+                    s.flex_grow(1.0)
+                        .height(ui_line_height.get())
+                        .color(color)
+                        .selectable(false)
+                }),
+                label({
+                    let git_status = git_status.clone();
+                    move || {
+                        git_status()
+                            .map(FileNodeGitStatus::badge)
+                            .unwrap_or_default()
+                            .to_string()
+                    }
+                })
+                .style(move |s| {
+                    let status = git_status();
+                    s.flex_shrink(0.0)
+                        .margin_left(6.0)
+                        .height(ui_line_height.get())
+                        .selectable(false)
+                        .apply_opt(status, |s, status| {
+                            s.color(config.get().color(status.color()))
+                        })
+                }),
+            )))
+            .style(|s| s.items_center())
+        }
         FileNodeViewKind::Renaming { path, err } => {
             if data.naming.with_untracked(Naming::editor_needs_reset) {
                 initialize_naming_editor_with_path(&data, &path);
@@ -217,6 +332,35 @@ fn file_node_text_view(
     view.style(|s| s.flex_grow(1.0).padding(0.0).margin(0.0))
 }
 
+/// Input used for filtering the file explorer tree
+fn file_explorer_filter_view(data: FileExplorerData) -> impl View {
+    let focus = data.common.focus;
+    let config = data.common.config;
+    let filter_focused = data.filter_focused;
+
+    let is_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::FileExplorer) && filter_focused.get()
+    };
+    container(
+        TextInputBuilder::new()
+            .is_focused(is_focused)
+            .key_focus(data.clone())
+            .build_editor(data.filter_editor_data.clone())
+            .placeholder(|| "Filter".to_string())
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                filter_focused.set(true);
+                focus.set(Focus::Panel(PanelKind::FileExplorer));
+            })
+            .style(move |s| {
+                s.width_full()
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+            }),
+    )
+    .style(|s| s.width_full().padding(6.0))
+}
+
 /// Input used for naming a file/directory
 fn file_node_input_view(data: FileExplorerData, err: Option<String>) -> Container {
     let ui_line_height = data.common.ui_line_height;
@@ -290,6 +434,14 @@ fn new_file_node_view(
     let ui_line_height = data.common.ui_line_height;
     let config = data.common.config;
     let naming = data.naming;
+    let filter = data.filter;
+    let filter_focused = data.filter_focused;
+    let select = data.select;
+    let drag_over = data.drag_over;
+    let scroll_to_line = data.scroll_to_line;
+    let dragging = data.common.dragging;
+    let focus = data.common.focus;
+    let drop_data = data.clone();
 
     let secondary_click_data = data.clone();
 
@@ -297,7 +449,13 @@ fn new_file_node_view(
         virtual_stack(
             VirtualDirection::Vertical,
             VirtualItemSize::Fixed(Box::new(move || ui_line_height.get())),
-            move || FileNodeVirtualList::new(root.get(), data.naming.get()),
+            move || {
+                FileNodeVirtualList::new(
+                    root.get(),
+                    data.naming.get(),
+                    &filter.get(),
+                )
+            },
             move |node| (node.kind.clone(), node.is_dir, node.open, node.level),
             move |node| {
                 let level = node.level;
@@ -306,9 +464,35 @@ fn new_file_node_view(
                 let double_click_data = data.clone();
                 let secondary_click_data = data.clone();
                 let aux_click_data = data.clone();
+                let drag_enter_data = data.clone();
+                let drop_node_data = data.clone();
                 let kind = node.kind.clone();
                 let open = node.open;
                 let is_dir = node.is_dir;
+                let is_drag_over = {
+                    let kind = kind.clone();
+                    move || {
+                        drag_over.with(|drag_over| {
+                            matches!(
+                                (&kind, drag_over),
+                                (FileNodeViewKind::Path(path), Some(drag_over))
+                                    if path == drag_over
+                            )
+                        })
+                    }
+                };
+                let is_selected = {
+                    let kind = kind.clone();
+                    move || {
+                        select.with(|select| {
+                            matches!(
+                                (&kind, select),
+                                (FileNodeViewKind::Path(path), Some(selected))
+                                    if path == selected
+                            )
+                        })
+                    }
+                };
 
                 let view = stack((
                     svg(move || {
@@ -340,11 +524,12 @@ fn new_file_node_view(
                         svg(move || {
                             let config = config.get();
                             if is_dir {
-                                let svg_str = match open {
-                                    true => LapceIcons::DIRECTORY_OPENED,
-                                    false => LapceIcons::DIRECTORY_CLOSED,
-                                };
-                                config.ui_svg(svg_str)
+                                match kind.path() {
+                                    Some(path) => config.folder_svg(path, open).0,
+                                    None => {
+                                        config.ui_svg(LapceIcons::DIRECTORY_CLOSED)
+                                    }
+                                }
                             } else if let Some(path) = kind.path() {
                                 config.file_svg(path).0
                             } else {
@@ -359,9 +544,16 @@ fn new_file_node_view(
                                 .flex_shrink(0.0)
                                 .margin_horiz(6.0)
                                 .apply_if(is_dir, |s| {
-                                    s.color(
-                                        config.color(LapceColor::LAPCE_ICON_ACTIVE),
-                                    )
+                                    let color =
+                                        match kind_for_style.path() {
+                                            Some(path) => {
+                                                config.folder_svg(path, open).1
+                                            }
+                                            None => Some(config.color(
+                                                LapceColor::LAPCE_ICON_ACTIVE,
+                                            )),
+                                        };
+                                    s.apply_opt(color, Style::color)
                                 })
                                 .apply_if(!is_dir, |s| {
                                     s.apply_opt(
@@ -379,6 +571,20 @@ fn new_file_node_view(
                     s.padding_right(5.0)
                         .padding_left((level * 10) as f32)
                         .align_items(AlignItems::Center)
+                        .apply_if(is_selected(), |s| {
+                            s.background(
+                                config
+                                    .get()
+                                    .color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                            )
+                        })
+                        .apply_if(is_drag_over(), |s| {
+                            s.background(
+                                config
+                                    .get()
+                                    .color(LapceColor::EDITOR_DRAG_DROP_BACKGROUND),
+                            )
+                        })
                         .hover(|s| {
                             s.background(
                                 config
@@ -394,7 +600,10 @@ fn new_file_node_view(
                     let click_path = path.clone();
                     let double_click_path = path.clone();
                     let secondary_click_path = path.clone();
-                    let aux_click_path = path;
+                    let aux_click_path = path.clone();
+                    let drag_path = path.clone();
+                    let drag_enter_path = path.clone();
+                    let drop_path = path;
                     view.on_click_stop(move |_| {
                         click_data.click(&click_path);
                     })
@@ -404,16 +613,66 @@ fn new_file_node_view(
                     .on_secondary_click_stop(move |_| {
                         secondary_click_data.secondary_click(&secondary_click_path);
                     })
-                    .on_event_stop(
-                        EventListener::PointerDown,
-                        move |event| {
-                            if let Event::PointerDown(pointer_event) = event {
-                                if pointer_event.button.is_auxiliary() {
-                                    aux_click_data.middle_click(&aux_click_path);
-                                }
+                    .on_event_stop(EventListener::PointerDown, move |event| {
+                        filter_focused.set(false);
+                        if focus.get_untracked()
+                            != Focus::Panel(PanelKind::FileExplorer)
+                        {
+                            focus.set(Focus::Panel(PanelKind::FileExplorer));
+                        }
+                        if let Event::PointerDown(pointer_event) = event {
+                            if pointer_event.button.is_auxiliary() {
+                                aux_click_data.middle_click(&aux_click_path);
                             }
-                        },
-                    )
+                        }
+                    })
+                    .on_event_stop(EventListener::DragStart, move |_| {
+                        dragging.set(Some(DragContent::FileNode(drag_path.clone())));
+                    })
+                    .on_event_stop(EventListener::DragEnd, move |_| {
+                        dragging.set(None);
+                        drag_over.set(None);
+                    })
+                    .on_event(EventListener::DragEnter, move |_| {
+                        if is_dragging_file_node(dragging) {
+                            drag_over
+                                .set(drag_enter_data.drop_target(&drag_enter_path));
+                            EventPropagation::Stop
+                        } else {
+                            EventPropagation::Continue
+                        }
+                    })
+                    .on_event(EventListener::Drop, move |event| {
+                        if let Some(DragContent::FileNode(current_path)) =
+                            dragging.get_untracked()
+                        {
+                            drag_over.set(None);
+                            if let (Event::PointerUp(pointer_event), Some(target)) =
+                                (event, drop_node_data.drop_target(&drop_path))
+                            {
+                                drop_node_data.drop_path(
+                                    &current_path,
+                                    &target,
+                                    is_copy_modifier(pointer_event.modifiers),
+                                );
+                            }
+                            EventPropagation::Stop
+                        } else {
+                            EventPropagation::Continue
+                        }
+                    })
+                    .draggable()
+                    .dragging_style(move |s| {
+                        let config = config.get();
+                        s.border(1.0)
+                            .border_radius(6.0)
+                            .background(
+                                config
+                                    .color(LapceColor::PANEL_BACKGROUND)
+                                    .with_alpha_factor(0.7),
+                            )
+                            .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    })
                 } else {
                     view
                 }
@@ -421,7 +680,41 @@ fn new_file_node_view(
         )
         .style(|s| s.flex_col().align_items(AlignItems::Stretch).width_full()),
     )
+    .ensure_visible(move || {
+        let line = scroll_to_line.get().unwrap_or(0);
+        let line_height = ui_line_height.get_untracked();
+        Size::new(1.0, line_height)
+            .to_rect()
+            .with_origin(Point::new(0.0, line as f64 * line_height))
+    })
     .style(|s| s.size_full())
+    .on_event_cont(EventListener::PointerDown, move |_| {
+        filter_focused.set(false);
+        if focus.get_untracked() != Focus::Panel(PanelKind::FileExplorer) {
+            focus.set(Focus::Panel(PanelKind::FileExplorer));
+        }
+    })
+    .on_event_cont(EventListener::DragLeave, move |_| {
+        drag_over.set(None);
+    })
+    .on_event(EventListener::Drop, move |event| {
+        // Dropping onto the empty space below the tree moves into the workspace root
+        if let Some(DragContent::FileNode(current_path)) = dragging.get_untracked() {
+            drag_over.set(None);
+            if let (Event::PointerUp(pointer_event), Some(workspace_path)) =
+                (event, drop_data.common.workspace.path.as_ref())
+            {
+                drop_data.drop_path(
+                    &current_path,
+                    workspace_path,
+                    is_copy_modifier(pointer_event.modifiers),
+                );
+            }
+            EventPropagation::Stop
+        } else {
+            EventPropagation::Continue
+        }
+    })
     .on_secondary_click_stop(move |_| {
         if let Naming::None = naming.get_untracked() {
             if let Some(path) = &secondary_click_data.common.workspace.path {
@@ -431,6 +724,21 @@ fn new_file_node_view(
     })
 }
 
+fn is_dragging_file_node(dragging: RwSignal<Option<DragContent>>) -> bool {
+    dragging.with_untracked(|dragging| {
+        matches!(dragging, Some(DragContent::FileNode(_)))
+    })
+}
+
+/// Whether a drop with `modifiers` held should copy instead of move.
+fn is_copy_modifier(modifiers: Modifiers) -> bool {
+    if cfg!(target_os = "macos") {
+        modifiers.alt()
+    } else {
+        modifiers.control()
+    }
+}
+
 fn open_editors_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     let diff_editors = window_tab_data.main_split.diff_editors;
     let editors = window_tab_data.main_split.editors;
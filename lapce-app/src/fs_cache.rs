@@ -0,0 +1,196 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
+};
+
+use lapce_rpc::file::FileNodeItem;
+
+/// A directory listing received from the proxy
+#[derive(Clone)]
+struct CachedDir {
+    items: Vec<FileNodeItem>,
+    /// The modification time of the directory when it was listed
+    mtime: Option<SystemTime>,
+    /// Whether no file change was reported in the directory since it was
+    /// listed. A stale listing is only used once the proxy confirmed the
+    /// directory still has the same modification time.
+    valid: bool,
+}
+
+#[derive(Default)]
+struct FsCacheInner {
+    dirs: HashMap<PathBuf, CachedDir>,
+    /// All the files of the workspace, as listed for the file palette
+    files: Option<Vec<PathBuf>>,
+}
+
+/// The directory listings and workspace files of a remote workspace, so that
+/// expanding folders in the explorer or opening the file palette don't go
+/// over the network each time.
+/// Listings are invalidated by the file changes the proxy's watcher reports.
+#[derive(Clone)]
+pub struct FsCache {
+    enabled: bool,
+    inner: Rc<RefCell<FsCacheInner>>,
+}
+
+impl FsCache {
+    /// A cache which only keeps anything when `enabled`, as reading a local
+    /// directory is cheap enough not to risk showing stale listings
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            inner: Rc::new(RefCell::new(FsCacheInner::default())),
+        }
+    }
+
+    /// The listing of `path`, if there is one that is known to be up to date
+    pub fn dir(&self, path: &Path) -> Option<Vec<FileNodeItem>> {
+        self.inner
+            .borrow()
+            .dirs
+            .get(path)
+            .filter(|dir| dir.valid)
+            .map(|dir| dir.items.clone())
+    }
+
+    /// The modification time of the cached listing of `path`, even if it is
+    /// stale, to let the proxy skip sending it again when it didn't change
+    pub fn dir_mtime(&self, path: &Path) -> Option<SystemTime> {
+        self.inner.borrow().dirs.get(path).and_then(|dir| dir.mtime)
+    }
+
+    pub fn set_dir(
+        &self,
+        path: PathBuf,
+        items: Vec<FileNodeItem>,
+        mtime: Option<SystemTime>,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        self.inner.borrow_mut().dirs.insert(
+            path,
+            CachedDir {
+                items,
+                mtime,
+                valid: true,
+            },
+        );
+    }
+
+    /// Marks the listing of `path` up to date again, after the proxy reported
+    /// the directory is unchanged, and returns it
+    pub fn revalidate_dir(&self, path: &Path) -> Option<Vec<FileNodeItem>> {
+        let mut inner = self.inner.borrow_mut();
+        let dir = inner.dirs.get_mut(path)?;
+        dir.valid = true;
+        Some(dir.items.clone())
+    }
+
+    pub fn files(&self) -> Option<Vec<PathBuf>> {
+        self.inner.borrow().files.clone()
+    }
+
+    pub fn set_files(&self, files: Vec<PathBuf>) {
+        if !self.enabled {
+            return;
+        }
+        self.inner.borrow_mut().files = Some(files);
+    }
+
+    /// Invalidates what `paths` being created, removed or renamed changed,
+    /// which is everything when the paths aren't known
+    pub fn invalidate(&self, paths: &[PathBuf]) {
+        if paths.is_empty() {
+            self.invalidate_all();
+            return;
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        inner.files = None;
+        for (dir_path, dir) in inner.dirs.iter_mut() {
+            // The parent's listing changed, and so did the path's own and its
+            // descendants' if it is a removed or replaced directory
+            if paths.iter().any(|path| {
+                path.parent() == Some(dir_path.as_path())
+                    || dir_path.starts_with(path)
+            }) {
+                dir.valid = false;
+            }
+        }
+    }
+
+    /// Invalidates every listing, keeping their modification times so that
+    /// the unchanged directories are cheap to check
+    pub fn invalidate_all(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.files = None;
+        for dir in inner.dirs.values_mut() {
+            dir.valid = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(path: &str, is_dir: bool) -> FileNodeItem {
+        FileNodeItem {
+            path: PathBuf::from(path),
+            is_dir,
+            read: false,
+            open: false,
+            children: HashMap::new(),
+            children_open_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let cache = FsCache::new(true);
+        let mtime = Some(SystemTime::UNIX_EPOCH);
+        cache.set_dir(PathBuf::from("/w"), vec![item("/w/src", true)], mtime);
+        cache.set_dir(
+            PathBuf::from("/w/src"),
+            vec![item("/w/src/lib", true)],
+            mtime,
+        );
+        cache.set_dir(PathBuf::from("/w/src/lib"), vec![], mtime);
+        cache.set_dir(PathBuf::from("/w/docs"), vec![], mtime);
+        cache.set_files(vec![PathBuf::from("/w/src/main.rs")]);
+
+        // A new file only changes its parent's listing
+        cache.invalidate(&[PathBuf::from("/w/src/new.rs")]);
+        assert!(cache.files().is_none());
+        assert!(cache.dir(Path::new("/w")).is_some());
+        assert!(cache.dir(Path::new("/w/src")).is_none());
+        assert!(cache.dir(Path::new("/w/src/lib")).is_some());
+        assert_eq!(cache.dir_mtime(Path::new("/w/src")), mtime);
+        assert!(cache.revalidate_dir(Path::new("/w/src")).is_some());
+        assert!(cache.dir(Path::new("/w/src")).is_some());
+
+        // A removed directory takes its descendants with it
+        cache.invalidate(&[PathBuf::from("/w/src")]);
+        assert!(cache.dir(Path::new("/w")).is_none());
+        assert!(cache.dir(Path::new("/w/src")).is_none());
+        assert!(cache.dir(Path::new("/w/src/lib")).is_none());
+        assert!(cache.dir(Path::new("/w/docs")).is_some());
+
+        cache.invalidate(&[]);
+        assert!(cache.dir(Path::new("/w/docs")).is_none());
+    }
+
+    #[test]
+    fn test_disabled() {
+        let cache = FsCache::new(false);
+        cache.set_dir(PathBuf::from("/w"), vec![], None);
+        cache.set_files(vec![]);
+        assert!(cache.dir(Path::new("/w")).is_none());
+        assert!(cache.files().is_none());
+    }
+}
@@ -0,0 +1,411 @@
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
+
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{ReadSignal, RwSignal, Scope},
+    style::CursorStyle,
+    views::{container, dyn_container, img, label, scroll, stack, svg, Decorators},
+    View,
+};
+use lapce_rpc::proxy::ProxyResponse;
+
+use crate::{
+    config::{color::LapceColor, LapceConfig},
+    id::ImageViewId,
+    window_tab::CommonData,
+};
+
+/// The extensions of the files that are opened in an image view rather than an
+/// editor
+const IMAGE_EXTENSIONS: [&str; 8] =
+    ["png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "svg"];
+
+const MIN_SCALE: f64 = 0.05;
+const MAX_SCALE: f64 = 32.0;
+const ZOOM_STEP: f64 = 1.25;
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| {
+            IMAGE_EXTENSIONS
+                .iter()
+                .any(|image| image.eq_ignore_ascii_case(e))
+        })
+        .unwrap_or(false)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageZoom {
+    /// Scaled down to fit in the view, but never scaled up
+    Fit,
+    Scale(f64),
+}
+
+#[derive(Clone)]
+pub enum ImageContent {
+    Loading,
+    Raster(Rc<Vec<u8>>),
+    Svg(String),
+    Error(String),
+}
+
+#[derive(Clone)]
+pub struct ImageViewData {
+    pub id: ImageViewId,
+    pub path: PathBuf,
+    pub content: RwSignal<ImageContent>,
+    /// The width and height of the image in pixels, once it's loaded
+    pub size: RwSignal<Option<(u32, u32)>>,
+    pub zoom: RwSignal<ImageZoom>,
+    /// The size of the area the image is shown in
+    pub viewport: RwSignal<(f64, f64)>,
+}
+
+impl ImageViewData {
+    pub fn new(
+        cx: Scope,
+        id: ImageViewId,
+        path: PathBuf,
+        common: &CommonData,
+    ) -> Self {
+        let data = Self {
+            id,
+            path: path.clone(),
+            content: cx.create_rw_signal(ImageContent::Loading),
+            size: cx.create_rw_signal(None),
+            zoom: cx.create_rw_signal(ImageZoom::Fit),
+            viewport: cx.create_rw_signal((0.0, 0.0)),
+        };
+
+        let content = data.content;
+        let size = data.size;
+        let is_svg = is_svg(&path);
+        let send = create_ext_action(cx, move |result: Result<Vec<u8>, String>| {
+            let bytes = match result {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    content.set(ImageContent::Error(e));
+                    return;
+                }
+            };
+            let Some(dimensions) = image_dimensions(&bytes, is_svg) else {
+                content.set(ImageContent::Error(
+                    "The image is in a format that isn't supported".to_string(),
+                ));
+                return;
+            };
+            size.set(Some(dimensions));
+            if is_svg {
+                content.set(ImageContent::Svg(
+                    String::from_utf8_lossy(&bytes).into_owned(),
+                ));
+            } else {
+                content.set(ImageContent::Raster(Rc::new(bytes)));
+            }
+        });
+        common.proxy.read_file(path, move |result| {
+            send(match result {
+                Ok(ProxyResponse::ReadFileResponse { content }) => Ok(content),
+                Ok(_) => Err("Unexpected response".to_string()),
+                Err(e) => Err(e.message),
+            });
+        });
+
+        data
+    }
+
+    /// The scale the image is shown at
+    pub fn scale(&self) -> f64 {
+        match self.zoom.get() {
+            ImageZoom::Fit => fit_scale(self.size.get(), self.viewport.get()),
+            ImageZoom::Scale(scale) => scale,
+        }
+    }
+
+    pub fn zoom_in(&self) {
+        let scale = self.scale_untracked() * ZOOM_STEP;
+        self.zoom.set(ImageZoom::Scale(scale.min(MAX_SCALE)));
+    }
+
+    pub fn zoom_out(&self) {
+        let scale = self.scale_untracked() / ZOOM_STEP;
+        self.zoom.set(ImageZoom::Scale(scale.max(MIN_SCALE)));
+    }
+
+    fn scale_untracked(&self) -> f64 {
+        match self.zoom.get_untracked() {
+            ImageZoom::Fit => {
+                fit_scale(self.size.get_untracked(), self.viewport.get_untracked())
+            }
+            ImageZoom::Scale(scale) => scale,
+        }
+    }
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("svg"))
+        .unwrap_or(false)
+}
+
+/// The scale that fits an image of `size` in `viewport`, which is never more
+/// than its actual size
+fn fit_scale(size: Option<(u32, u32)>, viewport: (f64, f64)) -> f64 {
+    let Some((width, height)) = size else {
+        return 1.0;
+    };
+    if width == 0 || height == 0 || viewport.0 <= 0.0 || viewport.1 <= 0.0 {
+        return 1.0;
+    }
+    (viewport.0 / width as f64)
+        .min(viewport.1 / height as f64)
+        .min(1.0)
+}
+
+fn image_dimensions(bytes: &[u8], is_svg: bool) -> Option<(u32, u32)> {
+    if is_svg {
+        svg_dimensions(&String::from_utf8_lossy(bytes))
+    } else {
+        let size = imagesize::blob_size(bytes).ok()?;
+        Some((size.width as u32, size.height as u32))
+    }
+}
+
+/// The size of an svg, from the `width` and `height` of its root element, or
+/// its `viewBox` when they aren't in pixels
+fn svg_dimensions(svg: &str) -> Option<(u32, u32)> {
+    let start = svg.find("<svg")?;
+    let end = start + svg[start..].find('>')?;
+    let tag = &svg[start..end];
+
+    let length = |name: &str| {
+        let value = svg_attribute(tag, name)?;
+        let value = value.strip_suffix("px").unwrap_or(value).trim();
+        value.parse::<f64>().ok().filter(|v| *v > 0.0)
+    };
+    if let (Some(width), Some(height)) = (length("width"), length("height")) {
+        return Some((width.round() as u32, height.round() as u32));
+    }
+
+    let view_box: Vec<f64> = svg_attribute(tag, "viewBox")?
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|v| !v.is_empty())
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    match view_box[..] {
+        [_, _, width, height] if width > 0.0 && height > 0.0 => {
+            Some((width.round() as u32, height.round() as u32))
+        }
+        _ => None,
+    }
+}
+
+fn svg_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(index) = rest.find(name) {
+        let before = rest[..index].chars().last();
+        let after = &rest[index + name.len()..];
+        if before.map(|c| c.is_whitespace()).unwrap_or(false) {
+            if let Some(after) = after.trim_start().strip_prefix('=') {
+                let after = after.trim_start();
+                let quote = after.chars().next()?;
+                if quote == '"' || quote == '\'' {
+                    let value = &after[1..];
+                    return value.find(quote).map(|end| &value[..end]);
+                }
+            }
+        }
+        rest = after;
+    }
+    None
+}
+
+/// An image file, with controls to zoom it, fit it in the view or show it at its
+/// actual size
+pub fn image_view(
+    data: ImageViewData,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let content = data.content;
+    let viewport = data.viewport;
+    let image_data = data.clone();
+    let zoom = data.zoom;
+
+    stack((
+        stack((
+            {
+                let data = data.clone();
+                label(move || format!("{:.0}%", data.scale() * 100.0)).style(
+                    move |s| {
+                        s.flex_grow(1.0)
+                            .color(config.get().color(LapceColor::EDITOR_DIM))
+                    },
+                )
+            },
+            toolbar_button(
+                "Fit",
+                move || zoom.set(ImageZoom::Fit),
+                move || zoom.get() == ImageZoom::Fit,
+                config,
+            ),
+            toolbar_button(
+                "Actual Size",
+                move || zoom.set(ImageZoom::Scale(1.0)),
+                move || zoom.get() == ImageZoom::Scale(1.0),
+                config,
+            ),
+            {
+                let data = data.clone();
+                toolbar_button("−", move || data.zoom_out(), || false, config)
+            },
+            toolbar_button("+", move || data.zoom_in(), || false, config),
+        ))
+        .style(move |s| {
+            let config = config.get();
+            s.width_full()
+                .items_center()
+                .padding_horiz(10.0)
+                .padding_vert(4.0)
+                .border_bottom(1.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+        }),
+        container(
+            scroll(
+                dyn_container(
+                    move || content.get(),
+                    move |content| {
+                        let data = image_data.clone();
+                        let size = move || {
+                            let (width, height) = data.size.get().unwrap_or((0, 0));
+                            let scale = data.scale();
+                            (
+                                (width as f64 * scale) as f32,
+                                (height as f64 * scale) as f32,
+                            )
+                        };
+                        match content {
+                            ImageContent::Loading => {
+                                label(|| "Loading...".to_string()).into_any()
+                            }
+                            ImageContent::Error(e) => label(move || e.clone())
+                                .style(move |s| {
+                                    s.color(
+                                        config.get().color(LapceColor::LAPCE_ERROR),
+                                    )
+                                })
+                                .into_any(),
+                            ImageContent::Raster(bytes) => {
+                                img(move || bytes.to_vec())
+                                    .style(move |s| {
+                                        let (width, height) = size();
+                                        s.size(width, height)
+                                    })
+                                    .into_any()
+                            }
+                            ImageContent::Svg(svg_str) => {
+                                svg(move || svg_str.clone())
+                                    .style(move |s| {
+                                        let (width, height) = size();
+                                        s.size(width, height)
+                                    })
+                                    .into_any()
+                            }
+                        }
+                    },
+                )
+                .style(|s| {
+                    s.min_width_pct(100.0)
+                        .min_height_pct(100.0)
+                        .justify_center()
+                        .items_center()
+                }),
+            )
+            .style(|s| s.absolute().size_full()),
+        )
+        .on_resize(move |rect| {
+            let size = (rect.width(), rect.height());
+            if viewport.get_untracked() != size {
+                viewport.set(size);
+            }
+        })
+        .style(|s| s.width_full().flex_basis(0.0).flex_grow(1.0)),
+    ))
+    .style(move |s| {
+        s.flex_col()
+            .size_full()
+            .background(config.get().color(LapceColor::EDITOR_BACKGROUND))
+    })
+    .debug_name("Image View")
+}
+
+fn toolbar_button(
+    text: &'static str,
+    on_click: impl Fn() + 'static,
+    active: impl Fn() -> bool + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(move || text.to_string())
+        .on_click_stop(move |_| on_click())
+        .style(move |s| {
+            let config = config.get();
+            s.margin_left(6.0)
+                .padding_horiz(8.0)
+                .border_radius(6.0)
+                .selectable(false)
+                .apply_if(active(), |s| {
+                    s.background(
+                        config.color(LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND),
+                    )
+                })
+                .hover(|s| {
+                    s.cursor(CursorStyle::Pointer).background(
+                        config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{fit_scale, is_image, svg_dimensions};
+
+    #[test]
+    fn test_is_image() {
+        assert!(is_image(Path::new("/a/logo.png")));
+        assert!(is_image(Path::new("/a/photo.JPG")));
+        assert!(is_image(Path::new("icon.svg")));
+        assert!(!is_image(Path::new("main.rs")));
+        assert!(!is_image(Path::new("png")));
+    }
+
+    #[test]
+    fn test_svg_dimensions() {
+        assert_eq!(
+            svg_dimensions(r#"<svg width="24" height="16px" stroke-width="2">"#),
+            Some((24, 16))
+        );
+        assert_eq!(
+            svg_dimensions(
+                r#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg" width="100%" viewBox="0 0 48 32"></svg>"#
+            ),
+            Some((48, 32))
+        );
+        assert_eq!(svg_dimensions("<svg></svg>"), None);
+        assert_eq!(svg_dimensions("not an svg"), None);
+    }
+
+    #[test]
+    fn test_fit_scale() {
+        assert_eq!(fit_scale(Some((200, 100)), (100.0, 100.0)), 0.5);
+        assert_eq!(fit_scale(Some((50, 50)), (100.0, 100.0)), 1.0);
+        assert_eq!(fit_scale(None, (100.0, 100.0)), 1.0);
+    }
+}
@@ -61,9 +61,8 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
                 let cmd = keypress.commands.get(&keymap.command).cloned()?;
 
                 let cmd_name_contains_pattern =
-                    cmd.kind.str().replace('_', " ").contains(&pattern);
+                    cmd.name().replace('_', " ").contains(&pattern);
                 let cmd_desc_contains_pattern = cmd
-                    .kind
                     .desc()
                     .map(|desc| desc.to_lowercase().contains(&pattern))
                     .unwrap_or(false);
@@ -89,9 +88,8 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
             })
             .collect::<im::Vector<(LapceCommand, Option<KeyMap>)>>();
         items.extend(keypress.commands_without_keymap.iter().filter_map(|cmd| {
-            let match_pattern = cmd.kind.str().replace('_', " ").contains(&pattern)
+            let match_pattern = cmd.name().replace('_', " ").contains(&pattern)
                 || cmd
-                    .kind
                     .desc()
                     .map(|desc| desc.to_lowercase().contains(&pattern))
                     .unwrap_or(false);
@@ -113,10 +111,9 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
             stack((
                 container(
                     text(
-                        cmd.kind
-                            .desc()
+                        cmd.desc()
                             .map(|desc| desc.to_string())
-                            .unwrap_or_else(|| cmd.kind.str().replace('_', " ")),
+                            .unwrap_or_else(|| cmd.name().replace('_', " ")),
                     )
                     .style(|s| {
                         s.text_ellipsis()
@@ -254,7 +251,7 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
                     keymap
                 } else {
                     KeyMap {
-                        command: local_cmd.kind.str().to_string(),
+                        command: local_cmd.name().into_owned(),
                         key: Vec::new(),
                         modes: Modes::empty(),
                         when: None,
@@ -353,7 +350,7 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
                     |(i, (cmd, keymap)): &(
                         usize,
                         (LapceCommand, Option<KeyMap>),
-                    )| { (*i, cmd.kind.str(), keymap.clone()) },
+                    )| { (*i, cmd.name(), keymap.clone()) },
                     view_fn,
                 )
                 .style(|s| s.flex_col().width_pct(100.0)),
@@ -385,10 +382,9 @@ fn keyboard_picker_view(
                 picker_cmd.with(|cmd| {
                     cmd.as_ref()
                         .map(|cmd| {
-                            cmd.kind
-                                .desc()
+                            cmd.desc()
                                 .map(|desc| desc.to_string())
-                                .unwrap_or_else(|| cmd.kind.str().replace('_', " "))
+                                .unwrap_or_else(|| cmd.name().replace('_', " "))
                         })
                         .unwrap_or_default()
                 })
@@ -15,6 +15,7 @@
 use indexmap::IndexMap;
 use itertools::Itertools;
 use lapce_core::mode::{Mode, Modes};
+use lapce_rpc::plugin::PluginCommand;
 
 pub use self::press::KeyPress;
 use self::{
@@ -180,6 +181,17 @@ pub fn update_keymaps(&mut self, config: &LapceConfig) {
         }
     }
 
+    /// Replace the volts' commands, so they can be bound and run by name
+    pub fn set_plugin_commands(&mut self, plugin_commands: &[PluginCommand]) {
+        let mut commands = lapce_internal_commands();
+        for command in plugin_commands {
+            let command = LapceCommand::plugin(command.clone());
+            commands.insert(command.name().into_owned(), command);
+        }
+        self.commands = Rc::new(commands);
+        self.load_commands();
+    }
+
     fn load_commands(&mut self) {
         let mut commands_with_keymap = Vec::new();
         let mut commands_without_keymap = Vec::new();
@@ -191,8 +203,8 @@ fn load_commands(&mut self) {
             }
         }
 
-        for (_, cmd) in self.commands.iter() {
-            if !self.command_keymaps.contains_key(cmd.kind.str()) {
+        for (name, cmd) in self.commands.iter() {
+            if !self.command_keymaps.contains_key(name) {
                 commands_without_keymap.push(cmd.clone());
             }
         }
@@ -663,6 +675,45 @@ pub fn update_file(keymap: &KeyMap, keys: &[KeyMapPress]) -> Option<()> {
         std::fs::write(path, table.to_string().as_bytes()).ok()?;
         None
     }
+
+    /// Add the keymaps after the ones already in the keymaps file
+    pub fn append_to_file(keymaps: &[KeyMap]) -> Option<()> {
+        let mut array = Self::get_file_array().unwrap_or_default();
+        for keymap in keymaps {
+            let mut table = toml_edit::Table::new();
+            table.insert(
+                "key",
+                toml_edit::value(toml_edit::Value::from(
+                    keymap.key.iter().join(" "),
+                )),
+            );
+            table.insert(
+                "command",
+                toml_edit::value(toml_edit::Value::from(keymap.command.clone())),
+            );
+            if !keymap.modes.is_empty() {
+                table.insert(
+                    "mode",
+                    toml_edit::value(toml_edit::Value::from(
+                        keymap.modes.to_string(),
+                    )),
+                );
+            }
+            if let Some(when) = keymap.when.as_ref() {
+                table.insert(
+                    "when",
+                    toml_edit::value(toml_edit::Value::from(when.to_string())),
+                );
+            }
+            array.push(table);
+        }
+
+        let mut table = toml_edit::Document::new();
+        table.insert("keymaps", toml_edit::Item::ArrayOfTables(array));
+        let path = Self::file()?;
+        std::fs::write(path, table.to_string().as_bytes()).ok()?;
+        Some(())
+    }
 }
 
 fn get_modes(toml_keymap: &toml_edit::Table) -> Modes {
@@ -5,6 +5,11 @@
 
 use super::keymap::{KeyMap, KeyMapPress};
 
+/// The commands that were renamed, by their old names, so that keymaps
+/// binding the old names keep working
+const RENAMED_COMMANDS: &[(&str, &str)] =
+    &[("palette.workspace", "open_recent_workspace")];
+
 pub struct KeyMapLoader {
     keymaps: IndexMap<Vec<KeyMapPress>, Vec<KeyMap>>,
     command_keymaps: IndexMap<String, Vec<KeyMap>>,
@@ -121,12 +126,25 @@ fn get_keymap(
             command: toml_keymap
                 .get("command")
                 .and_then(|c| c.as_str())
-                .map(|w| w.trim().to_string())
+                .map(|w| renamed_command(w.trim()))
                 .unwrap_or_default(),
         }))
     }
 }
 
+/// The current name of the command, keeping the `-` of an unbinding
+fn renamed_command(command: &str) -> String {
+    let (prefix, name) = match command.strip_prefix('-') {
+        Some(name) => ("-", name),
+        None => ("", command),
+    };
+    let name = RENAMED_COMMANDS
+        .iter()
+        .find(|(old, _)| *old == name)
+        .map_or(name, |(_, new)| new);
+    format!("{prefix}{name}")
+}
+
 fn get_modes(toml_keymap: &toml_edit::Table) -> Modes {
     toml_keymap
         .get("mode")
@@ -251,4 +269,37 @@ fn test_keymap() {
             KeyMapKey::Logical(Key::Character("+".into()))
         );
     }
+
+    #[test]
+    fn test_renamed_command() {
+        let keymaps = r#"
+[[keymaps]]
+key = "ctrl+r"
+command = "open_recent_workspace"
+
+[[keymaps]]
+key = "ctrl+r"
+command = "-palette.workspace"
+
+[[keymaps]]
+key = "ctrl+o"
+command = "palette.workspace"
+        "#;
+        let mut loader = KeyMapLoader::new();
+        loader.load_from_str(keymaps, false).unwrap();
+
+        let (keymaps, command_keymaps) = loader.finalize();
+
+        // The old name unbinds the keymap of the new one
+        let keypress = KeyMapPress::parse("ctrl+r");
+        assert!(keymaps.get(&keypress).unwrap().is_empty());
+
+        let keypress = KeyMapPress::parse("ctrl+o");
+        assert_eq!(
+            keymaps.get(&keypress).unwrap()[0].command,
+            "open_recent_workspace"
+        );
+        assert_eq!(command_keymaps["open_recent_workspace"].len(), 1);
+        assert!(!command_keymaps.contains_key("palette.workspace"));
+    }
 }
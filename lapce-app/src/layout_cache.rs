@@ -0,0 +1,44 @@
+use std::{collections::HashMap, hash::Hash};
+
+use floem::cosmic_text::TextLayout;
+
+/// How many layouts are kept before the cache starts over, which bounds it while
+/// scrolling through a long file
+const MAX_LAYOUTS: usize = 2048;
+
+/// The text layouts a view builds while painting, kept across frames so that text
+/// that hasn't changed isn't laid out again on each one. The layouts depend on the
+/// fonts and colors of the config, so they're all dropped when it changes.
+pub struct TextLayoutCache<K> {
+    config_id: u64,
+    layouts: HashMap<K, TextLayout>,
+}
+
+impl<K: Hash + Eq> Default for TextLayoutCache<K> {
+    fn default() -> Self {
+        Self {
+            config_id: 0,
+            layouts: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq> TextLayoutCache<K> {
+    /// The layout for `key` under the config with `config_id`, built with `build` if
+    /// there isn't one yet
+    pub fn get_or_build(
+        &mut self,
+        config_id: u64,
+        key: K,
+        build: impl FnOnce() -> TextLayout,
+    ) -> &TextLayout {
+        if self.config_id != config_id {
+            self.layouts.clear();
+            self.config_id = config_id;
+        }
+        if self.layouts.len() >= MAX_LAYOUTS && !self.layouts.contains_key(&key) {
+            self.layouts.clear();
+        }
+        self.layouts.entry(key).or_insert_with(build)
+    }
+}
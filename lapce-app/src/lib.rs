@@ -1,49 +1,63 @@
 pub mod about;
+pub mod activity_bar;
 pub mod alert;
 pub mod app;
+pub mod call_hierarchy;
 pub mod code_action;
 pub mod command;
 pub mod completion;
 pub mod config;
 pub mod db;
 pub mod debug;
+pub mod debug_console;
 pub mod doc;
 pub mod editor;
 pub mod editor_tab;
 pub mod file_explorer;
 pub mod find;
 pub mod focus_text;
+pub mod fs_cache;
 pub mod global_search;
 pub mod history;
 pub mod hover;
 pub mod id;
+pub mod image_view;
 pub mod inline_completion;
 pub mod keymap;
 pub mod keypress;
+pub mod layout_cache;
 pub mod listener;
 pub mod lsp;
 pub mod main_split;
 pub mod markdown;
+pub mod notification;
 pub mod palette;
 pub mod panel;
 pub mod plugin;
+pub mod ports;
+pub mod problem;
 pub mod proxy;
 pub mod rename;
 pub mod settings;
 pub mod snippet;
 pub mod source_control;
+pub mod ssh_prompt;
+pub mod startup;
 pub mod status;
 pub mod terminal;
 pub mod text_area;
 pub mod text_input;
 pub mod title;
 pub mod tracing;
+pub mod undo_tree;
 pub mod update;
 pub mod wave;
 pub mod web_link;
+pub mod welcome;
 pub mod window;
 pub mod window_tab;
 pub mod workspace;
+pub mod workspace_edit;
 
 #[cfg(windows)]
 extern crate windows_sys as windows;
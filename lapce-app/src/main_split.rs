@@ -1,7 +1,9 @@
 use std::{
-    collections::HashMap,
+    cell::Cell,
+    collections::VecDeque,
     path::{Path, PathBuf},
     rc::Rc,
+    sync::Arc,
 };
 
 use floem::{
@@ -10,23 +12,30 @@
     file::{FileDialogOptions, FileInfo},
     keyboard::Modifiers,
     peniko::kurbo::{Point, Rect, Vec2},
-    reactive::{Memo, RwSignal, Scope},
-    views::editor::id::EditorId,
+    reactive::{use_context, Memo, RwSignal, Scope},
+    views::editor::{id::EditorId, text::SystemClipboard},
 };
 use itertools::Itertools;
 use lapce_core::{
-    buffer::rope_text::RopeText, command::FocusCommand, cursor::Cursor,
-    rope_text_pos::RopeTextPosition, selection::Selection, syntax::Syntax,
+    buffer::rope_text::RopeText,
+    command::{EditCommand, FocusCommand},
+    cursor::Cursor,
+    editor::EditType,
+    register::{Clipboard, Register},
+    rope_text_pos::RopeTextPosition,
+    selection::Selection,
+    syntax::Syntax,
 };
 use lapce_rpc::{
     buffer::BufferId,
     plugin::{PluginId, VoltID},
     proxy::ProxyResponse,
+    RpcError,
 };
 use lapce_xi_rope::{spans::SpansBuilder, Rope};
 use lsp_types::{
-    CodeAction, CodeActionOrCommand, DiagnosticSeverity, DocumentChangeOperation,
-    DocumentChanges, OneOf, Position, TextEdit, Url, WorkspaceEdit,
+    CodeAction, CodeActionOrCommand, DiagnosticSeverity, Position, TextEdit,
+    WorkspaceEdit,
 };
 use serde::{Deserialize, Serialize};
 use tracing::{event, Level};
@@ -34,7 +43,11 @@
 use crate::{
     alert::AlertButton,
     command::InternalCommand,
-    doc::{DiagnosticData, Doc, DocContent, DocHistory, EditorDiagnostic},
+    db::LapceDb,
+    doc::{
+        DiagnosticData, Doc, DocContent, DocHistory, EditorDiagnostic,
+        RestoredContent,
+    },
     editor::{
         diff::DiffEditorData,
         location::{EditorLocation, EditorPosition},
@@ -44,11 +57,13 @@
         EditorTabChild, EditorTabChildSource, EditorTabData, EditorTabInfo,
     },
     id::{
-        DiffEditorId, EditorTabId, KeymapId, SettingsId, SplitId,
+        DiffEditorId, EditorTabId, ImageViewId, KeymapId, SettingsId, SplitId,
         ThemeColorSettingsId, VoltViewId,
     },
+    image_view::{is_image, ImageViewData},
     keypress::{EventRef, KeyPressData, KeyPressHandle},
     window_tab::{CommonData, Focus, WindowTabData},
+    workspace_edit::{workspace_edit_steps, AppliedStep, WorkspaceEditStep},
 };
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -135,6 +150,9 @@ pub struct SplitData {
 pub struct SplitInfo {
     pub children: Vec<SplitContentInfo>,
     pub direction: SplitDirection,
+    /// How much of the split each of the children takes, relative to the others
+    #[serde(default)]
+    pub sizes: Vec<f64>,
 }
 
 impl SplitInfo {
@@ -154,9 +172,16 @@ pub fn to_data(
                 children: self
                     .children
                     .iter()
-                    .map(|child| {
+                    .enumerate()
+                    .map(|(i, child)| {
+                        let size = self
+                            .sizes
+                            .get(i)
+                            .copied()
+                            .filter(|size| *size > 0.0)
+                            .unwrap_or(1.0);
                         (
-                            cx.create_rw_signal(1.0),
+                            cx.create_rw_signal(size),
                             child.to_data(data.clone(), split_id),
                         )
                     })
@@ -210,6 +235,11 @@ pub fn split_info(&self, data: &WindowTabData) -> SplitInfo {
                 .iter()
                 .map(|(_, child)| child.content_info(data))
                 .collect(),
+            sizes: self
+                .children
+                .iter()
+                .map(|(size, _)| size.get_untracked())
+                .collect(),
         };
         info
     }
@@ -365,6 +395,38 @@ pub fn with_editors_untracked<O>(
     ) -> O {
         self.0.with_untracked(f)
     }
+
+    /// Update the editors after the file or directory at `current_path` was renamed or moved
+    /// to `new_path`.  
+    /// If it was a file, the editors it is open in use the new path. If it was a directory,
+    /// the editors of every file it is an ancestor of use the file's new path.
+    pub fn rename_path(&self, current_path: &Path, new_path: &Path) {
+        let renamed_editors_content: Vec<_> =
+            self.with_editors_untracked(|editors| {
+                editors
+                    .values()
+                    .map(|editor| editor.doc().content)
+                    .filter(|content| {
+                        content.with_untracked(|content| match content {
+                            DocContent::File { path, .. } => {
+                                path.starts_with(current_path)
+                            }
+                            _ => false,
+                        })
+                    })
+                    .collect()
+            });
+
+        for content in renamed_editors_content {
+            content.update(|content| {
+                if let DocContent::File { path, .. } = content {
+                    if let Ok(suffix) = path.strip_prefix(current_path) {
+                        *path = new_path.join(suffix);
+                    }
+                }
+            });
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -376,15 +438,23 @@ pub struct MainSplitData {
     pub editor_tabs: RwSignal<im::HashMap<EditorTabId, RwSignal<EditorTabData>>>,
     pub editors: Editors,
     pub diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
+    pub images: RwSignal<im::HashMap<ImageViewId, ImageViewData>>,
     pub docs: RwSignal<im::HashMap<PathBuf, Rc<Doc>>>,
     pub scratch_docs: RwSignal<im::HashMap<String, Rc<Doc>>>,
+    /// The files of `docs` that no editor shows anymore, which plugins were
+    /// told are closed
+    pub closed_docs: RwSignal<im::HashSet<PathBuf>>,
     pub diagnostics: RwSignal<im::HashMap<PathBuf, DiagnosticData>>,
     pub active_editor: Memo<Option<EditorData>>,
+    /// The image of the active editor tab, when it's showing one
+    pub active_image: Memo<Option<ImageViewData>>,
     pub find_editor: EditorData,
     pub replace_editor: EditorData,
     pub locations: RwSignal<im::Vector<EditorLocation>>,
     pub current_location: RwSignal<usize>,
     pub width: RwSignal<f64>,
+    /// The changes of the last workspace edit, which can be undone
+    pub last_workspace_edit: RwSignal<Vec<AppliedStep>>,
     pub common: Rc<CommonData>,
 }
 
@@ -406,9 +476,12 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
         let editors = Editors::new(cx);
         let diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>> =
             cx.create_rw_signal(im::HashMap::new());
+        let images: RwSignal<im::HashMap<ImageViewId, ImageViewData>> =
+            cx.create_rw_signal(im::HashMap::new());
         let docs: RwSignal<im::HashMap<PathBuf, Rc<Doc>>> =
             cx.create_rw_signal(im::HashMap::new());
         let scratch_docs = cx.create_rw_signal(im::HashMap::new());
+        let closed_docs = cx.create_rw_signal(im::HashSet::new());
         let locations = cx.create_rw_signal(im::Vector::new());
         let current_location = cx.create_rw_signal(0);
         let diagnostics = cx.create_rw_signal(im::HashMap::new());
@@ -441,6 +514,20 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             Some(editor)
         });
 
+        let active_image = cx.create_memo(move |_| {
+            let active_editor_tab = active_editor_tab.get()?;
+            let editor_tab = editor_tabs
+                .with(|editor_tabs| editor_tabs.get(&active_editor_tab).copied())?;
+            let (_, _, child) = editor_tab.with(|editor_tab| {
+                editor_tab.children.get(editor_tab.active).cloned()
+            })?;
+            if let EditorTabChild::Image(id, _) = child {
+                images.with(|images| images.get(&id).cloned())
+            } else {
+                None
+            }
+        });
+
         {
             let buffer = find_editor.doc().buffer;
             let find = common.find.clone();
@@ -458,15 +545,19 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             editor_tabs,
             editors,
             diff_editors,
+            images,
             docs,
             scratch_docs,
+            closed_docs,
             active_editor,
+            active_image,
             find_editor,
             replace_editor,
             diagnostics,
             locations,
             current_location,
             width: cx.create_rw_signal(0.0),
+            last_workspace_edit: cx.create_rw_signal(Vec::new()),
             common,
         }
     }
@@ -508,6 +599,7 @@ pub fn key_down<'a>(
             EditorTabChild::ThemeColorSettings(_) => None,
             EditorTabChild::Keymap(_) => None,
             EditorTabChild::Volt(_, _) => None,
+            EditorTabChild::Image(_, _) => None,
         }
     }
 
@@ -591,6 +683,17 @@ pub fn get_doc(
         let cx = self.scope;
         let doc = self.docs.with_untracked(|docs| docs.get(&path).cloned());
         if let Some(doc) = doc {
+            // The document is kept once its last editor is closed, but plugins
+            // were told the file was closed
+            if self
+                .closed_docs
+                .with_untracked(|closed| closed.contains(&path))
+            {
+                self.closed_docs.update(|closed| {
+                    closed.remove(&path);
+                });
+                self.common.proxy.buffer_opened(path);
+            }
             (doc, false)
         } else {
             let diagnostic_data = self.get_diagnostic_data(&path);
@@ -602,41 +705,108 @@ pub fn get_doc(
                 self.editors,
                 self.common.clone(),
             );
+            if self
+                .common
+                .config
+                .with_untracked(|config| config.editor.is_read_only_file(&path))
+            {
+                doc.content.update(|content| {
+                    if let DocContent::File { read_only, .. } = content {
+                        *read_only = true;
+                    }
+                });
+            }
             let doc = Rc::new(doc);
             self.docs.update(|docs| {
                 docs.insert(path.clone(), doc.clone());
             });
+            self.load_doc(doc.clone(), path, unsaved);
 
-            {
-                let doc = doc.clone();
-                let local_doc = doc.clone();
-                let send = create_ext_action(cx, move |result| {
-                    if let Ok(ProxyResponse::NewBufferResponse {
-                        content,
-                        read_only,
-                    }) = result
-                    {
-                        local_doc.init_content(Rope::from(content));
-                        if read_only {
-                            local_doc.content.update(|content| {
-                                if let DocContent::File { read_only, .. } = content {
-                                    *read_only = true;
-                                }
-                            });
-                        } else if let Some(unsaved) = unsaved {
-                            local_doc.reload(Rope::from(unsaved), false);
-                        }
-                    }
-                });
+            (doc, true)
+        }
+    }
 
-                self.common
-                    .proxy
-                    .new_buffer(doc.buffer_id, path, move |result| {
-                        send(result);
+    /// The revision of each document with unsaved changes, to tell when the unsaved
+    /// changes need storing again
+    pub fn unsaved_revs(&self) -> Vec<(BufferId, u64)> {
+        let docs = self.docs.get_untracked();
+        let scratch_docs = self.scratch_docs.get_untracked();
+        docs.values()
+            .chain(scratch_docs.values())
+            .filter(|doc| !doc.is_pristine())
+            .map(|doc| (doc.buffer_id, doc.rev()))
+            .collect()
+    }
+
+    /// Ask the proxy for the content of `path` and fill `doc` with it
+    fn load_doc(&self, doc: Rc<Doc>, path: PathBuf, unsaved: Option<String>) {
+        // Unsaved changes replace the content the undo history was stored with
+        let db: Option<Arc<LapceDb>> =
+            unsaved.is_none().then(|| use_context().unwrap());
+        let local_doc = doc.clone();
+        let send = create_ext_action(
+            self.scope,
+            move |(content, read_only, encoding): (
+                RestoredContent,
+                bool,
+                String,
+            )| {
+                local_doc.encoding.set(encoding);
+                local_doc.init_restored_content(content);
+                if read_only {
+                    local_doc.content.update(|content| {
+                        if let DocContent::File { read_only, .. } = content {
+                            *read_only = true;
+                        }
                     });
-            }
+                } else if let Some(unsaved) = unsaved {
+                    local_doc.reload(Rope::from(unsaved), false);
+                }
+            },
+        );
 
-            (doc, true)
+        self.common
+            .proxy
+            .new_buffer(doc.buffer_id, path.clone(), move |result| {
+                // The rope of a large file takes a while to build, so it's built
+                // here rather than on the UI thread, as is the undo history
+                if let Ok(ProxyResponse::NewBufferResponse {
+                    content,
+                    read_only,
+                    encoding,
+                }) = result
+                {
+                    let content = Rope::from(content);
+                    let undos = db
+                        .filter(|_| !read_only)
+                        .and_then(|db| db.get_undo_history(&path, &content).ok())
+                        .unwrap_or_default();
+                    let content = RestoredContent::new(content, undos);
+                    send((content, read_only, encoding));
+                }
+            });
+    }
+
+    /// Open every document again on a proxy that was restarted by a
+    /// reconnection, keeping the unsaved edits the editor has
+    pub fn reopen_docs(&self) {
+        let docs = self.docs.get_untracked();
+        // The new proxy opens all of them on its plugins
+        self.closed_docs.set(im::HashSet::new());
+        for (path, doc) in docs {
+            if doc.loaded() {
+                let content = doc.buffer.with_untracked(|b| b.to_string());
+                self.common.proxy.reopen_buffer(
+                    doc.buffer_id,
+                    path,
+                    content,
+                    doc.rev(),
+                    doc.encoding.get_untracked(),
+                );
+            } else {
+                // The request for its content went to the old proxy
+                self.load_doc(doc, path, None);
+            }
         }
     }
 
@@ -649,6 +819,15 @@ pub fn go_to_location(
             self.common.focus.set(Focus::Workbench);
         }
         let path = location.path.clone();
+        // A jump to somewhere in an svg, like a search result, opens its text
+        if is_image(&path) && location.position.is_none() {
+            self.get_editor_tab_child(
+                EditorTabChildSource::Image(path),
+                location.ignore_unconfirmed,
+                location.same_editor_tab,
+            );
+            return;
+        }
         let (doc, new_doc) = self.get_doc(path.clone(), None);
 
         let child = self.get_editor_tab_child(
@@ -663,32 +842,111 @@ pub fn go_to_location(
         }
     }
 
+    /// Open the changes of `path`, including the unsaved ones, since `HEAD`
     pub fn open_file_changes(&self, path: PathBuf) {
-        let (right, _) = self.get_doc(path.clone(), None);
-        let left = Doc::new_history(
+        let (left, send) = self.history_doc(path.clone(), "head".to_string());
+        self.common
+            .proxy
+            .get_buffer_head(path.clone(), move |result| {
+                send(result);
+            });
+        self.open_unsaved_changes(path, left);
+    }
+
+    /// Open the unsaved changes of `path` against its content on disk
+    pub fn compare_with_saved(&self, path: PathBuf) {
+        let (left, send) = self.history_doc(path.clone(), "saved".to_string());
+        self.common
+            .proxy
+            .get_buffer_saved(path.clone(), move |result| {
+                send(result);
+            });
+        self.open_unsaved_changes(path, left);
+    }
+
+    /// Open the content of the clipboard against the unsaved content of `path`
+    pub fn compare_with_clipboard(&self, path: PathBuf) {
+        let Some(content) = SystemClipboard::new().get_string() else {
+            return;
+        };
+        let left = Rc::new(Doc::new_history(
             self.scope,
             DocContent::History(DocHistory {
                 path: path.clone(),
-                version: "head".to_string(),
+                version: "clipboard".to_string(),
             }),
             self.editors,
             self.common.clone(),
+        ));
+        left.init_content(Rope::from(content));
+        self.open_unsaved_changes(path, left);
+    }
+
+    /// Open a diff editor of `left` against `path`, with its unsaved edits
+    fn open_unsaved_changes(&self, path: PathBuf, left: Rc<Doc>) {
+        let (right, _) = self.get_doc(path, None);
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor { left, right },
+            false,
+            false,
         );
-        let left = Rc::new(left);
+    }
+
+    /// A read-only document of `path` at `version`, along with the callback to fill
+    /// it with the `BufferHeadResponse` of the proxy
+    fn history_doc(
+        &self,
+        path: PathBuf,
+        version: String,
+    ) -> (
+        Rc<Doc>,
+        impl FnOnce(Result<ProxyResponse, RpcError>) + Send + 'static,
+    ) {
+        let doc = Rc::new(Doc::new_history(
+            self.scope,
+            DocContent::History(DocHistory { path, version }),
+            self.editors,
+            self.common.clone(),
+        ));
 
         let send = {
-            let left = left.clone();
+            let doc = doc.clone();
             create_ext_action(self.scope, move |result| {
                 if let Ok(ProxyResponse::BufferHeadResponse { content, .. }) = result
                 {
-                    left.init_content(Rope::from(content));
+                    doc.init_content(Rope::from(content));
                 }
             })
         };
-        self.common.proxy.get_buffer_head(path, move |result| {
-            send(result);
-        });
 
+        (doc, send)
+    }
+
+    /// A read-only document with the content of `path` at the git `revision`
+    fn revision_doc(&self, path: PathBuf, revision: String) -> Rc<Doc> {
+        let (doc, send) = self.history_doc(path.clone(), revision.clone());
+        self.common
+            .proxy
+            .git_file_at_revision(path, revision, move |result| {
+                send(result);
+            });
+        doc
+    }
+
+    /// Open the content of `path` at the git `revision` in a read-only editor
+    pub fn open_file_at_revision(&self, path: PathBuf, revision: String) {
+        let doc = self.revision_doc(path.clone(), revision);
+        self.get_editor_tab_child(
+            EditorTabChildSource::Editor { path, doc },
+            false,
+            false,
+        );
+    }
+
+    /// Open the changes that the commit `id` made to `path`
+    pub fn open_commit_file_changes(&self, path: PathBuf, id: String) {
+        let left = self.revision_doc(path.clone(), format!("{id}^"));
+        let right = self.revision_doc(path, id);
         self.get_editor_tab_child(
             EditorTabChildSource::DiffEditor { left, right },
             false,
@@ -772,6 +1030,15 @@ fn get_editor_tab_child(
             *editor_tab
         };
 
+        // An old version of a file is opened in its own editor, rather than the one
+        // of the file
+        let is_history = match &source {
+            EditorTabChildSource::Editor { doc, .. } => doc
+                .content
+                .with_untracked(|c| matches!(c, DocContent::History(_))),
+            _ => false,
+        };
+
         let is_same_diff_editor =
             |diff_editor_id: &DiffEditorId, left: &Rc<Doc>, right: &Rc<Doc>| {
                 diff_editors
@@ -800,12 +1067,15 @@ fn get_editor_tab_child(
                                         ..
                                     } = &source
                                     {
-                                        doc.content.with_untracked(|content| {
-                                            content
-                                                .path()
-                                                .map(|p| p == path)
-                                                .unwrap_or(false)
-                                        })
+                                        !is_history
+                                            && doc.content.with_untracked(
+                                                |content| {
+                                                    content
+                                                        .path()
+                                                        .map(|p| p == path)
+                                                        .unwrap_or(false)
+                                                },
+                                            )
                                     } else {
                                         false
                                     };
@@ -839,6 +1109,7 @@ fn get_editor_tab_child(
                         EditorTabChild::ThemeColorSettings(_) => true,
                         EditorTabChild::Keymap(_) => true,
                         EditorTabChild::Volt(_, _) => true,
+                        EditorTabChild::Image(_, _) => true,
                     };
 
                     if can_be_selected {
@@ -853,6 +1124,7 @@ fn get_editor_tab_child(
                     .with_untracked(|editor_tab| {
                         editor_tab
                             .get_editor(editors, path)
+                            .filter(|_| !is_history)
                             .map(|(i, _)| i)
                             .or_else(|| {
                                 if ignore_unconfirmed {
@@ -1004,6 +1276,33 @@ fn get_editor_tab_child(
                         })
                     }
                 }
+                EditorTabChildSource::Image(path) => {
+                    if let Some(index) =
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab.children.iter().position(|(_, _, child)| {
+                                if let EditorTabChild::Image(_, current_path) = child
+                                {
+                                    current_path == path
+                                } else {
+                                    false
+                                }
+                            })
+                        })
+                    {
+                        Some(index)
+                    } else if ignore_unconfirmed {
+                        None
+                    } else {
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab
+                                .get_unconfirmed_editor_tab_child(
+                                    editors,
+                                    &diff_editors,
+                                )
+                                .map(|(i, _)| i)
+                        })
+                    }
+                }
             }
         };
 
@@ -1034,6 +1333,7 @@ fn get_editor_tab_child(
                         self.editors,
                         self.common.clone(),
                     );
+                    doc.use_default_line_ending();
                     let doc = Rc::new(doc);
                     self.scratch_docs.update(|scratch_docs| {
                         scratch_docs.insert(name, doc.clone());
@@ -1061,6 +1361,10 @@ fn get_editor_tab_child(
                 EditorTabChildSource::Volt(id) => {
                     EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
                 }
+                EditorTabChildSource::Image(path) => EditorTabChild::Image(
+                    self.new_image_view(path.to_owned()),
+                    path.to_owned(),
+                ),
                 EditorTabChildSource::DiffEditor { left, right } => {
                     let diff_editor_id = DiffEditorId::next();
                     let diff_editor = DiffEditorData::new(
@@ -1097,6 +1401,7 @@ fn get_editor_tab_child(
                         EditorTabChild::ThemeColorSettings(_) => {}
                         EditorTabChild::Keymap(_) => {}
                         EditorTabChild::Volt(_, _) => {}
+                        EditorTabChild::Image(_, _) => {}
                     }
                     (editor_tab_id, current_child.clone())
                 });
@@ -1108,10 +1413,10 @@ fn get_editor_tab_child(
                     EditorTabChildSource::Editor { path, doc },
                 ) => {
                     if let Some(editor) = editors.editor_untracked(*editor_id) {
-                        let same_path = editor
-                            .doc()
-                            .content
-                            .with_untracked(|content| content.path() == Some(path));
+                        let same_path = !is_history
+                            && editor.doc().content.with_untracked(|content| {
+                                content.path() == Some(path)
+                            });
                         if !same_path {
                             editor.update_doc(doc.clone());
                             editor.cursor().set(Cursor::origin(
@@ -1163,6 +1468,11 @@ fn get_editor_tab_child(
                 EditorTabChild::ThemeColorSettings(_) => {}
                 EditorTabChild::Keymap(_) => {}
                 EditorTabChild::Volt(_, _) => {}
+                EditorTabChild::Image(id, _) => {
+                    self.images.update(|images| {
+                        images.remove(id);
+                    });
+                }
             }
 
             // Now loading the new child
@@ -1238,6 +1548,18 @@ fn get_editor_tab_child(
                                         false
                                     }
                                 }),
+                            EditorTabChildSource::Image(path) => editor_tab
+                                .children
+                                .iter()
+                                .position(|(_, _, child)| {
+                                    if let EditorTabChild::Image(_, current_path) =
+                                        child
+                                    {
+                                        current_path == path
+                                    } else {
+                                        false
+                                    }
+                                }),
                             EditorTabChildSource::NewFileEditor => None,
                         })
                     {
@@ -1308,6 +1630,21 @@ pub fn remove_editor(&self, editor_id: EditorId) {
                         scratch_docs.remove(&name);
                     });
                 }
+            } else if let DocContent::File { path, .. } = content {
+                let doc_shown = self.editors.with_editors_untracked(|editors| {
+                    editors.iter().any(|(_, editor_data)| {
+                        editor_data
+                            .doc()
+                            .content
+                            .with_untracked(|content| content.path() == Some(&path))
+                    })
+                });
+                if !doc_shown {
+                    self.closed_docs.update(|closed| {
+                        closed.insert(path.clone());
+                    });
+                    self.common.proxy.buffer_closed(path);
+                }
             }
         }
     }
@@ -1542,6 +1879,10 @@ fn split_editor_tab(
             EditorTabChild::Volt(_, id) => {
                 EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
             }
+            EditorTabChild::Image(_, path) => EditorTabChild::Image(
+                self.new_image_view(path.to_owned()),
+                path.to_owned(),
+            ),
         };
 
         let editor_tab = {
@@ -1884,6 +2225,7 @@ fn editor_tab_child_close_warning(
             EditorTabChild::ThemeColorSettings(_) => None,
             EditorTabChild::Keymap(_) => None,
             EditorTabChild::Volt(_, _) => None,
+            EditorTabChild::Image(_, _) => None,
         }
     }
 
@@ -2066,6 +2408,11 @@ pub fn editor_tab_child_close(
             EditorTabChild::ThemeColorSettings(_) => {}
             EditorTabChild::Keymap(_) => {}
             EditorTabChild::Volt(_, _) => {}
+            EditorTabChild::Image(id, _) => {
+                self.images.update(|images| {
+                    images.remove(&id);
+                });
+            }
         }
 
         if editor_tab_children_len == 0 {
@@ -2125,44 +2472,300 @@ fn resolve_code_action(&self, plugin_id: PluginId, action: CodeAction) {
             });
     }
 
-    /// Perform a workspace edit, which are from the LSP (such as code actions, or symbol renaming)
+    /// Perform a workspace edit, which are from the LSP (such as code actions, or
+    /// symbol renaming) or plugins.
+    /// The documents open in an editor are edited in place, and the others are
+    /// edited and saved in the background. The edit can be undone with "Undo Last
+    /// Refactoring".
     pub fn apply_workspace_edit(&self, edit: &WorkspaceEdit) {
-        if let Some(DocumentChanges::Operations(_op)) =
-            edit.document_changes.as_ref()
-        {
-            // TODO
+        let steps = workspace_edit_steps(edit);
+
+        // Nothing is applied if any document changed since the edit was made
+        let outdated = steps.iter().find_map(|step| match step {
+            WorkspaceEditStep::Edit {
+                path,
+                version: Some(version),
+                ..
+            } => self
+                .docs
+                .with_untracked(|docs| docs.get(path).cloned())
+                .filter(|doc| doc.rev() as i32 != *version)
+                .map(|_| path.clone()),
+            _ => None,
+        });
+        if let Some(path) = outdated {
+            self.workspace_edit_failed(format!(
+                "{} was changed since the edit was made",
+                path.display()
+            ));
+            return;
         }
 
-        if let Some(edits) = workspace_edits(edit) {
-            for (url, edits) in edits {
-                if let Ok(path) = url.to_file_path() {
-                    let active_path = self
-                        .active_editor
-                        .get_untracked()
-                        .map(|editor| editor.doc())
-                        .map(|doc| doc.content.get_untracked())
-                        .and_then(|content| content.path().cloned());
-                    let position = if active_path.as_ref() == Some(&path) {
-                        None
+        self.apply_workspace_edit_steps(steps.into(), Vec::new());
+    }
+
+    /// Apply the steps in order, waiting for each file operation and document load
+    fn apply_workspace_edit_steps(
+        &self,
+        mut steps: VecDeque<WorkspaceEditStep>,
+        mut applied: Vec<AppliedStep>,
+    ) {
+        while let Some(step) = steps.pop_front() {
+            match step {
+                WorkspaceEditStep::Edit { path, edits, .. } => {
+                    let (doc, _) = self.get_doc(path, None);
+                    if !doc.loaded() {
+                        let main_split = self.clone();
+                        let loaded = doc.loaded;
+                        let pending = Cell::new(Some((steps, applied, edits)));
+                        self.scope.create_effect(move |_| {
+                            if !loaded.get() {
+                                return;
+                            }
+                            if let Some((steps, mut applied, edits)) = pending.take()
+                            {
+                                applied
+                                    .push(main_split.apply_text_edits(&doc, &edits));
+                                main_split
+                                    .apply_workspace_edit_steps(steps, applied);
+                            }
+                        });
+                        return;
+                    }
+                    applied.push(self.apply_text_edits(&doc, &edits));
+                }
+                WorkspaceEditStep::Create {
+                    path,
+                    overwrite,
+                    ignore_if_exists,
+                } => {
+                    let main_split = self.clone();
+                    let send_path = path.clone();
+                    let send = create_ext_action(
+                        self.scope,
+                        move |result: Result<ProxyResponse, RpcError>| {
+                            let mut applied = applied;
+                            match result {
+                                Ok(_) => {
+                                    applied.push(AppliedStep::Create {
+                                        path: send_path,
+                                    });
+                                }
+                                Err(_) if ignore_if_exists => {}
+                                Err(err) => {
+                                    main_split.last_workspace_edit.set(applied);
+                                    main_split.workspace_edit_failed(err.message);
+                                    return;
+                                }
+                            }
+                            main_split.apply_workspace_edit_steps(steps, applied);
+                        },
+                    );
+                    let proxy = self.common.proxy.clone();
+                    if overwrite {
+                        self.common.proxy.trash_path(path.clone(), move |_| {
+                            proxy.create_file(path, send);
+                        });
                     } else {
-                        edits
-                            .first()
-                            .map(|edit| EditorPosition::Position(edit.range.start))
-                    };
-                    let location = EditorLocation {
-                        path,
-                        position,
-                        scroll_offset: None,
-                        ignore_unconfirmed: true,
-                        same_editor_tab: false,
-                    };
-                    self.jump_to_location(location, Some(edits));
+                        proxy.create_file(path, send);
+                    }
+                    return;
+                }
+                WorkspaceEditStep::Rename {
+                    from,
+                    to,
+                    ignore_if_exists,
+                } => {
+                    let main_split = self.clone();
+                    let (send_from, send_to) = (from.clone(), to.clone());
+                    let send = create_ext_action(
+                        self.scope,
+                        move |result: Result<ProxyResponse, RpcError>| {
+                            let mut applied = applied;
+                            match result {
+                                Ok(_) => {
+                                    main_split.rename_docs(&send_from, &send_to);
+                                    applied.push(AppliedStep::Rename {
+                                        from: send_from,
+                                        to: send_to,
+                                    });
+                                }
+                                Err(_) if ignore_if_exists => {}
+                                Err(err) => {
+                                    main_split.last_workspace_edit.set(applied);
+                                    main_split.workspace_edit_failed(err.message);
+                                    return;
+                                }
+                            }
+                            main_split.apply_workspace_edit_steps(steps, applied);
+                        },
+                    );
+                    self.common.proxy.rename_path(from, to, send);
+                    return;
+                }
+                WorkspaceEditStep::Delete { path } => {
+                    let main_split = self.clone();
+                    let send = create_ext_action(
+                        self.scope,
+                        move |result: Result<ProxyResponse, RpcError>| {
+                            let mut applied = applied;
+                            if let Err(err) = result {
+                                main_split.last_workspace_edit.set(applied);
+                                main_split.workspace_edit_failed(err.message);
+                                return;
+                            }
+                            applied.push(AppliedStep::Delete);
+                            main_split.apply_workspace_edit_steps(steps, applied);
+                        },
+                    );
+                    self.common.proxy.trash_path(path, send);
+                    return;
                 }
             }
         }
+        self.last_workspace_edit.set(applied);
+    }
+
+    /// Apply the edits to the document with the first editor it's open in, or in the
+    /// background and save it if it isn't open
+    fn apply_text_edits(&self, doc: &Rc<Doc>, edits: &[TextEdit]) -> AppliedStep {
+        let editor = self.editors.with_editors_untracked(|editors| {
+            editors
+                .values()
+                .find(|editor| editor.doc().buffer_id == doc.buffer_id)
+                .cloned()
+        });
+        let saved = if let Some(editor) = editor {
+            editor.do_text_edit(edits);
+            false
+        } else {
+            let edits = doc.buffer.with_untracked(|buffer| {
+                edits
+                    .iter()
+                    .map(|edit| {
+                        (
+                            Selection::region(
+                                buffer.offset_of_position(&edit.range.start),
+                                buffer.offset_of_position(&edit.range.end),
+                            ),
+                            edit.new_text.as_str(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+            doc.do_raw_edit(&edits, EditType::Completion);
+            doc.save(|| {});
+            true
+        };
+        AppliedStep::Edit {
+            doc: doc.clone(),
+            rev: doc.rev(),
+            saved,
+        }
+    }
+
+    /// Undo the last workspace edit, leaving the documents that were changed since
+    /// as they are
+    pub fn undo_workspace_edit(&self) {
+        let applied = self.last_workspace_edit.try_update(std::mem::take);
+        if let Some(applied) = applied {
+            self.undo_workspace_edit_steps(applied);
+        }
+    }
+
+    fn undo_workspace_edit_steps(&self, mut applied: Vec<AppliedStep>) {
+        while let Some(step) = applied.pop() {
+            match step {
+                AppliedStep::Edit { doc, rev, saved } => {
+                    if doc.rev() != rev {
+                        continue;
+                    }
+                    let editor = self.editors.with_editors_untracked(|editors| {
+                        editors
+                            .values()
+                            .find(|editor| editor.doc().buffer_id == doc.buffer_id)
+                            .cloned()
+                    });
+                    if let Some(editor) = editor {
+                        editor.run_edit_command(&EditCommand::Undo);
+                    } else {
+                        let mut cursor = Cursor::origin(false);
+                        doc.do_edit(
+                            &mut cursor,
+                            &EditCommand::Undo,
+                            false,
+                            &mut Register::default(),
+                            false,
+                        );
+                    }
+                    if saved {
+                        doc.save(|| {});
+                    }
+                }
+                AppliedStep::Create { path } => {
+                    let main_split = self.clone();
+                    let send = create_ext_action(self.scope, move |_| {
+                        main_split.undo_workspace_edit_steps(applied);
+                    });
+                    self.common.proxy.trash_path(path, send);
+                    return;
+                }
+                AppliedStep::Rename { from, to } => {
+                    let main_split = self.clone();
+                    let (send_from, send_to) = (from.clone(), to.clone());
+                    let send = create_ext_action(
+                        self.scope,
+                        move |result: Result<ProxyResponse, RpcError>| {
+                            if result.is_ok() {
+                                main_split.rename_docs(&send_to, &send_from);
+                            }
+                            main_split.undo_workspace_edit_steps(applied);
+                        },
+                    );
+                    self.common.proxy.rename_path(to, from, send);
+                    return;
+                }
+                AppliedStep::Delete => {}
+            }
+        }
+    }
+
+    /// Update the documents and editors of the file renamed from `from` to `to`
+    fn rename_docs(&self, from: &Path, to: &Path) {
+        self.editors.rename_path(from, to);
+        self.docs.update(|docs| {
+            if let Some(doc) = docs.remove(from) {
+                docs.insert(to.to_path_buf(), doc);
+            }
+        });
+    }
+
+    fn workspace_edit_failed(&self, msg: String) {
+        self.common
+            .internal_command
+            .send(InternalCommand::ShowAlert {
+                title: "Failed to apply the edit".to_string(),
+                msg,
+                buttons: Vec::new(),
+            });
     }
 
     pub fn next_error(&self) {
+        self.jump_to_error(next_in_file_errors_offset);
+    }
+
+    pub fn previous_error(&self) {
+        self.jump_to_error(previous_in_file_errors_offset);
+    }
+
+    /// Jump to the error picked by `pick` from the position of the cursor
+    fn jump_to_error(
+        &self,
+        pick: fn(
+            Option<(PathBuf, usize, Position)>,
+            &[(PathBuf, Vec<EditorDiagnostic>)],
+        ) -> (PathBuf, EditorPosition),
+    ) {
         let file_diagnostics =
             self.file_diagnostics_items(DiagnosticSeverity::ERROR);
         if file_diagnostics.is_empty() {
@@ -2179,8 +2782,7 @@ pub fn next_error(&self) {
                 );
                 path.map(|path| (path, offset, position))
             });
-        let (path, position) =
-            next_in_file_errors_offset(active_path, &file_diagnostics);
+        let (path, position) = pick(active_path, &file_diagnostics);
         let location = EditorLocation {
             path,
             position: Some(position),
@@ -2268,6 +2870,21 @@ pub fn open_file_changed(&self, path: &Path, content: &str) {
         doc.handle_file_changed(Rope::from(content));
     }
 
+    /// Show the start of a large file in its document while the rest is read
+    pub fn buffer_preview(&self, path: &Path, content: &str) {
+        let doc = self.docs.with_untracked(|docs| docs.get(path).cloned());
+        if let Some(doc) = doc {
+            doc.show_preview(Rope::from(content));
+        }
+    }
+
+    pub fn open_file_deleted(&self, path: &Path) {
+        let doc = self.docs.with_untracked(|docs| docs.get(path).cloned());
+        if let Some(doc) = doc {
+            doc.handle_file_deleted();
+        }
+    }
+
     pub fn set_find_pattern(&self, pattern: Option<String>) {
         if let Some(pattern) = pattern {
             self.find_editor.doc().reload(Rope::from(pattern), true);
@@ -2286,6 +2903,16 @@ pub fn open_volt_view(&self, id: VoltID) {
         self.get_editor_tab_child(EditorTabChildSource::Volt(id), false, false);
     }
 
+    /// Start loading the image at `path`, for a new image view to show
+    pub fn new_image_view(&self, path: PathBuf) -> ImageViewId {
+        let id = ImageViewId::next();
+        let image = ImageViewData::new(self.scope, id, path, &self.common);
+        self.images.update(|images| {
+            images.insert(id, image);
+        });
+        id
+    }
+
     pub fn open_settings(&self) {
         self.get_editor_tab_child(EditorTabChildSource::Settings, false, false);
     }
@@ -2614,6 +3241,7 @@ fn editor_tab_child_set_parent(
             EditorTabChild::ThemeColorSettings(_) => {}
             EditorTabChild::Keymap(_) => {}
             EditorTabChild::Volt(_, _) => {}
+            EditorTabChild::Image(_, _) => {}
         }
         Some(())
     }
@@ -2849,48 +3477,6 @@ pub fn show_env(&self) {
     }
 }
 
-fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>> {
-    if let Some(changes) = edit.changes.as_ref() {
-        return Some(changes.clone());
-    }
-
-    let changes = edit.document_changes.as_ref()?;
-    let edits = match changes {
-        DocumentChanges::Edits(edits) => edits
-            .iter()
-            .map(|e| {
-                (
-                    e.text_document.uri.clone(),
-                    e.edits
-                        .iter()
-                        .map(|e| match e {
-                            OneOf::Left(e) => e.clone(),
-                            OneOf::Right(e) => e.text_edit.clone(),
-                        })
-                        .collect(),
-                )
-            })
-            .collect::<HashMap<Url, Vec<TextEdit>>>(),
-        DocumentChanges::Operations(ops) => ops
-            .iter()
-            .filter_map(|o| match o {
-                DocumentChangeOperation::Op(_op) => None,
-                DocumentChangeOperation::Edit(e) => Some((
-                    e.text_document.uri.clone(),
-                    e.edits
-                        .iter()
-                        .map(|e| match e {
-                            OneOf::Left(e) => e.clone(),
-                            OneOf::Right(e) => e.text_edit.clone(),
-                        })
-                        .collect(),
-                )),
-            })
-            .collect::<HashMap<Url, Vec<TextEdit>>>(),
-    };
-    Some(edits)
-}
-
 fn next_in_file_errors_offset(
     active_path: Option<(PathBuf, usize, Position)>,
     file_diagnostics: &[(PathBuf, Vec<EditorDiagnostic>)],
@@ -2949,3 +3535,41 @@ fn next_in_file_errors_offset(
         },
     )
 }
+
+fn previous_in_file_errors_offset(
+    active_path: Option<(PathBuf, usize, Position)>,
+    file_diagnostics: &[(PathBuf, Vec<EditorDiagnostic>)],
+) -> (PathBuf, EditorPosition) {
+    let start = |diagnostic: &EditorDiagnostic| {
+        if let Some((start, _)) = diagnostic.range {
+            EditorPosition::Offset(start)
+        } else {
+            EditorPosition::Position(diagnostic.diagnostic.range.start)
+        }
+    };
+
+    if let Some((active_path, offset, position)) = active_path {
+        for (current_path, diagnostics) in file_diagnostics.iter().rev() {
+            if &active_path == current_path {
+                for diagnostic in diagnostics.iter().rev() {
+                    let is_before = if let Some((start, _)) = diagnostic.range {
+                        start < offset
+                    } else {
+                        diagnostic.diagnostic.range.start < position
+                    };
+                    if is_before {
+                        return (current_path.clone(), start(diagnostic));
+                    }
+                }
+            }
+            if current_path < &active_path {
+                if let Some(diagnostic) = diagnostics.last() {
+                    return (current_path.clone(), start(diagnostic));
+                }
+            }
+        }
+    }
+
+    let (path, diagnostics) = &file_diagnostics[file_diagnostics.len() - 1];
+    (path.clone(), start(&diagnostics[diagnostics.len() - 1]))
+}
@@ -0,0 +1,275 @@
+use std::{sync::Arc, time::Duration};
+
+use floem::{
+    action::exec_after,
+    cosmic_text::Weight,
+    reactive::{ReadSignal, RwSignal, Scope},
+    style::{CursorStyle, FlexWrap},
+    views::{container, dyn_stack, label, scroll, stack, svg, text, Decorators},
+    View,
+};
+use lsp_types::MessageType;
+
+use crate::{
+    alert::AlertButton,
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+};
+
+/// How many notifications the history keeps, dropping the oldest after that
+const HISTORY_LEN: usize = 200;
+
+/// An error, warning or piece of information from Lapce, the proxy, a language
+/// server or a plugin
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub id: u64,
+    pub title: String,
+    pub message: String,
+    pub typ: MessageType,
+    /// When it was shown, in seconds since the Unix epoch
+    pub time: i64,
+    /// What can be done about it, shown as buttons on the toast and in the history
+    pub actions: Vec<AlertButton>,
+}
+
+#[derive(Clone, Copy)]
+pub struct NotificationData {
+    /// Every notification shown, newest first
+    pub history: RwSignal<im::Vector<Notification>>,
+    /// The notifications shown as toasts in the corner of the window, until
+    /// they're dismissed or time out
+    pub toasts: RwSignal<im::Vector<Notification>>,
+    /// How many notifications came in since the history was last looked at
+    pub unread: RwSignal<usize>,
+    next_id: RwSignal<u64>,
+}
+
+impl NotificationData {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            history: cx.create_rw_signal(im::Vector::new()),
+            toasts: cx.create_rw_signal(im::Vector::new()),
+            unread: cx.create_rw_signal(0),
+            next_id: cx.create_rw_signal(0),
+        }
+    }
+
+    /// Show a notification as a toast, and add it to the history
+    pub fn notify(
+        &self,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        typ: MessageType,
+        actions: Vec<AlertButton>,
+    ) {
+        let id = self.next_id.get_untracked();
+        self.next_id.set(id + 1);
+        let notification = Notification {
+            id,
+            title: title.into(),
+            message: message.into(),
+            typ,
+            time: chrono::Utc::now().timestamp(),
+            actions,
+        };
+
+        self.history.update(|history| {
+            history.push_front(notification.clone());
+            history.truncate(HISTORY_LEN);
+        });
+        self.unread.update(|unread| *unread += 1);
+        self.toasts.update(|toasts| toasts.push_back(notification));
+
+        let notifications = *self;
+        exec_after(toast_duration(typ), move |_| {
+            notifications.dismiss_toast(id);
+        });
+    }
+
+    /// Stop showing the notification `id` as a toast, keeping it in the history
+    pub fn dismiss_toast(&self, id: u64) {
+        if self.toasts.with_untracked(|t| t.iter().any(|n| n.id == id)) {
+            self.toasts.update(|toasts| toasts.retain(|n| n.id != id));
+        }
+    }
+
+    /// Remove the notification `id` from the history, and its toast
+    pub fn remove(&self, id: u64) {
+        self.dismiss_toast(id);
+        self.history
+            .update(|history| history.retain(|n| n.id != id));
+    }
+
+    pub fn clear(&self) {
+        self.toasts.set(im::Vector::new());
+        self.history.set(im::Vector::new());
+        self.unread.set(0);
+    }
+
+    pub fn mark_read(&self) {
+        if self.unread.get_untracked() > 0 {
+            self.unread.set(0);
+        }
+    }
+}
+
+/// How long a toast is shown for, longer for the notifications that matter more
+fn toast_duration(typ: MessageType) -> Duration {
+    match typ {
+        MessageType::ERROR => Duration::from_secs(15),
+        MessageType::WARNING => Duration::from_secs(10),
+        _ => Duration::from_secs(5),
+    }
+}
+
+/// The toasts of the notifications that came in lately, in the bottom right
+/// corner of the window
+pub fn notification_toasts(
+    notifications: NotificationData,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    container(
+        container(
+            container(
+                scroll(
+                    dyn_stack(
+                        move || notifications.toasts.get(),
+                        |notification| notification.id,
+                        move |notification| {
+                            let id = notification.id;
+                            notification_view(notification, config, move || {
+                                notifications.dismiss_toast(id)
+                            })
+                            .style(move |s| {
+                                let config = config.get();
+                                s.width_full()
+                                    .padding(10.0)
+                                    .margin_top(10.0)
+                                    .border(1.0)
+                                    .border_radius(6.0)
+                                    .border_color(
+                                        config.color(LapceColor::LAPCE_BORDER),
+                                    )
+                                    .background(
+                                        config.color(LapceColor::PANEL_BACKGROUND),
+                                    )
+                            })
+                        },
+                    )
+                    .style(|s| s.flex_col().width_full()),
+                )
+                .style(|s| {
+                    s.absolute().width_full().min_height(0.0).max_height_full()
+                }),
+            )
+            .style(|s| s.size_full()),
+        )
+        .style(|s| {
+            s.width(360.0)
+                .max_width_pct(80.0)
+                .padding(10.0)
+                .height_full()
+        }),
+    )
+    .style(|s| s.absolute().size_full().justify_end())
+    .debug_name("Notification Toasts")
+}
+
+/// A notification with its icon, its message and its actions. Closing it, or
+/// running one of its actions, calls `on_close`.
+pub fn notification_view(
+    notification: Notification,
+    config: ReadSignal<Arc<LapceConfig>>,
+    on_close: impl Fn() + Clone + 'static,
+) -> impl View {
+    let Notification {
+        title,
+        message,
+        typ,
+        actions,
+        ..
+    } = notification;
+    let on_action_close = on_close.clone();
+
+    stack((
+        svg(move || config.get().ui_svg(notification_icon(typ))).style(move |s| {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+            s.min_width(size)
+                .size(size, size)
+                .margin_right(10.0)
+                .margin_top(4.0)
+                .color(config.color(notification_color(typ)))
+        }),
+        stack((
+            text(title).style(|s| {
+                s.min_width(0.0).line_height(1.6).font_weight(Weight::BOLD)
+            }),
+            text(message)
+                .style(|s| s.min_width(0.0).line_height(1.6).margin_top(5.0)),
+            dyn_stack(
+                move || actions.clone(),
+                |action| action.text.clone(),
+                move |action| {
+                    let on_close = on_action_close.clone();
+                    label(move || action.text.clone())
+                        .on_click_stop(move |_| {
+                            (action.action)();
+                            on_close();
+                        })
+                        .style(move |s| {
+                            let config = config.get();
+                            s.margin_top(8.0)
+                                .margin_right(8.0)
+                                .padding_horiz(10.0)
+                                .line_height(1.6)
+                                .border(1.0)
+                                .border_radius(6.0)
+                                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                                .hover(|s| {
+                                    s.cursor(CursorStyle::Pointer).background(
+                                        config.color(
+                                            LapceColor::PANEL_HOVERED_BACKGROUND,
+                                        ),
+                                    )
+                                })
+                                .active(|s| {
+                                    s.background(config.color(
+                                        LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND,
+                                    ))
+                                })
+                        })
+                },
+            )
+            .style(|s| s.flex_wrap(FlexWrap::Wrap)),
+        ))
+        .style(|s| s.flex_col().min_width(0.0).flex_basis(0.0).flex_grow(1.0)),
+        clickable_icon(
+            || LapceIcons::CLOSE,
+            on_close,
+            || false,
+            || false,
+            || "Close",
+            config,
+        )
+        .style(|s| s.margin_left(6.0)),
+    ))
+    .style(|s| s.items_start())
+}
+
+fn notification_icon(typ: MessageType) -> &'static str {
+    match typ {
+        MessageType::ERROR => LapceIcons::ERROR,
+        MessageType::WARNING => LapceIcons::WARNING,
+        _ => LapceIcons::LIGHTBULB,
+    }
+}
+
+fn notification_color(typ: MessageType) -> &'static str {
+    match typ {
+        MessageType::ERROR => LapceColor::LAPCE_ERROR,
+        MessageType::WARNING => LapceColor::LAPCE_WARN,
+        _ => LapceColor::LAPCE_ICON_ACTIVE,
+    }
+}
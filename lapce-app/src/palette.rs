@@ -7,7 +7,7 @@
         atomic::{AtomicU64, Ordering},
         Arc,
     },
-    time::Instant,
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::Result;
@@ -16,14 +16,24 @@
     ext_event::{create_ext_action, create_signal_from_channel},
     keyboard::Modifiers,
     reactive::{use_context, ReadSignal, RwSignal, Scope},
+    views::editor::text::SystemClipboard,
 };
 use itertools::Itertools;
 use lapce_core::{
-    buffer::rope_text::RopeText, command::FocusCommand, language::LapceLanguage,
-    line_ending::LineEnding, mode::Mode, movement::Movement, selection::Selection,
+    buffer::rope_text::RopeText,
+    command::{EditCommand, FocusCommand},
+    language::LapceLanguage,
+    line_ending::LineEnding,
+    mode::Mode,
+    movement::Movement,
+    register::Clipboard,
+    selection::Selection,
     syntax::Syntax,
 };
-use lapce_rpc::proxy::ProxyResponse;
+use lapce_rpc::{
+    buffer::FILE_ENCODINGS, proxy::ProxyResponse, source_control::GitSyncKind,
+    terminal::TermId,
+};
 use lapce_xi_rope::Rope;
 use lsp_types::DocumentSymbolResponse;
 use nucleo::Utf32Str;
@@ -38,8 +48,11 @@
     command::{
         CommandExecuted, CommandKind, InternalCommand, LapceCommand, WindowCommand,
     },
+    config::core::{PaletteModeAction, PaletteModeConfig},
     db::LapceDb,
-    debug::{RunDebugConfigs, RunDebugMode},
+    debug::{
+        BreakpointConditionKind, BreakpointTarget, RunDebugConfigs, RunDebugMode,
+    },
     editor::{
         location::{EditorLocation, EditorPosition},
         EditorData,
@@ -49,7 +62,7 @@
     main_split::MainSplitData,
     source_control::SourceControlData,
     window_tab::{CommonData, Focus},
-    workspace::{LapceWorkspace, LapceWorkspaceType, SshHost},
+    workspace::{ContainerHost, LapceWorkspace, LapceWorkspaceType, SshHost},
 };
 
 pub mod item;
@@ -68,12 +81,36 @@ pub enum PaletteStatus {
 pub struct PaletteInput {
     pub input: String,
     pub kind: PaletteKind,
+    /// The name of the user defined palette mode, when `kind` is [`PaletteKind::Custom`]
+    pub custom_mode: Option<String>,
 }
 
 impl PaletteInput {
     /// Update the current input in the palette, and the kind of palette it is
-    pub fn update_input(&mut self, input: String, kind: PaletteKind) {
+    pub fn update_input(
+        &mut self,
+        input: String,
+        kind: PaletteKind,
+        modes: &HashMap<String, PaletteModeConfig>,
+    ) {
         self.kind = kind.get_palette_kind(&input);
+        self.custom_mode = None;
+        if self.kind == PaletteKind::File {
+            // User defined modes can only use prefixes that aren't taken by a builtin kind.
+            // The longest matching prefix wins, so that e.g. `!!` can coexist with `!`.
+            if let Some((name, mode)) = modes
+                .iter()
+                .filter(|(_, mode)| {
+                    !mode.prefix.is_empty() && input.starts_with(&mode.prefix)
+                })
+                .max_by_key(|(_, mode)| mode.prefix.len())
+            {
+                self.kind = PaletteKind::Custom;
+                self.custom_mode = Some(name.clone());
+                self.input = input[mode.prefix.len()..].to_string();
+                return;
+            }
+        }
         self.input = self.kind.get_input(&input).to_string();
     }
 }
@@ -100,6 +137,15 @@ pub struct PaletteData {
     pub executed_run_configs: Rc<RefCell<HashMap<(RunDebugMode, String), Instant>>>,
     pub main_split: MainSplitData,
     pub references: RwSignal<Vec<EditorLocation>>,
+    /// The git remote operation to retry with the access token entered
+    pub git_sync: RwSignal<Option<GitSyncKind>>,
+    /// The terminal to give the name entered in the palette
+    rename_terminal: RwSignal<Option<TermId>>,
+    /// The watch expression to replace with the one entered in the palette,
+    /// none to add it
+    watch_expression: RwSignal<Option<usize>>,
+    /// The breakpoint line to give the condition entered in the palette
+    breakpoint_condition: RwSignal<Option<BreakpointTarget>>,
     pub source_control: SourceControlData,
     pub common: Rc<CommonData>,
     left_diff_path: RwSignal<Option<PathBuf>>,
@@ -125,9 +171,14 @@ pub fn new(
         let preselect_index = cx.create_rw_signal(None);
         let index = cx.create_rw_signal(0);
         let references = cx.create_rw_signal(Vec::new());
+        let git_sync = cx.create_rw_signal(None);
+        let rename_terminal = cx.create_rw_signal(None);
+        let watch_expression = cx.create_rw_signal(None);
+        let breakpoint_condition = cx.create_rw_signal(None);
         let input = cx.create_rw_signal(PaletteInput {
             input: "".to_string(),
             kind: PaletteKind::File,
+            custom_mode: None,
         });
         let kind = cx.create_rw_signal(PaletteKind::File);
         let input_editor = main_split.editors.make_local(cx, common.clone());
@@ -222,6 +273,10 @@ pub fn new(
             executed_commands: Rc::new(RefCell::new(HashMap::new())),
             executed_run_configs: Rc::new(RefCell::new(HashMap::new())),
             references,
+            git_sync,
+            rename_terminal,
+            watch_expression,
+            breakpoint_condition,
             source_control,
             common,
             left_diff_path,
@@ -243,6 +298,7 @@ pub fn new(
             let palette = palette.clone();
             let doc = palette.input_editor.doc();
             let input = palette.input;
+            let config = palette.common.config;
             let status = palette.status.read_only();
             let preset_kind = palette.kind.read_only();
             // Monitors when the palette's input changes, so that it can update the stored input
@@ -266,14 +322,20 @@ pub fn new(
                 let changed = last_input.as_deref() != Some(new_input.as_str());
 
                 if changed {
+                    let config = config.get_untracked();
                     let new_kind = input
                         .try_update(|input| {
                             let kind = input.kind;
+                            let custom_mode = input.custom_mode.take();
                             input.update_input(
                                 new_input.clone(),
                                 preset_kind.get_untracked(),
+                                &config.core.palette_modes,
                             );
-                            if last_input.is_none() || kind != input.kind {
+                            if last_input.is_none()
+                                || kind != input.kind
+                                || custom_mode != input.custom_mode
+                            {
                                 Some(input.kind)
                             } else {
                                 None
@@ -282,10 +344,15 @@ pub fn new(
                         .unwrap();
                     if let Some(new_kind) = new_kind {
                         palette.run_inner(new_kind);
-                    } else if input
-                        .with_untracked(|i| i.kind == PaletteKind::WorkspaceSymbol)
-                    {
-                        palette.run_inner(PaletteKind::WorkspaceSymbol);
+                    } else if let Some(kind) = input.with_untracked(|i| {
+                        matches!(
+                            i.kind,
+                            PaletteKind::WorkspaceSymbol
+                                | PaletteKind::SCMReferences
+                        )
+                        .then_some(i.kind)
+                    }) {
+                        palette.run_inner(kind);
                     }
                 }
                 Some(new_input)
@@ -317,15 +384,44 @@ pub fn new(
 
     /// Start and focus the palette for the given kind.
     pub fn run(&self, kind: PaletteKind) {
+        self.run_with_input(kind, kind.symbol());
+    }
+
+    /// Start and focus the palette for the given kind, with `input` as the initial content of
+    /// the palette input.
+    fn run_with_input(&self, kind: PaletteKind, input: &str) {
         self.common.focus.set(Focus::Palette);
         self.status.set(PaletteStatus::Started);
-        let symbol = kind.symbol();
         self.kind.set(kind);
-        // Refresh the palette input with only the symbol prefix, losing old content.
-        self.input_editor.doc().reload(Rope::from(symbol), true);
+        // Refresh the palette input with only the given input, losing old content.
+        self.input_editor.doc().reload(Rope::from(input), true);
         self.input_editor
             .cursor()
-            .update(|cursor| cursor.set_insert(Selection::caret(symbol.len())));
+            .update(|cursor| cursor.set_insert(Selection::caret(input.len())));
+    }
+
+    /// Ask for a new name for the terminal `term_id`, starting from `name`
+    pub fn run_rename_terminal(&self, term_id: TermId, name: &str) {
+        self.rename_terminal.set(Some(term_id));
+        self.run_with_input(PaletteKind::RenameTerminal, name);
+    }
+
+    /// Ask for a watch expression to replace the one at `index` with, starting
+    /// from `expression`, or for one to add without `index`
+    pub fn run_watch_expression(&self, index: Option<usize>, expression: &str) {
+        self.watch_expression.set(index);
+        self.run_with_input(PaletteKind::WatchExpression, expression);
+    }
+
+    /// Ask for a condition of the breakpoint at the line, starting from its
+    /// current one
+    pub fn run_breakpoint_condition(
+        &self,
+        target: BreakpointTarget,
+        condition: &str,
+    ) {
+        self.breakpoint_condition.set(Some(target));
+        self.run_with_input(PaletteKind::BreakpointCondition, condition);
     }
 
     /// Get the placeholder text to use in the palette input field.
@@ -334,6 +430,7 @@ pub fn placeholder_text(&self) -> &'static str {
             PaletteKind::SshHost => {
                 "Type [user@]host or select a previously connected workspace below"
             }
+            PaletteKind::ContainerHost => "Select a running container",
             PaletteKind::DiffFiles => {
                 if self.left_diff_path.with(Option::is_some) {
                     "Select right file"
@@ -341,6 +438,37 @@ pub fn placeholder_text(&self) -> &'static str {
                     "Seleft left file"
                 }
             }
+            PaletteKind::SCMReferences => {
+                "Select a branch or tag to checkout, or type a new branch name"
+            }
+            PaletteKind::GitCredentials => {
+                "Authentication failed, enter an access token to try again"
+            }
+            PaletteKind::RenameTerminal => {
+                "Enter the terminal name, or nothing to use the shell's title"
+            }
+            PaletteKind::WatchExpression => {
+                "Enter the expression to watch, or nothing to remove it"
+            }
+            PaletteKind::BreakpointCondition => match self
+                .breakpoint_condition
+                .with(|target| target.as_ref().map(|target| target.kind))
+            {
+                Some(BreakpointConditionKind::HitCount) => {
+                    "Enter how many hits to stop after, or nothing to always stop"
+                }
+                Some(BreakpointConditionKind::LogMessage) => {
+                    "Enter the message to log with {expressions}, or nothing to stop"
+                }
+                _ => "Enter the condition to stop on, or nothing to always stop",
+            },
+            PaletteKind::LanguageServers => "Select a language server to restart it",
+            PaletteKind::ReopenWithEncoding => {
+                "Select the encoding to reopen the file with"
+            }
+            PaletteKind::SaveWithEncoding => {
+                "Select the encoding to save the file in"
+            }
             _ => "",
         }
     }
@@ -383,6 +511,9 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::WslHost => {
                 self.get_wsl_hosts();
             }
+            PaletteKind::ContainerHost => {
+                self.get_container_hosts();
+            }
             PaletteKind::RunAndDebug => {
                 self.get_run_configs();
             }
@@ -398,10 +529,22 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::LineEnding => {
                 self.get_line_endings();
             }
+            PaletteKind::ReopenWithEncoding => {
+                self.get_encodings(false);
+            }
+            PaletteKind::SaveWithEncoding => {
+                self.get_encodings(true);
+            }
             PaletteKind::SCMReferences => {
                 self.get_scm_references();
             }
             PaletteKind::TerminalProfile => self.get_terminal_profiles(),
+            PaletteKind::LanguageServers => self.get_language_servers(),
+            PaletteKind::GitCredentials
+            | PaletteKind::RenameTerminal
+            | PaletteKind::WatchExpression
+            | PaletteKind::BreakpointCondition => self.items.set(im::Vector::new()),
+            PaletteKind::Custom => self.get_custom_items(),
         }
     }
 
@@ -433,18 +576,41 @@ fn get_palette_help(&self) {
                     score: 0,
                     indices: vec![],
                 }
-            })
-            .collect();
+            });
 
-        self.items.set(items);
+        let config = self.common.config.get_untracked();
+        let custom_items = config
+            .core
+            .palette_modes
+            .iter()
+            .filter(|(_, mode)| !mode.prefix.is_empty())
+            .sorted_by(|(_, a), (_, b)| a.prefix.cmp(&b.prefix))
+            .map(|(name, mode)| {
+                let description = mode.prefix.clone()
+                    + " "
+                    + mode.description.as_deref().unwrap_or(name);
+
+                PaletteItem {
+                    content: PaletteItemContent::PaletteMode {
+                        prefix: mode.prefix.clone(),
+                    },
+                    filter_text: description,
+                    score: 0,
+                    indices: vec![],
+                }
+            });
+
+        self.items.set(items.chain(custom_items).collect());
     }
 
     /// Initialize the palette with the files in the current workspace.
     fn get_files(&self) {
         let workspace = self.workspace.clone();
         let set_items = self.items.write_only();
+        let fs_cache = self.common.fs_cache.clone();
         let send =
             create_ext_action(self.common.scope, move |items: Vec<PathBuf>| {
+                fs_cache.set_files(items.clone());
                 let items = items
                     .into_iter()
                     .map(|full_path| {
@@ -469,6 +635,10 @@ fn get_files(&self) {
                     .collect::<im::Vector<_>>();
                 set_items.set(items);
             });
+        if let Some(items) = self.common.fs_cache.files() {
+            send(items);
+            return;
+        }
         self.common.proxy.get_files(move |result| {
             if let Ok(ProxyResponse::GetFilesResponse { items }) = result {
                 send(items);
@@ -530,7 +700,7 @@ fn get_commands(&self) {
                 .rev()
                 .filter_map(|(key, _)| {
                     keypress.commands.get(key).and_then(|c| {
-                        c.kind.desc().as_ref().map(|m| PaletteItem {
+                        c.desc().map(|m| PaletteItem {
                             content: PaletteItemContent::Command { cmd: c.clone() },
                             filter_text: m.to_string(),
                             score: 0,
@@ -541,16 +711,16 @@ fn get_commands(&self) {
                 .collect();
             // Add all the rest of the commands, ignoring palette commands (because we're in it)
             // and commands that are sorted earlier due to being executed.
-            items.extend(keypress.commands.iter().filter_map(|(_, c)| {
-                if EXCLUDED_ITEMS.contains(&c.kind.str()) {
+            items.extend(keypress.commands.iter().filter_map(|(name, c)| {
+                if EXCLUDED_ITEMS.contains(&name.as_str()) {
                     return None;
                 }
 
-                if self.executed_commands.borrow().contains_key(c.kind.str()) {
+                if self.executed_commands.borrow().contains_key(name) {
                     return None;
                 }
 
-                c.kind.desc().as_ref().map(|m| PaletteItem {
+                c.desc().map(|m| PaletteItem {
                     content: PaletteItemContent::Command { cmd: c.clone() },
                     filter_text: m.to_string(),
                     score: 0,
@@ -564,14 +734,15 @@ fn get_commands(&self) {
         self.items.set(items);
     }
 
-    /// Initialize the palette with all the available workspaces, local and remote.
+    /// Initialize the palette with all the available workspaces, local and remote,
+    /// with the pinned ones first.
     fn get_workspaces(&self) {
         let db: Arc<LapceDb> = use_context().unwrap();
-        let workspaces = db.recent_workspaces().unwrap_or_default();
+        let workspaces = db.recent_workspaces_with_pins();
 
         let items = workspaces
             .into_iter()
-            .filter_map(|w| {
+            .filter_map(|(w, pinned)| {
                 let text = w.path.as_ref()?.to_str()?.to_string();
                 let filter_text = match &w.kind {
                     LapceWorkspaceType::Local => text,
@@ -582,9 +753,15 @@ fn get_workspaces(&self) {
                     LapceWorkspaceType::RemoteWSL(remote) => {
                         format!("[{remote}] {text}")
                     }
+                    LapceWorkspaceType::RemoteContainer(remote) => {
+                        format!("[{remote}] {text}")
+                    }
                 };
                 Some(PaletteItem {
-                    content: PaletteItemContent::Workspace { workspace: w },
+                    content: PaletteItemContent::Workspace {
+                        workspace: w,
+                        pinned,
+                    },
                     filter_text,
                     score: 0,
                     indices: vec![],
@@ -595,6 +772,24 @@ fn get_workspaces(&self) {
         self.items.set(items);
     }
 
+    /// Pin the workspace to the top of the recent workspaces, or unpin it
+    pub fn pin_workspace(&self, workspace: &LapceWorkspace, pinned: bool) {
+        let db: Arc<LapceDb> = use_context().unwrap();
+        if let Err(err) = db.set_workspace_pinned(workspace, pinned) {
+            error!("{:?}", err);
+        }
+        self.get_workspaces();
+    }
+
+    /// Forget the workspace was opened, so it's no longer in the recent workspaces
+    pub fn remove_workspace(&self, workspace: &LapceWorkspace) {
+        let db: Arc<LapceDb> = use_context().unwrap();
+        if let Err(err) = db.remove_recent_workspace(workspace) {
+            error!("{:?}", err);
+        }
+        self.get_workspaces();
+    }
+
     /// Initialize the list of references in the file, from the current editor location.
     fn get_references(&self) {
         let items = self
@@ -823,6 +1018,42 @@ fn get_wsl_hosts(&self) {
         self.items.set(items);
     }
 
+    /// The running containers of Docker and of Podman, whichever are installed
+    fn get_container_hosts(&self) {
+        let mut hosts = Vec::new();
+        for engine in ["docker", "podman"] {
+            let Ok(output) = new_command(engine)
+                .args(["ps", "--format", "{{.Names}}"])
+                .output()
+            else {
+                continue;
+            };
+            if !output.status.success() {
+                continue;
+            }
+            for container in String::from_utf8_lossy(&output.stdout).lines() {
+                let container = container.trim();
+                if !container.is_empty() {
+                    hosts.push(ContainerHost {
+                        engine: engine.to_string(),
+                        container: container.to_string(),
+                    });
+                }
+            }
+        }
+
+        let items = hosts
+            .into_iter()
+            .map(|host| PaletteItem {
+                filter_text: host.to_string(),
+                content: PaletteItemContent::ContainerHost { host },
+                score: 0,
+                indices: vec![],
+            })
+            .collect();
+        self.items.set(items);
+    }
+
     fn set_run_configs(&self, content: String) {
         let configs: Option<RunDebugConfigs> = toml::from_str(&content).ok();
         if configs.is_none() {
@@ -885,6 +1116,11 @@ fn set_run_configs(&self, content: String) {
     }
 
     fn get_run_configs(&self) {
+        // The run configurations of a workspace in restricted mode aren't run
+        if self.common.workspace_trust.get_untracked() != Some(true) {
+            self.items.set(im::Vector::new());
+            return;
+        }
         if let Some(workspace) = self.common.workspace.path.as_deref() {
             let run_toml = workspace.join(".lapce").join("run.toml");
             let (doc, new_doc) = self.main_split.get_doc(run_toml.clone(), None);
@@ -919,7 +1155,13 @@ fn get_color_themes(&self) {
             .color_theme_list()
             .iter()
             .map(|name| PaletteItem {
-                content: PaletteItemContent::ColorTheme { name: name.clone() },
+                content: PaletteItemContent::ColorTheme {
+                    name: name.clone(),
+                    source: config
+                        .color_theme_source(name)
+                        .unwrap_or_default()
+                        .to_string(),
+                },
                 filter_text: name.clone(),
                 score: 0,
                 indices: Vec::new(),
@@ -992,9 +1234,28 @@ fn get_line_endings(&self) {
     }
 
     fn get_scm_references(&self) {
+        let input = self.input.get_untracked().input;
         let branches = self.source_control.branches.get_untracked();
+        let remote_branches = self.source_control.remote_branches.get_untracked();
         let tags = self.source_control.tags.get_untracked();
         let mut items: im::Vector<PaletteItem> = im::Vector::new();
+
+        // Offer to create a branch named after the input when there isn't one yet
+        let name = input.trim();
+        if !name.is_empty()
+            && !name.contains(char::is_whitespace)
+            && !branches.iter().any(|b| b == name)
+        {
+            items.push_back(PaletteItem {
+                content: PaletteItemContent::SCMCreateBranch {
+                    name: name.to_string(),
+                },
+                filter_text: format!("Create branch: {name}"),
+                score: 0,
+                indices: Vec::new(),
+            });
+        }
+
         for refs in branches.into_iter() {
             items.push_back(PaletteItem {
                 content: PaletteItemContent::SCMReference {
@@ -1005,7 +1266,7 @@ fn get_scm_references(&self) {
                 indices: Vec::new(),
             });
         }
-        for refs in tags.into_iter() {
+        for refs in remote_branches.into_iter().chain(tags) {
             items.push_back(PaletteItem {
                 content: PaletteItemContent::SCMReference {
                     name: refs.to_owned(),
@@ -1019,38 +1280,175 @@ fn get_scm_references(&self) {
     }
 
     fn get_terminal_profiles(&self) {
-        let profiles = self.common.config.get().terminal.profiles.clone();
-        let mut items: im::Vector<PaletteItem> = im::Vector::new();
+        let config = self.common.config.get();
+        let items = config
+            .terminal
+            .profiles
+            .keys()
+            .sorted()
+            .filter_map(|name| {
+                let profile = config.terminal.get_profile(name)?;
+                Some(PaletteItem {
+                    content: PaletteItemContent::TerminalProfile {
+                        name: name.to_owned(),
+                        profile,
+                    },
+                    filter_text: name.to_owned(),
+                    score: 0,
+                    indices: Vec::new(),
+                })
+            })
+            .collect();
 
-        for (name, profile) in profiles.into_iter() {
-            let uri = match lsp_types::Url::parse(&format!(
-                "file://{}",
-                profile.workdir.unwrap_or_default().display()
-            )) {
-                Ok(v) => Some(v),
-                Err(e) => {
-                    error!("Failed to parse uri: {e}");
-                    None
-                }
+        self.items.set(items);
+    }
+
+    fn get_language_servers(&self) {
+        let set_items = self.items.write_only();
+        let send = create_ext_action(self.common.scope, move |result| {
+            let Ok(ProxyResponse::LanguageServersResponse { servers }) = result
+            else {
+                set_items.update(|items| items.clear());
+                return;
             };
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let items = servers
+                .into_iter()
+                .map(|server| {
+                    let mut detail = Vec::new();
+                    if let Some(pid) = server.pid {
+                        detail.push(format!("pid {pid}"));
+                    }
+                    detail.push(format!(
+                        "up {}",
+                        format_uptime(now.saturating_sub(server.started_at))
+                    ));
+                    if !server.capabilities.is_empty() {
+                        detail.push(server.capabilities.join(", "));
+                    }
+                    let detail = detail.join(" · ");
+                    let mut filter_text = server.name.clone();
+                    if !server.languages.is_empty() {
+                        filter_text +=
+                            &format!(" ({})", server.languages.join(", "));
+                    }
+                    filter_text += &detail;
+                    PaletteItem {
+                        content: PaletteItemContent::LanguageServer {
+                            server,
+                            detail,
+                        },
+                        filter_text,
+                        score: 0,
+                        indices: Vec::new(),
+                    }
+                })
+                .collect();
+            set_items.set(items);
+        });
 
-            items.push_back(PaletteItem {
-                content: PaletteItemContent::TerminalProfile {
-                    name: name.to_owned(),
-                    profile: lapce_rpc::terminal::TerminalProfile {
-                        name: name.to_owned(),
-                        command: profile.command,
-                        arguments: profile.arguments,
-                        workdir: uri,
-                        environment: profile.environment,
-                    },
+        self.common.proxy.language_servers(None, move |result| {
+            send(result);
+        });
+    }
+
+    /// Initialize the palette with the items of the current user defined palette mode. Items
+    /// from the mode's command are appended once it finishes.
+    fn get_custom_items(&self) {
+        let Some(name) = self.input.with_untracked(|i| i.custom_mode.clone()) else {
+            return;
+        };
+        let config = self.common.config.get_untracked();
+        let Some(mode) = config.core.palette_modes.get(&name) else {
+            return;
+        };
+
+        let custom_item = {
+            let name = name.clone();
+            move |text: &str| PaletteItem {
+                content: PaletteItemContent::Custom {
+                    mode: name.clone(),
+                    text: text.to_string(),
                 },
-                filter_text: name.to_owned(),
+                filter_text: text.to_string(),
                 score: 0,
                 indices: Vec::new(),
+            }
+        };
+
+        let mut items: im::Vector<PaletteItem> = mode
+            .items
+            .iter()
+            .flatten()
+            .map(|text| custom_item(text))
+            .collect();
+        self.items.set(items.clone());
+
+        let Some(command) = mode.command.clone() else {
+            return;
+        };
+        let arguments = mode.arguments.clone().unwrap_or_default();
+        let workdir = self.workspace.path.clone();
+        let run_id = self.run_id_counter.clone();
+        let current_run_id = self.run_id.get_untracked();
+        let set_items = self.items.write_only();
+        let send =
+            create_ext_action(self.common.scope, move |lines: Vec<String>| {
+                // The palette has been rerun while the command was running
+                if run_id.load(Ordering::Acquire) != current_run_id {
+                    return;
+                }
+                items.extend(lines.iter().map(|line| custom_item(line)));
+                set_items.set(items);
             });
-        }
+        std::thread::spawn(move || {
+            let mut cmd = std::process::Command::new(&command);
+            #[cfg(target_os = "windows")]
+            {
+                use std::os::windows::process::CommandExt;
+                cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+            }
+            cmd.args(arguments);
+            if let Some(workdir) = workdir {
+                cmd.current_dir(workdir);
+            }
+            match cmd.output() {
+                Ok(output) => {
+                    let lines = String::from_utf8_lossy(&output.stdout)
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .map(|line| line.to_string())
+                        .collect();
+                    send(lines);
+                }
+                Err(err) => {
+                    error!("Failed to run palette mode command {command}: {err}");
+                }
+            }
+        });
+    }
 
+    /// The encodings to reopen the current file with, or to `save` it in
+    fn get_encodings(&self, save: bool) {
+        let items = FILE_ENCODINGS
+            .iter()
+            .map(|name| PaletteItem {
+                content: PaletteItemContent::Encoding {
+                    name: name.to_string(),
+                    save,
+                },
+                filter_text: name.to_string(),
+                score: 0,
+                indices: Vec::new(),
+            })
+            .collect();
+        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+            let encoding = editor.doc().encoding.get_untracked();
+            self.preselect_matching(&items, &encoding);
+        }
         self.items.set(items);
     }
 
@@ -1079,6 +1477,9 @@ fn select(&self) {
 
                     self.common.lapce_command.send(cmd);
                 }
+                PaletteItemContent::PaletteMode { prefix } => {
+                    self.run_with_input(PaletteKind::File, prefix);
+                }
                 PaletteItemContent::File { full_path, .. } => {
                     if self.kind.get_untracked() == PaletteKind::DiffFiles {
                         if let Some(left_path) =
@@ -1132,7 +1533,7 @@ fn select(&self) {
                 PaletteItemContent::Command { cmd } => {
                     self.common.lapce_command.send(cmd.clone());
                 }
-                PaletteItemContent::Workspace { workspace } => {
+                PaletteItemContent::Workspace { workspace, .. } => {
                     self.common.window_common.window_command.send(
                         WindowCommand::SetWorkspace {
                             workspace: workspace.clone(),
@@ -1157,6 +1558,19 @@ fn select(&self) {
                         },
                     );
                 }
+                PaletteItemContent::ContainerHost { host } => {
+                    self.common.window_common.window_command.send(
+                        WindowCommand::SetWorkspace {
+                            workspace: LapceWorkspace {
+                                kind: LapceWorkspaceType::RemoteContainer(
+                                    host.clone(),
+                                ),
+                                path: None,
+                                last_open: 0,
+                            },
+                        },
+                    );
+                }
                 #[cfg(windows)]
                 PaletteItemContent::WslHost { host } => {
                     self.common.window_common.window_command.send(
@@ -1213,7 +1627,7 @@ fn select(&self) {
                         },
                     );
                 }
-                PaletteItemContent::ColorTheme { name } => self
+                PaletteItemContent::ColorTheme { name, .. } => self
                     .common
                     .internal_command
                     .send(InternalCommand::SetColorTheme {
@@ -1256,6 +1670,21 @@ fn select(&self) {
                     doc.buffer.update(|buffer| {
                         buffer.set_line_ending(*kind);
                     });
+                    // Convert the existing line breaks too, as an edit that can be
+                    // undone
+                    editor.run_edit_command(&EditCommand::NormalizeLineEndings);
+                }
+                PaletteItemContent::Encoding { name, save } => {
+                    let Some(editor) = self.main_split.active_editor.get_untracked()
+                    else {
+                        return;
+                    };
+                    let doc = editor.doc();
+                    if *save {
+                        doc.save_with_encoding(name.clone());
+                    } else {
+                        doc.reload_with_encoding(name.clone());
+                    }
                 }
                 PaletteItemContent::SCMReference { name } => {
                     self.common
@@ -1267,12 +1696,63 @@ fn select(&self) {
                         data: Some(serde_json::json!(name.to_owned())),
                     });
                 }
+                PaletteItemContent::SCMCreateBranch { name } => {
+                    self.common
+                        .lapce_command
+                        .send(crate::command::LapceCommand {
+                            kind: CommandKind::Workbench(
+                                crate::command::LapceWorkbenchCommand::CreateBranch,
+                            ),
+                            data: Some(serde_json::json!(name.to_owned())),
+                        });
+                }
                 PaletteItemContent::TerminalProfile { name: _, profile } => self
                     .common
                     .internal_command
                     .send(InternalCommand::NewTerminal {
                         profile: Some(profile.to_owned()),
                     }),
+                PaletteItemContent::LanguageServer { server, .. } => {
+                    self.common.internal_command.send(
+                        InternalCommand::RestartLanguageServers {
+                            volt_ids: vec![server.volt_id.clone()],
+                        },
+                    );
+                }
+                PaletteItemContent::Custom { mode, text } => {
+                    self.select_custom(mode, text);
+                }
+            }
+        } else if self.kind.get_untracked() == PaletteKind::GitCredentials {
+            let token = self.input.with_untracked(|input| input.input.clone());
+            if let Some(kind) = self.git_sync.try_update(Option::take).flatten() {
+                if !token.is_empty() {
+                    self.source_control.sync(kind, Some(token));
+                }
+            }
+        } else if self.kind.get_untracked() == PaletteKind::RenameTerminal {
+            let name = self.input.with_untracked(|input| input.input.clone());
+            if let Some(term_id) =
+                self.rename_terminal.try_update(Option::take).flatten()
+            {
+                self.common
+                    .internal_command
+                    .send(InternalCommand::RenameTerminal { term_id, name });
+            }
+        } else if self.kind.get_untracked() == PaletteKind::WatchExpression {
+            let expression = self.input.with_untracked(|input| input.input.clone());
+            let index = self.watch_expression.try_update(Option::take).flatten();
+            self.common
+                .internal_command
+                .send(InternalCommand::SetWatchExpression { index, expression });
+        } else if self.kind.get_untracked() == PaletteKind::BreakpointCondition {
+            let condition = self.input.with_untracked(|input| input.input.clone());
+            if let Some(target) =
+                self.breakpoint_condition.try_update(Option::take).flatten()
+            {
+                self.common.internal_command.send(
+                    InternalCommand::SetBreakpointCondition { target, condition },
+                );
             }
         } else if self.kind.get_untracked() == PaletteKind::SshHost {
             let input = self.input.with_untracked(|input| input.input.clone());
@@ -1289,6 +1769,58 @@ fn select(&self) {
         }
     }
 
+    /// Run the `on-select` action of a user defined palette mode for the selected item.
+    fn select_custom(&self, mode: &str, text: &str) {
+        let config = self.common.config.get_untracked();
+        let Some(mode) = config.core.palette_modes.get(mode) else {
+            return;
+        };
+        match mode.on_select {
+            PaletteModeAction::Insert => {
+                let Some(editor) = self.main_split.active_editor.get_untracked()
+                else {
+                    return;
+                };
+                let selection = editor.doc().buffer.with_untracked(|buffer| {
+                    editor.cursor().get_untracked().edit_selection(buffer)
+                });
+                editor.do_edit(&selection, &[(selection.clone(), text)]);
+            }
+            PaletteModeAction::Open => {
+                let path = PathBuf::from(text);
+                let path = match self.workspace.path.as_ref() {
+                    Some(workspace_path) if path.is_relative() => {
+                        workspace_path.join(path)
+                    }
+                    _ => path,
+                };
+                self.common
+                    .internal_command
+                    .send(InternalCommand::OpenFile { path });
+            }
+            PaletteModeAction::Copy => {
+                let mut clipboard = SystemClipboard::new();
+                clipboard.put_string(text);
+            }
+            PaletteModeAction::Command => {
+                let Some(command) = mode.select_command.as_ref() else {
+                    return;
+                };
+                let cmd = self.keypress.with_untracked(|keypress| {
+                    keypress.commands.get(command).cloned()
+                });
+                if let Some(cmd) = cmd {
+                    self.common.lapce_command.send(LapceCommand {
+                        kind: cmd.kind,
+                        data: Some(serde_json::json!(text)),
+                    });
+                } else {
+                    error!("Unknown palette mode select command {command}");
+                }
+            }
+        }
+    }
+
     /// Update the preview for the currently active palette item, if it has one.
     fn preview(&self) {
         if self.status.get_untracked() == PaletteStatus::Inactive {
@@ -1300,6 +1832,7 @@ fn preview(&self) {
         if let Some(item) = items.get(index) {
             match &item.content {
                 PaletteItemContent::PaletteHelp { .. } => {}
+                PaletteItemContent::PaletteMode { .. } => {}
                 PaletteItemContent::File { .. } => {}
                 PaletteItemContent::Line { line, .. } => {
                     self.has_preview.set(true);
@@ -1336,8 +1869,10 @@ fn preview(&self) {
                 PaletteItemContent::SshHost { .. } => {}
                 #[cfg(windows)]
                 PaletteItemContent::WslHost { .. } => {}
+                PaletteItemContent::ContainerHost { .. } => {}
                 PaletteItemContent::Language { .. } => {}
                 PaletteItemContent::LineEnding { .. } => {}
+                PaletteItemContent::Encoding { .. } => {}
                 PaletteItemContent::Reference { location, .. } => {
                     self.has_preview.set(true);
                     let (doc, new_doc) =
@@ -1389,7 +1924,7 @@ fn preview(&self) {
                         None,
                     );
                 }
-                PaletteItemContent::ColorTheme { name } => self
+                PaletteItemContent::ColorTheme { name, .. } => self
                     .common
                     .internal_command
                     .send(InternalCommand::SetColorTheme {
@@ -1404,7 +1939,10 @@ fn preview(&self) {
                         save: false,
                     }),
                 PaletteItemContent::SCMReference { .. } => {}
+                PaletteItemContent::SCMCreateBranch { .. } => {}
                 PaletteItemContent::TerminalProfile { .. } => {}
+                PaletteItemContent::LanguageServer { .. } => {}
+                PaletteItemContent::Custom { .. } => {}
             }
         }
     }
@@ -1421,6 +1959,10 @@ fn cancel(&self) {
         }
 
         self.left_diff_path.set(None);
+        self.git_sync.set(None);
+        self.rename_terminal.set(None);
+        self.watch_expression.set(None);
+        self.breakpoint_condition.set(None);
         self.close();
     }
 
@@ -1638,3 +2180,15 @@ fn receive_char(&self, c: &str) {
         self.input_editor.receive_char(c);
     }
 }
+
+/// Format a duration in seconds as its largest two units, like `1h 5m`
+fn format_uptime(secs: u64) -> String {
+    let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {secs}s")
+    } else {
+        format!("{secs}s")
+    }
+}
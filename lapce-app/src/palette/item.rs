@@ -1,14 +1,14 @@
 use std::path::PathBuf;
 
 use lapce_core::line_ending::LineEnding;
-use lapce_rpc::dap_types::RunDebugConfig;
+use lapce_rpc::{dap_types::RunDebugConfig, plugin::LanguageServerInfo};
 use lsp_types::{Range, SymbolKind};
 
 use crate::{
     command::{LapceCommand, LapceWorkbenchCommand},
     debug::RunDebugMode,
     editor::location::EditorLocation,
-    workspace::{LapceWorkspace, SshHost},
+    workspace::{ContainerHost, LapceWorkspace, SshHost},
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -24,6 +24,10 @@ pub enum PaletteItemContent {
     PaletteHelp {
         cmd: LapceWorkbenchCommand,
     },
+    /// Palette help entry for a user defined palette mode
+    PaletteMode {
+        prefix: String,
+    },
     File {
         path: PathBuf,
         full_path: PathBuf,
@@ -37,6 +41,7 @@ pub enum PaletteItemContent {
     },
     Workspace {
         workspace: LapceWorkspace,
+        pinned: bool,
     },
     Reference {
         path: PathBuf,
@@ -61,12 +66,19 @@ pub enum PaletteItemContent {
     WslHost {
         host: crate::workspace::WslHost,
     },
+    ContainerHost {
+        host: ContainerHost,
+    },
     RunAndDebug {
         mode: RunDebugMode,
         config: RunDebugConfig,
     },
     ColorTheme {
         name: String,
+        /// Where the theme comes from, see [`LapceConfig::color_theme_source`]
+        ///
+        /// [`LapceConfig::color_theme_source`]: crate::config::LapceConfig::color_theme_source
+        source: String,
     },
     IconTheme {
         name: String,
@@ -77,11 +89,30 @@ pub enum PaletteItemContent {
     LineEnding {
         kind: LineEnding,
     },
+    /// An encoding to reopen the current file with, or to `save` it in
+    Encoding {
+        name: String,
+        save: bool,
+    },
     SCMReference {
         name: String,
     },
+    /// Create a branch named after the palette input
+    SCMCreateBranch {
+        name: String,
+    },
     TerminalProfile {
         name: String,
         profile: lapce_rpc::terminal::TerminalProfile,
     },
+    LanguageServer {
+        server: LanguageServerInfo,
+        /// The process id, uptime and capabilities of the server
+        detail: String,
+    },
+    /// An item of a user defined palette mode
+    Custom {
+        mode: String,
+        text: String,
+    },
 }
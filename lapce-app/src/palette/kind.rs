@@ -15,14 +15,32 @@ pub enum PaletteKind {
     SshHost,
     #[cfg(windows)]
     WslHost,
+    /// The running Docker and Podman containers to open a workspace in
+    ContainerHost,
     RunAndDebug,
     ColorTheme,
     IconTheme,
     Language,
     LineEnding,
+    /// The encodings to read the current file again with
+    ReopenWithEncoding,
+    /// The encodings to save the current file in
+    SaveWithEncoding,
     SCMReferences,
     TerminalProfile,
     DiffFiles,
+    /// Asks for an access token to retry a git remote operation with
+    GitCredentials,
+    /// Asks for the new name of a terminal
+    RenameTerminal,
+    /// Asks for a watch expression of the debug panel
+    WatchExpression,
+    /// Asks for a condition, hit count or log message of a breakpoint
+    BreakpointCondition,
+    /// The running language servers, selecting one restarts it
+    LanguageServers,
+    /// A user defined palette mode from `core.palette-modes`
+    Custom,
 }
 
 impl PaletteKind {
@@ -40,13 +58,22 @@ pub fn symbol(&self) -> &'static str {
             PaletteKind::File
             | PaletteKind::Reference
             | PaletteKind::SshHost
+            | PaletteKind::ContainerHost
             | PaletteKind::RunAndDebug
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
             | PaletteKind::LineEnding
+            | PaletteKind::ReopenWithEncoding
+            | PaletteKind::SaveWithEncoding
             | PaletteKind::SCMReferences
-            | PaletteKind::DiffFiles => "",
+            | PaletteKind::DiffFiles
+            | PaletteKind::GitCredentials
+            | PaletteKind::RenameTerminal
+            | PaletteKind::WatchExpression
+            | PaletteKind::BreakpointCondition
+            | PaletteKind::LanguageServers
+            | PaletteKind::Custom => "",
             #[cfg(windows)]
             PaletteKind::WslHost => "",
         }
@@ -77,13 +104,18 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
             PaletteKind::WorkspaceSymbol => {
                 Some(LapceWorkbenchCommand::PaletteWorkspaceSymbol)
             }
-            PaletteKind::Workspace => Some(LapceWorkbenchCommand::PaletteWorkspace),
+            PaletteKind::Workspace => {
+                Some(LapceWorkbenchCommand::OpenRecentWorkspace)
+            }
             PaletteKind::Command => Some(LapceWorkbenchCommand::PaletteCommand),
             PaletteKind::File => Some(LapceWorkbenchCommand::Palette),
             PaletteKind::Reference => None, // InternalCommand::PaletteReferences
             PaletteKind::SshHost => Some(LapceWorkbenchCommand::ConnectSshHost),
             #[cfg(windows)]
             PaletteKind::WslHost => Some(LapceWorkbenchCommand::ConnectWslHost),
+            PaletteKind::ContainerHost => {
+                Some(LapceWorkbenchCommand::ConnectContainer)
+            }
             PaletteKind::RunAndDebug => {
                 Some(LapceWorkbenchCommand::PaletteRunAndDebug)
             }
@@ -93,11 +125,33 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
             PaletteKind::LineEnding => {
                 Some(LapceWorkbenchCommand::ChangeFileLineEnding)
             }
+            PaletteKind::ReopenWithEncoding => {
+                Some(LapceWorkbenchCommand::ReopenWithEncoding)
+            }
+            PaletteKind::SaveWithEncoding => {
+                Some(LapceWorkbenchCommand::SaveWithEncoding)
+            }
             PaletteKind::SCMReferences => {
                 Some(LapceWorkbenchCommand::PaletteSCMReferences)
             }
-            PaletteKind::TerminalProfile => None, // InternalCommand::NewTerminal
+            PaletteKind::TerminalProfile => {
+                Some(LapceWorkbenchCommand::NewTerminalTabWithProfile)
+            }
             PaletteKind::DiffFiles => Some(LapceWorkbenchCommand::DiffFiles),
+            // InternalCommand::PaletteGitCredentials
+            PaletteKind::GitCredentials => None,
+            PaletteKind::RenameTerminal => {
+                Some(LapceWorkbenchCommand::RenameTerminal)
+            }
+            PaletteKind::WatchExpression => {
+                Some(LapceWorkbenchCommand::DebugAddWatchExpression)
+            }
+            // Started from the breakpoints of the debug panel and the gutter
+            PaletteKind::BreakpointCondition => None,
+            PaletteKind::LanguageServers => {
+                Some(LapceWorkbenchCommand::ShowLanguageServers)
+            }
+            PaletteKind::Custom => None,
         }
     }
 
@@ -119,13 +173,22 @@ pub fn get_input<'a>(&self, input: &'a str) -> &'a str {
             PaletteKind::File
             | PaletteKind::Reference
             | PaletteKind::SshHost
+            | PaletteKind::ContainerHost
             | PaletteKind::RunAndDebug
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
             | PaletteKind::LineEnding
+            | PaletteKind::ReopenWithEncoding
+            | PaletteKind::SaveWithEncoding
             | PaletteKind::SCMReferences
-            | PaletteKind::DiffFiles => input,
+            | PaletteKind::DiffFiles
+            | PaletteKind::GitCredentials
+            | PaletteKind::RenameTerminal
+            | PaletteKind::WatchExpression
+            | PaletteKind::BreakpointCondition
+            | PaletteKind::LanguageServers
+            | PaletteKind::Custom => input,
             PaletteKind::PaletteHelp
             | PaletteKind::Command
             | PaletteKind::Workspace
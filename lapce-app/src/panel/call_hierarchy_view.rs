@@ -0,0 +1,196 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    reactive::ReadSignal,
+    style::CursorStyle,
+    views::{container, dyn_stack, label, scroll, stack, svg, Decorators},
+    View,
+};
+
+use super::position::PanelPosition;
+use crate::{
+    call_hierarchy::{CallHierarchyData, CallHierarchyNode, HierarchyDirection},
+    command::InternalCommand,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    window_tab::WindowTabData,
+};
+
+pub fn call_hierarchy_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let call_hierarchy = window_tab_data.call_hierarchy.clone();
+    let error = call_hierarchy.error;
+    let root = call_hierarchy.root;
+
+    stack((
+        direction_view(call_hierarchy.clone(), config),
+        label(move || {
+            error.get().unwrap_or_else(|| {
+                "Show the call hierarchy of a function or the type hierarchy \
+                 of a type"
+                    .to_string()
+            })
+        })
+        .style(move |s| {
+            s.padding(10.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+                .apply_if(root.with(|root| root.is_some()), |s| s.hide())
+        }),
+        container(
+            scroll(
+                dyn_stack(
+                    {
+                        let call_hierarchy = call_hierarchy.clone();
+                        move || call_hierarchy.visible_nodes()
+                    },
+                    |node| node.id,
+                    move |node| node_view(call_hierarchy.clone(), node, config),
+                )
+                .style(|s| s.flex_col().min_width_pct(100.0).line_height(1.6)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.size_full().flex_col())
+    .debug_name("Hierarchy Panel")
+}
+
+/// The tabs switching between the callers and the callees, or the supertypes
+/// and the subtypes
+fn direction_view(
+    call_hierarchy: CallHierarchyData,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let direction = call_hierarchy.direction;
+    let tab = move |text: &'static str, tab_direction: HierarchyDirection| {
+        let call_hierarchy = call_hierarchy.clone();
+        label(move || text.to_string())
+            .on_click_stop(move |_| {
+                call_hierarchy.set_direction(tab_direction);
+            })
+            .style(move |s| {
+                let config = config.get();
+                let active = direction.get() == tab_direction;
+                s.padding_horiz(10.0)
+                    .padding_vert(4.0)
+                    .margin_right(6.0)
+                    .border_radius(6.0)
+                    .cursor(CursorStyle::Pointer)
+                    .apply_if(active, |s| {
+                        s.background(
+                            config.color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                        )
+                    })
+                    .apply_if(
+                        tab_direction.is_type() != direction.get().is_type(),
+                        |s| s.hide(),
+                    )
+                    .apply_if(!active, |s| {
+                        s.color(config.color(LapceColor::EDITOR_DIM)).hover(|s| {
+                            s.background(
+                                config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            )
+                        })
+                    })
+            })
+    };
+    stack((
+        tab("Callers", HierarchyDirection::Callers),
+        tab("Callees", HierarchyDirection::Callees),
+        tab("Supertypes", HierarchyDirection::Supertypes),
+        tab("Subtypes", HierarchyDirection::Subtypes),
+    ))
+    .style(|s| s.padding(6.0).width_pct(100.0).items_center())
+}
+
+fn node_view(
+    call_hierarchy: CallHierarchyData,
+    node: CallHierarchyNode,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let internal_command = call_hierarchy.common.internal_command;
+    let open = node.open;
+    let children = node.children;
+    let level = node.level;
+    let kind = node.item.kind;
+    let name = node.item.name.clone();
+    let detail = match (node.item.detail.as_ref(), node.calls) {
+        (Some(detail), calls) if calls > 1 => format!("{detail} ({calls} calls)"),
+        (Some(detail), _) => detail.clone(),
+        (None, calls) if calls > 1 => format!("({calls} calls)"),
+        (None, _) => String::new(),
+    };
+    let location = node.location.clone();
+    let toggle_node = node.clone();
+
+    stack((
+        container(
+            svg(move || {
+                config.get().ui_svg(if open.get() {
+                    LapceIcons::ITEM_OPENED
+                } else {
+                    LapceIcons::ITEM_CLOSED
+                })
+            })
+            .style(move |s| {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+                // Nodes found without calls have nothing to open
+                let empty = open.get()
+                    && children.with(|c| c.as_ref().is_some_and(|c| c.is_empty()));
+                s.size(size, size)
+                    .min_size(size, size)
+                    .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+                    .apply_if(empty, |s| s.hide())
+            }),
+        )
+        .on_click_stop(move |_| {
+            call_hierarchy.toggle(&toggle_node);
+        })
+        .style(move |s| {
+            let size = config.get().ui.icon_size() as f32;
+            s.min_size(size, size).margin_right(6.0)
+        }),
+        svg(move || {
+            let config = config.get();
+            config
+                .symbol_svg(&kind)
+                .unwrap_or_else(|| config.ui_svg(LapceIcons::FILE))
+        })
+        .style(move |s| {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+            s.min_width(size)
+                .size(size, size)
+                .margin_right(6.0)
+                .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+        }),
+        label(move || name.clone()).style(|s| s.margin_right(6.0).text_ellipsis()),
+        label(move || detail.clone()).style(move |s| {
+            s.color(config.get().color(LapceColor::EDITOR_DIM))
+                .min_width(0.0)
+                .text_ellipsis()
+        }),
+    ))
+    .on_click_stop(move |_| {
+        internal_command.send(InternalCommand::JumpToLocation {
+            location: location.clone(),
+        });
+    })
+    .style(move |s| {
+        let config = config.get();
+        let size = config.ui.icon_size() as f32;
+        s.padding_left(10.0 + (size + 6.0) * level as f32)
+            .padding_right(10.0)
+            .width_pct(100.0)
+            .min_width(0.0)
+            .items_center()
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer)
+                    .background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+            })
+    })
+}
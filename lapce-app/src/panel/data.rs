@@ -27,29 +27,56 @@ pub fn default_panel_order() -> PanelOrder {
             PanelKind::Plugin,
             PanelKind::SourceControl,
             PanelKind::Debug,
+            PanelKind::PluginViews,
         ],
     );
     order.insert(
         PanelPosition::BottomLeft,
-        im::vector![PanelKind::Terminal, PanelKind::Search, PanelKind::Problem,],
+        im::vector![
+            PanelKind::Terminal,
+            PanelKind::Search,
+            PanelKind::Problem,
+            PanelKind::DebugConsole,
+            PanelKind::CallHierarchy,
+            PanelKind::UndoHistory,
+            PanelKind::Notification,
+        ],
     );
 
     order
 }
 
+/// Add the panels missing from `order`, such as ones added to Lapce after it was
+/// saved, at the top left
+pub fn add_missing_panels(order: &mut PanelOrder) {
+    use strum::IntoEnumIterator;
+    for kind in PanelKind::iter() {
+        if kind.position(order).is_none() {
+            order
+                .entry(PanelPosition::LeftTop)
+                .or_default()
+                .push_back(kind);
+        }
+    }
+}
+
 #[derive(Clone, Copy, Serialize, Deserialize, Hash, PartialEq, Eq)]
 pub enum PanelSection {
     OpenEditor,
     FileExplorer,
     Error,
     Warn,
+    StagedChanges,
     Changes,
+    Stashes,
+    Commits,
     Installed,
     Available,
     Process,
     Variable,
     StackFrame,
     Breakpoint,
+    Watch,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -68,6 +95,8 @@ pub struct PanelInfo {
     pub styles: im::HashMap<PanelPosition, PanelStyle>,
     pub size: PanelSize,
     pub sections: im::HashMap<PanelSection, bool>,
+    #[serde(default)]
+    pub collapsed: im::HashSet<PanelContainerPosition>,
 }
 
 #[derive(Clone)]
@@ -77,6 +106,8 @@ pub struct PanelData {
     pub size: RwSignal<PanelSize>,
     pub available_size: Memo<Size>,
     pub sections: RwSignal<im::HashMap<PanelSection, RwSignal<bool>>>,
+    /// The containers shown as just the icons of their panels
+    pub collapsed: RwSignal<im::HashSet<PanelContainerPosition>>,
     pub common: Rc<CommonData>,
 }
 
@@ -161,6 +192,7 @@ pub fn new(
             size,
             available_size,
             sections,
+            collapsed: cx.create_rw_signal(im::HashSet::new()),
             common,
         }
     }
@@ -176,6 +208,34 @@ pub fn panel_info(&self) -> PanelInfo {
                 .into_iter()
                 .map(|(key, value)| (key, value.get_untracked()))
                 .collect(),
+            collapsed: self.collapsed.get_untracked(),
+        }
+    }
+
+    pub fn is_collapsed(
+        &self,
+        position: &PanelContainerPosition,
+        tracked: bool,
+    ) -> bool {
+        if tracked {
+            self.collapsed.with(|c| c.contains(position))
+        } else {
+            self.collapsed.with_untracked(|c| c.contains(position))
+        }
+    }
+
+    /// Collapse the container to the icons of its panels, or expand it back
+    pub fn toggle_collapsed(&self, position: &PanelContainerPosition) {
+        let collapsed = !self.is_collapsed(position, false);
+        self.collapsed.update(|c| {
+            if collapsed {
+                c.insert(*position);
+            } else {
+                c.remove(position);
+            }
+        });
+        if !collapsed && !self.is_container_shown(position, false) {
+            self.set_shown(&position.first(), true);
         }
     }
 
@@ -238,6 +298,15 @@ pub fn is_panel_visible(&self, kind: &PanelKind) -> bool {
     }
 
     pub fn show_panel(&self, kind: &PanelKind) {
+        if self.panel_position(kind).is_none() {
+            // Panels added after the layout was saved go at the bottom
+            self.panels.update(|panels| {
+                panels
+                    .entry(PanelPosition::BottomLeft)
+                    .or_default()
+                    .push_back(*kind);
+            });
+        }
         if let Some((index, position)) = self.panel_position(kind) {
             self.styles.update(|styles| {
                 if let Some(style) = styles.get_mut(&position) {
@@ -245,6 +314,12 @@ pub fn show_panel(&self, kind: &PanelKind) {
                     style.active = index;
                 }
             });
+            let container = position.container();
+            if self.is_collapsed(&container, false) {
+                self.collapsed.update(|c| {
+                    c.remove(&container);
+                });
+            }
         }
     }
 
@@ -351,6 +426,10 @@ pub fn panel_bottom_maximized(&self, tracked: bool) -> bool {
     }
 
     pub fn toggle_container_visual(&self, position: &PanelContainerPosition) {
+        if self.is_collapsed(position, false) {
+            self.toggle_collapsed(position);
+            return;
+        }
         let is_hidden = !self.is_container_shown(position, false);
         if is_hidden {
             self.styles.update(|styles| {
@@ -0,0 +1,129 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    event::EventListener,
+    peniko::kurbo::Rect,
+    reactive::{create_memo, create_rw_signal, ReadSignal},
+    style::CursorStyle,
+    views::{container, dyn_stack, label, scroll, stack, Decorators},
+    View,
+};
+
+use super::{kind::PanelKind, position::PanelPosition};
+use crate::{
+    config::{color::LapceColor, LapceConfig},
+    debug::{ConsoleLine, ConsoleLineKind},
+    text_input::TextInputBuilder,
+    window_tab::{Focus, WindowTabData},
+};
+
+/// The output of the active debug session, and an input to evaluate
+/// expressions in the stack frame it's stopped at
+pub fn debug_console_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let terminal = window_tab_data.terminal.clone();
+    let lines = create_memo(move |_| {
+        terminal
+            .get_active_dap(true)
+            .map(|dap| dap.console.get())
+            .unwrap_or_default()
+    });
+    let content_height = create_rw_signal(0.0);
+
+    stack((
+        container(
+            scroll(
+                dyn_stack(
+                    move || lines.get(),
+                    |line| line.id,
+                    move |line| console_line(line, config),
+                )
+                .on_resize(move |rect| {
+                    let height = rect.height();
+                    if content_height.get_untracked() != height {
+                        content_height.set(height);
+                    }
+                })
+                .style(|s| {
+                    s.flex_col()
+                        .width_pct(100.0)
+                        .padding_horiz(10.0)
+                        .line_height(1.6)
+                }),
+            )
+            // Keep the latest output in view
+            .ensure_visible(move || {
+                let height = content_height.get();
+                Rect::new(0.0, height - 1.0, 1.0, height)
+            })
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.width_pct(100.0).flex_grow(1.0).flex_basis(0.0)),
+        input_view(window_tab_data),
+    ))
+    .style(|s| s.size_full().flex_col())
+    .debug_name("Debug Console Panel")
+}
+
+fn console_line(
+    line: ConsoleLine,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let kind = line.kind;
+    let text = match kind {
+        ConsoleLineKind::Input => format!("> {}", line.text),
+        _ => line.text,
+    };
+    label(move || text.clone()).style(move |s| {
+        let config = config.get();
+        let color = match kind {
+            ConsoleLineKind::Error => config.color(LapceColor::LAPCE_ERROR),
+            ConsoleLineKind::Input => config.color(LapceColor::EDITOR_DIM),
+            ConsoleLineKind::Output | ConsoleLineKind::Result => {
+                config.color(LapceColor::EDITOR_FOREGROUND)
+            }
+        };
+        s.color(color)
+            .font_family(config.editor.font_family.clone())
+    })
+}
+
+/// The input of the expression to evaluate
+fn input_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let editor = window_tab_data.debug_console.input_editor.clone();
+    let terminal = window_tab_data.terminal.clone();
+    let is_focused = move || focus.get() == Focus::Panel(PanelKind::DebugConsole);
+
+    container(
+        TextInputBuilder::new()
+            .is_focused(is_focused)
+            .build_editor(editor)
+            .placeholder(move || {
+                if terminal.get_active_dap(true).is_some() {
+                    "Evaluate an expression".to_string()
+                } else {
+                    "Start debugging to evaluate expressions".to_string()
+                }
+            })
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                focus.set(Focus::Panel(PanelKind::DebugConsole));
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.width_pct(100.0)
+                    .padding_vert(4.0)
+                    .padding_horiz(10.0)
+                    .cursor(CursorStyle::Text)
+                    .background(config.color(LapceColor::EDITOR_BACKGROUND))
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+            }),
+    )
+    .style(|s| s.width_pct(100.0).padding(10.0))
+}
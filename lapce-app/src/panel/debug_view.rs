@@ -1,10 +1,12 @@
-use std::{rc::Rc, sync::Arc};
+use std::{path::PathBuf, rc::Rc, sync::Arc};
 
 use floem::{
+    action::show_context_menu,
     cosmic_text::Style as FontStyle,
     event::EventListener,
+    menu::{Menu, MenuItem},
     peniko::Color,
-    reactive::{create_rw_signal, ReadSignal, RwSignal},
+    reactive::{create_memo, create_rw_signal, ReadSignal, RwSignal},
     style::CursorStyle,
     views::{
         container, dyn_stack, label, scroll, stack, svg, text, virtual_stack,
@@ -22,7 +24,10 @@
     app::clickable_icon,
     command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
-    debug::{DapVariable, RunDebugMode, StackTraceData},
+    debug::{
+        BreakpointConditionKind, BreakpointTarget, DapVariable, LapceBreakpoint,
+        RunDebugMode, StackTraceData,
+    },
     editor::location::{EditorLocation, EditorPosition},
     listener::Listener,
     settings::checkbox,
@@ -50,6 +55,12 @@ pub fn debug_panel(
             variables_view(window_tab_data.clone()),
             window_tab_data.panel.section_open(PanelSection::Variable),
         )
+        .add_height(
+            "Watch",
+            120.0,
+            watch_view(window_tab_data.clone()),
+            window_tab_data.panel.section_open(PanelSection::Watch),
+        )
         .add(
             "Stack Frames",
             debug_stack_traces(terminal.clone(), internal_command, config),
@@ -450,6 +461,132 @@ fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     .style(|s| s.width_full().line_height(1.6).flex_grow(1.0).flex_basis(0))
 }
 
+fn watch_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let terminal = window_tab_data.terminal.clone();
+    let debug = terminal.debug.clone();
+    let expressions = debug.watch_expressions;
+    let palette = window_tab_data.palette.clone();
+    let config = window_tab_data.common.config;
+    let values = create_memo(move |_| {
+        terminal
+            .get_active_dap(true)
+            .filter(|dap| dap.stopped.get())
+            .map(|dap| dap.watches.get())
+            .unwrap_or_default()
+    });
+
+    container(
+        scroll(
+            stack((
+                dyn_stack(
+                    move || expressions.get().into_iter().enumerate(),
+                    |(i, expression)| (*i, expression.clone()),
+                    {
+                        let palette = palette.clone();
+                        move |(i, expression)| {
+                            let debug = debug.clone();
+                            let palette = palette.clone();
+                            let value = {
+                                let expression = expression.clone();
+                                move || values.with(|v| v.get(&expression).cloned())
+                            };
+                            let is_error = {
+                                let value = value.clone();
+                                move || matches!(value(), Some(Err(_)))
+                            };
+                            stack((
+                                clickable_icon(
+                                    || LapceIcons::CLOSE,
+                                    move || {
+                                        debug.set_watch_expression(
+                                            Some(i),
+                                            String::new(),
+                                        );
+                                    },
+                                    || false,
+                                    || false,
+                                    || "Remove",
+                                    config,
+                                )
+                                .on_event_stop(EventListener::PointerDown, |_| {}),
+                                text(expression.clone())
+                                    .style(|s| s.margin_left(6.0).flex_shrink(0.0)),
+                                label(move || match value() {
+                                    Some(Ok(value)) => format!(" = {value}"),
+                                    Some(Err(err)) => format!(": {err}"),
+                                    None => String::new(),
+                                })
+                                .style(move |s| {
+                                    s.text_ellipsis()
+                                        .flex_grow(1.0)
+                                        .flex_basis(0.0)
+                                        .min_width(0.0)
+                                        .apply_if(is_error(), |s| {
+                                            s.color(
+                                                config
+                                                    .get()
+                                                    .color(LapceColor::EDITOR_DIM),
+                                            )
+                                        })
+                                }),
+                            ))
+                            .on_click_stop(move |_| {
+                                palette.run_watch_expression(Some(i), &expression);
+                            })
+                            .style(move |s| {
+                                s.items_center()
+                                    .padding_horiz(10.0)
+                                    .width_pct(100.0)
+                                    .cursor(CursorStyle::Pointer)
+                                    .hover(|s| {
+                                        s.background(config.get().color(
+                                            LapceColor::PANEL_HOVERED_BACKGROUND,
+                                        ))
+                                    })
+                            })
+                        }
+                    },
+                )
+                .style(|s| s.flex_col().width_pct(100.0)),
+                stack((
+                    svg(move || config.get().ui_svg(LapceIcons::ADD)).style(
+                        move |s| {
+                            let config = config.get();
+                            let size = config.ui.icon_size() as f32;
+                            s.size(size, size)
+                                .margin_horiz(4.0)
+                                .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+                        },
+                    ),
+                    text("Add Expression").style(move |s| {
+                        s.margin_left(6.0)
+                            .color(config.get().color(LapceColor::EDITOR_DIM))
+                    }),
+                ))
+                .on_click_stop(move |_| {
+                    palette.run_watch_expression(None, "");
+                })
+                .style(move |s| {
+                    s.items_center()
+                        .padding_horiz(10.0)
+                        .width_pct(100.0)
+                        .cursor(CursorStyle::Pointer)
+                        .hover(|s| {
+                            s.background(
+                                config
+                                    .get()
+                                    .color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            )
+                        })
+                }),
+            ))
+            .style(|s| s.flex_col().line_height(1.6).width_pct(100.0)),
+        )
+        .style(|s| s.absolute().size_pct(100.0, 100.0)),
+    )
+    .style(|s| s.size_pct(100.0, 100.0))
+}
+
 fn debug_stack_frames(
     dap_id: DapId,
     thread_id: ThreadId,
@@ -622,7 +759,9 @@ fn debug_stack_traces(
 }
 
 fn breakpoints_view(window_tab_data: Rc<WindowTabData>) -> impl View {
-    let breakpoints = window_tab_data.terminal.debug.breakpoints;
+    let debug = window_tab_data.terminal.debug.clone();
+    let breakpoints = debug.breakpoints;
+    let proxy = window_tab_data.common.proxy.clone();
     let config = window_tab_data.common.config;
     let workspace = window_tab_data.common.workspace.clone();
     let available_width = create_rw_signal(0.0);
@@ -639,13 +778,29 @@ fn breakpoints_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                         })
                 },
                 move |(path, breakpoint)| {
-                    (path.clone(), breakpoint.line, breakpoint.active)
+                    (
+                        path.clone(),
+                        breakpoint.line,
+                        breakpoint.active,
+                        breakpoint.condition.clone(),
+                        breakpoint.hit_condition.clone(),
+                        breakpoint.log_message.clone(),
+                    )
                 },
                 move |(path, breakpoint)| {
+                    let debug = debug.clone();
+                    let local_debug = debug.clone();
+                    let proxy = proxy.clone();
+                    let local_proxy = proxy.clone();
+                    let window_tab_data = window_tab_data.clone();
                     let line = breakpoint.line;
+                    let offset = breakpoint.offset;
+                    let conditions = breakpoint_conditions(&breakpoint);
+                    let has_conditions = !conditions.is_empty();
                     let full_path = path.clone();
                     let full_path_for_jump = path.clone();
                     let full_path_for_close = path.clone();
+                    let full_path_for_menu = path.clone();
                     let path = if let Some(workspace_path) = workspace.path.as_ref()
                     {
                         path.strip_prefix(workspace_path)
@@ -665,13 +820,11 @@ fn breakpoints_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                         clickable_icon(
                             move || LapceIcons::CLOSE,
                             move || {
-                                breakpoints.update(|breakpoints| {
-                                    if let Some(breakpoints) =
-                                        breakpoints.get_mut(&full_path_for_close)
-                                    {
-                                        breakpoints.remove(&line);
-                                    }
-                                });
+                                local_debug.remove_breakpoint(
+                                    &local_proxy,
+                                    &full_path_for_close,
+                                    line,
+                                );
                             },
                             || false,
                             || false,
@@ -684,17 +837,9 @@ fn breakpoints_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                                 s.margin_right(6.0).cursor(CursorStyle::Pointer)
                             })
                             .on_click_stop(move |_| {
-                                breakpoints.update(|breakpoints| {
-                                    if let Some(breakpoints) =
-                                        breakpoints.get_mut(&full_path)
-                                    {
-                                        if let Some(breakpoint) =
-                                            breakpoints.get_mut(&line)
-                                        {
-                                            breakpoint.active = !breakpoint.active;
-                                        }
-                                    }
-                                });
+                                debug.toggle_breakpoint_active(
+                                    &proxy, &full_path, line,
+                                );
                             }),
                         text(format!("{file_name}:{}", breakpoint.line + 1)).style(
                             move |s| {
@@ -705,6 +850,8 @@ fn breakpoints_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                                         - size
                                         - 6.0
                                         - size
+                                        - 8.0
+                                        - size
                                         - 8.0,
                                 )
                             },
@@ -718,6 +865,33 @@ fn breakpoints_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                                 .margin_left(6.0)
                                 .apply_if(folder_empty, |s| s.hide())
                         }),
+                        text(conditions).style(move |s| {
+                            s.text_ellipsis()
+                                .min_width(0.0)
+                                .margin_left(6.0)
+                                .font_style(FontStyle::Italic)
+                                .color(config.get().color(LapceColor::EDITOR_DIM))
+                                .apply_if(!has_conditions, |s| s.hide())
+                        }),
+                        clickable_icon(
+                            || LapceIcons::EDIT,
+                            move || {
+                                show_context_menu(
+                                    breakpoint_menu(
+                                        &window_tab_data,
+                                        full_path_for_menu.clone(),
+                                        line,
+                                        offset,
+                                    ),
+                                    None,
+                                );
+                            },
+                            || false,
+                            || false,
+                            || "Edit Breakpoint",
+                            config,
+                        )
+                        .on_event_stop(EventListener::PointerDown, |_| {}),
                     ))
                     .style(move |s| {
                         s.items_center().padding_horiz(10.0).width_pct(100.0).hover(
@@ -755,3 +929,103 @@ fn breakpoints_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     )
     .style(|s| s.size_pct(100.0, 100.0))
 }
+
+/// What the breakpoint does when it's hit, when it doesn't just stop
+fn breakpoint_conditions(breakpoint: &LapceBreakpoint) -> String {
+    let mut conditions = Vec::new();
+    if let Some(condition) = &breakpoint.condition {
+        conditions.push(format!("if {condition}"));
+    }
+    if let Some(hit_condition) = &breakpoint.hit_condition {
+        conditions.push(format!("hits {hit_condition}"));
+    }
+    if let Some(log_message) = &breakpoint.log_message {
+        conditions.push(format!("log \"{log_message}\""));
+    }
+    conditions.join(", ")
+}
+
+/// The menu to add the breakpoint at the line of the file, or to edit the one
+/// that's there
+pub fn breakpoint_menu(
+    window_tab_data: &WindowTabData,
+    path: PathBuf,
+    line: usize,
+    offset: usize,
+) -> Menu {
+    let debug = window_tab_data.terminal.debug.clone();
+    let proxy = window_tab_data.common.proxy.clone();
+    let palette = window_tab_data.palette.clone();
+    let breakpoint = debug
+        .breakpoints
+        .with_untracked(|b| b.get(&path).and_then(|b| b.get(&line)).cloned());
+
+    let mut menu = Menu::new("");
+
+    if let Some(breakpoint) = &breakpoint {
+        let debug_for_remove = debug.clone();
+        let proxy_for_remove = proxy.clone();
+        let path_for_remove = path.clone();
+        menu = menu.entry(MenuItem::new("Remove Breakpoint").action(move || {
+            debug_for_remove.remove_breakpoint(
+                &proxy_for_remove,
+                &path_for_remove,
+                line,
+            );
+        }));
+
+        let path = path.clone();
+        let text = if breakpoint.active {
+            "Disable Breakpoint"
+        } else {
+            "Enable Breakpoint"
+        };
+        menu = menu.entry(MenuItem::new(text).action(move || {
+            debug.toggle_breakpoint_active(&proxy, &path, line);
+        }));
+    } else {
+        let path = path.clone();
+        menu = menu.entry(MenuItem::new("Add Breakpoint").action(move || {
+            debug.toggle_breakpoint(&proxy, &path, line, offset);
+        }));
+    }
+
+    menu = menu.separator();
+
+    for (kind, add, edit) in [
+        (
+            BreakpointConditionKind::Expression,
+            "Add Conditional Breakpoint...",
+            "Edit Condition...",
+        ),
+        (
+            BreakpointConditionKind::HitCount,
+            "Add Hit Count Breakpoint...",
+            "Edit Hit Count...",
+        ),
+        (
+            BreakpointConditionKind::LogMessage,
+            "Add Logpoint...",
+            "Edit Log Message...",
+        ),
+    ] {
+        let palette = palette.clone();
+        let target = BreakpointTarget {
+            path: path.clone(),
+            line,
+            offset,
+            kind,
+        };
+        let condition = breakpoint
+            .as_ref()
+            .and_then(|b| b.condition(kind))
+            .unwrap_or_default()
+            .to_string();
+        let text = if breakpoint.is_some() { edit } else { add };
+        menu = menu.entry(MenuItem::new(text).action(move || {
+            palette.run_breakpoint_condition(target.clone(), &condition);
+        }));
+    }
+
+    menu
+}
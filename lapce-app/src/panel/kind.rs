@@ -15,6 +15,12 @@ pub enum PanelKind {
     Search,
     Problem,
     Debug,
+    CallHierarchy,
+    Ports,
+    PluginViews,
+    UndoHistory,
+    Notification,
+    DebugConsole,
 }
 
 impl PanelKind {
@@ -27,6 +33,12 @@ pub fn svg_name(&self) -> &'static str {
             PanelKind::Search => LapceIcons::SEARCH,
             PanelKind::Problem => LapceIcons::PROBLEM,
             PanelKind::Debug => LapceIcons::DEBUG,
+            PanelKind::CallHierarchy => LapceIcons::SYMBOL_KIND_FUNCTION,
+            PanelKind::Ports => LapceIcons::REMOTE,
+            PanelKind::PluginViews => LapceIcons::MENU,
+            PanelKind::UndoHistory => LapceIcons::LOCATION_BACKWARD,
+            PanelKind::Notification => LapceIcons::NOTIFICATION,
+            PanelKind::DebugConsole => LapceIcons::DEBUG_CONSOLE,
         }
     }
 
@@ -1,11 +1,17 @@
+pub mod call_hierarchy_view;
 pub mod data;
+pub mod debug_console_view;
 pub mod debug_view;
 pub mod global_search_view;
 pub mod kind;
+pub mod notification_view;
 pub mod plugin_view;
+pub mod plugin_views_view;
+pub mod ports_view;
 pub mod position;
 pub mod problem_view;
 pub mod source_control_view;
 pub mod style;
 pub mod terminal_view;
+pub mod undo_history_view;
 pub mod view;
@@ -0,0 +1,116 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    reactive::{create_effect, ReadSignal},
+    style::CursorStyle,
+    views::{container, dyn_stack, label, scroll, stack, Decorators},
+    View,
+};
+
+use super::{kind::PanelKind, position::PanelPosition};
+use crate::{
+    config::{color::LapceColor, LapceConfig},
+    editor::blame::relative_time,
+    notification::{notification_view, Notification, NotificationData},
+    window_tab::WindowTabData,
+};
+
+/// Every notification shown in the window tab, newest first, with the actions
+/// that can still be taken on them
+pub fn notification_panel(
+    window_tab_data: Rc<WindowTabData>,
+    position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let notifications = window_tab_data.notifications;
+    let panel = window_tab_data.panel.clone();
+
+    // Whatever comes in while the panel is in view has been seen
+    create_effect(move |_| {
+        notifications.history.track();
+        if let Some((PanelKind::Notification, true)) =
+            panel.active_panel_at_position(&position, true)
+        {
+            notifications.mark_read();
+        }
+    });
+
+    stack((
+        stack((
+            label(|| "Notifications".to_string())
+                .style(|s| s.flex_grow(1.0).min_width(0.0)),
+            label(|| "Clear All".to_string())
+                .on_click_stop(move |_| notifications.clear())
+                .style(move |s| {
+                    let config = config.get();
+                    s.padding_horiz(6.0)
+                        .border_radius(6.0)
+                        .apply_if(
+                            notifications.history.with(|h| h.is_empty()),
+                            |s| s.hide(),
+                        )
+                        .hover(|s| {
+                            s.cursor(CursorStyle::Pointer).background(
+                                config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            )
+                        })
+                }),
+        ))
+        .style(move |s| {
+            s.padding_horiz(10.0)
+                .padding_vert(6.0)
+                .width_pct(100.0)
+                .items_center()
+                .background(config.get().color(LapceColor::EDITOR_BACKGROUND))
+        }),
+        label(|| "No notifications".to_string()).style(move |s| {
+            s.padding(10.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+                .apply_if(notifications.history.with(|h| !h.is_empty()), |s| {
+                    s.hide()
+                })
+        }),
+        container(
+            scroll(
+                dyn_stack(
+                    move || notifications.history.get(),
+                    |notification| notification.id,
+                    move |notification| {
+                        notification_row(notification, notifications, config)
+                    },
+                )
+                .style(|s| s.flex_col().width_pct(100.0)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.size_full().flex_col())
+    .debug_name("Notification Panel")
+}
+
+fn notification_row(
+    notification: Notification,
+    notifications: NotificationData,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let id = notification.id;
+    let time = notification.time;
+    let now = chrono::Utc::now().timestamp();
+
+    stack((
+        notification_view(notification, config, move || notifications.remove(id)),
+        label(move || relative_time(time, now)).style(move |s| {
+            s.margin_left(26.0)
+                .margin_top(4.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+        }),
+    ))
+    .style(move |s| {
+        s.flex_col()
+            .width_pct(100.0)
+            .padding(10.0)
+            .border_bottom(1.0)
+            .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+    })
+}
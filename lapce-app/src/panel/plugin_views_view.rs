@@ -0,0 +1,189 @@
+use std::{collections::HashSet, rc::Rc};
+
+use floem::{
+    reactive::create_rw_signal,
+    style::CursorStyle,
+    views::{dyn_stack, label, scroll, stack, svg, Decorators},
+    View,
+};
+use lapce_rpc::plugin::{PluginView, PluginViewItem, VoltID};
+
+use super::{position::PanelPosition, view::foldable_panel_section};
+use crate::{
+    config::{color::LapceColor, icon::LapceIcons},
+    plugin::PluginData,
+    window_tab::WindowTabData,
+};
+
+pub fn plugin_views_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let plugin = window_tab_data.plugin.clone();
+    let views = plugin.views;
+
+    stack((
+        label(|| "No plugin shows a view in this workspace".to_string()).style(
+            move |s| {
+                s.padding(10.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+                    .apply_if(!views.with(|v| v.is_empty()), |s| s.hide())
+            },
+        ),
+        scroll(
+            dyn_stack(
+                move || views.get(),
+                |view| view.clone(),
+                move |view| view_section(plugin.clone(), view),
+            )
+            .style(|s| s.flex_col().width_pct(100.0)),
+        )
+        .style(|s| s.absolute().size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.size_full())
+    .debug_name("Plugin Views Panel")
+}
+
+/// A view as a foldable section, with its items as an indented tree
+fn view_section(plugin: PluginData, view: PluginView) -> impl View {
+    let config = plugin.common.config;
+    let folded = plugin.folded_view_items;
+    let title = format!("{}: {}", view.volt_display_name, view.title);
+    let volt_id = view.volt_id.clone();
+    let view_id = view.id.clone();
+
+    let rows = {
+        let volt_id = volt_id.clone();
+        let view_id = view_id.clone();
+        move || {
+            folded.with(|folded| {
+                let mut rows = Vec::new();
+                visible_items(&view.items, 0, folded, &volt_id, &view_id, &mut rows);
+                rows
+            })
+        }
+    };
+
+    foldable_panel_section(
+        label(move || title.clone()).style(|s| s.selectable(false)),
+        dyn_stack(
+            rows,
+            |row| row.clone(),
+            move |(depth, item, is_folded)| {
+                item_view(
+                    plugin.clone(),
+                    volt_id.clone(),
+                    view_id.clone(),
+                    depth,
+                    item,
+                    is_folded,
+                )
+            },
+        )
+        .style(|s| s.flex_col().width_pct(100.0).line_height(1.6)),
+        create_rw_signal(true),
+        config,
+    )
+}
+
+/// The items which aren't under a folded one, with their depth in the tree and
+/// whether they are folded themselves
+fn visible_items(
+    items: &[PluginViewItem],
+    depth: usize,
+    folded: &HashSet<(VoltID, String, String)>,
+    volt_id: &VoltID,
+    view_id: &str,
+    rows: &mut Vec<(usize, PluginViewItem, bool)>,
+) {
+    for item in items {
+        let is_folded = folded.contains(&(
+            volt_id.clone(),
+            view_id.to_string(),
+            item.id.clone(),
+        ));
+        rows.push((depth, item.clone(), is_folded));
+        if !is_folded {
+            visible_items(&item.children, depth + 1, folded, volt_id, view_id, rows);
+        }
+    }
+}
+
+fn item_view(
+    plugin: PluginData,
+    volt_id: VoltID,
+    view_id: String,
+    depth: usize,
+    item: PluginViewItem,
+    is_folded: bool,
+) -> impl View {
+    let config = plugin.common.config;
+    let proxy = plugin.common.proxy.clone();
+    let folded = plugin.folded_view_items;
+    let has_children = !item.children.is_empty();
+    let item_label = item.label.clone();
+    let description = item.description.clone().unwrap_or_default();
+
+    stack((
+        svg(move || {
+            config.get().ui_svg(if is_folded {
+                LapceIcons::ITEM_CLOSED
+            } else {
+                LapceIcons::ITEM_OPENED
+            })
+        })
+        .style(move |s| {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+            s.margin_right(6.0)
+                .min_width(size)
+                .size(size, size)
+                .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+                .apply_if(!has_children, |s| s.hide())
+        }),
+        label(move || item_label.clone()).style(|s| {
+            s.margin_right(6.0)
+                .max_width_pct(100.0)
+                .text_ellipsis()
+                .selectable(false)
+        }),
+        label(move || description.clone()).style(move |s| {
+            s.color(config.get().color(LapceColor::EDITOR_DIM))
+                .min_width(0.0)
+                .text_ellipsis()
+                .selectable(false)
+        }),
+    ))
+    .on_click_stop(move |_| {
+        if has_children {
+            let key = (volt_id.clone(), view_id.clone(), item.id.clone());
+            folded.update(|folded| {
+                if !folded.remove(&key) {
+                    folded.insert(key);
+                }
+            });
+        }
+        proxy.plugin_view_item_clicked(
+            volt_id.clone(),
+            view_id.clone(),
+            item.id.clone(),
+        );
+    })
+    .style(move |s| {
+        let config = config.get();
+        let indent = config.ui.icon_size() as f32 + 6.0;
+        s.width_pct(100.0)
+            .min_width(0.0)
+            .items_center()
+            .padding_left(10.0 + indent * depth as f32)
+            .apply_if(!has_children, |s| {
+                s.padding_left(10.0 + indent * (depth + 1) as f32)
+            })
+            .padding_right(10.0)
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer)
+                    .background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+            })
+    })
+}
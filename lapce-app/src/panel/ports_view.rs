@@ -0,0 +1,164 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    event::EventListener,
+    reactive::ReadSignal,
+    style::CursorStyle,
+    views::{container, dyn_stack, label, scroll, stack, Decorators},
+    View,
+};
+
+use super::{kind::PanelKind, position::PanelPosition};
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    ports::{ForwardedPort, PortsData},
+    text_input::TextInputBuilder,
+    window_tab::{Focus, WindowTabData},
+};
+
+pub fn ports_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let ports = window_tab_data.ports.clone();
+    let is_available = ports.is_available();
+    let ports_signal = ports.ports;
+
+    stack((
+        input_view(window_tab_data.clone())
+            .style(move |s| s.apply_if(!is_available, |s| s.hide())),
+        label(move || {
+            if is_available {
+                "Ports that processes on the remote start listening on are \
+                 forwarded automatically"
+                    .to_string()
+            } else {
+                "Ports can be forwarded in SSH workspaces".to_string()
+            }
+        })
+        .style(move |s| {
+            s.padding(10.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+                .apply_if(
+                    is_available && !ports_signal.with(|p| p.is_empty()),
+                    |s| s.hide(),
+                )
+        }),
+        container(
+            scroll(
+                dyn_stack(
+                    move || ports_signal.get(),
+                    |port| port.clone(),
+                    move |port| port_view(ports.clone(), port, config),
+                )
+                .style(|s| s.flex_col().width_pct(100.0).line_height(1.6)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.size_full().flex_col())
+    .debug_name("Ports Panel")
+}
+
+/// The input of a port to forward
+fn input_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let editor = window_tab_data.ports.input_editor.clone();
+    let is_focused = move || focus.get() == Focus::Panel(PanelKind::Ports);
+
+    container(
+        TextInputBuilder::new()
+            .is_focused(is_focused)
+            .build_editor(editor)
+            .placeholder(|| "Forward a port, like 3000".to_string())
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                focus.set(Focus::Panel(PanelKind::Ports));
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.width_pct(100.0)
+                    .padding_vert(4.0)
+                    .padding_horiz(10.0)
+                    .cursor(CursorStyle::Text)
+                    .background(config.color(LapceColor::EDITOR_BACKGROUND))
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+            }),
+    )
+    .style(|s| s.width_pct(100.0).padding(10.0))
+}
+
+fn port_view(
+    ports: PortsData,
+    port: ForwardedPort,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let remote_port = port.remote_port;
+    let forwarded = port.local_port.is_some();
+    let text = match (&port.error, port.local_port) {
+        (Some(error), _) => format!("{remote_port}: {error}"),
+        (None, Some(local_port)) => {
+            format!("{remote_port} → localhost:{local_port}")
+        }
+        (None, None) => format!("{remote_port} → forwarding…"),
+    };
+    let detected = port.detected;
+
+    stack((
+        label(move || text.clone())
+            .on_click_stop({
+                let ports = ports.clone();
+                move |_| ports.open_in_browser(remote_port)
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.flex_grow(1.0)
+                    .min_width(0.0)
+                    .text_ellipsis()
+                    .apply_if(port.error.is_some(), |s| {
+                        s.color(config.color(LapceColor::LAPCE_ERROR))
+                    })
+                    .apply_if(forwarded, |s| s.cursor(CursorStyle::Pointer))
+            }),
+        label(|| "auto".to_string()).style(move |s| {
+            s.margin_left(10.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+                .apply_if(!detected, |s| s.hide())
+        }),
+        clickable_icon(
+            || LapceIcons::LINK,
+            {
+                let ports = ports.clone();
+                move || ports.open_in_browser(remote_port)
+            },
+            || false,
+            move || !forwarded,
+            || "Open in Browser",
+            config,
+        )
+        .style(|s| s.margin_left(6.0)),
+        clickable_icon(
+            || LapceIcons::CLOSE,
+            move || ports.stop(remote_port),
+            || false,
+            || false,
+            || "Stop Forwarding",
+            config,
+        ),
+    ))
+    .style(move |s| {
+        s.width_pct(100.0)
+            .items_center()
+            .padding_horiz(10.0)
+            .hover(|s| {
+                s.background(
+                    config.get().color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                )
+            })
+    })
+}
@@ -32,6 +32,20 @@ pub fn is_first(&self) -> bool {
         )
     }
 
+    pub fn container(&self) -> PanelContainerPosition {
+        match &self {
+            PanelPosition::LeftTop | PanelPosition::LeftBottom => {
+                PanelContainerPosition::Left
+            }
+            PanelPosition::BottomLeft | PanelPosition::BottomRight => {
+                PanelContainerPosition::Bottom
+            }
+            PanelPosition::RightTop | PanelPosition::RightBottom => {
+                PanelContainerPosition::Right
+            }
+        }
+    }
+
     pub fn peer(&self) -> PanelPosition {
         match &self {
             PanelPosition::LeftTop => PanelPosition::LeftBottom,
@@ -44,7 +58,7 @@ pub fn peer(&self) -> PanelPosition {
     }
 }
 
-#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum PanelContainerPosition {
     Left,
     Bottom,
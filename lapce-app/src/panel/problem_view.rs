@@ -1,15 +1,18 @@
 use std::{path::PathBuf, rc::Rc, sync::Arc};
 
 use floem::{
+    event::EventListener,
     peniko::Color,
-    reactive::{create_effect, create_rw_signal, ReadSignal},
+    reactive::{create_effect, create_rw_signal, Memo, ReadSignal},
     style::{CursorStyle, Style},
     views::{container, dyn_stack, label, scroll, stack, svg, Decorators},
     View,
 };
-use lsp_types::{DiagnosticRelatedInformation, DiagnosticSeverity};
+use lsp_types::{Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity};
 
-use super::{data::PanelSection, position::PanelPosition, view::PanelBuilder};
+use super::{
+    data::PanelSection, kind::PanelKind, position::PanelPosition, view::PanelBuilder,
+};
 use crate::{
     command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
@@ -17,7 +20,9 @@
     editor::location::{EditorLocation, EditorPosition},
     listener::Listener,
     lsp::path_from_url,
-    window_tab::WindowTabData,
+    problem::filter_matches,
+    text_input::TextInputBuilder,
+    window_tab::{Focus, WindowTabData},
     workspace::LapceWorkspace,
 };
 
@@ -27,7 +32,7 @@ pub fn problem_panel(
 ) -> impl View {
     let config = window_tab_data.common.config;
     let is_bottom = position.is_bottom();
-    PanelBuilder::new(config, position)
+    let panel = PanelBuilder::new(config, position)
         .add_style(
             "Errors",
             problem_section(window_tab_data.clone(), DiagnosticSeverity::ERROR),
@@ -43,8 +48,44 @@ pub fn problem_panel(
             problem_section(window_tab_data.clone(), DiagnosticSeverity::WARNING),
             window_tab_data.panel.section_open(PanelSection::Warn),
         )
-        .build()
-        .debug_name("Problem Panel")
+        .build();
+
+    stack((
+        filter_view(window_tab_data),
+        panel.style(|s| s.flex_grow(1.0).flex_basis(0.0).min_height(0.0)),
+    ))
+    .style(|s| s.size_full().flex_col())
+    .debug_name("Problem Panel")
+}
+
+/// The input filtering the diagnostics by their message, source or file
+fn filter_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let editor = window_tab_data.problem.filter_editor.clone();
+    let is_focused = move || focus.get() == Focus::Panel(PanelKind::Problem);
+
+    container(
+        TextInputBuilder::new()
+            .is_focused(is_focused)
+            .build_editor(editor)
+            .placeholder(|| "Filter problems".to_string())
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                focus.set(Focus::Panel(PanelKind::Problem));
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.width_pct(100.0)
+                    .padding_vert(4.0)
+                    .padding_horiz(10.0)
+                    .cursor(CursorStyle::Text)
+                    .background(config.color(LapceColor::EDITOR_BACKGROUND))
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+            }),
+    )
+    .style(|s| s.width_pct(100.0).padding(10.0))
 }
 
 fn problem_section(
@@ -54,6 +95,7 @@ fn problem_section(
     let config = window_tab_data.common.config;
     let main_split = window_tab_data.main_split.clone();
     let internal_command = window_tab_data.common.internal_command;
+    let filter = window_tab_data.problem.filter;
     container({
         scroll(
             dyn_stack(
@@ -65,6 +107,7 @@ fn problem_section(
                         path,
                         diagnostic_data,
                         severity,
+                        filter,
                         internal_command,
                         config,
                     )
@@ -82,18 +125,35 @@ fn file_view(
     path: PathBuf,
     diagnostic_data: DiagnosticData,
     severity: DiagnosticSeverity,
+    filter: Memo<String>,
     internal_command: Listener<InternalCommand>,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
     let collpased = create_rw_signal(false);
 
+    let full_path = path.clone();
+    let path = if let Some(workspace_path) = workspace.path.as_ref() {
+        path.strip_prefix(workspace_path)
+            .unwrap_or(&full_path)
+            .to_path_buf()
+    } else {
+        path
+    };
+    let style_path = path.clone();
+
     let diagnostics = create_rw_signal(im::Vector::new());
+    let filter_path = path.clone();
     create_effect(move |_| {
+        let filter = filter.get();
+        let matches = |diag: &Diagnostic| {
+            diag.severity == Some(severity)
+                && filter_matches(&filter, &filter_path, diag)
+        };
         let span = diagnostic_data.diagnostics_span.get();
         let d = if !span.is_empty() {
             span.iter()
                 .filter_map(|(iv, diag)| {
-                    if diag.severity == Some(severity) {
+                    if matches(diag) {
                         Some(EditorDiagnostic {
                             range: Some((iv.start, iv.end)),
                             diagnostic: diag.to_owned(),
@@ -108,7 +168,7 @@ fn file_view(
             let diagnostics: im::Vector<EditorDiagnostic> = diagnostics
                 .into_iter()
                 .filter_map(|d| {
-                    if d.severity == Some(severity) {
+                    if matches(&d) {
                         Some(EditorDiagnostic {
                             range: None,
                             diagnostic: d,
@@ -123,16 +183,6 @@ fn file_view(
         diagnostics.set(d);
     });
 
-    let full_path = path.clone();
-    let path = if let Some(workspace_path) = workspace.path.as_ref() {
-        path.strip_prefix(workspace_path)
-            .unwrap_or(&full_path)
-            .to_path_buf()
-    } else {
-        path
-    };
-    let style_path = path.clone();
-
     let icon = match severity {
         DiagnosticSeverity::ERROR => LapceIcons::ERROR,
         _ => LapceIcons::WARNING,
@@ -1,31 +1,38 @@
-use std::{path::PathBuf, rc::Rc};
+use std::{path::PathBuf, rc::Rc, sync::Arc};
 
 use floem::{
     action::show_context_menu,
     event::{Event, EventListener},
     menu::{Menu, MenuItem},
     peniko::kurbo::Rect,
-    reactive::{create_memo, create_rw_signal},
+    reactive::{create_memo, create_rw_signal, ReadSignal, RwSignal},
     style::{CursorStyle, Style},
     views::{
         container, dyn_stack,
-        editor::view::{cursor_caret, LineRegion},
-        label, scroll, stack, svg, text, Decorators,
+        editor::{
+            text::SystemClipboard,
+            view::{cursor_caret, LineRegion},
+        },
+        label, scroll, stack, svg, Decorators,
     },
     View,
 };
-use lapce_core::buffer::rope_text::RopeText;
-use lapce_rpc::source_control::FileDiff;
+use indexmap::IndexMap;
+use lapce_core::{buffer::rope_text::RopeText, register::Clipboard};
+use lapce_rpc::source_control::{CommitInfo, FileDiff, StashInfo};
 
 use super::{
     data::PanelSection, kind::PanelKind, position::PanelPosition,
     view::foldable_panel_section,
 };
 use crate::{
+    app::{clickable_icon, tooltip_label},
     command::{CommandKind, InternalCommand, LapceCommand, LapceWorkbenchCommand},
-    config::{color::LapceColor, icon::LapceIcons},
-    editor::view::editor_view,
-    settings::checkbox,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    editor::{
+        blame::{commit_details, relative_time},
+        view::editor_view,
+    },
     source_control::SourceControlData,
     window_tab::{Focus, WindowTabData},
 };
@@ -148,44 +155,56 @@ pub fn source_control_panel(
                     .border_color(config.color(LapceColor::LAPCE_BORDER))
                     .background(config.color(LapceColor::EDITOR_BACKGROUND))
             }),
-            {
-                let source_control = source_control.clone();
-                label(|| "Commit".to_string())
-                    .on_click_stop(move |_| {
-                        source_control.commit();
-                    })
-                    .style(move |s| {
-                        let config = config.get();
-                        s.margin_top(10.0)
-                            .line_height(1.6)
-                            .width_pct(100.0)
-                            .justify_center()
-                            .border(1.0)
-                            .border_radius(6.0)
-                            .border_color(config.color(LapceColor::LAPCE_BORDER))
-                            .hover(|s| {
-                                s.cursor(CursorStyle::Pointer).background(
-                                    config
-                                        .color(LapceColor::PANEL_HOVERED_BACKGROUND),
-                                )
-                            })
-                            .active(|s| {
-                                s.background(config.color(
-                                    LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND,
-                                ))
-                            })
-                            .selectable(false)
-                    })
-            },
+            stack((
+                {
+                    let source_control = source_control.clone();
+                    commit_button(
+                        "Commit",
+                        move || source_control.commit(false),
+                        config,
+                    )
+                    .style(|s| s.flex_grow(1.0))
+                },
+                {
+                    let source_control = source_control.clone();
+                    commit_button(
+                        "Amend",
+                        move || source_control.commit(true),
+                        config,
+                    )
+                    .style(|s| s.margin_left(10.0).padding_horiz(10.0))
+                },
+                {
+                    let source_control = source_control.clone();
+                    commit_button("Stash", move || source_control.stash(), config)
+                        .style(|s| s.margin_left(10.0).padding_horiz(10.0))
+                },
+            ))
+            .style(|s| s.margin_top(10.0).width_pct(100.0)),
         ))
         .style(|s| s.flex_col().width_pct(100.0).padding(10.0)),
-        foldable_panel_section(
-            text("Changes"),
-            file_diffs_view(source_control),
+        file_diffs_section(
+            "Staged Changes",
+            source_control.clone(),
+            true,
+            window_tab_data
+                .panel
+                .section_open(PanelSection::StagedChanges),
+        ),
+        file_diffs_section(
+            "Changes",
+            source_control.clone(),
+            false,
             window_tab_data.panel.section_open(PanelSection::Changes),
-            config,
-        )
-        .style(|s| s.flex_col().size_pct(100.0, 100.0)),
+        ),
+        stashes_section(
+            source_control.clone(),
+            window_tab_data.panel.section_open(PanelSection::Stashes),
+        ),
+        commits_section(
+            source_control,
+            window_tab_data.panel.section_open(PanelSection::Commits),
+        ),
     ))
     .on_event_stop(EventListener::PointerDown, move |_| {
         if focus.get_untracked() != Focus::Panel(PanelKind::SourceControl) {
@@ -196,8 +215,70 @@ pub fn source_control_panel(
     .debug_name("Source Control Panel")
 }
 
-fn file_diffs_view(source_control: SourceControlData) -> impl View {
-    let file_diffs = source_control.file_diffs;
+fn commit_button(
+    text: &'static str,
+    on_click: impl Fn() + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(move || text.to_string())
+        .on_click_stop(move |_| on_click())
+        .style(move |s| {
+            let config = config.get();
+            s.line_height(1.6)
+                .justify_center()
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .hover(|s| {
+                    s.cursor(CursorStyle::Pointer).background(
+                        config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+                .active(|s| {
+                    s.background(
+                        config.color(LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND),
+                    )
+                })
+                .selectable(false)
+        })
+}
+
+/// The foldable list of either the staged or the unstaged changes.
+/// The staged section is hidden when nothing is staged.
+fn file_diffs_section(
+    name: &'static str,
+    source_control: SourceControlData,
+    staged: bool,
+    open: RwSignal<bool>,
+) -> impl View {
+    let config = source_control.common.config;
+    let file_diffs = if staged {
+        source_control.staged
+    } else {
+        source_control.unstaged
+    };
+
+    foldable_panel_section(
+        label(move || format!("{name} ({})", file_diffs.with(IndexMap::len))),
+        file_diffs_view(source_control, staged),
+        open,
+        config,
+    )
+    .style(move |s| {
+        s.flex_col()
+            .width_pct(100.0)
+            .min_height(0.0)
+            .apply_if(open.get(), |s| s.flex_grow(1.0).flex_basis(0.0))
+            .apply_if(staged && file_diffs.with(IndexMap::is_empty), |s| s.hide())
+    })
+}
+
+fn file_diffs_view(source_control: SourceControlData, staged: bool) -> impl View {
+    let file_diffs = if staged {
+        source_control.staged
+    } else {
+        source_control.unstaged
+    };
     let config = source_control.common.config;
     let workspace = source_control.common.workspace.clone();
     let panel_rect = create_rw_signal(Rect::ZERO);
@@ -205,11 +286,13 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
     let lapce_command = source_control.common.lapce_command;
     let internal_command = source_control.common.internal_command;
 
-    let view_fn = move |(path, (diff, checked)): (PathBuf, (FileDiff, bool))| {
+    let view_fn = move |(path, diff): (PathBuf, FileDiff)| {
         let diff_for_style = diff.clone();
         let full_path = path.clone();
         let diff_for_menu = diff.clone();
+        let diff_for_stage = diff.clone();
         let path_for_click = full_path.clone();
+        let source_control = source_control.clone();
 
         let path = if let Some(workspace_path) = workspace.path.as_ref() {
             path.strip_prefix(workspace_path)
@@ -230,15 +313,6 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
             .to_string();
         let style_path = path.clone();
         stack((
-            checkbox(move || checked, config)
-                .style(|s| s.hover(|s| s.cursor(CursorStyle::Pointer)))
-                .on_click_stop(move |_| {
-                    file_diffs.update(|diffs| {
-                        if let Some((_, checked)) = diffs.get_mut(&full_path) {
-                            *checked = !*checked;
-                        }
-                    });
-                }),
             svg(move || config.get().file_svg(&path).0).style(move |s| {
                 let config = config.get();
                 let size = config.ui.icon_size() as f32;
@@ -273,6 +347,37 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
                     .min_width(0.0)
                     .selectable(false)
             }),
+            {
+                let source_control = source_control.clone();
+                clickable_icon(
+                    move || {
+                        if staged {
+                            LapceIcons::SCM_CHANGE_REMOVE
+                        } else {
+                            LapceIcons::SCM_CHANGE_ADD
+                        }
+                    },
+                    move || {
+                        let diffs = [diff_for_stage.clone()];
+                        if staged {
+                            source_control.unstage(&diffs);
+                        } else {
+                            source_control.stage(&diffs);
+                        }
+                    },
+                    || false,
+                    || false,
+                    move || {
+                        if staged {
+                            "Unstage Changes"
+                        } else {
+                            "Stage Changes"
+                        }
+                    },
+                    config,
+                )
+                .style(|s| s.margin_right(6.0))
+            },
             container({
                 svg(move || {
                     let svg = match &diff {
@@ -312,24 +417,36 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
             });
         })
         .on_event_cont(EventListener::PointerDown, move |event| {
-            let diff_for_menu = diff_for_menu.clone();
-
-            let discard = move || {
-                lapce_command.send(LapceCommand {
-                    kind: CommandKind::Workbench(
-                        LapceWorkbenchCommand::SourceControlDiscardTargetFileChanges,
-                    ),
-                    data: Some(serde_json::json!(diff_for_menu.clone())),
-                });
+            let Event::PointerDown(pointer_event) = event else {
+                return;
             };
+            if !pointer_event.button.is_secondary() {
+                return;
+            }
 
-            if let Event::PointerDown(pointer_event) = event {
-                if pointer_event.button.is_secondary() {
-                    let menu = Menu::new("")
-                        .entry(MenuItem::new("Discard Changes").action(discard));
-                    show_context_menu(menu, None);
-                }
+            let diff = diff_for_menu.clone();
+            let source_control = source_control.clone();
+            let mut menu = Menu::new("");
+            if staged {
+                menu = menu.entry(MenuItem::new("Unstage Changes").action(move || {
+                    source_control.unstage(&[diff.clone()]);
+                }));
+            } else {
+                menu = menu.entry(MenuItem::new("Stage Changes").action(move || {
+                    source_control.stage(&[diff.clone()]);
+                }));
+
+                let diff = diff_for_menu.clone();
+                menu = menu.entry(MenuItem::new("Discard Changes").action(move || {
+                    lapce_command.send(LapceCommand {
+                        kind: CommandKind::Workbench(
+                            LapceWorkbenchCommand::SourceControlDiscardTargetFileChanges,
+                        ),
+                        data: Some(serde_json::json!(diff.clone())),
+                    });
+                }));
             }
+            show_context_menu(menu, None);
         })
         .style(move |s| {
             let config = config.get();
@@ -348,9 +465,7 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
         scroll({
             dyn_stack(
                 move || file_diffs.get(),
-                |(path, (diff, checked))| {
-                    (path.to_path_buf(), diff.clone(), *checked)
-                },
+                |(path, diff)| (path.to_path_buf(), diff.clone()),
                 view_fn,
             )
             .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
@@ -360,5 +475,355 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
     .on_resize(move |rect| {
         panel_rect.set(rect);
     })
-    .style(|s| s.size_pct(100.0, 100.0))
+    .style(|s| s.width_pct(100.0).flex_grow(1.0).flex_basis(0.0))
+}
+
+/// The foldable list of the stashed changes, hidden when there are none
+fn stashes_section(
+    source_control: SourceControlData,
+    open: RwSignal<bool>,
+) -> impl View {
+    let config = source_control.common.config;
+    let stashes = source_control.stashes;
+
+    let view_fn = move |stash: StashInfo| {
+        let index = stash.index;
+        let message = stash.message;
+        let source_control = source_control.clone();
+
+        stack((
+            label(move || message.clone()).style(|s| {
+                s.text_ellipsis()
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .min_width(0.0)
+                    .selectable(false)
+            }),
+            {
+                let source_control = source_control.clone();
+                clickable_icon(
+                    || LapceIcons::SCM_CHANGE_ADD,
+                    move || source_control.apply_stash(index, true),
+                    || false,
+                    || false,
+                    || "Pop Stash",
+                    config,
+                )
+                .style(|s| s.margin_left(6.0))
+            },
+            {
+                let source_control = source_control.clone();
+                clickable_icon(
+                    || LapceIcons::CLOSE,
+                    move || source_control.drop_stash(index),
+                    || false,
+                    || false,
+                    || "Drop Stash",
+                    config,
+                )
+                .style(|s| s.margin_left(6.0))
+            },
+        ))
+        .on_event_cont(EventListener::PointerDown, move |event| {
+            let Event::PointerDown(pointer_event) = event else {
+                return;
+            };
+            if !pointer_event.button.is_secondary() {
+                return;
+            }
+
+            let mut menu = Menu::new("");
+            for (text, pop) in [("Apply Stash", false), ("Pop Stash", true)] {
+                let source_control = source_control.clone();
+                menu = menu.entry(MenuItem::new(text).action(move || {
+                    source_control.apply_stash(index, pop);
+                }));
+            }
+            let source_control = source_control.clone();
+            menu = menu.entry(MenuItem::new("Drop Stash").action(move || {
+                source_control.drop_stash(index);
+            }));
+            show_context_menu(menu, None);
+        })
+        .style(move |s| {
+            let config = config.get();
+            s.padding_horiz(10.0)
+                .width_pct(100.0)
+                .items_center()
+                .hover(|s| {
+                    s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+                })
+        })
+    };
+
+    foldable_panel_section(
+        label(move || format!("Stashes ({})", stashes.with(im::Vector::len))),
+        container({
+            scroll({
+                dyn_stack(move || stashes.get(), |stash| stash.clone(), view_fn)
+                    .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
+            })
+            .style(|s| s.absolute().size_pct(100.0, 100.0))
+        })
+        .style(|s| s.width_pct(100.0).flex_grow(1.0).flex_basis(0.0)),
+        open,
+        config,
+    )
+    .style(move |s| {
+        s.flex_col()
+            .width_pct(100.0)
+            .min_height(0.0)
+            .apply_if(open.get(), |s| s.flex_grow(1.0).flex_basis(0.0))
+            .apply_if(stashes.with(im::Vector::is_empty), |s| s.hide())
+    })
+}
+
+/// The foldable list of the latest commits of the repository, or of a single file
+fn commits_section(
+    source_control: SourceControlData,
+    open: RwSignal<bool>,
+) -> impl View {
+    let config = source_control.common.config;
+    let log_file = source_control.log_file;
+
+    foldable_panel_section(
+        stack((
+            label(move || match log_file.get() {
+                Some(path) => format!(
+                    "Commits: {}",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ),
+                None => "Commits".to_string(),
+            })
+            .style(|s| s.text_ellipsis().min_width(0.0)),
+            {
+                let source_control = source_control.clone();
+                clickable_icon(
+                    || LapceIcons::CLOSE,
+                    move || source_control.show_log(None),
+                    || false,
+                    || false,
+                    || "Show All Commits",
+                    config,
+                )
+                .style(move |s| {
+                    s.margin_left(6.0)
+                        .apply_if(log_file.with(Option::is_none), |s| s.hide())
+                })
+            },
+        )),
+        commits_view(source_control),
+        open,
+        config,
+    )
+    .style(move |s| {
+        s.flex_col()
+            .width_pct(100.0)
+            .min_height(0.0)
+            .apply_if(open.get(), |s| s.flex_grow(1.0).flex_basis(0.0))
+    })
+}
+
+fn commits_view(source_control: SourceControlData) -> impl View {
+    let config = source_control.common.config;
+    let log = source_control.log;
+    let log_commit = source_control.log_commit;
+    let workspace = source_control.common.workspace.clone();
+    let internal_command = source_control.common.internal_command;
+
+    let view_fn = move |commit: CommitInfo| {
+        let id = commit.id.clone();
+        let short_id = commit.short_id().to_string();
+        let summary = commit.summary().to_string();
+        let time = relative_time(commit.time, chrono::Utc::now().timestamp());
+        let details = commit_details(Some(&commit));
+        let source_control = source_control.clone();
+        let workspace = workspace.clone();
+
+        let files = {
+            let id = id.clone();
+            move || {
+                log_commit
+                    .get()
+                    .filter(|(commit, _)| commit == &id)
+                    .map(|(_, diffs)| diffs)
+                    .unwrap_or_default()
+            }
+        };
+        let file_view = {
+            let id = id.clone();
+            move |diff: FileDiff| {
+                let full_path = diff.path().clone();
+                let path = workspace
+                    .path
+                    .as_ref()
+                    .and_then(|workspace_path| {
+                        full_path.strip_prefix(workspace_path).ok()
+                    })
+                    .unwrap_or(&full_path)
+                    .to_path_buf();
+                let file_name = path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("")
+                    .to_string();
+                let folder = path
+                    .parent()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("")
+                    .to_string();
+                let style_path = path.clone();
+                let id_for_click = id.clone();
+                let path_for_click = full_path.clone();
+                let id = id.clone();
+                let is_deleted = matches!(diff, FileDiff::Deleted(_));
+
+                stack((
+                    svg(move || config.get().file_svg(&path).0).style(move |s| {
+                        let config = config.get();
+                        let size = config.ui.icon_size() as f32;
+                        let color = config.file_svg(&style_path).1;
+                        s.min_width(size)
+                            .size(size, size)
+                            .margin(6.0)
+                            .apply_opt(color, Style::color)
+                    }),
+                    label(move || file_name.clone()).style(|s| {
+                        s.text_ellipsis().margin_right(6.0).selectable(false)
+                    }),
+                    label(move || folder.clone()).style(move |s| {
+                        s.text_ellipsis()
+                            .flex_grow(1.0)
+                            .flex_basis(0.0)
+                            .color(config.get().color(LapceColor::EDITOR_DIM))
+                            .min_width(0.0)
+                            .selectable(false)
+                    }),
+                ))
+                .on_click_stop(move |_| {
+                    internal_command.send(InternalCommand::OpenCommitFileChanges {
+                        path: path_for_click.clone(),
+                        id: id_for_click.clone(),
+                    });
+                })
+                .on_event_cont(EventListener::PointerDown, move |event| {
+                    let Event::PointerDown(pointer_event) = event else {
+                        return;
+                    };
+                    if !pointer_event.button.is_secondary() {
+                        return;
+                    }
+
+                    let mut menu = Menu::new("");
+                    let (path, id_for_changes) = (full_path.clone(), id.clone());
+                    menu = menu.entry(MenuItem::new("Open Changes").action(
+                        move || {
+                            internal_command.send(
+                                InternalCommand::OpenCommitFileChanges {
+                                    path: path.clone(),
+                                    id: id_for_changes.clone(),
+                                },
+                            );
+                        },
+                    ));
+                    // A deleted file only has content at the parent commit
+                    let revision = if is_deleted {
+                        format!("{id}^")
+                    } else {
+                        id.clone()
+                    };
+                    let path = full_path.clone();
+                    menu = menu.entry(
+                        MenuItem::new("Open File at Revision").action(move || {
+                            internal_command.send(
+                                InternalCommand::OpenFileAtRevision {
+                                    path: path.clone(),
+                                    revision: revision.clone(),
+                                },
+                            );
+                        }),
+                    );
+                    show_context_menu(menu, None);
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    s.padding_left(10.0 + config.ui.icon_size() as f32 + 6.0)
+                        .padding_right(10.0)
+                        .width_pct(100.0)
+                        .items_center()
+                        .hover(|s| {
+                            s.background(
+                                config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            )
+                        })
+                })
+            }
+        };
+
+        stack((
+            tooltip_label(
+                config,
+                stack((
+                    label(move || short_id.clone()).style(move |s| {
+                        s.margin_right(6.0)
+                            .color(config.get().color(LapceColor::EDITOR_DIM))
+                            .selectable(false)
+                    }),
+                    label(move || summary.clone()).style(|s| {
+                        s.text_ellipsis()
+                            .flex_grow(1.0)
+                            .flex_basis(0.0)
+                            .min_width(0.0)
+                            .selectable(false)
+                    }),
+                    label(move || time.clone()).style(move |s| {
+                        s.margin_left(6.0)
+                            .color(config.get().color(LapceColor::EDITOR_DIM))
+                            .selectable(false)
+                    }),
+                ))
+                .style(|s| s.width_pct(100.0).items_center()),
+                move || details.clone(),
+            )
+            .on_click_stop({
+                let id = id.clone();
+                move |_| source_control.toggle_log_commit(id.clone())
+            })
+            .on_event_cont(EventListener::PointerDown, move |event| {
+                let Event::PointerDown(pointer_event) = event else {
+                    return;
+                };
+                if !pointer_event.button.is_secondary() {
+                    return;
+                }
+
+                let id = id.clone();
+                let menu = Menu::new("").entry(
+                    MenuItem::new("Copy Commit ID").action(move || {
+                        let mut clipboard = SystemClipboard::new();
+                        clipboard.put_string(&id);
+                    }),
+                );
+                show_context_menu(menu, None);
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.padding_horiz(10.0).width_pct(100.0).hover(|s| {
+                    s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+                })
+            }),
+            dyn_stack(files, |diff| diff.clone(), file_view)
+                .style(|s| s.flex_col().width_pct(100.0)),
+        ))
+        .style(|s| s.flex_col().width_pct(100.0))
+    };
+
+    container({
+        scroll({
+            dyn_stack(move || log.get(), |commit| commit.id.clone(), view_fn)
+                .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
+        })
+        .style(|s| s.absolute().size_pct(100.0, 100.0))
+    })
+    .style(|s| s.width_pct(100.0).flex_grow(1.0).flex_basis(0.0))
 }
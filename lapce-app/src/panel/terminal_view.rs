@@ -1,9 +1,12 @@
 use std::rc::Rc;
 
+use alacritty_terminal::index::Direction;
 use floem::{
+    action::show_context_menu,
     event::EventPropagation,
     event::{Event, EventListener},
     kurbo::Size,
+    menu::{Menu, MenuItem},
     reactive::create_rw_signal,
     views::{
         container, dyn_stack, empty, label,
@@ -19,9 +22,11 @@
     command::LapceWorkbenchCommand,
     config::{color::LapceColor, icon::LapceIcons},
     debug::RunDebugMode,
+    listener::Listener,
     terminal::{
         panel::TerminalPanelData, tab::TerminalTabData, view::terminal_view,
     },
+    text_input::TextInputBuilder,
     window_tab::{Focus, WindowTabData},
 };
 
@@ -73,6 +78,11 @@ fn terminal_tab_header(window_tab_data: Rc<WindowTabData>) -> impl View {
                     let tab = tab.clone();
                     move || {
                         let terminal = tab.active_terminal(true);
+                        if let Some(name) =
+                            terminal.as_ref().and_then(|t| t.name.get())
+                        {
+                            return name;
+                        }
                         let run_debug = terminal.as_ref().map(|t| t.run_debug);
                         if let Some(run_debug) = run_debug {
                             if let Some(name) = run_debug.with(|run_debug| {
@@ -184,7 +194,7 @@ fn terminal_tab_header(window_tab_data: Rc<WindowTabData>) -> impl View {
                 ))
                 .on_event_cont(
                     EventListener::PointerDown,
-                    move |_| {
+                    move |event| {
                         if tab_info.with_untracked(|tab| tab.active)
                             != index.get_untracked()
                         {
@@ -193,6 +203,13 @@ fn terminal_tab_header(window_tab_data: Rc<WindowTabData>) -> impl View {
                             });
                             local_terminal.update_debug_active_term();
                         }
+
+                        let Event::PointerDown(pointer_event) = event else {
+                            return;
+                        };
+                        if pointer_event.button.is_secondary() {
+                            terminal_tab_menu(workbench_command);
+                        }
                     },
                 )
             },
@@ -250,6 +267,22 @@ fn terminal_tab_header(window_tab_data: Rc<WindowTabData>) -> impl View {
     })
 }
 
+/// The context menu of a terminal tab, acting on the active terminal of the tab
+fn terminal_tab_menu(workbench_command: Listener<LapceWorkbenchCommand>) {
+    let mut menu = Menu::new("");
+    for (text, cmd) in [
+        ("Rename Terminal", LapceWorkbenchCommand::RenameTerminal),
+        ("Split Terminal", LapceWorkbenchCommand::SplitTerminal),
+        ("Restart Terminal", LapceWorkbenchCommand::RestartTerminal),
+        ("Kill Terminal", LapceWorkbenchCommand::KillTerminal),
+    ] {
+        menu = menu.entry(MenuItem::new(text).action(move || {
+            workbench_command.send(cmd.clone());
+        }));
+    }
+    show_context_menu(menu, None);
+}
+
 fn terminal_tab_split(
     terminal_panel_data: TerminalPanelData,
     terminal_tab_data: TerminalTabData,
@@ -258,6 +291,7 @@ fn terminal_tab_split(
     let internal_command = terminal_panel_data.common.internal_command;
     let workspace = terminal_panel_data.workspace.clone();
     let active = terminal_tab_data.active;
+    let find_focus = terminal_panel_data.find.focus;
     let terminal_tab_scope = terminal_tab_data.scope;
     dyn_stack(
         move || {
@@ -286,6 +320,7 @@ fn terminal_tab_split(
                 )
                 .on_event_cont(EventListener::PointerDown, move |_| {
                     active.set(index.get_untracked());
+                    find_focus.set(false);
                 })
                 .on_event(EventListener::PointerWheel, move |event| {
                     if let Event::PointerWheel(pointer_event) = event {
@@ -320,11 +355,93 @@ fn terminal_tab_split(
 
 fn terminal_tab_content(window_tab_data: Rc<WindowTabData>) -> impl View {
     let terminal = window_tab_data.terminal.clone();
-    tab(
-        move || terminal.tab_info.with(|info| info.active),
-        move || terminal.tab_info.with(|info| info.tabs.clone()),
-        |(_, tab)| tab.terminal_tab_id,
-        move |(_, tab)| terminal_tab_split(terminal.clone(), tab),
-    )
+    stack((
+        {
+            let terminal = terminal.clone();
+            tab(
+                move || terminal.tab_info.with(|info| info.active),
+                move || terminal.tab_info.with(|info| info.tabs.clone()),
+                |(_, tab)| tab.terminal_tab_id,
+                move |(_, tab)| terminal_tab_split(terminal.clone(), tab),
+            )
+            .style(|s| s.size_pct(100.0, 100.0))
+        },
+        terminal_find_view(terminal),
+    ))
     .style(|s| s.size_pct(100.0, 100.0))
 }
+
+/// The find bar floating over the top right corner of the terminals
+fn terminal_find_view(terminal: TerminalPanelData) -> impl View {
+    let config = terminal.common.config;
+    let focus = terminal.common.focus;
+    let find = terminal.find.clone();
+    let visual = find.visual;
+    let find_focus = find.focus;
+
+    container(
+        stack((
+            TextInputBuilder::new()
+                .is_focused(move || {
+                    focus.get() == Focus::Panel(PanelKind::Terminal)
+                        && visual.get()
+                        && find_focus.get()
+                })
+                .build_editor(find.editor.clone())
+                .on_event_cont(EventListener::PointerDown, move |_| {
+                    find_focus.set(true);
+                })
+                .style(|s| s.width(200.0)),
+            clickable_icon(
+                || LapceIcons::SEARCH_BACKWARD,
+                {
+                    let find = find.clone();
+                    move || find.search(Direction::Left)
+                },
+                || false,
+                || false,
+                || "Previous Match",
+                config,
+            )
+            .style(|s| s.padding_left(6.0)),
+            clickable_icon(
+                || LapceIcons::SEARCH_FORWARD,
+                {
+                    let find = find.clone();
+                    move || find.search(Direction::Right)
+                },
+                || false,
+                || false,
+                || "Next Match",
+                config,
+            )
+            .style(|s| s.padding_left(6.0)),
+            clickable_icon(
+                || LapceIcons::CLOSE,
+                move || find.close(),
+                || false,
+                || false,
+                || "Close",
+                config,
+            )
+            .style(|s| s.padding_horiz(6.0)),
+        ))
+        .style(move |s| {
+            let config = config.get();
+            s.items_center()
+                .margin_right(20.0)
+                .padding_vert(4.0)
+                .padding_left(6.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .background(config.color(LapceColor::PANEL_BACKGROUND))
+        }),
+    )
+    .style(move |s| {
+        s.absolute()
+            .width_pct(100.0)
+            .justify_end()
+            .apply_if(!visual.get(), |s| s.hide())
+    })
+}
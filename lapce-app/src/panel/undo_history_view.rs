@@ -0,0 +1,139 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    reactive::{Memo, ReadSignal},
+    style::CursorStyle,
+    views::{container, dyn_stack, label, scroll, stack, Decorators},
+    View,
+};
+
+use super::position::PanelPosition;
+use crate::{
+    config::{color::LapceColor, LapceConfig},
+    editor::{blame::relative_time, EditorData},
+    window_tab::WindowTabData,
+};
+
+/// A state of the undo tree as a row of the panel
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct UndoRow {
+    node: usize,
+    column: usize,
+    time: i64,
+    current: bool,
+    saved: bool,
+}
+
+/// The undo tree of the active editor's document, with every state it has been in
+/// on any branch, any of which can be gone back to
+pub fn undo_history_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let active_editor = window_tab_data.main_split.active_editor;
+
+    let rows = move || {
+        let Some(editor) = active_editor.get() else {
+            return Vec::new();
+        };
+        editor.doc_signal().get().undo_tree.with(|tree| {
+            tree.rows()
+                .into_iter()
+                .map(|(node, column)| UndoRow {
+                    node,
+                    column,
+                    time: tree.nodes()[node].time,
+                    current: tree.current() == node,
+                    saved: tree.is_saved(node),
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    stack((
+        label(|| "Open a file to see the history of its edits".to_string()).style(
+            move |s| {
+                s.padding(10.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+                    .apply_if(active_editor.with(|e| e.is_some()), |s| s.hide())
+            },
+        ),
+        container(
+            scroll(
+                dyn_stack(
+                    rows,
+                    |row| row.clone(),
+                    move |row| undo_row_view(active_editor, row, config),
+                )
+                .style(|s| s.flex_col().min_width_pct(100.0).line_height(1.6)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.size_full().flex_col())
+    .debug_name("Undo History Panel")
+}
+
+fn undo_row_view(
+    active_editor: Memo<Option<EditorData>>,
+    row: UndoRow,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let UndoRow {
+        node,
+        column,
+        time,
+        current,
+        saved,
+    } = row;
+    let text = if node == 0 {
+        "Original".to_string()
+    } else {
+        format!("Edit {node}")
+    };
+    let now = chrono::Utc::now().timestamp();
+
+    stack((
+        label(move || if current { "●" } else { "○" }.to_string())
+            .style(|s| s.margin_right(6.0)),
+        label(move || text.clone()).style(|s| s.margin_right(6.0)),
+        label(move || {
+            let time = relative_time(time, now);
+            if saved {
+                format!("{time}, saved")
+            } else {
+                time
+            }
+        })
+        .style(move |s| {
+            s.color(config.get().color(LapceColor::EDITOR_DIM))
+                .min_width(0.0)
+                .text_ellipsis()
+        }),
+    ))
+    .on_click_stop(move |_| {
+        if let Some(editor) = active_editor.get_untracked() {
+            let doc = editor.doc();
+            editor.cursor().update(|cursor| {
+                doc.go_to_undo_state(Some(cursor), |tree| tree.go_to(node));
+            });
+        }
+    })
+    .style(move |s| {
+        let config = config.get();
+        s.padding_left(10.0 + 16.0 * column as f32)
+            .padding_right(10.0)
+            .width_pct(100.0)
+            .min_width(0.0)
+            .items_center()
+            .apply_if(current, |s| {
+                s.background(config.color(LapceColor::PANEL_CURRENT_BACKGROUND))
+            })
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer)
+                    .background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+            })
+    })
+}
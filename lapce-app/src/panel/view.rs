@@ -1,8 +1,10 @@
 use std::{rc::Rc, sync::Arc};
 
 use floem::{
+    action::show_context_menu,
     event::{Event, EventListener, EventPropagation},
     kurbo::{Point, Size},
+    menu::{Menu, MenuItem},
     reactive::{create_rw_signal, ReadSignal, RwSignal},
     style::{CursorStyle, Style},
     taffy::AlignItems,
@@ -15,14 +17,21 @@
 };
 
 use super::{
+    call_hierarchy_view::call_hierarchy_panel,
+    data::PanelData,
+    debug_console_view::debug_console_panel,
     debug_view::debug_panel,
     global_search_view::global_search_panel,
     kind::PanelKind,
+    notification_view::notification_panel,
     plugin_view::plugin_panel,
+    plugin_views_view::plugin_views_panel,
+    ports_view::ports_panel,
     position::{PanelContainerPosition, PanelPosition},
     problem_view::problem_panel,
     source_control_view::source_control_panel,
     terminal_view::terminal_panel,
+    undo_history_view::undo_history_panel,
 };
 use crate::{
     app::{clickable_icon, clickable_icon_base},
@@ -261,6 +270,7 @@ pub fn panel_container_view(
     let resize_drag_view = {
         let panel = panel.clone();
         let panel_size = panel.size;
+        let collapsed = panel.collapsed;
         move |position: PanelContainerPosition| {
             panel.panel_info();
             let view = empty();
@@ -346,6 +356,9 @@ pub fn panel_container_view(
                 let current_size = current_size.get();
                 let config = config.get();
                 s.absolute()
+                    .apply_if(collapsed.with(|c| c.contains(&position)), |s| {
+                        s.hide()
+                    })
                     .apply_if(position == PanelContainerPosition::Bottom, |s| {
                         s.width_pct(100.0).height(4.0).margin_top(-2.0)
                     })
@@ -413,30 +426,34 @@ pub fn panel_container_view(
             PanelContainerPosition::Right => s.right,
         });
         let is_maximized = panel.panel_bottom_maximized(true);
+        // A collapsed container is just its icons, sized to fit them
+        let collapsed = panel.is_collapsed(&position, true);
         let config = config.get();
-        s.apply_if(!panel.is_container_shown(&position, true), |s| s.hide())
-            .apply_if(position == PanelContainerPosition::Bottom, |s| {
-                s.width_pct(100.0)
-                    .apply_if(!is_maximized, |s| {
-                        s.border_top(1.0).height(size as f32)
-                    })
-                    .apply_if(is_maximized, |s| s.flex_grow(1.0))
-            })
-            .apply_if(position == PanelContainerPosition::Left, |s| {
-                s.border_right(1.0)
-                    .width(size as f32)
-                    .height_pct(100.0)
-                    .background(config.color(LapceColor::PANEL_BACKGROUND))
-            })
-            .apply_if(position == PanelContainerPosition::Right, |s| {
-                s.border_left(1.0)
-                    .width(size as f32)
-                    .height_pct(100.0)
-                    .background(config.color(LapceColor::PANEL_BACKGROUND))
-            })
-            .apply_if(!is_bottom, |s| s.flex_col())
-            .border_color(config.color(LapceColor::LAPCE_BORDER))
-            .color(config.color(LapceColor::PANEL_FOREGROUND))
+        s.apply_if(
+            !panel.is_container_shown(&position, true) && !collapsed,
+            |s| s.hide(),
+        )
+        .apply_if(position == PanelContainerPosition::Bottom, |s| {
+            s.width_pct(100.0)
+                .apply_if(!is_maximized || collapsed, |s| s.border_top(1.0))
+                .apply_if(!is_maximized && !collapsed, |s| s.height(size as f32))
+                .apply_if(is_maximized && !collapsed, |s| s.flex_grow(1.0))
+        })
+        .apply_if(position == PanelContainerPosition::Left, |s| {
+            s.border_right(1.0)
+                .apply_if(!collapsed, |s| s.width(size as f32))
+                .height_pct(100.0)
+                .background(config.color(LapceColor::PANEL_BACKGROUND))
+        })
+        .apply_if(position == PanelContainerPosition::Right, |s| {
+            s.border_left(1.0)
+                .apply_if(!collapsed, |s| s.width(size as f32))
+                .height_pct(100.0)
+                .background(config.color(LapceColor::PANEL_BACKGROUND))
+        })
+        .apply_if(!is_bottom, |s| s.flex_col())
+        .border_color(config.color(LapceColor::LAPCE_BORDER))
+        .color(config.color(LapceColor::PANEL_FOREGROUND))
     })
     .debug_name(format!("{:?} Pannel Container View", position))
 }
@@ -484,6 +501,25 @@ fn panel_view(
                 PanelKind::Debug => {
                     debug_panel(window_tab_data.clone(), position).into_any()
                 }
+                PanelKind::CallHierarchy => {
+                    call_hierarchy_panel(window_tab_data.clone(), position)
+                        .into_any()
+                }
+                PanelKind::Ports => {
+                    ports_panel(window_tab_data.clone(), position).into_any()
+                }
+                PanelKind::PluginViews => {
+                    plugin_views_panel(window_tab_data.clone(), position).into_any()
+                }
+                PanelKind::UndoHistory => {
+                    undo_history_panel(window_tab_data.clone(), position).into_any()
+                }
+                PanelKind::Notification => {
+                    notification_panel(window_tab_data.clone(), position).into_any()
+                }
+                PanelKind::DebugConsole => {
+                    debug_console_panel(window_tab_data.clone(), position).into_any()
+                }
             };
             view.style(|s| s.size_pct(100.0, 100.0))
         },
@@ -491,7 +527,8 @@ fn panel_view(
     .style(move |s| {
         s.size_pct(100.0, 100.0).apply_if(
             !panel.is_position_shown(&position, true)
-                || panel.is_position_empty(&position, true),
+                || panel.is_position_empty(&position, true)
+                || panel.is_collapsed(&position.container(), true),
             |s| s.hide(),
         )
     })
@@ -515,10 +552,16 @@ fn panel_picker(
 ) -> impl View {
     let panel = window_tab_data.panel.clone();
     let panels = panel.panels;
+    let collapsed = panel.collapsed;
     let config = window_tab_data.common.config;
     let dragging = window_tab_data.common.dragging;
+    let container_position = position.container();
     let is_bottom = position.is_bottom();
     let is_first = position.is_first();
+    let is_collapsed = move || collapsed.with(|c| c.contains(&container_position));
+    // The icons run across the container, except in a collapsed side container,
+    // where they're a strip down its length
+    let is_vertical = move || is_bottom != is_collapsed();
     dyn_stack(
         move || {
             panel
@@ -538,6 +581,20 @@ fn panel_picker(
                 PanelKind::Search => (LapceIcons::SEARCH, "Search"),
                 PanelKind::Problem => (LapceIcons::PROBLEM, "Problems"),
                 PanelKind::Debug => (LapceIcons::DEBUG_ALT, "Debug"),
+                PanelKind::CallHierarchy => {
+                    (LapceIcons::SYMBOL_KIND_FUNCTION, "Hierarchy")
+                }
+                PanelKind::Ports => (LapceIcons::REMOTE, "Ports"),
+                PanelKind::PluginViews => (LapceIcons::MENU, "Plugin Views"),
+                PanelKind::UndoHistory => {
+                    (LapceIcons::LOCATION_BACKWARD, "Undo History")
+                }
+                PanelKind::Notification => {
+                    (LapceIcons::NOTIFICATION, "Notifications")
+                }
+                PanelKind::DebugConsole => {
+                    (LapceIcons::DEBUG_CONSOLE, "Debug Console")
+                }
             };
             let is_active = {
                 let window_tab_data = window_tab_data.clone();
@@ -552,11 +609,16 @@ fn panel_picker(
                     }
                 }
             };
+            let menu_panel = window_tab_data.panel.clone();
             container(stack((
                 clickable_icon(
                     || icon,
                     move || {
-                        window_tab_data.toggle_panel_visual(p);
+                        if is_collapsed() {
+                            window_tab_data.show_panel(p);
+                        } else {
+                            window_tab_data.toggle_panel_visual(p);
+                        }
                     },
                     || false,
                     || false,
@@ -584,14 +646,24 @@ fn panel_picker(
                 })
                 .style(|s| s.padding(1.0)),
                 label(|| "".to_string()).style(move |s| {
+                    let collapsed = is_collapsed();
+                    let expanded = !collapsed;
                     s.selectable(false)
                         .absolute()
                         .size_pct(100.0, 100.0)
-                        .apply_if(!is_bottom && is_first, |s| s.margin_top(2.0))
-                        .apply_if(!is_bottom && !is_first, |s| s.margin_top(-2.0))
-                        .apply_if(is_bottom && is_first, |s| s.margin_left(-2.0))
-                        .apply_if(is_bottom && !is_first, |s| s.margin_left(2.0))
-                        .apply_if(is_active(), |s| {
+                        .apply_if(expanded && !is_bottom && is_first, |s| {
+                            s.margin_top(2.0)
+                        })
+                        .apply_if(expanded && !is_bottom && !is_first, |s| {
+                            s.margin_top(-2.0)
+                        })
+                        .apply_if(expanded && is_bottom && is_first, |s| {
+                            s.margin_left(-2.0)
+                        })
+                        .apply_if(expanded && is_bottom && !is_first, |s| {
+                            s.margin_left(2.0)
+                        })
+                        .apply_if(expanded && is_active(), |s| {
                             s.apply_if(!is_bottom && is_first, |s| {
                                 s.border_bottom(2.0)
                             })
@@ -601,6 +673,15 @@ fn panel_picker(
                                 s.border_right(2.0)
                             })
                         })
+                        // In a collapsed container, the panel that's open
+                        // when it's expanded is marked on the outer side
+                        .apply_if(collapsed && is_active(), |s| {
+                            match container_position {
+                                PanelContainerPosition::Left => s.border_left(2.0),
+                                PanelContainerPosition::Bottom => s.border_top(2.0),
+                                PanelContainerPosition::Right => s.border_right(2.0),
+                            }
+                        })
                         .border_color(
                             config
                                 .get()
@@ -608,10 +689,16 @@ fn panel_picker(
                         )
                 }),
             )))
+            .on_secondary_click_stop(move |_| {
+                show_context_menu(panel_menu(&menu_panel, p), None);
+            })
             .style(|s| s.padding(6.0))
         },
     )
     .style(move |s| {
+        // The icons of both positions of a collapsed container are next to each
+        // other, so only the first draws the line between them
+        let collapsed = is_collapsed();
         s.border_color(config.get().color(LapceColor::LAPCE_BORDER))
             .apply_if(
                 panels.with(|p| {
@@ -619,10 +706,43 @@ fn panel_picker(
                 }),
                 |s| s.hide(),
             )
-            .apply_if(is_bottom, |s| s.flex_col())
+            .apply_if(is_vertical(), |s| s.flex_col())
             .apply_if(is_bottom && is_first, |s| s.border_right(1.0))
-            .apply_if(is_bottom && !is_first, |s| s.border_left(1.0))
+            .apply_if(is_bottom && !is_first && !collapsed, |s| s.border_left(1.0))
             .apply_if(!is_bottom && is_first, |s| s.border_bottom(1.0))
-            .apply_if(!is_bottom && !is_first, |s| s.border_top(1.0))
+            .apply_if(!is_bottom && !is_first && !collapsed, |s| s.border_top(1.0))
     })
 }
+
+/// The menu of a panel's icon, which moves the panel to another side, or
+/// collapses its side to the icons
+fn panel_menu(panel: &PanelData, kind: PanelKind) -> Menu {
+    let current = panel.panel_position(&kind).map(|(_, p)| p.container());
+    let mut menu = Menu::new("");
+    for (container, text) in [
+        (PanelContainerPosition::Left, "Move to Left"),
+        (PanelContainerPosition::Bottom, "Move to Bottom"),
+        (PanelContainerPosition::Right, "Move to Right"),
+    ] {
+        let panel = panel.clone();
+        menu = menu.entry(
+            MenuItem::new(text)
+                .enabled(current != Some(container))
+                .action(move || {
+                    panel.move_panel_to_position(kind, &container.first());
+                }),
+        );
+    }
+    if let Some(container) = current {
+        let text = if panel.is_collapsed(&container, false) {
+            "Expand to Panels"
+        } else {
+            "Collapse to Icons"
+        };
+        let panel = panel.clone();
+        menu = menu.separator().entry(
+            MenuItem::new(text).action(move || panel.toggle_collapsed(&container)),
+        );
+    }
+    menu
+}
@@ -24,7 +24,7 @@
 use indexmap::IndexMap;
 use lapce_core::{command::EditCommand, directory::Directory, mode::Mode};
 use lapce_proxy::plugin::{download_volt, volt_icon, wasi::find_all_volts};
-use lapce_rpc::plugin::{VoltID, VoltInfo, VoltMetadata};
+use lapce_rpc::plugin::{PluginView, VoltID, VoltInfo, VoltMetadata};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
@@ -101,6 +101,11 @@ pub struct PluginData {
     pub all: RwSignal<im::HashMap<VoltID, AvailableVoltData>>,
     pub disabled: RwSignal<HashSet<VoltID>>,
     pub workspace_disabled: RwSignal<HashSet<VoltID>>,
+    /// The views the running volts show in the plugin views panel
+    pub views: RwSignal<Vec<PluginView>>,
+    /// The items of the views whose children are hidden, by volt, view and
+    /// item id
+    pub folded_view_items: RwSignal<HashSet<(VoltID, String, String)>>,
     pub common: Rc<CommonData>,
 }
 
@@ -174,6 +179,8 @@ pub fn new(
             all: cx.create_rw_signal(im::HashMap::new()),
             disabled,
             workspace_disabled,
+            views: cx.create_rw_signal(Vec::new()),
+            folded_view_items: cx.create_rw_signal(HashSet::new()),
             common,
         };
 
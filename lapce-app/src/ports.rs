@@ -0,0 +1,269 @@
+use std::{
+    cell::RefCell, collections::HashMap, net::TcpListener, rc::Rc, sync::Arc,
+};
+
+use anyhow::Result;
+use floem::{
+    ext_event::create_ext_action,
+    keyboard::Modifiers,
+    reactive::{RwSignal, Scope},
+};
+use lapce_core::{command::EditCommand, mode::Mode};
+use lapce_xi_rope::Rope;
+use tracing::error;
+
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    editor::EditorData,
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::Editors,
+    proxy::{PortForward, SshRemote},
+    window_tab::CommonData,
+};
+
+/// A port of the remote that's forwarded to localhost
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ForwardedPort {
+    pub remote_port: u16,
+    /// The port on localhost, once the forward is set up
+    pub local_port: Option<u16>,
+    /// Whether it was forwarded because a process started listening on it,
+    /// rather than by the user
+    pub detected: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct PortsData {
+    pub ports: RwSignal<im::Vector<ForwardedPort>>,
+    /// The input of a port to forward
+    pub input_editor: EditorData,
+    /// The forwards that are set up, by their remote port
+    forwards: Rc<RefCell<HashMap<u16, PortForward>>>,
+    ssh: Option<Arc<SshRemote>>,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for PortsData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PanelFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Edit(EditCommand::InsertNewLine) => {
+                self.forward_input();
+                CommandExecuted::Yes
+            }
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.input_editor.run_command(command, count, mods)
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.input_editor.receive_char(c);
+    }
+}
+
+impl PortsData {
+    pub fn new(
+        cx: Scope,
+        editors: Editors,
+        common: Rc<CommonData>,
+        ssh: Option<Arc<SshRemote>>,
+    ) -> Self {
+        let data = Self {
+            ports: cx.create_rw_signal(im::Vector::new()),
+            input_editor: editors.make_local(cx, common.clone()),
+            forwards: Rc::new(RefCell::new(HashMap::new())),
+            ssh,
+            common,
+        };
+        data.watch();
+        data
+    }
+
+    /// Whether ports can be forwarded, which is only from SSH hosts
+    pub fn is_available(&self) -> bool {
+        self.ssh.is_some()
+    }
+
+    fn watch(&self) {
+        if self.is_available() {
+            self.common.proxy.watch_listening_ports();
+        }
+    }
+
+    /// Forward the port typed in the input
+    pub fn forward_input(&self) {
+        let doc = self.input_editor.doc();
+        let input = doc.buffer.with_untracked(|buffer| buffer.to_string());
+        if let Ok(port) = input.trim().parse::<u16>() {
+            doc.reload(Rope::from(""), true);
+            self.forward(port, false);
+        }
+    }
+
+    pub fn forward(&self, remote_port: u16, detected: bool) {
+        if !self.is_available()
+            || self.ports.with_untracked(|ports| {
+                ports.iter().any(|p| p.remote_port == remote_port)
+            })
+        {
+            return;
+        }
+
+        self.ports.update(|ports| {
+            ports.push_back(ForwardedPort {
+                remote_port,
+                local_port: None,
+                detected,
+                error: None,
+            });
+        });
+        self.start_forward(remote_port, None);
+    }
+
+    /// Set up the forward of `remote_port` off the UI thread, after
+    /// removing an `old` one of it
+    fn start_forward(&self, remote_port: u16, old: Option<PortForward>) {
+        let Some(ssh) = self.ssh.clone() else {
+            return;
+        };
+
+        let ports = self.ports;
+        let forwards = self.forwards.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |result: Result<(u16, PortForward)>| {
+                let exists = ports.with_untracked(|ports| {
+                    ports.iter().any(|p| p.remote_port == remote_port)
+                });
+                if !exists {
+                    // Stopped while it was being set up
+                    if let Ok((_, forward)) = result {
+                        drop_forward(forward);
+                    }
+                    return;
+                }
+
+                let (local_port, error) = match result {
+                    Ok((local_port, forward)) => {
+                        forwards.borrow_mut().insert(remote_port, forward);
+                        (Some(local_port), None)
+                    }
+                    Err(e) => {
+                        error!("Failed to forward port {remote_port}: {e}");
+                        (None, Some(e.to_string()))
+                    }
+                };
+                ports.update(|ports| {
+                    if let Some(port) =
+                        ports.iter_mut().find(|p| p.remote_port == remote_port)
+                    {
+                        port.local_port = local_port;
+                        port.error = error;
+                    }
+                });
+            },
+        );
+        std::thread::spawn(move || {
+            drop(old);
+            let result = free_local_port(remote_port).and_then(|local_port| {
+                ssh.forward_port(local_port, remote_port)
+                    .map(|forward| (local_port, forward))
+            });
+            send(result);
+        });
+    }
+
+    pub fn stop(&self, remote_port: u16) {
+        self.ports.update(|ports| {
+            ports.retain(|p| p.remote_port != remote_port);
+        });
+        if let Some(forward) = self.forwards.borrow_mut().remove(&remote_port) {
+            drop_forward(forward);
+        }
+    }
+
+    pub fn open_in_browser(&self, remote_port: u16) {
+        let local_port = self.ports.with_untracked(|ports| {
+            ports
+                .iter()
+                .find(|p| p.remote_port == remote_port)
+                .and_then(|p| p.local_port)
+        });
+        if let Some(local_port) = local_port {
+            if let Err(e) = open::that(format!("http://localhost:{local_port}")) {
+                error!("Failed to open localhost:{local_port}: {e}");
+            }
+        }
+    }
+
+    /// Forward the ports processes started listening on, and stop the ones
+    /// forwarded that way which aren't listened on anymore
+    pub fn listening_ports_changed(&self, listening: &[u16]) {
+        let stopped: Vec<u16> = self.ports.with_untracked(|ports| {
+            ports
+                .iter()
+                .filter(|p| p.detected && !listening.contains(&p.remote_port))
+                .map(|p| p.remote_port)
+                .collect()
+        });
+        for port in stopped {
+            self.stop(port);
+        }
+        for port in listening {
+            self.forward(*port, true);
+        }
+    }
+
+    /// Set up the forwards again after the connection to the remote was
+    /// re-established, as they went away with it
+    pub fn reconnected(&self) {
+        self.watch();
+        let ports: Vec<u16> = self
+            .ports
+            .with_untracked(|ports| ports.iter().map(|p| p.remote_port).collect());
+        self.ports.update(|ports| {
+            for port in ports.iter_mut() {
+                port.local_port = None;
+                port.error = None;
+                // The new proxy counts the ports already listened on as
+                // uninteresting, so it won't tell when these are closed
+                port.detected = false;
+            }
+        });
+        for port in ports {
+            let old = self.forwards.borrow_mut().remove(&port);
+            self.start_forward(port, old);
+        }
+    }
+}
+
+/// Stopping a forward runs ssh, which isn't done on the UI thread
+fn drop_forward(forward: PortForward) {
+    std::thread::spawn(move || drop(forward));
+}
+
+/// The same port as on the remote when it's free here, another free one
+/// otherwise
+fn free_local_port(remote_port: u16) -> Result<u16> {
+    if TcpListener::bind(("127.0.0.1", remote_port)).is_ok() {
+        return Ok(remote_port);
+    }
+    Ok(TcpListener::bind(("127.0.0.1", 0))?.local_addr()?.port())
+}
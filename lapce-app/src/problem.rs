@@ -0,0 +1,83 @@
+use std::{path::Path, rc::Rc};
+
+use floem::{
+    keyboard::Modifiers,
+    reactive::{Memo, Scope},
+};
+use lapce_core::{command::EditCommand, mode::Mode};
+use lsp_types::Diagnostic;
+
+use crate::{
+    command::{CommandExecuted, CommandKind},
+    editor::EditorData,
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::Editors,
+    window_tab::CommonData,
+};
+
+#[derive(Clone, Debug)]
+pub struct ProblemData {
+    pub filter_editor: EditorData,
+    /// The lowercased text of the filter input
+    pub filter: Memo<String>,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for ProblemData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PanelFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Edit(EditCommand::InsertNewLine) => CommandExecuted::Yes,
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.filter_editor.run_command(command, count, mods)
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.filter_editor.receive_char(c);
+    }
+}
+
+impl ProblemData {
+    pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
+        let filter_editor = editors.make_local(cx, common.clone());
+        let buffer = filter_editor.doc().buffer;
+        let filter = cx.create_memo(move |_| {
+            buffer.with(|buffer| buffer.to_string().trim().to_lowercase())
+        });
+
+        Self {
+            filter_editor,
+            filter,
+            common,
+        }
+    }
+}
+
+/// Whether the diagnostic in the file at `path` matches the `filter`, by its
+/// message, source or the path
+pub fn filter_matches(filter: &str, path: &Path, diagnostic: &Diagnostic) -> bool {
+    filter.is_empty()
+        || diagnostic.message.to_lowercase().contains(filter)
+        || diagnostic
+            .source
+            .as_ref()
+            .is_some_and(|source| source.to_lowercase().contains(filter))
+        || path.to_string_lossy().to_lowercase().contains(filter)
+}
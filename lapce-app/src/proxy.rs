@@ -1,22 +1,28 @@
-use std::{collections::HashMap, path::PathBuf, process::Command, sync::Arc};
+use std::{
+    collections::HashMap, path::PathBuf, process::Command, sync::Arc, time::Duration,
+};
 
 use crossbeam_channel::Sender;
 use floem::{ext_event::create_signal_from_channel, reactive::ReadSignal};
 use lapce_proxy::dispatch::Dispatcher;
 use lapce_rpc::{
-    core::{CoreHandler, CoreNotification, CoreRpcHandler},
-    plugin::VoltID,
-    proxy::{ProxyRpcHandler, ProxyStatus},
+    core::{CoreHandler, CoreNotification, CoreRequest, CoreRpcHandler},
+    plugin::{LanguageServerConfig, VoltID},
+    proxy::{ProxyRpc, ProxyRpcHandler, ProxyStatus},
     terminal::TermId,
+    RpcError,
 };
+use lsp_types::{MessageType, ShowMessageParams};
 use tracing::error;
 
-use self::{remote::start_remote, ssh::SshRemote};
+use self::remote::{start_remote, Remote, RemoteExit};
+pub use self::ssh::{run_askpass, PortForward, SshRemote};
 use crate::{
     terminal::event::TermEvent,
     workspace::{LapceWorkspace, LapceWorkspaceType},
 };
 
+mod container;
 mod remote;
 mod ssh;
 #[cfg(windows)]
@@ -32,9 +38,18 @@ pub struct ProxyData {
     pub proxy_rpc: ProxyRpcHandler,
     pub core_rpc: CoreRpcHandler,
     pub notification: ReadSignal<Option<CoreNotification>>,
+    /// The host of an SSH workspace, which ports are forwarded from
+    pub ssh: Option<Arc<SshRemote>>,
+    start_tx: Sender<()>,
 }
 
 impl ProxyData {
+    /// Start the proxy, which waits to be started until its window tab is first
+    /// shown. What is sent to it before then is handled once it has started.
+    pub fn start(&self) {
+        let _ = self.start_tx.try_send(());
+    }
+
     pub fn shutdown(&self) {
         self.proxy_rpc.shutdown();
         self.core_rpc.shutdown();
@@ -46,29 +61,53 @@ pub fn new_proxy(
     disabled_volts: Vec<VoltID>,
     extra_plugin_paths: Vec<PathBuf>,
     plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+    language_servers: HashMap<String, LanguageServerConfig>,
+    env: HashMap<String, String>,
     term_tx: Sender<(TermId, TermEvent)>,
 ) -> ProxyData {
     let proxy_rpc = ProxyRpcHandler::new();
     let core_rpc = CoreRpcHandler::new();
+    let ssh = match &workspace.kind {
+        LapceWorkspaceType::RemoteSSH(remote) => {
+            Some(Arc::new(SshRemote::new(remote.clone(), core_rpc.clone())))
+        }
+        _ => None,
+    };
 
+    let (start_tx, start_rx) = crossbeam_channel::bounded(1);
     {
         let core_rpc = core_rpc.clone();
         let proxy_rpc = proxy_rpc.clone();
+        let ssh = ssh.clone();
         std::thread::spawn(move || {
+            // The window tab was closed without being shown
+            if start_rx.recv().is_err() {
+                return;
+            }
+
             core_rpc.notification(CoreNotification::ProxyStatus {
                 status: ProxyStatus::Connecting,
             });
-            proxy_rpc.initialize(
-                workspace.path.clone(),
-                disabled_volts,
-                extra_plugin_paths,
-                plugin_configurations,
-                1,
-                1,
-            );
+            let initialize = {
+                let proxy_rpc = proxy_rpc.clone();
+                let workspace = workspace.path.clone();
+                move || {
+                    proxy_rpc.initialize(
+                        workspace.clone(),
+                        disabled_volts.clone(),
+                        extra_plugin_paths.clone(),
+                        plugin_configurations.clone(),
+                        language_servers.clone(),
+                        env.clone(),
+                        1,
+                        1,
+                    );
+                }
+            };
 
             match &workspace.kind {
                 LapceWorkspaceType::Local => {
+                    initialize();
                     let core_rpc = core_rpc.clone();
                     let proxy_rpc = proxy_rpc.clone();
                     std::thread::spawn(move || {
@@ -77,28 +116,31 @@ pub fn new_proxy(
                         proxy_rpc.mainloop(&mut dispatcher);
                     });
                 }
-                LapceWorkspaceType::RemoteSSH(remote) => {
-                    if let Err(e) = start_remote(
-                        SshRemote {
-                            ssh: remote.clone(),
-                        },
-                        core_rpc.clone(),
-                        proxy_rpc.clone(),
-                    ) {
-                        error!("Failed to start SSH remote: {e}");
+                LapceWorkspaceType::RemoteSSH(_) => {
+                    if let Some(ssh) = ssh {
+                        run_remote(&*ssh, &core_rpc, &proxy_rpc, initialize);
                     }
                 }
                 #[cfg(windows)]
                 LapceWorkspaceType::RemoteWSL(remote) => {
-                    if let Err(e) = start_remote(
-                        wsl::WslRemote {
+                    run_remote(
+                        &wsl::WslRemote {
                             wsl: remote.clone(),
                         },
-                        core_rpc.clone(),
-                        proxy_rpc.clone(),
-                    ) {
-                        error!("Failed to start SSH remote: {e}");
-                    }
+                        &core_rpc,
+                        &proxy_rpc,
+                        initialize,
+                    );
+                }
+                LapceWorkspaceType::RemoteContainer(remote) => {
+                    run_remote(
+                        &container::ContainerRemote {
+                            container: remote.clone(),
+                        },
+                        &core_rpc,
+                        &proxy_rpc,
+                        initialize,
+                    );
                 }
             }
         });
@@ -122,6 +164,75 @@ pub fn new_proxy(
         proxy_rpc,
         core_rpc,
         notification,
+        ssh,
+        start_tx,
+    }
+}
+
+/// Keeps a remote proxy running, connecting to it again with an exponential
+/// backoff whenever the connection is lost
+fn run_remote(
+    remote: &impl Remote,
+    core_rpc: &CoreRpcHandler,
+    proxy_rpc: &ProxyRpcHandler,
+    initialize: impl Fn(),
+) {
+    const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+    let mut backoff = Duration::from_secs(1);
+    let mut reconnecting = false;
+    loop {
+        initialize();
+        match start_remote(remote, core_rpc.clone(), proxy_rpc.clone()) {
+            Ok(RemoteExit::Shutdown) => return,
+            Ok(RemoteExit::Lost) => {
+                error!("Lost the connection to the remote proxy");
+                // The new proxy starts from scratch, so the backoff does too
+                backoff = Duration::from_secs(1);
+            }
+            Err(e) if !reconnecting => {
+                error!("Failed to start remote: {e}");
+                core_rpc.show_message(
+                    "Failed to connect to remote".to_string(),
+                    ShowMessageParams {
+                        typ: MessageType::ERROR,
+                        message: format!("{e:#}"),
+                    },
+                );
+                core_rpc.notification(CoreNotification::ProxyStatus {
+                    status: ProxyStatus::Disconnected,
+                });
+                return;
+            }
+            Err(e) => {
+                error!("Failed to reconnect to remote: {e}");
+            }
+        }
+
+        reconnecting = true;
+        core_rpc.notification(CoreNotification::ProxyStatus {
+            status: ProxyStatus::Reconnecting,
+        });
+        std::thread::sleep(backoff);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+
+        // Whatever was sent while disconnected refers to the old proxy, so
+        // requests are failed rather than left waiting forever
+        for msg in proxy_rpc.rx().try_iter() {
+            match msg {
+                ProxyRpc::Request(id, _) => {
+                    proxy_rpc.handle_response(
+                        id,
+                        Err(RpcError {
+                            code: 0,
+                            message: "remote proxy disconnected".to_string(),
+                        }),
+                    );
+                }
+                ProxyRpc::Notification(_) => {}
+                ProxyRpc::Shutdown => return,
+            }
+        }
     }
 }
 
@@ -136,11 +247,29 @@ fn handle_notification(&mut self, rpc: lapce_rpc::core::CoreNotification) {
         let _ = self.tx.send(rpc);
     }
 
-    fn handle_request(
-        &mut self,
-        _id: lapce_rpc::RequestId,
-        _rpc: lapce_rpc::core::CoreRequest,
-    ) {
+    fn handle_request(&mut self, id: lapce_rpc::RequestId, rpc: CoreRequest) {
+        match rpc {
+            // Shown by the UI, which responds when the user answers
+            CoreRequest::SshPrompt {
+                host,
+                prompt,
+                secret,
+            } => {
+                let _ = self.tx.send(CoreNotification::SshPrompt {
+                    request_id: id,
+                    host,
+                    prompt,
+                    secret,
+                });
+            }
+            CoreRequest::ShowMessageRequest { title, params } => {
+                let _ = self.tx.send(CoreNotification::ShowMessageRequest {
+                    request_id: id,
+                    title,
+                    params,
+                });
+            }
+        }
     }
 }
 
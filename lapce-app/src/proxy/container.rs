@@ -0,0 +1,46 @@
+use std::{path::Path, process::Command};
+
+use anyhow::{anyhow, Result};
+
+use crate::workspace::ContainerHost;
+
+use super::{new_command, remote::Remote};
+
+pub struct ContainerRemote {
+    pub container: ContainerHost,
+}
+
+impl Remote for ContainerRemote {
+    fn upload_file(&self, local: impl AsRef<Path>, remote: &str) -> Result<()> {
+        let remote = if remote.starts_with('~') {
+            remote.replacen('~', &self.home_dir()?, 1)
+        } else {
+            remote.to_string()
+        };
+        let output = new_command(&self.container.engine)
+            .arg("cp")
+            .arg(local.as_ref())
+            .arg(format!("{}:{remote}", self.container.container))
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "failed to copy the proxy into the container: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    fn command_builder(&self) -> Command {
+        let mut cmd = new_command(&self.container.engine);
+        // Unlike ssh, `exec` doesn't go through a shell, so the arguments are
+        // joined and evaluated by one to get the same pipes and expansions
+        cmd.args(["exec", "-i", &self.container.container]).args([
+            "sh",
+            "-c",
+            "eval \"$@\"",
+            "sh",
+        ]);
+        cmd
+    }
+}
@@ -2,15 +2,24 @@
     io::{BufReader, Write},
     path::Path,
     process::{Command, Stdio},
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
+use crossbeam_channel::{Receiver, Sender};
 use flate2::read::GzDecoder;
 use lapce_core::{directory::Directory, meta};
 use lapce_rpc::{
-    core::CoreRpcHandler,
-    proxy::{ProxyRpc, ProxyRpcHandler},
-    stdio_transport, RpcMessage,
+    core::{CoreNotification, CoreRequest, CoreResponse, CoreRpcHandler},
+    proxy::{
+        ProxyNotification, ProxyRequest, ProxyResponse, ProxyRpc, ProxyRpcHandler,
+    },
+    stdio::{stdio_transport_with_progress, ChunkProgress},
+    RpcMessage, PROTOCOL_VERSION,
+};
+use lsp_types::{
+    NumberOrString, ProgressParams, ProgressParamsValue, WorkDoneProgress,
+    WorkDoneProgressBegin, WorkDoneProgressEnd, WorkDoneProgressReport,
 };
 use thiserror::Error;
 use tracing::{debug, error};
@@ -18,6 +27,9 @@
 const UNIX_PROXY_SCRIPT: &[u8] = include_bytes!("../../../extra/proxy.sh");
 const WINDOWS_PROXY_SCRIPT: &[u8] = include_bytes!("../../../extra/proxy.ps1");
 
+/// How long a remote proxy has to answer the version handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Clone, Copy, Error, Debug, PartialEq, Eq, strum_macros::Display)]
 #[strum(ascii_case_insensitive)]
 enum HostPlatform {
@@ -69,11 +81,22 @@ fn home_dir(&self) -> Result<String> {
     fn command_builder(&self) -> Command;
 }
 
+/// How a connection to a remote proxy ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemoteExit {
+    /// The proxy was shut down from our side
+    Shutdown,
+    /// The connection or the proxy process died
+    Lost,
+}
+
+/// Starts the proxy on the remote and relays messages to it until the
+/// connection ends
 pub fn start_remote(
-    remote: impl Remote,
+    remote: &impl Remote,
     core_rpc: CoreRpcHandler,
     proxy_rpc: ProxyRpcHandler,
-) -> Result<()> {
+) -> Result<RemoteExit> {
     // Note about platforms:
     // Windows can use either cmd.exe, powershell.exe or pwsh.exe as
     // SSH shell, syntax logic varies significantly that's why we bet on
@@ -85,7 +108,7 @@ pub fn start_remote(
     // shells retain similar syntax, although shells like Nushell might not
     // work (hopefully no one uses it as login shell)
     use HostPlatform::*;
-    let (platform, architecture) = host_specification(&remote).unwrap();
+    let (platform, architecture) = host_specification(remote)?;
 
     if platform == UnknownOS || architecture == HostArchitecture::UnknownArch {
         error!("detected remote host: {platform}/{architecture}");
@@ -113,22 +136,9 @@ pub fn start_remote(
         _ => format!("{remote_proxy_path}/lapce"),
     };
 
-    if !remote
-        .command_builder()
-        .args([&remote_proxy_file, "--version"])
-        .output()
-        .map(|output| {
-            if meta::VERSION == "debug" {
-                String::from_utf8_lossy(&output.stdout).starts_with("Lapce-proxy")
-            } else {
-                String::from_utf8_lossy(&output.stdout).trim()
-                    == format!("Lapce-proxy {}", meta::VERSION)
-            }
-        })
-        .unwrap_or(false)
-    {
+    if !proxy_up_to_date(remote, &remote_proxy_file) {
         download_remote(
-            &remote,
+            remote,
             &platform,
             &architecture,
             &remote_proxy_path,
@@ -170,57 +180,183 @@ pub fn start_remote(
 
     let (writer_tx, writer_rx) = crossbeam_channel::unbounded();
     let (reader_tx, reader_rx) = crossbeam_channel::unbounded();
-    stdio_transport(stdin, writer_rx, stdout, reader_tx);
+    let progress_rpc = core_rpc.clone();
+    stdio_transport_with_progress(
+        stdin,
+        writer_rx,
+        stdout,
+        reader_tx,
+        move |progress| transfer_progress(&progress_rpc, progress),
+    );
+
+    if let Err(e) = handshake(&writer_tx, &reader_rx, &remote_proxy_file) {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(e);
+    }
 
+    let (lost_tx, lost_rx) = crossbeam_channel::bounded::<()>(1);
     let local_proxy_rpc = proxy_rpc.clone();
     let local_writer_tx = writer_tx.clone();
-    std::thread::spawn(move || {
-        for msg in local_proxy_rpc.rx() {
-            match msg {
-                ProxyRpc::Request(id, rpc) => {
-                    let _ = local_writer_tx.send(RpcMessage::Request(id, rpc));
-                }
-                ProxyRpc::Notification(rpc) => {
-                    let _ = local_writer_tx.send(RpcMessage::Notification(rpc));
-                }
-                ProxyRpc::Shutdown => {
-                    let _ = child.kill();
-                    let _ = child.wait();
-                    return;
-                }
+    let writer = std::thread::spawn(move || {
+        let exit = loop {
+            crossbeam_channel::select! {
+                recv(local_proxy_rpc.rx()) -> msg => match msg {
+                    Ok(ProxyRpc::Request(id, rpc)) => {
+                        let _ = local_writer_tx.send(RpcMessage::Request(id, rpc));
+                    }
+                    Ok(ProxyRpc::Notification(rpc)) => {
+                        let _ =
+                            local_writer_tx.send(RpcMessage::Notification(rpc));
+                    }
+                    Ok(ProxyRpc::Shutdown) | Err(_) => break RemoteExit::Shutdown,
+                },
+                recv(lost_rx) -> _ => break RemoteExit::Lost,
             }
-        }
+        };
+        let _ = child.kill();
+        let _ = child.wait();
+        exit
     });
 
-    std::thread::spawn(move || {
-        for msg in reader_rx {
-            match msg {
-                RpcMessage::Request(id, req) => {
-                    let writer_tx = writer_tx.clone();
-                    let core_rpc = core_rpc.clone();
-                    std::thread::spawn(move || match core_rpc.request(req) {
-                        Ok(resp) => {
-                            let _ = writer_tx.send(RpcMessage::Response(id, resp));
-                        }
-                        Err(e) => {
-                            let _ = writer_tx.send(RpcMessage::Error(id, e));
-                        }
-                    });
-                }
-                RpcMessage::Notification(n) => {
-                    core_rpc.notification(n);
-                }
-                RpcMessage::Response(id, resp) => {
-                    proxy_rpc.handle_response(id, Ok(resp));
-                }
-                RpcMessage::Error(id, err) => {
-                    proxy_rpc.handle_response(id, Err(err));
-                }
+    for msg in reader_rx {
+        match msg {
+            RpcMessage::Request(id, req) => {
+                let writer_tx = writer_tx.clone();
+                let core_rpc = core_rpc.clone();
+                std::thread::spawn(move || match core_rpc.request(req) {
+                    Ok(resp) => {
+                        let _ = writer_tx.send(RpcMessage::Response(id, resp));
+                    }
+                    Err(e) => {
+                        let _ = writer_tx.send(RpcMessage::Error(id, e));
+                    }
+                });
+            }
+            RpcMessage::Notification(n) => {
+                core_rpc.notification(n);
+            }
+            RpcMessage::Response(id, resp) => {
+                proxy_rpc.handle_response(id, Ok(resp));
+            }
+            RpcMessage::Error(id, err) => {
+                proxy_rpc.handle_response(id, Err(err));
             }
         }
+    }
+
+    // The proxy's output ended, which is either because we killed it on
+    // shutdown or because the connection dropped
+    let _ = lost_tx.send(());
+    writer
+        .join()
+        .map_err(|_| anyhow!("remote proxy writer panicked"))
+}
+
+/// Shows the receiving of a message from the remote proxy that took more
+/// than one chunk, like the content of a large file
+fn transfer_progress(core_rpc: &CoreRpcHandler, progress: ChunkProgress) {
+    if progress.total <= 1 {
+        return;
+    }
+
+    let value = if progress.is_done() {
+        WorkDoneProgress::End(WorkDoneProgressEnd { message: None })
+    } else {
+        let percentage = Some((progress.received * 100 / progress.total) as u32);
+        if progress.received == 1 {
+            WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                title: format!(
+                    "Receiving {:.1} MB from remote",
+                    progress.size as f64 / (1024.0 * 1024.0)
+                ),
+                cancellable: Some(false),
+                message: None,
+                percentage,
+            })
+        } else {
+            WorkDoneProgress::Report(WorkDoneProgressReport {
+                cancellable: Some(false),
+                message: None,
+                percentage,
+            })
+        }
+    };
+    core_rpc.work_done_progress(ProgressParams {
+        token: NumberOrString::String("remote-transfer".to_string()),
+        value: ProgressParamsValue::WorkDone(value),
     });
+}
 
-    Ok(())
+/// Whether the proxy at `remote_proxy_file` exists and is the version of this
+/// Lapce
+fn proxy_up_to_date(remote: &impl Remote, remote_proxy_file: &str) -> bool {
+    remote
+        .command_builder()
+        .args([remote_proxy_file, "--version"])
+        .output()
+        .map(|output| {
+            if meta::VERSION == "debug" {
+                String::from_utf8_lossy(&output.stdout).starts_with("Lapce-proxy")
+            } else {
+                String::from_utf8_lossy(&output.stdout).trim()
+                    == format!("Lapce-proxy {}", meta::VERSION)
+            }
+        })
+        .unwrap_or(false)
+}
+
+/// Checks the remote proxy speaks the protocol of this Lapce before anything
+/// else is sent to it, so that an incompatible one is reported clearly
+/// rather than failing on messages it doesn't understand
+fn handshake(
+    writer_tx: &Sender<RpcMessage<ProxyRequest, ProxyNotification, CoreResponse>>,
+    reader_rx: &Receiver<RpcMessage<CoreRequest, CoreNotification, ProxyResponse>>,
+    remote_proxy_file: &str,
+) -> Result<()> {
+    // Nothing else has been sent yet, so the id can't clash with a request's
+    let _ = writer_tx.send(RpcMessage::Request(
+        0,
+        ProxyRequest::Handshake {
+            protocol_version: PROTOCOL_VERSION,
+        },
+    ));
+
+    let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match reader_rx.recv_timeout(timeout) {
+            Ok(RpcMessage::Response(
+                0,
+                ProxyResponse::HandshakeResponse { protocol_version },
+            )) => {
+                if protocol_version == PROTOCOL_VERSION {
+                    return Ok(());
+                }
+                return Err(anyhow!(
+                    "the remote proxy {remote_proxy_file} speaks protocol version \
+                     {protocol_version}, but this Lapce speaks version \
+                     {PROTOCOL_VERSION}; update Lapce or remove the remote proxy \
+                     so that a matching one is installed"
+                ));
+            }
+            Ok(RpcMessage::Error(0, e)) => {
+                return Err(anyhow!(
+                    "the remote proxy refused the version handshake: {}",
+                    e.message
+                ));
+            }
+            Ok(msg) => {
+                debug!("message from remote proxy before handshake: {msg:?}");
+            }
+            Err(_) => {
+                return Err(anyhow!(
+                    "the remote proxy {remote_proxy_file} didn't answer the version \
+                     handshake, it's likely too old or not a Lapce proxy"
+                ));
+            }
+        }
+    }
 }
 
 fn download_remote(
@@ -296,67 +432,56 @@ fn download_remote(
     };
 
     if !script_install.success() {
-        let cmd = match platform {
+        debug!("proxy install script failed: {script_install}");
+    }
+
+    // The script can fail, or install a version other than ours when the
+    // release isn't available, in which case we download the proxy here and
+    // upload it
+    if !proxy_up_to_date(remote, remote_proxy_file) {
+        let proxy_filename = format!("lapce-proxy-{platform}-{architecture}");
+        let local_proxy_file = Directory::proxy_directory()
+            .ok_or_else(|| anyhow!("can't find proxy directory"))?
+            .join(&proxy_filename);
+        // remove possibly outdated proxy
+        if local_proxy_file.exists() {
+            std::fs::remove_file(&local_proxy_file)?;
+        }
+        let proxy_version = meta::VERSION;
+        let url = format!("https://github.com/lapce/lapce/releases/download/{proxy_version}/{proxy_filename}.gz");
+        debug!("proxy download URI: {url}");
+        let mut resp = reqwest::blocking::get(url)?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("proxy download failed with: {}", resp.status()));
+        }
+        let mut out = std::fs::File::create(&local_proxy_file)?;
+        let mut gz = GzDecoder::new(&mut resp);
+        std::io::copy(&mut gz, &mut out)?;
+
+        match platform {
+            // Windows creates all dirs in provided path
             HostPlatform::Windows => remote
                 .command_builder()
-                .args(["dir", remote_proxy_file])
+                .arg("mkdir")
+                .arg(remote_proxy_path)
                 .status()?,
+            // Unix needs -p to do same
             _ => remote
                 .command_builder()
-                .arg("test")
-                .arg("-e")
-                .arg(remote_proxy_file)
+                .arg("mkdir")
+                .arg("-p")
+                .arg(remote_proxy_path)
                 .status()?,
         };
-        if !cmd.success() {
-            let proxy_filename = format!("lapce-proxy-{platform}-{architecture}");
-            let local_proxy_file = Directory::proxy_directory()
-                .ok_or_else(|| anyhow!("can't find proxy directory"))?
-                .join(&proxy_filename);
-            // remove possibly outdated proxy
-            if local_proxy_file.exists() {
-                // TODO: add proper proxy version detection and update proxy
-                // when needed
-                std::fs::remove_file(&local_proxy_file)?;
-            }
-            let proxy_version = meta::VERSION;
-            let url = format!("https://github.com/lapce/lapce/releases/download/{proxy_version}/{proxy_filename}.gz");
-            debug!("proxy download URI: {url}");
-            let mut resp = reqwest::blocking::get(url).expect("request failed");
-            if resp.status().is_success() {
-                let mut out = std::fs::File::create(&local_proxy_file)
-                    .expect("failed to create file");
-                let mut gz = GzDecoder::new(&mut resp);
-                std::io::copy(&mut gz, &mut out).expect("failed to copy content");
-            } else {
-                error!("proxy download failed with: {}", resp.status());
-            }
 
-            match platform {
-                // Windows creates all dirs in provided path
-                HostPlatform::Windows => remote
-                    .command_builder()
-                    .arg("mkdir")
-                    .arg(remote_proxy_path)
-                    .status()?,
-                // Unix needs -p to do same
-                _ => remote
-                    .command_builder()
-                    .arg("mkdir")
-                    .arg("-p")
-                    .arg(remote_proxy_path)
-                    .status()?,
-            };
-
-            remote.upload_file(&local_proxy_file, remote_proxy_file)?;
-            if platform != &HostPlatform::Windows {
-                remote
-                    .command_builder()
-                    .arg("chmod")
-                    .arg("+x")
-                    .arg(remote_proxy_file)
-                    .status()?;
-            }
+        remote.upload_file(&local_proxy_file, remote_proxy_file)?;
+        if platform != &HostPlatform::Windows {
+            remote
+                .command_builder()
+                .arg("chmod")
+                .arg("+x")
+                .arg(remote_proxy_file)
+                .status()?;
         }
     }
 
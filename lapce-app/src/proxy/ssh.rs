@@ -1,16 +1,120 @@
-use std::{path::Path, process::Command};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
-use anyhow::Result;
-use tracing::debug;
+use anyhow::{anyhow, Result};
+use lapce_rpc::core::{CoreRequest, CoreResponse, CoreRpcHandler};
+use tracing::{debug, error};
 
 use super::remote::Remote;
 use crate::{proxy::new_command, workspace::SshHost};
 
+/// Set for the ssh commands to the address and the token of the [`Askpass`]
+/// server, and tells Lapce it's run as ssh's askpass program
+const ASKPASS_ENV: &str = "LAPCE_SSH_ASKPASS";
+/// How long the askpass program has to send its token and prompt, so that a
+/// connection that sends nothing doesn't block the prompts after it
+const ASKPASS_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct SshRemote {
     pub ssh: SshHost,
+    askpass: Option<Askpass>,
 }
 
 impl SshRemote {
+    pub fn new(ssh: SshHost, core_rpc: CoreRpcHandler) -> Self {
+        let askpass = Askpass::start(ssh.to_string(), core_rpc)
+            .map_err(|e| error!("Failed to start the ssh askpass server: {e}"))
+            .ok();
+        Self { ssh, askpass }
+    }
+
+    /// Have ssh ask for passwords, key passphrases and host confirmations
+    /// through Lapce instead of a terminal it doesn't have. Keys that are in
+    /// ssh-agent or that don't have a passphrase are used without asking.
+    fn set_askpass(&self, cmd: &mut Command) {
+        let Some(askpass) = self.askpass.as_ref() else {
+            return;
+        };
+        let Ok(exe) = std::env::current_exe() else {
+            return;
+        };
+        cmd.env("SSH_ASKPASS", exe)
+            .env("SSH_ASKPASS_REQUIRE", "force")
+            .env(ASKPASS_ENV, format!("{} {}", askpass.addr, askpass.token));
+        // Versions of ssh before `SSH_ASKPASS_REQUIRE` only use the askpass
+        // program when there's a display
+        #[cfg(unix)]
+        if std::env::var_os("DISPLAY").is_none() {
+            cmd.env("DISPLAY", ":0");
+        }
+    }
+
+    /// Forwards `remote_port` on the remote's localhost to `local_port` on
+    /// ours, until the returned [`PortForward`] is dropped
+    pub fn forward_port(
+        &self,
+        local_port: u16,
+        remote_port: u16,
+    ) -> Result<PortForward> {
+        let spec = format!("127.0.0.1:{local_port}:localhost:{remote_port}");
+
+        // The master connection the proxy runs over does the forwarding
+        #[cfg(unix)]
+        {
+            let output = self
+                .ssh_command(&["-O", "forward", "-L", &spec])
+                .stdin(Stdio::null())
+                .output()?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "{}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            Ok(PortForward {
+                cancel: self.ssh_command(&["-O", "cancel", "-L", &spec]),
+            })
+        }
+
+        #[cfg(windows)]
+        {
+            let child = self
+                .ssh_command(&["-N", "-o", "ExitOnForwardFailure=yes", "-L", &spec])
+                .stdin(Stdio::null())
+                .spawn()?;
+            Ok(PortForward { child })
+        }
+    }
+
+    /// An ssh command to the host, with `args` given before the host
+    fn ssh_command(&self, args: &[&str]) -> Command {
+        let mut cmd = new_command("ssh");
+        cmd.args(Self::SSH_ARGS);
+        self.set_askpass(&mut cmd);
+
+        if let Some(port) = self.ssh.port {
+            cmd.arg("-p").arg(port.to_string());
+        }
+
+        cmd.args(args);
+        cmd.arg(self.ssh.user_host());
+
+        if !std::env::var("LAPCE_DEBUG").unwrap_or_default().is_empty() {
+            cmd.arg("-v");
+        }
+
+        cmd
+    }
+
     #[cfg(windows)]
     const SSH_ARGS: &'static [&'static str] = &[];
 
@@ -32,6 +136,7 @@ fn upload_file(&self, local: impl AsRef<Path>, remote: &str) -> Result<()> {
         let mut cmd = new_command("scp");
 
         cmd.args(Self::SSH_ARGS);
+        self.set_askpass(&mut cmd);
 
         if let Some(port) = self.ssh.port {
             cmd.arg("-P").arg(port.to_string());
@@ -49,19 +154,154 @@ fn upload_file(&self, local: impl AsRef<Path>, remote: &str) -> Result<()> {
     }
 
     fn command_builder(&self) -> Command {
-        let mut cmd = new_command("ssh");
-        cmd.args(Self::SSH_ARGS);
+        self.ssh_command(&[])
+    }
+}
 
-        if let Some(port) = self.ssh.port {
-            cmd.arg("-p").arg(port.to_string());
+/// A port of the remote forwarded to localhost, which stops being forwarded
+/// when dropped
+pub struct PortForward {
+    #[cfg(unix)]
+    cancel: Command,
+    #[cfg(windows)]
+    child: std::process::Child,
+}
+
+impl Drop for PortForward {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            let _ = self.cancel.stdin(Stdio::null()).output();
+        }
+        #[cfg(windows)]
+        {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
         }
+    }
+}
 
-        cmd.arg(self.ssh.user_host());
+/// A local server the askpass program connects to with ssh's prompts, which
+/// are asked in the UI
+struct Askpass {
+    addr: SocketAddr,
+    /// Sent first by the askpass program, so that other local processes
+    /// can't ask for the user's password
+    token: String,
+    closed: Arc<AtomicBool>,
+}
 
-        if !std::env::var("LAPCE_DEBUG").unwrap_or_default().is_empty() {
-            cmd.arg("-v");
+impl Askpass {
+    fn start(host: String, core_rpc: CoreRpcHandler) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let token = askpass_token()?;
+        let closed = Arc::new(AtomicBool::new(false));
+
+        {
+            let token = token.clone();
+            let closed = closed.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if closed.load(Ordering::Acquire) {
+                        return;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    // Prompts are answered one at a time, ssh waits for them
+                    if let Err(e) = answer_prompt(stream, &token, &host, &core_rpc) {
+                        error!("Failed to answer ssh prompt: {e}");
+                    }
+                }
+            });
         }
 
-        cmd
+        Ok(Self {
+            addr,
+            token,
+            closed,
+        })
+    }
+}
+
+impl Drop for Askpass {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Release);
+        // Wake up the server so it sees it's closed
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+fn askpass_token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| anyhow!("can't generate the askpass token: {e}"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+fn answer_prompt(
+    stream: TcpStream,
+    token: &str,
+    host: &str,
+    core_rpc: &CoreRpcHandler,
+) -> Result<()> {
+    stream.set_read_timeout(Some(ASKPASS_READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim_end() != token {
+        return Err(anyhow!("wrong askpass token"));
+    }
+
+    line.clear();
+    reader.read_line(&mut line)?;
+    let (prompt, secret): (String, bool) = serde_json::from_str(&line)?;
+
+    let answer = match core_rpc.request(CoreRequest::SshPrompt {
+        host: host.to_string(),
+        prompt: prompt.trim().to_string(),
+        secret,
+    }) {
+        Ok(CoreResponse::SshPrompt { answer }) => answer,
+        Ok(_) | Err(_) => None,
+    };
+
+    let mut stream = stream;
+    writeln!(stream, "{}", serde_json::to_string(&answer)?)?;
+    Ok(())
+}
+
+/// When Lapce is run by ssh as its askpass program, ask the UI for the
+/// answer to the prompt, print it and return the exit code
+pub fn run_askpass() -> Option<i32> {
+    let server = std::env::var(ASKPASS_ENV).ok()?;
+    let prompt = std::env::args().nth(1).unwrap_or_default();
+    // Confirmations like whether to trust a new host are answered in the open
+    let secret =
+        std::env::var("SSH_ASKPASS_PROMPT").is_err() && !prompt.contains("(yes/no");
+
+    let ask = || -> Result<Option<String>> {
+        let (addr, token) = server
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("invalid {ASKPASS_ENV}"))?;
+        let mut stream = TcpStream::connect(addr)?;
+        writeln!(stream, "{token}")?;
+        writeln!(stream, "{}", serde_json::to_string(&(prompt, secret))?)?;
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        Ok(serde_json::from_str(&line)?)
+    };
+
+    match ask() {
+        Ok(Some(answer)) => {
+            println!("{answer}");
+            Some(0)
+        }
+        Ok(None) => Some(1),
+        Err(e) => {
+            eprintln!("{e}");
+            Some(1)
+        }
     }
 }
@@ -1,6 +1,6 @@
 use std::{path::Path, process::Command};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use crate::workspace::WslHost;
 
@@ -10,11 +10,31 @@ pub struct WslRemote {
     pub wsl: WslHost,
 }
 
+impl WslRemote {
+    /// The name of the distribution, which is WSL's default one when the host
+    /// is empty, like for a workspace opened from inside WSL
+    fn distro(&self) -> Result<String> {
+        if !self.wsl.host.is_empty() {
+            return Ok(self.wsl.host.clone());
+        }
+        let output = self
+            .command_builder()
+            .args(["printenv", "WSL_DISTRO_NAME"])
+            .output()?;
+        let distro = String::from_utf8(output.stdout)?.trim().to_string();
+        if distro.is_empty() {
+            return Err(anyhow!("can't find the default WSL distribution"));
+        }
+        Ok(distro)
+    }
+}
+
 impl Remote for WslRemote {
     fn upload_file(&self, local: impl AsRef<Path>, remote: &str) -> Result<()> {
-        let mut wsl_path = Path::new(r"\\wsl.localhost\").join(&self.wsl.host);
+        let distro = self.distro()?;
+        let mut wsl_path = Path::new(r"\\wsl.localhost\").join(&distro);
         if !wsl_path.exists() {
-            wsl_path = Path::new(r"\\wsl$").join(&self.wsl.host);
+            wsl_path = Path::new(r"\\wsl$").join(&distro);
         }
         wsl_path = if remote.starts_with('~') {
             let home_dir = self.home_dir()?;
@@ -28,7 +48,10 @@ fn upload_file(&self, local: impl AsRef<Path>, remote: &str) -> Result<()> {
 
     fn command_builder(&self) -> Command {
         let mut cmd = new_command("wsl");
-        cmd.arg("-d").arg(&self.wsl.host).arg("--");
+        if !self.wsl.host.is_empty() {
+            cmd.arg("-d").arg(&self.wsl.host);
+        }
+        cmd.arg("--");
         cmd
     }
 }
@@ -5,7 +5,10 @@
     cosmic_text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     event::EventListener,
     keyboard::Modifiers,
-    peniko::kurbo::{Point, Rect, Size},
+    peniko::{
+        kurbo::{Point, Rect, Size},
+        Color,
+    },
     reactive::{
         create_effect, create_memo, create_rw_signal, Memo, ReadSignal, RwSignal,
         Scope,
@@ -22,7 +25,7 @@
 use indexmap::IndexMap;
 use inflector::Inflector;
 use lapce_core::mode::Mode;
-use lapce_rpc::plugin::VoltID;
+use lapce_rpc::plugin::{VoltConfig, VoltConfigType, VoltID};
 use lapce_xi_rope::Rope;
 use serde::Serialize;
 
@@ -66,6 +69,22 @@ fn from(v: serde_json::Value) -> Self {
     }
 }
 
+impl SettingsValue {
+    /// The value as the settings controls show it
+    fn text(&self) -> Option<String> {
+        match self {
+            SettingsValue::Float(n) => Some(n.to_string()),
+            SettingsValue::Integer(n) => Some(n.to_string()),
+            SettingsValue::String(s) => Some(s.to_string()),
+            SettingsValue::Bool(b) => Some(b.to_string()),
+            SettingsValue::Dropdown(dropdown) => {
+                dropdown.items.get(dropdown.active_index).cloned()
+            }
+            SettingsValue::Empty => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct SettingsItem {
     kind: String,
@@ -74,6 +93,11 @@ struct SettingsItem {
     description: String,
     filter_text: String,
     value: SettingsValue,
+    /// The default value as the settings controls show it
+    default: Option<String>,
+    /// Whether the settings file sets the value, rather than leaving it to
+    /// the default
+    modified: RwSignal<bool>,
     pos: RwSignal<Point>,
     size: RwSignal<Size>,
     // this is only the header that give an visual sepeartion between different type of settings
@@ -143,34 +167,40 @@ fn into_settings_map(
         }
 
         let config = common.config.get_untracked();
+        let default_config = LapceConfig::default_values();
+        let file_table = LapceConfig::get_file_table().unwrap_or_default();
         let mut items = im::Vector::new();
         let mut kinds = im::Vector::new();
         let mut item_height_accum = 0.0;
 
-        for (kind, fields, descs, mut settings_map) in [
+        for (kind, fields, descs, mut settings_map, default_map) in [
             (
                 "Core",
                 &CoreConfig::FIELDS[..],
                 &CoreConfig::DESCS[..],
                 into_settings_map(&config.core),
+                into_settings_map(&default_config.core),
             ),
             (
                 "Editor",
                 &EditorConfig::FIELDS[..],
                 &EditorConfig::DESCS[..],
                 into_settings_map(&config.editor),
+                into_settings_map(&default_config.editor),
             ),
             (
                 "UI",
                 &UIConfig::FIELDS[..],
                 &UIConfig::DESCS[..],
                 into_settings_map(&config.ui),
+                into_settings_map(&default_config.ui),
             ),
             (
                 "Terminal",
                 &TerminalConfig::FIELDS[..],
                 &TerminalConfig::DESCS[..],
                 into_settings_map(&config.terminal),
+                into_settings_map(&default_config.terminal),
             ),
         ] {
             let pos = cx.create_rw_signal(Point::new(0.0, item_height_accum));
@@ -181,6 +211,8 @@ fn into_settings_map(
                 filter_text: "".to_string(),
                 description: "".to_string(),
                 value: SettingsValue::Empty,
+                default: None,
+                modified: cx.create_rw_signal(false),
                 pos,
                 size: cx.create_rw_signal(Size::ZERO),
                 header: true,
@@ -198,9 +230,15 @@ fn into_settings_map(
                     SettingsValue::from(value)
                 };
 
+                let default = default_map
+                    .get(&field)
+                    .cloned()
+                    .and_then(|value| SettingsValue::from(value).text());
+
                 let name =
                     format!("{kind}: {}", name.replace('_', " ").to_title_case());
                 let kind = kind.to_lowercase();
+                let modified = LapceConfig::file_sets(&file_table, &kind, &field);
                 let filter_text = format!("{kind} {name} {desc}").to_lowercase();
                 let filter_text =
                     format!("{filter_text}{}", filter_text.replace(' ', ""));
@@ -211,6 +249,8 @@ fn into_settings_map(
                     filter_text,
                     description: desc.to_string(),
                     value,
+                    default,
+                    modified: cx.create_rw_signal(modified),
                     pos: cx.create_rw_signal(Point::ZERO),
                     size: cx.create_rw_signal(Size::ZERO),
                     header: false,
@@ -225,6 +265,7 @@ fn into_settings_map(
         cx.create_effect(move |_| {
             let mut item_height_accum = item_height_accum;
             let plugins = installed_plugin.get();
+            let file_table = LapceConfig::get_file_table().unwrap_or_default();
             let mut items = im::Vector::new();
             let mut kinds = im::Vector::new();
             for (_, volt) in plugins {
@@ -241,6 +282,8 @@ fn into_settings_map(
                         filter_text: "".to_string(),
                         description: "".to_string(),
                         value: SettingsValue::Empty,
+                        default: None,
+                        modified: cx.create_rw_signal(false),
                         pos,
                         size: cx.create_rw_signal(Size::ZERO),
                         header: true,
@@ -257,6 +300,14 @@ fn into_settings_map(
                                 meta.display_name,
                                 name.replace('_', " ").to_title_case()
                             );
+                            let value = plugin_setting_value(
+                                &config,
+                                plugin_config.and_then(|config| config.get(&field)),
+                            );
+                            let default = plugin_setting_value(&config, None).text();
+                            let modified =
+                                LapceConfig::file_sets(&file_table, &kind, &field);
+
                             let desc = config.description;
                             let filter_text =
                                 format!("{kind} {name} {desc}").to_lowercase();
@@ -265,11 +316,6 @@ fn into_settings_map(
                                 filter_text.replace(' ', "")
                             );
 
-                            let value = plugin_config
-                                .and_then(|config| config.get(&field).cloned())
-                                .unwrap_or(config.default);
-                            let value = SettingsValue::from(value);
-
                             let item = SettingsItem {
                                 kind: kind.clone(),
                                 name,
@@ -277,6 +323,8 @@ fn into_settings_map(
                                 filter_text,
                                 description: desc.to_string(),
                                 value,
+                                default,
+                                modified: cx.create_rw_signal(modified),
                                 pos: cx.create_rw_signal(Point::ZERO),
                                 size: cx.create_rw_signal(Size::ZERO),
                                 header: false,
@@ -304,6 +352,38 @@ fn into_settings_map(
     }
 }
 
+/// The value of a volt's setting as its declared type, an `enum` is picked
+/// from a dropdown
+fn plugin_setting_value(
+    config: &VoltConfig,
+    value: Option<&serde_json::Value>,
+) -> SettingsValue {
+    let value = config.checked_value(value);
+    match config.kind() {
+        VoltConfigType::Enum => {
+            let items: im::Vector<String> =
+                config.values.iter().flatten().cloned().collect();
+            let active_index = value
+                .as_str()
+                .and_then(|value| items.iter().position(|item| item == value))
+                .unwrap_or(0);
+            SettingsValue::Dropdown(DropdownInfo {
+                active_index,
+                items,
+            })
+        }
+        VoltConfigType::Number if config.default.is_f64() => {
+            SettingsValue::Float(value.as_f64().unwrap_or_default())
+        }
+        VoltConfigType::Number => SettingsValue::from(value),
+        VoltConfigType::String => match value {
+            serde_json::Value::String(s) => SettingsValue::String(s),
+            value => SettingsValue::String(value.to_string()),
+        },
+        VoltConfigType::Bool => SettingsValue::from(value),
+    }
+}
+
 pub fn settings_view(
     installed_plugins: RwSignal<IndexMap<VoltID, InstalledVoltData>>,
     editors: Editors,
@@ -535,6 +615,7 @@ fn settings_item_view(
     };
 
     let timer = create_rw_signal(TimerToken::INVALID);
+    let reset = create_rw_signal(0u64);
 
     let editor_value = match &item.value {
         SettingsValue::Float(n) => Some(n.to_string()),
@@ -559,6 +640,8 @@ fn settings_item_view(
                 let kind = item.kind.clone();
                 let field = item.field.clone();
                 let item_value = item.value.clone();
+                let default = item.default.clone();
+                let modified = item.modified;
                 create_effect(move |last| {
                     let doc = doc.get_untracked();
                     let rev = doc.buffer.with(|b| b.rev());
@@ -572,6 +655,7 @@ fn settings_item_view(
                     let field = field.clone();
                     let buffer = doc.buffer;
                     let item_value = item_value.clone();
+                    let default = default.clone();
                     let token =
                         exec_after(Duration::from_millis(500), move |token| {
                             if let Some(timer) = timer.try_get_untracked() {
@@ -580,31 +664,29 @@ fn settings_item_view(
                                         buffer.with_untracked(|b| b.to_string());
                                     let value = match &item_value {
                                         SettingsValue::Float(_) => {
-                                            value.parse::<f64>().ok().and_then(|v| {
-                                                serde::Serialize::serialize(
-                                        &v,
-                                        toml_edit::ser::ValueSerializer::new(),
-                                    ).ok()
+                                            value.parse::<f64>().ok().map(|v| {
+                                                (v.to_string(), toml_value(&v))
                                             })
                                         }
                                         SettingsValue::Integer(_) => {
-                                            value.parse::<i64>().ok().and_then(|v| {
-                                                serde::Serialize::serialize(
-                                        &v,
-                                        toml_edit::ser::ValueSerializer::new(),
-                                    ).ok()
+                                            value.parse::<i64>().ok().map(|v| {
+                                                (v.to_string(), toml_value(&v))
                                             })
                                         }
-                                        _ => serde::Serialize::serialize(
-                                            &value,
-                                            toml_edit::ser::ValueSerializer::new(),
-                                        )
-                                        .ok(),
+                                        _ => {
+                                            let v = toml_value(&value);
+                                            Some((value, v))
+                                        }
                                     };
 
-                                    if let Some(value) = value {
-                                        LapceConfig::update_file(
-                                            &kind, &field, value,
+                                    if let Some((text, Some(value))) = value {
+                                        write_setting(
+                                            &kind,
+                                            &field,
+                                            &text,
+                                            value,
+                                            default.as_deref(),
+                                            modified,
                                         );
                                     }
                                 }
@@ -615,6 +697,21 @@ fn settings_item_view(
                     rev
                 });
 
+                // Show the default again once the setting is reset
+                {
+                    let default = item.default.clone();
+                    create_effect(move |last| {
+                        reset.track();
+                        if last.is_none() {
+                            return;
+                        }
+                        if let Some(default) = default.as_ref() {
+                            doc.get_untracked()
+                                .reload(Rope::from(default.as_str()), true);
+                        }
+                    });
+                }
+
                 text_input_view
                     .keyboard_navigatable()
                     .style(move |s| {
@@ -633,6 +730,20 @@ fn settings_item_view(
                     .unwrap_or_default();
                 let current_value = create_rw_signal(current_value);
 
+                // Show the default again once the setting is reset
+                {
+                    let default = item.default.clone();
+                    create_effect(move |last| {
+                        reset.track();
+                        if last.is_none() {
+                            return;
+                        }
+                        if let Some(default) = default.as_ref() {
+                            current_value.set(default.to_string());
+                        }
+                    });
+                }
+
                 dropdown_view(
                     &item,
                     current_value,
@@ -660,15 +771,44 @@ fn settings_item_view(
         }
     };
 
+    let modified = item.modified;
+    let header = item.header;
     stack((
-        label(move || item.name.clone()).style(move |s| {
-            s.font_bold()
-                .text_ellipsis()
-                .min_width(0.0)
-                .max_width_pct(100.0)
-                .line_height(1.6)
-                .font_size(config.get().ui.font_size() as f32 + 1.0)
-        }),
+        stack((
+            label(move || item.name.clone()).style(move |s| {
+                s.font_bold()
+                    .text_ellipsis()
+                    .min_width(0.0)
+                    .line_height(1.6)
+                    .font_size(config.get().ui.font_size() as f32 + 1.0)
+            }),
+            {
+                let kind = item.kind.clone();
+                let field = item.field.clone();
+                text("Reset")
+                    .on_click_stop(move |_| {
+                        LapceConfig::reset_setting(&kind, &field);
+                        modified.set(false);
+                        reset.update(|reset| *reset += 1);
+                    })
+                    .style(move |s| {
+                        let config = config.get();
+                        s.margin_left(10)
+                            .padding_horiz(6)
+                            .cursor(CursorStyle::Pointer)
+                            .border(1)
+                            .border_radius(6)
+                            .border_color(config.color(LapceColor::LAPCE_BORDER))
+                            .apply_if(!modified.get(), |s| s.hide())
+                            .active(|s| {
+                                s.background(
+                                    config.color(LapceColor::PANEL_BACKGROUND),
+                                )
+                            })
+                    })
+            },
+        ))
+        .style(|s| s.items_center().max_width_pct(100.0)),
         stack((
             label(move || item.description.clone()).style(move |s| {
                 s.min_width(0.0)
@@ -684,19 +824,39 @@ fn settings_item_view(
 
                 let kind = item.kind.clone();
                 let field = item.field.clone();
+                let default = item.default.clone();
+                let modified = item.modified;
                 create_effect(move |last| {
                     let checked = checked.get();
                     if last.is_none() {
                         return;
                     }
-                    if let Ok(value) = serde::Serialize::serialize(
-                        &checked,
-                        toml_edit::ser::ValueSerializer::new(),
-                    ) {
-                        LapceConfig::update_file(&kind, &field, value);
+                    if let Some(value) = toml_value(&checked) {
+                        write_setting(
+                            &kind,
+                            &field,
+                            &checked.to_string(),
+                            value,
+                            default.as_deref(),
+                            modified,
+                        );
                     }
                 });
 
+                // Show the default again once the setting is reset
+                {
+                    let default = item.default.clone();
+                    create_effect(move |last| {
+                        reset.track();
+                        if last.is_none() {
+                            return;
+                        }
+                        if let Some(default) = default.as_ref() {
+                            checked.set(default == "true");
+                        }
+                    });
+                }
+
                 container(
                     stack((
                         checkbox(move || checked.get(), config),
@@ -731,14 +891,48 @@ fn settings_item_view(
             item.size.set(new_size);
         }
     })
-    .style(|s| {
+    .style(move |s| {
         s.flex_col()
             .padding_vert(10.0)
             .min_width_pct(100.0)
             .max_width(300.0)
+            .apply_if(!header, |s| {
+                // Mark the settings the settings file changes from their
+                // defaults
+                s.padding_left(8.0).border_left(2.0).border_color(
+                    if modified.get() {
+                        config.get().color(LapceColor::SOURCE_CONTROL_MODIFIED)
+                    } else {
+                        Color::TRANSPARENT
+                    },
+                )
+            })
     })
 }
 
+fn toml_value(value: &impl Serialize) -> Option<toml_edit::Value> {
+    value.serialize(toml_edit::ser::ValueSerializer::new()).ok()
+}
+
+/// Write the setting to the settings file, or take it out of there once it's
+/// back to its default
+fn write_setting(
+    kind: &str,
+    field: &str,
+    text: &str,
+    value: toml_edit::Value,
+    default: Option<&str>,
+    modified: RwSignal<bool>,
+) {
+    if default == Some(text) {
+        LapceConfig::reset_setting(kind, field);
+        modified.set(false);
+    } else {
+        LapceConfig::update_file(kind, field, value);
+        modified.set(true);
+    }
+}
+
 pub fn checkbox(
     checked: impl Fn() -> bool + 'static,
     config: ReadSignal<Arc<LapceConfig>>,
@@ -1214,18 +1408,25 @@ fn dropdown_scroll(
 
     let kind = item.kind.clone();
     let field = item.field.clone();
+    let default = item.default.clone();
+    let modified = item.modified;
     let view_fn = move |item_string: String| {
         let kind = kind.clone();
         let field = field.clone();
+        let default = default.clone();
         let local_item_string = item_string.clone();
         label(move || local_item_string.clone())
             .on_click_stop(move |_| {
                 current_value.set(item_string.clone());
-                if let Ok(value) = serde::Serialize::serialize(
-                    &item_string,
-                    toml_edit::ser::ValueSerializer::new(),
-                ) {
-                    LapceConfig::update_file(&kind, &field, value);
+                if let Some(value) = toml_value(&item_string) {
+                    write_setting(
+                        &kind,
+                        &field,
+                        &item_string,
+                        value,
+                        default.as_deref(),
+                        modified,
+                    );
                 }
                 expanded.set(false);
             })
@@ -1,28 +1,57 @@
-use std::{path::PathBuf, rc::Rc};
+use std::{collections::HashSet, path::PathBuf, rc::Rc};
 
 use floem::{
+    ext_event::create_ext_action,
     keyboard::Modifiers,
     reactive::{RwSignal, Scope},
 };
 use indexmap::IndexMap;
 use lapce_core::mode::Mode;
-use lapce_rpc::source_control::FileDiff;
+use lapce_rpc::{
+    proxy::ProxyResponse,
+    source_control::{
+        CommitInfo, FileDiff, GitSyncKind, StashInfo, GIT_AUTH_REQUIRED,
+    },
+    RpcError,
+};
 
 use crate::{
-    command::{CommandExecuted, CommandKind},
+    command::{CommandExecuted, CommandKind, InternalCommand},
     editor::EditorData,
     keypress::{condition::Condition, KeyPressFocus},
     main_split::Editors,
     window_tab::CommonData,
 };
 
+/// The number of commits loaded in the log
+const LOG_LIMIT: usize = 200;
+
 #[derive(Clone, Debug)]
 pub struct SourceControlData {
-    // VCS modified files & whether they should be included in the next commit
-    pub file_diffs: RwSignal<IndexMap<PathBuf, (FileDiff, bool)>>,
+    // VCS modified files, whether they are staged or not
+    pub file_diffs: RwSignal<IndexMap<PathBuf, FileDiff>>,
+    // Changes in the index, which are included in the next commit
+    pub staged: RwSignal<IndexMap<PathBuf, FileDiff>>,
+    // Changes in the working tree that aren't staged yet
+    pub unstaged: RwSignal<IndexMap<PathBuf, FileDiff>>,
+    // Files not tracked by VCS, these are also in `file_diffs` as added
+    pub untracked: RwSignal<HashSet<PathBuf>>,
+    // Files and directories ignored by VCS
+    pub ignored: RwSignal<Vec<PathBuf>>,
     pub branch: RwSignal<String>,
     pub branches: RwSignal<im::Vector<String>>,
+    pub remote_branches: RwSignal<im::Vector<String>>,
+    // Commits ahead of and behind the upstream branch
+    pub ahead_behind: RwSignal<(usize, usize)>,
     pub tags: RwSignal<im::Vector<String>>,
+    // The latest commits, of the whole repository or of `log_file`
+    pub log: RwSignal<im::Vector<CommitInfo>>,
+    // The file whose history is shown in the log, `None` for the whole repository
+    pub log_file: RwSignal<Option<PathBuf>>,
+    // The commit expanded in the log, with the files it changed
+    pub log_commit: RwSignal<Option<(String, im::Vector<FileDiff>)>>,
+    // Stashed changes, the latest first
+    pub stashes: RwSignal<im::Vector<StashInfo>>,
     pub editor: EditorData,
     pub common: Rc<CommonData>,
 }
@@ -64,31 +93,35 @@ impl SourceControlData {
     pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
         Self {
             file_diffs: cx.create_rw_signal(IndexMap::new()),
+            staged: cx.create_rw_signal(IndexMap::new()),
+            unstaged: cx.create_rw_signal(IndexMap::new()),
+            untracked: cx.create_rw_signal(HashSet::new()),
+            ignored: cx.create_rw_signal(Vec::new()),
             branch: cx.create_rw_signal("".to_string()),
             branches: cx.create_rw_signal(im::Vector::new()),
+            remote_branches: cx.create_rw_signal(im::Vector::new()),
+            ahead_behind: cx.create_rw_signal((0, 0)),
             tags: cx.create_rw_signal(im::Vector::new()),
+            log: cx.create_rw_signal(im::Vector::new()),
+            log_file: cx.create_rw_signal(None),
+            log_commit: cx.create_rw_signal(None),
+            stashes: cx.create_rw_signal(im::Vector::new()),
             editor: editors.make_local(cx, common.clone()),
             common,
         }
     }
 
-    pub fn commit(&self) {
-        let diffs: Vec<FileDiff> = self.file_diffs.with_untracked(|file_diffs| {
-            file_diffs
-                .iter()
-                .filter_map(
-                    |(_, (diff, checked))| {
-                        if *checked {
-                            Some(diff)
-                        } else {
-                            None
-                        }
-                    },
-                )
-                .cloned()
-                .collect()
-        });
-        if diffs.is_empty() {
+    /// Commit the staged changes, or all changes if nothing is staged.  
+    /// When `amend` is true the `HEAD` commit is replaced instead.
+    pub fn commit(&self, amend: bool) {
+        let has_staged = self.staged.with_untracked(|staged| !staged.is_empty());
+        let diffs: Vec<FileDiff> = if has_staged {
+            Vec::new()
+        } else {
+            self.unstaged
+                .with_untracked(|unstaged| unstaged.values().cloned().collect())
+        };
+        if !has_staged && diffs.is_empty() && !amend {
             return;
         }
 
@@ -103,6 +136,154 @@ pub fn commit(&self) {
         }
 
         self.editor.reset();
-        self.common.proxy.git_commit(message.to_string(), diffs);
+        self.common
+            .proxy
+            .git_commit(message.to_string(), diffs, amend);
+    }
+
+    /// Add the changes to the index
+    pub fn stage(&self, diffs: &[FileDiff]) {
+        let paths = diffs
+            .iter()
+            .flat_map(FileDiff::paths)
+            .cloned()
+            .collect::<Vec<_>>();
+        if !paths.is_empty() {
+            self.common.proxy.git_stage(paths);
+        }
+    }
+
+    /// Remove the changes from the index, keeping them in the working tree
+    pub fn unstage(&self, diffs: &[FileDiff]) {
+        let paths = diffs
+            .iter()
+            .flat_map(FileDiff::paths)
+            .cloned()
+            .collect::<Vec<_>>();
+        if !paths.is_empty() {
+            self.common.proxy.git_unstage(paths);
+        }
+    }
+
+    pub fn stage_all(&self) {
+        let diffs = self.unstaged.with_untracked(|unstaged| {
+            unstaged.values().cloned().collect::<Vec<_>>()
+        });
+        self.stage(&diffs);
+    }
+
+    pub fn unstage_all(&self) {
+        let diffs = self
+            .staged
+            .with_untracked(|staged| staged.values().cloned().collect::<Vec<_>>());
+        self.unstage(&diffs);
+    }
+
+    /// Stash all the changes, using the commit message as the stash message if there is
+    /// one
+    pub fn stash(&self) {
+        let has_changes = self.file_diffs.with_untracked(|diffs| !diffs.is_empty());
+        if !has_changes {
+            return;
+        }
+
+        let message = self
+            .editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        let message = message.trim();
+        if !message.is_empty() {
+            self.editor.reset();
+        }
+        self.common.proxy.git_stash(message.to_string());
+    }
+
+    /// Apply the stash at `index`, also removing it from the stashes if `pop` is true
+    pub fn apply_stash(&self, index: usize, pop: bool) {
+        if pop {
+            self.common.proxy.git_stash_pop(index);
+        } else {
+            self.common.proxy.git_stash_apply(index);
+        }
+    }
+
+    pub fn drop_stash(&self, index: usize) {
+        self.common.proxy.git_stash_drop(index);
+    }
+
+    /// Fetch, pull or push, asking the user for an access token if the remote needs
+    /// credentials that the ssh-agent and credential helpers don't have
+    pub fn sync(&self, kind: GitSyncKind, token: Option<String>) {
+        let internal_command = self.common.internal_command;
+        let send = create_ext_action(self.common.scope, move |result| {
+            if let Err(RpcError {
+                code: GIT_AUTH_REQUIRED,
+                ..
+            }) = result
+            {
+                internal_command
+                    .send(InternalCommand::PaletteGitCredentials { kind });
+            }
+        });
+        self.common.proxy.git_sync(kind, token, move |result| {
+            send(result);
+        });
+    }
+
+    /// Load the log of `log_file`, or of the whole repository
+    pub fn load_log(&self) {
+        let path = self.log_file.get_untracked();
+        let log = self.log;
+        let log_file = self.log_file;
+        let send = {
+            let path = path.clone();
+            create_ext_action(self.common.scope, move |result| {
+                if let Ok(ProxyResponse::GitLogResponse { commits }) = result {
+                    // Ignore the log of a file that's no longer shown
+                    if log_file.with_untracked(|f| f == &path) {
+                        log.set(commits.into());
+                    }
+                }
+            })
+        };
+        self.common.proxy.git_log(path, LOG_LIMIT, move |result| {
+            send(result);
+        });
+    }
+
+    /// Show the history of `path` in the log, or of the whole repository if it's `None`
+    pub fn show_log(&self, path: Option<PathBuf>) {
+        if self.log_file.get_untracked() != path {
+            self.log_file.set(path);
+            self.log.set(im::Vector::new());
+            self.log_commit.set(None);
+        }
+        self.load_log();
+    }
+
+    /// Expand the commit `id` in the log to list the files it changed, or collapse it
+    /// if it's already expanded
+    pub fn toggle_log_commit(&self, id: String) {
+        if self
+            .log_commit
+            .with_untracked(|c| c.as_ref().is_some_and(|(c, _)| c == &id))
+        {
+            self.log_commit.set(None);
+            return;
+        }
+
+        let log_commit = self.log_commit;
+        let send = {
+            let id = id.clone();
+            create_ext_action(self.common.scope, move |result| {
+                if let Ok(ProxyResponse::GitCommitFilesResponse { diffs }) = result {
+                    log_commit.set(Some((id, diffs.into())));
+                }
+            })
+        };
+        self.common.proxy.git_commit_files(id, move |result| {
+            send(result);
+        });
     }
 }
@@ -0,0 +1,262 @@
+use std::rc::Rc;
+
+use floem::{
+    event::EventListener,
+    keyboard::Modifiers,
+    reactive::{RwSignal, Scope},
+    style::CursorStyle,
+    views::{container, label, stack, Decorators},
+    View,
+};
+use lapce_core::{
+    command::{EditCommand, FocusCommand},
+    mode::Mode,
+};
+use lapce_rpc::{
+    core::{CoreResponse, CoreRpcHandler},
+    RequestId,
+};
+use lapce_xi_rope::Rope;
+
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    config::color::LapceColor,
+    editor::EditorData,
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::Editors,
+    text_input::TextInputBuilder,
+    window_tab::{CommonData, Focus, WindowTabData},
+};
+
+/// A prompt of ssh while connecting to a remote workspace, like for a
+/// password or a key's passphrase
+#[derive(Clone)]
+pub struct SshPromptData {
+    pub active: RwSignal<bool>,
+    pub host: RwSignal<String>,
+    pub prompt: RwSignal<String>,
+    /// Whether the answer is masked while it's typed
+    pub secret: RwSignal<bool>,
+    /// The request of the askpass server the answer is sent to
+    request_id: RwSignal<Option<RequestId>>,
+    pub editor: EditorData,
+    core_rpc: CoreRpcHandler,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for SshPromptData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::ListFocus | Condition::ModalFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Workbench(_) => {}
+            CommandKind::Scroll(_) => {}
+            CommandKind::Focus(cmd) => match cmd {
+                FocusCommand::ListSelect => self.confirm(),
+                FocusCommand::ModalClose => self.cancel(),
+                _ => {}
+            },
+            // Secrets aren't copied out of the input
+            CommandKind::Edit(
+                EditCommand::ClipboardCopy | EditCommand::ClipboardCut,
+            ) if self.secret.get_untracked() => {}
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.editor.run_command(command, count, mods);
+            }
+            CommandKind::MotionMode(_) => {}
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor.receive_char(c);
+    }
+}
+
+impl SshPromptData {
+    pub fn new(
+        cx: Scope,
+        editors: Editors,
+        common: Rc<CommonData>,
+        core_rpc: CoreRpcHandler,
+    ) -> Self {
+        let editor = editors.make_local(cx, common.clone());
+        Self {
+            active: cx.create_rw_signal(false),
+            host: cx.create_rw_signal(String::new()),
+            prompt: cx.create_rw_signal(String::new()),
+            secret: cx.create_rw_signal(true),
+            request_id: cx.create_rw_signal(None),
+            editor,
+            core_rpc,
+            common,
+        }
+    }
+
+    pub fn show(
+        &self,
+        request_id: RequestId,
+        host: String,
+        prompt: String,
+        secret: bool,
+    ) {
+        // ssh asks one thing at a time, so a prompt still open was given up
+        self.respond(None);
+        self.editor.doc().reload(Rope::from(""), true);
+        self.request_id.set(Some(request_id));
+        self.host.set(host);
+        self.prompt.set(prompt);
+        self.secret.set(secret);
+        self.active.set(true);
+        self.common.focus.set(Focus::SshPrompt);
+    }
+
+    fn confirm(&self) {
+        let answer = self
+            .editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        self.respond(Some(answer));
+        self.close();
+    }
+
+    fn cancel(&self) {
+        self.respond(None);
+        self.close();
+    }
+
+    fn respond(&self, answer: Option<String>) {
+        if let Some(id) = self.request_id.get_untracked() {
+            self.request_id.set(None);
+            self.core_rpc
+                .handle_response(id, Ok(CoreResponse::SshPrompt { answer }));
+        }
+    }
+
+    fn close(&self) {
+        // Don't keep the answer around
+        self.editor.doc().reload(Rope::from(""), true);
+        self.active.set(false);
+        if let Focus::SshPrompt = self.common.focus.get_untracked() {
+            self.common.focus.set(Focus::Workbench);
+        }
+    }
+}
+
+pub fn ssh_prompt(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let ssh_prompt = window_tab_data.ssh_prompt.clone();
+    let config = window_tab_data.common.config;
+    let active = ssh_prompt.active;
+    let host = ssh_prompt.host;
+    let prompt = ssh_prompt.prompt;
+    let secret = ssh_prompt.secret;
+
+    let button = move |text: &'static str, action: Rc<dyn Fn()>| {
+        label(move || text.to_string())
+            .on_click_stop(move |_| {
+                action();
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.margin_left(10.0)
+                    .padding_horiz(20.0)
+                    .line_height(1.6)
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+                    .active(|s| {
+                        s.background(
+                            config
+                                .color(LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND),
+                        )
+                    })
+            })
+    };
+
+    container(
+        container(
+            stack((
+                label(move || format!("SSH: {}", host.get())).style(move |s| {
+                    s.width_pct(100.0)
+                        .font_bold()
+                        .font_size((config.get().ui.font_size() + 1) as f32)
+                }),
+                label(move || prompt.get())
+                    .style(|s| s.width_pct(100.0).margin_top(10.0)),
+                container(
+                    TextInputBuilder::new()
+                        .is_focused(move || active.get())
+                        .build_editor(ssh_prompt.editor.clone())
+                        .secret(move || secret.get())
+                        .style(|s| s.width_pct(100.0)),
+                )
+                .style(move |s| {
+                    let config = config.get();
+                    s.width_pct(100.0)
+                        .margin_top(10.0)
+                        .border(1.0)
+                        .border_radius(6.0)
+                        .border_color(config.color(LapceColor::LAPCE_BORDER))
+                        .background(config.color(LapceColor::EDITOR_BACKGROUND))
+                }),
+                stack((
+                    button("Cancel", {
+                        let ssh_prompt = ssh_prompt.clone();
+                        Rc::new(move || ssh_prompt.cancel())
+                    }),
+                    button("OK", {
+                        let ssh_prompt = ssh_prompt.clone();
+                        Rc::new(move || ssh_prompt.confirm())
+                    }),
+                ))
+                .style(|s| s.width_pct(100.0).margin_top(20.0).justify_end()),
+            ))
+            .style(|s| s.flex_col().width(400.0)),
+        )
+        .on_event_stop(EventListener::PointerDown, |_| {})
+        .style(move |s| {
+            let config = config.get();
+            s.padding(20.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .background(config.color(LapceColor::PANEL_BACKGROUND))
+        }),
+    )
+    // The prompt has to be answered or cancelled before anything else
+    .on_event_stop(EventListener::PointerDown, |_| {})
+    .on_event_stop(EventListener::PointerMove, |_| {})
+    .style(move |s| {
+        s.absolute()
+            .size_pct(100.0, 100.0)
+            .items_center()
+            .justify_center()
+            .apply_if(!active.get(), |s| s.hide())
+            .background(
+                config
+                    .get()
+                    .color(LapceColor::LAPCE_DROPDOWN_SHADOW)
+                    .with_alpha_factor(0.5),
+            )
+    })
+    .debug_name("SSH Prompt")
+}
@@ -0,0 +1,85 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The time spent in each phase of starting up, while `--profile-startup` is
+/// timing it
+struct StartupProfile {
+    start: Instant,
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+static PROFILE: Mutex<Option<StartupProfile>> = Mutex::new(None);
+
+/// Time the phases of starting up, from `start`
+pub fn start(start: Instant) {
+    *PROFILE.lock().unwrap() = Some(StartupProfile {
+        start,
+        last: start,
+        phases: Vec::new(),
+    });
+}
+
+/// Mark the end of the phase `name`, if starting up is being timed
+pub fn phase(name: &'static str) {
+    if let Some(profile) = PROFILE.lock().unwrap().as_mut() {
+        let now = Instant::now();
+        profile.phases.push((name, now - profile.last));
+        profile.last = now;
+    }
+}
+
+/// Print the time spent in each phase, and stop timing
+pub fn report() {
+    let Some(profile) = PROFILE.lock().unwrap().take() else {
+        return;
+    };
+    eprintln!(
+        "{}",
+        format_report(&profile.phases, profile.last - profile.start)
+    );
+}
+
+fn format_report(phases: &[(&'static str, Duration)], total: Duration) -> String {
+    let width = phases.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut report = String::from("Startup profile:\n");
+    for (name, time) in phases {
+        report.push_str(&format!(
+            "  {name:<width$}  {:>8.1}ms\n",
+            time.as_secs_f64() * 1000.0
+        ));
+    }
+    report.push_str(&format!(
+        "  {:<width$}  {:>8.1}ms",
+        "total",
+        total.as_secs_f64() * 1000.0
+    ));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::format_report;
+
+    #[test]
+    fn test_format_report() {
+        let report = format_report(
+            &[
+                ("open database", Duration::from_micros(1500)),
+                ("create windows", Duration::from_millis(42)),
+            ],
+            Duration::from_micros(43500),
+        );
+        assert_eq!(
+            report,
+            "Startup profile:\n  \
+             open database        1.5ms\n  \
+             create windows      42.0ms\n  \
+             total               43.5ms"
+        );
+    }
+}
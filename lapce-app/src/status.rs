@@ -1,11 +1,16 @@
 use std::{
     rc::Rc,
     sync::{atomic::AtomicU64, Arc},
+    time::Duration,
 };
 
 use floem::{
+    action::{exec_after, show_context_menu},
     event::EventPropagation,
-    reactive::{create_memo, Memo, ReadSignal, RwSignal},
+    menu::{Menu, MenuItem},
+    reactive::{
+        create_effect, create_memo, create_rw_signal, Memo, ReadSignal, RwSignal,
+    },
     style::{AlignItems, CursorStyle, Display, FlexWrap},
     views::{dyn_stack, label, stack, svg, Decorators},
     View,
@@ -19,6 +24,7 @@
     command::LapceWorkbenchCommand,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
     editor::EditorData,
+    image_view::ImageViewData,
     listener::Listener,
     palette::kind::PaletteKind,
     panel::{kind::PanelKind, position::PanelContainerPosition},
@@ -36,6 +42,7 @@ pub fn status(
     let config = window_tab_data.common.config;
     let diagnostics = window_tab_data.main_split.diagnostics;
     let editor = window_tab_data.main_split.active_editor;
+    let image = window_tab_data.main_split.active_image;
     let panel = window_tab_data.panel.clone();
     let palette = window_tab_data.palette.clone();
     let diagnostic_count = create_memo(move |_| {
@@ -56,19 +63,34 @@ pub fn status(
     });
     let branch = source_control.branch;
     let file_diffs = source_control.file_diffs;
+    let ahead_behind = source_control.ahead_behind;
     let branch = move || {
+        let (ahead, behind) = ahead_behind.get();
         format!(
-            "{}{}",
+            "{}{}{}{}",
             branch.get(),
             if file_diffs.with(|diffs| diffs.is_empty()) {
                 ""
             } else {
                 "*"
+            },
+            if ahead > 0 {
+                format!(" ↑{ahead}")
+            } else {
+                String::new()
+            },
+            if behind > 0 {
+                format!(" ↓{behind}")
+            } else {
+                String::new()
             }
         )
     };
 
     let progresses = window_tab_data.progresses;
+    let unread = window_tab_data.notifications.unread;
+    let workspace_trust = window_tab_data.common.workspace_trust;
+    let has_local_settings = window_tab_data.workspace.has_local_settings();
     let mode = create_memo(move |_| window_tab_data.mode());
     let pointer_down = floem::reactive::create_rw_signal(false);
 
@@ -86,11 +108,12 @@ pub fn status(
             })
             .style(move |s| {
                 let config = config.get();
-                let display = if config.core.modal {
-                    Display::Flex
-                } else {
-                    Display::None
-                };
+                let display =
+                    if config.core.modal && StatusSegment::Mode.is_shown(&config) {
+                        Display::Flex
+                    } else {
+                        Display::None
+                    };
 
                 let (bg, fg) = match mode.get() {
                     Mode::Normal => (
@@ -122,6 +145,29 @@ pub fn status(
                     .align_items(Some(AlignItems::Center))
                     .selectable(false)
             }),
+            label(|| "Restricted Mode".to_string())
+                .on_click_stop(move |_| {
+                    workbench_command
+                        .send(LapceWorkbenchCommand::ManageWorkspaceTrust);
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    let restricted = match workspace_trust.get() {
+                        Some(trusted) => !trusted,
+                        None => has_local_settings,
+                    };
+                    s.apply_if(!restricted, |s| s.hide())
+                        .padding_horiz(10.0)
+                        .height_pct(100.0)
+                        .align_items(Some(AlignItems::Center))
+                        .color(config.color(LapceColor::STATUS_FOREGROUND))
+                        .selectable(false)
+                        .hover(|s| {
+                            s.cursor(CursorStyle::Pointer).background(
+                                config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            )
+                        })
+                }),
             stack((
                 svg(move || config.get().ui_svg(LapceIcons::SCM)).style(move |s| {
                     let config = config.get();
@@ -136,11 +182,15 @@ pub fn status(
                 }),
             ))
             .style(move |s| {
-                s.display(if branch().is_empty() {
-                    Display::None
-                } else {
-                    Display::Flex
-                })
+                s.display(
+                    if branch().is_empty()
+                        || !StatusSegment::Branch.is_shown(&config.get())
+                    {
+                        Display::None
+                    } else {
+                        Display::Flex
+                    },
+                )
                 .height_pct(100.0)
                 .padding_horiz(10.0)
                 .align_items(Some(AlignItems::Center))
@@ -211,19 +261,25 @@ pub fn status(
                     panel.show_panel(&PanelKind::Problem);
                 })
                 .style(move |s| {
-                    s.height_pct(100.0)
-                        .padding_horiz(10.0)
-                        .items_center()
-                        .hover(|s| {
-                            s.cursor(CursorStyle::Pointer).background(
-                                config
-                                    .get()
-                                    .color(LapceColor::PANEL_HOVERED_BACKGROUND),
-                            )
-                        })
+                    s.apply_if(
+                        !StatusSegment::Diagnostics.is_shown(&config.get()),
+                        |s| s.hide(),
+                    )
+                    .height_pct(100.0)
+                    .padding_horiz(10.0)
+                    .items_center()
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            config.get().color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
                 })
             },
-            progress_view(config, progresses),
+            progress_view(config, progresses).style(move |s| {
+                s.apply_if(!StatusSegment::Progress.is_shown(&config.get()), |s| {
+                    s.hide()
+                })
+            }),
         ))
         .style(|s| {
             s.height_pct(100.0)
@@ -318,67 +374,108 @@ pub fn status(
         }),
         stack({
             let palette_clone = palette.clone();
-            let cursor_info = status_text(config, editor, move || {
-                if let Some(editor) = editor.get() {
-                    let mut status = String::new();
-                    let cursor = editor.cursor().get();
-                    if let Some((line, column, character)) = editor
-                        .doc_signal()
-                        .get()
-                        .buffer
-                        .with(|buffer| cursor.get_line_col_char(buffer))
-                    {
-                        status = format!(
-                            "Ln {}, Col {}, Char {}",
-                            line + 1,
-                            column + 1,
-                            character,
-                        );
-                    }
-                    if let Some(selection) = cursor.get_selection() {
-                        let selection_range = selection.0.abs_diff(selection.1);
+            let cursor_info =
+                status_text(config, editor, StatusSegment::Cursor, move || {
+                    if let Some(editor) = editor.get() {
+                        let mut status = String::new();
+                        let cursor = editor.cursor().get();
+                        if let Some((line, column, character)) = editor
+                            .doc_signal()
+                            .get()
+                            .buffer
+                            .with(|buffer| cursor.get_line_col_char(buffer))
+                        {
+                            status = format!(
+                                "Ln {}, Col {}, Char {}",
+                                line + 1,
+                                column + 1,
+                                character,
+                            );
+                        }
+                        if let Some(selection) = cursor.get_selection() {
+                            let selection_range = selection.0.abs_diff(selection.1);
 
-                        if selection.0 != selection.1 {
+                            if selection.0 != selection.1 {
+                                status =
+                                    format!("{status} ({selection_range} selected)");
+                            }
+                        }
+                        let selection_count = cursor.get_selection_count();
+                        if selection_count > 1 {
                             status =
-                                format!("{status} ({selection_range} selected)");
+                                format!("{status} {selection_count} selections");
                         }
+                        return status;
                     }
-                    let selection_count = cursor.get_selection_count();
-                    if selection_count > 1 {
-                        status = format!("{status} {selection_count} selections");
+                    String::new()
+                })
+                .on_click_stop(move |_| {
+                    palette_clone.run(PaletteKind::Line);
+                });
+            let indentation_info =
+                status_text(config, editor, StatusSegment::Indentation, move || {
+                    if let Some(editor) = editor.get() {
+                        let doc = editor.doc_signal().get();
+                        let unit = doc.buffer.with(|b| b.indent_unit());
+                        if unit.starts_with('\t') {
+                            "Tabs".to_string()
+                        } else {
+                            format!("Spaces: {}", unit.len())
+                        }
+                    } else {
+                        String::new()
                     }
-                    return status;
-                }
-                String::new()
-            })
-            .on_click_stop(move |_| {
-                palette_clone.run(PaletteKind::Line);
-            });
+                })
+                .on_click_stop(move |_| {
+                    workbench_command.send(LapceWorkbenchCommand::OpenSettings);
+                });
             let palette_clone = palette.clone();
-            let line_ending_info = status_text(config, editor, move || {
-                if let Some(editor) = editor.get() {
-                    let doc = editor.doc_signal().get();
-                    doc.buffer.with(|b| b.line_ending()).as_str()
-                } else {
-                    ""
-                }
-            })
-            .on_click_stop(move |_| {
-                palette_clone.run(PaletteKind::LineEnding);
-            });
+            let line_ending_info =
+                status_text(config, editor, StatusSegment::LineEnding, move || {
+                    if let Some(editor) = editor.get() {
+                        let doc = editor.doc_signal().get();
+                        doc.buffer.with(|b| b.line_ending()).as_str()
+                    } else {
+                        ""
+                    }
+                })
+                .on_click_stop(move |_| {
+                    palette_clone.run(PaletteKind::LineEnding);
+                });
             let palette_clone = palette.clone();
-            let language_info = status_text(config, editor, move || {
-                if let Some(editor) = editor.get() {
-                    let doc = editor.doc_signal().get();
-                    doc.syntax().with(|s| s.language.name())
-                } else {
-                    "unknown"
-                }
-            })
-            .on_click_stop(move |_| {
-                palette_clone.run(PaletteKind::Language);
-            });
-            (cursor_info, line_ending_info, language_info)
+            let encoding_info =
+                status_text(config, editor, StatusSegment::Encoding, move || {
+                    if let Some(editor) = editor.get() {
+                        editor.doc_signal().get().encoding.get()
+                    } else {
+                        String::new()
+                    }
+                })
+                .on_click_stop(move |_| {
+                    palette_clone.run(PaletteKind::ReopenWithEncoding);
+                });
+            let palette_clone = palette.clone();
+            let language_info =
+                status_text(config, editor, StatusSegment::Language, move || {
+                    if let Some(editor) = editor.get() {
+                        let doc = editor.doc_signal().get();
+                        doc.syntax().with(|s| s.language.name())
+                    } else {
+                        "unknown"
+                    }
+                })
+                .on_click_stop(move |_| {
+                    palette_clone.run(PaletteKind::Language);
+                });
+            (
+                cursor_info,
+                image_size_view(config, image),
+                indentation_info,
+                line_ending_info,
+                encoding_info,
+                language_info,
+                notifications_view(config, unread, workbench_command),
+            )
         })
         .style(|s| {
             s.height_pct(100.0)
@@ -387,6 +484,9 @@ pub fn status(
                 .justify_end()
         }),
     ))
+    .on_secondary_click_stop(move |_| {
+        show_context_menu(segments_menu(&config.get_untracked()), None);
+    })
     .on_resize(move |rect| {
         let height = rect.height();
         if height != status_height.get_untracked() {
@@ -406,46 +506,154 @@ pub fn status(
     .debug_name("Status/Bottom Bar")
 }
 
+/// The size of the image in the active editor tab, and the zoom it's shown at
+fn image_size_view(
+    config: ReadSignal<Arc<LapceConfig>>,
+    image: Memo<Option<ImageViewData>>,
+) -> impl View {
+    label(move || {
+        image
+            .get()
+            .and_then(|image| {
+                let (width, height) = image.size.get()?;
+                Some(format!(
+                    "{width} × {height} ({:.0}%)",
+                    image.scale() * 100.0
+                ))
+            })
+            .unwrap_or_default()
+    })
+    .style(move |s| {
+        let config = config.get();
+        s.apply_if(
+            !StatusSegment::Cursor.is_shown(&config)
+                || image.with(|image| image.is_none()),
+            |s| s.hide(),
+        )
+        .height_full()
+        .padding_horiz(10.0)
+        .items_center()
+        .color(config.color(LapceColor::STATUS_FOREGROUND))
+        .selectable(false)
+    })
+}
+
+/// The bell opening the notifications, with how many of them are unread
+fn notifications_view(
+    config: ReadSignal<Arc<LapceConfig>>,
+    unread: RwSignal<usize>,
+    workbench_command: Listener<LapceWorkbenchCommand>,
+) -> impl View {
+    stack((
+        svg(move || config.get().ui_svg(LapceIcons::NOTIFICATION)).style(move |s| {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+            s.size(size, size)
+                .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+        }),
+        label(move || unread.get().to_string()).style(move |s| {
+            s.margin_left(5.0)
+                .color(config.get().color(LapceColor::STATUS_FOREGROUND))
+                .selectable(false)
+                .apply_if(unread.get() == 0, |s| s.hide())
+        }),
+    ))
+    .on_click_stop(move |_| {
+        workbench_command.send(LapceWorkbenchCommand::ToggleNotificationVisual);
+    })
+    .style(move |s| {
+        let config = config.get();
+        s.apply_if(!StatusSegment::Notifications.is_shown(&config), |s| {
+            s.hide()
+        })
+        .height_pct(100.0)
+        .padding_horiz(10.0)
+        .items_center()
+        .hover(|s| {
+            s.cursor(CursorStyle::Pointer)
+                .background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+        })
+    })
+}
+
 fn progress_view(
     config: ReadSignal<Arc<LapceConfig>>,
     progresses: RwSignal<IndexMap<ProgressToken, WorkProgress>>,
 ) -> impl View {
+    let frame = create_rw_signal(0);
+    let spinning = create_rw_signal(false);
+    create_effect(move |_| {
+        if progresses.with(|p| !p.is_empty()) && !spinning.get_untracked() {
+            spinning.set(true);
+            spin(frame, spinning, progresses);
+        }
+    });
+
     let id = AtomicU64::new(0);
     dyn_stack(
         move || progresses.get(),
         move |_| id.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
         move |(_, p)| {
-            let progress = match p.message {
-                Some(message) if !message.is_empty() => {
-                    format!("{}: {}", p.title, message)
+            let progress = match (p.message, p.percentage) {
+                (Some(message), _) if !message.is_empty() => {
+                    format!("{} {}", p.title, message)
                 }
+                (_, Some(percentage)) => format!("{} {percentage}%", p.title),
                 _ => p.title,
             };
-            label(move || progress.clone()).style(move |s| {
-                s.height_pct(100.0)
-                    .min_width(0.0)
-                    .margin_left(10.0)
-                    .text_ellipsis()
-                    .selectable(false)
-                    .items_center()
-                    .color(config.get().color(LapceColor::STATUS_FOREGROUND))
-            })
+            label(move || format!("{} {progress}", SPINNER[frame.get()])).style(
+                move |s| {
+                    s.height_pct(100.0)
+                        .min_width(0.0)
+                        .margin_left(10.0)
+                        .text_ellipsis()
+                        .selectable(false)
+                        .items_center()
+                        .color(config.get().color(LapceColor::STATUS_FOREGROUND))
+                },
+            )
         },
     )
     .style(move |s| s.flex_wrap(FlexWrap::Wrap).height_pct(100.0).min_width(0.0))
 }
 
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Advance the spinner in front of the progresses until they are all done
+fn spin(
+    frame: RwSignal<usize>,
+    spinning: RwSignal<bool>,
+    progresses: RwSignal<IndexMap<ProgressToken, WorkProgress>>,
+) {
+    exec_after(Duration::from_millis(100), move |_| {
+        if progresses.with_untracked(|p| p.is_empty()) {
+            spinning.set(false);
+            return;
+        }
+        frame.update(|frame| *frame = (*frame + 1) % SPINNER.len());
+        spin(frame, spinning, progresses);
+    });
+}
+
 fn status_text<S: std::fmt::Display + 'static>(
     config: ReadSignal<Arc<LapceConfig>>,
     editor: Memo<Option<EditorData>>,
+    segment: StatusSegment,
     text: impl Fn() -> S + 'static,
 ) -> impl View {
     label(text).style(move |s| {
         let config = config.get();
-        let display = if editor
-            .get()
-            .map(|editor| editor.doc_signal().get().content.with(|c| c.is_file()))
-            .unwrap_or(false)
+        let display = if segment.is_shown(&config)
+            && editor
+                .get()
+                .map(|editor| {
+                    editor.doc_signal().get().content.with(|c| {
+                        // An untitled file isn't read with an encoding
+                        c.is_file()
+                            || (c.is_scratch() && segment != StatusSegment::Encoding)
+                    })
+                })
+                .unwrap_or(false)
         {
             Display::Flex
         } else {
@@ -464,3 +672,101 @@ fn status_text<S: std::fmt::Display + 'static>(
             .selectable(false)
     })
 }
+
+/// The parts of the status bar, each of which a `ui.status-bar-*` setting shows
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StatusSegment {
+    Mode,
+    Branch,
+    Diagnostics,
+    Progress,
+    Cursor,
+    Indentation,
+    LineEnding,
+    Encoding,
+    Language,
+    Notifications,
+}
+
+impl StatusSegment {
+    const ALL: [StatusSegment; 10] = [
+        StatusSegment::Mode,
+        StatusSegment::Branch,
+        StatusSegment::Diagnostics,
+        StatusSegment::Progress,
+        StatusSegment::Cursor,
+        StatusSegment::Indentation,
+        StatusSegment::LineEnding,
+        StatusSegment::Encoding,
+        StatusSegment::Language,
+        StatusSegment::Notifications,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            StatusSegment::Mode => "Mode",
+            StatusSegment::Branch => "Branch",
+            StatusSegment::Diagnostics => "Problems",
+            StatusSegment::Progress => "Progress",
+            StatusSegment::Cursor => "Cursor Position",
+            StatusSegment::Indentation => "Indentation",
+            StatusSegment::LineEnding => "Line Ending",
+            StatusSegment::Encoding => "Encoding",
+            StatusSegment::Language => "Language",
+            StatusSegment::Notifications => "Notifications",
+        }
+    }
+
+    /// The key of the setting in the `ui` section
+    fn key(&self) -> &'static str {
+        match self {
+            StatusSegment::Mode => "status-bar-mode",
+            StatusSegment::Branch => "status-bar-branch",
+            StatusSegment::Diagnostics => "status-bar-diagnostics",
+            StatusSegment::Progress => "status-bar-progress",
+            StatusSegment::Cursor => "status-bar-cursor",
+            StatusSegment::Indentation => "status-bar-indentation",
+            StatusSegment::LineEnding => "status-bar-line-ending",
+            StatusSegment::Encoding => "status-bar-encoding",
+            StatusSegment::Language => "status-bar-language",
+            StatusSegment::Notifications => "status-bar-notifications",
+        }
+    }
+
+    fn is_shown(&self, config: &LapceConfig) -> bool {
+        let ui = &config.ui;
+        match self {
+            StatusSegment::Mode => ui.status_bar_mode,
+            StatusSegment::Branch => ui.status_bar_branch,
+            StatusSegment::Diagnostics => ui.status_bar_diagnostics,
+            StatusSegment::Progress => ui.status_bar_progress,
+            StatusSegment::Cursor => ui.status_bar_cursor,
+            StatusSegment::Indentation => ui.status_bar_indentation,
+            StatusSegment::LineEnding => ui.status_bar_line_ending,
+            StatusSegment::Encoding => ui.status_bar_encoding,
+            StatusSegment::Language => ui.status_bar_language,
+            StatusSegment::Notifications => ui.status_bar_notifications,
+        }
+    }
+}
+
+/// The menu of the status bar, which shows or hides each of its parts
+fn segments_menu(config: &LapceConfig) -> Menu {
+    let mut menu = Menu::new("");
+    for segment in StatusSegment::ALL {
+        let shown = segment.is_shown(config);
+        let text = if shown {
+            format!("Hide {}", segment.name())
+        } else {
+            format!("Show {}", segment.name())
+        };
+        menu = menu.entry(MenuItem::new(text).action(move || {
+            LapceConfig::update_file(
+                "ui",
+                segment.key(),
+                toml_edit::Value::from(!shown),
+            );
+        }));
+    }
+    menu
+}
@@ -2,8 +2,9 @@
 
 use alacritty_terminal::{
     grid::{Dimensions, Scroll},
+    index::{Boundary, Direction, Side},
     selection::{Selection, SelectionType},
-    term::{test::TermSize, TermMode},
+    term::{search::RegexSearch, test::TermSize, TermMode},
     vi_mode::ViMotion,
     Term,
 };
@@ -44,6 +45,9 @@ pub struct TerminalData {
     pub term_id: TermId,
     pub workspace: Arc<LapceWorkspace>,
     pub title: RwSignal<String>,
+    /// The name given by the user, shown instead of the title set by the shell
+    pub name: RwSignal<Option<String>>,
+    pub profile: Option<TerminalProfile>,
     pub launch_error: RwSignal<Option<String>>,
     pub mode: RwSignal<Mode>,
     pub visual_mode: RwSignal<VisualMode>,
@@ -260,27 +264,20 @@ fn run_command(
                         },
                     );
                 }
+                FocusCommand::Search => {
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::OpenTerminalFind);
+                }
                 FocusCommand::SearchForward => {
-                    // if let Some(search_string) = self.find.search_string.as_ref() {
-                    //     let mut raw = self.terminal.raw.lock();
-                    //     let term = &mut raw.term;
-                    //     self.terminal.search_next(
-                    //         term,
-                    //         search_string,
-                    //         Direction::Right,
-                    //     );
-                    // }
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::TerminalFindNext { forward: true });
                 }
                 FocusCommand::SearchBackward => {
-                    // if let Some(search_string) = self.find.search_string.as_ref() {
-                    //     let mut raw = self.terminal.raw.lock();
-                    //     let term = &mut raw.term;
-                    //     self.terminal.search_next(
-                    //         term,
-                    //         search_string,
-                    //         Direction::Left,
-                    //     );
-                    // }
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::TerminalFindNext { forward: false });
                 }
                 _ => return CommandExecuted::No,
             },
@@ -329,13 +326,14 @@ pub fn new_run_debug(
             cx.create_rw_signal(String::from("Default"))
         };
 
+        let name = cx.create_rw_signal(None);
         let launch_error = cx.create_rw_signal(None);
 
         let raw = Self::new_raw_terminal(
             &workspace,
             term_id,
             run_debug.as_ref(),
-            profile,
+            profile.clone(),
             common.clone(),
             launch_error,
         );
@@ -351,6 +349,8 @@ pub fn new_run_debug(
             workspace,
             raw,
             title,
+            name,
+            profile,
             run_debug,
             mode,
             visual_mode,
@@ -371,6 +371,7 @@ fn new_raw_terminal(
             term_id,
             common.proxy.clone(),
             common.term_notification_tx.clone(),
+            common.config.get_untracked().terminal.scrollback,
         )));
 
         let mut profile = profile.unwrap_or_default();
@@ -703,6 +704,65 @@ fn start_selection(
         term.selection = Some(Selection::new(ty, point, side));
     }
 
+    pub fn clear_selection(&self) {
+        self.raw.get_untracked().write().term.selection = None;
+        self.common.view_id.get_untracked().request_paint();
+    }
+
+    /// Select the next match of `pattern` from the current selection, or from the
+    /// cursor when there is none, and scroll it into view
+    pub fn search(&self, pattern: &str, direction: Direction) -> bool {
+        let Ok(mut regex) = RegexSearch::new(&regex::escape(pattern)) else {
+            return false;
+        };
+
+        let raw = self.raw.get_untracked();
+        let mut raw = raw.write();
+        let term = &mut raw.term;
+        let current = term.selection.as_ref().and_then(|s| s.to_range(term));
+        let (origin, side) = match direction {
+            Direction::Right => (
+                current
+                    .map(|range| range.end.add(term, Boundary::None, 1))
+                    .unwrap_or(term.grid().cursor.point),
+                Side::Left,
+            ),
+            Direction::Left => (
+                current
+                    .map(|range| range.start.sub(term, Boundary::None, 1))
+                    .unwrap_or(term.grid().cursor.point),
+                Side::Right,
+            ),
+        };
+
+        let found = term.search_next(&mut regex, origin, direction, side, None);
+        term.selection = found.as_ref().map(|m| {
+            let mut selection =
+                Selection::new(SelectionType::Simple, *m.start(), Side::Left);
+            selection.update(*m.end(), Side::Right);
+            selection
+        });
+        if let Some(m) = found.as_ref() {
+            term.scroll_to_point(*m.start());
+        }
+        self.common.view_id.get_untracked().request_paint();
+        found.is_some()
+    }
+
+    /// Write `text` to the terminal as if it was pasted, then press enter
+    pub fn run_text(&self, text: &str) {
+        let raw = self.raw.get_untracked();
+        let bracketed_paste =
+            raw.read().term.mode().contains(TermMode::BRACKETED_PASTE);
+        let content = if bracketed_paste {
+            format!("\x1b[200~{}\x1b[201~\r", text.replace('\x1b', ""))
+        } else {
+            format!("{text}\r")
+        };
+        self.common.proxy.terminal_write(self.term_id, content);
+        raw.write().term.scroll_display(Scroll::Bottom);
+    }
+
     pub fn new_process(&self, run_debug: Option<RunDebugProcess>) {
         let (width, height) = {
             let raw = self.raw.get_untracked();
@@ -0,0 +1,154 @@
+use alacritty_terminal::index::Direction;
+use floem::{
+    keyboard::Modifiers,
+    reactive::{Memo, RwSignal, Scope},
+};
+use lapce_core::{command::FocusCommand, mode::Mode};
+
+use super::{data::TerminalData, panel::TerminalTabInfo};
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    editor::EditorData,
+    keypress::{condition::Condition, KeyPressFocus},
+};
+
+/// The find bar of the terminal panel, searching the scrollback of the active
+/// terminal
+#[derive(Clone)]
+pub struct TerminalFindData {
+    pub editor: EditorData,
+    pub visual: RwSignal<bool>,
+    /// Whether the keyboard input goes to the find bar instead of the terminal
+    pub focus: RwSignal<bool>,
+    /// The text being searched for, `None` when the find bar is closed or empty
+    pub pattern: Memo<Option<String>>,
+    tab_info: RwSignal<TerminalTabInfo>,
+}
+
+impl KeyPressFocus for TerminalFindData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(
+            condition,
+            Condition::PanelFocus
+                | Condition::InputFocus
+                | Condition::SearchFocus
+                | Condition::SearchActive
+        )
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                return self.editor.run_command(command, count, mods);
+            }
+            CommandKind::Focus(cmd) => match cmd {
+                FocusCommand::SearchForward => {
+                    self.search(Direction::Right);
+                }
+                FocusCommand::SearchBackward => {
+                    self.search(Direction::Left);
+                }
+                FocusCommand::ClearSearch => {
+                    self.close();
+                }
+                FocusCommand::Search => {}
+                _ => return CommandExecuted::No,
+            },
+            _ => return CommandExecuted::No,
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor.receive_char(c);
+    }
+}
+
+impl TerminalFindData {
+    pub fn new(
+        cx: Scope,
+        editor: EditorData,
+        tab_info: RwSignal<TerminalTabInfo>,
+    ) -> Self {
+        let visual = cx.create_rw_signal(false);
+        let focus = cx.create_rw_signal(false);
+        let buffer = editor.doc().buffer;
+        let pattern = cx.create_memo(move |_| {
+            if !visual.get() {
+                return None;
+            }
+            let pattern = buffer.with(|buffer| buffer.to_string());
+            (!pattern.is_empty()).then_some(pattern)
+        });
+
+        let find = Self {
+            editor,
+            visual,
+            focus,
+            pattern,
+            tab_info,
+        };
+
+        {
+            let find = find.clone();
+            // Jump to the latest match while typing
+            cx.create_effect(move |_| {
+                let pattern = find.pattern.get();
+                if let Some(terminal) = find.active_terminal() {
+                    terminal.clear_selection();
+                    if let Some(pattern) = pattern {
+                        terminal.search(&pattern, Direction::Left);
+                    }
+                }
+            });
+        }
+
+        find
+    }
+
+    /// Show the find bar and move the keyboard input to it
+    pub fn open(&self) {
+        self.visual.set(true);
+        self.focus.set(true);
+    }
+
+    pub fn close(&self) {
+        self.visual.set(false);
+        self.focus.set(false);
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.visual.get_untracked() && self.focus.get_untracked()
+    }
+
+    /// Select the next match in the active terminal
+    pub fn search(&self, direction: Direction) {
+        let Some(pattern) = self.pattern.get_untracked() else {
+            return;
+        };
+        if let Some(terminal) = self.active_terminal() {
+            terminal.search(&pattern, direction);
+        }
+    }
+
+    fn active_terminal(&self) -> Option<TerminalData> {
+        let tab = self.tab_info.with_untracked(|info| {
+            info.tabs
+                .get(info.active)
+                .or_else(|| info.tabs.last())
+                .map(|(_, tab)| tab.clone())
+        })?;
+        tab.active_terminal(false)
+    }
+}
@@ -1,5 +1,6 @@
 pub mod data;
 pub mod event;
+pub mod find;
 pub mod panel;
 pub mod raw;
 pub mod tab;
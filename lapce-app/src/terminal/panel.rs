@@ -7,18 +7,19 @@
 use lapce_core::mode::Mode;
 use lapce_rpc::{
     dap_types::{
-        self, DapId, RunDebugConfig, StackFrame, Stopped, ThreadId, Variable,
+        self, DapId, Output, RunDebugConfig, StackFrame, Stopped, ThreadId, Variable,
     },
     proxy::ProxyResponse,
     terminal::{TermId, TerminalProfile},
 };
 
-use super::{data::TerminalData, tab::TerminalTabData};
+use super::{data::TerminalData, find::TerminalFindData, tab::TerminalTabData};
 use crate::{
     debug::{
-        DapData, DapVariable, RunDebugData, RunDebugMode, RunDebugProcess,
-        ScopeOrVar,
+        ConsoleLineKind, DapData, DapVariable, InlineDebugValues, RunDebugData,
+        RunDebugMode, RunDebugProcess, ScopeOrVar,
     },
+    editor::EditorData,
     id::TerminalTabId,
     keypress::{EventRef, KeyPressData, KeyPressFocus, KeyPressHandle},
     panel::kind::PanelKind,
@@ -38,6 +39,10 @@ pub struct TerminalPanelData {
     pub tab_info: RwSignal<TerminalTabInfo>,
     pub debug: RunDebugData,
     pub breakline: Memo<Option<(usize, PathBuf)>>,
+    /// The values of the variables of the active debug session, shown inline
+    /// while it's stopped
+    pub inline_debug_values: Memo<Option<InlineDebugValues>>,
+    pub find: TerminalFindData,
     pub common: Rc<CommonData>,
 }
 
@@ -45,6 +50,7 @@ impl TerminalPanelData {
     pub fn new(
         workspace: Arc<LapceWorkspace>,
         profile: Option<TerminalProfile>,
+        find_editor: EditorData,
         common: Rc<CommonData>,
     ) -> Self {
         let terminal_tab =
@@ -101,12 +107,28 @@ pub fn new(
             })
         };
 
+        let inline_debug_values = {
+            let active_term = debug.active_term;
+            let daps = debug.daps;
+            cx.create_memo(move |_| {
+                let active_term = active_term.get()?;
+                let dap = daps.with(|daps| {
+                    daps.values().find(|d| d.term_id == active_term).cloned()
+                })?;
+                dap.inline_values.get()
+            })
+        };
+
+        let find = TerminalFindData::new(cx, find_editor, tab_info);
+
         Self {
             cx,
             workspace,
             tab_info,
             debug,
             breakline,
+            inline_debug_values,
+            find,
             common,
         }
     }
@@ -140,6 +162,10 @@ pub fn key_down<'a>(
             self.new_tab(None);
         }
 
+        if self.find.is_focused() {
+            return Some(keypress.key_down(event, &self.find));
+        }
+
         let tab = self.active_tab(false);
         let terminal = tab.and_then(|tab| tab.active_terminal(false));
         if let Some(terminal) = terminal {
@@ -328,6 +354,42 @@ pub fn split_exchange(&self, term_id: TermId) {
         }
     }
 
+    /// Name the terminal, or go back to the shell's title if `name` is empty
+    pub fn rename_terminal(&self, term_id: &TermId, name: &str) {
+        if let Some(terminal) = self.get_terminal(term_id) {
+            let name = name.trim();
+            terminal
+                .name
+                .set((!name.is_empty()).then(|| name.to_string()));
+        }
+    }
+
+    /// Kill the process of the terminal and start a new one in its place
+    pub fn restart_terminal(&self, term_id: TermId) -> Option<()> {
+        let (_, terminal_tab, index, terminal) =
+            self.get_terminal_in_tab(&term_id)?;
+        if terminal.run_debug.with_untracked(|r| r.is_some()) {
+            return self.restart_run_debug(term_id);
+        }
+
+        self.common.proxy.terminal_close(term_id);
+        let new_terminal = TerminalData::new(
+            terminal_tab.scope,
+            self.workspace.clone(),
+            terminal.profile.clone(),
+            self.common.clone(),
+        );
+        new_terminal.name.set(terminal.name.get_untracked());
+        let new_term_id = new_terminal.term_id;
+        terminal_tab.terminals.update(|terminals| {
+            terminals[index] =
+                (new_terminal.scope.create_rw_signal(0), new_terminal);
+        });
+        self.focus_terminal(new_term_id);
+
+        Some(())
+    }
+
     pub fn close_terminal(&self, term_id: &TermId) {
         if let Some((_, tab, index, _)) = self.get_terminal_in_tab(term_id) {
             let active = tab.active.get_untracked();
@@ -597,6 +659,21 @@ pub fn dap_continued(&self, dap_id: &DapId) {
         }
     }
 
+    pub fn dap_output(&self, dap_id: &DapId, output: &Output) {
+        let dap = self
+            .debug
+            .daps
+            .with_untracked(|daps| daps.get(dap_id).cloned());
+        if let Some(dap) = dap {
+            let kind = if output.category.as_deref() == Some("stderr") {
+                ConsoleLineKind::Error
+            } else {
+                ConsoleLineKind::Output
+            };
+            dap.console_push(kind, &output.output);
+        }
+    }
+
     pub fn dap_stopped(
         &self,
         dap_id: &DapId,
@@ -610,6 +687,7 @@ pub fn dap_stopped(
             .with_untracked(|daps| daps.get(dap_id).cloned());
         if let Some(dap) = dap {
             dap.stopped(self.cx, stopped, stack_frames, variables);
+            dap.evaluate_watches(&self.debug.watch_expressions.get_untracked());
         }
         floem::action::focus_window();
     }
@@ -716,6 +794,8 @@ pub fn get_dap(&self, term_id: TermId, tracked: bool) -> Option<DapData> {
 
     pub fn dap_frame_scopes(&self, dap_id: DapId, frame_id: usize) {
         if let Some(dap) = self.debug.daps.get_untracked().get(&dap_id) {
+            dap.frame_id.set(Some(frame_id));
+            dap.evaluate_watches(&self.debug.watch_expressions.get_untracked());
             let variables = dap.variables;
             let send = create_ext_action(self.common.scope, move |result| {
                 if let Ok(ProxyResponse::DapGetScopesResponse { scopes }) = result {
@@ -48,8 +48,12 @@ pub fn new(
         term_id: TermId,
         proxy: ProxyRpcHandler,
         term_notification_tx: Sender<TermNotification>,
+        scrollback: usize,
     ) -> Self {
-        let config = alacritty_terminal::term::Config::default();
+        let config = alacritty_terminal::term::Config {
+            scrolling_history: scrollback,
+            ..Default::default()
+        };
         let event_proxy = EventProxy {
             term_id,
             proxy,
@@ -1,9 +1,10 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::SystemTime;
 
 use alacritty_terminal::index::Side;
 use alacritty_terminal::selection::{Selection, SelectionType};
-use alacritty_terminal::term::search::Match;
+use alacritty_terminal::term::search::{Match, RegexSearch};
 use alacritty_terminal::term::RenderableContent;
 use alacritty_terminal::{
     grid::Dimensions,
@@ -11,6 +12,7 @@
 };
 use floem::context::{EventCx, PaintCx};
 use floem::event::Event;
+use floem::keyboard::Modifiers;
 use floem::peniko::Color;
 use floem::pointer::PointerInputEvent;
 use floem::views::editor::core::register::Clipboard;
@@ -29,7 +31,10 @@
 use parking_lot::RwLock;
 use unicode_width::UnicodeWidthChar;
 
-use super::{panel::TerminalPanelData, raw::RawTerminal};
+use super::{
+    panel::TerminalPanelData,
+    raw::{visible_regex_match_iter, RawTerminal},
+};
 use crate::command::InternalCommand;
 use crate::editor::location::{EditorLocation, EditorPosition};
 use crate::listener::Listener;
@@ -44,10 +49,15 @@
 /// Threshold used for double_click/triple_click.
 const CLICK_THRESHOLD: u128 = 400;
 
+/// Matches urls and file locations like `src/main.rs:12:5` in the terminal output
+const LINK_REGEX: &str =
+    r#"(https?|file)://[^\s<>"'`]+|[\w./~\\-]*[\w-]+\.\w+:\d+(:\d+)?"#;
+
 enum TerminalViewState {
     Config,
     Focus(bool),
     Raw(Arc<RwLock<RawTerminal>>),
+    Find(Option<String>),
 }
 
 struct TerminalLineContent<'a> {
@@ -71,7 +81,11 @@ pub struct TerminalView {
     launch_error: RwSignal<Option<String>>,
     internal_command: Listener<InternalCommand>,
     workspace: Arc<LapceWorkspace>,
-    hyper_matches: Vec<Match>,
+    link_regex: Option<RegexSearch>,
+    /// The link under the pointer while the link modifier is held
+    hovered_link: Option<Match>,
+    /// The pattern of the find bar, whose matches are highlighted
+    find_regex: Option<RegexSearch>,
     previous_mouse_action: MouseAction,
     current_mouse_action: MouseAction,
 }
@@ -105,6 +119,12 @@ pub fn terminal_view(
         id.update_state(TerminalViewState::Config);
     });
 
+    let find_pattern = terminal_panel_data.find.pattern;
+    create_effect(move |_| {
+        let pattern = find_pattern.get();
+        id.update_state(TerminalViewState::Find(pattern));
+    });
+
     let proxy = terminal_panel_data.common.proxy.clone();
 
     create_effect(move |last| {
@@ -139,7 +159,9 @@ pub fn terminal_view(
         launch_error,
         internal_command,
         workspace,
-        hyper_matches: vec![],
+        link_regex: RegexSearch::new(LINK_REGEX).ok(),
+        hovered_link: None,
+        find_regex: None,
         previous_mouse_action: Default::default(),
         current_mouse_action: Default::default(),
     }
@@ -168,27 +190,50 @@ fn terminal_size(&self) -> (usize, usize) {
         (width.max(1), height.max(1))
     }
 
-    fn click(&self, pos: Point) -> Option<()> {
-        let raw_origin = self.raw.read();
-        let position = self.get_terminal_point(pos);
-        let hy = self.hyper_matches.iter().find(|x| x.contains(&position))?;
-        let hyperlink = raw_origin.term.bounds_to_string(*hy.start(), *hy.end());
-        let content: Vec<&str> = hyperlink.split(':').collect();
-        let (file, line_str, col_str) =
-            (content.first()?, content.get(1)?, content.get(2)?);
-        let (line, col) =
-            (line_str.parse::<u32>().ok()?, col_str.parse::<u32>().ok()?);
-        let parent_path = self.workspace.path.as_ref()?;
+    /// The url or file location link at `pos`
+    fn link_at(&mut self, pos: Point) -> Option<Match> {
+        let mut position = self.get_terminal_point(pos);
+        let raw = self.raw.read();
+        // The point is in the viewport, while matches are in the scrolled grid
+        position.line.0 -= raw.term.grid().display_offset() as i32;
+        let regex = self.link_regex.as_mut()?;
+        visible_regex_match_iter(&raw.term, regex).find(|m| m.contains(&position))
+    }
+
+    /// Open the link at `pos`, the url in the browser or the file location in an
+    /// editor
+    fn click(&mut self, pos: Point) -> Option<()> {
+        let link = self.link_at(pos)?;
+        let link = self
+            .raw
+            .read()
+            .term
+            .bounds_to_string(*link.start(), *link.end());
+        let link = link.trim_end_matches(['.', ',', ';', ')', ']', '\'', '"']);
+
+        if link.contains("://") {
+            self.internal_command.send(InternalCommand::OpenWebUri {
+                uri: link.to_string(),
+            });
+            return Some(());
+        }
+
+        let (path, position) = parse_file_link(link)?;
+        let path = if path.is_absolute() {
+            path
+        } else {
+            self.workspace.path.as_ref()?.join(path)
+        };
         self.internal_command.send(InternalCommand::JumpToLocation {
             location: EditorLocation {
-                path: parent_path.join(file),
-                position: Some(EditorPosition::Position(Position::new(line, col))),
+                path,
+                position: Some(EditorPosition::Position(position)),
                 scroll_offset: None,
                 ignore_unconfirmed: false,
                 same_editor_tab: false,
             },
         });
-        None
+        Some(())
     }
 
     fn update_mouse_action_by_down(&mut self, mouse: &PointerInputEvent) {
@@ -381,7 +426,11 @@ fn paint_content(
                 }
             }
 
-            if self.hyper_matches.iter().any(|x| x.contains(&point)) {
+            if self
+                .hovered_link
+                .as_ref()
+                .is_some_and(|link| link.contains(&point))
+            {
                 let mut extend = false;
                 if let Some((_, end, _, _)) = line_content.underline.last_mut() {
                     if *end == point.column.0 {
@@ -499,13 +548,26 @@ fn event_before_children(
             Event::PointerDown(e) => {
                 self.update_mouse_action_by_down(e);
             }
+            Event::PointerMove(e) => {
+                let link = if is_link_modifier(&e.modifiers) {
+                    self.link_at(e.pos)
+                } else {
+                    None
+                };
+                if link != self.hovered_link {
+                    self.hovered_link = link;
+                    _cx.app_state_mut().request_paint(self.id);
+                }
+            }
             Event::PointerUp(e) => {
                 self.update_mouse_action_by_up(e);
                 let mut clear_selection = false;
                 match self.current_mouse_action {
                     MouseAction::LeftOnce { pos, .. } => {
                         clear_selection = true;
-                        if self.click(pos).is_some() {
+                        if is_link_modifier(&e.modifiers)
+                            && self.click(pos).is_some()
+                        {
                             return EventPropagation::Stop;
                         }
                     }
@@ -587,6 +649,11 @@ fn update(
                 TerminalViewState::Raw(raw) => {
                     self.raw = raw;
                 }
+                TerminalViewState::Find(pattern) => {
+                    self.find_regex = pattern.and_then(|pattern| {
+                        RegexSearch::new(&regex::escape(&pattern)).ok()
+                    });
+                }
             }
             cx.app_state_mut().request_paint(self.id);
         }
@@ -652,9 +719,6 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
         let term = &raw.term;
         let content = term.renderable_content();
 
-        // let mut search = RegexSearch::new("[\\w\\\\?]+\\.rs:\\d+:\\d+").unwrap();
-        // self.hyper_matches = visible_regex_match_iter(term, &mut search).collect();
-
         if let Some(selection) = content.selection.as_ref() {
             let start_line = selection.start.line.0 + content.display_offset as i32;
             let start_line = if start_line < 0 {
@@ -701,66 +765,69 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
         }
 
         self.paint_content(cx, content, line_height, char_size, &config);
-        // if data.find.visual {
-        //     if let Some(search_string) = data.find.search_string.as_ref() {
-        //         if let Ok(dfas) = RegexSearch::new(&regex::escape(search_string)) {
-        //             let mut start = alacritty_terminal::index::Point::new(
-        //                 alacritty_terminal::index::Line(
-        //                     -(content.display_offset as i32),
-        //                 ),
-        //                 alacritty_terminal::index::Column(0),
-        //             );
-        //             let end_line = (start.line + term.screen_lines())
-        //                 .min(term.bottommost_line());
-        //             let mut max_lines = (end_line.0 - start.line.0) as usize;
-
-        //             while let Some(m) = term.search_next(
-        //                 &dfas,
-        //                 start,
-        //                 Direction::Right,
-        //                 Side::Left,
-        //                 Some(max_lines),
-        //             ) {
-        //                 let match_start = m.start();
-        //                 if match_start.line.0 < start.line.0
-        //                     || (match_start.line.0 == start.line.0
-        //                         && match_start.column.0 < start.column.0)
-        //                 {
-        //                     break;
-        //                 }
-        //                 let x = match_start.column.0 as f64 * char_width;
-        //                 let y = (match_start.line.0 as f64
-        //                     + content.display_offset as f64)
-        //                     * line_height;
-        //                 let rect = Rect::ZERO
-        //                     .with_origin(Point::new(x, y))
-        //                     .with_size(Size::new(
-        //                         (m.end().column.0 - m.start().column.0
-        //                             + term.grid()[*m.end()].c.width().unwrap_or(1))
-        //                             as f64
-        //                             * char_width,
-        //                         line_height,
-        //                     ));
-        //                 cx.stroke(
-        //                     &rect,
-        //                     config.get_color(LapceColor::TERMINAL_FOREGROUND),
-        //                     1.0,
-        //                 );
-        //                 start = *m.end();
-        //                 if start.column.0 < term.last_column() {
-        //                     start.column.0 += 1;
-        //                 } else if start.line.0 < term.bottommost_line() {
-        //                     start.column.0 = 0;
-        //                     start.line.0 += 1;
-        //                 }
-        //                 max_lines = (end_line.0 - start.line.0) as usize;
-        //             }
-        //         }
-        //     }
-        // }
+
+        if let Some(regex) = self.find_regex.as_mut() {
+            let display_offset = term.grid().display_offset() as i32;
+            for m in visible_regex_match_iter(term, regex) {
+                let (start, end) = (m.start(), m.end());
+                for line in start.line.0..=end.line.0 {
+                    let left_col = if line == start.line.0 {
+                        start.column.0
+                    } else {
+                        0
+                    };
+                    let right_col = if line == end.line.0 {
+                        end.column.0 + 1
+                    } else {
+                        term.columns()
+                    };
+                    let y0 = (line + display_offset) as f64 * line_height;
+                    cx.stroke(
+                        &Rect::new(
+                            left_col as f64 * char_width,
+                            y0,
+                            right_col as f64 * char_width,
+                            y0 + line_height,
+                        ),
+                        config.color(LapceColor::TERMINAL_FOREGROUND),
+                        1.0,
+                    );
+                }
+            }
+        }
     }
 }
 
+/// Links are opened by clicking with cmd on macOS and ctrl elsewhere
+fn is_link_modifier(modifiers: &Modifiers) -> bool {
+    if cfg!(target_os = "macos") {
+        modifiers.meta()
+    } else {
+        modifiers.control()
+    }
+}
+
+/// Parse a file location like `src/main.rs:12:5` or `src/main.rs:12`, with the
+/// 1-based line and column of compiler output
+fn parse_file_link(link: &str) -> Option<(PathBuf, Position)> {
+    let (rest, last) = link.rsplit_once(':')?;
+    let last = last.parse::<u32>().ok()?;
+    let (path, line, column) = match rest
+        .rsplit_once(':')
+        .and_then(|(path, line)| Some((path, line.parse::<u32>().ok()?)))
+    {
+        Some((path, line)) => (path, line, last),
+        None => (rest, last, 1),
+    };
+    if path.is_empty() {
+        return None;
+    }
+    Some((
+        PathBuf::from(path),
+        Position::new(line.saturating_sub(1), column.saturating_sub(1)),
+    ))
+}
+
 #[derive(Debug, Default, Copy, Clone)]
 enum MouseAction {
     #[default]
@@ -790,3 +857,26 @@ enum MouseAction {
         pos: Point,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use lsp_types::Position;
+
+    use super::parse_file_link;
+
+    #[test]
+    fn test_parse_file_link() {
+        assert_eq!(
+            parse_file_link("src/main.rs:12:5"),
+            Some((PathBuf::from("src/main.rs"), Position::new(11, 4)))
+        );
+        assert_eq!(
+            parse_file_link("./lib.rs:3"),
+            Some((PathBuf::from("./lib.rs"), Position::new(2, 0)))
+        );
+        assert_eq!(parse_file_link("src/main.rs"), None);
+        assert_eq!(parse_file_link(":12:5"), None);
+    }
+}
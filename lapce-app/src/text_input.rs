@@ -227,6 +227,7 @@ fn text_input_full<T: KeyPressFocus + 'static>(
         cursor_line,
         placeholder: "".to_string(),
         placeholder_text_layout: None,
+        secret: false,
         editor: e_data.clone(),
         cursor_pos: Point::ZERO,
         on_cursor_pos: None,
@@ -308,6 +309,7 @@ enum TextInputState {
     },
     Focus(bool),
     Placeholder(String),
+    Secret(bool),
 }
 
 pub struct TextInput {
@@ -325,6 +327,8 @@ pub struct TextInput {
     cursor_line: RwSignal<Line>,
     placeholder: String,
     placeholder_text_layout: Option<TextLayout>,
+    /// Whether the content is drawn masked, like a password
+    secret: bool,
     cursor_pos: Point,
     on_cursor_pos: Option<Box<dyn Fn(Point)>>,
     hide_cursor: RwSignal<bool>,
@@ -342,6 +346,17 @@ pub fn placeholder(self, placeholder: impl Fn() -> String + 'static) -> Self {
         self
     }
 
+    /// Mask the content, with a `*` for every byte so that the offsets of the
+    /// content are the same in what's drawn
+    pub fn secret(self, secret: impl Fn() -> bool + 'static) -> Self {
+        let id = self.id;
+        create_effect(move |_| {
+            let secret = secret();
+            id.update_state(TextInputState::Secret(secret));
+        });
+        self
+    }
+
     pub fn on_cursor_pos(mut self, cursor_pos: impl Fn(Point) + 'static) -> Self {
         self.on_cursor_pos = Some(Box::new(cursor_pos));
         self
@@ -387,9 +402,13 @@ fn set_text_layout(&mut self) {
         if let Some(line_height) = self.style.line_height() {
             attrs = attrs.line_height(line_height);
         }
+        let masked;
         text_layout.set_text(
             if self.content.is_empty() {
                 " "
+            } else if self.secret {
+                masked = "*".repeat(self.content.len());
+                masked.as_str()
             } else {
                 self.content.as_str()
             },
@@ -421,7 +440,12 @@ fn hit_index(&self, _cx: &mut EventCx, point: Point) -> usize {
                     };
                 let hit =
                     text_layout.hit_point(Point::new(point.x - padding_left, 0.0));
-                hit.index.min(self.content.len())
+                let mut index = hit.index.min(self.content.len());
+                // A masked character can be hit in the middle
+                while !self.content.is_char_boundary(index) {
+                    index -= 1;
+                }
+                index
             } else {
                 0
             }
@@ -540,6 +564,10 @@ fn update(
                     self.placeholder = placeholder;
                     self.placeholder_text_layout = None;
                 }
+                TextInputState::Secret(secret) => {
+                    self.secret = secret;
+                    self.text_layout.set(None);
+                }
             }
             self.id.request_layout();
         }
@@ -83,14 +83,21 @@ fn left(
         )
         .popout_menu(move || {
             #[allow(unused_mut)]
-            let mut menu = Menu::new("").entry(
-                MenuItem::new("Connect to SSH Host").action(move || {
+            let mut menu = Menu::new("")
+                .entry(MenuItem::new("Connect to SSH Host").action(move || {
                     workbench_command.send(LapceWorkbenchCommand::ConnectSshHost);
-                }),
-            );
+                }))
+                .entry(MenuItem::new("Connect to Container").action(move || {
+                    workbench_command.send(LapceWorkbenchCommand::ConnectContainer);
+                }));
             if !is_local
                 && proxy_status.get().is_some_and(|p| {
-                    matches!(p, ProxyStatus::Connecting | ProxyStatus::Connected)
+                    matches!(
+                        p,
+                        ProxyStatus::Connecting
+                            | ProxyStatus::Connected
+                            | ProxyStatus::Reconnecting
+                    )
                 })
             {
                 menu = menu.entry(MenuItem::new("Disconnect remote").action(
@@ -99,6 +106,10 @@ fn left(
                             .send(LapceWorkbenchCommand::DisconnectRemote);
                     },
                 ));
+                menu =
+                    menu.entry(MenuItem::new("Forward a Port").action(move || {
+                        workbench_command.send(LapceWorkbenchCommand::ForwardPort);
+                    }));
             }
             #[cfg(windows)]
             {
@@ -120,7 +131,7 @@ fn left(
                     Some(ProxyStatus::Connected) => {
                         config.color(LapceColor::LAPCE_REMOTE_CONNECTED)
                     }
-                    Some(ProxyStatus::Connecting) => {
+                    Some(ProxyStatus::Connecting | ProxyStatus::Reconnecting) => {
                         config.color(LapceColor::LAPCE_REMOTE_CONNECTING)
                     }
                     Some(ProxyStatus::Disconnected) => {
@@ -211,7 +222,8 @@ fn middle(
                     workbench_command.send(LapceWorkbenchCommand::OpenFolder);
                 }))
                 .entry(MenuItem::new("Open Recent Workspace").action(move || {
-                    workbench_command.send(LapceWorkbenchCommand::PaletteWorkspace);
+                    workbench_command
+                        .send(LapceWorkbenchCommand::OpenRecentWorkspace);
                 }))
         })
     };
@@ -255,7 +267,7 @@ fn middle(
             if workspace.clone().path.is_some() {
                 workbench_command.send(LapceWorkbenchCommand::Palette);
             } else {
-                workbench_command.send(LapceWorkbenchCommand::PaletteWorkspace);
+                workbench_command.send(LapceWorkbenchCommand::OpenRecentWorkspace);
             }
         })
         .style(move |s| {
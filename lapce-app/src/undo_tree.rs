@@ -0,0 +1,314 @@
+use std::{borrow::Cow, ops::Range};
+
+use lapce_xi_rope::Rope;
+
+/// A state of a document's content, reached by an edit from its parent
+#[derive(Clone, Debug)]
+pub struct UndoNode {
+    pub parent: Option<usize>,
+    pub text: Rope,
+    /// When the state was reached, in seconds since the Unix epoch
+    pub time: i64,
+    /// The child last made or gone to, which redo goes to
+    last_child: Option<usize>,
+    /// Where the typing that made this state ended, while more typing there can
+    /// still be merged into it
+    insert_end: Option<usize>,
+}
+
+/// The states a document's content has been in, as a tree in which editing after an
+/// undo starts a new branch, rather than dropping the edits that were undone
+#[derive(Clone, Debug)]
+pub struct UndoTree {
+    nodes: Vec<UndoNode>,
+    current: usize,
+    /// The state last saved to disk
+    saved: Option<usize>,
+}
+
+impl UndoTree {
+    pub fn new(text: Rope) -> Self {
+        Self {
+            nodes: vec![UndoNode {
+                parent: None,
+                text,
+                time: now(),
+                last_child: None,
+                insert_end: None,
+            }],
+            current: 0,
+            saved: Some(0),
+        }
+    }
+
+    /// The states in the order they were first reached
+    pub fn nodes(&self) -> &[UndoNode] {
+        &self.nodes
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn is_saved(&self, node: usize) -> bool {
+        self.saved == Some(node)
+    }
+
+    /// Mark the current state as the one on disk
+    pub fn set_saved(&mut self) {
+        self.saved = Some(self.current);
+    }
+
+    /// Add `text` as the state an edit made from the current one
+    pub fn record(&mut self, text: Rope) {
+        let node = self.nodes.len();
+        self.nodes.push(UndoNode {
+            parent: Some(self.current),
+            text,
+            time: now(),
+            last_child: None,
+            insert_end: None,
+        });
+        self.nodes[self.current].last_child = Some(node);
+        self.current = node;
+    }
+
+    /// Merge the state just recorded for typing at `start` into the state before it,
+    /// if that was made by typing that ended there. More typing at `end` is merged
+    /// into the state after this, while there is an `end`.
+    pub fn merge_insert(&mut self, start: usize, end: Option<usize>) {
+        let current = self.current;
+        let parent = self.nodes[current].parent;
+        let mergeable = parent.filter(|parent| {
+            current == self.nodes.len() - 1
+                && self.nodes[*parent].insert_end == Some(start)
+                && self.saved != Some(*parent)
+        });
+        match mergeable {
+            Some(parent) => {
+                let node = self.nodes.pop().unwrap();
+                let parent_node = &mut self.nodes[parent];
+                parent_node.text = node.text;
+                parent_node.time = node.time;
+                parent_node.last_child = None;
+                parent_node.insert_end = end;
+                self.current = parent;
+            }
+            None => self.nodes[current].insert_end = end,
+        }
+    }
+
+    /// Go to the state before the current one, returning its content
+    pub fn undo(&mut self) -> Option<Rope> {
+        let parent = self.nodes[self.current].parent?;
+        self.go_to(parent)
+    }
+
+    /// Go to the state last undone from or gone to after the current one, returning
+    /// its content
+    pub fn redo(&mut self) -> Option<Rope> {
+        let child = self.nodes[self.current].last_child?;
+        self.go_to(child)
+    }
+
+    /// Go to the state `node`, on whichever branch it is, returning its content
+    pub fn go_to(&mut self, node: usize) -> Option<Rope> {
+        if node >= self.nodes.len() || node == self.current {
+            return None;
+        }
+        // Typing is merged into neither the state left nor the one gone to, which
+        // may have children that were made from it as it is
+        self.nodes[self.current].insert_end = None;
+        self.nodes[node].insert_end = None;
+        self.current = node;
+
+        // Redo follows the branch that was gone to from then on
+        let mut child = node;
+        while let Some(parent) = self.nodes[child].parent {
+            self.nodes[parent].last_child = Some(child);
+            child = parent;
+        }
+        Some(self.nodes[node].text.clone())
+    }
+
+    /// Each state with the column it's shown at, in the order they're shown. A
+    /// state's first child is shown below it, and the other children are each
+    /// shown a column further in, after the first child's branch.
+    pub fn rows(&self) -> Vec<(usize, usize)> {
+        let mut children = vec![Vec::new(); self.nodes.len()];
+        for (node, n) in self.nodes.iter().enumerate() {
+            if let Some(parent) = n.parent {
+                children[parent].push(node);
+            }
+        }
+
+        let mut rows = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![(0, 0)];
+        while let Some((node, column)) = stack.pop() {
+            rows.push((node, column));
+            let children = &children[node];
+            for (i, child) in children.iter().enumerate().rev() {
+                let column = if i == 0 { column } else { column + 1 };
+                stack.push((*child, column));
+            }
+        }
+        rows
+    }
+}
+
+/// The range of `old` to replace, and the range of `new` to replace it with, to
+/// change `old` into `new` without touching the start and end they have in common
+pub fn changed_range(old: &Rope, new: &Rope) -> (Range<usize>, Range<usize>) {
+    let old_last = old.line_of_offset(old.len());
+    let new_last = new.line_of_offset(new.len());
+
+    let mut line = 0;
+    while line <= old_last.min(new_last) {
+        let (a, b) = (line_content(old, line), line_content(new, line));
+        if a != b {
+            break;
+        }
+        line += 1;
+    }
+    let prefix = if line > old_last.min(new_last) {
+        old.len().min(new.len())
+    } else {
+        let (a, b) = (line_content(old, line), line_content(new, line));
+        old.offset_of_line(line) + common_prefix(a.chars(), b.chars())
+    };
+
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let mut back = 0;
+    while back <= old_last.min(new_last) {
+        let (a, b) = (
+            line_content(old, old_last - back),
+            line_content(new, new_last - back),
+        );
+        if a != b {
+            break;
+        }
+        back += 1;
+    }
+    let suffix = if back > old_last.min(new_last) {
+        max_suffix
+    } else {
+        let (a, b) = (
+            line_content(old, old_last - back),
+            line_content(new, new_last - back),
+        );
+        let line_suffix = common_prefix(a.chars().rev(), b.chars().rev());
+        old.len() - old.offset_of_line(old_last - back + 1) + line_suffix
+    };
+    let suffix = suffix.min(max_suffix);
+
+    (prefix..old.len() - suffix, prefix..new.len() - suffix)
+}
+
+fn line_content(text: &Rope, line: usize) -> Cow<str> {
+    text.slice_to_cow(text.offset_of_line(line)..text.offset_of_line(line + 1))
+}
+
+/// The length in bytes of the characters `a` and `b` start with in common
+fn common_prefix(
+    a: impl Iterator<Item = char>,
+    b: impl Iterator<Item = char>,
+) -> usize {
+    a.zip(b)
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum()
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_xi_rope::Rope;
+
+    use super::{changed_range, UndoTree};
+
+    #[test]
+    fn test_undo_tree_branches() {
+        let mut tree = UndoTree::new(Rope::from("a"));
+        tree.record(Rope::from("ab"));
+        tree.record(Rope::from("abc"));
+        assert_eq!(tree.undo().map(|t| t.to_string()), Some("ab".to_string()));
+
+        // Editing after the undo keeps "abc" on its own branch
+        tree.record(Rope::from("abd"));
+        assert_eq!(tree.nodes().len(), 4);
+        assert_eq!(tree.undo().map(|t| t.to_string()), Some("ab".to_string()));
+        assert_eq!(tree.redo().map(|t| t.to_string()), Some("abd".to_string()));
+        assert_eq!(
+            tree.go_to(2).map(|t| t.to_string()),
+            Some("abc".to_string())
+        );
+        assert_eq!(tree.undo().map(|t| t.to_string()), Some("ab".to_string()));
+        assert_eq!(tree.redo().map(|t| t.to_string()), Some("abc".to_string()));
+        assert!(tree.redo().is_none());
+
+        assert_eq!(tree.rows(), vec![(0, 0), (1, 0), (2, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn test_merge_insert() {
+        let mut tree = UndoTree::new(Rope::from(""));
+        tree.record(Rope::from("a"));
+        tree.merge_insert(0, Some(1));
+        tree.record(Rope::from("ab"));
+        tree.merge_insert(1, Some(2));
+        assert_eq!(tree.nodes().len(), 2);
+        assert_eq!(tree.current(), 1);
+
+        // Typing elsewhere starts a new state
+        tree.record(Rope::from("xab"));
+        tree.merge_insert(0, Some(1));
+        assert_eq!(tree.nodes().len(), 3);
+        assert_eq!(tree.undo().map(|t| t.to_string()), Some("ab".to_string()));
+    }
+
+    #[test]
+    fn test_merge_insert_after_undo() {
+        let mut tree = UndoTree::new(Rope::from(""));
+        tree.record(Rope::from("a"));
+        tree.merge_insert(0, Some(1));
+        tree.record(Rope::from("ab"));
+        tree.merge_insert(1, Some(2));
+        tree.record(Rope::from("xab"));
+        tree.merge_insert(0, Some(1));
+        assert_eq!(tree.undo().map(|t| t.to_string()), Some("ab".to_string()));
+
+        // Typing where the state undone to was typed starts a branch
+        tree.record(Rope::from("abc"));
+        tree.merge_insert(2, Some(3));
+        assert_eq!(tree.nodes().len(), 4);
+
+        assert_eq!(tree.undo().map(|t| t.to_string()), Some("ab".to_string()));
+        assert_eq!(
+            tree.go_to(2).map(|t| t.to_string()),
+            Some("xab".to_string())
+        );
+        assert_eq!(tree.undo().map(|t| t.to_string()), Some("ab".to_string()));
+        assert_eq!(tree.undo().map(|t| t.to_string()), Some("".to_string()));
+        assert_eq!(
+            tree.go_to(3).map(|t| t.to_string()),
+            Some("abc".to_string())
+        );
+    }
+
+    #[test]
+    fn test_changed_range() {
+        let range =
+            |old: &str, new: &str| changed_range(&Rope::from(old), &Rope::from(new));
+        assert_eq!(range("abc\ndef\nghi", "abc\ndXf\nghi"), (5..6, 5..6));
+        assert_eq!(range("abc\ndef", "abc\ndef\nghi"), (7..7, 7..11));
+        assert_eq!(range("aa", "aaa"), (2..2, 2..3));
+        assert_eq!(range("héllo", "hallo"), (1..3, 1..2));
+        assert_eq!(range("same", "same"), (4..4, 4..4));
+    }
+}
@@ -0,0 +1,271 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    cosmic_text::Weight,
+    reactive::{create_rw_signal, use_context, ReadSignal, RwSignal},
+    style::CursorStyle,
+    views::{container, dyn_stack, label, scroll, stack, Decorators},
+    View,
+};
+use strum::EnumMessage;
+
+use crate::{
+    app::clickable_icon,
+    command::{CommandKind, LapceWorkbenchCommand, WindowCommand},
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    db::LapceDb,
+    listener::Listener,
+    window_tab::WindowTabData,
+    workspace::LapceWorkspace,
+};
+
+/// How many of the recent workspaces the welcome view lists
+const RECENT_LEN: usize = 10;
+
+/// The commands the welcome view shows the keybindings of
+const SHORTCUTS: [LapceWorkbenchCommand; 6] = [
+    LapceWorkbenchCommand::PaletteCommand,
+    LapceWorkbenchCommand::Palette,
+    LapceWorkbenchCommand::OpenRecentWorkspace,
+    LapceWorkbenchCommand::OpenFolder,
+    LapceWorkbenchCommand::OpenSettings,
+    LapceWorkbenchCommand::OpenKeyboardShortcuts,
+];
+
+/// The start view shown instead of an empty editor in a tab that has no
+/// workspace, with ways to open one and the keybindings to get started with
+pub fn welcome_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let workbench_command = window_tab_data.common.workbench_command;
+    let window_command = window_tab_data.common.window_common.window_command;
+    let editor_tabs = window_tab_data.main_split.editor_tabs;
+    let has_workspace = window_tab_data.workspace.path.is_some();
+
+    let recent = create_rw_signal(recent_workspaces());
+
+    let keymaps = window_tab_data
+        .common
+        .keypress
+        .get_untracked()
+        .command_keymaps;
+    let shortcuts: Vec<(&'static str, Vec<String>)> = SHORTCUTS
+        .iter()
+        .filter_map(|cmd| {
+            let keymap = keymaps
+                .get(CommandKind::Workbench(cmd.clone()).str())?
+                .first()?;
+            let keys = keymap
+                .key
+                .iter()
+                .map(|key| key.label().trim().to_string())
+                .filter(|l| !l.is_empty())
+                .collect();
+            Some((cmd.get_message()?, keys))
+        })
+        .collect();
+
+    container(
+        scroll(
+            container(
+                stack((
+                    label(|| "Lapce".to_string()).style(|s| {
+                        s.font_size(28.0)
+                            .font_weight(Weight::BOLD)
+                            .margin_bottom(20.0)
+                    }),
+                    section_title("Start", config),
+                    start_item(
+                        LapceWorkbenchCommand::OpenFolder,
+                        workbench_command,
+                        config,
+                    ),
+                    start_item(
+                        LapceWorkbenchCommand::ConnectSshHost,
+                        workbench_command,
+                        config,
+                    ),
+                    start_item(
+                        LapceWorkbenchCommand::NewFile,
+                        workbench_command,
+                        config,
+                    ),
+                    section_title("Recent", config),
+                    label(|| "No recent workspaces".to_string()).style(move |s| {
+                        s.padding_vert(3.0)
+                            .color(config.get().color(LapceColor::EDITOR_DIM))
+                            .apply_if(recent.with(|r| !r.is_empty()), |s| s.hide())
+                    }),
+                    dyn_stack(
+                        move || recent.get(),
+                        |(workspace, pinned)| (workspace.path.clone(), *pinned),
+                        move |(workspace, pinned)| {
+                            recent_item(
+                                workspace,
+                                pinned,
+                                recent,
+                                window_command,
+                                config,
+                            )
+                        },
+                    )
+                    .style(|s| s.flex_col()),
+                    section_title("Shortcuts", config),
+                    dyn_stack(
+                        move || shortcuts.clone(),
+                        |(name, _)| *name,
+                        move |(name, keys)| shortcut_item(name, keys, config),
+                    )
+                    .style(|s| s.flex_col()),
+                ))
+                .style(|s| {
+                    s.flex_col().width(400.0).max_width_pct(90.0).padding(40.0)
+                }),
+            )
+            .style(|s| s.width_full().justify_center()),
+        )
+        .style(|s| s.absolute().size_full()),
+    )
+    .style(move |s| {
+        s.absolute()
+            .size_full()
+            .background(config.get().color(LapceColor::EDITOR_BACKGROUND))
+            .apply_if(
+                has_workspace || editor_tabs.with(|tabs| !tabs.is_empty()),
+                |s| s.hide(),
+            )
+    })
+    .debug_name("Welcome")
+}
+
+fn section_title(
+    title: &'static str,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(move || title.to_string()).style(move |s| {
+        s.margin_top(20.0)
+            .margin_bottom(6.0)
+            .font_weight(Weight::BOLD)
+            .color(config.get().color(LapceColor::EDITOR_DIM))
+    })
+}
+
+fn start_item(
+    cmd: LapceWorkbenchCommand,
+    workbench_command: Listener<LapceWorkbenchCommand>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let text = cmd.get_message().unwrap_or_default();
+    link(text.to_string(), config)
+        .on_click_stop(move |_| workbench_command.send(cmd.clone()))
+}
+
+/// The recent workspaces the welcome view lists, with the pinned ones first
+fn recent_workspaces() -> Vec<(LapceWorkspace, bool)> {
+    let db: Arc<LapceDb> = use_context().unwrap();
+    db.recent_workspaces_with_pins()
+        .into_iter()
+        .filter(|(w, _)| w.path.is_some())
+        .take(RECENT_LEN)
+        .collect()
+}
+
+fn recent_item(
+    workspace: LapceWorkspace,
+    pinned: bool,
+    recent: RwSignal<Vec<(LapceWorkspace, bool)>>,
+    window_command: Listener<WindowCommand>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let name = workspace.display().unwrap_or_default();
+    let path = workspace
+        .path
+        .as_ref()
+        .and_then(|p| p.parent())
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let pin_workspace = workspace.clone();
+    let remove_workspace = workspace.clone();
+
+    stack((
+        link(name, config),
+        label(move || path.clone()).style(move |s| {
+            s.margin_left(10.0)
+                .min_width(0.0)
+                .flex_grow(1.0)
+                .text_ellipsis()
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+        }),
+        clickable_icon(
+            move || {
+                if pinned {
+                    LapceIcons::PINNED
+                } else {
+                    LapceIcons::PIN
+                }
+            },
+            move || {
+                let db: Arc<LapceDb> = use_context().unwrap();
+                let _ = db.set_workspace_pinned(&pin_workspace, !pinned);
+                recent.set(recent_workspaces());
+            },
+            || false,
+            || false,
+            move || if pinned { "Unpin" } else { "Pin" },
+            config,
+        ),
+        clickable_icon(
+            || LapceIcons::CLOSE,
+            move || {
+                let db: Arc<LapceDb> = use_context().unwrap();
+                let _ = db.remove_recent_workspace(&remove_workspace);
+                recent.set(recent_workspaces());
+            },
+            || false,
+            || false,
+            || "Remove from Recent",
+            config,
+        ),
+    ))
+    .on_click_stop(move |_| {
+        window_command.send(WindowCommand::SetWorkspace {
+            workspace: workspace.clone(),
+        });
+    })
+    .style(|s| s.items_center())
+}
+
+fn shortcut_item(
+    name: &'static str,
+    keys: Vec<String>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    stack((
+        label(move || name.to_string())
+            .style(|s| s.flex_grow(1.0).min_width(0.0).text_ellipsis()),
+        dyn_stack(
+            move || keys.clone(),
+            |k| k.clone(),
+            move |key| {
+                label(move || key.clone()).style(move |s| {
+                    s.padding_horiz(5.0)
+                        .padding_vert(1.0)
+                        .margin_left(5.0)
+                        .border(1.0)
+                        .border_radius(3.0)
+                        .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+                        .selectable(false)
+                })
+            },
+        ),
+    ))
+    .style(|s| s.items_center().padding_vert(3.0))
+}
+
+fn link(text: String, config: ReadSignal<Arc<LapceConfig>>) -> impl View {
+    label(move || text.clone()).style(move |s| {
+        s.padding_vert(3.0)
+            .color(config.get().color(LapceColor::EDITOR_LINK))
+            .hover(|s| s.cursor(CursorStyle::Pointer))
+    })
+}
@@ -173,6 +173,9 @@ pub fn new(
                 let tab = window_tabs
                     .with(|tabs| tabs.get(active).map(|(_, tab)| tab.clone()));
                 if let Some(tab) = tab {
+                    // A tab's proxy, and the plugins and indexing it runs, are
+                    // only started once the tab is first shown
+                    tab.proxy.start();
                     tab.common
                         .internal_command
                         .send(InternalCommand::ResetBlinkCursor);
@@ -4,12 +4,13 @@
     path::{Path, PathBuf},
     rc::Rc,
     sync::Arc,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
+use alacritty_terminal::index::Direction;
 use crossbeam_channel::Sender;
 use floem::{
-    action::{open_file, TimerToken},
+    action::{exec_after, open_file, TimerToken},
     cosmic_text::{Attrs, AttrsList, FamilyOwned, LineHeightValue, TextLayout},
     ext_event::{create_ext_action, create_signal_from_channel},
     file::FileDialogOptions,
@@ -22,39 +23,50 @@
 use indexmap::IndexMap;
 use itertools::Itertools;
 use lapce_core::{
-    command::FocusCommand, cursor::CursorAffinity, directory::Directory, meta,
-    mode::Mode, register::Register,
+    command::FocusCommand, cursor::CursorAffinity, directory::Directory,
+    language::LapceLanguage, meta, mode::Mode, register::Register,
+    rope_text_pos::RopeTextPosition,
 };
 use lapce_rpc::{
-    core::CoreNotification,
+    core::{CoreNotification, CoreResponse},
     dap_types::RunDebugConfig,
     file::{Naming, PathObject},
+    plugin::PluginCommand,
     proxy::{ProxyResponse, ProxyRpcHandler, ProxyStatus},
-    source_control::FileDiff,
+    source_control::{FileDiff, GitSyncKind},
     terminal::TermId,
-    RpcError,
+    RequestId, RpcError,
+};
+use lsp_types::{
+    Diagnostic, MessageType, ProgressParams, ProgressToken, Range,
+    ShowMessageParams, ShowMessageRequestParams,
 };
-use lsp_types::{Diagnostic, ProgressParams, ProgressToken, ShowMessageParams};
 use serde_json::Value;
 use tracing::{debug, error, event, Level};
 
 use crate::{
     about::AboutData,
     alert::{AlertBoxData, AlertButton},
+    call_hierarchy::{CallHierarchyData, HierarchyDirection},
     code_action::{CodeActionData, CodeActionStatus},
     command::{
         CommandExecuted, CommandKind, InternalCommand, LapceCommand,
         LapceWorkbenchCommand, WindowCommand,
     },
     completion::{CompletionData, CompletionStatus},
-    config::LapceConfig,
+    config::{vscode::VsCodeImport, LapceConfig},
     db::LapceDb,
     debug::{DapData, LapceBreakpoint, RunDebugMode, RunDebugProcess},
+    debug_console::DebugConsoleData,
     doc::DocContent,
-    editor::location::{EditorLocation, EditorPosition},
+    editor::{
+        conflict::ConflictResolution,
+        location::{EditorLocation, EditorPosition},
+    },
     editor_tab::EditorTabChild,
-    file_explorer::data::FileExplorerData,
+    file_explorer::data::{reveal_in_file_manager, FileExplorerData},
     find::Find,
+    fs_cache::FsCache,
     global_search::GlobalSearchData,
     hover::HoverData,
     id::WindowTabId,
@@ -63,16 +75,20 @@
     listener::Listener,
     lsp::path_from_url,
     main_split::{MainSplitData, SplitData, SplitDirection, SplitMoveDirection},
+    notification::NotificationData,
     palette::{kind::PaletteKind, PaletteData, PaletteStatus},
     panel::{
-        data::{default_panel_order, PanelData},
+        data::{add_missing_panels, default_panel_order, PanelData, PanelSection},
         kind::PanelKind,
         position::PanelContainerPosition,
     },
     plugin::PluginData,
+    ports::PortsData,
+    problem::ProblemData,
     proxy::{new_proxy, ProxyData},
     rename::RenameData,
     source_control::SourceControlData,
+    ssh_prompt::SshPromptData,
     terminal::{
         event::{terminal_update_process, TermEvent, TermNotification},
         panel::TerminalPanelData,
@@ -89,6 +105,7 @@ pub enum Focus {
     CodeAction,
     Rename,
     AboutPopup,
+    SshPrompt,
     Panel(PanelKind),
 }
 
@@ -96,6 +113,10 @@ pub enum Focus {
 pub enum DragContent {
     Panel(PanelKind),
     EditorTab(EditorTabChild),
+    /// A file or directory dragged from the file explorer
+    FileNode(PathBuf),
+    /// A panel's icon dragged to another place in the activity bar
+    ActivityBarItem(PanelKind),
 }
 
 impl DragContent {
@@ -131,6 +152,8 @@ pub struct CommonData {
     pub term_tx: Sender<(TermId, TermEvent)>,
     pub term_notification_tx: Sender<TermNotification>,
     pub proxy: ProxyRpcHandler,
+    /// The directory listings and files received from a remote proxy
+    pub fs_cache: FsCache,
     pub view_id: RwSignal<ViewId>,
     pub ui_line_height: Memo<f64>,
     pub dragging: RwSignal<Option<DragContent>>,
@@ -138,11 +161,24 @@ pub struct CommonData {
     pub proxy_status: RwSignal<Option<ProxyStatus>>,
     pub mouse_hover_timer: RwSignal<TimerToken>,
     pub breakpoints: RwSignal<BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>>,
+    /// Whether the settings and tasks in the workspace are used, if it was ever
+    /// asked. Until they're trusted, the workspace is in restricted mode.
+    pub workspace_trust: RwSignal<Option<bool>>,
     // the current focused view which will receive keyboard events
     pub keyboard_focus: RwSignal<Option<ViewId>>,
     pub window_common: Rc<WindowCommonData>,
 }
 
+/// Tells the command line waiting for files to be closed that they are, when
+/// it's dropped with the window tab they're open in
+struct FilesClosed(Sender<()>);
+
+impl Drop for FilesClosed {
+    fn drop(&mut self) {
+        let _ = self.0.try_send(());
+    }
+}
+
 impl std::fmt::Debug for CommonData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("CommonData")
@@ -165,7 +201,12 @@ pub struct WindowTabData {
     pub code_action: RwSignal<CodeActionData>,
     pub source_control: SourceControlData,
     pub rename: RenameData,
+    pub ssh_prompt: SshPromptData,
     pub global_search: GlobalSearchData,
+    pub problem: ProblemData,
+    pub ports: PortsData,
+    pub debug_console: DebugConsoleData,
+    pub call_hierarchy: CallHierarchyData,
     pub about_data: AboutData,
     pub alert_data: AlertBoxData,
     pub layout_rect: RwSignal<Rect>,
@@ -175,7 +216,7 @@ pub struct WindowTabData {
     pub set_config: WriteSignal<Arc<LapceConfig>>,
     pub update_in_progress: RwSignal<bool>,
     pub progresses: RwSignal<IndexMap<ProgressToken, WorkProgress>>,
-    pub messages: RwSignal<Vec<(String, ShowMessageParams)>>,
+    pub notifications: NotificationData,
     pub common: Rc<CommonData>,
 }
 
@@ -290,8 +331,9 @@ pub fn new(
             info
         };
 
+        let workspace_trust = db.get_workspace_trust(&workspace);
         let config = LapceConfig::load(
-            &workspace,
+            &workspace.settings_workspace(workspace_trust == Some(true)),
             &all_disabled_volts,
             &window_common.extra_plugin_paths,
         );
@@ -316,6 +358,8 @@ pub fn new(
             all_disabled_volts,
             window_common.extra_plugin_paths.as_ref().clone(),
             config.plugins.clone(),
+            config.lsp.clone(),
+            config.env.clone(),
             term_tx.clone(),
         );
         let (config, set_config) = cx.create_signal(Arc::new(config));
@@ -360,6 +404,7 @@ pub fn new(
             term_tx,
             term_notification_tx,
             proxy: proxy.proxy_rpc.clone(),
+            fs_cache: FsCache::new(workspace.kind.is_remote()),
             view_id,
             ui_line_height,
             dragging: cx.create_rw_signal(None),
@@ -369,6 +414,7 @@ pub fn new(
             mouse_hover_timer: cx.create_rw_signal(TimerToken::INVALID),
             window_origin: cx.create_rw_signal(Point::ZERO),
             breakpoints: cx.create_rw_signal(BTreeMap::new()),
+            workspace_trust: cx.create_rw_signal(workspace_trust),
             keyboard_focus: cx.create_rw_signal(None),
             window_common: window_common.clone(),
         });
@@ -378,8 +424,12 @@ pub fn new(
             cx.create_rw_signal(CodeActionData::new(cx, common.clone()));
         let source_control =
             SourceControlData::new(cx, main_split.editors, common.clone());
-        let file_explorer =
-            FileExplorerData::new(cx, main_split.editors, common.clone());
+        let file_explorer = FileExplorerData::new(
+            cx,
+            main_split.editors,
+            source_control.ignored.read_only(),
+            common.clone(),
+        );
 
         if let Some(info) = workspace_info.as_ref() {
             let root_split = main_split.root_split;
@@ -435,9 +485,10 @@ pub fn new(
         let panel = workspace_info
             .as_ref()
             .map(|i| {
-                let panel_order = db
-                    .get_panel_orders()
-                    .unwrap_or_else(|_| i.panel.panels.clone());
+                // The workspace keeps its own layout, and the one last used
+                // anywhere only applies to workspaces opened for the first time
+                let mut panel_order = i.panel.panels.clone();
+                add_missing_panels(&mut panel_order);
                 PanelData {
                     panels: cx.create_rw_signal(panel_order),
                     styles: cx.create_rw_signal(i.panel.styles.clone()),
@@ -450,6 +501,7 @@ pub fn new(
                             .map(|(key, value)| (*key, cx.create_rw_signal(*value)))
                             .collect(),
                     ),
+                    collapsed: cx.create_rw_signal(i.panel.collapsed.clone()),
                     common: common.clone(),
                 }
             })
@@ -469,6 +521,7 @@ pub fn new(
         let terminal = TerminalPanelData::new(
             workspace.clone(),
             common.config.get_untracked().terminal.get_default_profile(),
+            main_split.editors.make_local(cx, common.clone()),
             common.clone(),
         );
         if let Some(workspace_info) = workspace_info.as_ref() {
@@ -488,10 +541,34 @@ pub fn new(
                     })
                     .collect(),
             );
+            terminal
+                .debug
+                .watch_expressions
+                .set(workspace_info.watch_expressions.clone());
         }
 
         let rename = RenameData::new(cx, main_split.editors, common.clone());
+        let ssh_prompt = SshPromptData::new(
+            cx,
+            main_split.editors,
+            common.clone(),
+            proxy.core_rpc.clone(),
+        );
         let global_search = GlobalSearchData::new(cx, main_split.clone());
+        let problem = ProblemData::new(cx, main_split.editors, common.clone());
+        let ports = PortsData::new(
+            cx,
+            main_split.editors,
+            common.clone(),
+            proxy.ssh.clone(),
+        );
+        let debug_console = DebugConsoleData::new(
+            cx,
+            main_split.editors,
+            terminal.clone(),
+            common.clone(),
+        );
+        let call_hierarchy = CallHierarchyData::new(cx, common.clone());
 
         let plugin = PluginData::new(
             cx,
@@ -536,7 +613,12 @@ pub fn new(
             source_control,
             plugin,
             rename,
+            ssh_prompt,
             global_search,
+            problem,
+            ports,
+            debug_console,
+            call_hierarchy,
             about_data,
             alert_data,
             layout_rect: cx.create_rw_signal(Rect::ZERO),
@@ -546,7 +628,7 @@ pub fn new(
             set_config,
             update_in_progress: cx.create_rw_signal(false),
             progresses: cx.create_rw_signal(IndexMap::new()),
-            messages: cx.create_rw_signal(Vec::new()),
+            notifications: NotificationData::new(cx),
             common,
         };
 
@@ -566,6 +648,66 @@ pub fn new(
             });
         }
 
+        {
+            let file_explorer = window_tab_data.file_explorer.clone();
+            let active_editor = window_tab_data.main_split.active_editor;
+            let config = window_tab_data.common.config;
+            cx.create_effect(move |_| {
+                if !config.with(|config| config.ui.file_explorer_follow_active_file)
+                {
+                    return;
+                }
+                let path = active_editor.get().and_then(|editor| {
+                    editor.doc().content.with(|content| content.path().cloned())
+                });
+                if let Some(path) = path {
+                    file_explorer.reveal_path(&path);
+                }
+            });
+        }
+
+        // Tell the plugins about the selections of the active editor once
+        // they rest
+        {
+            let active_editor = window_tab_data.main_split.active_editor;
+            let proxy = window_tab_data.common.proxy.clone();
+            let selection_timer = cx.create_rw_signal(TimerToken::INVALID);
+            cx.create_effect(move |_| {
+                let Some(editor) = active_editor.get() else {
+                    return;
+                };
+                editor.cursor().track();
+                let proxy = proxy.clone();
+                let token = exec_after(Duration::from_millis(300), move |token| {
+                    if selection_timer.try_get_untracked() != Some(token) {
+                        return;
+                    }
+                    let doc = editor.doc();
+                    let Some(path) = doc
+                        .content
+                        .with_untracked(|content| content.path().cloned())
+                    else {
+                        return;
+                    };
+                    let selections = doc.buffer.with_untracked(|buffer| {
+                        editor
+                            .cursor()
+                            .get_untracked()
+                            .edit_selection(buffer)
+                            .regions()
+                            .iter()
+                            .map(|region| Range {
+                                start: buffer.offset_to_position(region.min()),
+                                end: buffer.offset_to_position(region.max()),
+                            })
+                            .collect()
+                    });
+                    proxy.selection_changed(path, selections);
+                });
+                selection_timer.set(token);
+            });
+        }
+
         {
             let window_tab_data = window_tab_data.clone();
             window_tab_data.common.lapce_command.listen(move |cmd| {
@@ -600,6 +742,14 @@ pub fn new(
             });
         }
 
+        // A workspace that comes with its own settings or tasks is asked about
+        // before they're used
+        if workspace_trust.is_none()
+            && window_tab_data.workspace.has_local_settings()
+        {
+            window_tab_data.ask_workspace_trust();
+        }
+
         window_tab_data
     }
 
@@ -614,13 +764,60 @@ pub fn reload_config(&self) {
         all_disabled_volts.extend(workspace_disabled_volts);
 
         let config = LapceConfig::load(
-            &self.workspace,
+            &self.settings_workspace(),
             &all_disabled_volts,
             &self.common.window_common.extra_plugin_paths,
         );
+        for error in &config.errors {
+            self.show_message_with_actions(
+                "Settings",
+                &ShowMessageParams {
+                    typ: MessageType::ERROR,
+                    message: error.clone(),
+                },
+                vec![self.workbench_action(
+                    "Open Settings",
+                    LapceWorkbenchCommand::OpenSettingsFile,
+                )],
+            );
+        }
         self.common.keypress.update(|keypress| {
             keypress.update_keymaps(&config);
         });
+
+        let old_lsp = self
+            .common
+            .config
+            .with_untracked(|config| config.lsp.clone());
+        if old_lsp != config.lsp {
+            let languages = old_lsp
+                .keys()
+                .chain(config.lsp.keys())
+                .filter(|language| {
+                    old_lsp.get(*language) != config.lsp.get(*language)
+                })
+                .cloned()
+                .collect();
+            self.common
+                .proxy
+                .update_language_server_configs(config.lsp.clone());
+            self.restart_language_servers_of(languages);
+        }
+
+        if self.common.config.with_untracked(|c| c.env != config.env) {
+            self.common.proxy.update_env(config.env.clone());
+        }
+
+        if self
+            .common
+            .config
+            .with_untracked(|c| c.plugins != config.plugins)
+        {
+            self.common
+                .proxy
+                .update_plugin_configs(config.plugins.clone());
+        }
+
         self.set_config.set(Arc::new(config));
     }
 
@@ -723,6 +920,63 @@ pub fn run_workbench_command(
             NewFile => {
                 self.main_split.new_file();
             }
+            NewUntitledFile => {
+                self.main_split.new_file();
+                self.palette.run(PaletteKind::Language);
+            }
+            FileExplorerNewFile => {
+                self.file_explorer.new_node_at_selection(false);
+            }
+            FileExplorerNewDirectory => {
+                self.file_explorer.new_node_at_selection(true);
+            }
+            FileExplorerRename => {
+                self.file_explorer.rename_selection();
+            }
+            FileExplorerDuplicate => {
+                self.file_explorer.duplicate_selection();
+            }
+            FileExplorerDelete => {
+                self.file_explorer.trash_selection();
+            }
+            FileExplorerCut => {
+                self.file_explorer.cut_selection();
+            }
+            FileExplorerCopy => {
+                self.file_explorer.copy_selection();
+            }
+            FileExplorerPaste => {
+                self.file_explorer.paste_selection();
+            }
+            FileExplorerCopyPath => {
+                self.file_explorer.copy_path_selection();
+            }
+            FileExplorerCopyRelativePath => {
+                self.file_explorer.copy_relative_path_selection();
+            }
+            FileExplorerOpenInTerminal => {
+                self.file_explorer.open_in_terminal_selection();
+            }
+            FileExplorerRevealInFileManager => {
+                self.file_explorer.reveal_in_file_manager_selection();
+            }
+            FileExplorerToggleHiddenFiles => {
+                self.file_explorer.toggle_show_hidden();
+            }
+            RevealInExplorer => {
+                let path = self.main_split.active_editor.get_untracked().and_then(
+                    |editor| {
+                        editor
+                            .doc()
+                            .content
+                            .with_untracked(|content| content.path().cloned())
+                    },
+                );
+                if let Some(path) = path {
+                    self.show_panel(PanelKind::FileExplorer);
+                    self.file_explorer.reveal_path(&path);
+                }
+            }
             RevealActiveFileInFileExplorer => {
                 if let Some(editor_data) = self.main_split.active_editor.get() {
                     let doc = editor_data.doc();
@@ -734,9 +988,8 @@ pub fn run_workbench_command(
                         None
                     };
                     let Some(path) = path else { return };
-                    let path = path.parent().unwrap_or(&path);
 
-                    open_uri(path);
+                    reveal_in_file_manager(&path);
                 }
             }
 
@@ -809,6 +1062,21 @@ pub fn run_workbench_command(
                     );
                 }
             }
+            ImportVsCodeSettings => {
+                let Some(dir) = Directory::vscode_user_directory() else {
+                    return;
+                };
+                let config = self.common.config.get_untracked();
+                let import = VsCodeImport::read(&dir, &config);
+                import.write();
+                self.show_message(
+                    "Import from VS Code",
+                    &ShowMessageParams {
+                        typ: MessageType::INFO,
+                        message: import.summary(),
+                    },
+                );
+            }
             OpenLogFile => {
                 if let Some(dir) = Directory::logs_directory() {
                     self.open_paths(&[PathObject::from_path(
@@ -846,6 +1114,43 @@ pub fn run_workbench_command(
                 self.main_split.export_theme();
             }
             ToggleInlayHints => {}
+            RestartLanguageServer => {
+                self.manage_language_servers(true);
+            }
+            StopLanguageServer => {
+                self.manage_language_servers(false);
+            }
+            ShowLanguageServers => {
+                self.palette.run(PaletteKind::LanguageServers);
+            }
+            OpenLanguageServerLog => {
+                self.open_language_server_log();
+            }
+            RunPluginCommand => {
+                match data.and_then(|data| {
+                    serde_json::from_value::<PluginCommand>(data).ok()
+                }) {
+                    Some(command) => self
+                        .proxy
+                        .proxy_rpc
+                        .run_plugin_command(command.volt_id, command.command),
+                    None => error!("No plugin command provided"),
+                }
+            }
+            UndoWorkspaceEdit => {
+                self.main_split.undo_workspace_edit();
+            }
+            FormatSelection => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.format_selection();
+                }
+            }
+            ShowCallHierarchy => {
+                self.show_hierarchy(HierarchyDirection::Callers);
+            }
+            ShowTypeHierarchy => {
+                self.show_hierarchy(HierarchyDirection::Supertypes);
+            }
 
             // ==== Window ====
             ReloadWindow => {
@@ -969,6 +1274,92 @@ pub fn run_workbench_command(
                 }
                 self.common.focus.set(Focus::Panel(PanelKind::Terminal));
             }
+            RunSelectionInTerminal => {
+                let Some(editor) = self.main_split.active_editor.get_untracked()
+                else {
+                    return;
+                };
+                let text = editor.selection_or_line();
+                if text.trim().is_empty() {
+                    return;
+                }
+
+                // Keep the focus in the editor to carry on with the next line
+                let focus = self.common.focus.get_untracked();
+                self.show_panel(PanelKind::Terminal);
+                self.common.focus.set(focus);
+                let terminal = self
+                    .terminal
+                    .active_tab(false)
+                    .and_then(|tab| tab.active_terminal(false));
+                if let Some(terminal) = terminal {
+                    terminal.run_text(&text);
+                }
+            }
+            RunFile => {
+                let Some(editor) = self.main_split.active_editor.get_untracked()
+                else {
+                    return;
+                };
+                let Some(path) = editor
+                    .doc()
+                    .content
+                    .with_untracked(|content| content.path().cloned())
+                else {
+                    return;
+                };
+                let config = self.common.config.get_untracked();
+                match config.terminal.get_run_config(&path) {
+                    Some(config) => {
+                        self.common.internal_command.send(
+                            InternalCommand::RunAndDebug {
+                                mode: RunDebugMode::Run,
+                                config,
+                            },
+                        );
+                    }
+                    None => {
+                        let language = LapceLanguage::from_path(&path);
+                        self.show_message(
+                            "Run File",
+                            &ShowMessageParams {
+                                typ: MessageType::ERROR,
+                                message: format!(
+                                    "No run template is configured for {}",
+                                    language.name()
+                                ),
+                            },
+                        );
+                    }
+                }
+            }
+            SplitTerminal | RenameTerminal | RestartTerminal | KillTerminal => {
+                let terminal = self
+                    .terminal
+                    .active_tab(false)
+                    .and_then(|tab| tab.active_terminal(false));
+                let Some(terminal) = terminal else {
+                    return;
+                };
+                let term_id = terminal.term_id;
+                match cmd {
+                    SplitTerminal => self.terminal.split(term_id),
+                    RenameTerminal => {
+                        let name = terminal
+                            .name
+                            .get_untracked()
+                            .unwrap_or_else(|| terminal.title.get_untracked());
+                        self.palette.run_rename_terminal(term_id, &name);
+                    }
+                    RestartTerminal => {
+                        self.terminal.restart_terminal(term_id);
+                    }
+                    _ => self.terminal.close_terminal(&term_id),
+                }
+            }
+            NewTerminalTabWithProfile => {
+                self.palette.run(PaletteKind::TerminalProfile);
+            }
             CloseTerminalTab => {
                 self.terminal.close_tab(None);
                 if self
@@ -1006,10 +1397,16 @@ pub fn run_workbench_command(
             ConnectSshHost => {
                 self.palette.run(PaletteKind::SshHost);
             }
+            ManageWorkspaceTrust => {
+                self.ask_workspace_trust();
+            }
             #[cfg(windows)]
             ConnectWslHost => {
                 self.palette.run(PaletteKind::WslHost);
             }
+            ConnectContainer => {
+                self.palette.run(PaletteKind::ContainerHost);
+            }
             DisconnectRemote => {
                 self.common.window_common.window_command.send(
                     WindowCommand::SetWorkspace {
@@ -1021,6 +1418,10 @@ pub fn run_workbench_command(
                     },
                 );
             }
+            ForwardPort => {
+                self.show_panel(PanelKind::Ports);
+                self.common.focus.set(Focus::Panel(PanelKind::Ports));
+            }
 
             // ==== Palette Commands ====
             PaletteHelp => self.palette.run(PaletteKind::PaletteHelp),
@@ -1037,11 +1438,15 @@ pub fn run_workbench_command(
             PaletteCommand => {
                 self.palette.run(PaletteKind::Command);
             }
-            PaletteWorkspace => {
+            OpenRecentWorkspace => {
                 self.palette.run(PaletteKind::Workspace);
             }
             PaletteRunAndDebug => {
-                self.palette.run(PaletteKind::RunAndDebug);
+                if self.common.workspace_trust.get_untracked() == Some(true) {
+                    self.palette.run(PaletteKind::RunAndDebug);
+                } else {
+                    self.ask_workspace_trust();
+                }
             }
             PaletteSCMReferences => {
                 self.palette.run(PaletteKind::SCMReferences);
@@ -1058,7 +1463,43 @@ pub fn run_workbench_command(
             ChangeFileLineEnding => {
                 self.palette.run(PaletteKind::LineEnding);
             }
+            ReopenWithEncoding => {
+                self.palette.run(PaletteKind::ReopenWithEncoding);
+            }
+            SaveWithEncoding => {
+                self.palette.run(PaletteKind::SaveWithEncoding);
+            }
+            ToggleReadOnly => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.doc().content.update(|content| {
+                        if let DocContent::File { read_only, .. } = content {
+                            *read_only = !*read_only;
+                        }
+                    });
+                }
+            }
+            KeepTabOpen => {
+                self.common
+                    .internal_command
+                    .send(InternalCommand::MakeConfirmed);
+            }
             DiffFiles => self.palette.run(PaletteKind::DiffFiles),
+            CompareWithSaved | CompareWithHead | CompareWithClipboard => {
+                let path = self
+                    .main_split
+                    .active_editor
+                    .get_untracked()
+                    .and_then(|editor| {
+                        editor.doc().content.with_untracked(|c| c.path().cloned())
+                    });
+                if let Some(path) = path {
+                    match cmd {
+                        CompareWithSaved => self.main_split.compare_with_saved(path),
+                        CompareWithHead => self.main_split.open_file_changes(path),
+                        _ => self.main_split.compare_with_clipboard(path),
+                    }
+                }
+            }
 
             // ==== Running / Debugging ====
             RunAndDebugRestart => {
@@ -1076,6 +1517,68 @@ pub fn run_workbench_command(
                     self.terminal.stop_run_debug(term_id);
                 }
             }
+            ToggleBreakpoint => {
+                if let Some(editor_data) =
+                    self.main_split.active_editor.get_untracked()
+                {
+                    let doc = editor_data.doc();
+                    if let Some(path) = doc.content.get_untracked().path() {
+                        let offset = editor_data.cursor().with_untracked(|c| {
+                            c.offset()
+                        });
+                        let (line, offset) = doc.buffer.with_untracked(|b| {
+                            let line = b.line_of_offset(offset);
+                            (line, b.offset_of_line(line))
+                        });
+                        self.terminal.debug.toggle_breakpoint(
+                            &self.common.proxy,
+                            path,
+                            line,
+                            offset,
+                        );
+                    }
+                }
+            }
+            DebugContinue => {
+                let active_term = self.terminal.debug.active_term.get_untracked();
+                if active_term
+                    .and_then(|term_id| self.terminal.dap_continue(term_id))
+                    .is_none()
+                {
+                    self.run_workbench_command(PaletteRunAndDebug, None);
+                }
+            }
+            DebugPause => {
+                if let Some(term_id) =
+                    self.terminal.debug.active_term.get_untracked()
+                {
+                    self.terminal.dap_pause(term_id);
+                }
+            }
+            DebugStepOver => {
+                if let Some(term_id) =
+                    self.terminal.debug.active_term.get_untracked()
+                {
+                    self.terminal.dap_step_over(term_id);
+                }
+            }
+            DebugStepInto => {
+                if let Some(term_id) =
+                    self.terminal.debug.active_term.get_untracked()
+                {
+                    self.terminal.dap_step_into(term_id);
+                }
+            }
+            DebugStepOut => {
+                if let Some(term_id) =
+                    self.terminal.debug.active_term.get_untracked()
+                {
+                    self.terminal.dap_step_out(term_id);
+                }
+            }
+            DebugAddWatchExpression => {
+                self.palette.run_watch_expression(None, "");
+            }
 
             // ==== UI ====
             ZoomIn => {
@@ -1164,6 +1667,15 @@ pub fn run_workbench_command(
             TogglePanelBottomVisual => {
                 self.toggle_container_visual(&PanelContainerPosition::Bottom);
             }
+            TogglePanelLeftCollapsed => {
+                self.panel.toggle_collapsed(&PanelContainerPosition::Left);
+            }
+            TogglePanelRightCollapsed => {
+                self.panel.toggle_collapsed(&PanelContainerPosition::Right);
+            }
+            TogglePanelBottomCollapsed => {
+                self.panel.toggle_collapsed(&PanelContainerPosition::Bottom);
+            }
             ToggleTerminalFocus => {
                 self.toggle_panel_focus(PanelKind::Terminal);
             }
@@ -1182,6 +1694,9 @@ pub fn run_workbench_command(
             ToggleSearchFocus => {
                 self.toggle_panel_focus(PanelKind::Search);
             }
+            ToggleDebugConsoleFocus => {
+                self.toggle_panel_focus(PanelKind::DebugConsole);
+            }
             ToggleTerminalVisual => {
                 self.toggle_panel_visual(PanelKind::Terminal);
             }
@@ -1203,6 +1718,24 @@ pub fn run_workbench_command(
             ToggleSearchVisual => {
                 self.toggle_panel_visual(PanelKind::Search);
             }
+            ToggleCallHierarchyVisual => {
+                self.toggle_panel_visual(PanelKind::CallHierarchy);
+            }
+            TogglePortsVisual => {
+                self.toggle_panel_visual(PanelKind::Ports);
+            }
+            TogglePluginViewsVisual => {
+                self.toggle_panel_visual(PanelKind::PluginViews);
+            }
+            ToggleUndoHistoryVisual => {
+                self.toggle_panel_visual(PanelKind::UndoHistory);
+            }
+            ToggleNotificationVisual => {
+                self.toggle_panel_visual(PanelKind::Notification);
+            }
+            ToggleDebugConsoleVisual => {
+                self.toggle_panel_visual(PanelKind::DebugConsole);
+            }
             FocusEditor => {
                 self.common.focus.set(Focus::Workbench);
             }
@@ -1228,8 +1761,68 @@ pub fn run_workbench_command(
                 }
                 None => error!("No ref provided"),
             },
+            CreateBranch => match data {
+                Some(name) => {
+                    if let Some(name) = name.as_str() {
+                        self.proxy.proxy_rpc.git_create_branch(name.to_string());
+                    }
+                }
+                None => error!("No branch name provided"),
+            },
+            SourceControlStash => {
+                self.source_control.stash();
+            }
+            SourceControlStashPop | SourceControlStashApply => {
+                // The latest stash, unless another one is given
+                let index = data
+                    .and_then(|data| data.as_u64())
+                    .map(|index| index as usize)
+                    .unwrap_or(0);
+                let has_stash = self.source_control.stashes.with_untracked(
+                    |stashes| stashes.iter().any(|stash| stash.index == index),
+                );
+                if has_stash {
+                    self.source_control
+                        .apply_stash(index, cmd == SourceControlStashPop);
+                }
+            }
+            SourceControlFetch => {
+                self.source_control.sync(GitSyncKind::Fetch, None);
+            }
+            SourceControlPull => {
+                self.source_control.sync(GitSyncKind::Pull, None);
+            }
+            SourceControlPush => {
+                self.source_control.sync(GitSyncKind::Push, None);
+            }
+            SourceControlShowLog => {
+                self.source_control.show_log(None);
+                self.show_git_log();
+            }
+            SourceControlShowFileHistory => {
+                let path = self
+                    .main_split
+                    .active_editor
+                    .get_untracked()
+                    .and_then(|editor| {
+                        editor.doc().content.with_untracked(|c| c.path().cloned())
+                    });
+                if let Some(path) = path {
+                    self.source_control.show_log(Some(path));
+                    self.show_git_log();
+                }
+            }
             SourceControlCommit => {
-                self.source_control.commit();
+                self.source_control.commit(false);
+            }
+            SourceControlCommitAmend => {
+                self.source_control.commit(true);
+            }
+            SourceControlStageAll => {
+                self.source_control.stage_all();
+            }
+            SourceControlUnstageAll => {
+                self.source_control.unstage_all();
             }
             SourceControlCopyActiveFileRemoteUrl => {
                 // TODO:
@@ -1344,7 +1937,95 @@ pub fn run_workbench_command(
             NextError => {
                 self.main_split.next_error();
             }
-            PreviousError => {}
+            PreviousError => {
+                self.main_split.previous_error();
+            }
+            NextHunk => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.next_hunk();
+                }
+            }
+            PreviousHunk => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.previous_hunk();
+                }
+            }
+            NextSymbolOccurrence => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.next_symbol_occurrence();
+                }
+            }
+            PreviousSymbolOccurrence => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.previous_symbol_occurrence();
+                }
+            }
+            ToggleBlame => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.toggle_blame();
+                }
+            }
+            ToggleInlineDiff => {
+                let diff_editor_id = self
+                    .main_split
+                    .active_editor
+                    .get_untracked()
+                    .and_then(|editor| editor.diff_editor_id.get_untracked());
+                if let Some((_, diff_editor_id)) = diff_editor_id {
+                    let diff_editor = self
+                        .main_split
+                        .diff_editors
+                        .with_untracked(|diff_editors| {
+                            diff_editors.get(&diff_editor_id).cloned()
+                        });
+                    if let Some(diff_editor) = diff_editor {
+                        diff_editor.toggle_inline();
+                    }
+                }
+            }
+            StageHunk => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    if let Some(hunk) = editor.hunk_at_cursor() {
+                        editor.stage_hunk(&hunk);
+                    }
+                }
+            }
+            RevertHunk => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    if let Some(hunk) = editor.hunk_at_cursor() {
+                        editor.revert_hunk(&hunk);
+                    }
+                }
+            }
+            AcceptCurrentChange | AcceptIncomingChange | AcceptBothChanges => {
+                let resolution = match cmd {
+                    AcceptCurrentChange => ConflictResolution::Current,
+                    AcceptIncomingChange => ConflictResolution::Incoming,
+                    _ => ConflictResolution::Both,
+                };
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    if let Some(conflict) = editor.conflict_at_cursor() {
+                        editor.resolve_conflict(&conflict, resolution);
+                    }
+                }
+            }
+            CompareConflict => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    if let Some(conflict) = editor.conflict_at_cursor() {
+                        editor.toggle_conflict_peek(conflict);
+                    }
+                }
+            }
+            NextConflict => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.next_conflict();
+                }
+            }
+            PreviousConflict => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.previous_conflict();
+                }
+            }
             Quit => {
                 floem::quit_app();
             }
@@ -1393,6 +2074,15 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::OpenFileChanges { path } => {
                 self.main_split.open_file_changes(path);
             }
+            InternalCommand::CompareWithSaved { path } => {
+                self.main_split.compare_with_saved(path);
+            }
+            InternalCommand::OpenFileAtRevision { path, revision } => {
+                self.main_split.open_file_at_revision(path, revision);
+            }
+            InternalCommand::OpenCommitFileChanges { path, id } => {
+                self.main_split.open_commit_file_changes(path, id);
+            }
             InternalCommand::ReloadFileExplorer => {
                 self.file_explorer.reload();
             }
@@ -1436,43 +2126,7 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                                     send_new_path
                                 };
 
-                            // If the renamed item is a file, update any editors the file is open
-                            // in to use the new path.
-                            // If the renamed item is a directory, update any editors in which a
-                            // file the renamed directory is an ancestor of is open to use the
-                            // file's new path.
-                            let renamed_editors_content: Vec<_> = editors
-                                .with_editors_untracked(|editors| {
-                                    editors
-                                        .values()
-                                        .map(|editor| editor.doc().content)
-                                        .filter(|content| {
-                                            content.with_untracked(|content| {
-                                                match content {
-                                                    DocContent::File {
-                                                        path,
-                                                        ..
-                                                    } => path.starts_with(
-                                                        &send_current_path,
-                                                    ),
-                                                    _ => false,
-                                                }
-                                            })
-                                        })
-                                        .collect()
-                                });
-
-                            for content in renamed_editors_content {
-                                content.update(|content| {
-                                    if let DocContent::File { path, .. } = content {
-                                        if let Ok(suffix) =
-                                            path.strip_prefix(&send_current_path)
-                                        {
-                                            *path = new_path.join(suffix);
-                                        }
-                                    }
-                                });
-                            }
+                            editors.rename_path(&send_current_path, &new_path);
 
                             file_explorer.reload();
                             file_explorer.naming.set(Naming::None);
@@ -1490,6 +2144,58 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                     .proxy
                     .rename_path(current_path.clone(), new_path, send);
             }
+            InternalCommand::MovePath {
+                current_path,
+                new_path,
+                copy,
+            } => {
+                let send_current_path = current_path.clone();
+                let send_new_path = new_path.clone();
+                let file_explorer = self.file_explorer.clone();
+                let editors = self.main_split.editors;
+                let internal_command = self.common.internal_command;
+
+                let send = create_ext_action(
+                    self.scope,
+                    move |response: Result<ProxyResponse, RpcError>| match response {
+                        Ok(response) => {
+                            if !copy {
+                                let new_path =
+                                    if let ProxyResponse::CreatePathResponse {
+                                        path,
+                                    } = response
+                                    {
+                                        path
+                                    } else {
+                                        send_new_path
+                                    };
+                                editors.rename_path(&send_current_path, &new_path);
+                            }
+
+                            file_explorer.reload();
+                        }
+                        Err(err) => {
+                            internal_command.send(InternalCommand::ShowAlert {
+                                title: format!(
+                                    "Failed to {} {}",
+                                    if copy { "copy" } else { "move" },
+                                    send_current_path.display()
+                                ),
+                                msg: err.message,
+                                buttons: Vec::new(),
+                            });
+                        }
+                    },
+                );
+
+                if copy {
+                    self.common
+                        .proxy
+                        .duplicate_path(current_path, new_path, send);
+                } else {
+                    self.common.proxy.rename_path(current_path, new_path, send);
+                }
+            }
             InternalCommand::FinishNewNode { is_dir, path } => {
                 let file_explorer = self.file_explorer.clone();
                 let internal_command = self.common.internal_command;
@@ -1559,6 +2265,10 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                 self.palette.references.set(references);
                 self.palette.run(PaletteKind::Reference);
             }
+            InternalCommand::PaletteGitCredentials { kind } => {
+                self.palette.git_sync.set(Some(kind));
+                self.palette.run(PaletteKind::GitCredentials);
+            }
             InternalCommand::Split {
                 direction,
                 editor_tab_id,
@@ -1610,9 +2320,62 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::NewTerminal { profile } => {
                 self.terminal.new_tab(profile);
             }
+            InternalCommand::NewTerminalInDirectory { path } => {
+                let mut profile = self
+                    .common
+                    .config
+                    .get_untracked()
+                    .terminal
+                    .get_default_profile()
+                    .unwrap_or_default();
+                profile.workdir = url::Url::from_file_path(&path).ok();
+                self.terminal.new_tab(Some(profile));
+                self.show_panel(PanelKind::Terminal);
+            }
             InternalCommand::SplitTerminal { term_id } => {
                 self.terminal.split(term_id);
             }
+            InternalCommand::RenameTerminal { term_id, name } => {
+                self.terminal.rename_terminal(&term_id, &name);
+            }
+            InternalCommand::SetWatchExpression { index, expression } => {
+                self.terminal.debug.set_watch_expression(index, expression);
+            }
+            InternalCommand::SetBreakpointCondition { target, condition } => {
+                self.terminal.debug.set_breakpoint_condition(
+                    &self.common.proxy,
+                    target,
+                    condition,
+                );
+            }
+            InternalCommand::RestartLanguageServers { volt_ids } => {
+                let installed = self.plugin.installed.get_untracked();
+                for volt_id in volt_ids {
+                    if let Some(volt) = installed.get(&volt_id) {
+                        self.plugin.reload_volt(volt.meta.get_untracked());
+                    }
+                }
+            }
+            InternalCommand::StopLanguageServers { volt_ids } => {
+                let installed = self.plugin.installed.get_untracked();
+                for volt_id in volt_ids {
+                    if let Some(volt) = installed.get(&volt_id) {
+                        self.common
+                            .proxy
+                            .disable_volt(volt.meta.get_untracked().info());
+                    }
+                }
+            }
+            InternalCommand::OpenTerminalFind => {
+                self.terminal.find.open();
+            }
+            InternalCommand::TerminalFindNext { forward } => {
+                self.terminal.find.search(if forward {
+                    Direction::Right
+                } else {
+                    Direction::Left
+                });
+            }
             InternalCommand::SplitTerminalNext { term_id } => {
                 self.terminal.split_next(term_id);
             }
@@ -1665,16 +2428,20 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             }
             InternalCommand::SetColorTheme { name, save } => {
                 if save {
+                    let setting = self
+                        .common
+                        .config
+                        .with_untracked(|config| config.color_theme_setting());
                     // The config file is watched
                     LapceConfig::update_file(
                         "core",
-                        "color-theme",
+                        setting,
                         toml_edit::Value::from(name),
                     );
                 } else {
                     let mut new_config = self.common.config.get_untracked();
                     Arc::make_mut(&mut new_config)
-                        .set_color_theme(&self.workspace, &name);
+                        .set_color_theme(&self.settings_workspace(), &name);
                     self.set_config.set(new_config);
                 }
             }
@@ -1689,7 +2456,7 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                 } else {
                     let mut new_config = self.common.config.get_untracked();
                     Arc::make_mut(&mut new_config)
-                        .set_icon_theme(&self.workspace, &name);
+                        .set_icon_theme(&self.settings_workspace(), &name);
                     self.set_config.set(new_config);
                 }
             }
@@ -1725,6 +2492,9 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::HideAlert => {
                 self.alert_data.active.set(false);
             }
+            InternalCommand::SetWorkspaceTrust { trusted } => {
+                self.set_workspace_trust(trusted);
+            }
             InternalCommand::SaveScratchDoc { doc } => {
                 self.main_split.save_scratch_doc(doc);
             }
@@ -1776,29 +2546,72 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
         let cx = self.scope;
         match rpc {
             CoreNotification::ProxyStatus { status } => {
+                let reconnected = matches!(status, ProxyStatus::Connected)
+                    && matches!(
+                        self.common.proxy_status.get_untracked(),
+                        Some(ProxyStatus::Reconnecting)
+                    );
                 self.common.proxy_status.set(Some(status.to_owned()));
+                if let ProxyStatus::Disconnected = status {
+                    let window_command = self.common.window_common.window_command;
+                    let workspace = (*self.workspace).clone();
+                    self.notifications.notify(
+                        "Disconnected",
+                        format!("Lost the connection to {}", self.workspace.kind),
+                        MessageType::WARNING,
+                        vec![AlertButton {
+                            text: "Retry".to_string(),
+                            action: Rc::new(move || {
+                                window_command.send(WindowCommand::SetWorkspace {
+                                    workspace: workspace.clone(),
+                                });
+                            }),
+                        }],
+                    );
+                }
+                if reconnected {
+                    self.main_split.reopen_docs();
+                    self.file_explorer.reload();
+                    self.ports.reconnected();
+                }
             }
             CoreNotification::DiffInfo { diff } => {
                 self.source_control.branch.set(diff.head.clone());
                 self.source_control
                     .branches
                     .set(diff.branches.iter().cloned().collect());
+                self.source_control
+                    .remote_branches
+                    .set(diff.remote_branches.iter().cloned().collect());
+                self.source_control
+                    .ahead_behind
+                    .set((diff.ahead, diff.behind));
                 self.source_control
                     .tags
                     .set(diff.tags.iter().cloned().collect());
-                self.source_control.file_diffs.update(|file_diffs| {
-                    *file_diffs = diff
-                        .diffs
+                self.source_control
+                    .stashes
+                    .set(diff.stashes.iter().cloned().collect());
+                self.source_control.load_log();
+                let to_map = |diffs: &[FileDiff]| {
+                    diffs
                         .iter()
-                        .cloned()
-                        .map(|diff| {
-                            let checked = file_diffs
-                                .get(diff.path())
-                                .map_or(true, |(_, c)| *c);
-                            (diff.path().clone(), (diff, checked))
-                        })
-                        .collect();
-                });
+                        .map(|diff| (diff.path().clone(), diff.clone()))
+                        .collect::<IndexMap<_, _>>()
+                };
+                self.source_control.file_diffs.set(to_map(&diff.diffs));
+                self.source_control.staged.set(to_map(&diff.staged));
+                self.source_control.unstaged.set(to_map(&diff.unstaged));
+                self.source_control
+                    .untracked
+                    .set(diff.untracked.iter().cloned().collect());
+                if self
+                    .source_control
+                    .ignored
+                    .with_untracked(|ignored| ignored != &diff.ignored)
+                {
+                    self.source_control.ignored.set(diff.ignored.clone());
+                }
 
                 let docs = self.main_split.docs.get_untracked();
                 for (_, doc) in docs {
@@ -1887,12 +2700,15 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 self.terminal
                     .dap_stopped(dap_id, stopped, stack_frames, variables);
             }
-            CoreNotification::OpenPaths { paths } => {
+            CoreNotification::OpenPaths { paths, .. } => {
                 self.open_paths(paths);
             }
             CoreNotification::DapContinued { dap_id } => {
                 self.terminal.dap_continued(dap_id);
             }
+            CoreNotification::DapOutput { dap_id, output } => {
+                self.terminal.dap_output(dap_id, output);
+            }
             CoreNotification::DapBreakpointsResp {
                 path, breakpoints, ..
             } => {
@@ -1934,6 +2750,12 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::OpenFileChanged { path, content } => {
                 self.main_split.open_file_changed(path, content);
             }
+            CoreNotification::BufferPreview { path, content } => {
+                self.main_split.buffer_preview(path, content);
+            }
+            CoreNotification::OpenFileDeleted { path } => {
+                self.main_split.open_file_deleted(path);
+            }
             CoreNotification::VoltInstalled { volt, icon } => {
                 self.plugin.volt_installed(volt, icon);
             }
@@ -1943,8 +2765,57 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::WorkDoneProgress { progress } => {
                 self.update_progress(progress);
             }
+            CoreNotification::ListeningPorts { ports } => {
+                self.ports.listening_ports_changed(ports);
+            }
             CoreNotification::ShowMessage { title, message } => {
-                self.show_message(title, message);
+                let actions = if message.typ == MessageType::ERROR {
+                    vec![self.workbench_action(
+                        "Open Log",
+                        LapceWorkbenchCommand::OpenLogFile,
+                    )]
+                } else {
+                    Vec::new()
+                };
+                self.show_message_with_actions(title, message, actions);
+            }
+            CoreNotification::SshPrompt {
+                request_id,
+                host,
+                prompt,
+                secret,
+            } => {
+                self.ssh_prompt.show(
+                    *request_id,
+                    host.clone(),
+                    prompt.clone(),
+                    *secret,
+                );
+            }
+            CoreNotification::PluginViews { views } => {
+                self.plugin.views.set(views.clone());
+            }
+            CoreNotification::PluginCommands { commands } => {
+                self.common.keypress.update(|keypress| {
+                    keypress.set_plugin_commands(commands);
+                });
+            }
+            CoreNotification::ShowMessageRequest {
+                request_id,
+                title,
+                params,
+            } => {
+                self.show_message_request(*request_id, title, params);
+            }
+            CoreNotification::ApplyWorkspaceEdit { edit } => {
+                self.main_split.apply_workspace_edit(edit);
+            }
+            CoreNotification::LanguageServerMissing {
+                language_id,
+                name,
+                version,
+            } => {
+                self.offer_language_server_install(language_id, name, version);
             }
             CoreNotification::Log {
                 level,
@@ -1975,7 +2846,6 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 }
             }
             CoreNotification::LogMessage { message, target } => {
-                use lsp_types::MessageType;
                 use tracing_log::log::{log, Level};
                 match message.typ {
                     MessageType::ERROR => {
@@ -1996,8 +2866,8 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                     _ => {}
                 }
             }
-            CoreNotification::WorkspaceFileChange => {
-                self.file_explorer.reload();
+            CoreNotification::WorkspaceFileChange { paths } => {
+                self.file_explorer.files_changed(paths);
             }
             _ => {}
         }
@@ -2018,6 +2888,7 @@ pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
             }
             Focus::Rename => Some(keypress.key_down(event, &self.rename)),
             Focus::AboutPopup => Some(keypress.key_down(event, &self.about_data)),
+            Focus::SshPrompt => Some(keypress.key_down(event, &self.ssh_prompt)),
             Focus::Panel(PanelKind::Terminal) => {
                 self.terminal.key_down(event, &keypress)
             }
@@ -2030,6 +2901,18 @@ pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
             Focus::Panel(PanelKind::SourceControl) => {
                 Some(keypress.key_down(event, &self.source_control))
             }
+            Focus::Panel(PanelKind::FileExplorer) => {
+                Some(keypress.key_down(event, &self.file_explorer))
+            }
+            Focus::Panel(PanelKind::Problem) => {
+                Some(keypress.key_down(event, &self.problem))
+            }
+            Focus::Panel(PanelKind::Ports) => {
+                Some(keypress.key_down(event, &self.ports))
+            }
+            Focus::Panel(PanelKind::DebugConsole) => {
+                Some(keypress.key_down(event, &self.debug_console))
+            }
             _ => None,
         };
 
@@ -2071,6 +2954,7 @@ pub fn workspace_info(&self) -> WorkspaceInfo {
                     (path, breakpoints.into_values().collect::<Vec<_>>())
                 })
                 .collect(),
+            watch_expressions: self.terminal.debug.watch_expressions.get_untracked(),
         }
     }
 
@@ -2304,17 +3188,23 @@ pub fn toggle_panel_visual(&self, kind: PanelKind) {
     /// Toggle a specific kind of panel.
     fn toggle_panel_focus(&self, kind: PanelKind) {
         let should_hide = match kind {
-            PanelKind::FileExplorer
-            | PanelKind::Plugin
-            | PanelKind::Problem
-            | PanelKind::Debug => {
+            PanelKind::Plugin
+            | PanelKind::Debug
+            | PanelKind::CallHierarchy
+            | PanelKind::PluginViews
+            | PanelKind::UndoHistory
+            | PanelKind::Notification => {
                 // Some panels don't accept focus (yet). Fall back to visibility check
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
             }
-            PanelKind::Terminal | PanelKind::SourceControl | PanelKind::Search => {
-                self.is_panel_focused(kind)
-            }
+            PanelKind::Terminal
+            | PanelKind::SourceControl
+            | PanelKind::Search
+            | PanelKind::Problem
+            | PanelKind::Ports
+            | PanelKind::DebugConsole
+            | PanelKind::FileExplorer => self.is_panel_focused(kind),
         };
         if should_hide {
             self.hide_panel(kind);
@@ -2325,6 +3215,10 @@ fn toggle_panel_focus(&self, kind: PanelKind) {
 
     /// Toggle a panel on one of the sides.
     fn toggle_container_visual(&self, position: &PanelContainerPosition) {
+        if self.panel.is_collapsed(position, false) {
+            self.panel.toggle_collapsed(position);
+            return;
+        }
         let shown = !self.panel.is_container_shown(position, false);
         self.panel.set_shown(&position.first(), shown);
         self.panel.set_shown(&position.second(), shown);
@@ -2372,6 +3266,12 @@ fn hide_panel(&self, kind: PanelKind) {
         self.common.focus.set(Focus::Workbench);
     }
 
+    /// Show the source control panel with its commits section open
+    fn show_git_log(&self) {
+        self.panel.section_open(PanelSection::Commits).set(true);
+        self.show_panel(PanelKind::SourceControl);
+    }
+
     pub fn show_panel(&self, kind: PanelKind) {
         if kind == PanelKind::Terminal
             && self
@@ -2516,7 +3416,96 @@ pub fn open_paths(&self, paths: &[PathObject]) {
         }
     }
 
+    /// Tell `closed` once none of the files is open in an editor anymore, after
+    /// they were opened, or once the window tab is closed
+    pub fn notify_files_closed(&self, paths: &[PathObject], closed: Sender<()>) {
+        let paths: Vec<PathBuf> = paths
+            .iter()
+            .filter(|p| !p.is_dir)
+            .map(|p| p.path.clone())
+            .collect();
+        if paths.is_empty() {
+            let _ = closed.send(());
+            return;
+        }
+        let editors = self.main_split.editors;
+        let closed = FilesClosed(closed);
+        // The document of an editor changes when a preview editor is reused for
+        // another file, so it's tracked along with the editors
+        notify_on_close(
+            self.scope,
+            move || {
+                editors.0.with(|editors| {
+                    editors.values().any(|editor| {
+                        editor.doc_signal().with(|doc| {
+                            doc.content.with(|content| {
+                                content
+                                    .path()
+                                    .is_some_and(|path| paths.contains(path))
+                            })
+                        })
+                    })
+                })
+            },
+            move || {
+                let _ = closed.0.try_send(());
+            },
+        );
+    }
+
+    /// The workspace to read the settings of, leaving out the ones in its folder
+    /// while it's in restricted mode
+    fn settings_workspace(&self) -> LapceWorkspace {
+        let trusted = self.common.workspace_trust.get_untracked() == Some(true);
+        self.workspace.settings_workspace(trusted)
+    }
+
+    /// Ask whether the settings and tasks in the workspace can be used, since
+    /// they can run programs, such as language servers and run configurations
+    pub fn ask_workspace_trust(&self) {
+        let Some(path) = self.workspace.path.as_ref() else {
+            return;
+        };
+        let internal_command = self.common.internal_command;
+        let button = |text: &str, trusted: bool| AlertButton {
+            text: text.to_string(),
+            action: Rc::new(move || {
+                internal_command.send(InternalCommand::HideAlert);
+                internal_command
+                    .send(InternalCommand::SetWorkspaceTrust { trusted });
+            }),
+        };
+        self.show_alert(
+            format!("Do you trust the files in {}?", path.display()),
+            "Its settings, language server overrides and run configurations can \
+             run programs. In restricted mode they aren't used."
+                .to_string(),
+            vec![
+                button("Trust", true),
+                button("Stay in Restricted Mode", false),
+            ],
+        );
+    }
+
+    /// Remember whether the workspace is trusted, and use its settings only if
+    /// it is
+    pub fn set_workspace_trust(&self, trusted: bool) {
+        let old = self.common.workspace_trust.get_untracked();
+        self.common.workspace_trust.set(Some(trusted));
+        let db: Arc<LapceDb> = use_context().unwrap();
+        db.save_workspace_trust(self.workspace.clone(), trusted);
+        if old.unwrap_or(false) != trusted {
+            self.reload_config();
+        }
+    }
+
     pub fn show_alert(&self, title: String, msg: String, buttons: Vec<AlertButton>) {
+        // The alert being replaced counts as cancelled
+        if let Some(on_cancel) =
+            self.alert_data.on_cancel.try_update(Option::take).flatten()
+        {
+            on_cancel();
+        }
         self.alert_data.title.set(title);
         self.alert_data.msg.set(msg);
         self.alert_data.buttons.set(buttons);
@@ -2555,9 +3544,250 @@ fn update_progress(&self, progress: &ProgressParams) {
         }
     }
 
+    /// Restart, or stop for this session, the language servers of the active
+    /// file's language
+    fn manage_language_servers(&self, restart: bool) {
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        let Some(path) = editor
+            .doc()
+            .content
+            .with_untracked(|content| content.path().cloned())
+        else {
+            return;
+        };
+        let language = LapceLanguage::from_path(&path);
+        let window_tab = self.clone();
+        let send = create_ext_action(
+            self.scope,
+            move |response: Result<ProxyResponse, RpcError>| {
+                let Ok(ProxyResponse::LanguageServersResponse { servers }) =
+                    response
+                else {
+                    return;
+                };
+                if servers.is_empty() {
+                    window_tab.show_message(
+                        "Language Server",
+                        &ShowMessageParams {
+                            typ: MessageType::INFO,
+                            message: format!(
+                                "No language server is running for {}",
+                                language.name()
+                            ),
+                        },
+                    );
+                    return;
+                }
+                let volt_ids = servers
+                    .into_iter()
+                    .map(|server| server.volt_id)
+                    .unique()
+                    .collect();
+                window_tab.common.internal_command.send(if restart {
+                    InternalCommand::RestartLanguageServers { volt_ids }
+                } else {
+                    InternalCommand::StopLanguageServers { volt_ids }
+                });
+            },
+        );
+        self.common.proxy.language_servers(Some(path), send);
+    }
+
+    /// Show the callers of the symbol at the cursor, or the supertypes of the
+    /// type at the cursor, in the call hierarchy panel
+    fn show_hierarchy(&self, direction: HierarchyDirection) {
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        let doc = editor.doc();
+        let Some(path) = doc
+            .content
+            .with_untracked(|content| content.path().cloned())
+        else {
+            return;
+        };
+        let offset = editor.cursor().with_untracked(|c| c.offset());
+        let position = doc
+            .buffer
+            .with_untracked(|buffer| buffer.offset_to_position(offset));
+        self.call_hierarchy.show(path, position, direction);
+        self.show_panel(PanelKind::CallHierarchy);
+    }
+
+    /// Open the log of the language servers of the active file's language, or
+    /// of all of them, scrolled to its latest entries
+    fn open_language_server_log(&self) {
+        let path =
+            self.main_split
+                .active_editor
+                .get_untracked()
+                .and_then(|editor| {
+                    editor
+                        .doc()
+                        .content
+                        .with_untracked(|content| content.path().cloned())
+                });
+        let window_tab = self.clone();
+        let send = create_ext_action(
+            self.scope,
+            move |response: Result<ProxyResponse, RpcError>| match response {
+                Ok(ProxyResponse::LanguageServerLogResponse { path, lines }) => {
+                    window_tab.main_split.jump_to_location(
+                        EditorLocation {
+                            path,
+                            position: Some(EditorPosition::Line(
+                                lines.saturating_sub(1),
+                            )),
+                            scroll_offset: None,
+                            ignore_unconfirmed: false,
+                            same_editor_tab: false,
+                        },
+                        None,
+                    );
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    window_tab.show_message(
+                        "LSP Log",
+                        &ShowMessageParams {
+                            typ: MessageType::ERROR,
+                            message: err.message,
+                        },
+                    );
+                }
+            },
+        );
+        self.common.proxy.language_server_log(path, send);
+    }
+
+    /// Restart the language servers handling any of `languages`, so they pick
+    /// up their new configs
+    fn restart_language_servers_of(&self, languages: Vec<String>) {
+        let internal_command = self.common.internal_command;
+        let send = create_ext_action(
+            self.scope,
+            move |response: Result<ProxyResponse, RpcError>| {
+                let Ok(ProxyResponse::LanguageServersResponse { servers }) =
+                    response
+                else {
+                    return;
+                };
+                let volt_ids = servers
+                    .into_iter()
+                    .filter(|server| {
+                        languages
+                            .iter()
+                            .any(|language| server.languages.contains(language))
+                    })
+                    .map(|server| server.volt_id)
+                    .unique()
+                    .collect::<Vec<_>>();
+                if !volt_ids.is_empty() {
+                    internal_command
+                        .send(InternalCommand::RestartLanguageServers { volt_ids });
+                }
+            },
+        );
+        self.common.proxy.language_servers(None, send);
+    }
+
     fn show_message(&self, title: &str, message: &ShowMessageParams) {
-        self.messages.update(|messages| {
-            messages.push((title.to_string(), message.clone()));
+        self.show_message_with_actions(title, message, Vec::new());
+    }
+
+    fn show_message_with_actions(
+        &self,
+        title: &str,
+        message: &ShowMessageParams,
+        actions: Vec<AlertButton>,
+    ) {
+        self.notifications.notify(
+            title,
+            message.message.clone(),
+            message.typ,
+            actions,
+        );
+    }
+
+    /// A button of a notification that runs `cmd`
+    fn workbench_action(
+        &self,
+        text: &str,
+        cmd: LapceWorkbenchCommand,
+    ) -> AlertButton {
+        let workbench_command = self.common.workbench_command;
+        AlertButton {
+            text: text.to_string(),
+            action: Rc::new(move || workbench_command.send(cmd.clone())),
+        }
+    }
+
+    /// Show a plugin's message with its actions as the alert's buttons, and
+    /// answer the plugin with the one the user picked
+    fn show_message_request(
+        &self,
+        id: RequestId,
+        title: &str,
+        params: &ShowMessageRequestParams,
+    ) {
+        let internal_command = self.common.internal_command;
+        let buttons = params
+            .actions
+            .iter()
+            .flatten()
+            .map(|action| {
+                let core_rpc = self.proxy.core_rpc.clone();
+                let action = action.clone();
+                AlertButton {
+                    text: action.title.clone(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                        core_rpc.handle_response(
+                            id,
+                            Ok(CoreResponse::ShowMessageRequest {
+                                action: Some(action.clone()),
+                            }),
+                        );
+                    }),
+                }
+            })
+            .collect();
+        self.show_alert(title.to_string(), params.message.clone(), buttons);
+
+        let core_rpc = self.proxy.core_rpc.clone();
+        self.alert_data.on_cancel.set(Some(Rc::new(move || {
+            core_rpc.handle_response(
+                id,
+                Ok(CoreResponse::ShowMessageRequest { action: None }),
+            );
+        })));
+    }
+
+    /// Ask whether to download the language server the proxy found missing
+    fn offer_language_server_install(
+        &self,
+        language_id: &str,
+        name: &str,
+        version: &str,
+    ) {
+        let internal_command = self.common.internal_command;
+        let proxy = self.common.proxy.clone();
+        let install_language_id = language_id.to_string();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("Install {name} {version}?"),
+            msg: format!(
+                "No language server for {language_id} was found. {name} can be \
+                 installed into Lapce's data directory."
+            ),
+            buttons: vec![AlertButton {
+                text: "Install".to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    proxy.install_language_server(install_language_id.clone());
+                }),
+            }],
         });
     }
 }
@@ -2573,3 +3803,59 @@ fn open_uri(path: &Path) {
         }
     }
 }
+
+/// Call `closed` when `open` turns false after it was true, running `open` again
+/// whenever a signal it read changes
+fn notify_on_close(
+    cx: Scope,
+    open: impl Fn() -> bool + 'static,
+    closed: impl Fn() + 'static,
+) {
+    cx.create_effect(move |was_open: Option<bool>| {
+        let open = open();
+        let was_open = was_open.unwrap_or(false);
+        if was_open && !open {
+            closed();
+        }
+        was_open || open
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, path::PathBuf, rc::Rc};
+
+    use floem::reactive::Scope;
+
+    use super::notify_on_close;
+
+    #[test]
+    fn test_notify_on_close() {
+        let cx = Scope::new();
+        let path = PathBuf::from("COMMIT_EDITMSG");
+        // The documents of two editors, the first one opened for the path
+        let first = cx.create_rw_signal(path.clone());
+        let second = cx.create_rw_signal(PathBuf::from("other"));
+        let editors = cx.create_rw_signal(vec![first, second]);
+        let closed = Rc::new(Cell::new(false));
+        {
+            let closed = closed.clone();
+            notify_on_close(
+                cx,
+                move || {
+                    editors.with(|editors| {
+                        editors.iter().any(|doc| doc.with(|p| *p == path))
+                    })
+                },
+                move || closed.set(true),
+            );
+        }
+
+        second.set(PathBuf::from("another"));
+        assert!(!closed.get());
+
+        // The editor is reused for another file, without the editors changing
+        first.set(PathBuf::from("next"));
+        assert!(closed.get());
+    }
+}
@@ -62,12 +62,28 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     }
 }
 
+/// A running container of Docker or Podman
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct ContainerHost {
+    /// The program managing the container, like `docker` or `podman`
+    pub engine: String,
+    /// The name or the id of the container
+    pub container: String,
+}
+
+impl Display for ContainerHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.container, self.engine)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LapceWorkspaceType {
     Local,
     RemoteSSH(SshHost),
     #[cfg(windows)]
     RemoteWSL(WslHost),
+    RemoteContainer(ContainerHost),
 }
 
 impl LapceWorkspaceType {
@@ -79,10 +95,10 @@ pub fn is_remote(&self) -> bool {
         use LapceWorkspaceType::*;
 
         #[cfg(not(windows))]
-        return matches!(self, RemoteSSH(_));
+        return matches!(self, RemoteSSH(_) | RemoteContainer(_));
 
         #[cfg(windows)]
-        return matches!(self, RemoteSSH(_) | RemoteWSL(_));
+        return matches!(self, RemoteSSH(_) | RemoteWSL(_) | RemoteContainer(_));
     }
 }
 
@@ -97,6 +113,9 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             LapceWorkspaceType::RemoteWSL(remote) => {
                 write!(f, "{remote} (WSL)")
             }
+            LapceWorkspaceType::RemoteContainer(remote) => {
+                write!(f, "{}://{}", remote.engine, remote.container)
+            }
         }
     }
 }
@@ -125,9 +144,41 @@ pub fn display(&self) -> Option<String> {
             LapceWorkspaceType::RemoteWSL(remote) => {
                 format!(" [WSL: {}]", remote.host)
             }
+            LapceWorkspaceType::RemoteContainer(remote) => {
+                format!(" [Container: {}]", remote.container)
+            }
         };
         Some(format!("{path}{remote}"))
     }
+
+    /// Whether it's the same folder on the same host as `other`, whenever either
+    /// was last opened
+    pub fn is_same(&self, other: &LapceWorkspace) -> bool {
+        self.path == other.path && self.kind == other.kind
+    }
+
+    /// Whether the folder has a `.lapce` folder of settings or tasks in it, which
+    /// can only be told for local workspaces
+    pub fn has_local_settings(&self) -> bool {
+        matches!(self.kind, LapceWorkspaceType::Local)
+            && self
+                .path
+                .as_ref()
+                .is_some_and(|path| path.join(".lapce").is_dir())
+    }
+
+    /// The workspace to read the settings of, which is one without a folder when
+    /// the workspace isn't trusted, so that the settings in it are left out
+    pub fn settings_workspace(&self, trusted: bool) -> LapceWorkspace {
+        if trusted {
+            self.clone()
+        } else {
+            LapceWorkspace {
+                path: None,
+                ..self.clone()
+            }
+        }
+    }
 }
 
 impl Default for LapceWorkspace {
@@ -156,4 +207,6 @@ pub struct WorkspaceInfo {
     pub split: SplitInfo,
     pub panel: PanelInfo,
     pub breakpoints: HashMap<PathBuf, Vec<LapceBreakpoint>>,
+    #[serde(default)]
+    pub watch_expressions: Vec<String>,
 }
@@ -0,0 +1,207 @@
+//! The workspace edits of language servers and plugins, which can change
+//! several files and create, rename or delete them
+
+use std::{path::PathBuf, rc::Rc};
+
+use lsp_types::{
+    DocumentChangeOperation, DocumentChanges, OneOf, ResourceOp, TextEdit,
+    WorkspaceEdit,
+};
+
+use crate::doc::Doc;
+
+/// A change of a workspace edit, applied in order
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorkspaceEditStep {
+    Edit {
+        path: PathBuf,
+        /// The version of the document the edits are for, if the server
+        /// asked for it to be checked
+        version: Option<i32>,
+        edits: Vec<TextEdit>,
+    },
+    Create {
+        path: PathBuf,
+        overwrite: bool,
+        ignore_if_exists: bool,
+    },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+        ignore_if_exists: bool,
+    },
+    Delete {
+        path: PathBuf,
+    },
+}
+
+/// A change that was applied, with what's needed to undo it
+#[derive(Clone)]
+pub enum AppliedStep {
+    /// The edits that took the document to `rev`, saved if it wasn't open
+    /// in an editor
+    Edit {
+        doc: Rc<Doc>,
+        rev: u64,
+        saved: bool,
+    },
+    Create {
+        path: PathBuf,
+    },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// Deleted files are moved to the trash, which isn't undone
+    Delete,
+}
+
+/// The changes of the workspace edit in the order they're applied
+pub fn workspace_edit_steps(edit: &WorkspaceEdit) -> Vec<WorkspaceEditStep> {
+    let mut steps = Vec::new();
+    if let Some(changes) = edit.document_changes.as_ref() {
+        match changes {
+            DocumentChanges::Edits(edits) => {
+                for edit in edits {
+                    steps.extend(edit_step(edit));
+                }
+            }
+            DocumentChanges::Operations(ops) => {
+                for op in ops {
+                    match op {
+                        DocumentChangeOperation::Edit(edit) => {
+                            steps.extend(edit_step(edit));
+                        }
+                        DocumentChangeOperation::Op(op) => {
+                            steps.extend(resource_step(op));
+                        }
+                    }
+                }
+            }
+        }
+    } else if let Some(changes) = edit.changes.as_ref() {
+        for (url, edits) in changes {
+            if let Ok(path) = url.to_file_path() {
+                steps.push(WorkspaceEditStep::Edit {
+                    path,
+                    version: None,
+                    edits: edits.clone(),
+                });
+            }
+        }
+    }
+    steps
+}
+
+fn edit_step(edit: &lsp_types::TextDocumentEdit) -> Option<WorkspaceEditStep> {
+    let path = edit.text_document.uri.to_file_path().ok()?;
+    Some(WorkspaceEditStep::Edit {
+        path,
+        version: edit.text_document.version,
+        edits: edit
+            .edits
+            .iter()
+            .map(|e| match e {
+                OneOf::Left(e) => e.clone(),
+                OneOf::Right(e) => e.text_edit.clone(),
+            })
+            .collect(),
+    })
+}
+
+fn resource_step(op: &ResourceOp) -> Option<WorkspaceEditStep> {
+    Some(match op {
+        ResourceOp::Create(create) => {
+            let options = create.options.as_ref();
+            WorkspaceEditStep::Create {
+                path: create.uri.to_file_path().ok()?,
+                overwrite: options.and_then(|o| o.overwrite).unwrap_or(false),
+                ignore_if_exists: options
+                    .and_then(|o| o.ignore_if_exists)
+                    .unwrap_or(false),
+            }
+        }
+        ResourceOp::Rename(rename) => WorkspaceEditStep::Rename {
+            from: rename.old_uri.to_file_path().ok()?,
+            to: rename.new_uri.to_file_path().ok()?,
+            ignore_if_exists: rename
+                .options
+                .as_ref()
+                .and_then(|o| o.ignore_if_exists)
+                .unwrap_or(false),
+        },
+        ResourceOp::Delete(delete) => WorkspaceEditStep::Delete {
+            path: delete.uri.to_file_path().ok()?,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use lsp_types::{
+        CreateFile, DocumentChangeOperation, DocumentChanges, OneOf,
+        OptionalVersionedTextDocumentIdentifier, Position, Range, RenameFile,
+        ResourceOp, TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+    };
+
+    use super::{workspace_edit_steps, WorkspaceEditStep};
+
+    #[test]
+    fn test_workspace_edit_steps() {
+        let (a, b) = if cfg!(windows) {
+            ("C:\\a.rs", "C:\\b.rs")
+        } else {
+            ("/a.rs", "/b.rs")
+        };
+        let edit = TextEdit {
+            range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+            new_text: "fn main() {}".to_string(),
+        };
+        let workspace_edit = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(vec![
+                DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                    uri: Url::from_file_path(a).unwrap(),
+                    options: None,
+                    annotation_id: None,
+                })),
+                DocumentChangeOperation::Edit(TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: Url::from_file_path(a).unwrap(),
+                        version: Some(1),
+                    },
+                    edits: vec![OneOf::Left(edit.clone())],
+                }),
+                DocumentChangeOperation::Op(ResourceOp::Rename(RenameFile {
+                    old_uri: Url::from_file_path(a).unwrap(),
+                    new_uri: Url::from_file_path(b).unwrap(),
+                    options: None,
+                    annotation_id: None,
+                })),
+            ])),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            workspace_edit_steps(&workspace_edit),
+            vec![
+                WorkspaceEditStep::Create {
+                    path: PathBuf::from(a),
+                    overwrite: false,
+                    ignore_if_exists: false,
+                },
+                WorkspaceEditStep::Edit {
+                    path: PathBuf::from(a),
+                    version: Some(1),
+                    edits: vec![edit],
+                },
+                WorkspaceEditStep::Rename {
+                    from: PathBuf::from(a),
+                    to: PathBuf::from(b),
+                    ignore_if_exists: false,
+                },
+            ]
+        );
+    }
+}
@@ -11,6 +11,12 @@ pub fn home_dir() -> Option<PathBuf> {
         BaseDirs::new().map(|d| PathBuf::from(d.home_dir()))
     }
 
+    /// Get the path to the directory VS Code keeps its user settings and
+    /// keybindings in
+    pub fn vscode_user_directory() -> Option<PathBuf> {
+        BaseDirs::new().map(|d| d.config_dir().join("Code").join("User"))
+    }
+
     #[cfg(not(feature = "portable"))]
     fn project_dirs() -> Option<ProjectDirs> {
         ProjectDirs::from("dev", "lapce", NAME)
@@ -169,4 +175,18 @@ pub fn grammars_directory() -> Option<PathBuf> {
             None
         }
     }
+
+    /// Where the language servers downloaded by Lapce are installed
+    pub fn language_servers_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("language-servers");
+            if !dir.exists() {
+                let _ = std::fs::create_dir(&dir);
+            }
+
+            Some(dir)
+        } else {
+            None
+        }
+    }
 }
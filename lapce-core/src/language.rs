@@ -1,11 +1,14 @@
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
-use lapce_rpc::style::{LineStyle, Style};
-use once_cell::sync::Lazy;
+use lapce_rpc::{
+    plugin::{VoltLanguage, VoltMetadata},
+    style::{LineStyle, Style},
+};
+use once_cell::sync::OnceCell;
 use strum_macros::{AsRefStr, Display, EnumMessage, EnumString, IntoStaticStr};
 use tracing::{debug, error};
 use tree_sitter::{Point, TreeCursor};
@@ -15,7 +18,8 @@
     syntax::highlight::{HighlightConfiguration, HighlightIssue},
 };
 
-pub static RUNTIME_LANGUAGES: Lazy<Vec<SyntaxProperties>> = Lazy::new(Vec::new);
+/// The languages added by volts, see [`LapceLanguage::load_runtime_languages`]
+static RUNTIME_LANGUAGES: OnceCell<Vec<RuntimeLanguage>> = OnceCell::new();
 
 #[allow(dead_code)]
 const DEFAULT_CODE_LENS_LIST: &[&str] = &["source_file"];
@@ -68,8 +72,7 @@ macro_rules! comment_properties {
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug, PartialOrd, Ord, Default)]
 pub struct SyntaxProperties {
-    /// An extra check to make sure that the array elements are in the correct order.  
-    /// If this id does not match the enum value, a panic will happen with a debug assertion message.
+    /// The language these are the properties of
     id: LapceLanguage,
 
     /// All tokens that can be used for comments in language
@@ -143,8 +146,75 @@ struct CommentProperties {
     multi_line_prefix: Option<&'static str>,
 }
 
-/// NOTE: Keep the enum variants "fieldless" so they can cast to usize as array
-/// indices into the LANGUAGES array.  See method `LapceLanguage::properties`.
+/// A language volts add with `[[languages]]` sections in their `volt.toml`.
+/// The strings are leaked, the languages are loaded once and live as long as
+/// the builtin ones.
+#[derive(Debug)]
+struct RuntimeLanguage {
+    /// The language id given to language servers
+    id: &'static str,
+    name: &'static str,
+    /// Where the grammar library is loaded from
+    grammars_dir: PathBuf,
+    /// Where `highlights.scm` and `injections.scm` are read from
+    queries_dir: PathBuf,
+    properties: SyntaxProperties,
+}
+
+impl RuntimeLanguage {
+    fn new(index: u16, dir: &Path, language: &VoltLanguage) -> Self {
+        fn leak(s: &str) -> &'static str {
+            Box::leak(s.to_string().into_boxed_str())
+        }
+        fn leak_all(strings: &[String]) -> &'static [&'static str] {
+            Box::leak(
+                strings
+                    .iter()
+                    .map(|s| leak(&s.to_lowercase()))
+                    .collect::<Vec<_>>()
+                    .into_boxed_slice(),
+            )
+        }
+
+        let id = leak(&language.id);
+        let (multi_line_start, multi_line_end) = language
+            .block_comment
+            .as_ref()
+            .map(|(start, end)| (Some(leak(start)), Some(leak(end))))
+            .unwrap_or_default();
+        let queries_dir = match language.queries.as_ref() {
+            Some(queries) => dir.join(queries),
+            None => dir.join("queries").join(&language.id),
+        };
+
+        Self {
+            id,
+            name: language.name.as_deref().map(leak).unwrap_or(id),
+            grammars_dir: dir.join("grammars"),
+            queries_dir,
+            properties: SyntaxProperties {
+                id: LapceLanguage::Runtime(index),
+                comment: CommentProperties {
+                    single_line_start: language.line_comment.as_deref().map(leak),
+                    single_line_end: None,
+                    multi_line_start,
+                    multi_line_end,
+                    multi_line_prefix: None,
+                },
+                indent: leak(language.indent.as_deref().unwrap_or("    ")),
+                files: leak_all(&language.files),
+                extensions: leak_all(&language.extensions),
+                tree_sitter: TreeSitterProperties {
+                    grammar: language.grammar.as_deref().map(leak),
+                    ..TreeSitterProperties::DEFAULT
+                },
+            },
+        }
+    }
+}
+
+/// NOTE: Every builtin variant needs an element in the LANGUAGES array, keep
+/// them in the same order.  See method `LapceLanguage::properties`.
 ///
 /// Do not assign values to the variants because the number of variants and
 /// number of elements in the LANGUAGES array change as different features
@@ -284,10 +354,15 @@ pub enum LapceLanguage {
     Yaml,
     #[strum(message = "Zig")]
     Zig,
+
+    /// A language added by a volt, the index of its entry in the languages
+    /// loaded by [`LapceLanguage::load_runtime_languages`]
+    #[strum(disabled)]
+    Runtime(u16),
 }
 
-/// NOTE: Elements in the array must be in the same order as the enum variants of
-/// `LapceLanguage` as they will be accessed using the enum variants as indices.
+/// NOTE: Keep the elements in the same order as the enum variants of
+/// `LapceLanguage`, languages added by volts aren't part of it.
 const LANGUAGES: &[SyntaxProperties] = &[
     // Languages
     SyntaxProperties {
@@ -976,7 +1051,8 @@ fn from_path_raw(path: &Path) -> Option<LapceLanguage> {
             .and_then(|s| s.to_str().map(|s| s.to_lowercase()));
         // NOTE: This is a linear search.  It is assumed that this function
         // isn't called in any tight loop.
-        for properties in LANGUAGES {
+        let runtime = Self::runtime_languages().iter().map(|l| &l.properties);
+        for properties in LANGUAGES.iter().chain(runtime) {
             if properties
                 .files
                 .iter()
@@ -1000,6 +1076,12 @@ pub fn from_name(name: &str) -> Option<LapceLanguage> {
         match LapceLanguage::from_str(name.to_lowercase().as_str()) {
             Ok(v) => Some(v),
             Err(e) => {
+                if let Some(l) = Self::runtime_languages().iter().find(|l| {
+                    l.id.eq_ignore_ascii_case(name)
+                        || l.name.eq_ignore_ascii_case(name)
+                }) {
+                    return Some(l.properties.id);
+                }
                 debug!("failed parsing {name} LapceLanguage: {e}");
                 None
             }
@@ -1014,28 +1096,81 @@ pub fn languages() -> Vec<&'static str> {
                 langs.push(lang)
             }
         }
+        langs.extend(Self::runtime_languages().iter().map(|l| l.name));
         langs
     }
 
-    // NOTE: Instead of using `&LANGUAGES[*self as usize]` directly, the
-    // `debug_assertion` gives better feedback should something has gone wrong
-    // badly.
-    fn properties(&self) -> &SyntaxProperties {
-        let i = *self as usize;
-        let l = &LANGUAGES[i];
-        debug_assert!(
-            l.id == *self,
-            "LANGUAGES[{i}]: Setting::id mismatch: {:?} != {:?}",
-            l.id,
-            self
-        );
-        l
+    /// Loads the languages of the volts which aren't disabled, so that files
+    /// are matched against them too. Only the first call loads anything, the
+    /// languages of volts installed afterwards are known after a restart.
+    pub fn load_runtime_languages(volts: &[VoltMetadata]) {
+        RUNTIME_LANGUAGES.get_or_init(|| {
+            let mut languages = Vec::new();
+            for meta in volts {
+                let Some(dir) = meta.dir.as_ref() else {
+                    continue;
+                };
+                for language in meta.languages.iter().flatten() {
+                    let Ok(index) = u16::try_from(languages.len()) else {
+                        break;
+                    };
+                    languages.push(RuntimeLanguage::new(index, dir, language));
+                }
+            }
+            languages
+        });
+    }
+
+    fn runtime_languages() -> &'static [RuntimeLanguage] {
+        RUNTIME_LANGUAGES
+            .get()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn runtime_language(&self) -> Option<&'static RuntimeLanguage> {
+        match self {
+            LapceLanguage::Runtime(i) => Self::runtime_languages().get(*i as usize),
+            _ => None,
+        }
+    }
+
+    /// The language id given to language servers of a language added by a
+    /// volt, the builtin ones are known by the proxy
+    pub fn runtime_id(&self) -> Option<&'static str> {
+        self.runtime_language().map(|l| l.id)
+    }
+
+    // NOTE: This is a linear search, variants with fields can't be cast to
+    // array indices.  There are few enough languages for it not to matter.
+    fn properties(&self) -> &'static SyntaxProperties {
+        if let LapceLanguage::Runtime(_) = self {
+            return self
+                .runtime_language()
+                .map(|l| &l.properties)
+                .unwrap_or(&LANGUAGES[0]);
+        }
+        let l = LANGUAGES.iter().find(|l| l.id == *self);
+        debug_assert!(l.is_some(), "{self:?} is missing from LANGUAGES");
+        l.unwrap_or(&LANGUAGES[0])
     }
 
     pub fn name(&self) -> &'static str {
+        if let Some(l) = self.runtime_language() {
+            return l.name;
+        }
         strum::EnumMessage::get_message(self).unwrap_or(self.into())
     }
 
+    /// The name to save the language as, which [`LapceLanguage::from_name`]
+    /// parses back, unlike display names like "C++"
+    pub fn key(&self) -> &'static str {
+        if let Some(l) = self.runtime_language() {
+            return l.id;
+        }
+        self.into()
+    }
+
     pub fn sticky_header_tags(&self) -> &[&'static str] {
         self.properties().tree_sitter.sticky_headers
     }
@@ -1055,6 +1190,10 @@ fn get_grammar(&self) -> Option<tree_sitter::Language> {
         let props = self.properties();
         let grammar_name = self.grammar_name();
 
+        if let Some(l) = self.runtime_language() {
+            return self::load_grammar(&grammar_name, &l.grammars_dir).ok();
+        }
+
         #[cfg(unix)]
         {
             let grammars_dir = Path::new(Self::SYSTEM_GRAMMARS_DIRECTORY);
@@ -1083,7 +1222,8 @@ fn query_name(&self) -> String {
         self.properties()
             .tree_sitter
             .query
-            .unwrap_or(self.properties().id.as_ref())
+            .or(self.runtime_id())
+            .unwrap_or_else(|| self.properties().id.as_ref())
             .to_lowercase()
     }
 
@@ -1091,11 +1231,25 @@ fn grammar_name(&self) -> String {
         self.properties()
             .tree_sitter
             .grammar
-            .unwrap_or(self.properties().id.as_ref())
+            .or(self.runtime_id())
+            .unwrap_or_else(|| self.properties().id.as_ref())
             .to_lowercase()
     }
 
     fn get_grammar_query(&self) -> (String, String) {
+        if let Some(l) = self.runtime_language() {
+            return (
+                std::fs::read_to_string(
+                    l.queries_dir.join(Self::HIGHLIGHTS_QUERIES_FILE_NAME),
+                )
+                .unwrap_or_default(),
+                std::fs::read_to_string(
+                    l.queries_dir.join(Self::HIGHLIGHTS_INJECTIONS_FILE_NAME),
+                )
+                .unwrap_or_default(),
+            );
+        }
+
         let query_name = self.query_name();
 
         // Try reading highlights from user config dir
@@ -1152,7 +1306,7 @@ pub(crate) fn new_highlight_config(
         match HighlightConfiguration::new(grammar, &query, &injection, "") {
             Ok(x) => Ok(x),
             Err(x) => {
-                let str = format!("Encountered {x:?} while trying to construct HighlightConfiguration for {}", strum::EnumMessage::get_message(self).unwrap_or(self.as_ref()));
+                let str = format!("Encountered {x:?} while trying to construct HighlightConfiguration for {}", self.name());
                 error!("{str}");
                 Err(HighlightIssue::Error(str))
             }
@@ -1319,11 +1473,70 @@ pub(crate) fn walk_tree_bracket_ast(
 mod tests {
     use std::path::PathBuf;
 
-    use super::LapceLanguage;
+    use lapce_rpc::plugin::{VoltLanguage, VoltMetadata};
+
+    use super::{LapceLanguage, LANGUAGES};
 
     #[test]
     fn test_lanaguage_from_path() {
         let l = LapceLanguage::from_path(&PathBuf::new().join("test.rs"));
         assert_eq!(l, LapceLanguage::Rust);
     }
+
+    #[test]
+    fn test_language_key() {
+        for l in LANGUAGES.iter().map(|l| l.id) {
+            assert_eq!(LapceLanguage::from_name(l.key()), Some(l), "{}", l.name());
+        }
+        assert_eq!(LapceLanguage::Cpp.key(), "Cpp");
+    }
+
+    #[test]
+    fn test_runtime_language() {
+        let meta = VoltMetadata {
+            name: "gleam".to_string(),
+            version: "0.1".to_string(),
+            display_name: "Gleam".to_string(),
+            author: "Author".to_string(),
+            description: "Gleam support".to_string(),
+            icon: None,
+            repository: None,
+            wasm: None,
+            color_themes: None,
+            icon_themes: None,
+            dir: Some(PathBuf::from("gleam")),
+            activation: None,
+            config: None,
+            capabilities: None,
+            languages: Some(vec![VoltLanguage {
+                id: "gleam".to_string(),
+                name: Some("Gleam".to_string()),
+                extensions: vec!["gleam".to_string()],
+                files: Vec::new(),
+                line_comment: Some("//".to_string()),
+                block_comment: None,
+                indent: Some("  ".to_string()),
+                grammar: None,
+                queries: None,
+                language_server: None,
+            }]),
+        };
+        LapceLanguage::load_runtime_languages(&[meta]);
+
+        let l = LapceLanguage::from_path(&PathBuf::new().join("test.gleam"));
+        assert_eq!(l, LapceLanguage::Runtime(0));
+        assert_eq!(l.name(), "Gleam");
+        assert_eq!(l.runtime_id(), Some("gleam"));
+        assert_eq!(l.comment_token(), "//");
+        assert_eq!(l.indent_unit(), "  ");
+        assert_eq!(l.grammar_name(), "gleam");
+        assert_eq!(l.query_name(), "gleam");
+        assert_eq!(LapceLanguage::from_name("gleam"), Some(l));
+        assert_eq!(LapceLanguage::from_name(l.key()), Some(l));
+        assert!(LapceLanguage::languages().contains(&"Gleam"));
+        assert_eq!(
+            LapceLanguage::from_path(&PathBuf::new().join("test.rs")),
+            LapceLanguage::Rust
+        );
+    }
 }
@@ -167,23 +167,15 @@ pub fn disable(&self) {
         *(self.active.borrow_mut()) = false;
     }*/
 
-    pub fn update_code(
-        &mut self,
-        code: String,
-        buffer: &Buffer,
-        syntax: Option<&Syntax>,
-    ) {
+    /// Work out the bracket colors of the buffer, from the syntax tree when
+    /// there is one, as the buffer is only copied out of its rope otherwise
+    pub fn update_code(&mut self, buffer: &Buffer, syntax: Option<&Syntax>) {
         let palette = vec![
             "bracket.color.1".to_string(),
             "bracket.color.2".to_string(),
             "bracket.color.3".to_string(),
         ];
-        if self.active
-            && code
-                .chars()
-                .fold(0, |i, c| if c == '\n' { i + 1 } else { i })
-                < self.limit as usize
-        {
+        if self.active && buffer.last_line() < self.limit as usize {
             self.bracket_pos = HashMap::new();
             if let Some(syntax) = syntax {
                 if let Some(layers) = &syntax.layers {
@@ -202,7 +194,7 @@ pub fn update_code(
                     }
                 }
             } else {
-                self.code = code.chars().collect();
+                self.code = buffer.to_string().chars().collect();
                 self.cur = 0;
                 self.parse();
                 let mut pos_vec = vec![];
@@ -425,6 +417,10 @@ fn parse(
     }
 }
 
+/// The trees and ranges of the layers before an update, see
+/// [`SyntaxLayers::edited_layers`]
+type OldLayers = HashMap<LayerId, (Option<Tree>, Vec<std::ops::Range<usize>>)>;
+
 #[derive(Clone)]
 pub struct SyntaxLayers {
     layers: HopSlotMap<LayerId, LanguageLayer>,
@@ -736,6 +732,67 @@ pub fn try_tree(&self) -> Option<&Tree> {
         self.layers[self.root].try_tree()
     }
 
+    /// The tree of each layer edited with `edits`, and the ranges the layer covers,
+    /// to find out what [`SyntaxLayers::update`] changes with the same edits. The
+    /// layers that weren't parsed at `rev` have no tree to compare with.
+    fn edited_layers(&self, rev: u64, edits: &[SyntaxEdit]) -> OldLayers {
+        self.layers
+            .iter()
+            .map(|(id, layer)| {
+                let tree =
+                    layer
+                        .tree
+                        .as_ref()
+                        .filter(|_| layer.rev == rev)
+                        .map(|tree| {
+                            let mut tree = tree.clone();
+                            for edit in edits.iter().flat_map(|edit| &edit.0) {
+                                tree.edit(edit);
+                            }
+                            tree
+                        });
+                let ranges = layer
+                    .ranges
+                    .iter()
+                    .map(|range| range.start_byte..range.end_byte)
+                    .collect();
+                (id, (tree, ranges))
+            })
+            .collect()
+    }
+
+    /// The ranges whose syntax changed since the layers were `old_layers`: those
+    /// the trees of the layers found changed, and the whole of the layers added
+    /// since. The ranges of the layers removed since are given apart, as they
+    /// are from before the edits.
+    fn changed_since(
+        &self,
+        mut old_layers: OldLayers,
+    ) -> (Vec<std::ops::Range<usize>>, Vec<std::ops::Range<usize>>) {
+        let mut changed = Vec::new();
+        for (id, layer) in self.layers.iter() {
+            let old_tree = old_layers.remove(&id).and_then(|(tree, _)| tree);
+            match (old_tree, layer.try_tree()) {
+                (Some(old_tree), Some(tree)) => changed.extend(
+                    old_tree
+                        .changed_ranges(tree)
+                        .map(|range| range.start_byte..range.end_byte),
+                ),
+                _ => changed.extend(
+                    layer
+                        .ranges
+                        .iter()
+                        .map(|range| range.start_byte..range.end_byte),
+                ),
+            }
+        }
+        let removed = old_layers
+            .into_values()
+            .flat_map(|(_, ranges)| ranges)
+            .collect();
+        (changed, removed)
+    }
+
     /// Iterate over the highlighted regions for a given slice of source code.
     pub fn highlight_iter<'a>(
         &'a self,
@@ -883,6 +940,9 @@ pub fn from_language(language: LapceLanguage) -> Syntax {
         }
     }
 
+    /// Reparse the text, reusing the previous tree when `edits` take it to
+    /// `new_rev`. The highlights are then only redone for the lines the
+    /// edits touched and the ones whose syntax the edits changed.
     pub fn parse(
         &mut self,
         new_rev: u64,
@@ -894,47 +954,40 @@ pub fn parse(
             None => return,
         };
         let edits = edits.filter(|edits| new_rev == self.rev + edits.len() as u64);
+
+        // The layers before the reparse, edited the same way, so they can tell
+        // what the reparse changed
+        let old_layers = edits.map(|edits| layers.edited_layers(self.rev, edits));
+
         let _ =
             layers.update(self.rev, new_rev, &new_text, edits, &self.cancel_flag);
         let tree = layers.try_tree();
 
-        let styles = if tree.is_some() {
-            let mut current_hl: Option<Highlight> = None;
-            let mut highlights: SpansBuilder<Style> =
-                SpansBuilder::new(new_text.len());
-
-            // TODO: Should we be ignoring highlight errors via flattening them?
-            for highlight in layers
-                .highlight_iter(
-                    &new_text,
-                    Some(0..new_text.len()),
-                    Some(&self.cancel_flag),
-                )
-                .flatten()
+        let styles = match (tree, old_layers, edits, self.styles.take()) {
+            // The previous highlights were shifted along with each edit, so only
+            // the changed ranges are out of date
+            (Some(_), Some(old_layers), Some(edits), Some(mut styles))
+                if styles.len() == new_text.len() =>
             {
-                match highlight {
-                    HighlightEvent::Source { start, end } => {
-                        if let Some(hl) = current_hl {
-                            if let Some(hl) = SCOPES.get(hl.0) {
-                                highlights.add_span(
-                                    Interval::new(start, end),
-                                    Style {
-                                        fg_color: Some(hl.to_string()),
-                                    },
-                                );
-                            }
-                        }
-                    }
-                    HighlightEvent::HighlightStart(hl) => {
-                        current_hl = Some(hl);
-                    }
-                    HighlightEvent::HighlightEnd => current_hl = None,
+                let (changed, removed) = layers.changed_since(old_layers);
+                for range in changed_ranges(&new_text, edits, changed, removed) {
+                    let highlights = Self::highlights(
+                        layers,
+                        &new_text,
+                        &range,
+                        &self.cancel_flag,
+                    );
+                    styles.edit(Interval::new(range.start, range.end), highlights);
                 }
+                Some(styles)
             }
-
-            Some(highlights.build())
-        } else {
-            None
+            (Some(_), ..) => Some(Self::highlights(
+                layers,
+                &new_text,
+                &(0..new_text.len()),
+                &self.cancel_flag,
+            )),
+            (None, ..) => None,
         };
 
         let normal_lines = if let Some(tree) = tree {
@@ -960,6 +1013,49 @@ pub fn parse(
         self.text = new_text
     }
 
+    /// The highlights of the text in `range`, offset from its start
+    fn highlights(
+        layers: &SyntaxLayers,
+        text: &Rope,
+        range: &std::ops::Range<usize>,
+        cancel_flag: &AtomicUsize,
+    ) -> Spans<Style> {
+        let mut current_hl: Option<Highlight> = None;
+        let mut highlights: SpansBuilder<Style> = SpansBuilder::new(range.len());
+
+        // TODO: Should we be ignoring highlight errors via flattening them?
+        for highlight in layers
+            .highlight_iter(text, Some(range.clone()), Some(cancel_flag))
+            .flatten()
+        {
+            match highlight {
+                HighlightEvent::Source { start, end } => {
+                    if let Some(hl) = current_hl {
+                        if let Some(hl) = SCOPES.get(hl.0) {
+                            let start = start.clamp(range.start, range.end);
+                            let end = end.clamp(range.start, range.end);
+                            highlights.add_span(
+                                Interval::new(
+                                    start - range.start,
+                                    end - range.start,
+                                ),
+                                Style {
+                                    fg_color: Some(hl.to_string()),
+                                },
+                            );
+                        }
+                    }
+                }
+                HighlightEvent::HighlightStart(hl) => {
+                    current_hl = Some(hl);
+                }
+                HighlightEvent::HighlightEnd => current_hl = None,
+            }
+        }
+
+        highlights.build()
+    }
+
     pub fn update_lens_height(&mut self, line_height: usize, lens_height: usize) {
         self.lens = Self::lens_from_normal_lines(
             self.text.line_of_offset(self.text.len()) + 1,
@@ -1099,6 +1195,25 @@ pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         Some(offsets)
     }
 
+    /// The start and end offsets of the innermost function, method or closure
+    /// around `offset`
+    pub fn enclosing_function(&self, offset: usize) -> Option<(usize, usize)> {
+        let tree = self.layers.as_ref()?.try_tree()?;
+        let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
+        loop {
+            let kind = node.kind();
+            if node.is_named()
+                && !kind.contains("call")
+                && ["function", "method", "closure", "lambda"]
+                    .iter()
+                    .any(|k| kind.contains(k))
+            {
+                return Some((node.start_byte(), node.end_byte()));
+            }
+            node = node.parent()?;
+        }
+    }
+
     pub fn find_enclosing_parentheses(
         &self,
         offset: usize,
@@ -1172,8 +1287,70 @@ pub fn find_enclosing_pair(&self, offset: usize) -> Option<(usize, usize)> {
     }
 }
 
+/// The ranges of `text` whose highlights an incremental reparse can have
+/// changed: the text the edits inserted, the `changed` ranges whose syntax
+/// changed, and the `removed` ranges from before the edits of the injected
+/// layers that are gone, widened to whole lines, sorted and merged
+fn changed_ranges(
+    text: &Rope,
+    edits: &[SyntaxEdit],
+    changed: Vec<std::ops::Range<usize>>,
+    removed: Vec<std::ops::Range<usize>>,
+) -> Vec<std::ops::Range<usize>> {
+    let edits: Vec<&tree_sitter::InputEdit> =
+        edits.iter().flat_map(|edit| &edit.0).collect();
+
+    // Each edit's offsets are from after the edits before it, so they have to
+    // be moved by the edits after it
+    let shift = |mut offset: usize, later: &[&tree_sitter::InputEdit]| {
+        for edit in later {
+            if offset >= edit.old_end_byte {
+                offset =
+                    (offset - edit.old_end_byte).saturating_add(edit.new_end_byte);
+            } else if offset > edit.start_byte {
+                offset = edit.new_end_byte;
+            }
+        }
+        offset
+    };
+
+    let mut ranges: Vec<std::ops::Range<usize>> = edits
+        .iter()
+        .enumerate()
+        .map(|(i, edit)| {
+            let later = &edits[i + 1..];
+            shift(edit.start_byte, later)..shift(edit.new_end_byte, later)
+        })
+        .chain(changed)
+        .chain(removed.into_iter().map(|range| {
+            shift(range.start, &edits[..])..shift(range.end, &edits[..])
+        }))
+        .map(|range| {
+            let start = text
+                .offset_of_line(text.line_of_offset(range.start.min(text.len())));
+            let end_line = text.line_of_offset(range.end.min(text.len()));
+            let end = text.offset_of_line(end_line + 1).min(text.len());
+            start..end.max(start)
+        })
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<std::ops::Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
 #[cfg(test)]
 mod tests {
+    use lapce_xi_rope::RopeDelta;
+
     use super::*;
 
     #[test]
@@ -1208,4 +1385,108 @@ fn test_lens_iter() {
         assert_eq!(Some((90, 25)), iter.next());
         assert_eq!(None, iter.next());
     }
+
+    /// Insert `text` at `offset`, and give the edit of the syntax
+    fn insert(
+        rope: &mut Rope,
+        offset: usize,
+        text: &str,
+    ) -> (RopeDelta, SyntaxEdit) {
+        let delta = RopeDelta::simple_edit(
+            Interval::new(offset, offset),
+            Rope::from(text),
+            rope.len(),
+        );
+        let edit = SyntaxEdit::from_delta(rope, delta.clone());
+        *rope = delta.apply(rope);
+        (delta, edit)
+    }
+
+    /// Highlights as their ranges and colors
+    type FlatStyles = Vec<(usize, usize, Option<String>)>;
+
+    /// The highlights as ranges, with the adjacent ones of the same style joined
+    fn flat_styles(styles: &Spans<Style>) -> FlatStyles {
+        let mut flat: FlatStyles = Vec::new();
+        for (interval, style) in styles.iter() {
+            match flat.last_mut() {
+                Some(last)
+                    if last.1 == interval.start && last.2 == style.fg_color =>
+                {
+                    last.1 = interval.end;
+                }
+                _ => {
+                    flat.push((interval.start, interval.end, style.fg_color.clone()))
+                }
+            }
+        }
+        flat
+    }
+
+    /// The highlights of `text` after inserting each of `inserts` with an
+    /// incremental reparse, and those of the edited text parsed at once. None
+    /// when the grammar isn't there, since grammars are downloaded at runtime.
+    fn incremental_and_full(
+        language: LapceLanguage,
+        text: &str,
+        inserts: &[(usize, &str)],
+    ) -> Option<(FlatStyles, FlatStyles)> {
+        let mut rope = Rope::from(text);
+        let mut syntax = Syntax::from_language(language);
+        syntax.parse(0, rope.clone(), None);
+        syntax.styles.as_ref()?;
+
+        for (i, (offset, text)) in inserts.iter().enumerate() {
+            let (delta, edit) = insert(&mut rope, *offset, text);
+            // The editor shifts the highlights along with each edit
+            if let Some(styles) = syntax.styles.as_mut() {
+                styles.apply_shape(&delta);
+            }
+            syntax.parse(i as u64 + 1, rope.clone(), Some(&[edit]));
+        }
+
+        let mut full = Syntax::from_language(language);
+        full.parse(0, rope, None);
+        Some((
+            flat_styles(syntax.styles.as_ref()?),
+            flat_styles(full.styles.as_ref()?),
+        ))
+    }
+
+    #[test]
+    fn test_changed_ranges() {
+        let mut rope = Rope::from("a\nb\nc\nd\n");
+        let (_, edit) = insert(&mut rope, 0, "x");
+        // The removed range is the line of `c` before the edit
+        assert_eq!(
+            changed_ranges(&rope, &[edit], vec![7..8], vec![4..5]),
+            vec![0..3, 5..9]
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the Rust and Markdown grammars in the grammars directory"]
+    fn test_incremental_highlights() {
+        let rust = "fn a() {}\nfn b() {\n    println!(\"{}\", 1);\n}\nfn c() {}\n";
+        let cases: &[(LapceLanguage, &str, &[(usize, &str)])] = &[
+            // A comment opener changes the lines after it
+            (LapceLanguage::Rust, rust, &[(10, "/* ")]),
+            (LapceLanguage::Rust, rust, &[(10, "/* "), (46, " */")]),
+            // An edit inside the injected layer of a macro
+            (LapceLanguage::Rust, rust, &[(32, "\"a\", ")]),
+            // A comment opener inside a fenced code block of markdown
+            (
+                LapceLanguage::Markdown,
+                "# A\n\n```rust\nlet a = 1;\nlet b = 2;\n```\n\ntext\n",
+                &[(13, "/* ")],
+            ),
+        ];
+        for (language, text, inserts) in cases {
+            let (incremental, full) = incremental_and_full(*language, text, inserts)
+                .unwrap_or_else(|| {
+                    panic!("the {language:?} grammar isn't installed")
+                });
+            assert_eq!(incremental, full, "{language:?} {inserts:?}");
+        }
+    }
 }
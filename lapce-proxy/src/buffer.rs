@@ -1,20 +1,24 @@
 use std::{
     borrow::Cow,
+    collections::VecDeque,
     ffi::OsString,
     fs,
     fs::File,
     io::{Read, Write},
     path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
     time::SystemTime,
 };
 
 use anyhow::{anyhow, Result};
 use floem_editor_core::buffer::rope_text::CharIndicesJoin;
-use lapce_core::encoding::offset_utf8_to_utf16;
+use lapce_core::{encoding::offset_utf8_to_utf16, language::LapceLanguage};
 use lapce_rpc::buffer::BufferId;
 use lapce_xi_rope::{interval::IntervalBounds, rope::Rope, RopeDelta};
 use lsp_types::*;
 
+use crate::encoding::{hex_dump, FileEncoding};
+
 #[derive(Clone)]
 pub struct Buffer {
     pub language_id: &'static str,
@@ -24,24 +28,31 @@ pub struct Buffer {
     pub path: PathBuf,
     pub rev: u64,
     pub mod_time: Option<SystemTime>,
+    /// The encoding the file is read and written with
+    pub encoding: FileEncoding,
+    /// Whether the content is a hex dump of a binary file
+    pub hex: bool,
+    /// The content of the saves being written, and the encoding each is written
+    /// with, oldest first
+    pub saving: VecDeque<(Rope, FileEncoding)>,
 }
 
 impl Buffer {
     pub fn new(id: BufferId, path: PathBuf) -> Buffer {
-        let (s, read_only) = match load_file(&path) {
-            Ok(s) => (s, false),
-            Err(err) => match err.downcast_ref::<std::io::Error>() {
-                Some(err) => match err.kind() {
-                    std::io::ErrorKind::PermissionDenied => {
-                        ("Permission Denied".to_string(), true)
-                    }
-                    std::io::ErrorKind::NotFound => ("".to_string(), false),
-                    _ => ("Not Supported".to_string(), true),
-                },
-                None => ("Not Supported".to_string(), true),
-            },
-        };
-        let rope = Rope::from(s);
+        let (content, read_only, encoding) = read_buffer_content(&path, |_| {});
+        Buffer::with_content(id, path, &content, read_only, encoding)
+    }
+
+    /// A buffer for `path` holding `content`, which was already read from it in
+    /// `encoding`, or is a hex dump of it without one
+    pub fn with_content(
+        id: BufferId,
+        path: PathBuf,
+        content: &str,
+        read_only: bool,
+        encoding: Option<FileEncoding>,
+    ) -> Buffer {
+        let rope = Rope::from(content);
         let rev = u64::from(!rope.is_empty());
         let language_id = language_id_from_path(&path).unwrap_or("");
         let mod_time = get_mod_time(&path);
@@ -53,10 +64,25 @@ pub fn new(id: BufferId, path: PathBuf) -> Buffer {
             language_id,
             rev,
             mod_time,
+            encoding: encoding.unwrap_or_default(),
+            hex: encoding.is_none(),
+            saving: VecDeque::new(),
         }
     }
 
-    pub fn save(&mut self, rev: u64, create_parents: bool) -> Result<()> {
+    /// Read the buffer's file again, the way it was read when opened
+    pub fn read_file(&self) -> Result<String> {
+        if self.hex {
+            Ok(hex_dump(&fs::read(&self.path)?))
+        } else {
+            load_file_with_encoding(&self.path, self.encoding)
+        }
+    }
+
+    /// Start saving the buffer at `rev` with `encoding`, returning the content to
+    /// write. It's remembered until [`Buffer::finish_save`] is called once it's
+    /// written.
+    pub fn start_save(&mut self, rev: u64, encoding: FileEncoding) -> Result<Rope> {
         if self.read_only {
             return Err(anyhow!("can't save to read only file"));
         }
@@ -64,47 +90,20 @@ pub fn save(&mut self, rev: u64, create_parents: bool) -> Result<()> {
         if self.rev != rev {
             return Err(anyhow!("not the right rev"));
         }
-        let bak_extension = self.path.extension().map_or_else(
-            || OsString::from("bak"),
-            |ext| {
-                let mut ext = ext.to_os_string();
-                ext.push(".bak");
-                ext
-            },
-        );
-        let path = if self.path.is_symlink() {
-            self.path.canonicalize()?
-        } else {
-            self.path.clone()
-        };
-        let new_file = !path.exists();
-
-        let bak_file_path = &path.with_extension(bak_extension);
-        if !new_file {
-            fs::copy(&path, bak_file_path)?;
-        }
-
-        if create_parents {
-            if let Some(parent) = path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-        }
 
-        let mut f = fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&path)?;
-        for chunk in self.rope.iter_chunks(..self.rope.len()) {
-            f.write_all(chunk.as_bytes())?;
-        }
+        self.saving.push_back((self.rope.clone(), encoding));
+        Ok(self.rope.clone())
+    }
 
-        self.mod_time = get_mod_time(&path);
-        if !new_file {
-            fs::remove_file(bak_file_path)?;
+    /// Finish the oldest save that's being written, returning the content it wrote.
+    /// A written save's encoding becomes the buffer's.
+    pub fn finish_save(&mut self, written: bool) -> Option<Rope> {
+        let (rope, encoding) = self.saving.pop_front()?;
+        if written {
+            self.mod_time = get_mod_time(&self.path);
+            self.encoding = encoding;
         }
-
-        Ok(())
+        Some(rope)
     }
 
     pub fn update(
@@ -181,6 +180,208 @@ pub fn is_empty(&self) -> bool {
     }
 }
 
+/// The content to open `path` with, whether it's read only, and the file's encoding.
+/// A file that couldn't be read is read only, and so is a binary file, whose content
+/// is a hex dump without an encoding. `preview` is given the first lines of a large
+/// file before the rest of it is read.
+pub fn read_buffer_content(
+    path: &Path,
+    preview: impl FnOnce(&str),
+) -> (String, bool, Option<FileEncoding>) {
+    let (content, read_only) = match load_file_with_preview(path, preview) {
+        Ok((s, Some(encoding))) => return (s, false, Some(encoding)),
+        Ok((s, None)) => return (s, true, None),
+        Err(err) => match err.downcast_ref::<std::io::Error>() {
+            Some(err) => match err.kind() {
+                std::io::ErrorKind::PermissionDenied => {
+                    ("Permission Denied".to_string(), true)
+                }
+                std::io::ErrorKind::NotFound => ("".to_string(), false),
+                _ => ("Not Supported".to_string(), true),
+            },
+            None => ("Not Supported".to_string(), true),
+        },
+    };
+    (content, read_only, Some(FileEncoding::default()))
+}
+
+/// Files at least this large have their start previewed while the rest is read
+const PREVIEW_THRESHOLD: u64 = 4 * 1024 * 1024;
+/// How much of a large file is read for its preview
+const PREVIEW_LEN: u64 = 64 * 1024;
+
+/// Read `path`, detecting its encoding, and giving `preview` the complete lines at
+/// the start of the file first when it's large. A binary file is read as a hex dump,
+/// without an encoding.
+pub fn load_file_with_preview(
+    path: &Path,
+    preview: impl FnOnce(&str),
+) -> Result<(String, Option<FileEncoding>)> {
+    let mut file = File::open(path)?;
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut buffer = Vec::with_capacity(len as usize);
+    if len >= PREVIEW_THRESHOLD {
+        (&mut file).take(PREVIEW_LEN).read_to_end(&mut buffer)?;
+        if let Some(text) = preview_text(&buffer) {
+            preview(text);
+        }
+    }
+    file.read_to_end(&mut buffer)?;
+
+    Ok(match FileEncoding::detect(buffer) {
+        Ok((content, encoding)) => (content, Some(encoding)),
+        Err(bytes) => (hex_dump(&bytes), None),
+    })
+}
+
+/// Read `path`, decoding it with `encoding`
+pub fn load_file_with_encoding(
+    path: &Path,
+    encoding: FileEncoding,
+) -> Result<String> {
+    let bytes = fs::read(path)?;
+    Ok(encoding.decode(&bytes))
+}
+
+/// The complete lines at the start of `bytes`, if they are UTF-8 text. Other
+/// encodings aren't previewed.
+fn preview_text(bytes: &[u8]) -> Option<&str> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    if bytes.contains(&0) {
+        return None;
+    }
+    let end = bytes.iter().rposition(|b| *b == b'\n')? + 1;
+    std::str::from_utf8(&bytes[..end]).ok()
+}
+
+/// Write `rope` to `path` in `encoding`, replacing the file at once so that it's never
+/// left half written. The content is written to a temporary file next to it, which is
+/// synced to disk to catch write errors, given the permissions and ownership of the
+/// file it replaces, and then renamed over it.
+/// A file that can't be written to isn't saved, even though the directory it's in would
+/// allow replacing it. A file with several hard links is written in place, since
+/// replacing it would split it from the other links.
+pub fn write_file(
+    path: &Path,
+    rope: &Rope,
+    encoding: FileEncoding,
+    create_parents: bool,
+) -> Result<()> {
+    static TEMP_ID: AtomicU64 = AtomicU64::new(0);
+
+    // Write through symlinks rather than replacing them
+    let path = if path.is_symlink() {
+        path.canonicalize()?
+    } else {
+        path.to_path_buf()
+    };
+
+    if create_parents {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    if let Ok(meta) = fs::metadata(&path) {
+        if meta.permissions().readonly() || !is_writable(&path) {
+            return Err(anyhow!("{} is read only", path.display()));
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            if meta.nlink() > 1 {
+                return write_in_place(&path, rope, encoding);
+            }
+        }
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} isn't a file path", path.display()))?;
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        TEMP_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = write_temp_file(&path, &temp_path, rope, encoding)
+        .and_then(|()| fs::rename(&temp_path, &path).map_err(Into::into));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn write_temp_file(
+    path: &Path,
+    temp_path: &Path,
+    rope: &Rope,
+    encoding: FileEncoding,
+) -> Result<()> {
+    let mut f = fs::OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .open(temp_path)?;
+    write_rope(&mut f, rope, encoding)?;
+
+    if let Ok(meta) = fs::metadata(path) {
+        fs::set_permissions(temp_path, meta.permissions())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            // Only root can give a file away, so a file of another user ends up
+            // owned by whoever saved it
+            let _ = std::os::unix::fs::chown(
+                temp_path,
+                Some(meta.uid()),
+                Some(meta.gid()),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Overwrite the file at `path` with `rope`, keeping the file itself
+fn write_in_place(path: &Path, rope: &Rope, encoding: FileEncoding) -> Result<()> {
+    let mut f = fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+    write_rope(&mut f, rope, encoding)
+}
+
+fn write_rope(f: &mut File, rope: &Rope, encoding: FileEncoding) -> Result<()> {
+    if encoding.is_plain_utf8() {
+        for chunk in rope.iter_chunks(..rope.len()) {
+            f.write_all(chunk.as_bytes())?;
+        }
+    } else {
+        f.write_all(&encoding.encode(&rope.to_string())?)?;
+    }
+    f.sync_all()?;
+    Ok(())
+}
+
+/// Whether the current user may write to the file at `path`
+#[cfg(unix)]
+fn is_writable(path: &Path) -> bool {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_writable(_path: &Path) -> bool {
+    true
+}
+
 pub fn load_file(path: &Path) -> Result<String> {
     read_path_to_string(path)
 }
@@ -200,6 +401,10 @@ pub fn read_path_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
 }
 
 pub fn language_id_from_path(path: &Path) -> Option<&'static str> {
+    builtin_language_id(path).or_else(|| LapceLanguage::from_path(path).runtime_id())
+}
+
+fn builtin_language_id(path: &Path) -> Option<&'static str> {
     // recommended language_id values
     // https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocumentItem
     Some(match path.extension() {
@@ -337,3 +542,45 @@ pub fn get_mod_time<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
         .and_then(|meta| meta.modified())
         .ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use lapce_xi_rope::Rope;
+
+    use super::write_file;
+    use crate::encoding::FileEncoding;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file_keeps_files() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let dir = std::env::temp_dir()
+            .join(format!("lapce-write-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // A read only file isn't replaced
+        let read_only = dir.join("read_only.txt");
+        fs::write(&read_only, "old").unwrap();
+        fs::set_permissions(&read_only, fs::Permissions::from_mode(0o444)).unwrap();
+        let rope = Rope::from("new");
+        assert!(
+            write_file(&read_only, &rope, FileEncoding::default(), false).is_err()
+        );
+        assert_eq!(fs::read_to_string(&read_only).unwrap(), "old");
+
+        // A hard linked file stays linked
+        let linked = dir.join("linked.txt");
+        let link = dir.join("link.txt");
+        fs::write(&linked, "old").unwrap();
+        fs::hard_link(&linked, &link).unwrap();
+        write_file(&linked, &rope, FileEncoding::default(), false).unwrap();
+        assert_eq!(fs::read_to_string(&link).unwrap(), "new");
+        assert_eq!(fs::metadata(&linked).unwrap().nlink(), 2);
+
+        fs::set_permissions(&read_only, fs::Permissions::from_mode(0o644)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    cell::Cell,
     collections::{HashMap, HashSet},
     fs, io,
     path::{Path, PathBuf},
@@ -14,32 +15,47 @@
 use anyhow::{anyhow, Context, Result};
 use crossbeam_channel::Sender;
 use git2::ErrorCode::NotFound;
-use git2::{build::CheckoutBuilder, DiffOptions, Oid, Repository};
+use git2::{
+    build::CheckoutBuilder, BranchType, Cred, CredentialType, DiffOptions, Oid,
+    Repository, StatusOptions,
+};
 use grep_matcher::Matcher;
 use grep_regex::RegexMatcherBuilder;
 use grep_searcher::{sinks::UTF8, SearcherBuilder};
 use indexmap::IndexMap;
 use lapce_rpc::{
+    buffer::HEX_ENCODING,
     core::{CoreNotification, CoreRpcHandler},
     file::FileNodeItem,
     proxy::{
         ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse,
         ProxyRpcHandler, SearchMatch,
     },
-    source_control::{DiffInfo, FileDiff},
+    source_control::{
+        BlameHunk, CommitInfo, DiffInfo, FileDiff, GitSyncKind, StashInfo,
+        GIT_AUTH_REQUIRED,
+    },
     style::{LineStyle, SemanticStyles},
     terminal::TermId,
-    RequestId, RpcError,
+    RequestId, RpcError, PROTOCOL_VERSION,
 };
 use lapce_xi_rope::Rope;
 use lsp_types::{
-    MessageType, Position, Range, ShowMessageParams, TextDocumentItem, Url,
+    FileChangeType, FileEvent, MessageType, NumberOrString, Position,
+    ProgressParams, ProgressParamsValue, Range, ShowMessageParams, TextDocumentItem,
+    Url, WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressEnd,
+    WorkDoneProgressReport,
 };
 use parking_lot::Mutex;
 
 use crate::{
-    buffer::{get_mod_time, load_file, Buffer},
+    buffer::{
+        get_mod_time, language_id_from_path, load_file, load_file_with_encoding,
+        read_buffer_content, write_file, Buffer,
+    },
+    encoding::FileEncoding,
     plugin::{catalog::PluginCatalog, PluginCatalogRpcHandler},
+    ports::watch_listening_ports,
     terminal::{Terminal, TerminalSender},
     watcher::{FileWatcher, Notify, WatchToken},
 };
@@ -55,10 +71,22 @@ pub struct Dispatcher {
     buffers: HashMap<PathBuf, Buffer>,
     terminals: HashMap<TermId, TerminalSender>,
     file_watcher: FileWatcher,
+    /// Sends the buffers to write to the save thread, which writes them in order
+    save_tx: Sender<SaveJob>,
     window_id: usize,
     tab_id: usize,
 }
 
+/// A buffer's content to write on the save thread
+struct SaveJob {
+    request_id: RequestId,
+    path: PathBuf,
+    rope: Rope,
+    encoding: FileEncoding,
+    create_parents: bool,
+    save_as: bool,
+}
+
 impl ProxyHandler for Dispatcher {
     fn handle_notification(&mut self, rpc: ProxyNotification) {
         use ProxyNotification::*;
@@ -68,6 +96,8 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 disabled_volts,
                 extra_plugin_paths,
                 plugin_configurations,
+                language_servers,
+                env,
                 window_id,
                 tab_id,
             } => {
@@ -78,12 +108,16 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     self.workspace.clone(),
                     self.core_rpc.clone(),
                     self.proxy_rpc.clone(),
+                    self.catalog_rpc.clone(),
                 ));
                 if let Some(workspace) = self.workspace.as_ref() {
                     self.file_watcher
                         .watch(workspace, true, WORKSPACE_EVENT_TOKEN);
                 }
 
+                self.catalog_rpc
+                    .update_language_server_configs(language_servers);
+                self.catalog_rpc.update_env(env);
                 let plugin_rpc = self.catalog_rpc.clone();
                 let workspace = self.workspace.clone();
                 thread::spawn(move || {
@@ -108,16 +142,100 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 }
             }
             OpenPaths { paths } => {
-                self.core_rpc
-                    .notification(CoreNotification::OpenPaths { paths });
+                self.core_rpc.notification(CoreNotification::OpenPaths {
+                    paths,
+                    new_window: false,
+                    wait: false,
+                });
             }
             OpenFileChanged { path } => {
-                if let Some(buffer) = self.buffers.get(&path) {
-                    if get_mod_time(&buffer.path) == buffer.mod_time {
-                        return;
+                self.check_open_file(&path);
+            }
+            CheckOpenFiles {} => {
+                let paths: Vec<PathBuf> = self.buffers.keys().cloned().collect();
+                for path in paths {
+                    self.check_open_file(&path);
+                }
+            }
+            BufferLoaded {
+                request_id,
+                buffer_id,
+                path,
+                content,
+                read_only,
+                encoding,
+                bom,
+            } => {
+                let file_encoding = (encoding != HEX_ENCODING).then(|| {
+                    FileEncoding::from_name_and_bom(&encoding, bom)
+                        .unwrap_or_default()
+                });
+                let buffer = Buffer::with_content(
+                    buffer_id,
+                    path.clone(),
+                    &content,
+                    read_only,
+                    file_encoding,
+                );
+                // A hex dump is nothing for a language server
+                if !buffer.hex {
+                    self.catalog_rpc.did_open_document(
+                        &path,
+                        buffer.language_id.to_string(),
+                        buffer.rev as i32,
+                        content.clone(),
+                    );
+                }
+                self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
+                self.buffers.insert(path, buffer);
+                self.respond_rpc(
+                    request_id,
+                    Ok(ProxyResponse::NewBufferResponse {
+                        content,
+                        read_only,
+                        encoding,
+                    }),
+                );
+            }
+            BufferWritten {
+                request_id,
+                path,
+                save_as,
+                error,
+            } => {
+                let rope = self
+                    .buffers
+                    .get_mut(&path)
+                    .and_then(|buffer| buffer.finish_save(error.is_none()));
+                match error {
+                    None => {
+                        if !save_as {
+                            if let Some(rope) = rope {
+                                self.catalog_rpc.did_save_text_document(&path, rope);
+                            }
+                        }
+                        let resp = if save_as {
+                            ProxyResponse::Success {}
+                        } else {
+                            ProxyResponse::SaveResponse {}
+                        };
+                        self.respond_rpc(request_id, Ok(resp));
                     }
-                    if let Ok(content) = load_file(&buffer.path) {
-                        self.core_rpc.open_file_changed(path, content);
+                    Some(message) => {
+                        self.core_rpc.show_message(
+                            "Save failure".to_owned(),
+                            ShowMessageParams {
+                                typ: MessageType::ERROR,
+                                message: format!(
+                                    "Couldn't save {}: {message}",
+                                    path.display()
+                                ),
+                            },
+                        );
+                        self.respond_rpc(
+                            request_id,
+                            Err(RpcError { code: 0, message }),
+                        );
                     }
                 }
             }
@@ -145,7 +263,11 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 self.proxy_rpc.shutdown();
             }
             Update { path, delta, rev } => {
-                let buffer = self.buffers.get_mut(&path).unwrap();
+                // Edits made while a remote proxy was reconnecting arrive
+                // before the buffer is reopened
+                let Some(buffer) = self.buffers.get_mut(&path) else {
+                    return;
+                };
                 let old_text = buffer.rope.clone();
                 buffer.update(&delta, rev);
                 self.catalog_rpc.did_change_text_document(
@@ -156,10 +278,91 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     buffer.rope.clone(),
                 );
             }
+            ReopenBuffer {
+                buffer_id,
+                path,
+                content,
+                rev,
+                encoding,
+            } => {
+                let file_encoding = (encoding != HEX_ENCODING).then(|| {
+                    FileEncoding::from_name_and_file(&encoding, &path)
+                        .unwrap_or_default()
+                });
+                // Only a hex dump is read only, saving checks the file's permissions
+                let read_only = file_encoding.is_none();
+                let mut buffer = Buffer::with_content(
+                    buffer_id,
+                    path.clone(),
+                    &content,
+                    read_only,
+                    file_encoding,
+                );
+                buffer.rev = rev;
+                if !buffer.hex {
+                    self.catalog_rpc.did_open_document(
+                        &path,
+                        buffer.language_id.to_string(),
+                        buffer.rev as i32,
+                        content,
+                    );
+                }
+                self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
+                self.buffers.insert(path, buffer);
+            }
+            SetBufferRev { path, rev } => {
+                if let Some(buffer) = self.buffers.get_mut(&path) {
+                    buffer.rev = rev;
+                }
+            }
             UpdatePluginConfigs { configs } => {
                 let _ = self.catalog_rpc.update_plugin_configs(configs);
             }
-            NewTerminal { term_id, profile } => {
+            UpdateLanguageServerConfigs { configs } => {
+                self.catalog_rpc.update_language_server_configs(configs);
+            }
+            UpdateEnv { env } => {
+                self.catalog_rpc.update_env(env);
+            }
+            InstallLanguageServer { language_id } => {
+                let _ = self.catalog_rpc.install_language_server(language_id);
+            }
+            RunPluginCommand { volt_id, command } => {
+                self.catalog_rpc.run_plugin_command(volt_id, command);
+            }
+            PluginViewItemClicked {
+                volt_id,
+                view,
+                item,
+            } => {
+                self.catalog_rpc
+                    .plugin_view_item_clicked(volt_id, view, item);
+            }
+            BufferClosed { path } => {
+                self.catalog_rpc.did_close_document(path);
+            }
+            BufferOpened { path } => {
+                if let Some(buffer) = self.buffers.get(&path) {
+                    if !buffer.hex {
+                        self.catalog_rpc.did_reopen_document(
+                            path,
+                            buffer.language_id.to_string(),
+                        );
+                    }
+                }
+            }
+            SelectionChanged { path, selections } => {
+                self.catalog_rpc.selection_changed(path, selections);
+            }
+            NewTerminal {
+                term_id,
+                mut profile,
+            } => {
+                // The profile's own variables take precedence
+                let mut env = self.catalog_rpc.env();
+                env.extend(profile.environment.take().unwrap_or_default());
+                profile.environment = Some(env);
+
                 let mut terminal = match Terminal::new(term_id, profile, 50, 10) {
                     Ok(terminal) => terminal,
                     Err(e) => {
@@ -281,10 +484,14 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             EnableVolt { volt } => {
                 let _ = self.catalog_rpc.enable_volt(volt);
             }
-            GitCommit { message, diffs } => {
+            GitCommit {
+                message,
+                diffs,
+                amend,
+            } => {
                 if let Some(workspace) = self.workspace.as_ref() {
-                    match git_commit(workspace, &message, diffs) {
-                        Ok(()) => (),
+                    match git_commit(workspace, &message, diffs, amend) {
+                        Ok(()) => self.send_diff_info(),
                         Err(e) => {
                             self.core_rpc.show_message(
                                 "Git Commit failure".to_owned(),
@@ -297,6 +504,58 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     }
                 }
             }
+            GitStage { paths } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    match git_stage(workspace, &paths) {
+                        Ok(()) => self.send_diff_info(),
+                        Err(e) => {
+                            self.core_rpc.show_message(
+                                "Git Stage failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: e.to_string(),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            GitUnstage { paths } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    match git_unstage(workspace, &paths) {
+                        Ok(()) => self.send_diff_info(),
+                        Err(e) => {
+                            self.core_rpc.show_message(
+                                "Git Unstage failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: e.to_string(),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            GitStageHunk {
+                path,
+                lines,
+                content,
+            } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    match git_stage_hunk(workspace, &path, lines, &content) {
+                        Ok(()) => self.send_diff_info(),
+                        Err(e) => {
+                            self.core_rpc.show_message(
+                                "Git Stage failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: e.to_string(),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
             GitCheckout { reference } => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_checkout(workspace, &reference) {
@@ -305,6 +564,88 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     }
                 }
             }
+            GitCreateBranch { name } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    match git_create_branch(workspace, &name) {
+                        Ok(()) => self.send_diff_info(),
+                        Err(e) => {
+                            self.core_rpc.show_message(
+                                "Git Create Branch failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: e.to_string(),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            GitStash { message } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    match git_stash(workspace, &message) {
+                        Ok(()) => self.send_diff_info(),
+                        Err(e) => {
+                            self.core_rpc.show_message(
+                                "Git Stash failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: e.to_string(),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            GitStashApply { index } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    match git_stash_apply(workspace, index, false) {
+                        Ok(()) => self.send_diff_info(),
+                        Err(e) => {
+                            self.core_rpc.show_message(
+                                "Git Stash Apply failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: e.to_string(),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            GitStashPop { index } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    match git_stash_apply(workspace, index, true) {
+                        Ok(()) => self.send_diff_info(),
+                        Err(e) => {
+                            self.core_rpc.show_message(
+                                "Git Stash Pop failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: e.to_string(),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            GitStashDrop { index } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    match Repository::discover(workspace)
+                        .and_then(|mut repo| repo.stash_drop(index))
+                    {
+                        Ok(()) => self.send_diff_info(),
+                        Err(e) => {
+                            self.core_rpc.show_message(
+                                "Git Stash Drop failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: e.to_string(),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
             GitDiscardFilesChanges { files } => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_discard_files_changes(
@@ -324,6 +665,9 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     }
                 }
             }
+            WatchListeningPorts {} => {
+                watch_listening_ports(self.core_rpc.clone());
+            }
             GitInit {} => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_init(workspace) {
@@ -338,23 +682,40 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
     fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
         use ProxyRequest::*;
         match rpc {
-            NewBuffer { buffer_id, path } => {
-                let buffer = Buffer::new(buffer_id, path.clone());
-                let content = buffer.rope.to_string();
-                let read_only = buffer.read_only;
-                self.catalog_rpc.did_open_document(
-                    &path,
-                    buffer.language_id.to_string(),
-                    buffer.rev as i32,
-                    content.clone(),
-                );
-                self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
-                self.buffers.insert(path, buffer);
+            Handshake { .. } => {
                 self.respond_rpc(
                     id,
-                    Ok(ProxyResponse::NewBufferResponse { content, read_only }),
+                    Ok(ProxyResponse::HandshakeResponse {
+                        protocol_version: PROTOCOL_VERSION,
+                    }),
                 );
             }
+            NewBuffer { buffer_id, path } => {
+                // Reading a large file takes a while, so it's done on its own thread,
+                // with the start of the file shown in the meantime
+                let core_rpc = self.core_rpc.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let (content, read_only, encoding) =
+                        read_buffer_content(&path, |preview| {
+                            core_rpc.notification(CoreNotification::BufferPreview {
+                                path: path.clone(),
+                                content: preview.to_string(),
+                            });
+                        });
+                    proxy_rpc.notification(ProxyNotification::BufferLoaded {
+                        request_id: id,
+                        buffer_id,
+                        path,
+                        content,
+                        read_only,
+                        encoding: encoding
+                            .map_or(HEX_ENCODING, |encoding| encoding.name())
+                            .to_string(),
+                        bom: encoding.is_some_and(|encoding| encoding.bom),
+                    });
+                });
+            }
             BufferHead { path } => {
                 let result = if let Some(workspace) = self.workspace.as_ref() {
                     let result = file_get_head(workspace, &path);
@@ -377,6 +738,22 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 };
                 self.respond_rpc(id, result);
             }
+            BufferSaved { path } => {
+                let result = match self.buffers.get(&path) {
+                    Some(buffer) => buffer.read_file(),
+                    None => load_file(&path),
+                };
+                let result = result
+                    .map(|content| ProxyResponse::BufferHeadResponse {
+                        version: "saved".to_string(),
+                        content,
+                    })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
             GlobalSearch {
                 pattern,
                 case_sensitive,
@@ -465,84 +842,311 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
-            GitGetRemoteFileUrl { file } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_get_remote_file_url(workspace, &file) {
-                        Ok(s) => self.proxy_rpc.handle_response(
-                            id,
-                            Ok(ProxyResponse::GitGetRemoteFileUrl { file_url: s }),
-                        ),
-                        Err(e) => eprintln!("{e:?}"),
-                    }
-                }
-            }
-            GetDefinition {
-                request_id,
-                path,
-                position,
-            } => {
+            GetDocumentHighlight { path, position } => {
                 let proxy_rpc = self.proxy_rpc.clone();
-                self.catalog_rpc.get_definition(
+                self.catalog_rpc.get_document_highlight(
                     &path,
                     position,
                     move |_, result| {
-                        let result = result.map(|definition| {
-                            ProxyResponse::GetDefinitionResponse {
-                                request_id,
-                                definition,
+                        let result = result.map(|highlights| {
+                            ProxyResponse::GetDocumentHighlightResponse {
+                                highlights,
                             }
                         });
                         proxy_rpc.handle_response(id, result);
                     },
                 );
             }
-            GetTypeDefinition {
-                request_id,
-                path,
-                position,
-            } => {
+            PrepareCallHierarchy { path, position } => {
                 let proxy_rpc = self.proxy_rpc.clone();
-                self.catalog_rpc.get_type_definition(
+                self.catalog_rpc.prepare_call_hierarchy(
                     &path,
                     position,
-                    move |_, result| {
-                        let result = result.map(|definition| {
-                            ProxyResponse::GetTypeDefinition {
-                                request_id,
-                                definition,
+                    move |plugin_id, result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::PrepareCallHierarchyResponse {
+                                plugin_id,
+                                items: items.unwrap_or_default(),
                             }
                         });
                         proxy_rpc.handle_response(id, result);
                     },
                 );
             }
-            GetInlayHints { path } => {
-                let proxy_rpc = self.proxy_rpc.clone();
-                let buffer = self.buffers.get(&path).unwrap();
-                let range = Range {
-                    start: Position::new(0, 0),
-                    end: buffer.offset_to_position(buffer.len()),
-                };
-                self.catalog_rpc
-                    .get_inlay_hints(&path, range, move |_, result| {
-                        let result = result
-                            .map(|hints| ProxyResponse::GetInlayHints { hints });
-                        proxy_rpc.handle_response(id, result);
-                    });
-            }
-            GetInlineCompletions {
-                path,
-                position,
-                trigger_kind,
+            CallHierarchyCalls {
+                plugin_id,
+                item,
+                direction,
             } => {
                 let proxy_rpc = self.proxy_rpc.clone();
-                self.catalog_rpc.get_inline_completions(
-                    &path,
-                    position,
-                    trigger_kind,
-                    move |_, result| {
-                        let result = result.map(|completions| {
-                            ProxyResponse::GetInlineCompletions { completions }
+                self.catalog_rpc.call_hierarchy_calls(
+                    plugin_id,
+                    item,
+                    direction,
+                    move |result| {
+                        let result = result.map(|calls| {
+                            ProxyResponse::CallHierarchyCallsResponse { calls }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            PrepareTypeHierarchy { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.prepare_type_hierarchy(
+                    &path,
+                    position,
+                    move |plugin_id, result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::PrepareTypeHierarchyResponse {
+                                plugin_id,
+                                items: items.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            TypeHierarchyRelated {
+                plugin_id,
+                item,
+                direction,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.type_hierarchy_related(
+                    plugin_id,
+                    item,
+                    direction,
+                    move |result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::TypeHierarchyRelatedResponse { items }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GitGetRemoteFileUrl { file } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    match git_get_remote_file_url(workspace, &file) {
+                        Ok(s) => self.proxy_rpc.handle_response(
+                            id,
+                            Ok(ProxyResponse::GitGetRemoteFileUrl { file_url: s }),
+                        ),
+                        Err(e) => eprintln!("{e:?}"),
+                    }
+                }
+            }
+            GitBlame { path } => {
+                let Some(workspace) = self.workspace.clone() else {
+                    self.respond_rpc(
+                        id,
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        }),
+                    );
+                    return;
+                };
+                // Blame the content of the buffer if it's open, so that unsaved lines
+                // show as uncommitted
+                let content = self
+                    .buffers
+                    .get(&path)
+                    .map(|buffer| buffer.rope.to_string());
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = git_blame(&workspace, &path, content.as_deref())
+                        .map(|hunks| ProxyResponse::GitBlameResponse { hunks })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitLog { path, limit } => {
+                let Some(workspace) = self.workspace.clone() else {
+                    self.respond_rpc(
+                        id,
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        }),
+                    );
+                    return;
+                };
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = git_log(&workspace, path.as_deref(), limit)
+                        .map(|commits| ProxyResponse::GitLogResponse { commits })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitCommitFiles { id: commit_id } => {
+                let result = self
+                    .workspace
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("no workspace set"))
+                    .and_then(|workspace| git_commit_files(workspace, &commit_id))
+                    .map(|diffs| ProxyResponse::GitCommitFilesResponse { diffs })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
+            GitFileAtRevision { path, revision } => {
+                let result =
+                    self.workspace
+                        .as_ref()
+                        .ok_or_else(|| anyhow!("no workspace set"))
+                        .and_then(|workspace| {
+                            git_file_at_revision(workspace, &path, &revision)
+                        })
+                        .map(|(version, content)| {
+                            ProxyResponse::BufferHeadResponse { version, content }
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                self.respond_rpc(id, result);
+            }
+            GitSync { kind, token } => {
+                let Some(workspace) = self.workspace.clone() else {
+                    self.respond_rpc(
+                        id,
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        }),
+                    );
+                    return;
+                };
+                let proxy_rpc = self.proxy_rpc.clone();
+                let core_rpc = self.core_rpc.clone();
+                thread::spawn(move || {
+                    let progress = GitSyncProgress::begin(&core_rpc, kind);
+                    let result =
+                        git_sync(&workspace, kind, token.clone(), &progress);
+                    progress.end();
+
+                    let result = match result {
+                        Ok(()) => Ok(ProxyResponse::GitSyncResponse {}),
+                        Err(e) => {
+                            let auth_failed = e
+                                .downcast_ref::<git2::Error>()
+                                .is_some_and(|e| e.code() == git2::ErrorCode::Auth);
+                            if auth_failed && token.is_none() {
+                                // The user is asked for credentials and the sync
+                                // retried, so there's no need to report the error
+                                Err(RpcError {
+                                    code: GIT_AUTH_REQUIRED,
+                                    message: e.to_string(),
+                                })
+                            } else {
+                                core_rpc.show_message(
+                                    format!("{} failure", kind.title()),
+                                    ShowMessageParams {
+                                        typ: MessageType::ERROR,
+                                        message: e.to_string(),
+                                    },
+                                );
+                                Err(RpcError {
+                                    code: 0,
+                                    message: e.to_string(),
+                                })
+                            }
+                        }
+                    };
+                    proxy_rpc.handle_response(id, result);
+
+                    if let Some(diff) = git_diff_new(&workspace) {
+                        core_rpc.diff_info(diff);
+                    }
+                });
+            }
+            GetDefinition {
+                request_id,
+                path,
+                position,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_definition(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|definition| {
+                            ProxyResponse::GetDefinitionResponse {
+                                request_id,
+                                definition,
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetTypeDefinition {
+                request_id,
+                path,
+                position,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_type_definition(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|definition| {
+                            ProxyResponse::GetTypeDefinition {
+                                request_id,
+                                definition,
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetInlayHints { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                let buffer = self.buffers.get(&path).unwrap();
+                let range = Range {
+                    start: Position::new(0, 0),
+                    end: buffer.offset_to_position(buffer.len()),
+                };
+                self.catalog_rpc
+                    .get_inlay_hints(&path, range, move |_, result| {
+                        let result = result
+                            .map(|hints| ProxyResponse::GetInlayHints { hints });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
+            GetDocumentLinks { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .get_document_links(&path, move |_, result| {
+                        let result = result.map(|links| {
+                            ProxyResponse::GetDocumentLinksResponse { links }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
+            GetInlineCompletions {
+                path,
+                position,
+                trigger_kind,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_inline_completions(
+                    &path,
+                    position,
+                    trigger_kind,
+                    move |_, result| {
+                        let result = result.map(|completions| {
+                            ProxyResponse::GetInlineCompletions { completions }
                         });
                         proxy_rpc.handle_response(id, result);
                     },
@@ -605,11 +1209,18 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     &path,
                     position,
                     diagnostics,
-                    move |plugin_id, result| {
-                        let result = result.map(|resp| {
-                            ProxyResponse::GetCodeActionsResponse { plugin_id, resp }
-                        });
-                        proxy_rpc.handle_response(id, result);
+                    move |responses| {
+                        let actions = responses
+                            .into_iter()
+                            .flat_map(|(plugin_id, resp)| {
+                                resp.into_iter()
+                                    .map(move |action| (plugin_id, action))
+                            })
+                            .collect();
+                        proxy_rpc.handle_response(
+                            id,
+                            Ok(ProxyResponse::GetCodeActionsResponse { actions }),
+                        );
                     },
                 );
             }
@@ -635,11 +1246,45 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
             GetDocumentFormatting { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc
-                    .get_document_formatting(&path, move |_, result| {
+                    .get_document_formatting(&path, move |result| {
+                        let result = result.map(|edits| {
+                            ProxyResponse::GetDocumentFormatting { edits }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
+            GetRangeFormatting { path, range } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .get_range_formatting(&path, range, move |result| {
+                        let result = result.map(|edits| {
+                            ProxyResponse::GetDocumentFormatting { edits }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
+            GetOnTypeFormatting { path, position, ch } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_on_type_formatting(
+                    &path,
+                    position,
+                    ch,
+                    move |result| {
                         let result = result.map(|edits| {
                             ProxyResponse::GetDocumentFormatting { edits }
                         });
                         proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            WillSaveDocument { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .will_save_text_document(&path, move |edits| {
+                        proxy_rpc.handle_response(
+                            id,
+                            Ok(ProxyResponse::WillSaveDocumentResponse { edits }),
+                        );
                     });
             }
             PrepareRename { path, position } => {
@@ -725,9 +1370,19 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 let resp = ProxyResponse::GetOpenFilesContentResponse { items };
                 self.proxy_rpc.handle_response(id, Ok(resp));
             }
-            ReadDir { path } => {
+            ReadDir { path, mtime } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
+                    let dir_mtime =
+                        fs::metadata(&path).and_then(|m| m.modified()).ok();
+                    if dir_mtime.is_some() && dir_mtime == mtime {
+                        proxy_rpc.handle_response(
+                            id,
+                            Ok(ProxyResponse::ReadDirUnchangedResponse {}),
+                        );
+                        return;
+                    }
+
                     let result = fs::read_dir(path)
                         .map(|entries| {
                             let mut items = entries
@@ -748,7 +1403,10 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
 
                             items.sort();
 
-                            ProxyResponse::ReadDirResponse { items }
+                            ProxyResponse::ReadDirResponse {
+                                items,
+                                mtime: dir_mtime,
+                            }
                         })
                         .map_err(|e| RpcError {
                             code: 0,
@@ -757,24 +1415,66 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     proxy_rpc.handle_response(id, result);
                 });
             }
+            ReadFile { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = fs::read(path)
+                        .map(|content| ProxyResponse::ReadFileResponse { content })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             Save {
                 rev,
                 path,
                 create_parents,
             } => {
-                let buffer = self.buffers.get_mut(&path).unwrap();
-                let result = buffer
-                    .save(rev, create_parents)
-                    .map(|_r| {
-                        self.catalog_rpc
-                            .did_save_text_document(&path, buffer.rope.clone());
-                        ProxyResponse::SaveResponse {}
-                    })
-                    .map_err(|e| RpcError {
+                self.save_buffer(id, rev, path, None, create_parents);
+            }
+            SaveWithEncoding {
+                rev,
+                path,
+                encoding,
+            } => match FileEncoding::from_name(&encoding) {
+                Some(encoding) => {
+                    self.save_buffer(id, rev, path, Some(encoding), true);
+                }
+                None => self.respond_rpc(
+                    id,
+                    Err(RpcError {
                         code: 0,
-                        message: e.to_string(),
-                    });
-                self.respond_rpc(id, result);
+                        message: format!("unknown encoding {encoding}"),
+                    }),
+                ),
+            },
+            ReloadWithEncoding { path, encoding } => {
+                let result = match (
+                    FileEncoding::from_name(&encoding),
+                    self.buffers.get_mut(&path),
+                ) {
+                    (Some(encoding), Some(buffer)) => {
+                        load_file_with_encoding(&path, encoding)
+                            .map(|content| {
+                                buffer.encoding = encoding;
+                                buffer.hex = false;
+                                ProxyResponse::NewBufferResponse {
+                                    content,
+                                    read_only: buffer.read_only,
+                                    encoding: encoding.name().to_string(),
+                                }
+                            })
+                            .map_err(|e| e.to_string())
+                    }
+                    (None, _) => Err(format!("unknown encoding {encoding}")),
+                    (_, None) => Err("buffer not open".to_string()),
+                };
+                self.respond_rpc(
+                    id,
+                    result.map_err(|message| RpcError { code: 0, message }),
+                );
             }
             SaveBufferAs {
                 buffer_id,
@@ -786,15 +1486,26 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 let mut buffer = Buffer::new(buffer_id, path.clone());
                 buffer.rope = Rope::from(content);
                 buffer.rev = rev;
-                let result = buffer
-                    .save(rev, create_parents)
-                    .map(|_| ProxyResponse::Success {})
-                    .map_err(|e| RpcError {
-                        code: 0,
-                        message: e.to_string(),
-                    });
-                self.buffers.insert(path, buffer);
-                self.respond_rpc(id, result);
+                let encoding = buffer.encoding;
+                let result = buffer.start_save(rev, encoding);
+                self.buffers.insert(path.clone(), buffer);
+                match result {
+                    Ok(rope) => self.write_buffer(SaveJob {
+                        request_id: id,
+                        path,
+                        rope,
+                        encoding,
+                        create_parents,
+                        save_as: true,
+                    }),
+                    Err(e) => self.respond_rpc(
+                        id,
+                        Err(RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        }),
+                    ),
+                }
             }
             CreateFile { path } => {
                 let result = path
@@ -853,7 +1564,12 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                             return;
                         }
                     }
-                    std::fs::copy(existing_path, new_path)
+                    let result = if existing_path.is_dir() {
+                        copy_dir_all(&existing_path, &new_path)
+                    } else {
+                        std::fs::copy(&existing_path, &new_path).map(|_| ())
+                    };
+                    result
                         .map(|_| ProxyResponse::Success {})
                         .map_err(|e| RpcError {
                             code: 0,
@@ -1028,7 +1744,84 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         );
                     });
             }
-        }
+            DapEvaluate {
+                dap_id,
+                expression,
+                frame_id,
+                context,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_evaluate(
+                    dap_id,
+                    expression,
+                    frame_id,
+                    context,
+                    move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|resp| ProxyResponse::DapEvaluateResponse {
+                                result: resp,
+                            }),
+                        );
+                    },
+                );
+            }
+            DapCompletions {
+                dap_id,
+                text,
+                column,
+                frame_id,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_completions(
+                    dap_id,
+                    text,
+                    column,
+                    frame_id,
+                    move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|items| {
+                                ProxyResponse::DapCompletionsResponse { items }
+                            }),
+                        );
+                    },
+                );
+            }
+            LanguageServers { path } => {
+                let language_id = path.as_deref().map(|path| {
+                    language_id_from_path(path).unwrap_or("").to_string()
+                });
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .language_servers(language_id, move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|servers| {
+                                ProxyResponse::LanguageServersResponse { servers }
+                            }),
+                        );
+                    });
+            }
+            LanguageServerLog { path } => {
+                let language_id = path.as_deref().map(|path| {
+                    language_id_from_path(path).unwrap_or("").to_string()
+                });
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .language_server_log(language_id, move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|(path, lines)| {
+                                ProxyResponse::LanguageServerLogResponse {
+                                    path,
+                                    lines,
+                                }
+                            }),
+                        );
+                    });
+            }
+        }
     }
 }
 
@@ -1039,6 +1832,31 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
 
         let file_watcher = FileWatcher::new();
 
+        // Writing a file can take a while, so it's done on its own thread, which
+        // reports back to the dispatcher once each write is done
+        let (save_tx, save_rx) = crossbeam_channel::unbounded::<SaveJob>();
+        {
+            let proxy_rpc = proxy_rpc.clone();
+            thread::spawn(move || {
+                for job in save_rx {
+                    let error = write_file(
+                        &job.path,
+                        &job.rope,
+                        job.encoding,
+                        job.create_parents,
+                    )
+                    .err()
+                    .map(|e| e.to_string());
+                    proxy_rpc.notification(ProxyNotification::BufferWritten {
+                        request_id: job.request_id,
+                        path: job.path,
+                        save_as: job.save_as,
+                        error,
+                    });
+                }
+            });
+        }
+
         Self {
             workspace: None,
             proxy_rpc,
@@ -1047,11 +1865,85 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             buffers: HashMap::new(),
             terminals: HashMap::new(),
             file_watcher,
+            save_tx,
             window_id: 1,
             tab_id: 1,
         }
     }
 
+    /// Send the current git status to the client right away, rather than waiting for the
+    /// file watcher to notice the change.
+    fn send_diff_info(&self) {
+        let Some(workspace) = self.workspace.clone() else {
+            return;
+        };
+        let core_rpc = self.core_rpc.clone();
+        thread::spawn(move || {
+            if let Some(diff) = git_diff_new(&workspace) {
+                core_rpc.diff_info(diff);
+            }
+        });
+    }
+
+    /// Tell the client if the file of the open buffer of `path` was deleted, or
+    /// changed since it was read or last noticed changing
+    fn check_open_file(&mut self, path: &Path) {
+        let Some(buffer) = self.buffers.get_mut(path) else {
+            return;
+        };
+        // The change is the save being written, whose modification time is only
+        // known once it's done
+        if !buffer.saving.is_empty() {
+            return;
+        }
+        if !buffer.path.exists() {
+            self.core_rpc.open_file_deleted(path.to_path_buf());
+            return;
+        }
+        let mod_time = get_mod_time(&buffer.path);
+        if mod_time == buffer.mod_time {
+            return;
+        }
+        if let Ok(content) = buffer.read_file() {
+            buffer.mod_time = mod_time;
+            self.core_rpc.open_file_changed(path.to_path_buf(), content);
+        }
+    }
+
+    /// Save the buffer of `path` at `rev`, in `encoding` rather than its own if given
+    fn save_buffer(
+        &mut self,
+        id: RequestId,
+        rev: u64,
+        path: PathBuf,
+        encoding: Option<FileEncoding>,
+        create_parents: bool,
+    ) {
+        let buffer = self.buffers.get_mut(&path).unwrap();
+        let encoding = encoding.unwrap_or(buffer.encoding);
+        match buffer.start_save(rev, encoding) {
+            Ok(rope) => self.write_buffer(SaveJob {
+                request_id: id,
+                path,
+                rope,
+                encoding,
+                create_parents,
+                save_as: false,
+            }),
+            Err(e) => self.respond_rpc(
+                id,
+                Err(RpcError {
+                    code: 0,
+                    message: e.to_string(),
+                }),
+            ),
+        }
+    }
+
+    fn write_buffer(&self, job: SaveJob) {
+        let _ = self.save_tx.send(job);
+    }
+
     fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
         self.proxy_rpc.handle_response(id, result);
     }
@@ -1060,8 +1952,9 @@ fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
 struct FileWatchNotifier {
     core_rpc: CoreRpcHandler,
     proxy_rpc: ProxyRpcHandler,
+    catalog_rpc: PluginCatalogRpcHandler,
     workspace: Option<PathBuf>,
-    workspace_fs_change_handler: Arc<Mutex<Option<Sender<bool>>>>,
+    workspace_fs_change_handler: Arc<Mutex<Option<Sender<Vec<PathBuf>>>>>,
     last_diff: Arc<Mutex<DiffInfo>>,
 }
 
@@ -1076,11 +1969,13 @@ fn new(
         workspace: Option<PathBuf>,
         core_rpc: CoreRpcHandler,
         proxy_rpc: ProxyRpcHandler,
+        catalog_rpc: PluginCatalogRpcHandler,
     ) -> Self {
         let notifier = Self {
             workspace,
             core_rpc,
             proxy_rpc,
+            catalog_rpc,
             workspace_fs_change_handler: Arc::new(Mutex::new(None)),
             last_diff: Arc::new(Mutex::new(DiffInfo::default())),
         };
@@ -1127,18 +2022,54 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             _ => return,
         };
 
+        let changes: Vec<FileEvent> = event
+            .paths
+            .iter()
+            .filter_map(|path| {
+                let typ = match &event.kind {
+                    notify::EventKind::Create(_) => FileChangeType::CREATED,
+                    notify::EventKind::Remove(_) => FileChangeType::DELETED,
+                    // A rename reports both of its paths
+                    notify::EventKind::Modify(notify::event::ModifyKind::Name(
+                        _,
+                    )) => {
+                        if path.exists() {
+                            FileChangeType::CREATED
+                        } else {
+                            FileChangeType::DELETED
+                        }
+                    }
+                    _ => FileChangeType::CHANGED,
+                };
+                Some(FileEvent::new(Url::from_file_path(path).ok()?, typ))
+            })
+            .collect();
+        if !changes.is_empty() {
+            self.catalog_rpc.workspace_files_changed(changes);
+        }
+
+        if explorer_change {
+            // Open files that are deleted, or replaced by a rename, lose their own watch
+            for path in &event.paths {
+                self.proxy_rpc
+                    .notification(ProxyNotification::OpenFileChanged {
+                        path: path.clone(),
+                    });
+            }
+        }
+
         let mut handler = self.workspace_fs_change_handler.lock();
         if let Some(sender) = handler.as_mut() {
             if explorer_change {
-                // only send the value if we need to update file explorer as well
-                let _ = sender.send(explorer_change);
+                // only send the paths if we need to update file explorer as well
+                let _ = sender.send(event.paths);
             }
             return;
         }
         let (sender, receiver) = crossbeam_channel::unbounded();
         if explorer_change {
-            // only send the value if we need to update file explorer as well
-            let _ = sender.send(explorer_change);
+            // only send the paths if we need to update file explorer as well
+            let _ = sender.send(event.paths);
         }
 
         let local_handler = self.workspace_fs_change_handler.clone();
@@ -1153,14 +2084,15 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             }
 
             let mut explorer_change = false;
+            let mut paths = Vec::new();
             for e in receiver {
-                if e {
-                    explorer_change = true;
-                    break;
-                }
+                explorer_change = true;
+                paths.extend(e);
             }
             if explorer_change {
-                core_rpc.workspace_file_change();
+                paths.sort();
+                paths.dedup();
+                core_rpc.workspace_file_change(paths);
             }
             if let Some(diff) = git_diff_new(&workspace) {
                 let mut last_diff = last_diff.lock();
@@ -1183,6 +2115,23 @@ pub struct DiffHunk {
     pub header: String,
 }
 
+/// Recursively copy the directory at `from` to `to`, creating `to` and any missing
+/// directories along the way.
+fn copy_dir_all(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        let new_path = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&path, &new_path)?;
+        } else {
+            fs::copy(&path, &new_path)?;
+        }
+    }
+    Ok(())
+}
+
 fn git_init(workspace_path: &Path) -> Result<()> {
     if Repository::discover(workspace_path).is_err() {
         Repository::init(workspace_path)?;
@@ -1194,6 +2143,7 @@ fn git_commit(
     workspace_path: &Path,
     message: &str,
     diffs: Vec<FileDiff>,
+    amend: bool,
 ) -> Result<()> {
     let repo = Repository::discover(workspace_path)?;
     let mut index = repo.index()?;
@@ -1215,6 +2165,12 @@ fn git_commit(
     let tree = index.write_tree()?;
     let tree = repo.find_tree(tree)?;
 
+    if amend {
+        let head = repo.head()?.peel_to_commit()?;
+        head.amend(Some("HEAD"), None, None, None, Some(message), Some(&tree))?;
+        return Ok(());
+    }
+
     match repo.signature() {
         Ok(signature) => {
             let parents = repo
@@ -1245,14 +2201,118 @@ fn git_commit(
     }
 }
 
+/// Add the current state of `paths` in the working tree to the index.
+fn git_stage(workspace_path: &Path, paths: &[PathBuf]) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let mut index = repo.index()?;
+    for path in paths {
+        let relative_path = path.strip_prefix(workspace_path)?;
+        if path.exists() {
+            index.add_path(relative_path)?;
+        } else {
+            index.remove_path(relative_path)?;
+        }
+    }
+    index.write()?;
+    Ok(())
+}
+
+/// Stage the change that replaces the `lines` of the `HEAD` version of `path` with
+/// `content`, leaving the rest of the index entry as it is.
+fn git_stage_hunk(
+    workspace_path: &Path,
+    path: &Path,
+    lines: std::ops::Range<usize>,
+    content: &str,
+) -> Result<()> {
+    // A file that isn't in `HEAD` is a single added change
+    let Ok((_, head)) = file_get_head(workspace_path, path) else {
+        return git_stage(workspace_path, &[path.to_path_buf()]);
+    };
+
+    let mut new_lines = head.split_inclusive('\n').collect::<Vec<_>>();
+    let end = lines.end.min(new_lines.len());
+    let start = lines.start.min(end);
+    new_lines.splice(start..end, [content]);
+    let new = new_lines.concat();
+
+    // Applying a patch rather than writing the blob keeps any other changes that are
+    // already staged for the file
+    let repo = Repository::discover(workspace_path)?;
+    let relative_path = path.strip_prefix(workspace_path)?;
+    let mut patch = git2::Patch::from_buffers(
+        head.as_bytes(),
+        Some(relative_path),
+        new.as_bytes(),
+        Some(relative_path),
+        None,
+    )?;
+    let diff = git2::Diff::from_buffer(&patch.to_buf()?)?;
+    repo.apply(&diff, git2::ApplyLocation::Index, None)?;
+    Ok(())
+}
+
+/// Reset the index entries of `paths` to their state in `HEAD`.
+fn git_unstage(workspace_path: &Path, paths: &[PathBuf]) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let relative_paths = paths
+        .iter()
+        .map(|path| path.strip_prefix(workspace_path))
+        .collect::<Result<Vec<_>, _>>()?;
+    match repo.head().and_then(|head| head.peel_to_commit()) {
+        Ok(head) => {
+            repo.reset_default(Some(head.as_object()), relative_paths)?;
+        }
+        // Without any commits everything in the index is new
+        Err(_) => {
+            let mut index = repo.index()?;
+            for path in relative_paths {
+                index.remove_path(path)?;
+            }
+            index.write()?;
+        }
+    }
+    Ok(())
+}
+
 fn git_checkout(workspace_path: &Path, reference: &str) -> Result<()> {
     let repo = Repository::discover(workspace_path)?;
+
+    // Checking out a remote branch creates a local branch tracking it, or switches to
+    // the local branch of the same name if there already is one
+    if let Ok(remote_branch) = repo.find_branch(reference, BranchType::Remote) {
+        let remote_name = repo.branch_remote_name(
+            remote_branch
+                .get()
+                .name()
+                .ok_or_else(|| anyhow!("invalid ref"))?,
+        )?;
+        let remote_name = remote_name.as_str().unwrap_or_default();
+        let name = reference
+            .strip_prefix(&format!("{remote_name}/"))
+            .unwrap_or(reference);
+        if repo.find_branch(name, BranchType::Local).is_err() {
+            let commit = remote_branch.get().peel_to_commit()?;
+            let mut branch = repo.branch(name, &commit, false)?;
+            branch.set_upstream(Some(reference))?;
+        }
+        return git_checkout(workspace_path, name);
+    }
+
     let (object, reference) = repo.revparse_ext(reference)?;
     repo.checkout_tree(&object, None)?;
     repo.set_head(reference.unwrap().name().unwrap())?;
     Ok(())
 }
 
+/// Create a branch at `HEAD` and switch to it
+fn git_create_branch(workspace_path: &Path, name: &str) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let commit = repo.head()?.peel_to_commit()?;
+    repo.branch(name, &commit, false)?;
+    git_checkout(workspace_path, name)
+}
+
 fn git_discard_files_changes<'a>(
     workspace_path: &Path,
     files: impl Iterator<Item = &'a Path>,
@@ -1283,6 +2343,261 @@ fn git_discard_files_changes<'a>(
     Ok(())
 }
 
+fn git_stash(workspace_path: &Path, message: &str) -> Result<()> {
+    let mut repo = Repository::discover(workspace_path)?;
+    let signature = repo.signature()?;
+    let message = (!message.is_empty()).then_some(message);
+    repo.stash_save2(
+        &signature,
+        message,
+        Some(git2::StashFlags::INCLUDE_UNTRACKED),
+    )?;
+    Ok(())
+}
+
+/// Apply the stash at `index` to the working tree, removing it from the stash list if
+/// `pop` is true
+fn git_stash_apply(workspace_path: &Path, index: usize, pop: bool) -> Result<()> {
+    let mut repo = Repository::discover(workspace_path)?;
+    let mut options = git2::StashApplyOptions::new();
+    options.reinstantiate_index();
+    if pop {
+        repo.stash_pop(index, Some(&mut options))?;
+    } else {
+        repo.stash_apply(index, Some(&mut options))?;
+    }
+    Ok(())
+}
+
+fn git_stashes(repo: &mut Repository) -> Vec<StashInfo> {
+    let mut stashes = Vec::new();
+    let _ = repo.stash_foreach(|index, message, _| {
+        stashes.push(StashInfo {
+            index,
+            message: message.to_string(),
+        });
+        true
+    });
+    stashes
+}
+
+/// Reports the progress of a [`git_sync`] in the status bar
+struct GitSyncProgress<'a> {
+    core_rpc: &'a CoreRpcHandler,
+    /// The last reported percentage, to not flood the editor with identical reports
+    percentage: Cell<Option<u32>>,
+}
+
+impl<'a> GitSyncProgress<'a> {
+    const TOKEN: &'static str = "git-sync";
+
+    fn begin(core_rpc: &'a CoreRpcHandler, kind: GitSyncKind) -> Self {
+        let progress = Self {
+            core_rpc,
+            percentage: Cell::new(None),
+        };
+        progress.send(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: kind.title().to_string(),
+            cancellable: None,
+            message: None,
+            percentage: None,
+        }));
+        progress
+    }
+
+    fn report(&self, message: String, done: usize, total: usize) {
+        let percentage = (total > 0).then(|| (done * 100 / total) as u32);
+        if percentage.is_some() && percentage == self.percentage.get() {
+            return;
+        }
+        self.percentage.set(percentage);
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            cancellable: None,
+            message: Some(message),
+            percentage,
+        }));
+    }
+
+    fn end(&self) {
+        self.send(WorkDoneProgress::End(WorkDoneProgressEnd { message: None }));
+    }
+
+    fn send(&self, progress: WorkDoneProgress) {
+        self.core_rpc.work_done_progress(ProgressParams {
+            token: NumberOrString::String(Self::TOKEN.to_string()),
+            value: ProgressParamsValue::WorkDone(progress),
+        });
+    }
+}
+
+/// Callbacks for the remote operations of a [`git_sync`], authenticating with the
+/// ssh-agent for ssh remotes, then the configured credential helpers and finally
+/// `token`
+fn git_remote_callbacks<'a>(
+    repo: &Repository,
+    token: Option<String>,
+    progress: &'a GitSyncProgress<'_>,
+) -> Result<git2::RemoteCallbacks<'a>> {
+    let config = repo.config()?;
+    let mut tried_agent = false;
+    let mut tried_helper = false;
+    let mut token = token;
+
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        let user = username.unwrap_or("git");
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(user);
+        }
+        if allowed.contains(CredentialType::SSH_KEY) && !tried_agent {
+            tried_agent = true;
+            return Cred::ssh_key_from_agent(user);
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(token) = token.take() {
+                // Hosts ignore the user name when authenticating with a token
+                return Cred::userpass_plaintext(
+                    username.unwrap_or("x-access-token"),
+                    &token,
+                );
+            }
+            if !tried_helper {
+                tried_helper = true;
+                if let Ok(cred) = Cred::credential_helper(&config, url, username) {
+                    return Ok(cred);
+                }
+            }
+        }
+        Err(git2::Error::new(
+            git2::ErrorCode::Auth,
+            git2::ErrorClass::Net,
+            format!("authentication failed for {url}"),
+        ))
+    });
+    callbacks.transfer_progress(|stats| {
+        progress.report(
+            format!(
+                "{}/{} objects",
+                stats.received_objects(),
+                stats.total_objects()
+            ),
+            stats.received_objects(),
+            stats.total_objects(),
+        );
+        true
+    });
+    callbacks.push_transfer_progress(|current, total, _| {
+        progress.report(format!("{current}/{total} objects"), current, total);
+    });
+    callbacks.push_update_reference(|reference, status| match status {
+        Some(status) => Err(git2::Error::from_str(&format!(
+            "failed to push {reference}: {status}"
+        ))),
+        None => Ok(()),
+    });
+    Ok(callbacks)
+}
+
+/// Fetch from, pull from or push to the upstream remote of `HEAD`, or `origin` if the
+/// branch doesn't have an upstream yet
+fn git_sync(
+    workspace_path: &Path,
+    kind: GitSyncKind,
+    token: Option<String>,
+    progress: &GitSyncProgress<'_>,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let head = repo.head()?;
+    let head_name = head.name().ok_or_else(|| anyhow!("invalid ref"))?;
+    let remote_name = match repo.branch_upstream_remote(head_name) {
+        Ok(name) => name.as_str().unwrap_or("origin").to_string(),
+        Err(_) => "origin".to_string(),
+    };
+    let mut remote = repo.find_remote(&remote_name)?;
+    let callbacks = git_remote_callbacks(&repo, token, progress)?;
+
+    match kind {
+        GitSyncKind::Fetch => {
+            let mut options = git2::FetchOptions::new();
+            options.remote_callbacks(callbacks);
+            remote.fetch::<&str>(&[], Some(&mut options), None)?;
+        }
+        GitSyncKind::Pull => {
+            let mut options = git2::FetchOptions::new();
+            options.remote_callbacks(callbacks);
+            remote.fetch::<&str>(&[], Some(&mut options), None)?;
+            git_merge_upstream(&repo)?;
+        }
+        GitSyncKind::Push => {
+            if !head.is_branch() {
+                return Err(anyhow!("can't push a detached HEAD"));
+            }
+            let mut options = git2::PushOptions::new();
+            options.remote_callbacks(callbacks);
+            remote
+                .push(&[format!("{head_name}:{head_name}")], Some(&mut options))?;
+
+            let mut branch = git2::Branch::wrap(head);
+            if branch.upstream().is_err() {
+                let name = branch.name()?.unwrap_or_default().to_string();
+                branch.set_upstream(Some(&format!("{remote_name}/{name}")))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merge the upstream branch into `HEAD`, fast-forwarding if possible. Conflicts are
+/// left in the working tree for the user to resolve and commit.
+fn git_merge_upstream(repo: &Repository) -> Result<()> {
+    let head = repo.head()?;
+    let head_name = head
+        .name()
+        .ok_or_else(|| anyhow!("invalid ref"))?
+        .to_string();
+    let branch = git2::Branch::wrap(head);
+    let upstream = branch
+        .upstream()
+        .map_err(|_| anyhow!("the current branch has no upstream branch"))?;
+    let upstream_name = upstream.name()?.unwrap_or_default().to_string();
+    let upstream = repo.reference_to_annotated_commit(upstream.get())?;
+
+    let (analysis, _) = repo.merge_analysis(&[&upstream])?;
+    if analysis.is_up_to_date() {
+        return Ok(());
+    }
+    if analysis.is_fast_forward() {
+        let target = repo.find_commit(upstream.id())?;
+        repo.checkout_tree(target.as_object(), Some(CheckoutBuilder::new().safe()))?;
+        repo.find_reference(&head_name)?
+            .set_target(upstream.id(), "pull: fast-forward")?;
+        return Ok(());
+    }
+
+    repo.merge(&[&upstream], None, None)?;
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        return Err(anyhow!(
+            "merging {upstream_name} has conflicts, resolve them and commit"
+        ));
+    }
+
+    let signature = repo.signature()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = repo.head()?.peel_to_commit()?;
+    let upstream = repo.find_commit(upstream.id())?;
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &format!("Merge branch '{upstream_name}'"),
+        &tree,
+        &[&parent, &upstream],
+    )?;
+    repo.cleanup_state()?;
+    Ok(())
+}
+
 fn git_discard_workspace_changes(workspace_path: &Path) -> Result<()> {
     let repo = Repository::discover(workspace_path)?;
     let mut checkout_b = CheckoutBuilder::new();
@@ -1318,17 +2633,28 @@ fn git_delta_format(
 }
 
 fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
-    let repo = Repository::discover(workspace_path).ok()?;
+    let mut repo = Repository::discover(workspace_path).ok()?;
     let name = match repo.head() {
         Ok(head) => head.shorthand()?.to_string(),
         _ => "(No branch)".to_owned(),
     };
 
     let mut branches = Vec::new();
-    for branch in repo.branches(None).ok()? {
+    for branch in repo.branches(Some(BranchType::Local)).ok()? {
         branches.push(branch.ok()?.0.name().ok()??.to_string());
     }
 
+    let mut remote_branches = Vec::new();
+    for branch in repo.branches(Some(BranchType::Remote)).ok()? {
+        let name = branch.ok()?.0.name().ok()??.to_string();
+        // Skip the symbolic `origin/HEAD`
+        if !name.ends_with("/HEAD") {
+            remote_branches.push(name);
+        }
+    }
+
+    let (ahead, behind) = git_ahead_behind(&repo).unwrap_or((0, 0));
+
     let mut tags = Vec::new();
     if let Ok(git_tags) = repo.tag_names(None) {
         for tag in git_tags.into_iter().flatten() {
@@ -1336,7 +2662,7 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         }
     }
 
-    let mut deltas = Vec::new();
+    let mut unstaged_deltas = Vec::new();
     let mut diff_options = DiffOptions::new();
     let diff = repo
         .diff_index_to_workdir(
@@ -1350,7 +2676,7 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         .ok()?;
     for delta in diff.deltas() {
         if let Some(delta) = git_delta_format(workspace_path, &delta) {
-            deltas.push(delta);
+            unstaged_deltas.push(delta);
         }
     }
 
@@ -1359,6 +2685,7 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         _ => Oid::zero(),
     };
 
+    let mut staged_deltas = Vec::new();
     let cached_diff = repo
         .diff_tree_to_index(repo.find_tree(oid).ok().as_ref(), None, None)
         .ok();
@@ -1366,10 +2693,70 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
     if let Some(cached_diff) = cached_diff {
         for delta in cached_diff.deltas() {
             if let Some(delta) = git_delta_format(workspace_path, &delta) {
-                deltas.push(delta);
+                staged_deltas.push(delta);
             }
         }
     }
+
+    let deltas = [unstaged_deltas.as_slice(), staged_deltas.as_slice()].concat();
+    let file_diffs = git_file_diffs(&deltas);
+    let staged = git_file_diffs(&staged_deltas);
+    let unstaged = git_file_diffs(&unstaged_deltas);
+
+    let mut untracked = Vec::new();
+    let mut ignored = Vec::new();
+    let mut status_options = StatusOptions::new();
+    status_options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(true)
+        .recurse_ignored_dirs(false)
+        .exclude_submodules(true);
+    if let Ok(statuses) = repo.statuses(Some(&mut status_options)) {
+        for entry in statuses.iter() {
+            let Some(path) = entry.path() else {
+                continue;
+            };
+            let status = entry.status();
+            if status.is_ignored() {
+                ignored.push(workspace_path.join(path));
+            } else if status.is_wt_new() {
+                untracked.push(workspace_path.join(path));
+            }
+        }
+    }
+
+    Some(DiffInfo {
+        head: name,
+        branches,
+        remote_branches,
+        tags,
+        diffs: file_diffs,
+        untracked,
+        ignored,
+        staged,
+        unstaged,
+        ahead,
+        behind,
+        stashes: git_stashes(&mut repo),
+    })
+}
+
+/// How many commits `HEAD` is ahead of and behind its upstream branch
+fn git_ahead_behind(repo: &Repository) -> Option<(usize, usize)> {
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+    let local = head.target()?;
+    let upstream = git2::Branch::wrap(head).upstream().ok()?;
+    let upstream = upstream.get().target()?;
+    repo.graph_ahead_behind(local, upstream).ok()
+}
+
+/// Turn the formatted deltas into file diffs, pairing up added and deleted files with the
+/// same content as renames.
+fn git_file_diffs(deltas: &[(git2::Delta, git2::Oid, PathBuf)]) -> Vec<FileDiff> {
     let mut renames = Vec::new();
     let mut renamed_deltas = HashSet::new();
 
@@ -1411,12 +2798,8 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         | FileDiff::Renamed(p, _)
         | FileDiff::Deleted(p) => p.clone(),
     });
-    Some(DiffInfo {
-        head: name,
-        branches,
-        tags,
-        diffs: file_diffs,
-    })
+
+    file_diffs
 }
 
 fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)> {
@@ -1432,6 +2815,133 @@ fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)>
     Ok((id, content))
 }
 
+fn git_blame(
+    workspace_path: &Path,
+    path: &Path,
+    content: Option<&str>,
+) -> Result<Vec<BlameHunk>> {
+    let repo = Repository::discover(workspace_path)?;
+    let relative_path = path.strip_prefix(workspace_path)?;
+    let blame = repo.blame_file(relative_path, None)?;
+    let blame = match content {
+        Some(content) => blame.blame_buffer(content.as_bytes())?,
+        None => blame,
+    };
+
+    let mut commits: HashMap<Oid, CommitInfo> = HashMap::new();
+    let mut hunks = Vec::new();
+    for hunk in blame.iter() {
+        let id = hunk.final_commit_id();
+        let commit = if id.is_zero() {
+            None
+        } else if let Some(commit) = commits.get(&id) {
+            Some(commit.clone())
+        } else {
+            let commit = commit_info(&repo.find_commit(id)?);
+            commits.insert(id, commit.clone());
+            Some(commit)
+        };
+
+        hunks.push(BlameHunk {
+            start_line: hunk.final_start_line().saturating_sub(1),
+            lines: hunk.lines_in_hunk(),
+            commit,
+        });
+    }
+    Ok(hunks)
+}
+
+fn commit_info(commit: &git2::Commit) -> CommitInfo {
+    CommitInfo {
+        id: commit.id().to_string(),
+        author: commit.author().name().unwrap_or_default().to_string(),
+        time: commit.time().seconds(),
+        message: commit.message().unwrap_or_default().trim_end().to_string(),
+    }
+}
+
+fn git_log(
+    workspace_path: &Path,
+    path: Option<&Path>,
+    limit: usize,
+) -> Result<Vec<CommitInfo>> {
+    let repo = Repository::discover(workspace_path)?;
+    let relative_path = path
+        .map(|path| path.strip_prefix(workspace_path))
+        .transpose()?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    let mut commits = Vec::new();
+    for id in revwalk {
+        let commit = repo.find_commit(id?)?;
+        if let Some(path) = relative_path {
+            let entry_id =
+                |tree: git2::Tree| tree.get_path(path).ok().map(|e| e.id());
+            let id = entry_id(commit.tree()?);
+            // Like `git log <path>`, skip commits where the file is the same as in any
+            // of the parents
+            let changed = if commit.parent_count() == 0 {
+                id.is_some()
+            } else {
+                commit
+                    .parents()
+                    .all(|parent| parent.tree().ok().and_then(entry_id) != id)
+            };
+            if !changed {
+                continue;
+            }
+        }
+
+        commits.push(commit_info(&commit));
+        if commits.len() >= limit {
+            break;
+        }
+    }
+    Ok(commits)
+}
+
+fn git_commit_files(workspace_path: &Path, id: &str) -> Result<Vec<FileDiff>> {
+    let repo = Repository::discover(workspace_path)?;
+    let commit = repo.find_commit(Oid::from_str(id)?)?;
+    let parent_tree = match commit.parents().next() {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+    let diff =
+        repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+
+    let deltas: Vec<_> = diff
+        .deltas()
+        .filter_map(|delta| git_delta_format(workspace_path, &delta))
+        .collect();
+    Ok(git_file_diffs(&deltas))
+}
+
+fn git_file_at_revision(
+    workspace_path: &Path,
+    path: &Path,
+    revision: &str,
+) -> Result<(String, String)> {
+    let repo = Repository::discover(workspace_path)?;
+    let tree = repo.revparse_single(revision)?.peel_to_tree()?;
+    let tree_entry = match tree.get_path(path.strip_prefix(workspace_path)?) {
+        Ok(tree_entry) => tree_entry,
+        // The file didn't exist at that revision
+        Err(e) if e.code() == NotFound => {
+            return Ok((revision.to_string(), String::new()))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let blob = repo.find_blob(tree_entry.id())?;
+    let content = std::str::from_utf8(blob.content())
+        .with_context(|| "content bytes to string")?
+        .to_string();
+    Ok((revision.to_string(), content))
+}
+
 fn git_get_remote_file_url(workspace_path: &Path, file: &Path) -> Result<String> {
     let repo = Repository::discover(workspace_path)?;
     let head = repo.head()?;
@@ -0,0 +1,328 @@
+use std::{borrow::Cow, fmt::Write, fs::File, io::Read, path::Path};
+
+use anyhow::{anyhow, Result};
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// The name of UTF-8 content that starts with a byte order mark
+const UTF_8_BOM_NAME: &str = "UTF-8 with BOM";
+
+/// How much of the start of a file is looked at to tell UTF-16 from binary content
+const SAMPLE_LEN: usize = 4096;
+
+/// The bytes shown on each line of a hex dump
+const HEX_LINE_LEN: usize = 16;
+/// How much of a binary file its hex dump shows
+const HEX_DUMP_LIMIT: usize = 16 * 1024 * 1024;
+
+/// The encoding of a file on disk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileEncoding {
+    pub encoding: &'static Encoding,
+    /// Whether the content starts with a byte order mark
+    pub bom: bool,
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+
+impl FileEncoding {
+    /// The encoding of a name from [`FileEncoding::name`], or any other label of an
+    /// encoding. UTF-16 is always written with a byte order mark, so that it can be
+    /// told apart when the file is read again.
+    pub fn from_name(name: &str) -> Option<FileEncoding> {
+        if name == UTF_8_BOM_NAME {
+            return Some(FileEncoding {
+                encoding: UTF_8,
+                bom: true,
+            });
+        }
+        let encoding = Encoding::for_label(name.as_bytes())?;
+        let bom = encoding == UTF_16LE || encoding == UTF_16BE;
+        Some(FileEncoding { encoding, bom })
+    }
+
+    /// The encoding of a name from [`FileEncoding::name`] with the byte order mark
+    /// the file was read with, which UTF-16 may be without
+    pub fn from_name_and_bom(name: &str, bom: bool) -> Option<FileEncoding> {
+        let encoding = FileEncoding::from_name(name)?;
+        Some(FileEncoding { bom, ..encoding })
+    }
+
+    /// The encoding of a name from [`FileEncoding::name`] for the file at `path`,
+    /// whose first bytes tell whether it has a byte order mark
+    pub fn from_name_and_file(name: &str, path: &Path) -> Option<FileEncoding> {
+        let mut start = Vec::with_capacity(3);
+        let bom = File::open(path)
+            .and_then(|file| file.take(3).read_to_end(&mut start))
+            .is_ok_and(|_| Encoding::for_bom(&start).is_some());
+        FileEncoding::from_name_and_bom(name, bom)
+    }
+
+    pub fn name(&self) -> &'static str {
+        if self.encoding == UTF_8 && self.bom {
+            UTF_8_BOM_NAME
+        } else {
+            self.encoding.name()
+        }
+    }
+
+    /// Decode the content of a file, detecting its encoding from its byte order
+    /// mark, or else by what it decodes as. Content that looks binary is given back.
+    pub fn detect(bytes: Vec<u8>) -> Result<(String, FileEncoding), Vec<u8>> {
+        if let Some((encoding, _)) = Encoding::for_bom(&bytes) {
+            let encoding = FileEncoding {
+                encoding,
+                bom: true,
+            };
+            return Ok((encoding.decode(&bytes), encoding));
+        }
+
+        // Most files are UTF-8, which doesn't need copying
+        let bytes = match String::from_utf8(bytes) {
+            Ok(s) => return Ok((s, FileEncoding::default())),
+            Err(e) => e.into_bytes(),
+        };
+
+        let Some(encoding) = detect_encoding(&bytes) else {
+            return Err(bytes);
+        };
+        let encoding = FileEncoding {
+            encoding,
+            bom: false,
+        };
+        Ok((encoding.decode(&bytes), encoding))
+    }
+
+    /// Decode `bytes`, skipping the byte order mark if there is one and replacing
+    /// what isn't valid in this encoding
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let bytes = match Encoding::for_bom(bytes) {
+            Some((encoding, len)) if encoding == self.encoding => &bytes[len..],
+            _ => bytes,
+        };
+        self.encoding
+            .decode_without_bom_handling(bytes)
+            .0
+            .into_owned()
+    }
+
+    /// Encode `text`, failing if some of it can't be represented in this encoding
+    pub fn encode<'a>(&self, text: &'a str) -> Result<Cow<'a, [u8]>> {
+        let bom: &[u8] = if !self.bom {
+            &[]
+        } else if self.encoding == UTF_8 {
+            b"\xEF\xBB\xBF"
+        } else if self.encoding == UTF_16LE {
+            b"\xFF\xFE"
+        } else if self.encoding == UTF_16BE {
+            b"\xFE\xFF"
+        } else {
+            &[]
+        };
+
+        // encoding_rs only decodes UTF-16
+        let bytes: Cow<'a, [u8]> = if self.encoding == UTF_16LE {
+            text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+        } else if self.encoding == UTF_16BE {
+            text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+        } else {
+            let (bytes, _, unmappable) = self.encoding.encode(text);
+            if unmappable {
+                return Err(anyhow!(
+                    "the content has characters that can't be written in {}",
+                    self.name()
+                ));
+            }
+            bytes
+        };
+
+        if bom.is_empty() {
+            Ok(bytes)
+        } else {
+            let mut with_bom = bom.to_vec();
+            with_bom.extend_from_slice(&bytes);
+            Ok(Cow::Owned(with_bom))
+        }
+    }
+
+    /// Whether text is written in this encoding as it is in memory
+    pub fn is_plain_utf8(&self) -> bool {
+        self.encoding == UTF_8 && !self.bom
+    }
+}
+
+/// Guess the encoding of content that isn't UTF-8 and has no byte order mark, or
+/// `None` if it looks binary
+fn detect_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(SAMPLE_LEN)];
+    let zeros = |start: usize| {
+        sample
+            .iter()
+            .skip(start)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    let pairs = sample.len() / 2;
+
+    // The ASCII characters of UTF-16 text have a zero byte on one side
+    if odd > pairs / 2 && even < pairs / 8 {
+        return Some(UTF_16LE);
+    }
+    if even > pairs / 2 && odd < pairs / 8 {
+        return Some(UTF_16BE);
+    }
+    if even + odd > 0 {
+        return None;
+    }
+
+    // Latin-1 text is often well-formed Shift_JIS too, since its accented letters
+    // are Shift_JIS lead bytes or half-width katakana. Its accented letters are
+    // mostly between ASCII ones though, while most Japanese characters are two
+    // bytes that aren't ASCII.
+    if has_shift_jis_pairs(sample)
+        && SHIFT_JIS
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .is_some()
+    {
+        return Some(SHIFT_JIS);
+    }
+
+    Some(WINDOWS_1252)
+}
+
+/// Whether there's a Shift_JIS lead byte followed by a trail byte that isn't ASCII
+fn has_shift_jis_pairs(bytes: &[u8]) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        if matches!(bytes[i], 0x81..=0x9F | 0xE0..=0xFC) {
+            if bytes.get(i + 1).is_some_and(|b| *b >= 0x80) {
+                return true;
+            }
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    false
+}
+
+/// Show binary content as lines of its offset, its bytes in hex, and the bytes that
+/// are printable ASCII
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let shown = &bytes[..bytes.len().min(HEX_DUMP_LIMIT)];
+    let mut dump = String::with_capacity(shown.len() / HEX_LINE_LEN * 80);
+    for (i, line) in shown.chunks(HEX_LINE_LEN).enumerate() {
+        let _ = write!(dump, "{:08x} ", i * HEX_LINE_LEN);
+        for j in 0..HEX_LINE_LEN {
+            if j % 8 == 0 {
+                dump.push(' ');
+            }
+            match line.get(j) {
+                Some(b) => {
+                    let _ = write!(dump, "{b:02x} ");
+                }
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str(" |");
+        dump.extend(line.iter().map(|b| {
+            if b.is_ascii_graphic() || *b == b' ' {
+                *b as char
+            } else {
+                '.'
+            }
+        }));
+        dump.push_str("|\n");
+    }
+    if bytes.len() > shown.len() {
+        let _ = writeln!(
+            dump,
+            "... {} more bytes not shown",
+            bytes.len() - shown.len()
+        );
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hex_dump, FileEncoding};
+
+    #[test]
+    fn test_detect_encoding() {
+        let (text, encoding) = FileEncoding::detect(b"plain".to_vec()).unwrap();
+        assert_eq!(text, "plain");
+        assert_eq!(encoding.name(), "UTF-8");
+
+        let (text, encoding) =
+            FileEncoding::detect(b"\xEF\xBB\xBFwith bom".to_vec()).unwrap();
+        assert_eq!(text, "with bom");
+        assert_eq!(encoding.name(), "UTF-8 with BOM");
+
+        let utf16: Vec<u8> = "wide text"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let (text, encoding) = FileEncoding::detect(utf16).unwrap();
+        assert_eq!(text, "wide text");
+        assert_eq!(encoding.name(), "UTF-16LE");
+        // Saved again without the byte order mark it didn't have
+        assert!(!encoding.bom);
+        assert_eq!(
+            FileEncoding::from_name_and_bom(encoding.name(), encoding.bom),
+            Some(encoding)
+        );
+
+        let (text, encoding) =
+            FileEncoding::detect(b"caf\xE9 au lait".to_vec()).unwrap();
+        assert_eq!(text, "café au lait");
+        assert_eq!(encoding.name(), "windows-1252");
+
+        let (text, encoding) = FileEncoding::detect(b"caf\xE9".to_vec()).unwrap();
+        assert_eq!(text, "café");
+        assert_eq!(encoding.name(), "windows-1252");
+        // Well-formed Shift_JIS as well
+        let (text, encoding) =
+            FileEncoding::detect(b"\xC7a co\xFBte r\xE9ellement".to_vec()).unwrap();
+        assert_eq!(text, "Ça coûte réellement");
+        assert_eq!(encoding.name(), "windows-1252");
+
+        let (text, encoding) =
+            FileEncoding::detect(b"\x93\xfa\x96\x7b\x8c\xea".to_vec()).unwrap();
+        assert_eq!(text, "日本語");
+        assert_eq!(encoding.name(), "Shift_JIS");
+
+        assert!(
+            FileEncoding::detect(b"\x7fELF\x02\x01\x00\x00\xff".to_vec()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        assert_eq!(
+            hex_dump(b"\x7fELF\x02\x01\x00\x00 binary file!!\xff"),
+            "00000000  7f 45 4c 46 02 01 00 00  20 62 69 6e 61 72 79 20  |.ELF.... binary |\n\
+             00000010  66 69 6c 65 21 21 ff                              |file!!.|\n"
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let encoding = FileEncoding::from_name("UTF-16LE").unwrap();
+        let bytes = encoding.encode("hi").unwrap();
+        assert_eq!(&bytes[..], b"\xFF\xFEh\x00i\x00");
+        assert_eq!(encoding.decode(&bytes), "hi");
+
+        let encoding = FileEncoding::from_name("windows-1252").unwrap();
+        assert_eq!(&encoding.encode("café").unwrap()[..], b"caf\xE9");
+        assert!(encoding.encode("日本語").is_err());
+    }
+}
@@ -3,7 +3,10 @@
 pub mod buffer;
 pub mod cli;
 pub mod dispatch;
+pub mod encoding;
 pub mod plugin;
+pub mod ports;
+pub mod shell_env;
 pub mod terminal;
 pub mod watcher;
 
@@ -52,6 +55,11 @@ pub fn mainloop() {
         };
         exit(1);
     }
+
+    // The proxy of a remote workspace is started by a non-login shell, so the
+    // user's profile files haven't been sourced yet
+    shell_env::load_shell_env();
+
     let core_rpc = CoreRpcHandler::new();
     let proxy_rpc = ProxyRpcHandler::new();
     let mut dispatcher = Dispatcher::new(core_rpc.clone(), proxy_rpc.clone());
@@ -1,6 +1,6 @@
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::PathBuf,
     sync::{
         atomic::{AtomicUsize, Ordering},
@@ -9,28 +9,36 @@
     thread,
 };
 
-use lapce_rpc::plugin::VoltInfo;
+use lapce_rpc::plugin::{LanguageServerInfo, VoltInfo};
 use lapce_rpc::{
     dap_types::{self, DapId, DapServer, SetBreakpointsResponse},
-    plugin::{PluginId, VoltID, VoltMetadata},
+    plugin::{PluginCommand, PluginId, PluginView, VoltID, VoltMetadata},
     proxy::ProxyResponse,
     style::LineStyle,
     RpcError,
 };
 use lapce_xi_rope::{Rope, RopeDelta};
-use lsp_types::request::Request;
+use lsp_types::request::{ExecuteCommand, Request};
 use lsp_types::{
-    notification::DidOpenTextDocument, DidOpenTextDocumentParams, SemanticTokens,
-    TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
+    notification::{DidChangeConfiguration, DidOpenTextDocument},
+    DidChangeConfigurationParams, DidOpenTextDocumentParams, ExecuteCommandParams,
+    MessageType, SemanticTokens, ShowMessageParams, TextDocumentIdentifier,
+    TextDocumentItem, Url, VersionedTextDocumentIdentifier,
 };
 use parking_lot::Mutex;
 use psp_types::Notification;
 use serde_json::Value;
 
 use super::{
-    dap::{DapClient, DapRpcHandler, DebuggerData},
+    dap::{self, DapClient, DapRpcHandler, DebuggerData},
+    dap_adapters,
+    host_api::{
+        self, BufferOpenedParams, BufferParams, PluginEvent, SelectionChangedParams,
+        ViewItemClicked, ViewItemClickedParams, WorkspaceFilesChangedParams,
+    },
+    lsp_install::{self, KnownServer},
     psp::{ClonableCallback, PluginServerRpc, PluginServerRpcHandler, RpcCallback},
-    wasi::{load_all_volts, start_volt},
+    wasi::{load_all_volts, start_volt, unflatten_map},
     PluginCatalogNotification, PluginCatalogRpcHandler,
 };
 use crate::plugin::{
@@ -43,9 +51,24 @@ pub struct PluginCatalog {
     plugins: HashMap<PluginId, PluginServerRpcHandler>,
     daps: HashMap<DapId, DapRpcHandler>,
     debuggers: HashMap<String, DebuggerData>,
+    /// The commands the running volts registered, with the plugin which
+    /// registered each
+    commands: Vec<(PluginId, PluginCommand)>,
+    /// The views the running volts show, with the plugin which created each
+    views: Vec<(PluginId, PluginView)>,
+    /// The events each plugin asked to be told about
+    event_subscriptions: HashMap<PluginId, HashSet<PluginEvent>>,
     plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
     unactivated_volts: HashMap<VoltID, VoltMetadata>,
+    /// The enabled volts, activated or not
+    volts: HashMap<VoltID, VoltMetadata>,
+    /// Whether the installed volts were loaded, before which it isn't known which
+    /// languages they support
+    volts_loaded: bool,
     open_files: HashMap<PathBuf, String>,
+    language_servers: HashMap<PluginId, LanguageServerInfo>,
+    /// The servers Lapce can install that were looked for this session
+    checked_servers: HashSet<&'static str>,
 }
 
 impl PluginCatalog {
@@ -63,8 +86,15 @@ pub fn new(
             plugins: HashMap::new(),
             daps: HashMap::new(),
             debuggers: HashMap::new(),
+            commands: Vec::new(),
+            views: Vec::new(),
+            event_subscriptions: HashMap::new(),
             unactivated_volts: HashMap::new(),
+            volts: HashMap::new(),
+            volts_loaded: false,
             open_files: HashMap::new(),
+            language_servers: HashMap::new(),
+            checked_servers: HashSet::new(),
         };
 
         thread::spawn(move || {
@@ -183,6 +213,26 @@ pub fn shutdown_volt(
         f: Box<dyn ClonableCallback<Value, RpcError>>,
     ) {
         let id = volt.id();
+        self.language_servers.retain(|_, info| info.volt_id != id);
+        if self
+            .commands
+            .iter()
+            .any(|(_, command)| command.volt_id == id)
+        {
+            self.commands.retain(|(_, command)| command.volt_id != id);
+            self.commands_changed();
+        }
+        if self.views.iter().any(|(_, view)| view.volt_id == id) {
+            self.views.retain(|(_, view)| view.volt_id != id);
+            self.views_changed();
+        }
+        let plugins = &self.plugins;
+        self.event_subscriptions.retain(|plugin_id, _| {
+            plugins
+                .get(plugin_id)
+                .is_some_and(|plugin| plugin.volt_id != id)
+        });
+        self.plugin_rpc.clear_diagnostics(&id);
         for (plugin_id, plugin) in self.plugins.iter() {
             if plugin.volt_id == id {
                 let f = dyn_clone::clone_box(&*f);
@@ -202,6 +252,38 @@ pub fn shutdown_volt(
         }
     }
 
+    /// Let the editor know about the commands it can run
+    fn commands_changed(&self) {
+        self.plugin_rpc.core_rpc.plugin_commands(
+            self.commands
+                .iter()
+                .map(|(_, command)| command.clone())
+                .collect(),
+        );
+    }
+
+    fn views_changed(&self) {
+        self.plugin_rpc
+            .core_rpc
+            .plugin_views(self.views.iter().map(|(_, view)| view.clone()).collect());
+    }
+
+    /// Send the notification to the plugins which subscribed to the event
+    fn emit_event<N: lsp_types::notification::Notification>(
+        &self,
+        event: PluginEvent,
+        params: N::Params,
+    ) {
+        for (plugin_id, events) in self.event_subscriptions.iter() {
+            if !events.contains(&event) {
+                continue;
+            }
+            if let Some(plugin) = self.plugins.get(plugin_id) {
+                plugin.server_notification(N::METHOD, &params, None, None, false);
+            }
+        }
+    }
+
     fn start_unactivated_volts(&mut self, to_be_activated: Vec<VoltID>) {
         for id in to_be_activated.iter() {
             let workspace = self.workspace.clone();
@@ -221,16 +303,10 @@ fn check_unactivated_volts(&mut self) {
             .unactivated_volts
             .iter()
             .filter_map(|(id, meta)| {
-                let contains = meta
-                    .activation
-                    .as_ref()
-                    .and_then(|a| a.language.as_ref())
-                    .map(|l| {
-                        self.open_files
-                            .iter()
-                            .any(|(_, language_id)| l.contains(language_id))
-                    })
-                    .unwrap_or(false);
+                let contains = self
+                    .open_files
+                    .values()
+                    .any(|language_id| meta.activated_by_language(language_id));
                 if contains {
                     return Some(id.clone());
                 }
@@ -277,12 +353,7 @@ pub fn handle_did_open_text_document(&mut self, document: TextDocumentItem) {
             .unactivated_volts
             .iter()
             .filter_map(|(id, meta)| {
-                let contains = meta
-                    .activation
-                    .as_ref()
-                    .and_then(|a| a.language.as_ref())
-                    .map(|l| l.contains(&document.language_id))?;
-                if contains {
+                if meta.activated_by_language(&document.language_id) {
                     Some(id.clone())
                 } else {
                     None
@@ -290,6 +361,15 @@ pub fn handle_did_open_text_document(&mut self, document: TextDocumentItem) {
             })
             .collect();
         self.start_unactivated_volts(to_be_activated);
+        self.check_language_server(&document.language_id);
+
+        self.emit_event::<host_api::BufferOpened>(
+            PluginEvent::BufferOpened,
+            BufferOpenedParams {
+                uri: document.uri.clone(),
+                language_id: document.language_id.clone(),
+            },
+        );
 
         let path = document.uri.to_file_path().ok();
         for (_, plugin) in self.plugins.iter() {
@@ -305,6 +385,56 @@ pub fn handle_did_open_text_document(&mut self, document: TextDocumentItem) {
         }
     }
 
+    /// Start the installed server of the language if Lapce knows one and no
+    /// volt supports the language, or offer to install it if it can't be found.
+    /// Until the volts are loaded this waits for them, and the languages of
+    /// the files open by then are checked.
+    fn check_language_server(&mut self, language_id: &str) {
+        if !self.volts_loaded {
+            return;
+        }
+        let Some(server) = lsp_install::known_server(language_id) else {
+            return;
+        };
+        let supported = self
+            .volts
+            .values()
+            .any(|meta| meta.activated_by_language(language_id))
+            || self
+                .language_servers
+                .values()
+                .any(|info| info.languages.iter().any(|l| l == language_id));
+        if supported || !self.checked_servers.insert(server.name) {
+            return;
+        }
+
+        if let Some(path) = server.installed_path() {
+            self.spawn_known_server(server, path);
+        } else if !server.on_path() {
+            self.plugin_rpc.core_rpc.language_server_missing(
+                language_id.to_string(),
+                server.name.to_string(),
+                server.version.to_string(),
+            );
+        }
+    }
+
+    fn spawn_known_server(&self, server: &'static KnownServer, path: PathBuf) {
+        let plugin_rpc = self.plugin_rpc.clone();
+        let workspace = self.workspace.clone();
+        thread::spawn(move || {
+            if let Err(err) = server.start(plugin_rpc.clone(), workspace, &path) {
+                plugin_rpc.core_rpc.show_message(
+                    format!("Failed to start {}", server.name),
+                    ShowMessageParams {
+                        typ: MessageType::ERROR,
+                        message: err.to_string(),
+                    },
+                );
+            }
+        });
+    }
+
     pub fn handle_did_save_text_document(
         &mut self,
         language_id: String,
@@ -312,6 +442,12 @@ pub fn handle_did_save_text_document(
         text_document: TextDocumentIdentifier,
         text: Rope,
     ) {
+        self.emit_event::<host_api::BufferSaved>(
+            PluginEvent::BufferSaved,
+            BufferParams {
+                uri: text_document.uri.clone(),
+            },
+        );
         for (_, plugin) in self.plugins.iter() {
             plugin.handle_rpc(PluginServerRpc::DidSaveTextDocument {
                 language_id: language_id.clone(),
@@ -364,6 +500,25 @@ pub fn format_semantic_tokens(
         }
     }
 
+    pub fn language_servers(
+        &self,
+        language_id: Option<String>,
+        f: Box<dyn RpcCallback<Vec<LanguageServerInfo>, RpcError>>,
+    ) {
+        let mut servers: Vec<LanguageServerInfo> = self
+            .language_servers
+            .values()
+            .filter(|info| {
+                language_id
+                    .as_deref()
+                    .map_or(true, |language_id| info.handles_language(language_id))
+            })
+            .cloned()
+            .collect();
+        servers.sort_by_key(|info| info.started_at);
+        f.call(Ok(servers));
+    }
+
     pub fn dap_variable(
         &self,
         dap_id: DapId,
@@ -458,17 +613,101 @@ pub fn dap_get_scopes(
         }
     }
 
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: String,
+        f: Box<dyn RpcCallback<dap_types::EvaluateResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.evaluate_async(
+                expression,
+                frame_id,
+                context,
+                move |result: Result<dap_types::EvaluateResponse, RpcError>| {
+                    f.call(result)
+                },
+            );
+        } else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "plugin doesn't exist".to_string(),
+            }));
+        }
+    }
+
+    pub fn dap_completions(
+        &self,
+        dap_id: DapId,
+        text: String,
+        column: usize,
+        frame_id: Option<usize>,
+        f: Box<dyn RpcCallback<Vec<dap_types::CompletionItem>, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.completions_async(
+                text,
+                column,
+                frame_id,
+                move |result: Result<dap_types::CompletionsResponse, RpcError>| {
+                    f.call(result.map(|resp| resp.targets))
+                },
+            );
+        } else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "plugin doesn't exist".to_string(),
+            }));
+        }
+    }
+
     pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
         use PluginCatalogNotification::*;
         match notification {
             UnactivatedVolts(volts) => {
                 for volt in volts {
                     let id = volt.id();
+                    self.volts.insert(id.clone(), volt.clone());
                     self.unactivated_volts.insert(id, volt);
                 }
                 self.check_unactivated_volts();
+
+                // The first volts are all the installed ones
+                if !self.volts_loaded {
+                    self.volts_loaded = true;
+                    let languages: HashSet<String> =
+                        self.open_files.values().cloned().collect();
+                    for language_id in languages {
+                        self.check_language_server(&language_id);
+                    }
+                }
             }
             UpdatePluginConfigs(configs) => {
+                // Volts are told about changes to their own settings, which the
+                // language servers they start don't share
+                for (plugin_id, plugin) in self.plugins.iter() {
+                    if self.language_servers.contains_key(plugin_id) {
+                        continue;
+                    }
+                    let name = &plugin.volt_id.name;
+                    let config = configs.get(name);
+                    if config == self.plugin_configurations.get(name) {
+                        continue;
+                    }
+                    plugin.server_notification(
+                        DidChangeConfiguration::METHOD,
+                        DidChangeConfigurationParams {
+                            settings: config
+                                .map(unflatten_map)
+                                .unwrap_or_else(|| serde_json::json!({})),
+                        },
+                        None,
+                        None,
+                        false,
+                    );
+                }
                 self.plugin_configurations = configs;
             }
             PluginServerLoaded(plugin) => {
@@ -506,6 +745,30 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                     }
                 }
             }
+            LanguageServerStarted(info) => {
+                self.language_servers.insert(info.plugin_id, info);
+            }
+            InstallLanguageServer(language_id) => {
+                if let Some(server) = lsp_install::known_server(&language_id) {
+                    let plugin_rpc = self.plugin_rpc.clone();
+                    let workspace = self.workspace.clone();
+                    thread::spawn(move || {
+                        let result =
+                            server.install(&plugin_rpc.core_rpc).and_then(|path| {
+                                server.start(plugin_rpc.clone(), workspace, &path)
+                            });
+                        if let Err(err) = result {
+                            plugin_rpc.core_rpc.show_message(
+                                format!("Failed to install {}", server.name),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: err.to_string(),
+                                },
+                            );
+                        }
+                    });
+                }
+            }
             InstallVolt(volt) => {
                 let workspace = self.workspace.clone();
                 let configurations =
@@ -519,6 +782,9 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
             }
             ReloadVolt(volt) => {
                 let volt_id = volt.id();
+                self.language_servers
+                    .retain(|_, info| info.volt_id != volt_id);
+                self.plugin_rpc.clear_diagnostics(&volt_id);
                 let ids: Vec<PluginId> = self.plugins.keys().cloned().collect();
                 for id in ids {
                     if self.plugins.get(&id).unwrap().volt_id == volt_id {
@@ -530,6 +796,10 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
             }
             StopVolt(volt) => {
                 let volt_id = volt.id();
+                self.volts.remove(&volt_id);
+                self.language_servers
+                    .retain(|_, info| info.volt_id != volt_id);
+                self.plugin_rpc.clear_diagnostics(&volt_id);
                 let ids: Vec<PluginId> = self.plugins.keys().cloned().collect();
                 for id in ids {
                     if self.plugins.get(&id).unwrap().volt_id == volt_id {
@@ -562,28 +832,70 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
             } => {
                 let workspace = self.workspace.clone();
                 let plugin_rpc = self.plugin_rpc.clone();
-                if let Some(debugger) = config
-                    .ty
-                    .as_ref()
-                    .and_then(|ty| self.debuggers.get(ty).cloned())
-                {
-                    thread::spawn(move || {
-                        if let Ok(dap_rpc) = DapClient::start(
-                            DapServer {
-                                program: debugger.program,
-                                args: debugger.args.unwrap_or_default(),
-                                cwd: workspace,
+                let config = dap::expand_workspace(config, workspace.as_deref());
+                let Some(ty) = config.ty.clone() else {
+                    return;
+                };
+                // The debuggers of the plugins come first, then the adapters Lapce
+                // knows of
+                let plugin_debugger = self.debuggers.get(&ty).cloned();
+                thread::spawn(move || {
+                    let debugger = plugin_debugger
+                        .or_else(|| dap_adapters::known_adapter(&ty)?.debugger(&ty));
+                    let Some(debugger) = debugger else {
+                        plugin_rpc.core_rpc.show_message(
+                            "Debug".to_string(),
+                            ShowMessageParams {
+                                typ: MessageType::ERROR,
+                                message: format!(
+                                    "No debug adapter for the type \"{ty}\" was \
+                                     found. Install a plugin for it, or one of \
+                                     lldb-dap, codelldb or debugpy."
+                                ),
                             },
-                            config.clone(),
-                            breakpoints,
-                            plugin_rpc.clone(),
-                        ) {
+                        );
+                        return;
+                    };
+                    match DapClient::start(
+                        DapServer {
+                            program: debugger.program.clone(),
+                            args: debugger.args.unwrap_or_default(),
+                            cwd: workspace,
+                        },
+                        config.clone(),
+                        breakpoints,
+                        plugin_rpc.clone(),
+                    ) {
+                        Ok(dap_rpc) => {
                             let _ = plugin_rpc.dap_loaded(dap_rpc.clone());
 
-                            let _ = dap_rpc.launch(&config);
+                            if let Err(err) = dap_rpc.launch(&config) {
+                                plugin_rpc.core_rpc.show_message(
+                                    "Debug".to_string(),
+                                    ShowMessageParams {
+                                        typ: MessageType::ERROR,
+                                        message: format!(
+                                            "Failed to launch {}: {err}",
+                                            config.name
+                                        ),
+                                    },
+                                );
+                            }
                         }
-                    });
-                }
+                        Err(err) => {
+                            plugin_rpc.core_rpc.show_message(
+                                "Debug".to_string(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: format!(
+                                        "Failed to start {}: {err}",
+                                        debugger.program
+                                    ),
+                                },
+                            );
+                        }
+                    }
+                });
             }
             DapProcessId {
                 dap_id,
@@ -682,6 +994,128 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                     },
                 );
             }
+            RegisterCommand { plugin_id, command } => {
+                self.commands.retain(|(_, c)| {
+                    c.volt_id != command.volt_id || c.command != command.command
+                });
+                self.commands.push((plugin_id, command));
+                self.commands_changed();
+            }
+            RunPluginCommand { volt_id, command } => {
+                let Some((plugin, title)) = self
+                    .commands
+                    .iter()
+                    .find(|(_, c)| c.volt_id == volt_id && c.command == command)
+                    .and_then(|(plugin_id, c)| {
+                        Some((
+                            self.plugins.get(plugin_id)?,
+                            c.volt_display_name.clone(),
+                        ))
+                    })
+                else {
+                    return;
+                };
+                let core_rpc = self.plugin_rpc.core_rpc.clone();
+                plugin.server_request_async(
+                    ExecuteCommand::METHOD,
+                    ExecuteCommandParams {
+                        command,
+                        arguments: Vec::new(),
+                        work_done_progress_params: Default::default(),
+                    },
+                    None,
+                    None,
+                    false,
+                    move |result| {
+                        // What the command changed comes through the host API,
+                        // only a failure is left to report
+                        if let Err(err) = result {
+                            core_rpc.show_message(
+                                title,
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: err.message,
+                                },
+                            );
+                        }
+                    },
+                );
+            }
+            SetView { plugin_id, view } => {
+                match self
+                    .views
+                    .iter_mut()
+                    .find(|(_, v)| v.volt_id == view.volt_id && v.id == view.id)
+                {
+                    Some(existing) => *existing = (plugin_id, view),
+                    None => self.views.push((plugin_id, view)),
+                }
+                self.views_changed();
+            }
+            RemoveView { volt_id, view } => {
+                self.views
+                    .retain(|(_, v)| v.volt_id != volt_id || v.id != view);
+                self.views_changed();
+            }
+            PluginViewItemClicked {
+                volt_id,
+                view,
+                item,
+            } => {
+                if let Some(plugin) = self
+                    .views
+                    .iter()
+                    .find(|(_, v)| v.volt_id == volt_id && v.id == view)
+                    .and_then(|(plugin_id, _)| self.plugins.get(plugin_id))
+                {
+                    plugin.server_notification(
+                        ViewItemClicked::METHOD,
+                        ViewItemClickedParams { view, item },
+                        None,
+                        None,
+                        false,
+                    );
+                }
+            }
+            SubscribeEvents { plugin_id, events } => {
+                self.event_subscriptions
+                    .insert(plugin_id, events.into_iter().collect());
+            }
+            DidCloseTextDocument(path) => {
+                self.open_files.remove(&path);
+                if let Ok(uri) = Url::from_file_path(&path) {
+                    self.emit_event::<host_api::BufferClosed>(
+                        PluginEvent::BufferClosed,
+                        BufferParams { uri },
+                    );
+                }
+            }
+            DidReopenTextDocument { path, language_id } => {
+                if let Ok(uri) = Url::from_file_path(&path) {
+                    self.emit_event::<host_api::BufferOpened>(
+                        PluginEvent::BufferOpened,
+                        BufferOpenedParams {
+                            uri,
+                            language_id: language_id.clone(),
+                        },
+                    );
+                }
+                self.open_files.insert(path, language_id);
+            }
+            SelectionChanged { path, selections } => {
+                if let Ok(uri) = Url::from_file_path(&path) {
+                    self.emit_event::<host_api::SelectionChanged>(
+                        PluginEvent::SelectionChanged,
+                        SelectionChangedParams { uri, selections },
+                    );
+                }
+            }
+            WorkspaceFilesChanged(changes) => {
+                self.emit_event::<host_api::WorkspaceFilesChanged>(
+                    PluginEvent::WorkspaceFilesChanged,
+                    WorkspaceFilesChangedParams { changes },
+                );
+            }
             Shutdown => {
                 for (_, plugin) in self.plugins.iter() {
                     plugin.shutdown();
@@ -1,7 +1,7 @@
 use std::{
     collections::HashMap,
     io::{BufReader, BufWriter, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{Child, Command, Stdio},
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -14,11 +14,12 @@
 use crossbeam_channel::{Receiver, Sender};
 use lapce_rpc::{
     dap_types::{
-        self, ConfigurationDone, Continue, ContinueArguments, ContinueResponse,
-        DapEvent, DapId, DapPayload, DapRequest, DapResponse, DapServer,
-        DebuggerCapabilities, Disconnect, Initialize, Launch, Next, NextArguments,
-        Pause, PauseArguments, Request, RunDebugConfig, RunInTerminal,
-        RunInTerminalArguments, RunInTerminalResponse, Scope, Scopes,
+        self, Completions, CompletionsArguments, CompletionsResponse,
+        ConfigurationDone, Continue, ContinueArguments, ContinueResponse, DapEvent,
+        DapId, DapPayload, DapRequest, DapResponse, DapServer, DebuggerCapabilities,
+        Disconnect, Evaluate, EvaluateArguments, EvaluateResponse, Initialize,
+        Launch, Next, NextArguments, Pause, PauseArguments, Request, RunDebugConfig,
+        RunInTerminal, RunInTerminalArguments, RunInTerminalResponse, Scope, Scopes,
         ScopesArguments, ScopesResponse, SetBreakpoints, SetBreakpointsArguments,
         SetBreakpointsResponse, Source, SourceBreakpoint, StackTrace,
         StackTraceArguments, StackTraceResponse, StepIn, StepInArguments, StepOut,
@@ -291,7 +292,13 @@ fn handle_host_event(&mut self, event: &DapEvent) -> Result<()> {
                 let _ = self.check_restart();
             }
             DapEvent::Thread { .. } => {}
-            DapEvent::Output(_) => {}
+            DapEvent::Output(output) => {
+                if output.category.as_deref() != Some("telemetry") {
+                    self.plugin_rpc
+                        .core_rpc
+                        .dap_output(self.dap_rpc.dap_id, output.clone());
+                }
+            }
             DapEvent::Breakpoint { .. } => {}
             DapEvent::Module { .. } => {}
             DapEvent::LoadedSource { .. } => {}
@@ -306,7 +313,7 @@ pub(crate) fn initialize(&mut self) -> Result<()> {
         let params = dap_types::InitializeParams {
             client_id: Some("lapce".to_owned()),
             client_name: Some("Lapce".to_owned()),
-            adapter_id: "".to_string(),
+            adapter_id: self.config.ty.clone().unwrap_or_default(),
             locale: Some("en-us".to_owned()),
             lines_start_at_one: Some(true),
             columns_start_at_one: Some(true),
@@ -405,6 +412,22 @@ pub enum DapRpc {
     Disconnected,
 }
 
+/// The config with `${workspace}` in its program, arguments and working
+/// directory replaced by the path of the workspace, as when it's run
+pub fn expand_workspace(
+    mut config: RunDebugConfig,
+    workspace: Option<&Path>,
+) -> RunDebugConfig {
+    let Some(workspace) = workspace.and_then(|w| w.to_str()) else {
+        return config;
+    };
+    let expand = |s: &mut String| *s = s.replace("${workspace}", workspace);
+    expand(&mut config.program);
+    config.args.iter_mut().flatten().for_each(expand);
+    config.cwd.iter_mut().for_each(expand);
+    config
+}
+
 #[derive(Clone)]
 pub struct DebuggerData {
     pub debugger_type: String,
@@ -588,11 +611,16 @@ pub fn handle_server_message(&self, message_str: &str) {
     }
 
     pub fn launch(&self, config: &RunDebugConfig) -> Result<()> {
+        // Each adapter has its own way of being asked to run the program in the
+        // terminal, and ignores those of the others
         let params = serde_json::json!({
             "program": config.program,
             "args": config.args,
             "cwd": config.cwd,
+            "env": config.env,
             "runInTerminal": true,
+            "terminal": "integrated",
+            "console": "integratedTerminal",
         });
         let _resp = self
             .request::<Launch>(params)
@@ -755,6 +783,39 @@ pub fn variables_async(
         self.request_async::<Variables>(args, f);
     }
 
+    pub fn evaluate_async(
+        &self,
+        expression: String,
+        frame_id: Option<usize>,
+        context: String,
+        f: impl RpcCallback<EvaluateResponse, RpcError> + 'static,
+    ) {
+        let args = EvaluateArguments {
+            expression,
+            frame_id,
+            context: Some(context),
+        };
+
+        self.request_async::<Evaluate>(args, f);
+    }
+
+    pub fn completions_async(
+        &self,
+        text: String,
+        column: usize,
+        frame_id: Option<usize>,
+        f: impl RpcCallback<CompletionsResponse, RpcError> + 'static,
+    ) {
+        let args = CompletionsArguments {
+            frame_id,
+            text,
+            column,
+            line: None,
+        };
+
+        self.request_async::<Completions>(args, f);
+    }
+
     pub fn next(&self, thread_id: ThreadId) {
         let args = NextArguments {
             thread_id,
@@ -783,3 +844,31 @@ pub fn step_out(&self, thread_id: ThreadId) {
         self.request_async::<StepOut>(args, move |_| {});
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use lapce_rpc::dap_types::RunDebugConfig;
+
+    use super::expand_workspace;
+
+    #[test]
+    fn test_expand_workspace() {
+        let config = RunDebugConfig {
+            ty: Some("lldb".to_string()),
+            name: "test".to_string(),
+            program: "${workspace}/target/debug/test".to_string(),
+            args: Some(vec!["--dir=${workspace}".to_string()]),
+            cwd: Some("${workspace}".to_string()),
+            env: None,
+            prelaunch: None,
+            debug_command: None,
+            dap_id: Default::default(),
+        };
+        let config = expand_workspace(config, Some(Path::new("/project")));
+        assert_eq!(config.program, "/project/target/debug/test");
+        assert_eq!(config.args, Some(vec!["--dir=/project".to_string()]));
+        assert_eq!(config.cwd.as_deref(), Some("/project"));
+    }
+}
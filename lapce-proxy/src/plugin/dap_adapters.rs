@@ -0,0 +1,91 @@
+//! Debug adapters Lapce can start by itself when they're installed, for the
+//! debugger types no plugin provides
+
+use std::process::{Command, Stdio};
+
+use super::{dap::DebuggerData, lsp_install::is_on_path};
+
+pub struct KnownAdapter {
+    pub name: &'static str,
+    /// The `type`s of the run configurations it debugs
+    pub types: &'static [&'static str],
+    /// The programs it can be started with and their arguments, tried in order
+    commands: &'static [(&'static str, &'static [&'static str])],
+    /// The arguments to run the program with to check the adapter is installed,
+    /// for the adapters that are run by a program installed without them
+    probe: Option<&'static [&'static str]>,
+}
+
+pub const KNOWN_ADAPTERS: &[KnownAdapter] = &[
+    KnownAdapter {
+        name: "lldb-dap",
+        types: &["lldb"],
+        commands: &[("lldb-dap", &[]), ("lldb-vscode", &[])],
+        probe: None,
+    },
+    KnownAdapter {
+        name: "CodeLLDB",
+        types: &["codelldb"],
+        commands: &[("codelldb", &[])],
+        probe: None,
+    },
+    KnownAdapter {
+        name: "debugpy",
+        types: &["debugpy", "python"],
+        commands: &[
+            ("python3", &["-m", "debugpy.adapter"]),
+            ("python", &["-m", "debugpy.adapter"]),
+        ],
+        probe: Some(&["-c", "import debugpy"]),
+    },
+];
+
+/// The adapter Lapce can start for the debugger type
+pub fn known_adapter(ty: &str) -> Option<&'static KnownAdapter> {
+    KNOWN_ADAPTERS
+        .iter()
+        .find(|adapter| adapter.types.contains(&ty))
+}
+
+impl KnownAdapter {
+    /// The debugger to start for the adapter, if it's installed
+    pub fn debugger(&self, ty: &str) -> Option<DebuggerData> {
+        let (program, args) = self
+            .commands
+            .iter()
+            .find(|(program, _)| self.installed(program))?;
+        Some(DebuggerData {
+            debugger_type: ty.to_string(),
+            program: program.to_string(),
+            args: Some(args.iter().map(|arg| arg.to_string()).collect()),
+        })
+    }
+
+    fn installed(&self, program: &str) -> bool {
+        if !is_on_path(program) {
+            return false;
+        }
+        let Some(probe) = self.probe else {
+            return true;
+        };
+        Command::new(program)
+            .args(probe)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::known_adapter;
+
+    #[test]
+    fn test_known_adapter() {
+        assert_eq!(known_adapter("lldb").map(|a| a.name), Some("lldb-dap"));
+        assert_eq!(known_adapter("python").map(|a| a.name), Some("debugpy"));
+        assert!(known_adapter("gdb").is_none());
+    }
+}
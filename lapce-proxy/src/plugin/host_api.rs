@@ -0,0 +1,202 @@
+//! Requests of the host API that Lapce offers to volts on top of the plugin
+//! server protocol, each of them needing a [`VoltCapability`] to be declared.
+//!
+//! [`VoltCapability`]: lapce_rpc::plugin::VoltCapability
+
+use lapce_rpc::plugin::PluginViewItem;
+use lsp_types::{
+    notification::Notification, request::Request, FileEvent, Range, Url,
+};
+use serde::{Deserialize, Serialize};
+
+/// Registers a command the user can run from the palette, or bind in their
+/// keymaps as `plugin.<author>.<volt>.<command>`, which runs it by sending the
+/// volt a `workspace/executeCommand` request
+#[derive(Debug)]
+pub enum RegisterCommand {}
+
+impl Request for RegisterCommand {
+    type Params = RegisterCommandParams;
+    type Result = ();
+    const METHOD: &'static str = "lapce/registerCommand";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterCommandParams {
+    /// The name of the command, unique within the volt
+    pub command: String,
+    /// What the palette shows for the command
+    pub title: String,
+}
+
+/// Gets the content of a document, as it is in the editor if it is open
+#[derive(Debug)]
+pub enum GetBufferContent {}
+
+impl Request for GetBufferContent {
+    type Params = GetBufferContentParams;
+    type Result = GetBufferContentResult;
+    const METHOD: &'static str = "lapce/getBufferContent";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetBufferContentParams {
+    pub uri: Url,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetBufferContentResult {
+    pub content: String,
+    /// The version of the open document, none when it was read from disk
+    pub version: Option<i32>,
+}
+
+/// Shows a view in the plugin views panel, or replaces the items of the one
+/// the volt already created with the same id
+#[derive(Debug)]
+pub enum CreateView {}
+
+impl Request for CreateView {
+    type Params = CreateViewParams;
+    type Result = ();
+    const METHOD: &'static str = "lapce/createView";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateViewParams {
+    pub id: String,
+    pub title: String,
+    pub items: Vec<PluginViewItem>,
+}
+
+#[derive(Debug)]
+pub enum RemoveView {}
+
+impl Request for RemoveView {
+    type Params = RemoveViewParams;
+    type Result = ();
+    const METHOD: &'static str = "lapce/removeView";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveViewParams {
+    pub id: String,
+}
+
+/// Sent to the volt when an item of one of its views is clicked
+#[derive(Debug)]
+pub enum ViewItemClicked {}
+
+impl Notification for ViewItemClicked {
+    type Params = ViewItemClickedParams;
+    const METHOD: &'static str = "lapce/viewItemClicked";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewItemClickedParams {
+    pub view: String,
+    pub item: String,
+}
+
+/// Asks to be told about the events, with the notifications below. Each call
+/// replaces the events the volt was subscribed to.
+#[derive(Debug)]
+pub enum SubscribeEvents {}
+
+impl Request for SubscribeEvents {
+    type Params = SubscribeEventsParams;
+    type Result = ();
+    const METHOD: &'static str = "lapce/subscribeEvents";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribeEventsParams {
+    pub events: Vec<PluginEvent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PluginEvent {
+    BufferOpened,
+    BufferSaved,
+    BufferClosed,
+    SelectionChanged,
+    WorkspaceFilesChanged,
+}
+
+#[derive(Debug)]
+pub enum BufferOpened {}
+
+impl Notification for BufferOpened {
+    type Params = BufferOpenedParams;
+    const METHOD: &'static str = "lapce/bufferOpened";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferOpenedParams {
+    pub uri: Url,
+    pub language_id: String,
+}
+
+#[derive(Debug)]
+pub enum BufferSaved {}
+
+impl Notification for BufferSaved {
+    type Params = BufferParams;
+    const METHOD: &'static str = "lapce/bufferSaved";
+}
+
+/// Sent once no editor shows the document anymore
+#[derive(Debug)]
+pub enum BufferClosed {}
+
+impl Notification for BufferClosed {
+    type Params = BufferParams;
+    const METHOD: &'static str = "lapce/bufferClosed";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferParams {
+    pub uri: Url,
+}
+
+/// Sent when the cursor of the active editor stopped moving for a moment
+#[derive(Debug)]
+pub enum SelectionChanged {}
+
+impl Notification for SelectionChanged {
+    type Params = SelectionChangedParams;
+    const METHOD: &'static str = "lapce/selectionChanged";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectionChangedParams {
+    pub uri: Url,
+    /// The selections, which are empty ranges for carets
+    pub selections: Vec<Range>,
+}
+
+/// Files created, changed or deleted in the workspace, by Lapce or not
+#[derive(Debug)]
+pub enum WorkspaceFilesChanged {}
+
+impl Notification for WorkspaceFilesChanged {
+    type Params = WorkspaceFilesChangedParams;
+    const METHOD: &'static str = "lapce/workspaceFilesChanged";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceFilesChangedParams {
+    pub changes: Vec<FileEvent>,
+}
@@ -1,25 +1,29 @@
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
     io::{BufRead, BufReader, BufWriter, Write},
     path::{Path, PathBuf},
     process::{self, Child, Command, Stdio},
     sync::Arc,
     thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{anyhow, Result};
-use jsonrpc_lite::{Id, Params};
+use jsonrpc_lite::{Id, JsonRpc, Params};
 use lapce_core::meta;
 use lapce_rpc::{
-    plugin::{PluginId, VoltID},
+    plugin::{LanguageServerInfo, PluginId, VoltCapability, VoltID},
+    proxy::ProxyResponse,
     style::LineStyle,
     RpcError,
 };
 use lapce_xi_rope::Rope;
 use lsp_types::{
-    notification::{Initialized, Notification},
-    request::{Initialize, Request},
+    notification::{DidChangeConfiguration, Initialized, Notification},
+    request::{Initialize, Request, WorkspaceConfiguration},
     *,
 };
 use parking_lot::Mutex;
@@ -28,7 +32,7 @@
 use super::{
     client_capabilities,
     psp::{
-        handle_plugin_server_message, PluginHandlerNotification, PluginHostHandler,
+        handle_plugin_server_rpc, PluginHandlerNotification, PluginHostHandler,
         PluginServerHandler, PluginServerRpcHandler, ResponseSender, RpcCallback,
     },
 };
@@ -37,6 +41,11 @@
 const HEADER_CONTENT_LENGTH: &str = "content-length";
 const HEADER_CONTENT_TYPE: &str = "content-type";
 
+/// The number of bytes of entries kept in the log of a language server
+const LOG_CAPACITY: usize = 4 * 1024 * 1024;
+/// The number of bytes of a message kept in its log entry
+const LOG_MESSAGE_LEN: usize = 2000;
+
 pub enum LspRpc {
     Request {
         id: u64,
@@ -64,6 +73,14 @@ pub struct LspClient {
     workspace: Option<PathBuf>,
     host: PluginHostHandler,
     options: Option<Value>,
+    volt_id: VoltID,
+    name: String,
+    /// The language ids from the document selector
+    languages: Vec<String>,
+    /// Names of the files marking the root directory of a project
+    root_patterns: Vec<String>,
+    /// The settings to answer `workspace/configuration` requests with
+    settings: Option<Value>,
 }
 
 impl PluginServerHandler for LspClient {
@@ -71,6 +88,10 @@ fn method_registered(&mut self, method: &str) -> bool {
         self.host.method_registered(method)
     }
 
+    fn request_supported(&mut self, method: &str, params: &Params) -> bool {
+        self.host.request_supported(method, params)
+    }
+
     fn document_supported(
         &mut self,
         lanaguage_id: Option<&str>,
@@ -105,6 +126,15 @@ fn handle_host_request(
         params: Params,
         resp: ResponseSender,
     ) {
+        if method == WorkspaceConfiguration::METHOD && self.settings.is_some() {
+            match serde_json::to_value(params)
+                .and_then(serde_json::from_value::<ConfigurationParams>)
+            {
+                Ok(params) => resp.send(self.configuration(params)),
+                Err(err) => resp.send_err(0, err.to_string()),
+            }
+            return;
+        }
         self.host.handle_request(id, method, params, resp);
     }
 
@@ -159,6 +189,14 @@ fn format_semantic_tokens(
     ) {
         self.host.format_semantic_tokens(tokens, text, f);
     }
+
+    fn pending_changes_deadline(&self) -> Option<Instant> {
+        self.host.pending_changes_deadline()
+    }
+
+    fn send_pending_changes(&mut self) {
+        self.host.send_pending_changes();
+    }
 }
 
 impl LspClient {
@@ -176,25 +214,41 @@ fn new(
         args: Vec<String>,
         options: Option<Value>,
     ) -> Result<Self> {
-        let server = match server_uri.scheme() {
-            "file" => {
-                let path = server_uri.to_file_path().map_err(|_| anyhow!(""))?;
-                #[cfg(unix)]
-                let _ = std::process::Command::new("chmod")
-                    .arg("+x")
-                    .arg(&path)
-                    .output();
-                path.to_str().ok_or_else(|| anyhow!(""))?.to_string()
-            }
-            "urn" => server_uri.path().to_string(),
-            _ => return Err(anyhow!("uri not supported")),
+        let languages: Vec<String> = document_selector
+            .iter()
+            .filter_map(|filter| filter.language.clone())
+            .collect();
+        // The user's config for the languages overrides what the plugin asks for
+        let config = plugin_rpc
+            .language_server_config(&languages)
+            .unwrap_or_default();
+        let args = config.args.unwrap_or(args);
+        let options = config.initialization_options.or(options);
+
+        let server = match config.command {
+            Some(command) => command,
+            None => match server_uri.scheme() {
+                "file" => {
+                    let path = server_uri.to_file_path().map_err(|_| anyhow!(""))?;
+                    #[cfg(unix)]
+                    let _ = std::process::Command::new("chmod")
+                        .arg("+x")
+                        .arg(&path)
+                        .output();
+                    path.to_str().ok_or_else(|| anyhow!(""))?.to_string()
+                }
+                "urn" => server_uri.path().to_string(),
+                _ => return Err(anyhow!("uri not supported")),
+            },
         };
 
-        let mut process = Self::process(workspace.as_ref(), &server, &args)?;
+        let mut process =
+            Self::process(workspace.as_ref(), &server, &args, &plugin_rpc.env())?;
         let stdin = process.stdin.take().unwrap();
         let stdout = process.stdout.take().unwrap();
         let stderr = process.stderr.take().unwrap();
 
+        let log = plugin_rpc.lsp_log(&volt_id);
         let mut writer = Box::new(BufWriter::new(stdin));
         let (io_tx, io_rx) = crossbeam_channel::unbounded();
         let server_rpc = PluginServerRpcHandler::new(
@@ -203,6 +257,7 @@ fn new(
             plugin_id,
             io_tx.clone(),
         );
+        let local_log = log.clone();
         thread::spawn(move || {
             for msg in io_rx {
                 if msg
@@ -212,9 +267,10 @@ fn new(
                 {
                     break;
                 }
-                if let Ok(msg) = serde_json::to_string(&msg) {
+                if let Ok(text) = serde_json::to_string(&msg) {
+                    local_log.message("-->", &msg, &text);
                     let msg =
-                        format!("Content-Length: {}\r\n\r\n{}", msg.len(), msg);
+                        format!("Content-Length: {}\r\n\r\n{}", text.len(), text);
                     let _ = writer.write(msg.as_bytes());
                     let _ = writer.flush();
                 }
@@ -224,19 +280,29 @@ fn new(
         let local_server_rpc = server_rpc.clone();
         let core_rpc = plugin_rpc.core_rpc.clone();
         let volt_id_closure = volt_id.clone();
+        let local_log = log.clone();
         thread::spawn(move || {
             let mut reader = Box::new(BufReader::new(stdout));
             loop {
                 match read_message(&mut reader) {
-                    Ok(message_str) => {
-                        if let Some(resp) = handle_plugin_server_message(
-                            &local_server_rpc,
-                            &message_str,
-                        ) {
-                            let _ = io_tx.send(resp);
+                    Ok(message_str) => match JsonRpc::parse(&message_str) {
+                        Ok(message) => {
+                            local_log.message("<--", &message, &message_str);
+                            if let Some(resp) =
+                                handle_plugin_server_rpc(&local_server_rpc, message)
+                            {
+                                let _ = io_tx.send(resp);
+                            }
                         }
-                    }
+                        Err(err) => {
+                            local_log.push(format!(
+                                "<-- invalid ({err})  {}",
+                                truncate_message(&message_str)
+                            ));
+                        }
+                    },
                     Err(_err) => {
+                        local_log.push(format!("server {server} stopped"));
                         core_rpc.log(
                             lapce_rpc::core::LogLevel::Error,
                             format!("lsp server {server} stopped!"),
@@ -262,6 +328,7 @@ fn new(
                         if n == 0 {
                             return;
                         }
+                        log.push(format!("stderr {}", line.trim_end()));
                         core_rpc.log(
                             lapce_rpc::core::LogLevel::Trace,
                             line.trim_end().to_string(),
@@ -278,16 +345,20 @@ fn new(
             }
         });
 
-        let host = PluginHostHandler::new(
+        let mut host = PluginHostHandler::new(
             workspace.clone(),
             pwd,
-            volt_id,
-            volt_display_name,
+            volt_id.clone(),
+            volt_display_name.clone(),
             document_selector,
             plugin_rpc.core_rpc.clone(),
             server_rpc.clone(),
             plugin_rpc.clone(),
+            VoltCapability::language_server(),
         );
+        if let Some(debounce) = config.change_debounce {
+            host.change_debounce = Duration::from_millis(debounce);
+        }
 
         Ok(Self {
             plugin_rpc,
@@ -296,6 +367,11 @@ fn new(
             workspace,
             host,
             options,
+            volt_id,
+            name: volt_display_name,
+            languages,
+            root_patterns: config.root_patterns,
+            settings: config.settings,
         })
     }
 
@@ -337,8 +413,7 @@ pub fn start(
 
     fn initialize(&mut self) {
         let root_uri = self
-            .workspace
-            .clone()
+            .root_directory()
             .map(|p| Url::from_directory_path(p).unwrap());
         #[allow(deprecated)]
         let params = InitializeParams {
@@ -377,6 +452,15 @@ fn initialize(&mut self) {
                 None,
                 false,
             );
+            if let Some(settings) = self.settings.clone() {
+                self.server_rpc.server_notification(
+                    DidChangeConfiguration::METHOD,
+                    DidChangeConfigurationParams { settings },
+                    None,
+                    None,
+                    false,
+                );
+            }
             if self
                 .plugin_rpc
                 .plugin_server_loaded(self.server_rpc.clone())
@@ -384,7 +468,21 @@ fn initialize(&mut self) {
             {
                 self.server_rpc.shutdown();
                 self.shutdown();
+                return;
             }
+            let started_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let _ = self.plugin_rpc.language_server_started(LanguageServerInfo {
+                plugin_id: self.server_rpc.plugin_id,
+                volt_id: self.volt_id.clone(),
+                name: self.name.clone(),
+                languages: self.languages.clone(),
+                pid: Some(self.process.id()),
+                started_at,
+                capabilities: capability_summary(&self.host.server_capabilities),
+            });
         }
         //     move |result| {
         //         if let Ok(value) = result {
@@ -398,6 +496,51 @@ fn initialize(&mut self) {
         // );
     }
 
+    /// The nearest directory above an open file of the server's languages
+    /// that contains one of the root patterns, or the workspace
+    fn root_directory(&self) -> Option<PathBuf> {
+        let workspace = self.workspace.as_ref()?;
+        if self.root_patterns.is_empty() {
+            return Some(workspace.clone());
+        }
+        let Ok(ProxyResponse::GetOpenFilesContentResponse { items }) =
+            self.plugin_rpc.proxy_rpc.get_open_files_content()
+        else {
+            return Some(workspace.clone());
+        };
+        items
+            .iter()
+            .filter(|item| self.languages.contains(&item.language_id))
+            .filter_map(|item| item.uri.to_file_path().ok())
+            .find_map(|path| {
+                path.ancestors()
+                    .skip(1)
+                    .take_while(|dir| dir.starts_with(workspace))
+                    .find(|dir| {
+                        self.root_patterns
+                            .iter()
+                            .any(|pattern| dir.join(pattern).exists())
+                    })
+                    .map(Path::to_path_buf)
+            })
+            .or_else(|| Some(workspace.clone()))
+    }
+
+    /// The values of the settings at the sections the server asks for
+    fn configuration(&self, params: ConfigurationParams) -> Vec<Value> {
+        params
+            .items
+            .iter()
+            .map(|item| {
+                let mut value = self.settings.as_ref();
+                for key in item.section.iter().flat_map(|s| s.split('.')) {
+                    value = value.and_then(|value| value.get(key));
+                }
+                value.cloned().unwrap_or(Value::Null)
+            })
+            .collect()
+    }
+
     fn shutdown(&mut self) {
         let _ = self.process.kill();
         let _ = self.process.wait();
@@ -407,13 +550,14 @@ fn process(
         workspace: Option<&PathBuf>,
         server: &str,
         args: &[String],
+        env: &HashMap<String, String>,
     ) -> Result<Child> {
         let mut process = Command::new(server);
         if let Some(workspace) = workspace {
             process.current_dir(workspace);
         }
 
-        process.args(args);
+        process.args(args).envs(env);
 
         #[cfg(target_os = "windows")]
         let process = process.creation_flags(0x08000000);
@@ -426,6 +570,136 @@ fn process(
     }
 }
 
+/// Short names of the features a server supports, for the status view
+fn capability_summary(capabilities: &ServerCapabilities) -> Vec<String> {
+    fn enabled<T>(provider: &Option<OneOf<bool, T>>) -> bool {
+        matches!(provider, Some(OneOf::Left(true) | OneOf::Right(_)))
+    }
+
+    let features = [
+        ("completion", capabilities.completion_provider.is_some()),
+        (
+            "hover",
+            !matches!(
+                capabilities.hover_provider,
+                None | Some(HoverProviderCapability::Simple(false))
+            ),
+        ),
+        ("definition", enabled(&capabilities.definition_provider)),
+        ("references", enabled(&capabilities.references_provider)),
+        ("rename", enabled(&capabilities.rename_provider)),
+        (
+            "formatting",
+            enabled(&capabilities.document_formatting_provider),
+        ),
+        (
+            "code actions",
+            !matches!(
+                capabilities.code_action_provider,
+                None | Some(CodeActionProviderCapability::Simple(false))
+            ),
+        ),
+        (
+            "signature help",
+            capabilities.signature_help_provider.is_some(),
+        ),
+        ("symbols", enabled(&capabilities.document_symbol_provider)),
+        ("inlay hints", enabled(&capabilities.inlay_hint_provider)),
+        (
+            "semantic tokens",
+            capabilities.semantic_tokens_provider.is_some(),
+        ),
+    ];
+    features
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// The latest JSON-RPC messages exchanged with a language server and lines it
+/// wrote to stderr, shared by the restarts of the server
+#[derive(Clone)]
+pub struct LspLog {
+    start: Instant,
+    entries: Arc<Mutex<LogEntries>>,
+}
+
+#[derive(Default)]
+struct LogEntries {
+    entries: VecDeque<String>,
+    /// The number of bytes of `entries`
+    len: usize,
+}
+
+impl Default for LspLog {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            entries: Arc::new(Mutex::new(LogEntries::default())),
+        }
+    }
+}
+
+impl LspLog {
+    fn push(&self, entry: String) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let entry = format!("[{elapsed:>10.3}] {entry}");
+        let mut entries = self.entries.lock();
+        entries.len += entry.len();
+        entries.entries.push_back(entry);
+        while entries.len > LOG_CAPACITY {
+            let Some(oldest) = entries.entries.pop_front() else {
+                break;
+            };
+            entries.len -= oldest.len();
+        }
+    }
+
+    /// Log a message sent to (`-->`) or received from (`<--`) the server. The
+    /// message is kept on one line after a summary of its kind, id and method,
+    /// so it reads as folded, and cut after [`LOG_MESSAGE_LEN`] bytes.
+    fn message(&self, direction: &str, message: &JsonRpc, text: &str) {
+        let summary = message_summary(message);
+        self.push(format!("{direction} {summary}  {}", truncate_message(text)));
+    }
+
+    pub fn entries(&self) -> Vec<String> {
+        self.entries.lock().entries.iter().cloned().collect()
+    }
+}
+
+fn message_summary(message: &JsonRpc) -> String {
+    let id = message.get_id().map(|id| match id {
+        Id::Num(id) => id.to_string(),
+        Id::Str(id) => format!("{id:?}"),
+        Id::None(_) => "null".to_string(),
+    });
+    match message {
+        JsonRpc::Request(_) => format!(
+            "request #{} {}",
+            id.unwrap_or_default(),
+            message.get_method().unwrap_or_default()
+        ),
+        JsonRpc::Notification(_) => {
+            format!("notification {}", message.get_method().unwrap_or_default())
+        }
+        JsonRpc::Success(_) => format!("response #{}", id.unwrap_or_default()),
+        JsonRpc::Error(_) => format!("error #{}", id.unwrap_or_default()),
+    }
+}
+
+fn truncate_message(text: &str) -> Cow<'_, str> {
+    if text.len() <= LOG_MESSAGE_LEN {
+        return Cow::Borrowed(text);
+    }
+    let mut end = LOG_MESSAGE_LEN;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    Cow::Owned(format!("{}… ({} bytes)", &text[..end], text.len()))
+}
+
 pub struct DocumentFilter {
     /// The document must have this language id, if it exists
     pub language_id: Option<String>,
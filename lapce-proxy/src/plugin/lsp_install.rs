@@ -0,0 +1,325 @@
+//! Language servers that Lapce can download and install by itself, for the
+//! languages that have no plugin providing one
+
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Result};
+use flate2::read::GzDecoder;
+use lapce_core::directory::Directory;
+use lapce_rpc::{core::CoreRpcHandler, plugin::VoltID};
+use lsp_types::{
+    DocumentFilter, NumberOrString, ProgressParams, ProgressParamsValue, Url,
+    WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressEnd,
+    WorkDoneProgressReport,
+};
+
+use super::{lsp::LspClient, PluginCatalogRpcHandler};
+
+/// How a server release is installed
+pub enum ServerInstall {
+    /// A gzipped binary from the release assets on GitHub
+    GithubGz {
+        repository: &'static str,
+        asset: fn() -> Option<String>,
+    },
+    /// `go install` of a package, which needs Go
+    Go { package: &'static str },
+    /// `npm install` of packages, which needs Node.js
+    Npm { packages: &'static [&'static str] },
+}
+
+pub struct KnownServer {
+    pub name: &'static str,
+    /// The pinned release that gets installed
+    pub version: &'static str,
+    pub languages: &'static [&'static str],
+    binary: &'static str,
+    args: &'static [&'static str],
+    install: ServerInstall,
+}
+
+pub const KNOWN_SERVERS: &[KnownServer] = &[
+    KnownServer {
+        name: "rust-analyzer",
+        version: "2024-05-27",
+        languages: &["rust"],
+        binary: "rust-analyzer",
+        args: &[],
+        install: ServerInstall::GithubGz {
+            repository: "rust-lang/rust-analyzer",
+            asset: rust_analyzer_asset,
+        },
+    },
+    KnownServer {
+        name: "gopls",
+        version: "v0.15.3",
+        languages: &["go"],
+        binary: "gopls",
+        args: &[],
+        install: ServerInstall::Go {
+            package: "golang.org/x/tools/gopls",
+        },
+    },
+    KnownServer {
+        name: "typescript-language-server",
+        version: "4.3.3",
+        languages: &[
+            "typescript",
+            "typescriptreact",
+            "javascript",
+            "javascriptreact",
+        ],
+        binary: "typescript-language-server",
+        args: &["--stdio"],
+        install: ServerInstall::Npm {
+            packages: &["typescript@5.4.5"],
+        },
+    },
+];
+
+/// The server Lapce can install for the language
+pub fn known_server(language_id: &str) -> Option<&'static KnownServer> {
+    KNOWN_SERVERS
+        .iter()
+        .find(|server| server.languages.contains(&language_id))
+}
+
+fn rust_analyzer_asset() -> Option<String> {
+    let target = match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => "x86_64-unknown-linux-gnu",
+        ("linux", "aarch64") => "aarch64-unknown-linux-gnu",
+        ("macos", "x86_64") => "x86_64-apple-darwin",
+        ("macos", "aarch64") => "aarch64-apple-darwin",
+        ("windows", "x86_64") => "x86_64-pc-windows-msvc",
+        ("windows", "aarch64") => "aarch64-pc-windows-msvc",
+        _ => return None,
+    };
+    Some(format!("rust-analyzer-{target}.gz"))
+}
+
+impl KnownServer {
+    pub fn volt_id(&self) -> VoltID {
+        VoltID {
+            author: "lapce".to_string(),
+            name: self.name.to_string(),
+        }
+    }
+
+    /// The directory the pinned release is installed into
+    fn directory(&self) -> Option<PathBuf> {
+        Some(
+            Directory::language_servers_directory()?
+                .join(self.name)
+                .join(self.version),
+        )
+    }
+
+    fn binary_path(&self) -> Option<PathBuf> {
+        let dir = self.directory()?;
+        let binary = format!("{}{}", self.binary, std::env::consts::EXE_SUFFIX);
+        Some(match self.install {
+            ServerInstall::GithubGz { .. } | ServerInstall::Go { .. } => {
+                dir.join(binary)
+            }
+            ServerInstall::Npm { .. } => {
+                let binary = if cfg!(windows) {
+                    format!("{}.cmd", self.binary)
+                } else {
+                    self.binary.to_string()
+                };
+                dir.join("node_modules").join(".bin").join(binary)
+            }
+        })
+    }
+
+    /// The path of the installed binary, if the pinned release was installed
+    pub fn installed_path(&self) -> Option<PathBuf> {
+        self.binary_path().filter(|path| path.is_file())
+    }
+
+    /// Whether the server's binary can be found in `PATH`
+    pub fn on_path(&self) -> bool {
+        is_on_path(self.binary)
+    }
+
+    /// Download and install the pinned release, reporting the progress to
+    /// the editor, and return the path of the binary
+    pub fn install(&self, core_rpc: &CoreRpcHandler) -> Result<PathBuf> {
+        let dir = self
+            .directory()
+            .ok_or_else(|| anyhow!("can't find the data directory"))?;
+        let path = self
+            .binary_path()
+            .ok_or_else(|| anyhow!("can't find the data directory"))?;
+        fs::create_dir_all(&dir)?;
+
+        let token = NumberOrString::String(format!("install-{}", self.name));
+        let progress = |value: WorkDoneProgress| {
+            core_rpc.work_done_progress(ProgressParams {
+                token: token.clone(),
+                value: ProgressParamsValue::WorkDone(value),
+            });
+        };
+        progress(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: format!("Installing {} {}", self.name, self.version),
+            cancellable: Some(false),
+            message: None,
+            percentage: Some(0),
+        }));
+
+        let result = match &self.install {
+            ServerInstall::GithubGz { repository, asset } => match asset() {
+                Some(asset) => {
+                    let url = format!(
+                        "https://github.com/{repository}/releases/download/{}/{}",
+                        self.version, asset
+                    );
+                    download_gz(&url, &path, |percentage| {
+                        progress(WorkDoneProgress::Report(WorkDoneProgressReport {
+                            cancellable: Some(false),
+                            message: None,
+                            percentage: Some(percentage),
+                        }))
+                    })
+                }
+                None => {
+                    Err(anyhow!("{} has no release for this platform", self.name))
+                }
+            },
+            ServerInstall::Go { package } => run_installer(
+                Command::new("go")
+                    .arg("install")
+                    .arg(format!("{package}@{}", self.version))
+                    .env("GOBIN", &dir),
+            ),
+            ServerInstall::Npm { packages } => run_installer(
+                Command::new(if cfg!(windows) { "npm.cmd" } else { "npm" })
+                    .arg("install")
+                    .arg("--prefix")
+                    .arg(&dir)
+                    .arg(format!("{}@{}", self.binary, self.version))
+                    .args(packages.iter()),
+            ),
+        };
+
+        progress(WorkDoneProgress::End(WorkDoneProgressEnd { message: None }));
+
+        match result {
+            Ok(()) if path.is_file() => Ok(path),
+            Ok(()) => Err(anyhow!("{} wasn't installed", self.binary)),
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                Err(e)
+            }
+        }
+    }
+
+    /// Start the installed server at `path`, like the servers of plugins
+    pub fn start(
+        &self,
+        plugin_rpc: PluginCatalogRpcHandler,
+        workspace: Option<PathBuf>,
+        path: &Path,
+    ) -> Result<()> {
+        let document_selector = self
+            .languages
+            .iter()
+            .map(|language| DocumentFilter {
+                language: Some(language.to_string()),
+                scheme: None,
+                pattern: None,
+            })
+            .collect();
+        let server_uri = Url::from_file_path(path)
+            .map_err(|_| anyhow!("invalid path {}", path.display()))?;
+        LspClient::start(
+            plugin_rpc,
+            document_selector,
+            workspace,
+            self.volt_id(),
+            self.name.to_string(),
+            None,
+            None,
+            None,
+            server_uri,
+            self.args.iter().map(|arg| arg.to_string()).collect(),
+            None,
+        )?;
+        Ok(())
+    }
+}
+
+fn download_gz(url: &str, path: &Path, progress: impl Fn(u32)) -> Result<()> {
+    let mut resp = reqwest::blocking::get(url)?;
+    if !resp.status().is_success() {
+        return Err(anyhow!("can't download {url}"));
+    }
+    let total = resp.content_length().unwrap_or(0);
+    let mut compressed = Vec::new();
+    let mut buf = [0; 64 * 1024];
+    let mut last_percentage = 0;
+    loop {
+        let n = resp.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        compressed.extend_from_slice(&buf[..n]);
+        if total > 0 {
+            let percentage = (compressed.len() as u64 * 100 / total) as u32;
+            if percentage != last_percentage {
+                last_percentage = percentage;
+                progress(percentage);
+            }
+        }
+    }
+
+    let mut binary = Vec::new();
+    GzDecoder::new(compressed.as_slice()).read_to_end(&mut binary)?;
+    let mut file = fs::File::create(path)?;
+    file.write_all(&binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+fn run_installer(command: &mut Command) -> Result<()> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn is_on_path(binary: &str) -> bool {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    let binary = format!("{binary}{}", std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(&paths).any(|dir| dir.join(&binary).is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::known_server;
+
+    #[test]
+    fn test_known_server() {
+        assert_eq!(known_server("rust").map(|s| s.name), Some("rust-analyzer"));
+        assert_eq!(
+            known_server("javascriptreact").map(|s| s.name),
+            Some("typescript-language-server")
+        );
+        assert!(known_server("plaintext").is_none());
+    }
+}
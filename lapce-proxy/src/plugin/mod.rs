@@ -1,6 +1,9 @@
 pub mod catalog;
 pub mod dap;
+pub mod dap_adapters;
+pub mod host_api;
 pub mod lsp;
+pub mod lsp_install;
 pub mod psp;
 pub mod wasi;
 
@@ -24,45 +27,65 @@
 use lapce_rpc::{
     core::CoreRpcHandler,
     dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
-    plugin::{PluginId, VoltInfo, VoltMetadata},
-    proxy::ProxyRpcHandler,
+    plugin::{
+        LanguageServerConfig, LanguageServerInfo, PluginCommand, PluginId,
+        PluginView, VoltID, VoltInfo, VoltMetadata,
+    },
+    proxy::{CallHierarchyDirection, ProxyRpcHandler, TypeHierarchyDirection},
     style::LineStyle,
     terminal::TermId,
     RequestId, RpcError,
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
+    notification::{Notification, WillSaveTextDocument},
     request::{
-        CodeActionRequest, CodeActionResolveRequest, Completion,
+        CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls,
+        CallHierarchyPrepare, CodeActionRequest, CodeActionResolveRequest,
+        Completion, DocumentHighlightRequest, DocumentLinkRequest,
         DocumentSymbolRequest, Formatting, GotoDefinition, GotoTypeDefinition,
         GotoTypeDefinitionParams, GotoTypeDefinitionResponse, HoverRequest,
-        InlayHintRequest, InlineCompletionRequest, PrepareRenameRequest, References,
-        Rename, Request, ResolveCompletionItem, SelectionRangeRequest,
-        SemanticTokensFullRequest, SignatureHelpRequest, WorkspaceSymbolRequest,
+        InlayHintRequest, InlineCompletionRequest, OnTypeFormatting,
+        PrepareRenameRequest, RangeFormatting, References, Rename, Request,
+        ResolveCompletionItem, SelectionRangeRequest, SemanticTokensFullRequest,
+        SignatureHelpRequest, TypeHierarchyPrepare, TypeHierarchySubtypes,
+        TypeHierarchySupertypes, WillSaveWaitUntil, WorkspaceSymbolRequest,
     },
+    CallHierarchyClientCapabilities, CallHierarchyIncomingCall,
+    CallHierarchyIncomingCallsParams, CallHierarchyItem, CallHierarchyOutgoingCall,
+    CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
     ClientCapabilities, CodeAction, CodeActionCapabilityResolveSupport,
     CodeActionClientCapabilities, CodeActionContext, CodeActionKind,
     CodeActionKindLiteralSupport, CodeActionLiteralSupport, CodeActionParams,
     CodeActionResponse, CompletionClientCapabilities, CompletionItem,
     CompletionItemCapability, CompletionItemCapabilityResolveSupport,
     CompletionParams, CompletionResponse, Diagnostic, DocumentFormattingParams,
-    DocumentSymbolParams, DocumentSymbolResponse, FormattingOptions, GotoCapability,
-    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverClientCapabilities,
-    HoverParams, InlayHint, InlayHintClientCapabilities, InlayHintParams,
+    DocumentHighlight, DocumentHighlightClientCapabilities, DocumentHighlightParams,
+    DocumentLink, DocumentLinkClientCapabilities, DocumentLinkParams,
+    DocumentOnTypeFormattingClientCapabilities, DocumentOnTypeFormattingParams,
+    DocumentRangeFormattingClientCapabilities, DocumentRangeFormattingParams,
+    DocumentSymbolParams, DocumentSymbolResponse, FailureHandlingKind, FileEvent,
+    FormattingOptions, GotoCapability, GotoDefinitionParams, GotoDefinitionResponse,
+    Hover, HoverClientCapabilities, HoverParams, InlayHint,
+    InlayHintClientCapabilities, InlayHintParams,
     InlineCompletionClientCapabilities, InlineCompletionParams,
     InlineCompletionResponse, InlineCompletionTriggerKind, Location, MarkupKind,
     MessageActionItemCapabilities, ParameterInformationSettings,
     PartialResultParams, Position, PrepareRenameResponse,
-    PublishDiagnosticsClientCapabilities, Range, ReferenceContext, ReferenceParams,
-    RenameParams, SelectionRange, SelectionRangeParams, SemanticTokens,
+    PublishDiagnosticsClientCapabilities, PublishDiagnosticsParams, Range,
+    ReferenceContext, ReferenceParams, RenameParams, ResourceOperationKind,
+    SelectionRange, SelectionRangeParams, SemanticTokens,
     SemanticTokensClientCapabilities, SemanticTokensParams,
     ShowMessageRequestClientCapabilities, SignatureHelp,
     SignatureHelpClientCapabilities, SignatureHelpParams,
     SignatureInformationSettings, SymbolInformation, TextDocumentClientCapabilities,
     TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams,
-    TextDocumentSyncClientCapabilities, TextEdit, Url,
-    VersionedTextDocumentIdentifier, WindowClientCapabilities,
-    WorkDoneProgressParams, WorkspaceClientCapabilities, WorkspaceEdit,
+    TextDocumentSaveReason, TextDocumentSyncClientCapabilities, TextEdit,
+    TypeHierarchyClientCapabilities, TypeHierarchyItem, TypeHierarchyPrepareParams,
+    TypeHierarchySubtypesParams, TypeHierarchySupertypesParams, Url,
+    VersionedTextDocumentIdentifier, WillSaveTextDocumentParams,
+    WindowClientCapabilities, WorkDoneProgressParams, WorkspaceClientCapabilities,
+    WorkspaceEdit, WorkspaceEditClientCapabilities,
     WorkspaceSymbolClientCapabilities, WorkspaceSymbolParams,
 };
 use parking_lot::Mutex;
@@ -74,6 +97,8 @@
 use self::{
     catalog::PluginCatalog,
     dap::DapRpcHandler,
+    host_api::PluginEvent,
+    lsp::LspLog,
     psp::{ClonableCallback, PluginServerRpcHandler, RpcCallback},
     wasi::{load_volt, start_volt},
 };
@@ -122,6 +147,24 @@ pub enum PluginCatalogRpc {
             >,
         >,
     },
+    DapEvaluate {
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: String,
+        f: Box<dyn RpcCallback<dap_types::EvaluateResponse, RpcError>>,
+    },
+    DapCompletions {
+        dap_id: DapId,
+        text: String,
+        column: usize,
+        frame_id: Option<usize>,
+        f: Box<dyn RpcCallback<Vec<dap_types::CompletionItem>, RpcError>>,
+    },
+    LanguageServers {
+        language_id: Option<String>,
+        f: Box<dyn RpcCallback<Vec<LanguageServerInfo>, RpcError>>,
+    },
     DidOpenTextDocument {
         document: TextDocumentItem,
     },
@@ -151,6 +194,8 @@ pub enum PluginCatalogNotification {
     UpdatePluginConfigs(HashMap<String, HashMap<String, serde_json::Value>>),
     UnactivatedVolts(Vec<VoltMetadata>),
     PluginServerLoaded(PluginServerRpcHandler),
+    LanguageServerStarted(LanguageServerInfo),
+    InstallLanguageServer(String),
     InstallVolt(VoltInfo),
     StopVolt(VoltInfo),
     EnableVolt(VoltInfo),
@@ -206,6 +251,47 @@ pub enum PluginCatalogNotification {
         program: String,
         args: Option<Vec<String>>,
     },
+    /// A command registered by the plugin, which runs it when asked to
+    RegisterCommand {
+        plugin_id: PluginId,
+        command: PluginCommand,
+    },
+    RunPluginCommand {
+        volt_id: VoltID,
+        command: String,
+    },
+    /// A view shown by the plugin, which is told about clicks on its items
+    SetView {
+        plugin_id: PluginId,
+        view: PluginView,
+    },
+    RemoveView {
+        volt_id: VoltID,
+        view: String,
+    },
+    PluginViewItemClicked {
+        volt_id: VoltID,
+        view: String,
+        item: String,
+    },
+    /// The events the plugin is told about, replacing those it asked for
+    /// before
+    SubscribeEvents {
+        plugin_id: PluginId,
+        events: Vec<PluginEvent>,
+    },
+    DidCloseTextDocument(PathBuf),
+    /// A document closed with `DidCloseTextDocument` is shown again, without
+    /// being opened on the servers again
+    DidReopenTextDocument {
+        path: PathBuf,
+        language_id: String,
+    },
+    SelectionChanged {
+        path: PathBuf,
+        selections: Vec<Range>,
+    },
+    WorkspaceFilesChanged(Vec<FileEvent>),
     Shutdown,
 }
 
@@ -219,6 +305,16 @@ pub struct PluginCatalogRpcHandler {
     id: Arc<AtomicU64>,
     #[allow(dead_code, clippy::type_complexity)]
     pending: Arc<Mutex<HashMap<u64, Sender<Result<Value, RpcError>>>>>,
+    lsp_logs: Arc<Mutex<HashMap<VoltID, LspLog>>>,
+    /// The user's language server configs by language id
+    lsp_configs: Arc<Mutex<HashMap<String, LanguageServerConfig>>>,
+    /// The environment variables the user set for the workspace's language
+    /// servers and terminals
+    env: Arc<Mutex<HashMap<String, String>>>,
+    /// The diagnostics of each document by the volt whose servers published
+    /// them, the editor is sent all of them merged
+    #[allow(clippy::type_complexity)]
+    diagnostics: Arc<Mutex<HashMap<Url, HashMap<VoltID, Vec<Diagnostic>>>>>,
 }
 
 impl PluginCatalogRpcHandler {
@@ -231,6 +327,10 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             plugin_rx: Arc::new(Mutex::new(Some(plugin_rx))),
             id: Arc::new(AtomicU64::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            lsp_logs: Arc::new(Mutex::new(HashMap::new())),
+            lsp_configs: Arc::new(Mutex::new(HashMap::new())),
+            env: Arc::new(Mutex::new(HashMap::new())),
+            diagnostics: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -339,6 +439,27 @@ pub fn mainloop(&self, plugin: &mut PluginCatalog) {
                 } => {
                     plugin.dap_get_scopes(dap_id, frame_id, f);
                 }
+                PluginCatalogRpc::DapEvaluate {
+                    dap_id,
+                    expression,
+                    frame_id,
+                    context,
+                    f,
+                } => {
+                    plugin.dap_evaluate(dap_id, expression, frame_id, context, f);
+                }
+                PluginCatalogRpc::DapCompletions {
+                    dap_id,
+                    text,
+                    column,
+                    frame_id,
+                    f,
+                } => {
+                    plugin.dap_completions(dap_id, text, column, frame_id, f);
+                }
+                PluginCatalogRpc::LanguageServers { language_id, f } => {
+                    plugin.language_servers(language_id, f);
+                }
                 PluginCatalogRpc::Shutdown => {
                     return;
                 }
@@ -416,6 +537,50 @@ fn send_request_to_all_plugins<P, Resp>(
         );
     }
 
+    /// Send the request to every plugin and call `cb` once all of them have
+    /// responded, with the successful responses
+    fn collect_from_all_plugins<P, Resp>(
+        &self,
+        method: &'static str,
+        params: P,
+        language_id: Option<String>,
+        path: Option<PathBuf>,
+        cb: impl FnOnce(Vec<(PluginId, Resp)>) + Send + 'static,
+    ) where
+        P: Serialize,
+        Resp: DeserializeOwned + Send + 'static,
+    {
+        let request_sent = Arc::new(AtomicUsize::new(0));
+        let received = Arc::new(AtomicUsize::new(0));
+        let responses = Arc::new(Mutex::new(Vec::new()));
+        let cb = Arc::new(Mutex::new(Some(cb)));
+        self.send_request(
+            None,
+            Some(request_sent.clone()),
+            method,
+            params,
+            language_id,
+            path,
+            true,
+            move |plugin_id, result| {
+                if let Ok(item) = result.and_then(|value| {
+                    serde_json::from_value::<Resp>(value).map_err(|_| RpcError {
+                        code: 0,
+                        message: "deserialize error".to_string(),
+                    })
+                }) {
+                    responses.lock().push((plugin_id, item));
+                }
+                let rx = received.fetch_add(1, Ordering::Relaxed) + 1;
+                if request_sent.load(Ordering::Acquire) == rx {
+                    if let Some(cb) = cb.lock().take() {
+                        cb(std::mem::take(&mut *responses.lock()));
+                    }
+                }
+            },
+        );
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn send_request<P: Serialize>(
         &self,
@@ -491,6 +656,44 @@ pub fn did_save_text_document(&self, path: &Path, text: Rope) {
         });
     }
 
+    /// Send `textDocument/willSave`, then ask the servers for the edits to
+    /// make before saving, which are of the first server that has any
+    pub fn will_save_text_document(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(Vec<TextEdit>) + Send + 'static,
+    ) {
+        let text_document =
+            TextDocumentIdentifier::new(Url::from_file_path(path).unwrap());
+        let language_id = language_id_from_path(path).unwrap_or("").to_string();
+        let params = WillSaveTextDocumentParams {
+            text_document,
+            reason: TextDocumentSaveReason::MANUAL,
+        };
+        self.send_notification(
+            None,
+            WillSaveTextDocument::METHOD,
+            params.clone(),
+            Some(language_id.clone()),
+            Some(path.to_path_buf()),
+            true,
+        );
+        self.collect_from_all_plugins(
+            WillSaveWaitUntil::METHOD,
+            params,
+            Some(language_id),
+            Some(path.to_path_buf()),
+            move |responses: Vec<(PluginId, Option<Vec<TextEdit>>)>| {
+                let edits = responses
+                    .into_iter()
+                    .filter_map(|(_, edits)| edits)
+                    .find(|edits| !edits.is_empty())
+                    .unwrap_or_default();
+                cb(edits);
+            },
+        );
+    }
+
     pub fn did_change_text_document(
         &self,
         path: &Path,
@@ -611,15 +814,246 @@ pub fn get_references(
         );
     }
 
-    pub fn get_code_actions(
+    pub fn get_document_highlight(
         &self,
         path: &Path,
         position: Position,
-        diagnostics: Vec<Diagnostic>,
-        cb: impl FnOnce(PluginId, Result<CodeActionResponse, RpcError>)
+        cb: impl FnOnce(PluginId, Result<Vec<DocumentHighlight>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = DocumentHighlightRequest::METHOD;
+        let params = DocumentHighlightParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn prepare_call_hierarchy(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<CallHierarchyItem>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = CallHierarchyPrepare::METHOD;
+        let params = CallHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    /// The callers or the callees of an item, with the ranges of the calls,
+    /// from the server that prepared the item
+    pub fn call_hierarchy_calls(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        direction: CallHierarchyDirection,
+        cb: impl FnOnce(Result<Vec<(CallHierarchyItem, Vec<Range>)>, RpcError>)
+            + Send
+            + Clone
+            + 'static,
+    ) {
+        let deserialize_error = || RpcError {
+            code: 0,
+            message: "call hierarchy deserialize error".to_string(),
+        };
+        match direction {
+            CallHierarchyDirection::Incoming => {
+                let params = CallHierarchyIncomingCallsParams {
+                    item,
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                    partial_result_params: PartialResultParams::default(),
+                };
+                self.send_request(
+                    Some(plugin_id),
+                    None,
+                    CallHierarchyIncomingCalls::METHOD,
+                    params,
+                    None,
+                    None,
+                    true,
+                    move |_, result| {
+                        cb(result.and_then(|value| {
+                            serde_json::from_value::<
+                                Option<Vec<CallHierarchyIncomingCall>>,
+                            >(value)
+                            .map_err(|_| deserialize_error())
+                            .map(|calls| {
+                                calls
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(|call| (call.from, call.from_ranges))
+                                    .collect()
+                            })
+                        }))
+                    },
+                );
+            }
+            CallHierarchyDirection::Outgoing => {
+                let params = CallHierarchyOutgoingCallsParams {
+                    item,
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                    partial_result_params: PartialResultParams::default(),
+                };
+                self.send_request(
+                    Some(plugin_id),
+                    None,
+                    CallHierarchyOutgoingCalls::METHOD,
+                    params,
+                    None,
+                    None,
+                    true,
+                    move |_, result| {
+                        cb(result.and_then(|value| {
+                            serde_json::from_value::<
+                                Option<Vec<CallHierarchyOutgoingCall>>,
+                            >(value)
+                            .map_err(|_| deserialize_error())
+                            .map(|calls| {
+                                calls
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(|call| (call.to, call.from_ranges))
+                                    .collect()
+                            })
+                        }))
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn prepare_type_hierarchy(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<TypeHierarchyItem>>, RpcError>)
             + Clone
             + Send
             + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = TypeHierarchyPrepare::METHOD;
+        let params = TypeHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    /// The supertypes or the subtypes of an item, from the server that
+    /// prepared the item
+    pub fn type_hierarchy_related(
+        &self,
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        direction: TypeHierarchyDirection,
+        cb: impl FnOnce(Result<Vec<TypeHierarchyItem>, RpcError>)
+            + Send
+            + Clone
+            + 'static,
+    ) {
+        let work_done_progress_params = WorkDoneProgressParams::default();
+        let partial_result_params = PartialResultParams::default();
+        let (method, params) = match direction {
+            TypeHierarchyDirection::Supertypes => (
+                TypeHierarchySupertypes::METHOD,
+                serde_json::to_value(TypeHierarchySupertypesParams {
+                    item,
+                    work_done_progress_params,
+                    partial_result_params,
+                }),
+            ),
+            TypeHierarchyDirection::Subtypes => (
+                TypeHierarchySubtypes::METHOD,
+                serde_json::to_value(TypeHierarchySubtypesParams {
+                    item,
+                    work_done_progress_params,
+                    partial_result_params,
+                }),
+            ),
+        };
+        let params = match params {
+            Ok(params) => params,
+            Err(err) => {
+                cb(Err(RpcError {
+                    code: 0,
+                    message: err.to_string(),
+                }));
+                return;
+            }
+        };
+        self.send_request(
+            Some(plugin_id),
+            None,
+            method,
+            params,
+            None,
+            None,
+            true,
+            move |_, result| {
+                cb(result.and_then(|value| {
+                    serde_json::from_value::<Option<Vec<TypeHierarchyItem>>>(value)
+                        .map(|items| items.unwrap_or_default())
+                        .map_err(|_| RpcError {
+                            code: 0,
+                            message: "type hierarchy deserialize error".to_string(),
+                        })
+                }))
+            },
+        );
+    }
+
+    pub fn get_code_actions(
+        &self,
+        path: &Path,
+        position: Position,
+        diagnostics: Vec<Diagnostic>,
+        cb: impl FnOnce(Vec<(PluginId, CodeActionResponse)>) + Send + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
         let method = CodeActionRequest::METHOD;
@@ -639,7 +1073,7 @@ pub fn get_code_actions(
         };
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
-        self.send_request_to_all_plugins(
+        self.collect_from_all_plugins(
             method,
             params,
             language_id,
@@ -675,6 +1109,32 @@ pub fn get_inlay_hints(
         );
     }
 
+    pub fn get_document_links(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(PluginId, Result<Vec<DocumentLink>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = DocumentLinkRequest::METHOD;
+        let params = DocumentLinkParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
     pub fn get_inline_completions(
         &self,
         path: &Path,
@@ -755,30 +1215,108 @@ pub fn get_workspace_symbols(
     pub fn get_document_formatting(
         &self,
         path: &Path,
-        cb: impl FnOnce(PluginId, Result<Vec<TextEdit>, RpcError>)
-            + Clone
-            + Send
-            + 'static,
+        cb: impl FnOnce(Result<Vec<TextEdit>, RpcError>) + Send + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = Formatting::METHOD;
         let params = DocumentFormattingParams {
             text_document: TextDocumentIdentifier { uri },
-            options: FormattingOptions {
-                tab_size: 4,
-                insert_spaces: true,
-                ..Default::default()
-            },
+            options: formatting_options(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        self.collect_formatting(Formatting::METHOD, params, path, cb);
+    }
+
+    pub fn get_range_formatting(
+        &self,
+        path: &Path,
+        range: Range,
+        cb: impl FnOnce(Result<Vec<TextEdit>, RpcError>) + Send + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let params = DocumentRangeFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            options: formatting_options(),
             work_done_progress_params: WorkDoneProgressParams::default(),
         };
+        self.collect_formatting(RangeFormatting::METHOD, params, path, cb);
+    }
+
+    /// Format after `ch` was typed at `position`, which only the servers that
+    /// have `ch` as a trigger character are asked for
+    pub fn get_on_type_formatting(
+        &self,
+        path: &Path,
+        position: Position,
+        ch: String,
+        cb: impl FnOnce(Result<Vec<TextEdit>, RpcError>) + Send + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let params = DocumentOnTypeFormattingParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            ch,
+            options: formatting_options(),
+        };
+        self.collect_formatting(OnTypeFormatting::METHOD, params, path, cb);
+    }
+
+    /// Format with every server of the document's language, the edits used
+    /// are of the first that can in the language's `formatting-priority`,
+    /// then in the order the servers were started
+    fn collect_formatting<P: Serialize>(
+        &self,
+        method: &'static str,
+        params: P,
+        path: &Path,
+        cb: impl FnOnce(Result<Vec<TextEdit>, RpcError>) + Send + 'static,
+    ) {
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
-        self.send_request_to_all_plugins(
+        let priority = language_id
+            .as_ref()
+            .and_then(|language_id| {
+                self.lsp_configs
+                    .lock()
+                    .get(language_id)
+                    .map(|config| config.formatting_priority.clone())
+            })
+            .unwrap_or_default();
+        let catalog_rpc = self.clone();
+        self.collect_from_all_plugins(
             method,
             params,
-            language_id,
+            language_id.clone(),
             Some(path.to_path_buf()),
-            cb,
+            move |responses: Vec<(PluginId, Option<Vec<TextEdit>>)>| {
+                catalog_rpc.language_servers(language_id, move |servers| {
+                    let servers = servers.unwrap_or_default();
+                    let rank = |plugin_id: &PluginId| {
+                        let index = servers
+                            .iter()
+                            .position(|server| &server.plugin_id == plugin_id);
+                        let priority = index.and_then(|index| {
+                            let volt_id = &servers[index].volt_id;
+                            priority.iter().position(|name| {
+                                name == &volt_id.name || name == &volt_id.to_string()
+                            })
+                        });
+                        (priority.unwrap_or(usize::MAX), index.unwrap_or(usize::MAX))
+                    };
+                    let edits = responses
+                        .into_iter()
+                        .min_by_key(|(plugin_id, _)| rank(plugin_id))
+                        .map(|(_, edits)| edits.unwrap_or_default())
+                        .ok_or_else(|| RpcError {
+                            code: 0,
+                            message: "no language server can format the document"
+                                .to_string(),
+                        });
+                    cb(edits);
+                });
+            },
         );
     }
 
@@ -943,11 +1481,15 @@ pub fn completion(
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
 
-        self.send_request_to_all_plugins(
+        // Every server's items are sent on their own, the editor merges them
+        self.send_request(
+            None,
+            None,
             method,
             params,
             language_id,
             Some(path.to_path_buf()),
+            true,
             move |plugin_id, result| {
                 if let Ok(value) = result {
                     if let Ok(resp) =
@@ -1112,6 +1654,18 @@ pub fn plugin_server_loaded(
         ))
     }
 
+    pub fn language_server_started(&self, info: LanguageServerInfo) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::LanguageServerStarted(
+            info,
+        ))
+    }
+
+    pub fn install_language_server(&self, language_id: String) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::InstallLanguageServer(
+            language_id,
+        ))
+    }
+
     pub fn update_plugin_configs(
         &self,
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
@@ -1293,6 +1847,159 @@ pub fn dap_get_scopes(
         });
     }
 
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: String,
+        f: impl FnOnce(Result<dap_types::EvaluateResponse, RpcError>) + Send + 'static,
+    ) {
+        let _ = self.plugin_tx.send(PluginCatalogRpc::DapEvaluate {
+            dap_id,
+            expression,
+            frame_id,
+            context,
+            f: Box::new(f),
+        });
+    }
+
+    pub fn dap_completions(
+        &self,
+        dap_id: DapId,
+        text: String,
+        column: usize,
+        frame_id: Option<usize>,
+        f: impl FnOnce(Result<Vec<dap_types::CompletionItem>, RpcError>) + Send + 'static,
+    ) {
+        let _ = self.plugin_tx.send(PluginCatalogRpc::DapCompletions {
+            dap_id,
+            text,
+            column,
+            frame_id,
+            f: Box::new(f),
+        });
+    }
+
+    pub fn language_servers(
+        &self,
+        language_id: Option<String>,
+        f: impl FnOnce(Result<Vec<LanguageServerInfo>, RpcError>) + Send + 'static,
+    ) {
+        let _ = self.plugin_tx.send(PluginCatalogRpc::LanguageServers {
+            language_id,
+            f: Box::new(f),
+        });
+    }
+
+    /// Replace the language server configs, which apply to the servers
+    /// started afterwards
+    pub fn update_language_server_configs(
+        &self,
+        configs: HashMap<String, LanguageServerConfig>,
+    ) {
+        *self.lsp_configs.lock() = configs;
+    }
+
+    /// Replace the environment variables, which apply to the language servers
+    /// and terminals started afterwards
+    pub fn update_env(&self, env: HashMap<String, String>) {
+        *self.env.lock() = env;
+    }
+
+    pub fn env(&self) -> HashMap<String, String> {
+        self.env.lock().clone()
+    }
+
+    /// The config of the first language of `languages` that has one
+    pub fn language_server_config(
+        &self,
+        languages: &[String],
+    ) -> Option<LanguageServerConfig> {
+        let configs = self.lsp_configs.lock();
+        languages
+            .iter()
+            .find_map(|language| configs.get(language))
+            .cloned()
+    }
+
+    /// Replace the diagnostics the servers of the volt published for the
+    /// document and send the editor those of all the volts
+    pub fn publish_diagnostics(
+        &self,
+        volt_id: &VoltID,
+        params: PublishDiagnosticsParams,
+    ) {
+        let mut diagnostics = self.diagnostics.lock();
+        let by_volt = diagnostics.entry(params.uri.clone()).or_default();
+        if params.diagnostics.is_empty() {
+            by_volt.remove(volt_id);
+        } else {
+            by_volt.insert(volt_id.clone(), params.diagnostics);
+        }
+        let merged = by_volt.values().flatten().cloned().collect();
+        if by_volt.is_empty() {
+            diagnostics.remove(&params.uri);
+        }
+        self.core_rpc.publish_diagnostics(PublishDiagnosticsParams {
+            uri: params.uri,
+            diagnostics: merged,
+            version: params.version,
+        });
+    }
+
+    /// Remove the diagnostics of a volt whose servers were stopped
+    pub fn clear_diagnostics(&self, volt_id: &VoltID) {
+        let uris: Vec<Url> = self
+            .diagnostics
+            .lock()
+            .iter()
+            .filter(|(_, by_volt)| by_volt.contains_key(volt_id))
+            .map(|(uri, _)| uri.clone())
+            .collect();
+        for uri in uris {
+            self.publish_diagnostics(
+                volt_id,
+                PublishDiagnosticsParams {
+                    uri,
+                    diagnostics: Vec::new(),
+                    version: None,
+                },
+            );
+        }
+    }
+
+    /// The log of the language servers of a volt, kept across their restarts
+    pub fn lsp_log(&self, volt_id: &VoltID) -> LspLog {
+        self.lsp_logs
+            .lock()
+            .entry(volt_id.clone())
+            .or_default()
+            .clone()
+    }
+
+    /// Write the logs of the language servers handling `language_id`, or of
+    /// all of them, to a read-only file in the logs directory. Responds with
+    /// the file's path and its number of lines.
+    pub fn language_server_log(
+        &self,
+        language_id: Option<String>,
+        f: impl FnOnce(Result<(PathBuf, usize), RpcError>) + Send + 'static,
+    ) {
+        let lsp_logs = self.lsp_logs.clone();
+        let name = language_id.clone().unwrap_or_else(|| "all".to_string());
+        self.language_servers(language_id, move |result| {
+            f(result.and_then(|servers| {
+                write_lsp_log(&lsp_logs.lock(), &servers, &name).map_err(|e| {
+                    RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    }
+                })
+            }));
+        });
+    }
+
     pub fn register_debugger_type(
         &self,
         debugger_type: String,
@@ -1307,6 +2014,85 @@ pub fn register_debugger_type(
             },
         );
     }
+
+    pub fn register_command(&self, plugin_id: PluginId, command: PluginCommand) {
+        let _ =
+            self.catalog_notification(PluginCatalogNotification::RegisterCommand {
+                plugin_id,
+                command,
+            });
+    }
+
+    pub fn run_plugin_command(&self, volt_id: VoltID, command: String) {
+        let _ =
+            self.catalog_notification(PluginCatalogNotification::RunPluginCommand {
+                volt_id,
+                command,
+            });
+    }
+
+    pub fn set_view(&self, plugin_id: PluginId, view: PluginView) {
+        let _ = self.catalog_notification(PluginCatalogNotification::SetView {
+            plugin_id,
+            view,
+        });
+    }
+
+    pub fn remove_view(&self, volt_id: VoltID, view: String) {
+        let _ = self.catalog_notification(PluginCatalogNotification::RemoveView {
+            volt_id,
+            view,
+        });
+    }
+
+    pub fn plugin_view_item_clicked(
+        &self,
+        volt_id: VoltID,
+        view: String,
+        item: String,
+    ) {
+        let _ = self.catalog_notification(
+            PluginCatalogNotification::PluginViewItemClicked {
+                volt_id,
+                view,
+                item,
+            },
+        );
+    }
+
+    pub fn subscribe_events(&self, plugin_id: PluginId, events: Vec<PluginEvent>) {
+        let _ =
+            self.catalog_notification(PluginCatalogNotification::SubscribeEvents {
+                plugin_id,
+                events,
+            });
+    }
+
+    pub fn did_close_document(&self, path: PathBuf) {
+        let _ = self.catalog_notification(
+            PluginCatalogNotification::DidCloseTextDocument(path),
+        );
+    }
+
+    pub fn did_reopen_document(&self, path: PathBuf, language_id: String) {
+        let _ = self.catalog_notification(
+            PluginCatalogNotification::DidReopenTextDocument { path, language_id },
+        );
+    }
+
+    pub fn selection_changed(&self, path: PathBuf, selections: Vec<Range>) {
+        let _ =
+            self.catalog_notification(PluginCatalogNotification::SelectionChanged {
+                path,
+                selections,
+            });
+    }
+
+    pub fn workspace_files_changed(&self, changes: Vec<FileEvent>) {
+        let _ = self.catalog_notification(
+            PluginCatalogNotification::WorkspaceFilesChanged(changes),
+        );
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -1442,10 +2228,94 @@ pub fn remove_volt(
     Ok(())
 }
 
+fn write_lsp_log(
+    lsp_logs: &HashMap<VoltID, LspLog>,
+    servers: &[LanguageServerInfo],
+    name: &str,
+) -> Result<(PathBuf, usize)> {
+    if servers.is_empty() {
+        return Err(anyhow!("No language server is running"));
+    }
+    let path = Directory::logs_directory()
+        .ok_or_else(|| anyhow!("can't find the logs directory"))?
+        .join(format!("lsp.{name}.log"));
+
+    let mut volt_ids: Vec<&VoltID> = Vec::new();
+    for server in servers {
+        if !volt_ids.contains(&&server.volt_id) {
+            volt_ids.push(&server.volt_id);
+        }
+    }
+    let mut content = String::new();
+    for volt_id in volt_ids {
+        content += &format!("==== {volt_id} ====\n");
+        for entry in lsp_logs
+            .get(volt_id)
+            .map(LspLog::entries)
+            .unwrap_or_default()
+        {
+            content += &entry;
+            content.push('\n');
+        }
+    }
+
+    // The file is rewritten in place, so that the watcher of an open editor
+    // reloads it
+    if path.exists() {
+        let mut permissions = fs::metadata(&path)?.permissions();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            permissions.set_mode(0o644);
+        }
+        #[cfg(not(unix))]
+        #[allow(clippy::permissions_set_readonly_false)]
+        {
+            permissions.set_readonly(false);
+        }
+        fs::set_permissions(&path, permissions)?;
+    }
+    fs::write(&path, &content)?;
+    let mut permissions = fs::metadata(&path)?.permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&path, permissions)?;
+
+    Ok((path, content.lines().count()))
+}
+
+fn formatting_options() -> FormattingOptions {
+    FormattingOptions {
+        tab_size: 4,
+        insert_spaces: true,
+        ..Default::default()
+    }
+}
+
 fn client_capabilities() -> ClientCapabilities {
     ClientCapabilities {
         text_document: Some(TextDocumentClientCapabilities {
+            call_hierarchy: Some(CallHierarchyClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            type_hierarchy: Some(TypeHierarchyClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            document_highlight: Some(DocumentHighlightClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            range_formatting: Some(DocumentRangeFormattingClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            on_type_formatting: Some(DocumentOnTypeFormattingClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            document_link: Some(DocumentLinkClientCapabilities {
+                dynamic_registration: Some(false),
+                tooltip_support: Some(false),
+            }),
             synchronization: Some(TextDocumentSyncClientCapabilities {
+                will_save: Some(true),
+                will_save_wait_until: Some(true),
                 did_save: Some(true),
                 dynamic_registration: Some(true),
                 ..Default::default()
@@ -1539,6 +2409,17 @@ fn client_capabilities() -> ClientCapabilities {
             ..Default::default()
         }),
         workspace: Some(WorkspaceClientCapabilities {
+            apply_edit: Some(true),
+            workspace_edit: Some(WorkspaceEditClientCapabilities {
+                document_changes: Some(true),
+                resource_operations: Some(vec![
+                    ResourceOperationKind::Create,
+                    ResourceOperationKind::Rename,
+                    ResourceOperationKind::Delete,
+                ]),
+                failure_handling: Some(FailureHandlingKind::Abort),
+                ..Default::default()
+            }),
             symbol: Some(WorkspaceSymbolClientCapabilities {
                 ..Default::default()
             }),
@@ -7,17 +7,20 @@
         Arc,
     },
     thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
 use dyn_clone::DynClone;
 use floem_editor_core::buffer::rope_text::{RopeText, RopeTextRef};
 use jsonrpc_lite::{Id, JsonRpc, Params};
 use lapce_core::{encoding::offset_utf16_to_utf8, rope_text_pos::RopeTextPosition};
 use lapce_rpc::{
     core::CoreRpcHandler,
-    plugin::{PluginId, VoltID},
+    core::{CoreRequest, CoreResponse},
+    plugin::{PluginCommand, PluginId, PluginView, VoltCapability, VoltID},
+    proxy::ProxyResponse,
     style::{LineStyle, Style},
     RpcError,
 };
@@ -26,25 +29,33 @@
     notification::{
         DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument,
         Initialized, LogMessage, Notification, Progress, PublishDiagnostics,
-        ShowMessage,
+        ShowMessage, WillSaveTextDocument,
     },
     request::{
-        CodeActionRequest, CodeActionResolveRequest, Completion,
+        ApplyWorkspaceEdit, CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls,
+        CallHierarchyPrepare, CodeActionRequest, CodeActionResolveRequest,
+        Completion, DocumentHighlightRequest, DocumentLinkRequest,
         DocumentSymbolRequest, Formatting, GotoDefinition, GotoTypeDefinition,
         HoverRequest, Initialize, InlayHintRequest, InlineCompletionRequest,
-        PrepareRenameRequest, References, RegisterCapability, Rename,
-        ResolveCompletionItem, SelectionRangeRequest, SemanticTokensFullRequest,
-        SignatureHelpRequest, WorkDoneProgressCreate, WorkspaceSymbolRequest,
+        OnTypeFormatting, PrepareRenameRequest, RangeFormatting, References,
+        RegisterCapability, Rename, ResolveCompletionItem, SelectionRangeRequest,
+        SemanticTokensFullRequest, ShowMessageRequest, SignatureHelpRequest,
+        TypeHierarchyPrepare, TypeHierarchySubtypes, TypeHierarchySupertypes,
+        WillSaveWaitUntil, WorkDoneProgressCreate, WorkspaceSymbolRequest,
     },
-    CodeActionProviderCapability, DidChangeTextDocumentParams,
-    DidSaveTextDocumentParams, DocumentSelector, HoverProviderCapability,
-    InitializeResult, LogMessageParams, OneOf, ProgressParams,
-    PublishDiagnosticsParams, Range, Registration, RegistrationParams,
-    SemanticTokens, SemanticTokensLegend, SemanticTokensServerCapabilities,
-    ServerCapabilities, ShowMessageParams, TextDocumentContentChangeEvent,
+    ApplyWorkspaceEditParams, ApplyWorkspaceEditResponse,
+    CallHierarchyServerCapability, CodeActionProviderCapability,
+    DidChangeTextDocumentParams, DidSaveTextDocumentParams,
+    DocumentOnTypeFormattingParams, DocumentSelector, HoverProviderCapability,
+    InitializeResult, LogMessageParams, NumberOrString, OneOf, ProgressParams,
+    ProgressParamsValue, PublishDiagnosticsParams, Range, Registration,
+    RegistrationParams, SemanticTokens, SemanticTokensLegend,
+    SemanticTokensServerCapabilities, ServerCapabilities, ShowMessageParams,
+    ShowMessageRequestParams, TextDocumentContentChangeEvent,
     TextDocumentIdentifier, TextDocumentSaveRegistrationOptions,
-    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncSaveOptions,
-    VersionedTextDocumentIdentifier,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    TextDocumentSyncSaveOptions, Url, VersionedTextDocumentIdentifier,
+    WorkDoneProgress,
 };
 use parking_lot::Mutex;
 use psp_types::{
@@ -58,6 +69,11 @@
 use serde_json::Value;
 
 use super::{
+    host_api::{
+        CreateView, CreateViewParams, GetBufferContent, GetBufferContentParams,
+        GetBufferContentResult, RegisterCommand, RegisterCommandParams, RemoveView,
+        RemoveViewParams, SubscribeEvents, SubscribeEventsParams,
+    },
     lsp::{DocumentFilter, LspClient},
     PluginCatalogRpcHandler,
 };
@@ -100,6 +116,10 @@ fn call(self: Box<F>, result: Result<Resp, Error>) {
     }
 }
 
+/// How long to wait after an edit for more edits before sending the server the
+/// changes together, when the language server config doesn't say
+pub const DEFAULT_CHANGE_DEBOUNCE: Duration = Duration::from_millis(50);
+
 pub enum PluginHandlerNotification {
     Initialize,
     InitializeResult(InitializeResult),
@@ -209,6 +229,9 @@ fn document_supported(
         path: Option<&Path>,
     ) -> bool;
     fn method_registered(&mut self, method: &str) -> bool;
+    /// Whether the server takes a request of a registered method with the
+    /// params, for the methods it only takes some of the params of
+    fn request_supported(&mut self, method: &str, params: &Params) -> bool;
     fn handle_host_notification(&mut self, method: String, params: Params);
     fn handle_host_request(
         &mut self,
@@ -248,6 +271,9 @@ fn format_semantic_tokens(
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     );
+    /// When the edits held back to be sent together are due to be sent
+    fn pending_changes_deadline(&self) -> Option<Instant>;
+    fn send_pending_changes(&mut self);
 }
 
 impl PluginServerRpcHandler {
@@ -427,7 +453,34 @@ pub fn mainloop<H>(&self, handler: &mut H)
     where
         H: PluginServerHandler,
     {
-        for msg in &self.rpc_rx {
+        loop {
+            let msg = match handler.pending_changes_deadline() {
+                Some(deadline) => match self.rpc_rx.recv_deadline(deadline) {
+                    Ok(msg) => msg,
+                    Err(RecvTimeoutError::Timeout) => {
+                        handler.send_pending_changes();
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return,
+                },
+                None => match self.rpc_rx.recv() {
+                    Ok(msg) => msg,
+                    Err(_) => return,
+                },
+            };
+
+            // The server has to have the edits held back before anything else
+            // about the document is sent to it
+            if !matches!(
+                msg,
+                PluginServerRpc::DidChangeTextDocument { .. }
+                    | PluginServerRpc::HostRequest { .. }
+                    | PluginServerRpc::HostNotification { .. }
+                    | PluginServerRpc::FormatSemanticTokens { .. }
+            ) {
+                handler.send_pending_changes();
+            }
+
             match msg {
                 PluginServerRpc::ServerRequest {
                     id,
@@ -440,6 +493,7 @@ pub fn mainloop<H>(&self, handler: &mut H)
                     if handler
                         .document_supported(language_id.as_deref(), path.as_deref())
                         && handler.method_registered(&method)
+                        && handler.request_supported(&method, &params)
                     {
                         self.send_server_request(id, &method, params, rh);
                     } else {
@@ -522,7 +576,22 @@ pub fn handle_plugin_server_message(
     message: &str,
 ) -> Option<JsonRpc> {
     match JsonRpc::parse(message) {
-        Ok(value @ JsonRpc::Request(_)) => {
+        Ok(value) => handle_plugin_server_rpc(server_rpc, value),
+        Err(err) => {
+            eprintln!("parse error {err} message {message}");
+            None
+        }
+    }
+}
+
+/// Handle a message from a plugin server that was already parsed, returning
+/// the response to send back to it if there is one
+pub fn handle_plugin_server_rpc(
+    server_rpc: &PluginServerRpcHandler,
+    value: JsonRpc,
+) -> Option<JsonRpc> {
+    match value {
+        value @ JsonRpc::Request(_) => {
             let (tx, rx) = crossbeam_channel::bounded(1);
             let id = value.get_id().unwrap();
             let rpc = PluginServerRpc::HostRequest {
@@ -546,7 +615,7 @@ pub fn handle_plugin_server_message(
             };
             Some(resp)
         }
-        Ok(value @ JsonRpc::Notification(_)) => {
+        value @ JsonRpc::Notification(_) => {
             let rpc = PluginServerRpc::HostNotification {
                 method: value.get_method().unwrap().to_string(),
                 params: value.get_params().unwrap(),
@@ -554,12 +623,12 @@ pub fn handle_plugin_server_message(
             server_rpc.handle_rpc(rpc);
             None
         }
-        Ok(value @ JsonRpc::Success(_)) => {
+        value @ JsonRpc::Success(_) => {
             let result = value.get_result().unwrap().clone();
             server_rpc.handle_server_response(value.get_id().unwrap(), Ok(result));
             None
         }
-        Ok(value @ JsonRpc::Error(_)) => {
+        value @ JsonRpc::Error(_) => {
             let error = value.get_error().unwrap();
             server_rpc.handle_server_response(
                 value.get_id().unwrap(),
@@ -570,10 +639,6 @@ pub fn handle_plugin_server_message(
             );
             None
         }
-        Err(err) => {
-            eprintln!("parse error {err} message {message}");
-            None
-        }
     }
 }
 
@@ -598,10 +663,27 @@ pub struct PluginHostHandler {
     pub server_rpc: PluginServerRpcHandler,
     pub server_capabilities: ServerCapabilities,
     server_registrations: ServerRegistrations,
+    /// The parts of the host API the volt or language server may use, everything
+    /// else is denied
+    capabilities: Vec<VoltCapability>,
 
     /// Language servers that this plugin has spawned.  
     /// Note that these plugin ids could be 'dead' if the LSP died/exited.  
     spawned_lsp: HashMap<PluginId, SpawnedLspInfo>,
+
+    /// How long to wait after an edit for more edits, before sending them together
+    pub change_debounce: Duration,
+    pending_changes: Option<PendingChanges>,
+}
+
+/// The edits of a document not yet sent to the server
+struct PendingChanges {
+    language_id: String,
+    path: Option<PathBuf>,
+    /// The version after the last of the edits
+    document: VersionedTextDocumentIdentifier,
+    changes: Vec<TextDocumentContentChangeEvent>,
+    deadline: Instant,
 }
 
 impl PluginHostHandler {
@@ -615,6 +697,7 @@ pub fn new(
         core_rpc: CoreRpcHandler,
         server_rpc: PluginServerRpcHandler,
         catalog_rpc: PluginCatalogRpcHandler,
+        capabilities: Vec<VoltCapability>,
     ) -> Self {
         let document_selector = document_selector
             .iter()
@@ -631,10 +714,27 @@ pub fn new(
             server_rpc,
             server_capabilities: ServerCapabilities::default(),
             server_registrations: ServerRegistrations::default(),
+            capabilities,
             spawned_lsp: HashMap::new(),
+            change_debounce: DEFAULT_CHANGE_DEBOUNCE,
+            pending_changes: None,
         }
     }
 
+    /// Errors if the volt didn't declare it uses `capability`
+    fn require(&self, capability: VoltCapability) -> Result<()> {
+        if self.capabilities.contains(&capability) {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "{} doesn't declare the {} capability",
+            self.volt_display_name,
+            serde_json::to_value(capability)?
+                .as_str()
+                .unwrap_or_default()
+        ))
+    }
+
     pub fn document_supported(
         &self,
         language_id: Option<&str>,
@@ -662,6 +762,37 @@ pub fn document_supported(
         }
     }
 
+    pub fn request_supported(&self, method: &str, params: &Params) -> bool {
+        match method {
+            // Only the typed characters the server asked for are sent
+            OnTypeFormatting::METHOD => {
+                let Some(options) = self
+                    .server_capabilities
+                    .document_on_type_formatting_provider
+                    .as_ref()
+                else {
+                    return false;
+                };
+                serde_json::to_value(params)
+                    .ok()
+                    .and_then(|params| {
+                        serde_json::from_value::<DocumentOnTypeFormattingParams>(
+                            params,
+                        )
+                        .ok()
+                    })
+                    .is_some_and(|params| {
+                        options.first_trigger_character == params.ch
+                            || options
+                                .more_trigger_character
+                                .as_ref()
+                                .is_some_and(|more| more.contains(&params.ch))
+                    })
+            }
+            _ => true,
+        }
+    }
+
     pub fn method_registered(&mut self, method: &str) -> bool {
         match method {
             Initialize::METHOD => true,
@@ -736,6 +867,15 @@ pub fn method_registered(&mut self, method: &str) -> bool {
                     OneOf::Right(_) => true,
                 })
                 .unwrap_or(false),
+            DocumentHighlightRequest::METHOD => self
+                .server_capabilities
+                .document_highlight_provider
+                .as_ref()
+                .map(|h| match h {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
             CodeActionRequest::METHOD => self
                 .server_capabilities
                 .code_action_provider
@@ -754,9 +894,33 @@ pub fn method_registered(&mut self, method: &str) -> bool {
                     OneOf::Right(_) => true,
                 })
                 .unwrap_or(false),
+            RangeFormatting::METHOD => self
+                .server_capabilities
+                .document_range_formatting_provider
+                .as_ref()
+                .map(|f| match f {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
+            OnTypeFormatting::METHOD => self
+                .server_capabilities
+                .document_on_type_formatting_provider
+                .is_some(),
             SemanticTokensFullRequest::METHOD => {
                 self.server_capabilities.semantic_tokens_provider.is_some()
             }
+            WillSaveTextDocument::METHOD => self
+                .sync_options()
+                .and_then(|o| o.will_save)
+                .unwrap_or(false),
+            WillSaveWaitUntil::METHOD => self
+                .sync_options()
+                .and_then(|o| o.will_save_wait_until)
+                .unwrap_or(false),
+            DocumentLinkRequest::METHOD => {
+                self.server_capabilities.document_link_provider.is_some()
+            }
             InlayHintRequest::METHOD => {
                 self.server_capabilities.inlay_hint_provider.is_some()
             }
@@ -780,24 +944,44 @@ pub fn method_registered(&mut self, method: &str) -> bool {
             CodeActionResolveRequest::METHOD => {
                 self.server_capabilities.code_action_provider.is_some()
             }
+            CallHierarchyPrepare::METHOD
+            | CallHierarchyIncomingCalls::METHOD
+            | CallHierarchyOutgoingCalls::METHOD => self
+                .server_capabilities
+                .call_hierarchy_provider
+                .as_ref()
+                .map(|c| match c {
+                    CallHierarchyServerCapability::Simple(is_capable) => *is_capable,
+                    CallHierarchyServerCapability::Options(_) => true,
+                })
+                .unwrap_or(false),
+            // The server capabilities don't have the type hierarchy provider,
+            // so the servers without it are left to answer with an error
+            TypeHierarchyPrepare::METHOD
+            | TypeHierarchySupertypes::METHOD
+            | TypeHierarchySubtypes::METHOD => true,
             _ => false,
         }
     }
 
+    fn sync_options(&self) -> Option<&TextDocumentSyncOptions> {
+        self.server_capabilities
+            .text_document_sync
+            .as_ref()
+            .and_then(|sync| match sync {
+                TextDocumentSyncCapability::Kind(_) => None,
+                TextDocumentSyncCapability::Options(options) => Some(options),
+            })
+    }
+
     fn check_save_capability(&self, language_id: &str, path: &Path) -> (bool, bool) {
         if self.document_supported(Some(language_id), Some(path)) {
             let (should_send, include_text) = self
-                .server_capabilities
-                .text_document_sync
-                .as_ref()
-                .and_then(|sync| match sync {
-                    TextDocumentSyncCapability::Kind(_) => None,
-                    TextDocumentSyncCapability::Options(options) => Some(options),
-                })
+                .sync_options()
                 .and_then(|o| o.save.as_ref())
                 .map(|o| match o {
                     TextDocumentSyncSaveOptions::Supported(is_supported) => {
-                        (*is_supported, true)
+                        (*is_supported, false)
                     }
                     TextDocumentSyncSaveOptions::SaveOptions(options) => {
                         (true, options.include_text.unwrap_or(false))
@@ -881,6 +1065,18 @@ pub fn process_request(
             WorkDoneProgressCreate::METHOD => {
                 resp.send_null();
             }
+            ApplyWorkspaceEdit::METHOD => {
+                self.require(VoltCapability::EditBuffers)?;
+                let params: ApplyWorkspaceEditParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc.core_rpc.apply_workspace_edit(params.edit);
+                // The edit is applied by the editor, which doesn't report back
+                resp.send(ApplyWorkspaceEditResponse {
+                    applied: true,
+                    failure_reason: None,
+                    failed_change: None,
+                });
+            }
             RegisterCapability::METHOD => {
                 let params: RegistrationParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
@@ -888,6 +1084,7 @@ pub fn process_request(
                 resp.send_null();
             }
             ExecuteProcess::METHOD => {
+                self.require(VoltCapability::Processes)?;
                 let params: ExecuteProcessParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 let output = std::process::Command::new(params.program)
@@ -901,6 +1098,7 @@ pub fn process_request(
                 });
             }
             RegisterDebuggerType::METHOD => {
+                self.require(VoltCapability::Processes)?;
                 let params: RegisterDebuggerTypeParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 self.catalog_rpc.register_debugger_type(
@@ -911,6 +1109,7 @@ pub fn process_request(
                 resp.send_null();
             }
             StartLspServer::METHOD => {
+                self.require(VoltCapability::LanguageServers)?;
                 let params: StartLspServerParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 let workspace = self.workspace.clone();
@@ -999,6 +1198,87 @@ pub fn process_request(
                     },
                 )
             }
+            RegisterCommand::METHOD => {
+                self.require(VoltCapability::Commands)?;
+                let params: RegisterCommandParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc.register_command(
+                    self.server_rpc.plugin_id,
+                    PluginCommand {
+                        volt_id: self.volt_id.clone(),
+                        volt_display_name: self.volt_display_name.clone(),
+                        command: params.command,
+                        title: params.title,
+                    },
+                );
+                resp.send_null();
+            }
+            CreateView::METHOD => {
+                self.require(VoltCapability::Views)?;
+                let params: CreateViewParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc.set_view(
+                    self.server_rpc.plugin_id,
+                    PluginView {
+                        volt_id: self.volt_id.clone(),
+                        volt_display_name: self.volt_display_name.clone(),
+                        id: params.id,
+                        title: params.title,
+                        items: params.items,
+                    },
+                );
+                resp.send_null();
+            }
+            RemoveView::METHOD => {
+                self.require(VoltCapability::Views)?;
+                let params: RemoveViewParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc
+                    .remove_view(self.volt_id.clone(), params.id);
+                resp.send_null();
+            }
+            SubscribeEvents::METHOD => {
+                self.require(VoltCapability::Events)?;
+                let params: SubscribeEventsParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc
+                    .subscribe_events(self.server_rpc.plugin_id, params.events);
+                resp.send_null();
+            }
+            GetBufferContent::METHOD => {
+                self.require(VoltCapability::ReadBuffers)?;
+                let params: GetBufferContentParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let workspace = self.workspace.clone();
+                let catalog_rpc = self.catalog_rpc.clone();
+                // Asking the dispatcher for the open documents blocks, which the
+                // plugin's own thread mustn't do
+                thread::spawn(move || {
+                    match buffer_content(&catalog_rpc, workspace, params.uri) {
+                        Ok(result) => resp.send(result),
+                        Err(err) => resp.send_err(0, err.to_string()),
+                    }
+                });
+            }
+            ShowMessageRequest::METHOD => {
+                self.require(VoltCapability::Prompts)?;
+                let params: ShowMessageRequestParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let core_rpc = self.core_rpc.clone();
+                let title = self.volt_display_name.clone();
+                // The request is only answered once the user picked an action
+                thread::spawn(move || {
+                    match core_rpc
+                        .request(CoreRequest::ShowMessageRequest { title, params })
+                    {
+                        Ok(CoreResponse::ShowMessageRequest { action }) => {
+                            resp.send(action)
+                        }
+                        Ok(_) => resp.send_null(),
+                        Err(err) => resp.send_err(err.code, err.message),
+                    }
+                });
+            }
             _ => return Err(anyhow!("request not supported")),
         }
 
@@ -1013,6 +1293,7 @@ pub fn handle_notification(
         match method.as_str() {
             // TODO: remove this after the next release and once we convert all the existing plugins to use the request.
             StartLspServer::METHOD => {
+                self.require(VoltCapability::LanguageServers)?;
                 self.core_rpc.log(
                     lapce_rpc::core::LogLevel::Warn,
                     format!(
@@ -1048,11 +1329,27 @@ pub fn handle_notification(
             PublishDiagnostics::METHOD => {
                 let diagnostics: PublishDiagnosticsParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
-                self.catalog_rpc.core_rpc.publish_diagnostics(diagnostics);
+                self.catalog_rpc
+                    .publish_diagnostics(&self.volt_id, diagnostics);
             }
             Progress::METHOD => {
-                let progress: ProgressParams =
+                let mut progress: ProgressParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
+                // Tokens are only unique per server, so they are namespaced by the
+                // plugin, whose name is shown in front of the title
+                let token = match &progress.token {
+                    NumberOrString::Number(n) => n.to_string(),
+                    NumberOrString::String(s) => s.clone(),
+                };
+                progress.token =
+                    NumberOrString::String(format!("{}:{token}", self.volt_id));
+                if let ProgressParamsValue::WorkDone(WorkDoneProgress::Begin(
+                    begin,
+                )) = &mut progress.value
+                {
+                    begin.title =
+                        format!("{}: {}", self.volt_display_name, begin.title);
+                }
                 self.catalog_rpc.core_rpc.work_done_progress(progress);
             }
             ShowMessage::METHOD => {
@@ -1169,19 +1466,58 @@ pub fn handle_did_change_text_document(
             TextDocumentSyncKind::NONE => return,
             _ => return,
         };
+        drop(existing);
 
-        let path = document.uri.to_file_path().ok();
+        // The edits of another document go in a notification of their own
+        if self
+            .pending_changes
+            .as_ref()
+            .is_some_and(|pending| pending.document.uri != document.uri)
+        {
+            self.send_pending_changes();
+        }
 
+        let deadline = Instant::now() + self.change_debounce;
+        let pending = self.pending_changes.get_or_insert_with(|| PendingChanges {
+            language_id: lanaguage_id,
+            path: document.uri.to_file_path().ok(),
+            document: document.clone(),
+            changes: Vec::new(),
+            deadline,
+        });
+        pending.document = document;
+        pending.deadline = deadline;
+        // Each full sync replaces the content the ones before it sent
+        if kind == TextDocumentSyncKind::FULL {
+            pending.changes.clear();
+        }
+        pending.changes.push(change);
+
+        if self.change_debounce.is_zero() {
+            self.send_pending_changes();
+        }
+    }
+
+    pub fn pending_changes_deadline(&self) -> Option<Instant> {
+        self.pending_changes
+            .as_ref()
+            .map(|pending| pending.deadline)
+    }
+
+    /// Send the server the edits held back, in one notification
+    pub fn send_pending_changes(&mut self) {
+        let Some(pending) = self.pending_changes.take() else {
+            return;
+        };
         let params = DidChangeTextDocumentParams {
-            text_document: document,
-            content_changes: vec![change],
+            text_document: pending.document,
+            content_changes: pending.changes,
         };
-
         self.server_rpc.server_notification(
             DidChangeTextDocument::METHOD,
             params,
-            Some(lanaguage_id),
-            path,
+            Some(pending.language_id),
+            pending.path,
             false,
         );
     }
@@ -1229,6 +1565,40 @@ struct SpawnedLspInfo {
     resp: Option<ResponseSender>,
 }
 
+/// The content of the document at `uri`, from the editor if it is open, or
+/// else from disk as long as it is a file of the workspace
+fn buffer_content(
+    catalog_rpc: &PluginCatalogRpcHandler,
+    workspace: Option<PathBuf>,
+    uri: Url,
+) -> Result<GetBufferContentResult> {
+    if let Ok(ProxyResponse::GetOpenFilesContentResponse { items }) =
+        catalog_rpc.proxy_rpc.get_open_files_content()
+    {
+        if let Some(item) = items.into_iter().find(|item| item.uri == uri) {
+            return Ok(GetBufferContentResult {
+                content: item.text,
+                version: Some(item.version),
+            });
+        }
+    }
+
+    let path = uri
+        .to_file_path()
+        .map_err(|_| anyhow!("{uri} isn't a file"))?
+        .canonicalize()?;
+    if !workspace
+        .and_then(|workspace| workspace.canonicalize().ok())
+        .is_some_and(|workspace| path.starts_with(workspace))
+    {
+        return Err(anyhow!("{} isn't in the workspace", path.display()));
+    }
+    Ok(GetBufferContentResult {
+        content: std::fs::read_to_string(path)?,
+        version: None,
+    })
+}
+
 fn get_document_content_change(
     text: &Rope,
     delta: &RopeDelta,
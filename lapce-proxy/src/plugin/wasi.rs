@@ -9,22 +9,23 @@
     process,
     sync::{Arc, RwLock},
     thread,
+    time::Instant,
 };
 
 use anyhow::{anyhow, Result};
 use jsonrpc_lite::{Id, Params};
-use lapce_core::directory::Directory;
+use lapce_core::{directory::Directory, language::LapceLanguage};
 use lapce_rpc::{
-    plugin::{PluginId, VoltID, VoltInfo, VoltMetadata},
+    plugin::{PluginId, VoltCapability, VoltID, VoltInfo, VoltMetadata},
     style::LineStyle,
     RpcError,
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
     notification::Initialized, request::Initialize, DocumentFilter,
-    InitializeParams, InitializedParams, TextDocumentContentChangeEvent,
-    TextDocumentIdentifier, Url, VersionedTextDocumentIdentifier,
-    WorkDoneProgressParams, WorkspaceFolder,
+    InitializeParams, InitializedParams, MessageType, ShowMessageParams,
+    TextDocumentContentChangeEvent, TextDocumentIdentifier, Url,
+    VersionedTextDocumentIdentifier, WorkDoneProgressParams, WorkspaceFolder,
 };
 use parking_lot::Mutex;
 use psp_types::{Notification, Request};
@@ -34,6 +35,7 @@
 
 use super::{
     client_capabilities,
+    lsp::LspClient,
     psp::{
         handle_plugin_server_message, PluginHandlerNotification, PluginHostHandler,
         PluginServerHandler, PluginServerRpc, ResponseSender, RpcCallback,
@@ -94,6 +96,10 @@ fn method_registered(&mut self, method: &str) -> bool {
         self.host.method_registered(method)
     }
 
+    fn request_supported(&mut self, method: &str, params: &Params) -> bool {
+        self.host.request_supported(method, params)
+    }
+
     fn document_supported(
         &mut self,
         language_id: Option<&str>,
@@ -184,6 +190,14 @@ fn format_semantic_tokens(
     ) {
         self.host.format_semantic_tokens(tokens, text, f);
     }
+
+    fn pending_changes_deadline(&self) -> Option<Instant> {
+        self.host.pending_changes_deadline()
+    }
+
+    fn send_pending_changes(&mut self) {
+        self.host.send_pending_changes();
+    }
 }
 
 impl Plugin {
@@ -243,10 +257,19 @@ pub fn load_all_volts(
     disabled_volts: Vec<VoltID>,
 ) {
     let all_volts = find_all_volts(extra_plugin_paths);
+    LapceLanguage::load_runtime_languages(
+        &all_volts
+            .iter()
+            .filter(|meta| !disabled_volts.contains(&meta.id()))
+            .cloned()
+            .collect::<Vec<_>>(),
+    );
     let volts = all_volts
         .into_iter()
         .filter_map(|meta| {
-            meta.wasm.as_ref()?;
+            if meta.wasm.is_none() {
+                meta.languages.as_ref()?;
+            }
             let icon = volt_icon(&meta);
             plugin_rpc.core_rpc.volt_installed(meta.clone(), icon);
             if disabled_volts.contains(&meta.id()) {
@@ -352,7 +375,9 @@ pub fn find_all_volts(extra_plugin_paths: &[PathBuf]) -> Vec<VoltMetadata> {
 ///         icon_themes: None,
 ///         dir: parent_path.canonicalize().ok(),
 ///         activation: None,
-///         config: None
+///         config: None,
+///         capabilities: None,
+///         languages: None
 ///     }
 /// );
 /// let _ = std::fs::remove_file(parent_path.join("volt.toml"));
@@ -404,12 +429,70 @@ pub fn enable_volt(
     Ok(())
 }
 
+/// Starts the servers of the languages the volt adds, the binaries are looked
+/// up in the volt before `PATH`
+fn start_language_servers(
+    workspace: Option<PathBuf>,
+    plugin_rpc: PluginCatalogRpcHandler,
+    meta: &VoltMetadata,
+) {
+    let Some(dir) = meta.dir.as_ref() else {
+        return;
+    };
+    for language in meta.languages.iter().flatten() {
+        let Some(server) = language.language_server.as_ref() else {
+            continue;
+        };
+        let path = dir.join(&server.command);
+        let server_uri = if path.is_file() {
+            Url::from_file_path(&path).ok()
+        } else {
+            Url::parse(&format!("urn:{}", server.command)).ok()
+        };
+        let Some(server_uri) = server_uri else {
+            continue;
+        };
+        let document_selector = vec![DocumentFilter {
+            language: Some(language.id.clone()),
+            scheme: None,
+            pattern: None,
+        }];
+        if let Err(err) = LspClient::start(
+            plugin_rpc.clone(),
+            document_selector,
+            workspace.clone(),
+            meta.id(),
+            meta.display_name.clone(),
+            None,
+            None,
+            Some(dir.clone()),
+            server_uri,
+            server.args.clone(),
+            server.initialization_options.clone(),
+        ) {
+            plugin_rpc.core_rpc.show_message(
+                format!("Failed to start {}", server.command),
+                ShowMessageParams {
+                    typ: MessageType::ERROR,
+                    message: err.to_string(),
+                },
+            );
+        }
+    }
+}
+
 pub fn start_volt(
     workspace: Option<PathBuf>,
     configurations: Option<HashMap<String, serde_json::Value>>,
     plugin_rpc: PluginCatalogRpcHandler,
     meta: VoltMetadata,
 ) -> Result<()> {
+    start_language_servers(workspace.clone(), plugin_rpc.clone(), &meta);
+    if meta.wasm.is_none() && meta.languages.is_some() {
+        // The volt only bundles languages
+        return Ok(());
+    }
+
     let engine = wasmtime::Engine::default();
     let module = wasmtime::Module::from_file(
         &engine,
@@ -569,6 +652,9 @@ pub fn start_volt(
             plugin_rpc.core_rpc.clone(),
             rpc.clone(),
             plugin_rpc.clone(),
+            meta.capabilities
+                .clone()
+                .unwrap_or_else(VoltCapability::legacy),
         ),
         configurations,
     };
@@ -589,7 +675,9 @@ fn wasi_read_string(stdout: &Arc<RwLock<WasiPipe>>) -> Result<String> {
     Ok(buf)
 }
 
-fn unflatten_map(map: &HashMap<String, serde_json::Value>) -> serde_json::Value {
+pub(crate) fn unflatten_map(
+    map: &HashMap<String, serde_json::Value>,
+) -> serde_json::Value {
     let mut new = serde_json::json!({});
     for (key, value) in map.iter() {
         let mut current = new.as_object_mut().unwrap();
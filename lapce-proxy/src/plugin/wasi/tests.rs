@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
-use lapce_rpc::plugin::VoltMetadata;
+use lapce_rpc::plugin::{
+    VoltCapability, VoltLanguage, VoltLanguageServer, VoltMetadata,
+};
 use serde_json::{json, Value};
 
 use super::{load_volt, unflatten_map};
@@ -145,7 +147,9 @@ fn test_load_volt() {
             icon_themes: Some(icon_themes_pathes),
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            capabilities: None,
+            languages: None
         }
     );
 
@@ -205,7 +209,12 @@ fn test_load_volt() {
             icon_themes: Some(icon_themes_pathes),
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            capabilities: Some(vec![
+                VoltCapability::ReadBuffers,
+                VoltCapability::Prompts
+            ]),
+            languages: None
         }
     );
 
@@ -231,7 +240,24 @@ fn test_load_volt() {
             icon_themes: Some(Vec::new()),
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            capabilities: None,
+            languages: Some(vec![VoltLanguage {
+                id: "gleam".to_string(),
+                name: Some("Gleam".to_string()),
+                extensions: vec!["gleam".to_string()],
+                files: Vec::new(),
+                line_comment: Some("//".to_string()),
+                block_comment: None,
+                indent: Some("  ".to_string()),
+                grammar: Some("gleam".to_string()),
+                queries: None,
+                language_server: Some(VoltLanguageServer {
+                    command: "gleam".to_string(),
+                    args: vec!["lsp".to_string()],
+                    initialization_options: None
+                })
+            }])
         }
     );
 }
@@ -0,0 +1,105 @@
+use std::{
+    collections::BTreeSet,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use lapce_rpc::core::CoreRpcHandler;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// Polls the listening TCP ports, reporting the ones opened after the
+/// watching started whenever they change. The ports that were already open,
+/// like ssh's, aren't interesting to forward.
+pub fn watch_listening_ports(core_rpc: CoreRpcHandler) {
+    if WATCHING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    thread::spawn(move || {
+        let Some(initial) = listening_ports() else {
+            return;
+        };
+        let mut reported = BTreeSet::new();
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let Some(ports) = listening_ports() else {
+                return;
+            };
+            let opened: BTreeSet<u16> =
+                ports.difference(&initial).copied().collect();
+            if opened != reported {
+                core_rpc.listening_ports(opened.iter().copied().collect());
+                reported = opened;
+            }
+        }
+    });
+}
+
+/// The TCP ports in the listening state, or `None` if they can't be found
+/// on this platform
+#[cfg(target_os = "linux")]
+fn listening_ports() -> Option<BTreeSet<u16>> {
+    let mut ports = BTreeSet::new();
+    let mut found = false;
+    for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        if let Ok(content) = std::fs::read_to_string(table) {
+            found = true;
+            ports.extend(parse_listening_ports(&content));
+        }
+    }
+    found.then_some(ports)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn listening_ports() -> Option<BTreeSet<u16>> {
+    None
+}
+
+/// The ports of the listening sockets in a table like /proc/net/tcp, whose
+/// lines are `sl local_address rem_address st ...` with the addresses as
+/// `ADDR:PORT` in hex
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_listening_ports(content: &str) -> impl Iterator<Item = u16> + '_ {
+    // The state of a listening socket
+    const TCP_LISTEN: &str = "0A";
+
+    content.lines().skip(1).filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        let local_address = fields.nth(1)?;
+        let state = fields.nth(1)?;
+        if state != TCP_LISTEN {
+            return None;
+        }
+        let (_, port) = local_address.rsplit_once(':')?;
+        u16::from_str_radix(port, 16).ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listening_ports() {
+        let content = "  sl  local_address rem_address   st tx_queue rx_queue
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000
+   1: 0100007F:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000
+   2: 0100007F:0BB8 0100007F:C350 01 00000000:00000000 00:00000000 00000000
+";
+        let ports: Vec<u16> = parse_listening_ports(content).collect();
+        assert_eq!(ports, vec![22, 3000]);
+    }
+
+    #[test]
+    fn test_parse_listening_ports_ipv6() {
+        let content = "  sl  local_address                         rem_address                           st
+   0: 00000000000000000000000001000000:1F90 00000000000000000000000000000000:0000 0A
+";
+        let ports: Vec<u16> = parse_listening_ports(content).collect();
+        assert_eq!(ports, vec![8080]);
+    }
+}
@@ -0,0 +1,48 @@
+use std::process::Command;
+
+use tracing::{error, warn};
+
+/// Uses a login shell to load the correct shell environment for the current user,
+/// so that programs started from Lapce find what they would in a terminal.
+pub fn load_shell_env() {
+    #[cfg(not(windows))]
+    let shell = match std::env::var("SHELL") {
+        Ok(s) => s,
+        Err(error) => {
+            // Shell variable is not set, so we can't determine the correct shell executable.
+            error!("Failed to obtain shell environment: {error}");
+            return;
+        }
+    };
+
+    #[cfg(windows)]
+    let shell = "powershell";
+
+    let mut command = Command::new(shell);
+
+    #[cfg(not(windows))]
+    command.args(["--login", "-c", "printenv"]);
+
+    #[cfg(windows)]
+    command.args(["{ ls env: | foreach { '{0}={1}' -f $_.Name, $_.Value } }"]);
+
+    let env = match command.output() {
+        Ok(output) => String::from_utf8(output.stdout).unwrap_or_default(),
+
+        Err(error) => {
+            error!("Failed to obtain shell environment: {error}");
+            return;
+        }
+    };
+
+    env.split('\n')
+        .filter_map(|line| line.split_once('='))
+        .for_each(|(key, value)| {
+            if let Ok(v) = std::env::var(key) {
+                if v != value {
+                    warn!("Overwriting '{key}', previous value: '{v}', new value '{value}'");
+                }
+            };
+            std::env::set_var(key, value);
+        })
+}
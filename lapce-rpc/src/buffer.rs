@@ -12,6 +12,29 @@ pub fn next() -> Self {
     }
 }
 
+/// The encoding name of a binary file, which is shown as a read only hex dump
+pub const HEX_ENCODING: &str = "Hex";
+
+/// The names of the encodings a file can be reopened or saved with
+pub const FILE_ENCODINGS: &[&str] = &[
+    "UTF-8",
+    "UTF-8 with BOM",
+    "UTF-16LE",
+    "UTF-16BE",
+    "windows-1252",
+    "ISO-8859-2",
+    "ISO-8859-15",
+    "windows-1250",
+    "windows-1251",
+    "KOI8-R",
+    "Shift_JIS",
+    "EUC-JP",
+    "EUC-KR",
+    "GBK",
+    "gb18030",
+    "Big5",
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewBufferResponse {
     pub content: String,
@@ -9,8 +9,9 @@
 
 use crossbeam_channel::{Receiver, Sender};
 use lsp_types::{
-    CompletionResponse, LogMessageParams, ProgressParams, PublishDiagnosticsParams,
-    ShowMessageParams, SignatureHelp,
+    CompletionResponse, LogMessageParams, MessageActionItem, ProgressParams,
+    PublishDiagnosticsParams, ShowMessageParams, ShowMessageRequestParams,
+    SignatureHelp, WorkspaceEdit,
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
@@ -20,7 +21,7 @@
         self, DapId, RunDebugConfig, Scope, StackFrame, Stopped, ThreadId, Variable,
     },
     file::PathObject,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{PluginCommand, PluginId, PluginView, VoltInfo, VoltMetadata},
     proxy::ProxyStatus,
     source_control::DiffInfo,
     terminal::TermId,
@@ -44,6 +45,15 @@ pub enum CoreNotification {
         path: PathBuf,
         content: String,
     },
+    OpenFileDeleted {
+        path: PathBuf,
+    },
+    /// The first lines of a large file being opened, to show while the rest of it
+    /// is read
+    BufferPreview {
+        path: PathBuf,
+        content: String,
+    },
     CompletionResponse {
         request_id: usize,
         input: String,
@@ -57,8 +67,18 @@ pub enum CoreNotification {
     },
     OpenPaths {
         paths: Vec<PathObject>,
+        /// Open them in a new window rather than the active one
+        #[serde(default)]
+        new_window: bool,
+        /// The command line that asked to open them waits for the files to be
+        /// closed
+        #[serde(default)]
+        wait: bool,
+    },
+    /// Paths of the workspace were created, removed or renamed
+    WorkspaceFileChange {
+        paths: Vec<PathBuf>,
     },
-    WorkspaceFileChange,
     PublishDiagnostics {
         diagnostics: PublishDiagnosticsParams,
     },
@@ -69,6 +89,25 @@ pub enum CoreNotification {
         title: String,
         message: ShowMessageParams,
     },
+    /// A language server or plugin asked for the workspace edit to be applied
+    ApplyWorkspaceEdit {
+        edit: WorkspaceEdit,
+    },
+    /// The commands the workspace's volts registered changed
+    PluginCommands {
+        commands: Vec<PluginCommand>,
+    },
+    /// The views the workspace's volts show changed
+    PluginViews {
+        views: Vec<PluginView>,
+    },
+    /// No language server was found for an opened document, but Lapce can
+    /// install one
+    LanguageServerMissing {
+        language_id: String,
+        name: String,
+        version: String,
+    },
     LogMessage {
         message: LogMessageParams,
         target: String,
@@ -76,6 +115,11 @@ pub enum CoreNotification {
     HomeDir {
         path: PathBuf,
     },
+    /// The TCP ports processes started listening on since the proxy began
+    /// watching them
+    ListeningPorts {
+        ports: Vec<u16>,
+    },
     VoltInstalled {
         volt: VoltMetadata,
         icon: Option<Vec<u8>>,
@@ -127,20 +171,60 @@ pub enum CoreNotification {
     DapContinued {
         dap_id: DapId,
     },
+    /// Output of a debug session, from the adapter or the program it runs
+    DapOutput {
+        dap_id: DapId,
+        output: dap_types::Output,
+    },
     DapBreakpointsResp {
         dap_id: DapId,
         path: PathBuf,
         breakpoints: Vec<dap_types::Breakpoint>,
     },
+    /// A [`CoreRequest::SshPrompt`] for the UI to show, which it answers with
+    /// the request's id
+    SshPrompt {
+        request_id: RequestId,
+        host: String,
+        prompt: String,
+        secret: bool,
+    },
+    /// A [`CoreRequest::ShowMessageRequest`] for the UI to show, which it
+    /// answers with the request's id
+    ShowMessageRequest {
+        request_id: RequestId,
+        title: String,
+        params: ShowMessageRequestParams,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum CoreRequest {}
+pub enum CoreRequest {
+    /// Ask the user for what ssh prompts for while connecting to the host,
+    /// like a password, a key's passphrase or whether to trust the host
+    SshPrompt {
+        host: String,
+        prompt: String,
+        /// Whether the answer is hidden while it's typed
+        secret: bool,
+    },
+    /// Ask the user to pick one of the actions of a plugin's message
+    ShowMessageRequest {
+        /// The name of the plugin asking
+        title: String,
+        params: ShowMessageRequestParams,
+    },
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
-pub enum CoreResponse {}
+pub enum CoreResponse {
+    /// The answer to the prompt, none when the user cancelled it
+    SshPrompt { answer: Option<String> },
+    /// The action the user picked, none when they dismissed the message
+    ShowMessageRequest { action: Option<MessageActionItem> },
+}
 
 pub type CoreMessage = RpcMessage<CoreRequest, CoreNotification, CoreResponse>;
 
@@ -227,8 +311,8 @@ pub fn notification(&self, notification: CoreNotification) {
         let _ = self.tx.send(CoreRpc::Notification(Box::new(notification)));
     }
 
-    pub fn workspace_file_change(&self) {
-        self.notification(CoreNotification::WorkspaceFileChange);
+    pub fn workspace_file_change(&self, paths: Vec<PathBuf>) {
+        self.notification(CoreNotification::WorkspaceFileChange { paths });
     }
 
     pub fn diff_info(&self, diff: DiffInfo) {
@@ -239,6 +323,10 @@ pub fn open_file_changed(&self, path: PathBuf, content: String) {
         self.notification(CoreNotification::OpenFileChanged { path, content });
     }
 
+    pub fn open_file_deleted(&self, path: PathBuf) {
+        self.notification(CoreNotification::OpenFileDeleted { path });
+    }
+
     pub fn completion_response(
         &self,
         request_id: usize,
@@ -310,6 +398,31 @@ pub fn show_message(&self, title: String, message: ShowMessageParams) {
         self.notification(CoreNotification::ShowMessage { title, message });
     }
 
+    pub fn plugin_commands(&self, commands: Vec<PluginCommand>) {
+        self.notification(CoreNotification::PluginCommands { commands });
+    }
+
+    pub fn plugin_views(&self, views: Vec<PluginView>) {
+        self.notification(CoreNotification::PluginViews { views });
+    }
+
+    pub fn apply_workspace_edit(&self, edit: WorkspaceEdit) {
+        self.notification(CoreNotification::ApplyWorkspaceEdit { edit });
+    }
+
+    pub fn language_server_missing(
+        &self,
+        language_id: String,
+        name: String,
+        version: String,
+    ) {
+        self.notification(CoreNotification::LanguageServerMissing {
+            language_id,
+            name,
+            version,
+        });
+    }
+
     pub fn log_message(&self, message: LogMessageParams, target: String) {
         self.notification(CoreNotification::LogMessage { message, target });
     }
@@ -352,6 +465,10 @@ pub fn dap_continued(&self, dap_id: DapId) {
         self.notification(CoreNotification::DapContinued { dap_id });
     }
 
+    pub fn dap_output(&self, dap_id: DapId, output: dap_types::Output) {
+        self.notification(CoreNotification::DapOutput { dap_id, output });
+    }
+
     pub fn dap_breakpoints_resp(
         &self,
         dap_id: DapId,
@@ -368,6 +485,10 @@ pub fn dap_breakpoints_resp(
     pub fn home_dir(&self, path: PathBuf) {
         self.notification(CoreNotification::HomeDir { path });
     }
+
+    pub fn listening_ports(&self, ports: Vec<u16>) {
+        self.notification(CoreNotification::ListeningPorts { ports });
+    }
 }
 
 impl Default for CoreRpcHandler {
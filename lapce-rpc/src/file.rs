@@ -366,6 +366,31 @@ pub fn get_file_node_mut(&mut self, path: &Path) -> Option<&mut FileNodeItem> {
             .try_fold(self, |node, path| node.children.get_mut(path))
     }
 
+    /// The row of the node at `path` in the list of visible descendants of this node, as laid
+    /// out by [`FileNodeItem::append_children_view_slice`].  
+    /// Returns `None` if the node doesn't exist or one of its ancestors isn't open.
+    pub fn view_index(&self, path: &Path) -> Option<usize> {
+        let mut node = self;
+        let mut row = None;
+        let mut next_row = 0;
+        for ancestor in self.ancestors_rev(path)? {
+            if !node.open {
+                return None;
+            }
+            node = node.sorted_children().into_iter().find(|child| {
+                if child.path == ancestor {
+                    true
+                } else {
+                    next_row += child.children_open_count + 1;
+                    false
+                }
+            })?;
+            row = Some(next_row);
+            next_row += 1;
+        }
+        row
+    }
+
     /// Remove a specific child from the node.  
     /// The path is recursive and will remove the child from parent indicated by the path.
     pub fn remove_child(&mut self, path: &Path) -> Option<FileNodeItem> {
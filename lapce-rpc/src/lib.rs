@@ -17,6 +17,10 @@
 use serde::{Deserialize, Serialize};
 pub use stdio::stdio_transport;
 
+/// The version of the protocol spoken between Lapce and its proxy, which has
+/// to be bumped whenever the messages change in an incompatible way
+pub const PROTOCOL_VERSION: u64 = 19;
+
 #[derive(Debug)]
 pub enum RpcMessage<Req, Notif, Resp> {
     Request(RequestId, Req),
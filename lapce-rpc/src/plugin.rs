@@ -49,10 +49,54 @@ pub struct VoltActivation {
     pub workspace_contains: Option<Vec<String>>,
 }
 
+/// A setting of a volt, shown in the settings editor and given to the volt at
+/// initialization and with `workspace/didChangeConfiguration` when it changes
 #[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
 pub struct VoltConfig {
+    /// Guessed from the default when not given
+    #[serde(rename = "type")]
+    pub kind: Option<VoltConfigType>,
     pub default: Value,
     pub description: String,
+    /// The values an `enum` setting can take
+    #[serde(rename = "enum")]
+    pub values: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VoltConfigType {
+    String,
+    Bool,
+    Enum,
+    Number,
+}
+
+impl VoltConfig {
+    pub fn kind(&self) -> VoltConfigType {
+        if let Some(kind) = self.kind {
+            return kind;
+        }
+        match &self.default {
+            Value::Bool(_) => VoltConfigType::Bool,
+            Value::Number(_) => VoltConfigType::Number,
+            _ if self.values.is_some() => VoltConfigType::Enum,
+            _ => VoltConfigType::String,
+        }
+    }
+
+    /// The value if it is of the setting's type, the default otherwise
+    pub fn checked_value(&self, value: Option<&Value>) -> Value {
+        let valid = value.filter(|value| match self.kind() {
+            VoltConfigType::String => value.is_string(),
+            VoltConfigType::Bool => value.is_boolean(),
+            VoltConfigType::Number => value.is_number(),
+            VoltConfigType::Enum => value.as_str().is_some_and(|value| {
+                self.values.iter().flatten().any(|v| v == value)
+            }),
+        });
+        valid.unwrap_or(&self.default).clone()
+    }
 }
 
 #[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
@@ -71,6 +115,125 @@ pub struct VoltMetadata {
     pub dir: Option<PathBuf>,
     pub activation: Option<VoltActivation>,
     pub config: Option<HashMap<String, VoltConfig>>,
+    /// What the volt may do through the host API, see [`VoltCapability`]
+    pub capabilities: Option<Vec<VoltCapability>>,
+    /// The languages the volt adds support for
+    pub languages: Option<Vec<VoltLanguage>>,
+}
+
+/// A language from the `[[languages]]` sections of a `volt.toml`, which needs
+/// no wasm to be supported
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct VoltLanguage {
+    /// The language id given to language servers, like `gleam`
+    pub id: String,
+    /// What the user is shown, the id when not given
+    pub name: Option<String>,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Names of the files of the language, like `Justfile`
+    #[serde(default)]
+    pub files: Vec<String>,
+    pub line_comment: Option<String>,
+    /// The start and end tokens of block comments
+    pub block_comment: Option<(String, String)>,
+    /// The indent unit, four spaces when not given
+    pub indent: Option<String>,
+    /// The name of the tree-sitter grammar, loaded from
+    /// `grammars/tree-sitter-<grammar>.<so|dylib|dll>` in the volt
+    pub grammar: Option<String>,
+    /// The directory in the volt with the `highlights.scm` and `injections.scm`
+    /// queries, `queries/<id>` when not given
+    pub queries: Option<String>,
+    pub language_server: Option<VoltLanguageServer>,
+}
+
+/// The language server a volt runs for one of its languages, as soon as a
+/// document of the language is opened
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct VoltLanguageServer {
+    /// The server binary, relative to the volt or looked up in `PATH`
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub initialization_options: Option<Value>,
+}
+
+/// A part of the host API a volt has to declare in its `volt.toml` to use it
+#[derive(Deserialize, Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum VoltCapability {
+    /// Register commands the user can run
+    Commands,
+    /// Read the content of documents
+    ReadBuffers,
+    /// Edit documents and files with workspace edits
+    EditBuffers,
+    /// Ask the user to pick between choices
+    Prompts,
+    /// Start language servers
+    LanguageServers,
+    /// Run programs, including debug adapters
+    Processes,
+    /// Show tree views in the plugin views panel
+    Views,
+    /// Be told about documents being opened, saved and closed, selection
+    /// changes and file changes in the workspace
+    Events,
+}
+
+/// A command a volt registered for the user to run
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct PluginCommand {
+    pub volt_id: VoltID,
+    pub volt_display_name: String,
+    /// The name of the command, unique within the volt
+    pub command: String,
+    pub title: String,
+}
+
+/// A tree of items a volt shows in the plugin views panel, like tests or todos
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq, Hash)]
+pub struct PluginView {
+    pub volt_id: VoltID,
+    pub volt_display_name: String,
+    /// The name of the view, unique within the volt
+    pub id: String,
+    pub title: String,
+    pub items: Vec<PluginViewItem>,
+}
+
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginViewItem {
+    /// What identifies the item to the volt when it is clicked
+    pub id: String,
+    pub label: String,
+    /// Dimmed text shown after the label
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub children: Vec<PluginViewItem>,
+}
+
+impl VoltCapability {
+    /// The capabilities of a volt which doesn't declare any, which are those
+    /// the host API had before they had to be declared
+    pub fn legacy() -> Vec<VoltCapability> {
+        vec![
+            VoltCapability::EditBuffers,
+            VoltCapability::LanguageServers,
+            VoltCapability::Processes,
+        ]
+    }
+
+    /// The capabilities of a language server, which only needs those of the
+    /// requests in the language server protocol
+    pub fn language_server() -> Vec<VoltCapability> {
+        vec![VoltCapability::EditBuffers]
+    }
 }
 
 impl VoltMetadata {
@@ -78,6 +241,22 @@ pub fn id(&self) -> VoltID {
         VoltID::from(self)
     }
 
+    /// Whether opening a document of the language starts the volt, because it
+    /// asks to be or because it adds the language
+    pub fn activated_by_language(&self, language_id: &str) -> bool {
+        let activation = self
+            .activation
+            .as_ref()
+            .and_then(|a| a.language.as_ref())
+            .is_some_and(|l| l.iter().any(|l| l == language_id));
+        activation
+            || self
+                .languages
+                .iter()
+                .flatten()
+                .any(|language| language.id == language_id)
+    }
+
     pub fn info(&self) -> VoltInfo {
         VoltInfo {
             name: self.name.clone(),
@@ -140,9 +319,91 @@ fn from(volt: &VoltInfo) -> Self {
     }
 }
 
+/// The user's overrides for the language server of a language, from the
+/// `[lsp.<language id>]` sections of the settings
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LanguageServerConfig {
+    /// The server binary to run instead of the one the plugin starts
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    /// Names of the files marking the root directory of a project, like
+    /// `Cargo.toml`. The workspace is the root when none is found.
+    #[serde(default)]
+    pub root_patterns: Vec<String>,
+    /// Replaces the `initializationOptions` the plugin gives the server
+    pub initialization_options: Option<Value>,
+    /// Sent with `workspace/didChangeConfiguration`, and the answer to
+    /// `workspace/configuration` requests
+    pub settings: Option<Value>,
+    /// Names of the plugins whose servers format documents of the language,
+    /// the first that can is used
+    #[serde(default)]
+    pub formatting_priority: Vec<String>,
+    /// How long in milliseconds to wait after an edit for more edits, before
+    /// sending the server the changes together, with 0 sending each straight away
+    pub change_debounce: Option<u64>,
+}
+
+/// A running language server, as reported to the status view
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LanguageServerInfo {
+    pub plugin_id: PluginId,
+    pub volt_id: VoltID,
+    pub name: String,
+    /// The language ids from the server's document selector
+    pub languages: Vec<String>,
+    pub pid: Option<u32>,
+    /// Seconds since the unix epoch at which the server was initialized
+    pub started_at: u64,
+    /// Short names of the features the server advertised as supported
+    pub capabilities: Vec<String>,
+}
+
+impl LanguageServerInfo {
+    /// Whether the server handles documents of `language_id`, servers that
+    /// only match by path pattern are assumed to handle every language
+    pub fn handles_language(&self, language_id: &str) -> bool {
+        self.languages.is_empty()
+            || self
+                .languages
+                .iter()
+                .any(|language| language == language_id)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{VoltID, VoltInfo, VoltMetadata};
+    use serde_json::json;
+
+    use super::{
+        LanguageServerInfo, PluginId, VoltConfig, VoltConfigType, VoltID, VoltInfo,
+        VoltMetadata,
+    };
+
+    #[test]
+    fn test_volt_config_checked_value() {
+        let config: VoltConfig = serde_json::from_value(json!({
+            "type": "enum",
+            "default": "fast",
+            "description": "How to run",
+            "enum": ["fast", "slow"],
+        }))
+        .unwrap();
+        assert_eq!(config.kind(), VoltConfigType::Enum);
+        assert_eq!(config.checked_value(Some(&json!("slow"))), json!("slow"));
+        assert_eq!(config.checked_value(Some(&json!("other"))), json!("fast"));
+        assert_eq!(config.checked_value(None), json!("fast"));
+
+        let config: VoltConfig = serde_json::from_value(json!({
+            "default": 4,
+            "description": "How many",
+        }))
+        .unwrap();
+        assert_eq!(config.kind(), VoltConfigType::Number);
+        assert_eq!(config.checked_value(Some(&json!(8))), json!(8));
+        assert_eq!(config.checked_value(Some(&json!(true))), json!(4));
+    }
 
     #[test]
     fn test_volt_metadata_id() {
@@ -160,6 +421,8 @@ fn test_volt_metadata_id() {
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
+            capabilities: None,
+            languages: None,
         };
         let volt_id = VoltID {
             author: "Author".to_string(),
@@ -198,6 +461,8 @@ fn test_volt_metadata_info() {
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
+            capabilities: None,
+            languages: None,
         };
         let volt_info = VoltInfo {
             name: "plugin".to_string(),
@@ -234,4 +499,25 @@ fn test_volt_info_id() {
         assert_eq!(<&VoltInfo as Into<VoltID>>::into(&volt_info), volt_id);
         assert_eq!(<VoltInfo as Into<VoltID>>::into(volt_info), volt_id);
     }
+
+    #[test]
+    fn test_language_server_handles_language() {
+        let mut server = LanguageServerInfo {
+            plugin_id: PluginId(0),
+            volt_id: VoltID {
+                author: "Author".to_string(),
+                name: "plugin".to_string(),
+            },
+            name: "Plugin".to_string(),
+            languages: vec!["rust".to_string()],
+            pid: None,
+            started_at: 0,
+            capabilities: Vec::new(),
+        };
+        assert!(server.handles_language("rust"));
+        assert!(!server.handles_language("python"));
+
+        server.languages.clear();
+        assert!(server.handles_language("python"));
+    }
 }
@@ -1,21 +1,24 @@
 use std::{
     collections::HashMap,
+    ops::Range,
     path::PathBuf,
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc,
     },
+    time::SystemTime,
 };
 
 use crossbeam_channel::{Receiver, Sender};
 use indexmap::IndexMap;
 use lapce_xi_rope::RopeDelta;
 use lsp_types::{
-    request::GotoTypeDefinitionResponse, CodeAction, CodeActionResponse,
-    CompletionItem, Diagnostic, DocumentSymbolResponse, GotoDefinitionResponse,
-    Hover, InlayHint, InlineCompletionResponse, InlineCompletionTriggerKind,
-    Location, Position, PrepareRenameResponse, SelectionRange, SymbolInformation,
-    TextDocumentItem, TextEdit, WorkspaceEdit,
+    request::GotoTypeDefinitionResponse, CallHierarchyItem, CodeAction,
+    CodeActionOrCommand, CompletionItem, Diagnostic, DocumentHighlight,
+    DocumentLink, DocumentSymbolResponse, GotoDefinitionResponse, Hover, InlayHint,
+    InlineCompletionResponse, InlineCompletionTriggerKind, Location, Position,
+    PrepareRenameResponse, Range, SelectionRange, SymbolInformation,
+    TextDocumentItem, TextEdit, TypeHierarchyItem, WorkspaceEdit,
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
@@ -25,8 +28,10 @@
     buffer::BufferId,
     dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
     file::{FileNodeItem, PathObject},
-    plugin::{PluginId, VoltInfo, VoltMetadata},
-    source_control::FileDiff,
+    plugin::{
+        LanguageServerConfig, LanguageServerInfo, PluginId, VoltInfo, VoltMetadata,
+    },
+    source_control::{BlameHunk, CommitInfo, FileDiff, GitSyncKind},
     style::SemanticStyles,
     terminal::{TermId, TerminalProfile},
     RequestId, RpcError, RpcMessage,
@@ -44,6 +49,22 @@ pub enum ProxyStatus {
     Connecting,
     Connected,
     Disconnected,
+    /// The connection to a remote proxy was lost and is being re-established
+    Reconnecting,
+}
+
+/// Whether a call hierarchy lists the callers or the callees of an item
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CallHierarchyDirection {
+    Incoming,
+    Outgoing,
+}
+
+/// Whether a type hierarchy lists the supertypes or the subtypes of an item
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TypeHierarchyDirection {
+    Supertypes,
+    Subtypes,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +79,10 @@ pub struct SearchMatch {
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
 pub enum ProxyRequest {
+    /// Sent first to a remote proxy to check it speaks the same protocol
+    Handshake {
+        protocol_version: u64,
+    },
     NewBuffer {
         buffer_id: BufferId,
         path: PathBuf,
@@ -65,6 +90,10 @@ pub enum ProxyRequest {
     BufferHead {
         path: PathBuf,
     },
+    /// The content of `path` on disk, responded to with a `BufferHeadResponse`
+    BufferSaved {
+        path: PathBuf,
+    },
     GlobalSearch {
         pattern: String,
         case_sensitive: bool,
@@ -95,10 +124,56 @@ pub enum ProxyRequest {
     GitGetRemoteFileUrl {
         file: PathBuf,
     },
+    GitBlame {
+        path: PathBuf,
+    },
+    /// The latest commits reachable from `HEAD`, only those that changed `path` if
+    /// it's given
+    GitLog {
+        path: Option<PathBuf>,
+        limit: usize,
+    },
+    /// The files changed by a commit compared to its first parent
+    GitCommitFiles {
+        id: String,
+    },
+    /// The content of a file at a revision, responded to with a `BufferHeadResponse`
+    GitFileAtRevision {
+        path: PathBuf,
+        revision: String,
+    },
+    /// Fetch from, pull from or push to the upstream remote of `HEAD`, authenticating
+    /// with `token` if the ssh-agent and credential helpers don't suffice
+    GitSync {
+        kind: GitSyncKind,
+        token: Option<String>,
+    },
     GetReferences {
         path: PathBuf,
         position: Position,
     },
+    GetDocumentHighlight {
+        path: PathBuf,
+        position: Position,
+    },
+    PrepareCallHierarchy {
+        path: PathBuf,
+        position: Position,
+    },
+    CallHierarchyCalls {
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        direction: CallHierarchyDirection,
+    },
+    PrepareTypeHierarchy {
+        path: PathBuf,
+        position: Position,
+    },
+    TypeHierarchyRelated {
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        direction: TypeHierarchyDirection,
+    },
     GetDefinition {
         request_id: usize,
         path: PathBuf,
@@ -112,6 +187,9 @@ pub enum ProxyRequest {
     GetInlayHints {
         path: PathBuf,
     },
+    GetDocumentLinks {
+        path: PathBuf,
+    },
     GetInlineCompletions {
         path: PathBuf,
         position: Position,
@@ -144,12 +222,33 @@ pub enum ProxyRequest {
     GetDocumentFormatting {
         path: PathBuf,
     },
+    GetRangeFormatting {
+        path: PathBuf,
+        range: lsp_types::Range,
+    },
+    GetOnTypeFormatting {
+        path: PathBuf,
+        position: Position,
+        ch: String,
+    },
+    /// Notify the servers that the document is about to be saved, and get
+    /// the edits they want made before it is
+    WillSaveDocument {
+        path: PathBuf,
+    },
     GetOpenFilesContent {},
     GetFiles {
         path: String,
     },
     ReadDir {
         path: PathBuf,
+        /// The modification time of the listing the caller has, which isn't
+        /// sent again if the directory still has it
+        mtime: Option<SystemTime>,
+    },
+    /// Read the bytes of a file that isn't opened as a buffer, like an image
+    ReadFile {
+        path: PathBuf,
     },
     Save {
         rev: u64,
@@ -157,6 +256,18 @@ pub enum ProxyRequest {
         /// Whether to create the parent directories if they do not exist.
         create_parents: bool,
     },
+    /// Save the buffer in `encoding`, which it's then read and written with
+    SaveWithEncoding {
+        rev: u64,
+        path: PathBuf,
+        encoding: String,
+    },
+    /// Read the buffer's file again in `encoding`, which it's then written with,
+    /// responded to with a `NewBufferResponse`
+    ReloadWithEncoding {
+        path: PathBuf,
+        encoding: String,
+    },
     SaveBufferAs {
         buffer_id: BufferId,
         path: PathBuf,
@@ -193,6 +304,24 @@ pub enum ProxyRequest {
         dap_id: DapId,
         frame_id: usize,
     },
+    DapEvaluate {
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: String,
+    },
+    DapCompletions {
+        dap_id: DapId,
+        text: String,
+        column: usize,
+        frame_id: Option<usize>,
+    },
+    LanguageServers {
+        path: Option<PathBuf>,
+    },
+    LanguageServerLog {
+        path: Option<PathBuf>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -205,12 +334,40 @@ pub enum ProxyNotification {
         /// Paths to extra plugins that should be loaded
         extra_plugin_paths: Vec<PathBuf>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+        /// The language server configs by language id
+        language_servers: HashMap<String, LanguageServerConfig>,
+        /// The environment variables set for language servers and terminals
+        env: HashMap<String, String>,
         window_id: usize,
         tab_id: usize,
     },
     OpenFileChanged {
         path: PathBuf,
     },
+    /// Check whether any open file changed on disk without the watcher noticing,
+    /// when the window is focused again
+    CheckOpenFiles {},
+    /// The content of a `NewBuffer` request's file, read off the proxy's main
+    /// thread, to answer the request with
+    BufferLoaded {
+        request_id: RequestId,
+        buffer_id: BufferId,
+        path: PathBuf,
+        content: String,
+        read_only: bool,
+        encoding: String,
+        /// Whether the file starts with a byte order mark, which the encoding's
+        /// name doesn't tell for UTF-16
+        bom: bool,
+    },
+    /// A `Save` or `SaveBufferAs` request's content was written, or failed to be
+    /// written with `error`, on the proxy's save thread
+    BufferWritten {
+        request_id: RequestId,
+        path: PathBuf,
+        save_as: bool,
+        error: Option<String>,
+    },
     OpenPaths {
         paths: Vec<PathObject>,
     },
@@ -231,9 +388,61 @@ pub enum ProxyNotification {
         delta: RopeDelta,
         rev: u64,
     },
+    /// Opens a buffer again with the content, revision and encoding the editor
+    /// has for it, after the proxy was restarted by a reconnection
+    ReopenBuffer {
+        buffer_id: BufferId,
+        path: PathBuf,
+        content: String,
+        rev: u64,
+        encoding: String,
+    },
+    /// The editor's buffer of `path` moved on to `rev` without its content
+    /// changing, as its undo history was restored
+    SetBufferRev {
+        path: PathBuf,
+        rev: u64,
+    },
     UpdatePluginConfigs {
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
     },
+    UpdateLanguageServerConfigs {
+        configs: HashMap<String, LanguageServerConfig>,
+    },
+    /// Replace the environment variables set for the language servers and
+    /// terminals started afterwards
+    UpdateEnv {
+        env: HashMap<String, String>,
+    },
+    /// Download and start the server Lapce knows for the language
+    InstallLanguageServer {
+        language_id: String,
+    },
+    /// Run a command a volt registered
+    RunPluginCommand {
+        volt_id: VoltID,
+        command: String,
+    },
+    /// An item of a volt's view was clicked
+    PluginViewItemClicked {
+        volt_id: VoltID,
+        view: String,
+        item: String,
+    },
+    /// No editor shows the file anymore
+    BufferClosed {
+        path: PathBuf,
+    },
+    /// An editor shows the file again after it was closed with `BufferClosed`,
+    /// with the buffer the proxy kept for it
+    BufferOpened {
+        path: PathBuf,
+    },
+    /// The selections of the active editor, once its cursor stopped moving
+    SelectionChanged {
+        path: PathBuf,
+        selections: Vec<Range>,
+    },
     NewTerminal {
         term_id: TermId,
         profile: TerminalProfile,
@@ -256,15 +465,51 @@ pub enum ProxyNotification {
     GitCommit {
         message: String,
         diffs: Vec<FileDiff>,
+        /// Replace the `HEAD` commit instead of adding a new one
+        #[serde(default)]
+        amend: bool,
+    },
+    GitStage {
+        paths: Vec<PathBuf>,
+    },
+    GitUnstage {
+        paths: Vec<PathBuf>,
+    },
+    /// Stage a single change of `path`, which replaces the `lines` of its HEAD version
+    /// with `content`
+    GitStageHunk {
+        path: PathBuf,
+        lines: Range<usize>,
+        content: String,
     },
     GitCheckout {
         reference: String,
     },
+    GitCreateBranch {
+        name: String,
+    },
+    /// Stash the changes in the working tree and the index, untracked files included.
+    /// Git's default message is used when `message` is empty.
+    GitStash {
+        message: String,
+    },
+    GitStashApply {
+        index: usize,
+    },
+    GitStashPop {
+        index: usize,
+    },
+    GitStashDrop {
+        index: usize,
+    },
     GitDiscardFilesChanges {
         files: Vec<PathBuf>,
     },
     GitDiscardWorkspaceChanges {},
     GitInit {},
+    /// Report the ports processes start listening on, so that a remote
+    /// workspace can forward them
+    WatchListeningPorts {},
     TerminalWrite {
         term_id: TermId,
         content: String,
@@ -327,12 +572,27 @@ pub enum ProxyNotification {
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
 pub enum ProxyResponse {
+    HandshakeResponse {
+        protocol_version: u64,
+    },
     GitGetRemoteFileUrl {
         file_url: String,
     },
+    GitBlameResponse {
+        hunks: Vec<BlameHunk>,
+    },
+    GitLogResponse {
+        commits: Vec<CommitInfo>,
+    },
+    GitCommitFilesResponse {
+        diffs: Vec<FileDiff>,
+    },
+    GitSyncResponse {},
     NewBufferResponse {
         content: String,
         read_only: bool,
+        /// The name of the encoding the file was read with
+        encoding: String,
     },
     BufferHeadResponse {
         version: String,
@@ -340,6 +600,13 @@ pub enum ProxyResponse {
     },
     ReadDirResponse {
         items: Vec<FileNodeItem>,
+        /// The modification time of the directory
+        mtime: Option<SystemTime>,
+    },
+    /// The directory still has the modification time of the caller's listing
+    ReadDirUnchangedResponse {},
+    ReadFileResponse {
+        content: Vec<u8>,
     },
     CompletionResolveResponse {
         item: Box<CompletionItem>,
@@ -362,9 +629,28 @@ pub enum ProxyResponse {
     GetReferencesResponse {
         references: Vec<Location>,
     },
-    GetCodeActionsResponse {
+    GetDocumentHighlightResponse {
+        highlights: Vec<DocumentHighlight>,
+    },
+    PrepareCallHierarchyResponse {
+        plugin_id: PluginId,
+        items: Vec<CallHierarchyItem>,
+    },
+    CallHierarchyCallsResponse {
+        /// The callers or callees, with the ranges of the calls, which are
+        /// in the caller
+        calls: Vec<(CallHierarchyItem, Vec<lsp_types::Range>)>,
+    },
+    PrepareTypeHierarchyResponse {
         plugin_id: PluginId,
-        resp: CodeActionResponse,
+        items: Vec<TypeHierarchyItem>,
+    },
+    TypeHierarchyRelatedResponse {
+        items: Vec<TypeHierarchyItem>,
+    },
+    GetCodeActionsResponse {
+        /// The actions of every server, with the plugin that resolves them
+        actions: Vec<(PluginId, CodeActionOrCommand)>,
     },
     GetFilesResponse {
         items: Vec<PathBuf>,
@@ -372,6 +658,9 @@ pub enum ProxyResponse {
     GetDocumentFormatting {
         edits: Vec<TextEdit>,
     },
+    WillSaveDocumentResponse {
+        edits: Vec<TextEdit>,
+    },
     GetDocumentSymbols {
         resp: DocumentSymbolResponse,
     },
@@ -384,6 +673,9 @@ pub enum ProxyResponse {
     GetInlayHints {
         hints: Vec<InlayHint>,
     },
+    GetDocumentLinksResponse {
+        links: Vec<DocumentLink>,
+    },
     GetInlineCompletions {
         completions: InlineCompletionResponse,
     },
@@ -408,6 +700,19 @@ pub enum ProxyResponse {
     DapGetScopesResponse {
         scopes: Vec<(dap_types::Scope, Vec<dap_types::Variable>)>,
     },
+    DapEvaluateResponse {
+        result: dap_types::EvaluateResponse,
+    },
+    DapCompletionsResponse {
+        items: Vec<dap_types::CompletionItem>,
+    },
+    LanguageServersResponse {
+        servers: Vec<LanguageServerInfo>,
+    },
+    LanguageServerLogResponse {
+        path: PathBuf,
+        lines: usize,
+    },
     CreatePathResponse {
         path: PathBuf,
     },
@@ -536,14 +841,63 @@ pub fn git_init(&self) {
         self.notification(ProxyNotification::GitInit {});
     }
 
-    pub fn git_commit(&self, message: String, diffs: Vec<FileDiff>) {
-        self.notification(ProxyNotification::GitCommit { message, diffs });
+    pub fn watch_listening_ports(&self) {
+        self.notification(ProxyNotification::WatchListeningPorts {});
+    }
+
+    pub fn git_commit(&self, message: String, diffs: Vec<FileDiff>, amend: bool) {
+        self.notification(ProxyNotification::GitCommit {
+            message,
+            diffs,
+            amend,
+        });
+    }
+
+    pub fn git_stage(&self, paths: Vec<PathBuf>) {
+        self.notification(ProxyNotification::GitStage { paths });
+    }
+
+    pub fn git_unstage(&self, paths: Vec<PathBuf>) {
+        self.notification(ProxyNotification::GitUnstage { paths });
+    }
+
+    pub fn git_stage_hunk(
+        &self,
+        path: PathBuf,
+        lines: Range<usize>,
+        content: String,
+    ) {
+        self.notification(ProxyNotification::GitStageHunk {
+            path,
+            lines,
+            content,
+        });
     }
 
     pub fn git_checkout(&self, reference: String) {
         self.notification(ProxyNotification::GitCheckout { reference });
     }
 
+    pub fn git_create_branch(&self, name: String) {
+        self.notification(ProxyNotification::GitCreateBranch { name });
+    }
+
+    pub fn git_stash(&self, message: String) {
+        self.notification(ProxyNotification::GitStash { message });
+    }
+
+    pub fn git_stash_apply(&self, index: usize) {
+        self.notification(ProxyNotification::GitStashApply { index });
+    }
+
+    pub fn git_stash_pop(&self, index: usize) {
+        self.notification(ProxyNotification::GitStashPop { index });
+    }
+
+    pub fn git_stash_drop(&self, index: usize) {
+        self.notification(ProxyNotification::GitStashDrop { index });
+    }
+
     pub fn install_volt(&self, volt: VoltInfo) {
         self.notification(ProxyNotification::InstallVolt { volt });
     }
@@ -569,12 +923,15 @@ pub fn shutdown(&self) {
         let _ = self.tx.send(ProxyRpc::Shutdown);
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         &self,
         workspace: Option<PathBuf>,
         disabled_volts: Vec<VoltID>,
         extra_plugin_paths: Vec<PathBuf>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+        language_servers: HashMap<String, LanguageServerConfig>,
+        env: HashMap<String, String>,
         window_id: usize,
         tab_id: usize,
     ) {
@@ -583,6 +940,8 @@ pub fn initialize(
             disabled_volts,
             extra_plugin_paths,
             plugin_configurations,
+            language_servers,
+            env,
             window_id,
             tab_id,
         });
@@ -649,6 +1008,10 @@ pub fn get_buffer_head(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::BufferHead { path }, f);
     }
 
+    pub fn get_buffer_saved(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::BufferSaved { path }, f);
+    }
+
     pub fn create_file(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::CreateFile { path }, f);
     }
@@ -750,6 +1113,32 @@ pub fn save(
         );
     }
 
+    pub fn save_with_encoding(
+        &self,
+        rev: u64,
+        path: PathBuf,
+        encoding: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::SaveWithEncoding {
+                rev,
+                path,
+                encoding,
+            },
+            f,
+        );
+    }
+
+    pub fn reload_with_encoding(
+        &self,
+        path: PathBuf,
+        encoding: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ReloadWithEncoding { path, encoding }, f);
+    }
+
     pub fn get_files(&self, f: impl ProxyCallback + 'static) {
         self.request_async(
             ProxyRequest::GetFiles {
@@ -763,8 +1152,17 @@ pub fn get_open_files_content(&self) -> Result<ProxyResponse, RpcError> {
         self.request(ProxyRequest::GetOpenFilesContent {})
     }
 
-    pub fn read_dir(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
-        self.request_async(ProxyRequest::ReadDir { path }, f);
+    pub fn read_dir(
+        &self,
+        path: PathBuf,
+        mtime: Option<SystemTime>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ReadDir { path, mtime }, f);
+    }
+
+    pub fn read_file(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::ReadFile { path }, f);
     }
 
     pub fn completion_resolve(
@@ -857,6 +1255,67 @@ pub fn get_references(
         self.request_async(ProxyRequest::GetReferences { path, position }, f);
     }
 
+    pub fn get_document_highlight(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetDocumentHighlight { path, position }, f);
+    }
+
+    pub fn prepare_call_hierarchy(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::PrepareCallHierarchy { path, position }, f);
+    }
+
+    pub fn call_hierarchy_calls(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        direction: CallHierarchyDirection,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CallHierarchyCalls {
+                plugin_id,
+                item,
+                direction,
+            },
+            f,
+        );
+    }
+
+    pub fn prepare_type_hierarchy(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::PrepareTypeHierarchy { path, position }, f);
+    }
+
+    pub fn type_hierarchy_related(
+        &self,
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        direction: TypeHierarchyDirection,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::TypeHierarchyRelated {
+                plugin_id,
+                item,
+                direction,
+            },
+            f,
+        );
+    }
+
     pub fn get_code_actions(
         &self,
         path: PathBuf,
@@ -882,6 +1341,36 @@ pub fn get_document_formatting(
         self.request_async(ProxyRequest::GetDocumentFormatting { path }, f);
     }
 
+    pub fn get_range_formatting(
+        &self,
+        path: PathBuf,
+        range: lsp_types::Range,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetRangeFormatting { path, range }, f);
+    }
+
+    pub fn will_save_document(
+        &self,
+        path: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::WillSaveDocument { path }, f);
+    }
+
+    pub fn get_on_type_formatting(
+        &self,
+        path: PathBuf,
+        position: Position,
+        ch: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetOnTypeFormatting { path, position, ch },
+            f,
+        );
+    }
+
     pub fn get_semantic_tokens(
         &self,
         path: PathBuf,
@@ -923,6 +1412,41 @@ pub fn git_get_remote_file_url(
         self.request_async(ProxyRequest::GitGetRemoteFileUrl { file }, f);
     }
 
+    pub fn git_blame(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitBlame { path }, f);
+    }
+
+    pub fn git_log(
+        &self,
+        path: Option<PathBuf>,
+        limit: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitLog { path, limit }, f);
+    }
+
+    pub fn git_commit_files(&self, id: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitCommitFiles { id }, f);
+    }
+
+    pub fn git_file_at_revision(
+        &self,
+        path: PathBuf,
+        revision: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitFileAtRevision { path, revision }, f);
+    }
+
+    pub fn git_sync(
+        &self,
+        kind: GitSyncKind,
+        token: Option<String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitSync { kind, token }, f);
+    }
+
     pub fn rename(
         &self,
         path: PathBuf,
@@ -944,6 +1468,14 @@ pub fn get_inlay_hints(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::GetInlayHints { path }, f);
     }
 
+    pub fn get_document_links(
+        &self,
+        path: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetDocumentLinks { path }, f);
+    }
+
     pub fn get_inline_completions(
         &self,
         path: PathBuf,
@@ -961,10 +1493,35 @@ pub fn get_inline_completions(
         );
     }
 
+    pub fn check_open_files(&self) {
+        self.notification(ProxyNotification::CheckOpenFiles {});
+    }
+
     pub fn update(&self, path: PathBuf, delta: RopeDelta, rev: u64) {
         self.notification(ProxyNotification::Update { path, delta, rev });
     }
 
+    pub fn set_buffer_rev(&self, path: PathBuf, rev: u64) {
+        self.notification(ProxyNotification::SetBufferRev { path, rev });
+    }
+
+    pub fn reopen_buffer(
+        &self,
+        buffer_id: BufferId,
+        path: PathBuf,
+        content: String,
+        rev: u64,
+        encoding: String,
+    ) {
+        self.notification(ProxyNotification::ReopenBuffer {
+            buffer_id,
+            path,
+            content,
+            rev,
+            encoding,
+        });
+    }
+
     pub fn update_plugin_configs(
         &self,
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
@@ -972,6 +1529,52 @@ pub fn update_plugin_configs(
         self.notification(ProxyNotification::UpdatePluginConfigs { configs });
     }
 
+    pub fn update_language_server_configs(
+        &self,
+        configs: HashMap<String, LanguageServerConfig>,
+    ) {
+        self.notification(ProxyNotification::UpdateLanguageServerConfigs {
+            configs,
+        });
+    }
+
+    pub fn update_env(&self, env: HashMap<String, String>) {
+        self.notification(ProxyNotification::UpdateEnv { env });
+    }
+
+    pub fn run_plugin_command(&self, volt_id: VoltID, command: String) {
+        self.notification(ProxyNotification::RunPluginCommand { volt_id, command });
+    }
+
+    pub fn plugin_view_item_clicked(
+        &self,
+        volt_id: VoltID,
+        view: String,
+        item: String,
+    ) {
+        self.notification(ProxyNotification::PluginViewItemClicked {
+            volt_id,
+            view,
+            item,
+        });
+    }
+
+    pub fn buffer_closed(&self, path: PathBuf) {
+        self.notification(ProxyNotification::BufferClosed { path });
+    }
+
+    pub fn buffer_opened(&self, path: PathBuf) {
+        self.notification(ProxyNotification::BufferOpened { path });
+    }
+
+    pub fn selection_changed(&self, path: PathBuf, selections: Vec<Range>) {
+        self.notification(ProxyNotification::SelectionChanged { path, selections });
+    }
+
+    pub fn install_language_server(&self, language_id: String) {
+        self.notification(ProxyNotification::InstallLanguageServer { language_id });
+    }
+
     pub fn git_discard_files_changes(&self, files: Vec<PathBuf>) {
         self.notification(ProxyNotification::GitDiscardFilesChanges { files });
     }
@@ -1082,6 +1685,67 @@ pub fn dap_get_scopes(
     ) {
         self.request_async(ProxyRequest::DapGetScopes { dap_id, frame_id }, f);
     }
+
+    /// Evaluate the expression in the stack frame, or globally without one.
+    /// `context` tells the adapter what it's for, like "watch" or "repl".
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: &str,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapEvaluate {
+                dap_id,
+                expression,
+                frame_id,
+                context: context.to_string(),
+            },
+            f,
+        );
+    }
+
+    /// The completions at `column` of `text` typed in the debug console
+    pub fn dap_completions(
+        &self,
+        dap_id: DapId,
+        text: String,
+        column: usize,
+        frame_id: Option<usize>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapCompletions {
+                dap_id,
+                text,
+                column,
+                frame_id,
+            },
+            f,
+        );
+    }
+
+    /// The running language servers, only those handling the file at `path`
+    /// if it is given
+    pub fn language_servers(
+        &self,
+        path: Option<PathBuf>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::LanguageServers { path }, f);
+    }
+
+    /// Write the log of the language servers handling the file at `path`, or
+    /// of all of them, to a file
+    pub fn language_server_log(
+        &self,
+        path: Option<PathBuf>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::LanguageServerLog { path }, f);
+    }
 }
 
 impl Default for ProxyRpcHandler {
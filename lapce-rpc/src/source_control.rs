@@ -5,9 +5,62 @@
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub struct DiffInfo {
     pub head: String,
+    /// Local branches
     pub branches: Vec<String>,
+    /// Remote tracking branches, such as `origin/master`
+    #[serde(default)]
+    pub remote_branches: Vec<String>,
     pub tags: Vec<String>,
     pub diffs: Vec<FileDiff>,
+    /// Files that aren't tracked by git yet, these are also in `diffs` as added
+    #[serde(default)]
+    pub untracked: Vec<PathBuf>,
+    /// Files and directories ignored by git. Ignored directories aren't recursed into.
+    #[serde(default)]
+    pub ignored: Vec<PathBuf>,
+    /// Changes in the index compared to `HEAD`
+    #[serde(default)]
+    pub staged: Vec<FileDiff>,
+    /// Changes in the working tree compared to the index, including untracked files
+    #[serde(default)]
+    pub unstaged: Vec<FileDiff>,
+    /// Commits on `HEAD` that aren't on its upstream branch
+    #[serde(default)]
+    pub ahead: usize,
+    /// Commits on the upstream branch of `HEAD` that aren't on `HEAD`
+    #[serde(default)]
+    pub behind: usize,
+    /// The stashed changes, the latest first
+    #[serde(default)]
+    pub stashes: Vec<StashInfo>,
+}
+
+/// The error code of a failed [`GitSync`](crate::proxy::ProxyRequest::GitSync) that
+/// needs credentials from the user to be retried
+pub const GIT_AUTH_REQUIRED: i64 = 401;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GitSyncKind {
+    Fetch,
+    Pull,
+    Push,
+}
+
+impl GitSyncKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            GitSyncKind::Fetch => "Git Fetch",
+            GitSyncKind::Pull => "Git Pull",
+            GitSyncKind::Push => "Git Push",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct StashInfo {
+    /// The position in the stash list, 0 being the latest
+    pub index: usize,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -28,6 +81,16 @@ pub fn path(&self) -> &PathBuf {
         }
     }
 
+    /// All the paths the diff touches, the new path first for a rename
+    pub fn paths(&self) -> Vec<&PathBuf> {
+        match &self {
+            FileDiff::Modified(p) | FileDiff::Added(p) | FileDiff::Deleted(p) => {
+                vec![p]
+            }
+            FileDiff::Renamed(new, old) => vec![new, old],
+        }
+    }
+
     pub fn kind(&self) -> FileDiffKind {
         match self {
             FileDiff::Modified(_) => FileDiffKind::Modified,
@@ -45,3 +108,34 @@ pub enum FileDiffKind {
     Deleted,
     Renamed,
 }
+
+/// The commit that last changed a range of lines of a file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlameHunk {
+    /// The first line of the range, starting from 0
+    pub start_line: usize,
+    pub lines: usize,
+    /// `None` for lines that haven't been committed yet
+    pub commit: Option<CommitInfo>,
+}
+
+/// A commit in the history of the repository
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct CommitInfo {
+    pub id: String,
+    pub author: String,
+    /// Seconds since the Unix epoch
+    pub time: i64,
+    pub message: String,
+}
+
+impl CommitInfo {
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(7)]
+    }
+
+    /// The first line of the commit message
+    pub fn summary(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+}
@@ -4,17 +4,90 @@
 };
 
 use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
 use crossbeam_channel::{Receiver, Sender};
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use crate::{RpcError, RpcMessage, RpcObject};
 
+/// Messages at least this long are compressed with zstd before being sent
+pub const COMPRESSION_THRESHOLD: usize = 16 * 1024;
+
+/// The most base64 bytes of a compressed message sent on a single line, so
+/// that the transfer of a large message can be followed
+pub const CHUNK_SIZE: usize = 256 * 1024;
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// One line of a compressed message, whose chunks are always written one
+/// after the other
+#[derive(Serialize, Deserialize)]
+struct Chunk {
+    index: usize,
+    total: usize,
+    /// The length of the uncompressed message
+    size: usize,
+    data: String,
+}
+
+/// How far the receiving of a chunked message is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkProgress {
+    /// The number of chunks received so far
+    pub received: usize,
+    pub total: usize,
+    /// The length of the uncompressed message
+    pub size: usize,
+}
+
+impl ChunkProgress {
+    pub fn is_done(&self) -> bool {
+        self.received >= self.total
+    }
+}
+
 pub fn stdio_transport<W, R, Req1, Notif1, Resp1, Req2, Notif2, Resp2>(
+    writer: W,
+    writer_receiver: Receiver<RpcMessage<Req2, Notif2, Resp2>>,
+    reader: R,
+    reader_sender: Sender<RpcMessage<Req1, Notif1, Resp1>>,
+) where
+    W: 'static + Write + Send,
+    R: 'static + BufRead + Send,
+    Req1: 'static + Serialize + DeserializeOwned + Send + Sync,
+    Notif1: 'static + Serialize + DeserializeOwned + Send + Sync,
+    Resp1: 'static + Serialize + DeserializeOwned + Send + Sync,
+    Req2: 'static + Serialize + DeserializeOwned + Send + Sync,
+    Notif2: 'static + Serialize + DeserializeOwned + Send + Sync,
+    Resp2: 'static + Serialize + DeserializeOwned + Send + Sync,
+{
+    stdio_transport_with_progress(
+        writer,
+        writer_receiver,
+        reader,
+        reader_sender,
+        |_| {},
+    );
+}
+
+/// Like [`stdio_transport`], calling `progress` for every chunk received of
+/// a large message
+pub fn stdio_transport_with_progress<
+    W,
+    R,
+    Req1,
+    Notif1,
+    Resp1,
+    Req2,
+    Notif2,
+    Resp2,
+>(
     mut writer: W,
     writer_receiver: Receiver<RpcMessage<Req2, Notif2, Resp2>>,
     mut reader: R,
     reader_sender: Sender<RpcMessage<Req1, Notif1, Resp1>>,
+    mut progress: impl FnMut(ChunkProgress) + Send + 'static,
 ) where
     W: 'static + Write + Send,
     R: 'static + BufRead + Send,
@@ -34,7 +107,7 @@ pub fn stdio_transport<W, R, Req1, Notif1, Resp1, Req2, Notif2, Resp2>(
     });
     thread::spawn(move || -> Result<()> {
         loop {
-            if let Some(msg) = read_msg(&mut reader)? {
+            if let Some(msg) = read_msg_with_progress(&mut reader, &mut progress)? {
                 reader_sender.send(msg)?;
             }
         }
@@ -73,8 +146,27 @@ pub fn write_msg<W, Req, Notif, Resp>(
             })
         }
     };
-    let msg = format!("{}\n", serde_json::to_string(&value)?);
-    out.write_all(msg.as_bytes())?;
+    let msg = serde_json::to_string(&value)?;
+    if msg.len() < COMPRESSION_THRESHOLD {
+        out.write_all(msg.as_bytes())?;
+        out.write_all(b"\n")?;
+    } else {
+        let compressed = zstd::encode_all(msg.as_bytes(), COMPRESSION_LEVEL)?;
+        let data = general_purpose::STANDARD.encode(compressed);
+        let total = data.len().div_ceil(CHUNK_SIZE);
+        // base64 is ascii, so the chunks are valid utf8 anywhere they're cut
+        for (index, data) in data.as_bytes().chunks(CHUNK_SIZE).enumerate() {
+            let chunk = Chunk {
+                index,
+                total,
+                size: msg.len(),
+                data: String::from_utf8_lossy(data).into_owned(),
+            };
+            let line = serde_json::to_string(&json!({ "chunk": chunk }))?;
+            out.write_all(line.as_bytes())?;
+            out.write_all(b"\n")?;
+        }
+    }
     out.flush()?;
     Ok(())
 }
@@ -88,9 +180,44 @@ pub fn read_msg<R, Req, Notif, Resp>(
     Notif: DeserializeOwned,
     Resp: DeserializeOwned,
 {
-    let mut buf = String::new();
-    let _ = inp.read_line(&mut buf)?;
-    let value: Value = serde_json::from_str(&buf)?;
+    read_msg_with_progress(inp, &mut |_| {})
+}
+
+/// Reads the next message, putting a compressed one back together from its
+/// chunks and calling `progress` for each of them
+pub fn read_msg_with_progress<R, Req, Notif, Resp>(
+    inp: &mut R,
+    progress: &mut impl FnMut(ChunkProgress),
+) -> io::Result<Option<RpcMessage<Req, Notif, Resp>>>
+where
+    R: BufRead,
+    Req: DeserializeOwned,
+    Notif: DeserializeOwned,
+    Resp: DeserializeOwned,
+{
+    let mut data = String::new();
+    let value = loop {
+        let mut buf = String::new();
+        let _ = inp.read_line(&mut buf)?;
+        let mut value: Value = serde_json::from_str(&buf)?;
+        let Some(chunk) = value.get_mut("chunk").map(Value::take) else {
+            break value;
+        };
+        let chunk: Chunk = serde_json::from_value(chunk)?;
+        data.push_str(&chunk.data);
+        progress(ChunkProgress {
+            received: chunk.index + 1,
+            total: chunk.total,
+            size: chunk.size,
+        });
+        if chunk.index + 1 >= chunk.total {
+            let compressed = general_purpose::STANDARD
+                .decode(&data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let msg = zstd::decode_all(compressed.as_slice())?;
+            break serde_json::from_slice(&msg)?;
+        }
+    };
 
     match parse_value(value) {
         Ok(msg) => Ok(Some(msg)),
@@ -140,3 +267,68 @@ fn parse_value<Req, Notif, Resp>(
     };
     Ok(msg)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    type Message = RpcMessage<Value, Value, Value>;
+
+    fn round_trip(msg: Message) -> (Message, Vec<ChunkProgress>) {
+        let mut out = Vec::new();
+        write_msg(&mut out, msg).unwrap();
+        let mut progress = Vec::new();
+        let msg =
+            read_msg_with_progress(&mut BufReader::new(out.as_slice()), &mut |p| {
+                progress.push(p)
+            })
+            .unwrap()
+            .unwrap();
+        (msg, progress)
+    }
+
+    #[test]
+    fn test_small_message_is_sent_as_is() {
+        let mut out = Vec::new();
+        let msg: Message = RpcMessage::Response(1, json!("content"));
+        write_msg(&mut out, msg).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"id\":1,\"result\":\"content\"}\n"
+        );
+
+        let (msg, progress) = round_trip(RpcMessage::Response(1, json!("content")));
+        assert!(matches!(msg, RpcMessage::Response(1, v) if v == "content"));
+        assert!(progress.is_empty());
+    }
+
+    #[test]
+    fn test_large_message_is_compressed_and_chunked() {
+        // Random enough to span several chunks once compressed
+        let mut state = 0x2545f4914f6cdd1du64;
+        let content: String = (0..2_000_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                char::from(b'a' + (state % 26) as u8)
+            })
+            .collect();
+
+        let mut out = Vec::new();
+        let msg: Message = RpcMessage::Response(7, json!(content));
+        write_msg(&mut out, msg).unwrap();
+        assert!(out.len() < content.len());
+        assert!(out
+            .split(|b| *b == b'\n')
+            .all(|line| line.len() <= CHUNK_SIZE + 100));
+
+        let (msg, progress) = round_trip(RpcMessage::Response(7, json!(content)));
+        assert!(matches!(msg, RpcMessage::Response(7, v) if v == content.as_str()));
+        assert!(progress.len() > 1);
+        assert!(progress.last().unwrap().is_done());
+        assert!(progress.iter().all(|p| p.size > content.len()));
+    }
+}